@@ -0,0 +1,300 @@
+//! Benchmarks the leader's first step of an aggregation job, via the aggregation job driver's job
+//! stepper, for aggregation jobs of varying sizes.
+//!
+//! The helper is mocked, so the timings include loading the aggregation job's reports from the
+//! datastore, VDAF preparation, encoding the request to the helper, and writing the results, but
+//! not the helper's own work.
+//!
+//! These benchmarks require a Postgres instance, obtained in the same way as for the datastore
+//! tests.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use janus_aggregator::aggregator::{
+    aggregation_job_driver::AggregationJobDriver, compute_pool::ComputePool,
+};
+use janus_aggregator_core::{
+    datastore::{
+        models::{
+            AggregationJob, AggregationJobState, Batch, BatchState, LeaderStoredReport,
+            ReportAggregation, ReportAggregationState,
+        },
+        test_util::ephemeral_datastore,
+        Datastore,
+    },
+    query_type::AccumulableQueryType,
+    task::{test_util::TaskBuilder, QueryType, Task, VerifyKey},
+    test_util::noop_meter,
+};
+use janus_core::{
+    hpke::{
+        self, test_util::generate_test_hpke_config_and_private_key, HpkeApplicationInfo, Label,
+    },
+    task::{VdafInstance, VERIFY_KEY_LENGTH},
+    test_util::run_vdaf,
+    time::{Clock, IntervalExt, MockClock, TimeExt},
+};
+use janus_messages::{
+    query_type::TimeInterval, AggregationJobResp, AggregationJobRound, HpkeConfig, InputShareAad,
+    Interval, PlaintextInputShare, PrepareStep, PrepareStepResult, ReportId, ReportMetadata, Role,
+    Time,
+};
+use mockito::Matcher;
+use prio::{
+    codec::Encode,
+    vdaf::prio3::{Prio3, Prio3Count},
+};
+use rand::random;
+use reqwest::Url;
+use std::{
+    sync::Arc,
+    time::{Duration as StdDuration, Instant},
+};
+use tokio::runtime::Runtime;
+
+const REPORT_COUNTS: [usize; 3] = [10, 100, 1000];
+
+/// Returns a [`LeaderStoredReport`] for a Prio3Count measurement, along with the helper's prepare
+/// share for it.
+fn generate_report(
+    task: &Task,
+    time: Time,
+    helper_hpke_config: &HpkeConfig,
+) -> (LeaderStoredReport<VERIFY_KEY_LENGTH, Prio3Count>, Vec<u8>) {
+    let vdaf = Prio3::new_count(2).unwrap();
+    let verify_key: VerifyKey<VERIFY_KEY_LENGTH> = task.primary_vdaf_verify_key().unwrap();
+    let report_metadata = ReportMetadata::new(random(), time);
+    let transcript = run_vdaf(&vdaf, verify_key.as_bytes(), &(), report_metadata.id(), &1);
+
+    let encrypted_helper_input_share = hpke::seal(
+        helper_hpke_config,
+        &HpkeApplicationInfo::new(&Label::InputShare, &Role::Client, &Role::Helper),
+        &PlaintextInputShare::new(
+            Vec::new(),
+            transcript.input_shares[Role::Helper.index().unwrap()].get_encoded(),
+        )
+        .get_encoded(),
+        &InputShareAad::new(
+            *task.id(),
+            report_metadata.clone(),
+            transcript.public_share.get_encoded(),
+        )
+        .get_encoded(),
+    )
+    .unwrap();
+    let (_, helper_prep_share) = transcript.helper_prep_state(0);
+
+    (
+        LeaderStoredReport::new(
+            *task.id(),
+            report_metadata,
+            transcript.public_share.clone(),
+            Vec::new(),
+            transcript.input_shares[Role::Leader.index().unwrap()].clone(),
+            encrypted_helper_input_share,
+        ),
+        helper_prep_share.get_encoded(),
+    )
+}
+
+/// Writes a task and `report_count` client reports, and mocks the helper's response to an
+/// aggregation job containing those reports, in order.
+async fn setup(
+    ds: &Datastore<MockClock>,
+    server: &mut mockito::Server,
+    report_count: usize,
+) -> (Task, Time, Vec<ReportId>, mockito::Mock) {
+    let task = TaskBuilder::new(
+        QueryType::TimeInterval,
+        VdafInstance::Prio3Count,
+        Role::Leader,
+    )
+    .with_helper_aggregator_endpoint(Url::parse(&server.url()).unwrap())
+    .build();
+    let time = MockClock::default()
+        .now()
+        .to_batch_interval_start(task.time_precision())
+        .unwrap();
+    let helper_hpke_keypair = generate_test_hpke_config_and_private_key();
+    let (reports, helper_prep_shares): (Vec<_>, Vec<_>) = (0..report_count)
+        .map(|_| generate_report(&task, time, helper_hpke_keypair.config()))
+        .unzip();
+    let report_ids = reports
+        .iter()
+        .map(|report| *report.metadata().id())
+        .collect();
+
+    ds.run_tx(|tx| {
+        let (task, reports) = (task.clone(), reports.clone());
+        Box::pin(async move {
+            tx.put_task(&task).await?;
+            for report in &reports {
+                tx.put_client_report(&Prio3::new_count(2).unwrap(), report)
+                    .await?;
+            }
+            // Every aggregation job written by the benchmark completes, decrementing the batch's
+            // count of outstanding aggregation jobs, so start it high enough not to underflow.
+            tx.put_batch(&Batch::<VERIFY_KEY_LENGTH, TimeInterval, Prio3Count>::new(
+                *task.id(),
+                TimeInterval::to_batch_identifier(&task, &(), &time).unwrap(),
+                (),
+                BatchState::Open,
+                u64::from(u32::MAX),
+                Interval::from_time(&time).unwrap(),
+            ))
+            .await
+        })
+    })
+    .await
+    .unwrap();
+
+    let helper_response = AggregationJobResp::new(
+        reports
+            .iter()
+            .zip(helper_prep_shares)
+            .map(|(report, helper_prep_share)| {
+                PrepareStep::new(
+                    *report.metadata().id(),
+                    PrepareStepResult::Continued(helper_prep_share),
+                )
+            })
+            .collect(),
+    );
+    let mock = server
+        .mock(
+            "PUT",
+            Matcher::Regex(format!("^/tasks/{}/aggregation_jobs/", task.id())),
+        )
+        .with_status(200)
+        .with_header("Content-Type", AggregationJobResp::MEDIA_TYPE)
+        .with_body(helper_response.get_encoded())
+        .create_async()
+        .await;
+
+    (task, time, report_ids, mock)
+}
+
+/// Writes a new aggregation job over the given reports, then times its first step.
+async fn step_new_aggregation_job(
+    ds: &Arc<Datastore<MockClock>>,
+    aggregation_job_driver: &Arc<AggregationJobDriver>,
+    task: &Task,
+    time: Time,
+    report_ids: &[ReportId],
+) -> StdDuration {
+    let aggregation_job_id = random();
+    let lease = ds
+        .run_tx(|tx| {
+            let task = task.clone();
+            let report_ids = report_ids.to_vec();
+            Box::pin(async move {
+                tx.put_aggregation_job(&AggregationJob::<
+                    VERIFY_KEY_LENGTH,
+                    TimeInterval,
+                    Prio3Count,
+                >::new(
+                    *task.id(),
+                    aggregation_job_id,
+                    (),
+                    (),
+                    Interval::from_time(&time).unwrap(),
+                    AggregationJobState::InProgress,
+                    AggregationJobRound::from(0),
+                ))
+                .await?;
+                for (ord, report_id) in report_ids.iter().enumerate() {
+                    tx.put_report_aggregation(
+                        &ReportAggregation::<VERIFY_KEY_LENGTH, Prio3Count>::new(
+                            *task.id(),
+                            aggregation_job_id,
+                            *report_id,
+                            time,
+                            ord.try_into().unwrap(),
+                            None,
+                            ReportAggregationState::Start,
+                        ),
+                    )
+                    .await?;
+                }
+                Ok(tx
+                    .acquire_incomplete_aggregation_jobs(&StdDuration::from_secs(600), 1)
+                    .await?
+                    .remove(0))
+            })
+        })
+        .await
+        .unwrap();
+
+    let stepper = Arc::clone(aggregation_job_driver).make_job_stepper_callback(Arc::clone(ds), 1);
+    let start = Instant::now();
+    stepper(lease).await.unwrap();
+    let elapsed = start.elapsed();
+
+    // The job now waits to be continued with the helper, which isn't measured here. Finish it so
+    // that it isn't acquired in place of the next iteration's job.
+    ds.run_tx(|tx| {
+        let task_id = *task.id();
+        Box::pin(async move {
+            let aggregation_job = tx
+                .get_aggregation_job::<VERIFY_KEY_LENGTH, TimeInterval, Prio3Count>(
+                    &task_id,
+                    &aggregation_job_id,
+                )
+                .await?
+                .unwrap();
+            tx.update_aggregation_job(&aggregation_job.with_state(AggregationJobState::Finished))
+                .await
+        })
+    })
+    .await
+    .unwrap();
+
+    elapsed
+}
+
+fn aggregation_job_step(c: &mut Criterion) {
+    let runtime = Runtime::new().unwrap();
+    let mut server = runtime.block_on(mockito::Server::new_async());
+    let ephemeral_datastore = runtime.block_on(ephemeral_datastore());
+    let ds = Arc::new(runtime.block_on(ephemeral_datastore.datastore(MockClock::default())));
+    let aggregation_job_driver = Arc::new(AggregationJobDriver::new(
+        reqwest::Client::builder().build().unwrap(),
+        &noop_meter(),
+        32,
+        ComputePool::new(&noop_meter(), None).unwrap(),
+    ));
+
+    let mut group = c.benchmark_group("aggregation_job_step");
+    for report_count in REPORT_COUNTS {
+        let (task, time, report_ids, _mock) =
+            runtime.block_on(setup(&ds, &mut server, report_count));
+
+        group.bench_with_input(
+            BenchmarkId::new("init", report_count),
+            &report_count,
+            |b, _| {
+                b.to_async(&runtime).iter_custom(|iters| {
+                    let (ds, aggregation_job_driver, task, report_ids) =
+                        (&ds, &aggregation_job_driver, &task, &report_ids);
+                    async move {
+                        let mut elapsed = StdDuration::ZERO;
+                        for _ in 0..iters {
+                            elapsed += step_new_aggregation_job(
+                                ds,
+                                aggregation_job_driver,
+                                task,
+                                time,
+                                report_ids,
+                            )
+                            .await;
+                        }
+                        elapsed
+                    }
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, aggregation_job_step);
+criterion_main!(benches);
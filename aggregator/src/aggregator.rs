@@ -4,12 +4,14 @@ pub use crate::aggregator::error::Error;
 use crate::{
     aggregator::{
         aggregate_share::compute_aggregate_share,
+        compute_pool::ComputePool,
         error::{BatchMismatch, OptOutReason},
         query_type::{CollectableQueryType, UploadableQueryType},
+        rate_limiter::RateLimiter,
         report_writer::{ReportWriteBatcher, WritableReport},
     },
-    cache::{GlobalHpkeKeypairCache, PeerAggregatorCache},
-    config::TaskprovConfig,
+    cache::{GlobalHpkeKeypairCache, PeerAggregatorCache, TaskCache},
+    config::{RequestBodySizeLimits, TaskCacheConfig, TaskprovConfig, UploadRateLimitConfig},
     Operation,
 };
 use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
@@ -33,7 +35,7 @@ use janus_aggregator_core::{
         Datastore, Error as DatastoreError, Transaction,
     },
     query_type::AccumulableQueryType,
-    task::{self, Task, VerifyKey},
+    task::{self, Task, TaskInvalidationHook, VerifyKey},
     taskprov::{self, PeerAggregator},
 };
 #[cfg(feature = "test-util")]
@@ -42,18 +44,20 @@ use janus_core::{
     hpke::{self, HpkeApplicationInfo, HpkeKeypair, Label},
     http::response_to_problem_details,
     task::{AuthenticationToken, VdafInstance, VERIFY_KEY_LENGTH},
+    taskprov::TASKPROV_HEADER,
     time::{Clock, DurationExt, IntervalExt, TimeExt},
 };
 use janus_messages::{
+    batch_discovery::{CollectableBatch, CollectableBatchList},
     problem_type::DapProblemType,
     query_type::{FixedSize, TimeInterval},
     taskprov::TaskConfig,
     AggregateShare, AggregateShareAad, AggregateShareReq, AggregationJobContinueReq,
     AggregationJobId, AggregationJobInitializeReq, AggregationJobResp, AggregationJobRound,
-    BatchSelector, Collection, CollectionJobId, CollectionReq, Duration, HpkeConfig,
-    HpkeConfigList, InputShareAad, Interval, PartialBatchSelector, PlaintextInputShare,
-    PrepareStep, PrepareStepResult, Report, ReportIdChecksum, ReportShare, ReportShareError, Role,
-    TaskId,
+    BatchId, BatchSelector, Collection, CollectionJobId, CollectionReq, Duration, HpkeConfig,
+    HpkeConfigId, HpkeConfigList, InputShareAad, Interval, PartialBatchSelector,
+    PlaintextInputShare, PrepareStep, PrepareStepResult, Report, ReportIdChecksum, ReportShare,
+    ReportShareError, Role, TaskId,
 };
 use opentelemetry::{
     metrics::{Counter, Histogram, Meter, Unit},
@@ -76,17 +80,21 @@ use reqwest::Client;
 use ring::digest::{digest, SHA256};
 use std::{
     borrow::Borrow,
-    collections::{hash_map::Entry, HashMap, HashSet},
+    collections::{btree_map, hash_map::Entry, BTreeMap, HashMap, HashSet},
     fmt::Debug,
     hash::Hash,
     panic,
     sync::Arc,
     time::{Duration as StdDuration, Instant},
 };
-use tokio::{sync::Mutex, try_join};
+use tokio::try_join;
 use tracing::{debug, info, trace_span, warn};
 use url::Url;
 
+/// The maximum number of batch IDs considered for a single page of the batch discovery endpoint.
+/// Batches which are too small to be collected are left out of a page, so pages may be shorter.
+const GET_BATCHES_PAGE_SIZE: u64 = 1000;
+
 pub mod accumulator;
 #[cfg(test)]
 mod aggregate_init_tests;
@@ -99,11 +107,14 @@ pub mod batch_creator;
 pub mod collection_job_driver;
 #[cfg(test)]
 mod collection_job_tests;
+pub mod compute_pool;
+mod dp;
 mod error;
 pub mod garbage_collector;
 pub mod http_handlers;
 pub mod problem_details;
 pub mod query_type;
+mod rate_limiter;
 pub mod report_writer;
 #[cfg(test)]
 mod taskprov_tests;
@@ -156,7 +167,7 @@ pub struct Aggregator<C: Clock> {
     /// Report writer, with support for batching.
     report_writer: Arc<ReportWriteBatcher<C>>,
     /// Cache of task aggregators.
-    task_aggregators: Mutex<HashMap<TaskId, Arc<TaskAggregator<C>>>>,
+    task_aggregators: Arc<TaskCache<Arc<TaskAggregator<C>>>>,
 
     // Metrics.
     /// Counter tracking the number of failed decryptions while handling the /upload endpoint.
@@ -166,12 +177,21 @@ pub struct Aggregator<C: Clock> {
     /// Counters tracking the number of failures to step client reports through the aggregation
     /// process.
     aggregate_step_failure_counter: Counter<u64>,
+    /// Counter tracking the number of requests rejected for exceeding a request body size limit or
+    /// rate limit.
+    request_limit_rejection_counter: Counter<u64>,
+
+    /// Rate limiter for the /upload endpoint, if rate limiting is enabled.
+    upload_rate_limiter: Option<RateLimiter>,
 
     /// Cache of global HPKE keypairs and configs.
     global_hpke_keypairs: GlobalHpkeKeypairCache,
 
     /// Cache of taskprov peer aggregators.
     peer_aggregators: PeerAggregatorCache,
+
+    /// Thread pool used for VDAF preparation.
+    compute_pool: ComputePool,
 }
 
 /// Config represents a configuration for an Aggregator.
@@ -195,7 +215,25 @@ pub struct Config {
     /// becomes aware of key state changes.
     pub global_hpke_configs_refresh_interval: StdDuration,
 
+    /// Defines how often to refresh the taskprov peer aggregators cache. This affects how often an
+    /// aggregator becomes aware of changes to peer aggregators.
+    pub peer_aggregators_refresh_interval: StdDuration,
+
     pub taskprov_config: TaskprovConfig,
+
+    /// Defines the number of threads used for VDAF preparation. If unset, one thread per available
+    /// CPU is used.
+    pub vdaf_preparation_threads: Option<usize>,
+
+    /// Defines the maximum request body size accepted by each endpoint.
+    pub request_body_size_limits: RequestBodySizeLimits,
+
+    /// Defines the rate limit applied to the /upload endpoint. If unset, uploads are not rate
+    /// limited.
+    pub upload_rate_limit: Option<UploadRateLimitConfig>,
+
+    /// Defines the expiry and capacity of the cache of tasks.
+    pub task_cache: TaskCacheConfig,
 }
 
 impl Default for Config {
@@ -205,7 +243,12 @@ impl Default for Config {
             max_upload_batch_write_delay: StdDuration::ZERO,
             batch_aggregation_shard_count: 1,
             global_hpke_configs_refresh_interval: GlobalHpkeKeypairCache::DEFAULT_REFRESH_INTERVAL,
+            peer_aggregators_refresh_interval: PeerAggregatorCache::DEFAULT_REFRESH_INTERVAL,
             taskprov_config: TaskprovConfig::default(),
+            vdaf_preparation_threads: None,
+            request_body_size_limits: RequestBodySizeLimits::default(),
+            upload_rate_limit: None,
+            task_cache: TaskCacheConfig::default(),
         }
     }
 }
@@ -240,25 +283,53 @@ impl<C: Clock> Aggregator<C> {
         let aggregate_step_failure_counter = aggregate_step_failure_counter(meter);
         aggregate_step_failure_counter.add(0, &[]);
 
+        let request_limit_rejection_counter = meter
+            .u64_counter("janus_request_limit_rejections")
+            .with_description(
+                "Number of requests rejected for exceeding a request body size limit or rate limit.",
+            )
+            .with_unit(Unit::new("{request}"))
+            .init();
+        for limit in ["body_size", "rate"] {
+            request_limit_rejection_counter.add(0, &[KeyValue::new("limit", limit)]);
+        }
+
+        let upload_rate_limiter = cfg.upload_rate_limit.as_ref().map(RateLimiter::new);
+
         let global_hpke_keypairs = GlobalHpkeKeypairCache::new(
             datastore.clone(),
             cfg.global_hpke_configs_refresh_interval,
         )
         .await?;
 
-        let peer_aggregators = PeerAggregatorCache::new(&datastore).await?;
+        let peer_aggregators = PeerAggregatorCache::new(
+            Arc::clone(&datastore),
+            cfg.peer_aggregators_refresh_interval,
+        )
+        .await?;
+
+        let compute_pool = ComputePool::new(meter, cfg.vdaf_preparation_threads)?;
+
+        let task_aggregators = Arc::new(TaskCache::new(
+            StdDuration::from_secs(cfg.task_cache.ttl_s),
+            StdDuration::from_secs(cfg.task_cache.negative_ttl_s),
+            cfg.task_cache.capacity,
+        ));
 
         Ok(Self {
             datastore,
             clock,
             cfg,
             report_writer,
-            task_aggregators: Mutex::new(HashMap::new()),
+            task_aggregators,
             upload_decrypt_failure_counter,
             upload_decode_failure_counter,
             aggregate_step_failure_counter,
+            request_limit_rejection_counter,
+            upload_rate_limiter,
             global_hpke_keypairs,
             peer_aggregators,
+            compute_pool,
         })
     }
 
@@ -316,13 +387,37 @@ impl<C: Clock> Aggregator<C> {
         }
     }
 
-    async fn handle_upload(&self, task_id: &TaskId, report_bytes: &[u8]) -> Result<(), Arc<Error>> {
+    async fn handle_upload(
+        &self,
+        task_id: &TaskId,
+        report_bytes: &[u8],
+        taskprov_task_config: Option<&TaskConfig>,
+    ) -> Result<(), Arc<Error>> {
         let report = Report::get_decoded(report_bytes).map_err(|err| Arc::new(Error::from(err)))?;
 
-        let task_aggregator = self
-            .task_aggregator_for(task_id)
-            .await?
-            .ok_or(Error::UnrecognizedTask(*task_id))?;
+        let task_aggregator = match self.task_aggregator_for(task_id).await? {
+            Some(task_aggregator) => task_aggregator,
+            None if self.cfg.taskprov_config.enabled
+                && self.cfg.taskprov_config.opt_in_on_upload
+                && taskprov_task_config.is_some() =>
+            {
+                self.taskprov_opt_in(
+                    TaskprovRequester::Client,
+                    task_id,
+                    taskprov_task_config.unwrap(),
+                )
+                .await?;
+
+                debug!(
+                    ?task_id,
+                    "taskprov: opt-in successful, retrying task acquisition"
+                );
+                self.task_aggregator_for(task_id).await?.ok_or_else(|| {
+                    Error::Internal("unexpectedly failed to create task".to_string())
+                })?
+            }
+            None => return Err(Arc::new(Error::UnrecognizedTask(*task_id))),
+        };
         if task_aggregator.task.role() != &Role::Leader {
             return Err(Arc::new(Error::UnrecognizedTask(*task_id)));
         }
@@ -360,10 +455,9 @@ impl<C: Clock> Aggregator<C> {
             }
             None if self.cfg.taskprov_config.enabled && taskprov_task_config.is_some() => {
                 self.taskprov_opt_in(
-                    &Role::Leader,
+                    TaskprovRequester::Leader(auth_token.as_ref()),
                     task_id,
                     taskprov_task_config.unwrap(),
-                    auth_token.as_ref(),
                 )
                 .await?;
 
@@ -386,6 +480,7 @@ impl<C: Clock> Aggregator<C> {
             .handle_aggregate_init(
                 &self.datastore,
                 &self.global_hpke_keypairs,
+                &self.compute_pool,
                 &self.aggregate_step_failure_counter,
                 aggregation_job_id,
                 req_bytes,
@@ -411,10 +506,9 @@ impl<C: Clock> Aggregator<C> {
 
         if self.cfg.taskprov_config.enabled && taskprov_task_config.is_some() {
             self.taskprov_authorize_request(
-                &Role::Leader,
+                TaskprovRequester::Leader(auth_token.as_ref()),
                 task_id,
                 taskprov_task_config.unwrap(),
-                auth_token.as_ref(),
             )
             .await?;
         } else if !auth_token
@@ -431,6 +525,7 @@ impl<C: Clock> Aggregator<C> {
         task_aggregator
             .handle_aggregate_continue(
                 &self.datastore,
+                &self.compute_pool,
                 &self.aggregate_step_failure_counter,
                 self.cfg.batch_aggregation_shard_count,
                 aggregation_job_id,
@@ -448,11 +543,28 @@ impl<C: Clock> Aggregator<C> {
         collection_job_id: &CollectionJobId,
         req_bytes: &[u8],
         auth_token: Option<AuthenticationToken>,
+        taskprov_task_config: Option<&TaskConfig>,
     ) -> Result<(), Error> {
-        let task_aggregator = self
-            .task_aggregator_for(task_id)
-            .await?
-            .ok_or(Error::UnrecognizedTask(*task_id))?;
+        let task_aggregator = match self.task_aggregator_for(task_id).await? {
+            Some(task_aggregator) => task_aggregator,
+            None if self.cfg.taskprov_config.enabled && taskprov_task_config.is_some() => {
+                self.taskprov_opt_in(
+                    TaskprovRequester::Collector(auth_token.as_ref()),
+                    task_id,
+                    taskprov_task_config.unwrap(),
+                )
+                .await?;
+
+                debug!(
+                    ?task_id,
+                    "taskprov: opt-in successful, retrying task acquisition"
+                );
+                self.task_aggregator_for(task_id).await?.ok_or_else(|| {
+                    Error::Internal("unexpectedly failed to create task".to_string())
+                })?
+            }
+            None => return Err(Error::UnrecognizedTask(*task_id)),
+        };
         if task_aggregator.task.role() != &Role::Leader {
             return Err(Error::UnrecognizedTask(*task_id));
         }
@@ -525,6 +637,33 @@ impl<C: Clock> Aggregator<C> {
         Ok(())
     }
 
+    /// Handle a GET request for the collectable batches of a fixed-size task. This is a Janus
+    /// extension to DAP, allowing collectors to discover batches by their IDs.
+    async fn handle_get_batches(
+        &self,
+        task_id: &TaskId,
+        auth_token: Option<AuthenticationToken>,
+        pagination_token: Option<BatchId>,
+    ) -> Result<CollectableBatchList, Error> {
+        let task_aggregator = self
+            .task_aggregator_for(task_id)
+            .await?
+            .ok_or(Error::UnrecognizedTask(*task_id))?;
+        if task_aggregator.task.role() != &Role::Leader {
+            return Err(Error::UnrecognizedTask(*task_id));
+        }
+        if !auth_token
+            .map(|t| task_aggregator.task.check_collector_auth_token(&t))
+            .unwrap_or(false)
+        {
+            return Err(Error::UnauthorizedRequest(*task_id));
+        }
+
+        task_aggregator
+            .handle_get_batches(&self.datastore, pagination_token)
+            .await
+    }
+
     /// Handle an aggregate share request. Only supported by the helper. `req_bytes` is an encoded
     /// [`AggregateShareReq`]. Returns an [`AggregateShare`].
     async fn handle_aggregate_share(
@@ -548,14 +687,13 @@ impl<C: Clock> Aggregator<C> {
             if self.cfg.taskprov_config.enabled && taskprov_task_config.is_some() {
                 let (peer_aggregator, _, _) = self
                     .taskprov_authorize_request(
-                        &Role::Leader,
+                        TaskprovRequester::Leader(auth_token.as_ref()),
                         task_id,
                         taskprov_task_config.unwrap(),
-                        auth_token.as_ref(),
                     )
                     .await?;
 
-                peer_aggregator.collector_hpke_config()
+                peer_aggregator.collector_hpke_config().clone()
             } else {
                 if !auth_token
                     .map(|t| task_aggregator.task.check_aggregator_auth_token(&t))
@@ -570,6 +708,7 @@ impl<C: Clock> Aggregator<C> {
                     .ok_or_else(|| {
                         Error::Internal("task is missing collector_hpke_config".to_string())
                     })?
+                    .clone()
             };
 
         task_aggregator
@@ -578,7 +717,7 @@ impl<C: Clock> Aggregator<C> {
                 &self.clock,
                 self.cfg.batch_aggregation_shard_count,
                 req_bytes,
-                collector_hpke_config,
+                &collector_hpke_config,
             )
             .await
     }
@@ -587,63 +726,44 @@ impl<C: Clock> Aggregator<C> {
         &self,
         task_id: &TaskId,
     ) -> Result<Option<Arc<TaskAggregator<C>>>, Error> {
-        // TODO(#238): don't cache forever (decide on & implement some cache eviction policy).
-        // This is important both to avoid ever-growing resource usage, and to allow aggregators to
-        // notice when a task changes (e.g. due to key rotation).
-
-        // Fast path: grab an existing task aggregator if one exists for this task.
-        {
-            let task_aggs = self.task_aggregators.lock().await;
-            if let Some(task_agg) = task_aggs.get(task_id) {
-                return Ok(Some(Arc::clone(task_agg)));
-            }
-        }
-
-        // TODO(#1639): not holding the lock while querying means that multiple tokio::tasks could
-        // enter this section and redundantly query the database. This could be costly at high QPS.
-
-        // Slow path: retrieve task, create a task aggregator, store it to the cache, then return it.
-        match self
-            .datastore
-            .run_tx_with_name("task_aggregator_get_task", |tx| {
-                let task_id = *task_id;
-                Box::pin(async move { tx.get_task(&task_id).await })
-            })
-            .await?
-        {
-            Some(task) => {
-                let task_agg =
-                    Arc::new(TaskAggregator::new(task, Arc::clone(&self.report_writer))?);
+        self.task_aggregators
+            .get_or_load(task_id, || async {
+                match self
+                    .datastore
+                    .run_tx_with_name("task_aggregator_get_task", |tx| {
+                        let task_id = *task_id;
+                        Box::pin(async move { tx.get_task(&task_id).await })
+                    })
+                    .await?
                 {
-                    let mut task_aggs = self.task_aggregators.lock().await;
-                    Ok(Some(Arc::clone(
-                        task_aggs.entry(*task_id).or_insert(task_agg),
-                    )))
+                    Some(task) => Ok(Some(Arc::new(TaskAggregator::new(
+                        task,
+                        Arc::clone(&self.report_writer),
+                    )?))),
+                    None => Ok(None),
                 }
-            }
-            // Avoid caching None, in case a previously non-existent task is provisioned while the
-            // system is live. Note that for #238, if we're improving this cache to indeed cache
-            // None, we must provide some mechanism for taskprov tasks to force a cache refresh.
-            None => Ok(None),
-        }
+            })
+            .await
+    }
+
+    /// Returns a hook which invalidates this aggregator's cached copy of a task. This should be
+    /// invoked whenever a task is changed other than through this aggregator.
+    pub(crate) fn task_invalidation_hook(&self) -> Arc<dyn TaskInvalidationHook> {
+        Arc::clone(&self.task_aggregators) as Arc<dyn TaskInvalidationHook>
     }
 
     /// Opts in or out of a taskprov task.
-    #[tracing::instrument(skip(self, aggregator_auth_token), err)]
+    #[tracing::instrument(skip(self, requester), err)]
     async fn taskprov_opt_in(
         &self,
-        peer_role: &Role,
+        requester: TaskprovRequester<'_>,
         task_id: &TaskId,
         task_config: &TaskConfig,
-        aggregator_auth_token: Option<&AuthenticationToken>,
     ) -> Result<(), Error> {
         let (peer_aggregator, leader_url, helper_url) = self
-            .taskprov_authorize_request(peer_role, task_id, task_config, aggregator_auth_token)
+            .taskprov_authorize_request(requester, task_id, task_config)
             .await?;
 
-        // TODO(#1647): Check whether task config parameters are acceptable for privacy and
-        // availability of the system.
-
         let vdaf_instance =
             task_config
                 .vdaf_config()
@@ -653,14 +773,9 @@ impl<C: Clock> Aggregator<C> {
                     Error::InvalidTask(*task_id, OptOutReason::InvalidParameter(err.to_string()))
                 })?;
 
-        let our_role = match peer_role {
+        let our_role = match peer_aggregator.role() {
             Role::Leader => Role::Helper,
-            Role::Helper => Role::Leader,
-            _ => {
-                return Err(Error::Internal(
-                    "role should have only been Helper or Leader".to_string(),
-                ))
-            }
+            _ => Role::Leader,
         };
 
         let vdaf_verify_keys =
@@ -676,12 +791,23 @@ impl<C: Clock> Aggregator<C> {
             vdaf_verify_keys,
             task_config.query_config().max_batch_query_count() as u64,
             Some(*task_config.task_expiration()),
-            peer_aggregator.report_expiry_age().cloned(),
             task_config.query_config().min_batch_size() as u64,
             *task_config.query_config().time_precision(),
-            *peer_aggregator.tolerable_clock_skew(),
+            (&task_config.vdaf_config().dp_config())
+                .try_into()
+                .map_err(|err| Error::InvalidTask(*task_id, OptOutReason::TaskParameters(err)))?,
+            task_config,
+            &peer_aggregator,
         )
         .map_err(|err| Error::InvalidTask(*task_id, OptOutReason::TaskParameters(err)))?;
+
+        // Check whether the task's parameters are acceptable for the privacy and availability of
+        // the system, as configured for this peer.
+        peer_aggregator
+            .policy()
+            .check(task.task(), self.clock.now())
+            .map_err(|err| Error::InvalidTask(*task_id, OptOutReason::PolicyViolation(err)))?;
+
         self.datastore
             .run_tx_with_name("taskprov_put_task", |tx| {
                 let task = task.clone();
@@ -704,6 +830,8 @@ impl<C: Clock> Aggregator<C> {
                     error => Err(error.into()),
                 }
             })?;
+        // The task's absence may have been cached when this request first looked it up.
+        self.task_aggregators.invalidate(task_id);
 
         info!(?task, ?peer_aggregator, "taskprov: opted into new task");
         Ok(())
@@ -712,14 +840,14 @@ impl<C: Clock> Aggregator<C> {
     /// Validate and authorize a taskprov request. Returns values necessary for determining whether
     /// we can opt into the task. This function might return an opt-out error for conditions that
     /// are relevant for all DAP workflows (e.g. task expiration).
-    #[tracing::instrument(skip(self, aggregator_auth_token), err)]
+    #[tracing::instrument(skip(self, requester), err)]
     async fn taskprov_authorize_request(
         &self,
-        peer_role: &Role,
+        requester: TaskprovRequester<'_>,
         task_id: &TaskId,
         task_config: &TaskConfig,
-        aggregator_auth_token: Option<&AuthenticationToken>,
-    ) -> Result<(&PeerAggregator, Url, Url), Error> {
+    ) -> Result<(Arc<PeerAggregator>, Url, Url), Error> {
+        let peer_role = &requester.peer_role();
         let aggregator_urls = task_config
             .aggregator_endpoints()
             .iter()
@@ -741,10 +869,18 @@ impl<C: Clock> Aggregator<C> {
                 OptOutReason::NoSuchPeer(*peer_role),
             ))?;
 
-        if !aggregator_auth_token
-            .map(|t| peer_aggregator.check_aggregator_auth_token(t))
-            .unwrap_or(false)
-        {
+        let authorized = match requester {
+            TaskprovRequester::Leader(auth_token) => auth_token
+                .map(|t| peer_aggregator.check_aggregator_auth_token(t))
+                .unwrap_or(false),
+            TaskprovRequester::Collector(auth_token) => auth_token
+                .map(|t| peer_aggregator.check_collector_auth_token(t))
+                .unwrap_or(false),
+            // Clients are not authenticated in DAP, so uploads may only provision tasks if the
+            // operator has explicitly allowed it.
+            TaskprovRequester::Client => self.cfg.taskprov_config.opt_in_on_upload,
+        };
+        if !authorized {
             return Err(Error::UnauthorizedRequest(*task_id));
         }
 
@@ -771,6 +907,28 @@ impl<C: Clock> Aggregator<C> {
     }
 }
 
+/// The sender of a request which may provision a taskprov task, along with the credential it
+/// presented.
+#[derive(Clone, Copy)]
+enum TaskprovRequester<'a> {
+    /// The leader, in a request to this aggregator as the helper.
+    Leader(Option<&'a AuthenticationToken>),
+    /// The collector, in a request to this aggregator as the leader.
+    Collector(Option<&'a AuthenticationToken>),
+    /// A client uploading a report to this aggregator as the leader.
+    Client,
+}
+
+impl TaskprovRequester<'_> {
+    /// Returns the role of the peer aggregator with which the task would be shared.
+    fn peer_role(&self) -> Role {
+        match self {
+            Self::Leader(_) => Role::Leader,
+            Self::Collector(_) | Self::Client => Role::Helper,
+        }
+    }
+}
+
 /// TaskAggregator provides aggregation functionality for a single task.
 // TODO(#224): refactor Aggregator to perform indepedent batched operations (e.g. report handling in
 // Aggregate requests) using a parallelized library like Rayon.
@@ -920,6 +1078,7 @@ impl<C: Clock> TaskAggregator<C> {
         &self,
         datastore: &Datastore<C>,
         global_hpke_keypairs: &GlobalHpkeKeypairCache,
+        compute_pool: &ComputePool,
         aggregate_step_failure_counter: &Counter<u64>,
         aggregation_job_id: &AggregationJobId,
         req_bytes: &[u8],
@@ -928,6 +1087,7 @@ impl<C: Clock> TaskAggregator<C> {
             .handle_aggregate_init(
                 datastore,
                 global_hpke_keypairs,
+                compute_pool,
                 aggregate_step_failure_counter,
                 Arc::clone(&self.task),
                 aggregation_job_id,
@@ -939,6 +1099,7 @@ impl<C: Clock> TaskAggregator<C> {
     async fn handle_aggregate_continue(
         &self,
         datastore: &Datastore<C>,
+        compute_pool: &ComputePool,
         aggregate_step_failure_counter: &Counter<u64>,
         batch_aggregation_shard_count: u64,
         aggregation_job_id: &AggregationJobId,
@@ -948,6 +1109,7 @@ impl<C: Clock> TaskAggregator<C> {
         self.vdaf_ops
             .handle_aggregate_continue(
                 datastore,
+                compute_pool,
                 aggregate_step_failure_counter,
                 Arc::clone(&self.task),
                 batch_aggregation_shard_count,
@@ -994,6 +1156,16 @@ impl<C: Clock> TaskAggregator<C> {
             .await
     }
 
+    async fn handle_get_batches(
+        &self,
+        datastore: &Datastore<C>,
+        pagination_token: Option<BatchId>,
+    ) -> Result<CollectableBatchList, Error> {
+        self.vdaf_ops
+            .handle_get_batches(datastore, Arc::clone(&self.task), pagination_token)
+            .await
+    }
+
     async fn handle_aggregate_share(
         &self,
         datastore: &Datastore<C>,
@@ -1203,7 +1375,7 @@ impl VdafOps {
     /// Implements the `/aggregate` endpoint for initialization requests for the helper, described
     /// in §4.4.4.1 & §4.4.4.2 of draft-gpew-priv-ppm.
     #[tracing::instrument(
-        skip(self, datastore, aggregate_step_failure_counter, task, req_bytes),
+        skip(self, datastore, compute_pool, aggregate_step_failure_counter, task, req_bytes),
         fields(task_id = ?task.id()),
         err
     )]
@@ -1211,6 +1383,7 @@ impl VdafOps {
         &self,
         datastore: &Datastore<C>,
         global_hpke_keypairs: &GlobalHpkeKeypairCache,
+        compute_pool: &ComputePool,
         aggregate_step_failure_counter: &Counter<u64>,
         task: Arc<Task>,
         aggregation_job_id: &AggregationJobId,
@@ -1222,7 +1395,8 @@ impl VdafOps {
                     Self::handle_aggregate_init_generic::<VERIFY_KEY_LENGTH, TimeInterval, VdafType, _>(
                         datastore,
                         global_hpke_keypairs,
-                        vdaf,
+                        compute_pool,
+                        Arc::clone(vdaf),
                         aggregate_step_failure_counter,
                         task,
                         aggregation_job_id,
@@ -1237,7 +1411,8 @@ impl VdafOps {
                     Self::handle_aggregate_init_generic::<VERIFY_KEY_LENGTH, FixedSize, VdafType, _>(
                         datastore,
                         global_hpke_keypairs,
-                        vdaf,
+                        compute_pool,
+                        Arc::clone(vdaf),
                         aggregate_step_failure_counter,
                         task,
                         aggregation_job_id,
@@ -1251,13 +1426,22 @@ impl VdafOps {
     }
 
     #[tracing::instrument(
-        skip(self, datastore, aggregate_step_failure_counter, task, req, request_hash),
+        skip(
+            self,
+            datastore,
+            compute_pool,
+            aggregate_step_failure_counter,
+            task,
+            req,
+            request_hash
+        ),
         fields(task_id = ?task.id()),
         err
     )]
     async fn handle_aggregate_continue<C: Clock>(
         &self,
         datastore: &Datastore<C>,
+        compute_pool: &ComputePool,
         aggregate_step_failure_counter: &Counter<u64>,
         task: Arc<Task>,
         batch_aggregation_shard_count: u64,
@@ -1270,6 +1454,7 @@ impl VdafOps {
                 vdaf_ops_dispatch!(self, (vdaf, _, VdafType, VERIFY_KEY_LENGTH) => {
                     Self::handle_aggregate_continue_generic::<VERIFY_KEY_LENGTH, TimeInterval, VdafType, _>(
                         datastore,
+                        compute_pool,
                         Arc::clone(vdaf),
                         aggregate_step_failure_counter,
                         task,
@@ -1285,6 +1470,7 @@ impl VdafOps {
                 vdaf_ops_dispatch!(self, (vdaf, _, VdafType, VERIFY_KEY_LENGTH) => {
                     Self::handle_aggregate_continue_generic::<VERIFY_KEY_LENGTH, FixedSize, VdafType, _>(
                         datastore,
+                        compute_pool,
                         Arc::clone(vdaf),
                         aggregate_step_failure_counter,
                         task,
@@ -1533,12 +1719,195 @@ impl VdafOps {
         Ok(existing_aggregation_job.eq(incoming_aggregation_job))
     }
 
+    /// Decrypts a single report share received by the helper and computes its initial
+    /// preparation state. Failures specific to the report share are recorded in the returned
+    /// report aggregation rather than returned as errors.
+    #[allow(clippy::too_many_arguments)]
+    fn prepare_init_report_share<const SEED_SIZE: usize, A>(
+        vdaf: &A,
+        task: &Task,
+        global_hpke_keypairs: &HashMap<HpkeConfigId, Arc<HpkeKeypair>>,
+        verify_key: &VerifyKey<SEED_SIZE>,
+        agg_param: &A::AggregationParam,
+        aggregate_step_failure_counter: &Counter<u64>,
+        aggregation_job_id: AggregationJobId,
+        ord: usize,
+        report_share: ReportShare,
+    ) -> Result<ReportShareData<SEED_SIZE, A>, Error>
+    where
+        A: vdaf::Aggregator<SEED_SIZE, 16>,
+    {
+        let task_hpke_keypair = task
+            .hpke_keys()
+            .get(report_share.encrypted_input_share().config_id());
+
+        let global_hpke_keypair = global_hpke_keypairs
+            .get(report_share.encrypted_input_share().config_id())
+            .cloned();
+
+        // If decryption fails, then the aggregator MUST fail with error `hpke-decrypt-error`. (§4.4.2.2)
+        let try_hpke_open = |hpke_keypair: &HpkeKeypair| {
+            hpke::open(
+                hpke_keypair.config(),
+                hpke_keypair.private_key(),
+                &HpkeApplicationInfo::new(&Label::InputShare, &Role::Client, &Role::Helper),
+                report_share.encrypted_input_share(),
+                &InputShareAad::new(
+                    *task.id(),
+                    report_share.metadata().clone(),
+                    report_share.public_share().to_vec(),
+                )
+                .get_encoded(),
+            )
+        };
+
+        let check_keypairs = if task_hpke_keypair.is_none() && global_hpke_keypair.is_none() {
+            info!(
+                config_id = %report_share.encrypted_input_share().config_id(),
+                "Helper encrypted input share references unknown HPKE config ID"
+            );
+            aggregate_step_failure_counter
+                .add(1, &[KeyValue::new("type", "unknown_hpke_config_id")]);
+            Err(ReportShareError::HpkeUnknownConfigId)
+        } else {
+            Ok(())
+        };
+
+        let plaintext = check_keypairs.and_then(|_| {
+            match (task_hpke_keypair, global_hpke_keypair) {
+                (None, None) => unreachable!("already checked this condition"),
+                (None, Some(global_hpke_keypair)) => try_hpke_open(&global_hpke_keypair),
+                (Some(task_hpke_keypair), None) => try_hpke_open(task_hpke_keypair),
+                (Some(task_hpke_keypair), Some(global_hpke_keypair)) => {
+                    try_hpke_open(task_hpke_keypair).or_else(|error| match error {
+                        // Only attempt second trial if _decryption_ fails, and not some
+                        // error in server-side HPKE configuration.
+                        hpke::Error::Hpke(_) => try_hpke_open(&global_hpke_keypair),
+                        error => Err(error),
+                    })
+                }
+            }
+            .map_err(|error| {
+                info!(
+                    task_id = %task.id(),
+                    metadata = ?report_share.metadata(),
+                    ?error,
+                    "Couldn't decrypt helper's report share"
+                );
+                aggregate_step_failure_counter.add(1, &[KeyValue::new("type", "decrypt_failure")]);
+                ReportShareError::HpkeDecryptError
+            })
+        });
+
+        let plaintext_input_share = plaintext.and_then(|plaintext| {
+                        let plaintext_input_share = PlaintextInputShare::get_decoded(&plaintext).map_err(|error| {
+                            info!(task_id = %task.id(), metadata = ?report_share.metadata(), ?error, "Couldn't decode helper's plaintext input share");
+                            aggregate_step_failure_counter.add(1, &[KeyValue::new("type", "plaintext_input_share_decode_failure")]);
+                            ReportShareError::UnrecognizedMessage
+                        })?;
+                        // Check for repeated extensions.
+                        let mut extension_types = HashSet::new();
+                        if !plaintext_input_share
+                            .extensions()
+                            .iter()
+                            .all(|extension| extension_types.insert(extension.extension_type())) {
+                                info!(task_id = %task.id(), metadata = ?report_share.metadata(), "Received report share with duplicate extensions");
+                                aggregate_step_failure_counter.add(1, &[KeyValue::new("type", "duplicate_extension")]);
+                                return Err(ReportShareError::UnrecognizedMessage)
+                        }
+                        Ok(plaintext_input_share)
+                    });
+
+        let input_share = plaintext_input_share.and_then(|plaintext_input_share| {
+                        A::InputShare::get_decoded_with_param(&(vdaf, Role::Helper.index().unwrap()), plaintext_input_share.payload())
+                            .map_err(|error| {
+                                info!(task_id = %task.id(), metadata = ?report_share.metadata(), ?error, "Couldn't decode helper's input share");
+                                aggregate_step_failure_counter.add(1, &[KeyValue::new("type", "input_share_decode_failure")]);
+                                ReportShareError::UnrecognizedMessage
+                            })
+                    });
+
+        let public_share = A::PublicShare::get_decoded_with_param(vdaf, report_share.public_share()).map_err(|error|{
+                        info!(task_id = %task.id(), metadata = ?report_share.metadata(), ?error, "Couldn't decode public share");
+                        aggregate_step_failure_counter.add(1, &[KeyValue::new("type", "public_share_decode_failure")]);
+                        ReportShareError::UnrecognizedMessage
+                    });
+
+        let shares = input_share.and_then(|input_share| Ok((public_share?, input_share)));
+
+        // Next, the aggregator runs the preparation-state initialization algorithm for the VDAF
+        // associated with the task and computes the first state transition. [...] If either
+        // step fails, then the aggregator MUST fail with error `vdaf-prep-error`. (§4.4.2.2)
+        let init_rslt = shares.and_then(|(public_share, input_share)| {
+            trace_span!("VDAF preparation")
+                .in_scope(|| {
+                    vdaf.prepare_init(
+                        verify_key.as_bytes(),
+                        Role::Helper.index().unwrap(),
+                        agg_param,
+                        report_share.metadata().id().as_ref(),
+                        &public_share,
+                        &input_share,
+                    )
+                })
+                .map_err(|error| {
+                    info!(
+                        task_id = %task.id(),
+                        report_id = %report_share.metadata().id(),
+                        ?error,
+                        "Couldn't prepare_init report share"
+                    );
+                    aggregate_step_failure_counter
+                        .add(1, &[KeyValue::new("type", "prepare_init_failure")]);
+                    ReportShareError::VdafPrepError
+                })
+        });
+
+        Ok(match init_rslt {
+            Ok((prep_state, prep_share)) => {
+                let encoded_prep_share = prep_share.get_encoded();
+                ReportShareData::new(
+                    report_share.clone(),
+                    ReportAggregation::<SEED_SIZE, A>::new(
+                        *task.id(),
+                        aggregation_job_id,
+                        *report_share.metadata().id(),
+                        *report_share.metadata().time(),
+                        ord.try_into()?,
+                        Some(PrepareStep::new(
+                            *report_share.metadata().id(),
+                            PrepareStepResult::Continued(encoded_prep_share),
+                        )),
+                        ReportAggregationState::<SEED_SIZE, A>::Waiting(prep_state, None),
+                    ),
+                )
+            }
+
+            Err(err) => ReportShareData::new(
+                report_share.clone(),
+                ReportAggregation::<SEED_SIZE, A>::new(
+                    *task.id(),
+                    aggregation_job_id,
+                    *report_share.metadata().id(),
+                    *report_share.metadata().time(),
+                    ord.try_into()?,
+                    Some(PrepareStep::new(
+                        *report_share.metadata().id(),
+                        PrepareStepResult::Failed(err),
+                    )),
+                    ReportAggregationState::<SEED_SIZE, A>::Failed(err),
+                ),
+            ),
+        })
+    }
+
     /// Implements the aggregate initialization request portion of the `/aggregate` endpoint for the
     /// helper, described in §4.4.4.1 of draft-gpew-priv-ppm.
     async fn handle_aggregate_init_generic<const SEED_SIZE: usize, Q, A, C>(
         datastore: &Datastore<C>,
         global_hpke_keypairs: &GlobalHpkeKeypairCache,
-        vdaf: &A,
+        compute_pool: &ComputePool,
+        vdaf: Arc<A>,
         aggregate_step_failure_counter: &Counter<u64>,
         task: Arc<Task>,
         aggregation_job_id: &AggregationJobId,
@@ -1573,14 +1942,24 @@ impl VdafOps {
             }
         }
 
-        // Decrypt shares & prepare initialization states. (§4.4.4.1)
-        let mut saw_continue = false;
-        let mut report_share_data = Vec::new();
+        // Snapshot the global HPKE keypairs referenced by this request, so that report shares can
+        // be decrypted away from the async runtime.
+        let global_hpke_keypairs: HashMap<_, _> = req
+            .report_shares()
+            .iter()
+            .filter_map(|report_share| {
+                let config_id = report_share.encrypted_input_share().config_id();
+                global_hpke_keypairs
+                    .keypair(config_id)
+                    .map(|keypair| (*config_id, keypair))
+            })
+            .collect();
+        let agg_param = A::AggregationParam::get_decoded(req.aggregation_parameter())?;
+
+        // Compute intervals for each batch identifier included in this aggregation job.
         let mut interval_per_batch_identifier: HashMap<Q::BatchIdentifier, Interval> =
             HashMap::new();
-        let agg_param = A::AggregationParam::get_decoded(req.aggregation_parameter())?;
-        for (ord, report_share) in req.report_shares().iter().enumerate() {
-            // Compute intervals for each batch identifier included in this aggregation job.
+        for report_share in req.report_shares() {
             let batch_identifier = Q::to_batch_identifier(
                 &task,
                 req.batch_selector().batch_identifier(),
@@ -1594,172 +1973,51 @@ impl VdafOps {
                     entry.insert(Interval::from_time(report_share.metadata().time())?);
                 }
             }
+        }
 
-            let task_hpke_keypair = task
-                .hpke_keys()
-                .get(report_share.encrypted_input_share().config_id());
-
-            let global_hpke_keypair =
-                global_hpke_keypairs.keypair(report_share.encrypted_input_share().config_id());
-
-            // If decryption fails, then the aggregator MUST fail with error `hpke-decrypt-error`. (§4.4.2.2)
-            let try_hpke_open = |hpke_keypair: &HpkeKeypair| {
-                hpke::open(
-                    hpke_keypair.config(),
-                    hpke_keypair.private_key(),
-                    &HpkeApplicationInfo::new(&Label::InputShare, &Role::Client, &Role::Helper),
-                    report_share.encrypted_input_share(),
-                    &InputShareAad::new(
-                        *task.id(),
-                        report_share.metadata().clone(),
-                        report_share.public_share().to_vec(),
-                    )
-                    .get_encoded(),
-                )
-            };
-
-            let check_keypairs = if task_hpke_keypair.is_none() && global_hpke_keypair.is_none() {
-                info!(
-                    config_id = %report_share.encrypted_input_share().config_id(),
-                    "Helper encrypted input share references unknown HPKE config ID"
+        // Decrypt shares & prepare initialization states. (§4.4.4.1) This is done in parallel on
+        // the compute pool, outside of any datastore transaction, as VDAF preparation may be
+        // expensive.
+        let report_share_data = compute_pool
+            .map(req.report_shares().iter().cloned().enumerate().collect(), {
+                let (
+                    vdaf,
+                    task,
+                    verify_key,
+                    agg_param,
+                    aggregate_step_failure_counter,
+                    aggregation_job_id,
+                ) = (
+                    Arc::clone(&vdaf),
+                    Arc::clone(&task),
+                    VerifyKey::<SEED_SIZE>::new(*verify_key.as_bytes()),
+                    agg_param.clone(),
+                    aggregate_step_failure_counter.clone(),
+                    *aggregation_job_id,
                 );
-                aggregate_step_failure_counter
-                    .add(1, &[KeyValue::new("type", "unknown_hpke_config_id")]);
-                Err(ReportShareError::HpkeUnknownConfigId)
-            } else {
-                Ok(())
-            };
-
-            let plaintext = check_keypairs.and_then(|_| {
-                match (task_hpke_keypair, global_hpke_keypair) {
-                    (None, None) => unreachable!("already checked this condition"),
-                    (None, Some(global_hpke_keypair)) => try_hpke_open(&global_hpke_keypair),
-                    (Some(task_hpke_keypair), None) => try_hpke_open(task_hpke_keypair),
-                    (Some(task_hpke_keypair), Some(global_hpke_keypair)) => {
-                        try_hpke_open(task_hpke_keypair).or_else(|error| match error {
-                            // Only attempt second trial if _decryption_ fails, and not some
-                            // error in server-side HPKE configuration.
-                            hpke::Error::Hpke(_) => try_hpke_open(&global_hpke_keypair),
-                            error => Err(error),
-                        })
-                    }
+                move |(ord, report_share)| {
+                    Self::prepare_init_report_share(
+                        vdaf.as_ref(),
+                        &task,
+                        &global_hpke_keypairs,
+                        &verify_key,
+                        &agg_param,
+                        &aggregate_step_failure_counter,
+                        aggregation_job_id,
+                        ord,
+                        report_share,
+                    )
                 }
-                .map_err(|error| {
-                    info!(
-                        task_id = %task.id(),
-                        metadata = ?report_share.metadata(),
-                        ?error,
-                        "Couldn't decrypt helper's report share"
-                    );
-                    aggregate_step_failure_counter
-                        .add(1, &[KeyValue::new("type", "decrypt_failure")]);
-                    ReportShareError::HpkeDecryptError
-                })
-            });
-
-            let plaintext_input_share = plaintext.and_then(|plaintext| {
-                let plaintext_input_share = PlaintextInputShare::get_decoded(&plaintext).map_err(|error| {
-                    info!(task_id = %task.id(), metadata = ?report_share.metadata(), ?error, "Couldn't decode helper's plaintext input share");
-                    aggregate_step_failure_counter.add(1, &[KeyValue::new("type", "plaintext_input_share_decode_failure")]);
-                    ReportShareError::UnrecognizedMessage
-                })?;
-                // Check for repeated extensions.
-                let mut extension_types = HashSet::new();
-                if !plaintext_input_share
-                    .extensions()
-                    .iter()
-                    .all(|extension| extension_types.insert(extension.extension_type())) {
-                        info!(task_id = %task.id(), metadata = ?report_share.metadata(), "Received report share with duplicate extensions");
-                        aggregate_step_failure_counter.add(1, &[KeyValue::new("type", "duplicate_extension")]);
-                        return Err(ReportShareError::UnrecognizedMessage)
-                }
-                Ok(plaintext_input_share)
-            });
-
-            let input_share = plaintext_input_share.and_then(|plaintext_input_share| {
-                A::InputShare::get_decoded_with_param(&(vdaf, Role::Helper.index().unwrap()), plaintext_input_share.payload())
-                    .map_err(|error| {
-                        info!(task_id = %task.id(), metadata = ?report_share.metadata(), ?error, "Couldn't decode helper's input share");
-                        aggregate_step_failure_counter.add(1, &[KeyValue::new("type", "input_share_decode_failure")]);
-                        ReportShareError::UnrecognizedMessage
-                    })
-            });
-
-            let public_share = A::PublicShare::get_decoded_with_param(vdaf, report_share.public_share()).map_err(|error|{
-                info!(task_id = %task.id(), metadata = ?report_share.metadata(), ?error, "Couldn't decode public share");
-                aggregate_step_failure_counter.add(1, &[KeyValue::new("type", "public_share_decode_failure")]);
-                ReportShareError::UnrecognizedMessage
-            });
-
-            let shares = input_share.and_then(|input_share| Ok((public_share?, input_share)));
-
-            // Next, the aggregator runs the preparation-state initialization algorithm for the VDAF
-            // associated with the task and computes the first state transition. [...] If either
-            // step fails, then the aggregator MUST fail with error `vdaf-prep-error`. (§4.4.2.2)
-            let init_rslt = shares.and_then(|(public_share, input_share)| {
-                trace_span!("VDAF preparation")
-                    .in_scope(|| {
-                        vdaf.prepare_init(
-                            verify_key.as_bytes(),
-                            Role::Helper.index().unwrap(),
-                            &agg_param,
-                            report_share.metadata().id().as_ref(),
-                            &public_share,
-                            &input_share,
-                        )
-                    })
-                    .map_err(|error| {
-                        info!(
-                            task_id = %task.id(),
-                            report_id = %report_share.metadata().id(),
-                            ?error,
-                            "Couldn't prepare_init report share"
-                        );
-                        aggregate_step_failure_counter
-                            .add(1, &[KeyValue::new("type", "prepare_init_failure")]);
-                        ReportShareError::VdafPrepError
-                    })
-            });
-
-            report_share_data.push(match init_rslt {
-                Ok((prep_state, prep_share)) => {
-                    saw_continue = true;
-
-                    let encoded_prep_share = prep_share.get_encoded();
-                    ReportShareData::new(
-                        report_share.clone(),
-                        ReportAggregation::<SEED_SIZE, A>::new(
-                            *task.id(),
-                            *aggregation_job_id,
-                            *report_share.metadata().id(),
-                            *report_share.metadata().time(),
-                            ord.try_into()?,
-                            Some(PrepareStep::new(
-                                *report_share.metadata().id(),
-                                PrepareStepResult::Continued(encoded_prep_share),
-                            )),
-                            ReportAggregationState::<SEED_SIZE, A>::Waiting(prep_state, None),
-                        ),
-                    )
-                }
-
-                Err(err) => ReportShareData::new(
-                    report_share.clone(),
-                    ReportAggregation::<SEED_SIZE, A>::new(
-                        *task.id(),
-                        *aggregation_job_id,
-                        *report_share.metadata().id(),
-                        *report_share.metadata().time(),
-                        ord.try_into()?,
-                        Some(PrepareStep::new(
-                            *report_share.metadata().id(),
-                            PrepareStepResult::Failed(err),
-                        )),
-                        ReportAggregationState::<SEED_SIZE, A>::Failed(err),
-                    ),
-                ),
-            });
-        }
+            })
+            .await?
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>()?;
+        let saw_continue = report_share_data.iter().any(|data| {
+            matches!(
+                data.report_aggregation.state(),
+                ReportAggregationState::Waiting(_, _)
+            )
+        });
 
         // Store data to datastore.
         let req = Arc::new(req);
@@ -1958,6 +2216,7 @@ impl VdafOps {
         C: Clock,
     >(
         datastore: &Datastore<C>,
+        compute_pool: &ComputePool,
         vdaf: Arc<A>,
         aggregate_step_failure_counter: &Counter<u64>,
         task: Arc<Task>,
@@ -1982,26 +2241,18 @@ impl VdafOps {
             ));
         }
 
-        // TODO(#224): don't hold DB transaction open while computing VDAF updates?
+        // Read existing state.
         // TODO(#224): don't do O(n) network round-trips (where n is the number of prepare steps)
-        Ok(datastore
-            .run_tx_with_name("aggregate_continue", |tx| {
-                let (
-                    vdaf,
-                    aggregate_step_failure_counter,
-                    task,
-                    aggregation_job_id,
-                    leader_aggregation_job,
-                ) = (
+        let (helper_aggregation_job, report_aggregations) = match datastore
+            .run_tx_with_name("aggregate_continue_read", |tx| {
+                let (vdaf, task, aggregation_job_id, leader_aggregation_job) = (
                     Arc::clone(&vdaf),
-                    aggregate_step_failure_counter.clone(),
                     Arc::clone(&task),
                     *aggregation_job_id,
                     Arc::clone(&leader_aggregation_job),
                 );
 
                 Box::pin(async move {
-                    // Read existing state.
                     let (helper_aggregation_job, report_aggregations) = try_join!(
                         tx.get_aggregation_job::<SEED_SIZE, Q, A>(task.id(), &aggregation_job_id),
                         tx.get_report_aggregations_for_aggregation_job(
@@ -2019,63 +2270,118 @@ impl VdafOps {
                         )
                     })?;
 
-                    // If the leader's request is on the same round as our stored aggregation job,
-                    // then we probably have already received this message and computed this round,
-                    // but the leader never got our response and so retried stepping the job.
-                    // TODO(issue #1087): measure how often this happens with a Prometheus metric
-                    if helper_aggregation_job.round() == leader_aggregation_job.round() {
-                        match helper_aggregation_job.last_request_hash() {
-                            None => {
-                                return Err(datastore::Error::User(
-                                    Error::Internal(format!(
-                                        "aggregation job {aggregation_job_id} is in round {} but \
-                                         has no last request hash",
-                                        helper_aggregation_job.round(),
-                                    ))
+                    if Self::check_aggregation_job_continue_round(
+                        &task,
+                        &helper_aggregation_job,
+                        &leader_aggregation_job,
+                        &request_hash,
+                    )
+                    .map_err(|err| datastore::Error::User(err.into()))?
+                    {
+                        return Ok(Err(Self::aggregation_job_resp_for(report_aggregations)));
+                    }
+                    Ok(Ok((helper_aggregation_job, report_aggregations)))
+                })
+            })
+            .await?
+        {
+            Ok(state) => state,
+            Err(replayed_response) => return Ok(replayed_response),
+        };
+
+        // The leader is advancing us to the next round. Step the aggregation job to compute the
+        // next round of prepare messages and state. This is done on the compute pool, outside of
+        // any datastore transaction, as VDAF preparation may be expensive.
+        let stepped_report_aggregations = Self::step_aggregation_job(
+            compute_pool,
+            &task,
+            Arc::clone(&vdaf),
+            &helper_aggregation_job,
+            report_aggregations,
+            &leader_aggregation_job,
+            aggregate_step_failure_counter,
+        )
+        .await?;
+        let (helper_aggregation_job, stepped_report_aggregations) = (
+            Arc::new(helper_aggregation_job),
+            Arc::new(stepped_report_aggregations),
+        );
+
+        // Write the new state, first checking that the aggregation job was not concurrently
+        // modified since we read it. If it was, the stepped report aggregations are discarded and
+        // the request is evaluated against the updated aggregation job.
+        Ok(datastore
+            .run_tx_with_name("aggregate_continue_write", |tx| {
+                let (
+                    vdaf,
+                    task,
+                    aggregation_job_id,
+                    leader_aggregation_job,
+                    helper_aggregation_job,
+                    stepped_report_aggregations,
+                ) = (
+                    Arc::clone(&vdaf),
+                    Arc::clone(&task),
+                    *aggregation_job_id,
+                    Arc::clone(&leader_aggregation_job),
+                    Arc::clone(&helper_aggregation_job),
+                    Arc::clone(&stepped_report_aggregations),
+                );
+
+                Box::pin(async move {
+                    let current_aggregation_job = tx
+                        .get_aggregation_job::<SEED_SIZE, Q, A>(task.id(), &aggregation_job_id)
+                        .await?
+                        .ok_or_else(|| {
+                            datastore::Error::User(
+                                Error::UnrecognizedAggregationJob(*task.id(), aggregation_job_id)
                                     .into(),
-                                ));
-                            }
-                            Some(previous_hash) => {
-                                if request_hash != previous_hash {
-                                    return Err(datastore::Error::User(
-                                        Error::ForbiddenMutation {
-                                            resource_type: "aggregation job continuation",
-                                            identifier: aggregation_job_id.to_string(),
-                                        }
-                                        .into(),
-                                    ));
-                                }
-                            }
-                        }
-                        return Ok(Self::aggregation_job_resp_for(report_aggregations));
-                    } else if helper_aggregation_job.round().increment()
-                        != leader_aggregation_job.round()
+                            )
+                        })?;
+
+                    if current_aggregation_job.round() != helper_aggregation_job.round()
+                        || current_aggregation_job.last_request_hash()
+                            != helper_aggregation_job.last_request_hash()
                     {
-                        // If this is not a replay, the leader should be advancing our state to the next
-                        // round and no further.
-                        return Err(datastore::Error::User(
-                            Error::RoundMismatch {
-                                task_id: *task.id(),
-                                aggregation_job_id,
-                                expected_round: helper_aggregation_job.round().increment(),
-                                got_round: leader_aggregation_job.round(),
-                            }
-                            .into(),
+                        // The aggregation job was modified by another request after we read it.
+                        // This is expected if the leader retried this request concurrently, in
+                        // which case we serve the stored response.
+                        if !Self::check_aggregation_job_continue_round(
+                            &task,
+                            &current_aggregation_job,
+                            &leader_aggregation_job,
+                            &request_hash,
+                        )
+                        .map_err(|err| datastore::Error::User(err.into()))?
+                        {
+                            return Err(datastore::Error::User(
+                                Error::Internal(format!(
+                                    "aggregation job {aggregation_job_id} was concurrently \
+                                     modified"
+                                ))
+                                .into(),
+                            ));
+                        }
+                        return Ok(Self::aggregation_job_resp_for(
+                            tx.get_report_aggregations_for_aggregation_job(
+                                vdaf.as_ref(),
+                                &Role::Helper,
+                                task.id(),
+                                &aggregation_job_id,
+                            )
+                            .await?,
                         ));
                     }
 
-                    // The leader is advancing us to the next round. Step the aggregation job to
-                    // compute the next round of prepare messages and state.
-                    Self::step_aggregation_job(
+                    Self::write_stepped_aggregation_job(
                         tx,
                         task,
                         vdaf,
                         batch_aggregation_shard_count,
-                        helper_aggregation_job,
-                        report_aggregations,
+                        current_aggregation_job,
+                        stepped_report_aggregations.as_ref().clone(),
                         leader_aggregation_job,
                         request_hash,
-                        aggregate_step_failure_counter,
                     )
                     .await
                 })
@@ -2083,6 +2389,56 @@ impl VdafOps {
             .await?)
     }
 
+    /// Checks the round of a continue request from the leader against the helper's stored
+    /// aggregation job. Returns `true` if the request is a replay of the request that advanced the
+    /// aggregation job to its current round, `false` if the request advances the aggregation job
+    /// to the next round, or an error otherwise.
+    fn check_aggregation_job_continue_round<const SEED_SIZE: usize, Q, A>(
+        task: &Task,
+        helper_aggregation_job: &AggregationJob<SEED_SIZE, Q, A>,
+        leader_aggregation_job: &AggregationJobContinueReq,
+        request_hash: &[u8; 32],
+    ) -> Result<bool, Error>
+    where
+        Q: AccumulableQueryType,
+        A: vdaf::Aggregator<SEED_SIZE, 16>,
+    {
+        // If the leader's request is on the same round as our stored aggregation job, then we
+        // probably have already received this message and computed this round, but the leader
+        // never got our response and so retried stepping the job.
+        // TODO(issue #1087): measure how often this happens with a Prometheus metric
+        if helper_aggregation_job.round() == leader_aggregation_job.round() {
+            match helper_aggregation_job.last_request_hash() {
+                None => {
+                    return Err(Error::Internal(format!(
+                        "aggregation job {} is in round {} but has no last request hash",
+                        helper_aggregation_job.id(),
+                        helper_aggregation_job.round(),
+                    )));
+                }
+                Some(previous_hash) => {
+                    if request_hash != &previous_hash {
+                        return Err(Error::ForbiddenMutation {
+                            resource_type: "aggregation job continuation",
+                            identifier: helper_aggregation_job.id().to_string(),
+                        });
+                    }
+                }
+            }
+            return Ok(true);
+        } else if helper_aggregation_job.round().increment() != leader_aggregation_job.round() {
+            // If this is not a replay, the leader should be advancing our state to the next
+            // round and no further.
+            return Err(Error::RoundMismatch {
+                task_id: *task.id(),
+                aggregation_job_id: *helper_aggregation_job.id(),
+                expected_round: helper_aggregation_job.round().increment(),
+                got_round: leader_aggregation_job.round(),
+            });
+        }
+        Ok(false)
+    }
+
     /// Handle requests to the leader to create a collection job.
     #[tracing::instrument(
         skip(self, datastore, task, collection_req_bytes),
@@ -2201,63 +2557,70 @@ impl VdafOps {
                     }
 
                     debug!(collect_request = ?req, "Cache miss, creating new collection job");
-                    let (_, report_count, batches, batches_with_reports) = try_join!(
-                        Q::validate_query_count::<SEED_SIZE, C, A>(
-                            tx,
-                            &vdaf,
-                            &task,
-                            &collection_identifier,
-                            &aggregation_param,
-                        ),
-                        Q::count_client_reports(tx, &task, &collection_identifier),
-                        try_join_all(
-                            Q::batch_identifiers_for_collection_identifier(
-                                &task,
-                                &collection_identifier
-                            )
-                            .map(|batch_identifier| {
-                                let task_id = *task.id();
-                                let aggregation_param = Arc::clone(&aggregation_param);
-                                async move {
-                                    let batch = tx
-                                        .get_batch::<SEED_SIZE, Q, A>(
-                                            &task_id,
-                                            &batch_identifier,
-                                            &aggregation_param,
-                                        )
-                                        .await?;
-                                    Ok::<_, datastore::Error>((batch_identifier, batch))
-                                }
-                            }),
-                        ),
-                        try_join_all(
-                            Q::batch_identifiers_for_collection_identifier(
+                    let (_, report_count, existing_collection_jobs, batches, batches_with_reports) =
+                        try_join!(
+                            Q::validate_query_count::<SEED_SIZE, C, A>(
+                                tx,
+                                &vdaf,
                                 &task,
-                                &collection_identifier
-                            )
-                            .map(|batch_identifier| {
-                                let task_id = *task.id();
-                                async move {
-                                    if let Some(batch_interval) =
-                                        Q::to_batch_interval(&batch_identifier)
-                                    {
-                                        if tx
-                                            .interval_has_unaggregated_reports(
+                                &collection_identifier,
+                                &aggregation_param,
+                            ),
+                            Q::count_client_reports(tx, &task, &collection_identifier),
+                            Q::get_collection_jobs_including::<SEED_SIZE, C, A>(
+                                tx,
+                                &vdaf,
+                                task.id(),
+                                &collection_identifier,
+                            ),
+                            try_join_all(
+                                Q::batch_identifiers_for_collection_identifier(
+                                    &task,
+                                    &collection_identifier
+                                )
+                                .map(|batch_identifier| {
+                                    let task_id = *task.id();
+                                    let aggregation_param = Arc::clone(&aggregation_param);
+                                    async move {
+                                        let batch = tx
+                                            .get_batch::<SEED_SIZE, Q, A>(
                                                 &task_id,
-                                                batch_interval,
+                                                &batch_identifier,
+                                                &aggregation_param,
                                             )
-                                            .await?
+                                            .await?;
+                                        Ok::<_, datastore::Error>((batch_identifier, batch))
+                                    }
+                                }),
+                            ),
+                            try_join_all(
+                                Q::batch_identifiers_for_collection_identifier(
+                                    &task,
+                                    &collection_identifier
+                                )
+                                .map(|batch_identifier| {
+                                    let task_id = *task.id();
+                                    async move {
+                                        if let Some(batch_interval) =
+                                            Q::to_batch_interval(&batch_identifier)
                                         {
-                                            return Ok::<_, datastore::Error>(Some(
-                                                batch_identifier.clone(),
-                                            ));
+                                            if tx
+                                                .interval_has_unaggregated_reports(
+                                                    &task_id,
+                                                    batch_interval,
+                                                )
+                                                .await?
+                                            {
+                                                return Ok::<_, datastore::Error>(Some(
+                                                    batch_identifier.clone(),
+                                                ));
+                                            }
                                         }
+                                        Ok(None)
                                     }
-                                    Ok(None)
-                                }
-                            })
-                        ),
-                    )?;
+                                })
+                            ),
+                        )?;
 
                     let batches_with_reports: HashSet<_> =
                         batches_with_reports.into_iter().flatten().collect();
@@ -2271,6 +2634,34 @@ impl VdafOps {
                         ));
                     }
 
+                    // If this batch has already been collected with this aggregation parameter,
+                    // the new collection job may be able to reuse the existing results rather than
+                    // recomputing them. The batches were closed when the existing collection job
+                    // was created, so there is no need to update them.
+                    if let Some(finished_state) = reusable_collection_job_state(
+                        &task,
+                        &collection_identifier,
+                        aggregation_param.as_ref(),
+                        existing_collection_jobs,
+                    ) {
+                        debug!(
+                            collect_request = ?req,
+                            "Reusing results of finished collection job"
+                        );
+                        let collection_job = CollectionJob::<SEED_SIZE, Q, A>::new(
+                            *task.id(),
+                            collection_job_id,
+                            req.query().clone(),
+                            aggregation_param.as_ref().clone(),
+                            collection_identifier,
+                            CollectionJobState::Collectable,
+                        );
+                        tx.put_collection_job(&collection_job).await?;
+                        tx.update_collection_job(&collection_job.with_state(finished_state))
+                            .await?;
+                        return Ok(());
+                    }
+
                     // Prepare to update all batches to CLOSING/CLOSED, as well as determining the
                     // initial state of the collection job (which will be START, unless all batches
                     // went to CLOSED, in which case the collection job will start at COLLECTABLE).
@@ -2438,7 +2829,7 @@ impl VdafOps {
         A::AggregationParam: Send + Sync,
         A::AggregateShare: Send + Sync,
     {
-        let (collection_job, spanned_interval) = datastore
+        let (collection_job, collection) = datastore
             .run_tx_with_name("get_collection_job", |tx| {
                 let (task, vdaf, collection_job_id) =
                     (Arc::clone(&task), Arc::clone(&vdaf), *collection_job_id);
@@ -2452,6 +2843,18 @@ impl VdafOps {
                             )
                         })?;
 
+                    // The Collection message for a finished collection job is computed & stored
+                    // the first time the collection job is polled, and the stored message is
+                    // served as-is thereafter.
+                    if matches!(collection_job.state(), CollectionJobState::Finished { .. }) {
+                        if let Some(collection) = tx
+                            .get_collection::<Q>(task.id(), &collection_job_id)
+                            .await?
+                        {
+                            return Ok((collection_job, Some(collection)));
+                        }
+                    }
+
                     let (batches, _) = try_join!(
                         Q::get_batches_for_collection_identifier(
                             tx,
@@ -2475,82 +2878,46 @@ impl VdafOps {
                         }
                     }
 
-                    Ok((collection_job, spanned_interval))
+                    let collection = match collection_job.state() {
+                        CollectionJobState::Finished { .. } => {
+                            let collection = Self::collection_for_finished_collection_job(
+                                &task,
+                                &collection_job,
+                                spanned_interval,
+                            )
+                            .map_err(|err| datastore::Error::User(err.into()))?;
+                            tx.put_collection(task.id(), &collection_job_id, &collection)
+                                .await?;
+                            Some(collection)
+                        }
+                        _ => None,
+                    };
+
+                    Ok((collection_job, collection))
                 })
             })
             .await?;
 
-        match collection_job.state() {
-            CollectionJobState::Start | CollectionJobState::Collectable => {
+        match (collection_job.state(), collection) {
+            (CollectionJobState::Start | CollectionJobState::Collectable, _) => {
                 debug!(%collection_job_id, task_id = %task.id(), "collection job has not run yet");
                 Ok(None)
             }
 
-            CollectionJobState::Finished {
-                report_count,
-                encrypted_helper_aggregate_share,
-                leader_aggregate_share,
-            } => {
-                let spanned_interval = spanned_interval
-                    .ok_or_else(|| {
-                        datastore::Error::User(
-                            Error::Internal(format!(
-                                "collection job {collection_job_id} is finished but spans no time \
-                                 interval"
-                            ))
-                            .into(),
-                        )
-                    })?
-                    .align_to_time_precision(task.time_precision())?;
-
-                // §4.4.4.3: HPKE encrypt aggregate share to the collector. We store the leader
-                // aggregate share *unencrypted* in the datastore so that we can encrypt cached
-                // results to the collector HPKE config valid when the current collection job request
-                // was made, and not whatever was valid at the time the aggregate share was first
-                // computed.
-                // However we store the helper's *encrypted* share.
-
-                // TODO(#240): consider fetching freshly encrypted helper aggregate share if it has
-                // been long enough since the encrypted helper share was cached -- tricky thing is
-                // deciding what "long enough" is.
+            (CollectionJobState::Finished { .. }, Some(collection)) => {
                 debug!(
                     %collection_job_id,
                     task_id = %task.id(),
                     "Serving cached collection job response"
                 );
-                let encrypted_leader_aggregate_share = hpke::seal(
-                    // Unwrap safety: collector_hpke_config is only None for taskprov tasks. Taskprov
-                    // is not currently supported for Janus operating as the Leader, so this unwrap
-                    // is not reachable.
-                    task.collector_hpke_config().unwrap(),
-                    &HpkeApplicationInfo::new(
-                        &Label::AggregateShare,
-                        &Role::Leader,
-                        &Role::Collector,
-                    ),
-                    &leader_aggregate_share.get_encoded(),
-                    &AggregateShareAad::new(
-                        *collection_job.task_id(),
-                        BatchSelector::<Q>::new(collection_job.batch_identifier().clone()),
-                    )
-                    .get_encoded(),
-                )?;
-
-                Ok(Some(
-                    Collection::<Q>::new(
-                        PartialBatchSelector::new(
-                            Q::partial_batch_identifier(collection_job.batch_identifier()).clone(),
-                        ),
-                        *report_count,
-                        spanned_interval,
-                        encrypted_leader_aggregate_share,
-                        encrypted_helper_aggregate_share.clone(),
-                    )
-                    .get_encoded(),
-                ))
+                Ok(Some(collection.get_encoded()))
             }
 
-            CollectionJobState::Abandoned => {
+            (CollectionJobState::Finished { .. }, None) => Err(Error::Internal(format!(
+                "collection job {collection_job_id} is finished but has no collection"
+            ))),
+
+            (CollectionJobState::Abandoned, _) => {
                 // TODO(#248): decide how to respond for abandoned collection jobs.
                 warn!(
                     %collection_job_id,
@@ -2560,10 +2927,92 @@ impl VdafOps {
                 Ok(None)
             }
 
-            CollectionJobState::Deleted => Err(Error::DeletedCollectionJob(*collection_job_id)),
+            (CollectionJobState::Deleted, _) => {
+                Err(Error::DeletedCollectionJob(*collection_job_id))
+            }
         }
     }
 
+    /// Builds the Collection message for a finished collection job, given the interval spanned by
+    /// the reports in its batches.
+    fn collection_for_finished_collection_job<
+        const SEED_SIZE: usize,
+        Q: CollectableQueryType,
+        A: vdaf::Aggregator<SEED_SIZE, 16>,
+    >(
+        task: &Task,
+        collection_job: &CollectionJob<SEED_SIZE, Q, A>,
+        spanned_interval: Option<Interval>,
+    ) -> Result<Collection<Q>, Error> {
+        let (report_count, encrypted_helper_aggregate_share, leader_aggregate_share) =
+            match collection_job.state() {
+                CollectionJobState::Finished {
+                    report_count,
+                    encrypted_helper_aggregate_share,
+                    leader_aggregate_share,
+                    ..
+                } => (
+                    report_count,
+                    encrypted_helper_aggregate_share,
+                    leader_aggregate_share,
+                ),
+                state => {
+                    return Err(Error::Internal(format!(
+                        "collection job {} is in state {state}, not finished",
+                        collection_job.id()
+                    )))
+                }
+            };
+
+        let spanned_interval = spanned_interval
+            .ok_or_else(|| {
+                Error::Internal(format!(
+                    "collection job {} is finished but spans no time interval",
+                    collection_job.id()
+                ))
+            })?
+            .align_to_time_precision(task.time_precision())?;
+
+        // §4.4.4.3: HPKE encrypt aggregate share to the collector. We store the leader aggregate
+        // share *unencrypted* in the datastore so that we can encrypt it to the collector HPKE
+        // config valid when the collection job is first polled after finishing, and not whatever
+        // was valid at the time the aggregate share was first computed.
+        //
+        // However we store the helper's *encrypted* share, which the leader can't re-encrypt. If
+        // the collector HPKE config has since changed, the collector must create a new collection
+        // job, which will not reuse this collection job's results (see
+        // `reusable_collection_job_state`), so that the helper encrypts its aggregate share anew.
+        //
+        // Leader tasks always have a collector HPKE config: taskprov tasks take theirs from the
+        // peer aggregator. It is only absent for helper taskprov tasks.
+        let collector_hpke_config = task.collector_hpke_config().ok_or_else(|| {
+            Error::Internal(format!(
+                "task {} has no collector HPKE config",
+                collection_job.task_id()
+            ))
+        })?;
+        let encrypted_leader_aggregate_share = hpke::seal(
+            collector_hpke_config,
+            &HpkeApplicationInfo::new(&Label::AggregateShare, &Role::Leader, &Role::Collector),
+            &leader_aggregate_share.get_encoded(),
+            &AggregateShareAad::new(
+                *collection_job.task_id(),
+                BatchSelector::<Q>::new(collection_job.batch_identifier().clone()),
+            )
+            .get_encoded(),
+        )?;
+
+        Ok(Collection::<Q>::new(
+            PartialBatchSelector::new(
+                Q::partial_batch_identifier(collection_job.batch_identifier()).clone(),
+            ),
+            *report_count,
+            spanned_interval,
+            encrypted_leader_aggregate_share,
+            encrypted_helper_aggregate_share.clone(),
+        ))
+    }
+
     #[tracing::instrument(skip(self, datastore, task), fields(task_id = ?task.id()), err)]
     async fn handle_delete_collection_job<C: Clock>(
         &self,
@@ -2644,6 +3093,152 @@ impl VdafOps {
         Ok(())
     }
 
+    #[tracing::instrument(skip(self, datastore, task), fields(task_id = ?task.id()), err)]
+    async fn handle_get_batches<C: Clock>(
+        &self,
+        datastore: &Datastore<C>,
+        task: Arc<Task>,
+        pagination_token: Option<BatchId>,
+    ) -> Result<CollectableBatchList, Error> {
+        match task.query_type() {
+            task::QueryType::TimeInterval => Err(Error::BadRequest(
+                "batch discovery is only supported for fixed-size tasks".to_string(),
+            )),
+            task::QueryType::FixedSize {
+                batch_time_window_size,
+                ..
+            } => {
+                let batch_time_window_size = *batch_time_window_size;
+                vdaf_ops_dispatch!(self, (_, _, VdafType, VERIFY_KEY_LENGTH) => {
+                    Self::handle_get_batches_generic::<VERIFY_KEY_LENGTH, VdafType, _>(
+                        datastore,
+                        task,
+                        batch_time_window_size,
+                        pagination_token,
+                    )
+                    .await
+                })
+            }
+        }
+    }
+
+    async fn handle_get_batches_generic<
+        const SEED_SIZE: usize,
+        A: vdaf::Aggregator<SEED_SIZE, 16> + Send + Sync + 'static,
+        C: Clock,
+    >(
+        datastore: &Datastore<C>,
+        task: Arc<Task>,
+        batch_time_window_size: Option<Duration>,
+        pagination_token: Option<BatchId>,
+    ) -> Result<CollectableBatchList, Error>
+    where
+        A::AggregationParam: Send + Sync,
+    {
+        // Collectors can tolerate a slightly stale view of the task's batches, so this is served
+        // from a read replica, if one is configured.
+        let (batches, pagination_token) = datastore
+            .run_read_only_tx_with_name("get_batches", |tx| {
+                let task = Arc::clone(&task);
+                Box::pin(async move {
+                    // A batch is written once per aggregation parameter it is aggregated with; take
+                    // the interval spanned by the batch's reports under any aggregation parameter.
+                    let mut batch_intervals = BTreeMap::new();
+                    for batch in tx
+                        .get_fixed_size_batches_for_task::<SEED_SIZE, A>(
+                            task.id(),
+                            pagination_token.as_ref(),
+                            GET_BATCHES_PAGE_SIZE,
+                        )
+                        .await?
+                    {
+                        match batch_intervals.entry(*batch.batch_identifier()) {
+                            btree_map::Entry::Vacant(entry) => {
+                                entry.insert(*batch.client_timestamp_interval());
+                            }
+                            btree_map::Entry::Occupied(mut entry) => {
+                                let interval =
+                                    entry.get().merge(batch.client_timestamp_interval())?;
+                                entry.insert(interval);
+                            }
+                        }
+                    }
+
+                    // A full page may be followed by more batches. The next page picks up after
+                    // the last batch ID considered, whether or not that batch is listed.
+                    let pagination_token =
+                        if u64::try_from(batch_intervals.len())? >= GET_BATCHES_PAGE_SIZE {
+                            batch_intervals.keys().next_back().copied()
+                        } else {
+                            None
+                        };
+
+                    // Batches which are still outstanding have not yet been collected. Outstanding
+                    // batches are stored per time bucket, which can be recovered from the time of
+                    // any report in the batch.
+                    let time_bucket_starts = batch_intervals
+                        .values()
+                        .map(|interval| {
+                            batch_time_window_size
+                                .map(|batch_time_window_size| {
+                                    interval
+                                        .start()
+                                        .to_batch_interval_start(&batch_time_window_size)
+                                })
+                                .transpose()
+                        })
+                        .collect::<Result<HashSet<_>, _>>()?;
+                    let outstanding_batch_ids: HashSet<_> =
+                        try_join_all(time_bucket_starts.iter().map(|time_bucket_start| {
+                            tx.get_outstanding_batches(task.id(), time_bucket_start)
+                        }))
+                        .await?
+                        .into_iter()
+                        .flatten()
+                        .map(|outstanding_batch| *outstanding_batch.id())
+                        .collect();
+
+                    // Count reports the same way as when validating the batch size of a new
+                    // collection job, so that every batch listed can be collected.
+                    let batch_ids: Vec<_> = batch_intervals.keys().copied().collect();
+                    let report_counts = tx
+                        .count_client_reports_for_batch_ids(task.id(), &batch_ids)
+                        .await?;
+
+                    let batches = batch_intervals
+                        .into_iter()
+                        .map(|(batch_id, interval)| {
+                            (
+                                batch_id,
+                                report_counts.get(&batch_id).copied().unwrap_or_default(),
+                                interval,
+                                !outstanding_batch_ids.contains(&batch_id),
+                            )
+                        })
+                        .collect::<Vec<_>>();
+                    Ok((batches, pagination_token))
+                })
+            })
+            .await?;
+
+        let collectable_batches = batches
+            .into_iter()
+            .filter(|(_, report_count, _, _)| task.validate_batch_size(*report_count))
+            .map(|(batch_id, report_count, interval, collected)| {
+                Ok(CollectableBatch::new(
+                    batch_id,
+                    report_count,
+                    interval.align_to_time_precision(task.time_precision())?,
+                    collected,
+                ))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        Ok(CollectableBatchList::new(
+            collectable_batches,
+            pagination_token,
+        ))
+    }
+
     /// Implements the `/aggregate_share` endpoint for the helper, described in §4.4.4.3
     #[tracing::instrument(
         skip(self, datastore, clock, task, req_bytes),
@@ -2805,6 +3400,8 @@ impl VdafOps {
                             let (helper_aggregate_share, report_count, checksum) =
                                 compute_aggregate_share::<SEED_SIZE, Q, A>(
                                     &task,
+                                    vdaf.as_ref(),
+                                    &aggregation_param,
                                     &batch_aggregations,
                                 )
                                 .await
@@ -2880,6 +3477,49 @@ impl VdafOps {
     }
 }
 
+/// Determines whether a new collection job over the given batch & aggregation parameter may reuse
+/// the results of one of the given existing collection jobs, rather than computing its aggregate
+/// shares anew. If so, returns the finished state to give the new collection job.
+///
+/// Results may be reused from a finished collection job over the same batch with the same
+/// aggregation parameter: batch aggregations are no longer updated once they have been collected,
+/// so recomputing the aggregate shares would produce the same results, at the cost of reading every
+/// batch aggregation again & making another aggregate share request to the helper. The helper's
+/// aggregate share must also have been encrypted to the task's current collector HPKE config, as
+/// the leader can't re-encrypt it. This compares the whole HPKE config rather than its ID, since a
+/// rotated collector key may keep the same config ID. Otherwise, the new collection job is computed
+/// from scratch, and the helper encrypts its (cached) aggregate share to the current collector
+/// HPKE config.
+fn reusable_collection_job_state<
+    const SEED_SIZE: usize,
+    Q: CollectableQueryType,
+    A: vdaf::Aggregator<SEED_SIZE, 16>,
+>(
+    task: &Task,
+    batch_identifier: &Q::BatchIdentifier,
+    aggregation_param: &A::AggregationParam,
+    collection_jobs: Vec<CollectionJob<SEED_SIZE, Q, A>>,
+) -> Option<CollectionJobState<SEED_SIZE, A>>
+where
+    A::AggregationParam: PartialEq,
+{
+    let collector_hpke_config = task.collector_hpke_config()?;
+    collection_jobs
+        .into_iter()
+        .find_map(|collection_job| match collection_job.state() {
+            CollectionJobState::Finished {
+                helper_aggregate_share_hpke_config: Some(helper_aggregate_share_hpke_config),
+                ..
+            } if collection_job.batch_identifier() == batch_identifier
+                && collection_job.aggregation_parameter() == aggregation_param
+                && helper_aggregate_share_hpke_config == collector_hpke_config =>
+            {
+                Some(collection_job.state().clone())
+            }
+            _ => None,
+        })
+}
+
 fn empty_batch_aggregations<
     const SEED_SIZE: usize,
     Q: CollectableQueryType,
@@ -2940,20 +3580,28 @@ async fn send_request_to_helper<T: Encode>(
     content_type: &str,
     request: T,
     auth_token: &AuthenticationToken,
+    taskprov_task_config: Option<&[u8]>,
     http_request_duration_histogram: &Histogram<f64>,
 ) -> Result<Bytes, Error> {
     let domain = url.domain().unwrap_or_default().to_string();
     let request_body = request.get_encoded();
     let (auth_header, auth_value) = auth_token.request_authentication();
 
-    let start = Instant::now();
-    let response_result = http_client
+    let mut request_builder = http_client
         .request(method, url)
         .header(CONTENT_TYPE, content_type)
-        .header(auth_header, auth_value)
-        .body(request_body)
-        .send()
-        .await;
+        .header(auth_header, auth_value);
+    // Tasks provisioned via taskprov must carry their configuration in every request to the
+    // helper, so that the helper can opt in to or authorize the task.
+    if let Some(taskprov_task_config) = taskprov_task_config {
+        request_builder = request_builder.header(
+            TASKPROV_HEADER,
+            janus_core::taskprov::header_value(taskprov_task_config),
+        );
+    }
+
+    let start = Instant::now();
+    let response_result = request_builder.body(request_body).send().await;
     let response = match response_result {
         Ok(response) => response,
         Err(error) => {
@@ -3162,7 +3810,7 @@ mod tests {
         let report = create_report(&task, clock.now());
 
         aggregator
-            .handle_upload(task.id(), &report.get_encoded())
+            .handle_upload(task.id(), &report.get_encoded(), None)
             .await
             .unwrap();
 
@@ -3180,7 +3828,7 @@ mod tests {
 
         // Report uploads are idempotent
         aggregator
-            .handle_upload(task.id(), &report.get_encoded())
+            .handle_upload(task.id(), &report.get_encoded(), None)
             .await
             .unwrap();
 
@@ -3192,7 +3840,7 @@ mod tests {
             task.current_hpke_key(),
         );
         let error = aggregator
-            .handle_upload(task.id(), &mutated_report.get_encoded())
+            .handle_upload(task.id(), &mutated_report.get_encoded(), None)
             .await
             .unwrap_err();
         assert_matches!(error.as_ref(), Error::ReportRejected(task_id, report_id, timestamp) => {
@@ -3225,7 +3873,7 @@ mod tests {
             let aggregator = Arc::clone(&aggregator);
             let enc = r.get_encoded();
             let task_id = task.id();
-            async move { aggregator.handle_upload(task_id, &enc).await }
+            async move { aggregator.handle_upload(task_id, &enc, None).await }
         }))
         .await
         .unwrap();
@@ -3272,7 +3920,7 @@ mod tests {
             report.helper_encrypted_input_share().clone(),
         );
 
-        assert_matches!(aggregator.handle_upload(task.id(), &report.get_encoded()).await.unwrap_err().as_ref(), Error::OutdatedHpkeConfig(task_id, config_id) => {
+        assert_matches!(aggregator.handle_upload(task.id(), &report.get_encoded(), None).await.unwrap_err().as_ref(), Error::OutdatedHpkeConfig(task_id, config_id) => {
             assert_eq!(task.id(), task_id);
             assert_eq!(config_id, &unused_hpke_config_id);
         });
@@ -3287,7 +3935,7 @@ mod tests {
         let report = create_report(&task, clock.now().add(task.tolerable_clock_skew()).unwrap());
 
         aggregator
-            .handle_upload(task.id(), &report.get_encoded())
+            .handle_upload(task.id(), &report.get_encoded(), None)
             .await
             .unwrap();
 
@@ -3321,7 +3969,7 @@ mod tests {
         );
 
         let upload_error = aggregator
-            .handle_upload(task.id(), &report.get_encoded())
+            .handle_upload(task.id(), &report.get_encoded(), None)
             .await
             .unwrap_err();
 
@@ -3373,7 +4021,7 @@ mod tests {
             .unwrap();
 
         // Try to upload the report, verify that we get the expected error.
-        assert_matches!(aggregator.handle_upload(task.id(), &report.get_encoded()).await.unwrap_err().as_ref(), Error::ReportRejected(err_task_id, err_report_id, err_time) => {
+        assert_matches!(aggregator.handle_upload(task.id(), &report.get_encoded(), None).await.unwrap_err().as_ref(), Error::ReportRejected(err_task_id, err_report_id, err_time) => {
             assert_eq!(task.id(), err_task_id);
             assert_eq!(report.metadata().id(), err_report_id);
             assert_eq!(report.metadata().time(), err_time);
@@ -3434,7 +4082,7 @@ mod tests {
             ),
         ] {
             aggregator
-                .handle_upload(task.id(), &report.get_encoded())
+                .handle_upload(task.id(), &report.get_encoded(), None)
                 .await
                 .unwrap();
 
@@ -1,6 +1,6 @@
 //! Implements functionality for computing & validating aggregate shares.
 
-use super::Error;
+use super::{dp::add_noise_to_aggregate_share, Error};
 use janus_aggregator_core::{datastore::models::BatchAggregation, task::Task};
 use janus_core::report_id::ReportIdChecksumExt;
 use janus_messages::{query_type::QueryType, ReportIdChecksum};
@@ -10,13 +10,23 @@ use prio::vdaf::{self, Aggregatable};
 /// The assumption is that all aggregation jobs contributing to those batch aggregations have
 /// been driven to completion, and that the query count requirements have been validated for the
 /// included batches.
-#[tracing::instrument(skip(task, batch_aggregations), fields(task_id = ?task.id()), err)]
+///
+/// If the task has a differential privacy mechanism, noise is added to the aggregate share. The
+/// caller must persist the returned aggregate share rather than recomputing it, so that the noise
+/// cannot be averaged away over repeated requests.
+#[tracing::instrument(
+    skip(task, vdaf, aggregation_param, batch_aggregations),
+    fields(task_id = ?task.id()),
+    err
+)]
 pub(crate) async fn compute_aggregate_share<
     const SEED_SIZE: usize,
     Q: QueryType,
     A: vdaf::Aggregator<SEED_SIZE, 16>,
 >(
     task: &Task,
+    vdaf: &A,
+    aggregation_param: &A::AggregationParam,
     batch_aggregations: &[BatchAggregation<SEED_SIZE, Q, A>],
 ) -> Result<(A::AggregateShare, u64, ReportIdChecksum), Error> {
     // At the moment we construct an aggregate share (either handling AggregateShareReq in the
@@ -75,5 +85,8 @@ pub(crate) async fn compute_aggregate_share<
         return Err(Error::InvalidBatchSize(*task.id(), total_report_count));
     }
 
+    let total_aggregate_share =
+        add_noise_to_aggregate_share(task, vdaf, aggregation_param, total_aggregate_share)?;
+
     Ok((total_aggregate_share, total_report_count, total_checksum))
 }
@@ -1,6 +1,6 @@
 //! Implements portions of aggregation job continuation for the helper.
 
-use crate::aggregator::{accumulator::Accumulator, Error, VdafOps};
+use crate::aggregator::{accumulator::Accumulator, compute_pool::ComputePool, Error, VdafOps};
 use futures::future::try_join_all;
 use janus_aggregator_core::{
     datastore::{
@@ -20,52 +20,74 @@ use prio::{
     codec::{Encode, ParameterizedDecode},
     vdaf::{self, PrepareTransition},
 };
-use std::{io::Cursor, sync::Arc};
+use std::{collections::HashSet, io::Cursor, sync::Arc};
 use tokio::try_join;
 use tracing::{info, trace_span};
 
+/// A helper report aggregation which has been stepped to the next round of VDAF preparation, but
+/// which has not yet been written to the datastore.
+#[derive(Clone)]
+pub(super) struct SteppedReportAggregation<const SEED_SIZE: usize, A>
+where
+    A: vdaf::Aggregator<SEED_SIZE, 16>,
+{
+    report_aggregation: ReportAggregation<SEED_SIZE, A>,
+    /// Whether the leader sent a prepare step for this report aggregation in this round.
+    stepped: bool,
+    /// The output share recovered from this report aggregation, if preparation finished in this
+    /// round.
+    output_share: Option<A::OutputShare>,
+}
+
 impl VdafOps {
     /// Step the helper's aggregation job to the next round of VDAF preparation using the round `n`
     /// prepare state in `report_aggregations` with the round `n+1` broadcast prepare messages in
     /// `leader_aggregation_job`.
-    pub(super) async fn step_aggregation_job<const SEED_SIZE: usize, C, Q, A>(
-        tx: &Transaction<'_, C>,
-        task: Arc<Task>,
+    ///
+    /// This performs no datastore operations, so that it may be run outside of a transaction; the
+    /// results must be written to the datastore with [`Self::write_stepped_aggregation_job`]. VDAF
+    /// preparation is performed in parallel on `compute_pool`.
+    pub(super) async fn step_aggregation_job<const SEED_SIZE: usize, Q, A>(
+        compute_pool: &ComputePool,
+        task: &Task,
         vdaf: Arc<A>,
-        batch_aggregation_shard_count: u64,
-        helper_aggregation_job: AggregationJob<SEED_SIZE, Q, A>,
-        mut report_aggregations: Vec<ReportAggregation<SEED_SIZE, A>>,
-        leader_aggregation_job: Arc<AggregationJobContinueReq>,
-        request_hash: [u8; 32],
-        aggregate_step_failure_counter: Counter<u64>,
-    ) -> Result<AggregationJobResp, datastore::Error>
+        helper_aggregation_job: &AggregationJob<SEED_SIZE, Q, A>,
+        report_aggregations: Vec<ReportAggregation<SEED_SIZE, A>>,
+        leader_aggregation_job: &AggregationJobContinueReq,
+        aggregate_step_failure_counter: &Counter<u64>,
+    ) -> Result<Vec<SteppedReportAggregation<SEED_SIZE, A>>, Error>
     where
-        C: Clock,
         Q: AccumulableQueryType,
-        A: vdaf::Aggregator<SEED_SIZE, 16> + 'static + Send + Sync,
-        for<'a> A::PrepareState: Send + Sync + Encode + ParameterizedDecode<(&'a A, usize)>,
+        A: vdaf::Aggregator<SEED_SIZE, 16> + Send + Sync + 'static,
+        A::PrepareMessage: Send,
+        A::PrepareShare: Send,
+        A::OutputShare: Send,
+        for<'a> A::PrepareState: Send + Encode + ParameterizedDecode<(&'a A, usize)>,
     {
-        // Handle each transition in the request.
-        let mut report_aggregations_iter = report_aggregations.iter_mut();
-        let mut accumulator = Accumulator::<SEED_SIZE, Q, A>::new(
-            Arc::clone(&task),
-            batch_aggregation_shard_count,
-            helper_aggregation_job.aggregation_parameter().clone(),
-        );
+        let mut stepped_report_aggregations: Vec<_> = report_aggregations
+            .into_iter()
+            .map(|report_aggregation| SteppedReportAggregation {
+                report_aggregation,
+                stepped: false,
+                output_share: None,
+            })
+            .collect();
 
+        // Handle each transition in the request, gathering the preparation work to be done.
+        let mut preparations = Vec::new();
+        let mut report_aggregations_iter = stepped_report_aggregations.iter_mut().enumerate();
         for prep_step in leader_aggregation_job.prepare_steps() {
             // Match preparation step received from leader to stored report aggregation, and extract
             // the stored preparation step.
-            let report_aggregation = loop {
-                let report_agg = report_aggregations_iter.next().ok_or_else(|| {
-                    datastore::Error::User(
-                        Error::UnrecognizedMessage(
+            let (index, stepped_report_aggregation) = loop {
+                let (index, stepped_report_agg) =
+                    report_aggregations_iter
+                        .next()
+                        .ok_or(Error::UnrecognizedMessage(
                             Some(*task.id()),
                             "leader sent unexpected, duplicate, or out-of-order prepare steps",
-                        )
-                        .into(),
-                    )
-                })?;
+                        ))?;
+                let report_agg = &mut stepped_report_agg.report_aggregation;
                 if report_agg.report_id() != prep_step.report_id() {
                     // This report was omitted by the leader because of a prior failure. Note that
                     // the report was dropped (if it's not already in an error state) and continue.
@@ -79,40 +101,17 @@ impl VdafOps {
                     }
                     continue;
                 }
-                break report_agg;
+                break (index, stepped_report_agg);
             };
-
-            // Make sure this report isn't in an interval that has already started collection.
-            let conflicting_aggregate_share_jobs = tx
-                .get_aggregate_share_jobs_including_time::<SEED_SIZE, A>(
-                    &vdaf,
-                    task.id(),
-                    report_aggregation.time(),
-                )
-                .await?;
-
-            if !conflicting_aggregate_share_jobs.is_empty() {
-                *report_aggregation = report_aggregation
-                    .clone()
-                    .with_state(ReportAggregationState::Failed(
-                        ReportShareError::BatchCollected,
-                    ))
-                    .with_last_prep_step(Some(PrepareStep::new(
-                        *prep_step.report_id(),
-                        PrepareStepResult::Failed(ReportShareError::BatchCollected),
-                    )));
-                continue;
-            }
+            stepped_report_aggregation.stepped = true;
+            let report_aggregation = &stepped_report_aggregation.report_aggregation;
 
             let prep_state = match report_aggregation.state() {
                 ReportAggregationState::Waiting(prep_state, _) => prep_state,
                 _ => {
-                    return Err(datastore::Error::User(
-                        Error::UnrecognizedMessage(
-                            Some(*task.id()),
-                            "leader sent prepare step for non-WAITING report aggregation",
-                        )
-                        .into(),
+                    return Err(Error::UnrecognizedMessage(
+                        Some(*task.id()),
+                        "leader sent prepare step for non-WAITING report aggregation",
                     ));
                 }
             };
@@ -122,44 +121,63 @@ impl VdafOps {
                 PrepareStepResult::Continued(payload) => A::PrepareMessage::decode_with_param(
                     prep_state,
                     &mut Cursor::new(payload.as_ref()),
-                )?,
+                )
+                .map_err(|err| Error::Datastore(err.into()))?,
                 _ => {
-                    return Err(datastore::Error::User(
-                        Error::UnrecognizedMessage(
-                            Some(*task.id()),
-                            "leader sent non-Continued prepare step",
-                        )
-                        .into(),
+                    return Err(Error::UnrecognizedMessage(
+                        Some(*task.id()),
+                        "leader sent non-Continued prepare step",
                     ));
                 }
             };
 
-            // Compute the next transition.
-            let prepare_step_res = trace_span!("VDAF preparation")
-                .in_scope(|| vdaf.prepare_step(prep_state.clone(), prep_msg));
+            preparations.push((index, prep_state.clone(), prep_msg));
+        }
+
+        for (_, stepped_report_agg) in report_aggregations_iter {
+            // This report was omitted by the leader because of a prior failure. Note that the
+            // report was dropped (if it's not already in an error state) and continue.
+            let report_agg = &mut stepped_report_agg.report_aggregation;
+            if matches!(report_agg.state(), ReportAggregationState::Waiting(_, _)) {
+                *report_agg = report_agg
+                    .clone()
+                    .with_state(ReportAggregationState::Failed(
+                        ReportShareError::ReportDropped,
+                    ))
+                    .with_last_prep_step(None);
+            }
+        }
+
+        // Compute the next transitions.
+        let transitions = compute_pool
+            .map(preparations, move |(index, prep_state, prep_msg)| {
+                let prepare_step_res = trace_span!("VDAF preparation")
+                    .in_scope(|| vdaf.prepare_step(prep_state, prep_msg));
+                (index, prepare_step_res)
+            })
+            .await?;
+        for (index, prepare_step_res) in transitions {
+            let stepped_report_aggregation = &mut stepped_report_aggregations[index];
+            let report_aggregation = &mut stepped_report_aggregation.report_aggregation;
+            let report_id = *report_aggregation.report_id();
             match prepare_step_res {
                 Ok(PrepareTransition::Continue(prep_state, prep_share)) => {
                     *report_aggregation = report_aggregation
                         .clone()
                         .with_state(ReportAggregationState::Waiting(prep_state, None))
                         .with_last_prep_step(Some(PrepareStep::new(
-                            *prep_step.report_id(),
+                            report_id,
                             PrepareStepResult::Continued(prep_share.get_encoded()),
                         )));
                 }
 
                 Ok(PrepareTransition::Finish(output_share)) => {
-                    accumulator.update(
-                        helper_aggregation_job.partial_batch_identifier(),
-                        prep_step.report_id(),
-                        report_aggregation.time(),
-                        &output_share,
-                    )?;
+                    stepped_report_aggregation.output_share = Some(output_share);
                     *report_aggregation = report_aggregation
                         .clone()
                         .with_state(ReportAggregationState::Finished)
                         .with_last_prep_step(Some(PrepareStep::new(
-                            *prep_step.report_id(),
+                            report_id,
                             PrepareStepResult::Finished,
                         )));
                 }
@@ -168,7 +186,7 @@ impl VdafOps {
                     info!(
                         task_id = %task.id(),
                         job_id = %helper_aggregation_job.id(),
-                        report_id = %prep_step.report_id(),
+                        %report_id,
                         ?error, "Prepare step failed",
                     );
                     aggregate_step_failure_counter
@@ -179,24 +197,95 @@ impl VdafOps {
                             ReportShareError::VdafPrepError,
                         ))
                         .with_last_prep_step(Some(PrepareStep::new(
-                            *prep_step.report_id(),
+                            report_id,
                             PrepareStepResult::Failed(ReportShareError::VdafPrepError),
                         )))
                 }
             };
         }
 
-        for report_agg in report_aggregations_iter {
-            // This report was omitted by the leader because of a prior failure. Note that the
-            // report was dropped (if it's not already in an error state) and continue.
-            if matches!(report_agg.state(), ReportAggregationState::Waiting(_, _)) {
-                *report_agg = report_agg
-                    .clone()
-                    .with_state(ReportAggregationState::Failed(
-                        ReportShareError::ReportDropped,
-                    ))
-                    .with_last_prep_step(None);
+        Ok(stepped_report_aggregations)
+    }
+
+    /// Write the results of [`Self::step_aggregation_job`] to the datastore, accumulating any
+    /// recovered output shares. Reports which fall into a batch which has started collection since
+    /// they were stepped are failed with `BatchCollected`.
+    ///
+    /// The caller is responsible for checking that the aggregation job has not been concurrently
+    /// modified since the report aggregations were read.
+    pub(super) async fn write_stepped_aggregation_job<const SEED_SIZE: usize, C, Q, A>(
+        tx: &Transaction<'_, C>,
+        task: Arc<Task>,
+        vdaf: Arc<A>,
+        batch_aggregation_shard_count: u64,
+        helper_aggregation_job: AggregationJob<SEED_SIZE, Q, A>,
+        stepped_report_aggregations: Vec<SteppedReportAggregation<SEED_SIZE, A>>,
+        leader_aggregation_job: Arc<AggregationJobContinueReq>,
+        request_hash: [u8; 32],
+    ) -> Result<AggregationJobResp, datastore::Error>
+    where
+        C: Clock,
+        Q: AccumulableQueryType,
+        A: vdaf::Aggregator<SEED_SIZE, 16> + 'static + Send + Sync,
+        for<'a> A::PrepareState: Send + Sync + Encode + ParameterizedDecode<(&'a A, usize)>,
+    {
+        // Make sure each stepped report isn't in an interval that has already started collection.
+        let conflicting_aggregate_share_jobs = try_join_all(
+            stepped_report_aggregations
+                .iter()
+                .filter(|stepped_report_aggregation| stepped_report_aggregation.stepped)
+                .map(|stepped_report_aggregation| {
+                    let report_aggregation = &stepped_report_aggregation.report_aggregation;
+                    async {
+                        Ok::<_, datastore::Error>(
+                            (!tx.get_aggregate_share_jobs_including_time::<SEED_SIZE, A>(
+                                &vdaf,
+                                task.id(),
+                                report_aggregation.time(),
+                            )
+                            .await?
+                            .is_empty())
+                            .then_some(*report_aggregation.report_id()),
+                        )
+                    }
+                }),
+        )
+        .await?
+        .into_iter()
+        .flatten()
+        .collect::<HashSet<_>>();
+
+        let mut accumulator = Accumulator::<SEED_SIZE, Q, A>::new(
+            Arc::clone(&task),
+            batch_aggregation_shard_count,
+            helper_aggregation_job.aggregation_parameter().clone(),
+        );
+        let mut report_aggregations = Vec::with_capacity(stepped_report_aggregations.len());
+        for stepped_report_aggregation in stepped_report_aggregations {
+            let report_aggregation = stepped_report_aggregation.report_aggregation;
+            if conflicting_aggregate_share_jobs.contains(report_aggregation.report_id()) {
+                report_aggregations.push(
+                    report_aggregation
+                        .clone()
+                        .with_state(ReportAggregationState::Failed(
+                            ReportShareError::BatchCollected,
+                        ))
+                        .with_last_prep_step(Some(PrepareStep::new(
+                            *report_aggregation.report_id(),
+                            PrepareStepResult::Failed(ReportShareError::BatchCollected),
+                        ))),
+                );
+                continue;
+            }
+            if let Some(output_share) = &stepped_report_aggregation.output_share {
+                accumulator.update(
+                    helper_aggregation_job.partial_batch_identifier(),
+                    report_aggregation.report_id(),
+                    report_aggregation.time(),
+                    output_share,
+                )?;
             }
+            report_aggregations.push(report_aggregation);
         }
 
         // Write accumulated aggregation values back to the datastore; mark any reports that can't
@@ -397,6 +486,7 @@ mod tests {
     use prio::codec::Encode;
     use rand::random;
     use std::sync::Arc;
+    use tokio::join;
     use trillium::{Handler, Status};
 
     struct AggregationJobContinueTestCase {
@@ -576,6 +666,42 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn aggregation_job_continue_concurrent_requests() {
+        let test_case = setup_aggregation_job_continue_test().await;
+
+        // Send the same request twice concurrently, simulating the leader retrying a request
+        // before the first attempt completes. Whichever request is written second will find that
+        // the aggregation job was modified after it was read, and should send back the same
+        // response as the first.
+        let (first_continue_resp, second_continue_resp) = join!(
+            post_aggregation_job_and_decode(
+                &test_case.task,
+                &test_case.aggregation_job_id,
+                &test_case.first_continue_request,
+                &test_case.handler,
+            ),
+            post_aggregation_job_and_decode(
+                &test_case.task,
+                &test_case.aggregation_job_id,
+                &test_case.first_continue_request,
+                &test_case.handler,
+            ),
+        );
+        assert_eq!(
+            first_continue_resp,
+            AggregationJobResp::new(
+                test_case
+                    .first_continue_request
+                    .prepare_steps()
+                    .iter()
+                    .map(|step| PrepareStep::new(*step.report_id(), PrepareStepResult::Finished))
+                    .collect()
+            )
+        );
+        assert_eq!(first_continue_resp, second_continue_resp);
+    }
+
     #[tokio::test]
     #[allow(clippy::unit_arg)]
     async fn aggregation_job_continue_round_recovery_mutate_continue_request() {
@@ -160,7 +160,7 @@ impl<C: Clock + 'static> AggregationJobCreator<C> {
         debug!("Updating tasks");
         let tasks = self
             .datastore
-            .run_tx_with_name("aggregation_job_creator_get_tasks", |tx| {
+            .run_read_only_tx_with_name("aggregation_job_creator_get_tasks", |tx| {
                 Box::pin(async move { tx.get_tasks().await })
             })
             .await?;
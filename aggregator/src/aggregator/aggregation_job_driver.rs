@@ -1,11 +1,11 @@
 use crate::aggregator::{
     accumulator::Accumulator, aggregate_step_failure_counter,
-    aggregation_job_writer::AggregationJobWriter, http_handlers::AGGREGATION_JOB_ROUTE,
-    query_type::CollectableQueryType, send_request_to_helper,
+    aggregation_job_writer::AggregationJobWriter, compute_pool::ComputePool,
+    http_handlers::AGGREGATION_JOB_ROUTE, query_type::CollectableQueryType, send_request_to_helper,
 };
 use anyhow::{anyhow, Context as _, Result};
 use derivative::Derivative;
-use futures::future::{try_join_all, BoxFuture, FutureExt};
+use futures::future::BoxFuture;
 use janus_aggregator_core::{
     datastore::{
         self,
@@ -46,6 +46,7 @@ use tracing::{info, trace_span, warn};
 pub struct AggregationJobDriver {
     batch_aggregation_shard_count: u64,
     http_client: reqwest::Client,
+    compute_pool: ComputePool,
     #[derivative(Debug = "ignore")]
     aggregate_step_failure_counter: Counter<u64>,
     #[derivative(Debug = "ignore")]
@@ -61,6 +62,7 @@ impl AggregationJobDriver {
         http_client: reqwest::Client,
         meter: &Meter,
         batch_aggregation_shard_count: u64,
+        compute_pool: ComputePool,
     ) -> AggregationJobDriver {
         let aggregate_step_failure_counter = aggregate_step_failure_counter(meter);
 
@@ -89,6 +91,7 @@ impl AggregationJobDriver {
         AggregationJobDriver {
             batch_aggregation_shard_count,
             http_client,
+            compute_pool,
             aggregate_step_failure_counter,
             job_cancel_counter,
             job_retry_counter,
@@ -161,15 +164,16 @@ impl AggregationJobDriver {
                         lease.leased().task_id(),
                         lease.leased().aggregation_job_id(),
                     );
+                    // Read report aggregations, along with the client reports for report
+                    // aggregations in state START, in a single query.
                     let report_aggregations_future = tx
-                        .get_report_aggregations_for_aggregation_job(
+                        .get_report_aggregations_and_client_reports_for_aggregation_job(
                             vdaf.as_ref(),
-                            &Role::Leader,
                             lease.leased().task_id(),
                             lease.leased().aggregation_job_id(),
                         );
 
-                    let (aggregation_job, report_aggregations) =
+                    let (aggregation_job, report_aggregations_and_client_reports) =
                         try_join!(aggregation_job_future, report_aggregations_future)?;
                     let aggregation_job = aggregation_job.ok_or_else(|| {
                         datastore::Error::User(
@@ -182,41 +186,17 @@ impl AggregationJobDriver {
                         )
                     })?;
 
-                    // Read client reports, but only for report aggregations in state START.
-                    // TODO(#224): create "get_client_reports_for_aggregation_job" datastore
-                    // operation to avoid needing to join many futures?
-                    let client_reports: HashMap<_, _> =
-                        try_join_all(report_aggregations.iter().filter_map(|report_aggregation| {
-                            if matches!(report_aggregation.state(), &ReportAggregationState::Start)
-                            {
-                                Some(
-                                    tx.get_client_report(
-                                        vdaf.as_ref(),
-                                        lease.leased().task_id(),
-                                        report_aggregation.report_id(),
-                                    )
-                                    .map(|rslt| {
-                                        rslt.context(format!(
-                                            "couldn't get report {} for task {}",
-                                            *report_aggregation.report_id(),
-                                            lease.leased().task_id(),
-                                        ))
-                                        .map(|report| {
-                                            report.map(|report| {
-                                                (*report_aggregation.report_id(), report)
-                                            })
-                                        })
-                                        .map_err(|err| datastore::Error::User(err.into()))
-                                    }),
-                                )
-                            } else {
-                                None
-                            }
-                        }))
-                        .await?
-                        .into_iter()
-                        .flatten()
-                        .collect();
+                    let mut report_aggregations =
+                        Vec::with_capacity(report_aggregations_and_client_reports.len());
+                    let mut client_reports = HashMap::new();
+                    for (report_aggregation, client_report) in
+                        report_aggregations_and_client_reports
+                    {
+                        if let Some(client_report) = client_report {
+                            client_reports.insert(*report_aggregation.report_id(), client_report);
+                        }
+                        report_aggregations.push(report_aggregation);
+                    }
 
                     Ok((
                         Arc::new(task),
@@ -292,7 +272,7 @@ impl AggregationJobDriver {
         task: Arc<Task>,
         aggregation_job: AggregationJob<SEED_SIZE, Q, A>,
         report_aggregations: Vec<ReportAggregation<SEED_SIZE, A>>,
-        client_reports: HashMap<ReportId, LeaderStoredReport<SEED_SIZE, A>>,
+        mut client_reports: HashMap<ReportId, LeaderStoredReport<SEED_SIZE, A>>,
         verify_key: VerifyKey<SEED_SIZE>,
     ) -> Result<()>
     where
@@ -313,14 +293,13 @@ impl AggregationJobDriver {
             })
             .collect();
 
-        // Compute report shares to send to helper, and decrypt our input shares & initialize
-        // preparation state.
+        // Gather the reports to be prepared, failing any which can't be.
         let mut report_aggregations_to_write = Vec::new();
-        let mut report_shares = Vec::new();
-        let mut stepped_aggregations = Vec::new();
+        let mut preparations = Vec::new();
         for report_aggregation in report_aggregations {
             // Look up report.
-            let report = if let Some(report) = client_reports.get(report_aggregation.report_id()) {
+            let report = if let Some(report) = client_reports.remove(report_aggregation.report_id())
+            {
                 report
             } else {
                 info!(report_id = %report_aggregation.report_id(), "Attempted to aggregate missing report (most likely garbage collected)");
@@ -348,17 +327,38 @@ impl AggregationJobDriver {
                 continue;
             }
 
-            // Initialize the leader's preparation state from the input share.
-            let prepare_init_res = trace_span!("VDAF preparation").in_scope(|| {
-                vdaf.prepare_init(
-                    verify_key.as_bytes(),
-                    Role::Leader.index().unwrap(),
-                    aggregation_job.aggregation_parameter(),
-                    report.metadata().id().as_ref(),
-                    report.public_share(),
-                    report.leader_input_share(),
-                )
-            });
+            preparations.push((report_aggregation, report));
+        }
+
+        // Initialize the leader's preparation state from each input share, in parallel on the
+        // compute pool.
+        let preparations = self
+            .compute_pool
+            .map(preparations, {
+                let (vdaf, aggregation_param) = (
+                    Arc::clone(&vdaf),
+                    aggregation_job.aggregation_parameter().clone(),
+                );
+                move |(report_aggregation, report)| {
+                    let prepare_init_res = trace_span!("VDAF preparation").in_scope(|| {
+                        vdaf.prepare_init(
+                            verify_key.as_bytes(),
+                            Role::Leader.index().unwrap(),
+                            &aggregation_param,
+                            report.metadata().id().as_ref(),
+                            report.public_share(),
+                            report.leader_input_share(),
+                        )
+                    });
+                    (report_aggregation, report, prepare_init_res)
+                }
+            })
+            .await?;
+
+        // Compute report shares to send to helper.
+        let mut report_shares = Vec::new();
+        let mut stepped_aggregations = Vec::new();
+        for (report_aggregation, report, prepare_init_res) in preparations {
             let (prep_state, prep_share) = match prepare_init_res {
                 Ok(prep_state_and_share) => prep_state_and_share,
                 Err(error) => {
@@ -400,6 +400,7 @@ impl AggregationJobDriver {
             AggregationJobInitializeReq::<Q>::MEDIA_TYPE,
             req,
             task.primary_aggregator_auth_token(),
+            task.taskprov_task_config(),
             &self.http_request_duration_histogram,
         )
         .await?;
@@ -441,17 +442,16 @@ impl AggregationJobDriver {
         A::PrepareShare: Send + Sync,
         A::PrepareMessage: Send + Sync,
     {
-        // Visit the report aggregations, ignoring any that have already failed; compute our own
-        // next step & transitions to send to the helper.
+        // Visit the report aggregations, ignoring any that have already failed; gather the
+        // preparation steps to be computed.
         let mut report_aggregations_to_write = Vec::new();
-        let mut prepare_steps = Vec::new();
-        let mut stepped_aggregations = Vec::new();
+        let mut preparations = Vec::new();
         for report_aggregation in report_aggregations {
             if let ReportAggregationState::Waiting(prep_state, prep_msg) =
                 report_aggregation.state()
             {
-                let prep_msg = match prep_msg.as_ref() {
-                    Some(prep_msg) => prep_msg,
+                let (prep_state, prep_msg) = match prep_msg.as_ref() {
+                    Some(prep_msg) => (prep_state.clone(), prep_msg.clone()),
                     None => {
                         // This error indicates programmer/system error (i.e. it cannot possibly be
                         // the fault of our co-aggregator). We still record this failure against a
@@ -466,32 +466,48 @@ impl AggregationJobDriver {
                         continue;
                     }
                 };
+                preparations.push((report_aggregation, prep_state, prep_msg));
+            }
+        }
 
-                // Step our own state.
-                let prepare_step_res = trace_span!("VDAF preparation")
-                    .in_scope(|| vdaf.prepare_step(prep_state.clone(), prep_msg.clone()));
-                let leader_transition = match prepare_step_res {
-                    Ok(leader_transition) => leader_transition,
-                    Err(error) => {
-                        info!(report_id = %report_aggregation.report_id(), ?error, "Prepare step failed");
-                        self.aggregate_step_failure_counter
-                            .add(1, &[KeyValue::new("type", "prepare_step_failure")]);
-                        report_aggregations_to_write.push(report_aggregation.with_state(
-                            ReportAggregationState::Failed(ReportShareError::VdafPrepError),
-                        ));
-                        continue;
-                    }
-                };
+        // Step our own state, in parallel on the compute pool.
+        let preparations = self
+            .compute_pool
+            .map(preparations, {
+                let vdaf = Arc::clone(&vdaf);
+                move |(report_aggregation, prep_state, prep_msg)| {
+                    let prepare_step_res = trace_span!("VDAF preparation")
+                        .in_scope(|| vdaf.prepare_step(prep_state, prep_msg.clone()));
+                    (report_aggregation, prep_msg, prepare_step_res)
+                }
+            })
+            .await?;
 
-                prepare_steps.push(PrepareStep::new(
-                    *report_aggregation.report_id(),
-                    PrepareStepResult::Continued(prep_msg.get_encoded()),
-                ));
-                stepped_aggregations.push(SteppedAggregation {
-                    report_aggregation,
-                    leader_transition,
-                })
-            }
+        // Compute the transitions to send to the helper.
+        let mut prepare_steps = Vec::new();
+        let mut stepped_aggregations = Vec::new();
+        for (report_aggregation, prep_msg, prepare_step_res) in preparations {
+            let leader_transition = match prepare_step_res {
+                Ok(leader_transition) => leader_transition,
+                Err(error) => {
+                    info!(report_id = %report_aggregation.report_id(), ?error, "Prepare step failed");
+                    self.aggregate_step_failure_counter
+                        .add(1, &[KeyValue::new("type", "prepare_step_failure")]);
+                    report_aggregations_to_write.push(report_aggregation.with_state(
+                        ReportAggregationState::Failed(ReportShareError::VdafPrepError),
+                    ));
+                    continue;
+                }
+            };
+
+            prepare_steps.push(PrepareStep::new(
+                *report_aggregation.report_id(),
+                PrepareStepResult::Continued(prep_msg.get_encoded()),
+            ));
+            stepped_aggregations.push(SteppedAggregation {
+                report_aggregation,
+                leader_transition,
+            })
         }
 
         // Construct request, send it to the helper, and process the response.
@@ -507,6 +523,7 @@ impl AggregationJobDriver {
             AggregationJobContinueReq::MEDIA_TYPE,
             req,
             task.primary_aggregator_auth_token(),
+            task.taskprov_task_config(),
             &self.http_request_duration_histogram,
         )
         .await?;
@@ -867,7 +884,10 @@ struct SteppedAggregation<const SEED_SIZE: usize, A: vdaf::Aggregator<SEED_SIZE,
 #[cfg(test)]
 mod tests {
     use crate::{
-        aggregator::{aggregation_job_driver::AggregationJobDriver, DapProblemType, Error},
+        aggregator::{
+            aggregation_job_driver::AggregationJobDriver, compute_pool::ComputePool,
+            DapProblemType, Error,
+        },
         binary_utils::job_driver::JobDriver,
     };
     use assert_matches::assert_matches;
@@ -1083,6 +1103,7 @@ mod tests {
             reqwest::Client::new(),
             &noop_meter(),
             32,
+            ComputePool::new(&noop_meter(), None).unwrap(),
         ));
         let stopper = Stopper::new();
 
@@ -1400,6 +1421,7 @@ mod tests {
             reqwest::Client::builder().build().unwrap(),
             &noop_meter(),
             32,
+            ComputePool::new(&noop_meter(), None).unwrap(),
         );
         let error = aggregation_job_driver
             .step_aggregation_job(ds.clone(), Arc::new(lease.clone()))
@@ -1717,6 +1739,7 @@ mod tests {
             reqwest::Client::builder().build().unwrap(),
             &noop_meter(),
             32,
+            ComputePool::new(&noop_meter(), None).unwrap(),
         );
         let error = aggregation_job_driver
             .step_aggregation_job(ds.clone(), Arc::new(lease.clone()))
@@ -2004,6 +2027,7 @@ mod tests {
             reqwest::Client::builder().build().unwrap(),
             &noop_meter(),
             32,
+            ComputePool::new(&noop_meter(), None).unwrap(),
         );
         let error = aggregation_job_driver
             .step_aggregation_job(ds.clone(), Arc::new(lease.clone()))
@@ -2369,6 +2393,7 @@ mod tests {
             reqwest::Client::builder().build().unwrap(),
             &noop_meter(),
             32,
+            ComputePool::new(&noop_meter(), None).unwrap(),
         );
         let error = aggregation_job_driver
             .step_aggregation_job(ds.clone(), Arc::new(lease.clone()))
@@ -2614,6 +2639,7 @@ mod tests {
             reqwest::Client::builder().build().unwrap(),
             &noop_meter(),
             32,
+            ComputePool::new(&noop_meter(), None).unwrap(),
         );
         aggregation_job_driver
             .cancel_aggregation_job(Arc::clone(&ds), lease)
@@ -2822,6 +2848,7 @@ mod tests {
             reqwest::Client::new(),
             &noop_meter(),
             32,
+            ComputePool::new(&noop_meter(), None).unwrap(),
         ));
         let job_driver = Arc::new(
             JobDriver::new(
@@ -156,19 +156,28 @@ impl CollectionJobDriver {
                             )
                         })?;
 
+                    // If the collection job has already been finished, e.g. because this is a
+                    // retry of a step whose results were written but whose lease was not
+                    // released, there is no need to touch the batch aggregations again.
+                    if matches!(collection_job.state(), CollectionJobState::Finished { .. }) {
+                        return Ok((task, collection_job, Vec::new()));
+                    }
+
                     // Read batch aggregations, and mark them as read-for-collection to avoid
-                    // further aggregation.
-                    let batch_aggregations: Vec<_> =
-                        Q::get_batch_aggregations_for_collection_identifier(
-                            tx,
-                            &task,
-                            vdaf.as_ref(),
-                            collection_job.batch_identifier(),
-                            collection_job.aggregation_parameter(),
-                        )
-                        .await?
-                        .into_iter()
-                        .map(|ba| ba.with_state(BatchAggregationState::Collected))
+                    // further aggregation. Batch aggregations which were already collected (e.g.
+                    // by an earlier collection job over the same batch) need not be rewritten.
+                    let batch_aggregations = Q::get_batch_aggregations_for_collection_identifier(
+                        tx,
+                        &task,
+                        vdaf.as_ref(),
+                        collection_job.batch_identifier(),
+                        collection_job.aggregation_parameter(),
+                    )
+                    .await?;
+                    let uncollected_batch_aggregations: Vec<_> = batch_aggregations
+                        .iter()
+                        .filter(|ba| ba.state() != &BatchAggregationState::Collected)
+                        .map(|ba| ba.clone().with_state(BatchAggregationState::Collected))
                         .collect();
 
                     // To ensure that concurrent aggregations don't write into a
@@ -186,7 +195,7 @@ impl CollectionJobDriver {
 
                     try_join!(
                         try_join_all(
-                            batch_aggregations
+                            uncollected_batch_aggregations
                                 .iter()
                                 .map(|ba| tx.update_batch_aggregation(ba))
                         ),
@@ -209,9 +218,14 @@ impl CollectionJobDriver {
         }
 
         let (leader_aggregate_share, report_count, checksum) =
-            compute_aggregate_share::<SEED_SIZE, Q, A>(&task, &batch_aggregations)
-                .await
-                .map_err(|e| datastore::Error::User(e.into()))?;
+            compute_aggregate_share::<SEED_SIZE, Q, A>(
+                &task,
+                vdaf.as_ref(),
+                collection_job.aggregation_parameter(),
+                &batch_aggregations,
+            )
+            .await
+            .map_err(|e| datastore::Error::User(e.into()))?;
 
         // Send an aggregate share request to the helper.
         let req = AggregateShareReq::<Q>::new(
@@ -229,6 +243,7 @@ impl CollectionJobDriver {
             AggregateShareReq::<TimeInterval>::MEDIA_TYPE,
             req,
             task.primary_aggregator_auth_token(),
+            task.taskprov_task_config(),
             &self.metrics.http_request_duration_histogram,
         )
         .await?;
@@ -241,6 +256,7 @@ impl CollectionJobDriver {
                 encrypted_helper_aggregate_share: AggregateShare::get_decoded(&resp_bytes)?
                     .encrypted_aggregate_share()
                     .clone(),
+                helper_aggregate_share_hpke_config: task.collector_hpke_config().cloned(),
                 leader_aggregate_share,
             }),
         );
@@ -1,7 +1,7 @@
 use crate::aggregator::{
     http_handlers::{
         aggregator_handler,
-        test_util::{decode_response_body, take_problem_details},
+        test_util::{decode_response_body, take_problem_details, take_response_body},
     },
     Config,
 };
@@ -21,8 +21,12 @@ use janus_aggregator_core::{
 };
 use janus_core::{
     hpke::{
-        self, test_util::generate_test_hpke_config_and_private_key, HpkeApplicationInfo,
-        HpkeKeypair, Label,
+        self,
+        test_util::{
+            generate_test_hpke_config_and_private_key,
+            generate_test_hpke_config_and_private_key_with_id,
+        },
+        HpkeApplicationInfo, HpkeKeypair, Label,
     },
     task::{AuthenticationToken, VdafInstance},
     test_util::{
@@ -32,6 +36,7 @@ use janus_core::{
     time::{Clock, IntervalExt, MockClock},
 };
 use janus_messages::{
+    batch_discovery::{CollectableBatch, CollectableBatchList},
     query_type::{FixedSize, QueryType as QueryTypeTrait, TimeInterval},
     AggregateShareAad, AggregationJobRound, BatchId, BatchSelector, Collection, CollectionJobId,
     CollectionReq, Duration, FixedSizeQuery, Interval, Query, ReportIdChecksum, Role, Time,
@@ -43,7 +48,7 @@ use std::{collections::HashSet, sync::Arc};
 use trillium::{Handler, KnownHeaderName, Status};
 use trillium_testing::{
     assert_headers,
-    prelude::{post, put},
+    prelude::{get, post, put},
     TestConn,
 };
 
@@ -124,6 +129,25 @@ impl CollectionJobTestCase {
         )
         .await
     }
+
+    pub(super) async fn get_batches_with_auth_token(
+        &self,
+        auth_token: Option<&AuthenticationToken>,
+        pagination_token: Option<&str>,
+    ) -> TestConn {
+        let mut test_conn = get(match pagination_token {
+            Some(pagination_token) => format!(
+                "/tasks/{}/batches?pagination_token={pagination_token}",
+                self.task.id()
+            ),
+            None => format!("/tasks/{}/batches", self.task.id()),
+        });
+        if let Some(auth) = auth_token {
+            let (header, value) = auth.request_authentication();
+            test_conn = test_conn.with_request_header(header, value);
+        }
+        test_conn.run_async(&self.handler).await
+    }
 }
 
 pub(crate) async fn setup_collection_job_test_case(
@@ -333,6 +357,9 @@ async fn collection_job_success_fixed_size() {
                         &collection_job.with_state(CollectionJobState::Finished {
                             report_count: task.min_batch_size() + 1,
                             encrypted_helper_aggregate_share,
+                            helper_aggregate_share_hpke_config: task
+                                .collector_hpke_config()
+                                .cloned(),
                             leader_aggregate_share,
                         }),
                     )
@@ -425,6 +452,397 @@ async fn collection_job_success_fixed_size() {
     );
 }
 
+#[tokio::test]
+async fn collection_job_finished_reuse_time_interval() {
+    // This test finishes a collection job, then verifies that repeated polls of it are served the
+    // same collection, and that a new collection job over the same batch reuses its results.
+    let test_case = setup_collection_job_test_case(Role::Leader, QueryType::TimeInterval).await;
+
+    let vdaf = dummy_vdaf::Vdaf::new();
+    let batch_interval = Interval::new(
+        Time::from_seconds_since_epoch(0),
+        *test_case.task.time_precision(),
+    )
+    .unwrap();
+    let request = CollectionReq::new(
+        Query::new_time_interval(batch_interval),
+        AggregationParam::default().get_encoded(),
+    );
+
+    let collection_job_id: CollectionJobId = random();
+    let test_conn = test_case
+        .put_collection_job(&collection_job_id, &request)
+        .await;
+    assert_eq!(test_conn.status(), Some(Status::Created));
+
+    let finished_state = test_case
+        .datastore
+        .run_tx(|tx| {
+            let (task, vdaf) = (test_case.task.clone(), vdaf.clone());
+            Box::pin(async move {
+                let encrypted_helper_aggregate_share = hpke::seal(
+                    task.collector_hpke_config().unwrap(),
+                    &HpkeApplicationInfo::new(
+                        &Label::AggregateShare,
+                        &Role::Helper,
+                        &Role::Collector,
+                    ),
+                    &dummy_vdaf::AggregateShare(1).get_encoded(),
+                    &AggregateShareAad::new(
+                        *task.id(),
+                        BatchSelector::new_time_interval(batch_interval),
+                    )
+                    .get_encoded(),
+                )
+                .unwrap();
+                let finished_state = CollectionJobState::Finished {
+                    report_count: 12,
+                    encrypted_helper_aggregate_share,
+                    helper_aggregate_share_hpke_config: task.collector_hpke_config().cloned(),
+                    leader_aggregate_share: dummy_vdaf::AggregateShare(0),
+                };
+
+                let collection_job = tx
+                    .get_collection_job::<0, TimeInterval, dummy_vdaf::Vdaf>(
+                        &vdaf,
+                        task.id(),
+                        &collection_job_id,
+                    )
+                    .await
+                    .unwrap()
+                    .unwrap();
+                tx.update_collection_job(&collection_job.with_state(finished_state.clone()))
+                    .await
+                    .unwrap();
+
+                Ok(finished_state)
+            })
+        })
+        .await
+        .unwrap();
+
+    // Polling the finished collection job repeatedly returns the same collection each time, even
+    // though the leader's aggregate share is encrypted with a randomized scheme.
+    let mut test_conn = test_case.post_collection_job(&collection_job_id).await;
+    assert_eq!(test_conn.status(), Some(Status::Ok));
+    let first_body = take_response_body(&mut test_conn).await.into_owned();
+    let mut test_conn = test_case.post_collection_job(&collection_job_id).await;
+    assert_eq!(test_conn.status(), Some(Status::Ok));
+    assert_eq!(
+        take_response_body(&mut test_conn).await.into_owned(),
+        first_body
+    );
+
+    // A new collection job over the same batch, with the same aggregation parameter, is finished
+    // as soon as it is created, with the same results.
+    let reusing_collection_job_id: CollectionJobId = random();
+    let test_conn = test_case
+        .put_collection_job(&reusing_collection_job_id, &request)
+        .await;
+    assert_eq!(test_conn.status(), Some(Status::Created));
+
+    let reusing_collection_job = test_case
+        .datastore
+        .run_tx(|tx| {
+            let (task, vdaf) = (test_case.task.clone(), vdaf.clone());
+            Box::pin(async move {
+                Ok(tx
+                    .get_collection_job::<0, TimeInterval, dummy_vdaf::Vdaf>(
+                        &vdaf,
+                        task.id(),
+                        &reusing_collection_job_id,
+                    )
+                    .await
+                    .unwrap()
+                    .unwrap())
+            })
+        })
+        .await
+        .unwrap();
+    assert_eq!(reusing_collection_job.state(), &finished_state);
+
+    let mut test_conn = test_case
+        .post_collection_job(&reusing_collection_job_id)
+        .await;
+    assert_eq!(test_conn.status(), Some(Status::Ok));
+    let collection: Collection<TimeInterval> = decode_response_body(&mut test_conn).await;
+    let first_collection = Collection::<TimeInterval>::get_decoded(&first_body).unwrap();
+    assert_eq!(collection.report_count(), 12);
+    assert_eq!(
+        collection.helper_encrypted_aggregate_share(),
+        first_collection.helper_encrypted_aggregate_share()
+    );
+    let decrypted_leader_aggregate_share = hpke::open(
+        test_case.task.collector_hpke_config().unwrap(),
+        test_case.collector_hpke_keypair.private_key(),
+        &HpkeApplicationInfo::new(&Label::AggregateShare, &Role::Leader, &Role::Collector),
+        collection.leader_encrypted_aggregate_share(),
+        &AggregateShareAad::new(
+            *test_case.task.id(),
+            BatchSelector::new_time_interval(batch_interval),
+        )
+        .get_encoded(),
+    )
+    .unwrap();
+    assert_eq!(
+        dummy_vdaf::AggregateShare::get_decoded(&decrypted_leader_aggregate_share).unwrap(),
+        dummy_vdaf::AggregateShare(0)
+    );
+}
+
+#[tokio::test]
+async fn collection_job_finished_no_reuse_after_collector_key_rotation() {
+    // This test finishes a collection job with a helper aggregate share encrypted to a previous
+    // collector HPKE config, which has the same config ID as the task's current collector HPKE
+    // config, and verifies that a new collection job over the same batch doesn't reuse its results.
+    let test_case = setup_collection_job_test_case(Role::Leader, QueryType::TimeInterval).await;
+
+    let vdaf = dummy_vdaf::Vdaf::new();
+    let batch_interval = Interval::new(
+        Time::from_seconds_since_epoch(0),
+        *test_case.task.time_precision(),
+    )
+    .unwrap();
+    let request = CollectionReq::new(
+        Query::new_time_interval(batch_interval),
+        AggregationParam::default().get_encoded(),
+    );
+    let previous_collector_hpke_keypair = generate_test_hpke_config_and_private_key_with_id(
+        (*test_case.task.collector_hpke_config().unwrap().id()).into(),
+    );
+
+    let collection_job_id: CollectionJobId = random();
+    let test_conn = test_case
+        .put_collection_job(&collection_job_id, &request)
+        .await;
+    assert_eq!(test_conn.status(), Some(Status::Created));
+
+    test_case
+        .datastore
+        .run_tx(|tx| {
+            let (task, vdaf) = (test_case.task.clone(), vdaf.clone());
+            let previous_collector_hpke_config = previous_collector_hpke_keypair.config().clone();
+            Box::pin(async move {
+                let encrypted_helper_aggregate_share = hpke::seal(
+                    &previous_collector_hpke_config,
+                    &HpkeApplicationInfo::new(
+                        &Label::AggregateShare,
+                        &Role::Helper,
+                        &Role::Collector,
+                    ),
+                    &dummy_vdaf::AggregateShare(1).get_encoded(),
+                    &AggregateShareAad::new(
+                        *task.id(),
+                        BatchSelector::new_time_interval(batch_interval),
+                    )
+                    .get_encoded(),
+                )
+                .unwrap();
+
+                let collection_job = tx
+                    .get_collection_job::<0, TimeInterval, dummy_vdaf::Vdaf>(
+                        &vdaf,
+                        task.id(),
+                        &collection_job_id,
+                    )
+                    .await
+                    .unwrap()
+                    .unwrap();
+                tx.update_collection_job(&collection_job.with_state(
+                    CollectionJobState::Finished {
+                        report_count: 12,
+                        encrypted_helper_aggregate_share,
+                        helper_aggregate_share_hpke_config: Some(previous_collector_hpke_config),
+                        leader_aggregate_share: dummy_vdaf::AggregateShare(0),
+                    },
+                ))
+                .await
+                .unwrap();
+
+                Ok(())
+            })
+        })
+        .await
+        .unwrap();
+
+    // A new collection job over the same batch must be computed anew, so that the helper encrypts
+    // its aggregate share to the current collector HPKE config.
+    let new_collection_job_id: CollectionJobId = random();
+    let test_conn = test_case
+        .put_collection_job(&new_collection_job_id, &request)
+        .await;
+    assert_eq!(test_conn.status(), Some(Status::Created));
+
+    let new_collection_job = test_case
+        .datastore
+        .run_tx(|tx| {
+            let (task, vdaf) = (test_case.task.clone(), vdaf.clone());
+            Box::pin(async move {
+                Ok(tx
+                    .get_collection_job::<0, TimeInterval, dummy_vdaf::Vdaf>(
+                        &vdaf,
+                        task.id(),
+                        &new_collection_job_id,
+                    )
+                    .await
+                    .unwrap()
+                    .unwrap())
+            })
+        })
+        .await
+        .unwrap();
+    assert_eq!(new_collection_job.state(), &CollectionJobState::Collectable);
+
+    let test_conn = test_case.post_collection_job(&new_collection_job_id).await;
+    assert_eq!(test_conn.status(), Some(Status::Accepted));
+}
+
+#[tokio::test]
+async fn batches_get_fixed_size() {
+    let (test_case, batch_id_1, batch_id_2, spanned_interval) =
+        setup_fixed_size_current_batch_collection_job_test_case().await;
+    let aligned_interval = spanned_interval
+        .align_to_time_precision(test_case.task.time_precision())
+        .unwrap();
+
+    // Both batches are listed, and neither has been collected yet.
+    let mut test_conn = test_case
+        .get_batches_with_auth_token(Some(test_case.task.primary_collector_auth_token()), None)
+        .await;
+    assert_eq!(test_conn.status(), Some(Status::Ok));
+    assert_headers!(&test_conn, "content-type" => (CollectableBatchList::MEDIA_TYPE));
+    let batches: CollectableBatchList = decode_response_body(&mut test_conn).await;
+    let (first_batch_id, second_batch_id) = if batch_id_1 < batch_id_2 {
+        (batch_id_1, batch_id_2)
+    } else {
+        (batch_id_2, batch_id_1)
+    };
+    assert_eq!(
+        batches,
+        CollectableBatchList::new(
+            Vec::from([
+                CollectableBatch::new(
+                    first_batch_id,
+                    test_case.task.min_batch_size() + 1,
+                    aligned_interval,
+                    false,
+                ),
+                CollectableBatch::new(
+                    second_batch_id,
+                    test_case.task.min_batch_size() + 1,
+                    aligned_interval,
+                    false,
+                ),
+            ]),
+            None,
+        )
+    );
+
+    // Listing resumes after the pagination token.
+    let mut test_conn = test_case
+        .get_batches_with_auth_token(
+            Some(test_case.task.primary_collector_auth_token()),
+            Some(&first_batch_id.to_string()),
+        )
+        .await;
+    assert_eq!(test_conn.status(), Some(Status::Ok));
+    let batches: CollectableBatchList = decode_response_body(&mut test_conn).await;
+    assert_eq!(
+        batches,
+        CollectableBatchList::new(
+            Vec::from([CollectableBatch::new(
+                second_batch_id,
+                test_case.task.min_batch_size() + 1,
+                aligned_interval,
+                false,
+            )]),
+            None,
+        )
+    );
+
+    let test_conn = test_case
+        .get_batches_with_auth_token(
+            Some(test_case.task.primary_collector_auth_token()),
+            Some("not-a-batch-id"),
+        )
+        .await;
+    assert_eq!(test_conn.status(), Some(Status::BadRequest));
+
+    // Once a collection job for the current batch has been polled, its batch is reported as
+    // collected.
+    let collection_job_id: CollectionJobId = random();
+    let test_conn = test_case
+        .put_collection_job(
+            &collection_job_id,
+            &CollectionReq::new(
+                Query::new_fixed_size(FixedSizeQuery::CurrentBatch),
+                AggregationParam::default().get_encoded(),
+            ),
+        )
+        .await;
+    assert_eq!(test_conn.status(), Some(Status::Created));
+    let test_conn = test_case.post_collection_job(&collection_job_id).await;
+    assert_eq!(test_conn.status(), Some(Status::Accepted));
+    let collected_batch_id = test_case
+        .datastore
+        .run_tx(|tx| {
+            let task = test_case.task.clone();
+            Box::pin(async move {
+                Ok(*tx
+                    .get_collection_job::<0, FixedSize, dummy_vdaf::Vdaf>(
+                        &dummy_vdaf::Vdaf::new(),
+                        task.id(),
+                        &collection_job_id,
+                    )
+                    .await
+                    .unwrap()
+                    .unwrap()
+                    .batch_identifier())
+            })
+        })
+        .await
+        .unwrap();
+
+    let mut test_conn = test_case
+        .get_batches_with_auth_token(Some(test_case.task.primary_collector_auth_token()), None)
+        .await;
+    assert_eq!(test_conn.status(), Some(Status::Ok));
+    let batches: CollectableBatchList = decode_response_body(&mut test_conn).await;
+    assert_eq!(batches.batches().len(), 2);
+    for batch in batches.batches() {
+        assert_eq!(batch.collected(), batch.batch_id() == &collected_batch_id);
+    }
+
+    // Requests must be authenticated with the collector's token.
+    let mut test_conn = test_case.get_batches_with_auth_token(None, None).await;
+    assert_eq!(
+        take_problem_details(&mut test_conn).await,
+        json!({
+            "status": StatusCode::BAD_REQUEST.as_u16(),
+            "type": "urn:ietf:params:ppm:dap:error:unauthorizedRequest",
+            "title": "The request's authorization is not valid.",
+            "taskid": format!("{}", test_case.task.id()),
+        })
+    );
+    let mut test_conn = test_case
+        .get_batches_with_auth_token(Some(test_case.task.primary_aggregator_auth_token()), None)
+        .await;
+    assert_eq!(
+        take_problem_details(&mut test_conn).await["type"],
+        "urn:ietf:params:ppm:dap:error:unauthorizedRequest"
+    );
+}
+
+#[tokio::test]
+async fn batches_get_time_interval() {
+    let test_case = setup_collection_job_test_case(Role::Leader, QueryType::TimeInterval).await;
+
+    let test_conn = test_case
+        .get_batches_with_auth_token(Some(test_case.task.primary_collector_auth_token()), None)
+        .await;
+    assert_eq!(test_conn.status(), Some(Status::BadRequest));
+}
+
 #[tokio::test]
 async fn collection_job_put_idempotence_time_interval() {
     let test_case = setup_collection_job_test_case(Role::Leader, QueryType::TimeInterval).await;
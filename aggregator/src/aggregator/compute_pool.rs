@@ -0,0 +1,180 @@
+//! A dedicated thread pool for CPU-intensive work, such as VDAF preparation.
+
+use crate::aggregator::Error;
+use derivative::Derivative;
+use opentelemetry::metrics::{Meter, Unit, UpDownCounter};
+use rayon::{
+    iter::{IntoParallelIterator, ParallelIterator},
+    ThreadPool, ThreadPoolBuilder,
+};
+use std::sync::Arc;
+use tokio::sync::oneshot;
+use tracing::{error, Span};
+
+/// ComputePool runs CPU-intensive computations, such as VDAF preparation, on a fixed set of
+/// threads outside of the async runtime. Work submitted to the pool is spread across all of its
+/// threads, so the size of the pool bounds how much VDAF computation may happen concurrently across
+/// all requests and jobs handled by the process.
+#[derive(Clone, Derivative)]
+#[derivative(Debug)]
+pub struct ComputePool {
+    #[derivative(Debug = "ignore")]
+    pool: Arc<ThreadPool>,
+    #[derivative(Debug = "ignore")]
+    busy_threads: UpDownCounter<i64>,
+}
+
+impl ComputePool {
+    /// Creates a new compute pool with the given number of threads. If `threads` is `None`, the
+    /// pool will have one thread per available CPU.
+    pub fn new(meter: &Meter, threads: Option<usize>) -> Result<Self, Error> {
+        let pool = ThreadPoolBuilder::new()
+            // A thread count of zero tells rayon to pick the number of threads itself.
+            .num_threads(threads.unwrap_or(0))
+            .thread_name(|index| format!("janus-compute-{index}"))
+            .panic_handler(|_| error!("Panic while running task on compute pool"))
+            .build()
+            .map_err(|err| Error::Internal(format!("couldn't build compute pool: {err}")))?;
+
+        let thread_count = u64::try_from(pool.current_num_threads())?;
+        meter
+            .u64_observable_gauge("janus_compute_pool_threads")
+            .with_description("Number of threads available for VDAF preparation.")
+            .with_unit(Unit::new("{thread}"))
+            .with_callback(move |gauge| gauge.observe(thread_count, &[]))
+            .init();
+
+        let busy_threads = meter
+            .i64_up_down_counter("janus_compute_pool_busy_threads")
+            .with_description(
+                "Number of compute pool threads currently performing VDAF preparation.",
+            )
+            .with_unit(Unit::new("{thread}"))
+            .init();
+        busy_threads.add(0, &[]);
+
+        Ok(Self {
+            pool: Arc::new(pool),
+            busy_threads,
+        })
+    }
+
+    /// Returns the number of threads in this pool.
+    pub fn threads(&self) -> usize {
+        self.pool.current_num_threads()
+    }
+
+    /// Applies `f` to each of `items` in parallel on the compute pool, returning the results in
+    /// the same order as the corresponding items. `f` runs within the caller's current span.
+    pub async fn map<T, U, F>(&self, items: Vec<T>, f: F) -> Result<Vec<U>, Error>
+    where
+        T: Send + 'static,
+        U: Send + 'static,
+        F: Fn(T) -> U + Send + Sync + 'static,
+    {
+        let (sender, receiver) = oneshot::channel();
+        let busy_threads = self.busy_threads.clone();
+        let span = Span::current();
+        self.pool.spawn(move || {
+            let results = items
+                .into_par_iter()
+                .map(|item| {
+                    let _busy_thread = BusyThreadGuard::new(&busy_threads);
+                    span.in_scope(|| f(item))
+                })
+                .collect();
+            // The receiver is only dropped if the caller stopped waiting for the results, in which
+            // case there is nothing left to do with them.
+            let _ = sender.send(results);
+        });
+        receiver
+            .await
+            .map_err(|_| Error::Internal("compute pool task failed".to_string()))
+    }
+}
+
+/// Counts a compute pool thread as busy for as long as it is held. The count is decremented when
+/// the guard is dropped, including when the computation panics.
+struct BusyThreadGuard<'a>(&'a UpDownCounter<i64>);
+
+impl<'a> BusyThreadGuard<'a> {
+    fn new(busy_threads: &'a UpDownCounter<i64>) -> Self {
+        busy_threads.add(1, &[]);
+        Self(busy_threads)
+    }
+}
+
+impl Drop for BusyThreadGuard<'_> {
+    fn drop(&mut self) {
+        self.0.add(-1, &[]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ComputePool;
+    use opentelemetry::global::meter;
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        thread::sleep,
+        time::Duration,
+    };
+
+    #[tokio::test]
+    async fn map_preserves_order() {
+        let pool = ComputePool::new(&meter("janus_aggregator"), Some(4)).unwrap();
+        assert_eq!(pool.threads(), 4);
+
+        let results = pool
+            .map((0..1000u64).collect(), |item| item * 2)
+            .await
+            .unwrap();
+        assert_eq!(
+            results,
+            (0..1000u64).map(|item| item * 2).collect::<Vec<_>>()
+        );
+    }
+
+    #[tokio::test]
+    async fn map_bounds_parallelism() {
+        let pool = ComputePool::new(&meter("janus_aggregator"), Some(2)).unwrap();
+        let running = Arc::new(AtomicUsize::new(0));
+        let max_running = Arc::new(AtomicUsize::new(0));
+
+        let (first, second) = tokio::join!(
+            pool.map((0..8).collect(), {
+                let (running, max_running) = (Arc::clone(&running), Arc::clone(&max_running));
+                move |_: u32| {
+                    let now_running = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    max_running.fetch_max(now_running, Ordering::SeqCst);
+                    sleep(Duration::from_millis(10));
+                    running.fetch_sub(1, Ordering::SeqCst);
+                }
+            }),
+            pool.map((0..8).collect(), {
+                let (running, max_running) = (Arc::clone(&running), Arc::clone(&max_running));
+                move |_: u32| {
+                    let now_running = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    max_running.fetch_max(now_running, Ordering::SeqCst);
+                    sleep(Duration::from_millis(10));
+                    running.fetch_sub(1, Ordering::SeqCst);
+                }
+            }),
+        );
+        assert_eq!(first.unwrap().len(), 8);
+        assert_eq!(second.unwrap().len(), 8);
+        assert!(max_running.load(Ordering::SeqCst) <= 2);
+    }
+
+    #[tokio::test]
+    async fn map_panic() {
+        let pool = ComputePool::new(&meter("janus_aggregator"), Some(1)).unwrap();
+        pool.map(vec![()], |_| panic!("oops")).await.unwrap_err();
+
+        // The pool is still usable after a panic.
+        assert_eq!(pool.map(vec![1], |item| item + 1).await.unwrap(), vec![2]);
+    }
+}
@@ -0,0 +1,473 @@
+//! Implements differential privacy noise for aggregate shares.
+//!
+//! Noise is sampled exactly, using the algorithms of [CKS20][1], so that the privacy guarantee is
+//! not weakened by floating-point artifacts. The only floating-point computation is the
+//! calibration of the discrete Gaussian's variance, which is rounded up.
+//!
+//! [1]: https://arxiv.org/abs/2004.00010
+
+use super::Error;
+use janus_aggregator_core::task::{DpConfig, Task};
+use janus_core::task::VdafInstance;
+use prio::{
+    codec::{Encode, ParameterizedDecode},
+    field::{Field128, Field64, FieldElementWithInteger},
+    vdaf,
+};
+use rand::{thread_rng, Rng};
+
+/// The denominator used to represent the variance of the discrete Gaussian distribution as a
+/// rational number.
+const VARIANCE_DENOMINATOR: u128 = 1 << 20;
+
+/// Adds noise to `aggregate_share` according to the task's differential privacy mechanism, and
+/// returns the noised aggregate share. Noise is sampled independently for each element of the
+/// aggregate share.
+pub(crate) fn add_noise_to_aggregate_share<
+    const SEED_SIZE: usize,
+    A: vdaf::Aggregator<SEED_SIZE, 16>,
+>(
+    task: &Task,
+    vdaf: &A,
+    aggregation_param: &A::AggregationParam,
+    aggregate_share: A::AggregateShare,
+) -> Result<A::AggregateShare, Error> {
+    let distribution = match NoiseDistribution::new(task.dp_config()) {
+        Some(distribution) => distribution,
+        None => return Ok(aggregate_share),
+    };
+
+    let encoded_aggregate_share = aggregate_share.get_encoded();
+    let noised_aggregate_share = match task.vdaf() {
+        VdafInstance::Prio3Count => add_noise::<Field64>(&distribution, &encoded_aggregate_share)?,
+        VdafInstance::Prio3CountVec { .. }
+        | VdafInstance::Prio3Sum { .. }
+        | VdafInstance::Prio3SumVec { .. }
+        | VdafInstance::Prio3Histogram { .. } => {
+            add_noise::<Field128>(&distribution, &encoded_aggregate_share)?
+        }
+        vdaf_instance => {
+            return Err(Error::Internal(format!(
+                "differential privacy is not supported for VDAF {vdaf_instance:?}"
+            )))
+        }
+    };
+
+    Ok(A::AggregateShare::get_decoded_with_param(
+        &(vdaf, aggregation_param),
+        &noised_aggregate_share,
+    )?)
+}
+
+/// Decodes `encoded_aggregate_share` as a vector of field elements, adds noise to each element,
+/// and returns the encoded result.
+fn add_noise<F>(
+    distribution: &NoiseDistribution,
+    encoded_aggregate_share: &[u8],
+) -> Result<Vec<u8>, Error>
+where
+    F: FieldElementWithInteger,
+    F::Integer: TryFrom<u128>,
+{
+    let mut elements = F::byte_slice_into_vec(encoded_aggregate_share)
+        .map_err(|err| Error::Internal(format!("couldn't decode aggregate share: {err}")))?;
+
+    // The thread-local RNG is a CSPRNG, which is required for noise to provide privacy.
+    let mut rng = thread_rng();
+    for element in elements.iter_mut() {
+        let noise = distribution.sample(&mut rng)?;
+        let magnitude = F::Integer::try_from(noise.unsigned_abs())
+            .map(F::from)
+            .map_err(|_| Error::Internal("noise out of range for field".to_string()))?;
+        if noise < 0 {
+            *element -= magnitude;
+        } else {
+            *element += magnitude;
+        }
+    }
+
+    Ok(F::slice_into_byte_vec(&elements))
+}
+
+/// A noise distribution over the integers, with parameters derived from a [`DpConfig`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NoiseDistribution {
+    /// The discrete Laplace distribution with scale `numerator / denominator`.
+    DiscreteLaplace { numerator: u128, denominator: u128 },
+    /// The discrete Gaussian distribution with variance parameter `sigma^2` equal to
+    /// `numerator / denominator`.
+    DiscreteGaussian { numerator: u128, denominator: u128 },
+}
+
+impl NoiseDistribution {
+    /// Determines the noise distribution for a DP mechanism, or `None` if no noise is to be added.
+    /// The DP mechanism's parameters are assumed to have been validated.
+    fn new(dp_config: &DpConfig) -> Option<Self> {
+        match dp_config {
+            DpConfig::None => None,
+            DpConfig::DiscreteLaplace {
+                epsilon,
+                sensitivity,
+            } => {
+                // scale = sensitivity / epsilon
+                Some(Self::DiscreteLaplace {
+                    numerator: u128::from(sensitivity.numerator())
+                        * u128::from(epsilon.denominator()),
+                    denominator: u128::from(sensitivity.denominator())
+                        * u128::from(epsilon.numerator()),
+                })
+            }
+            DpConfig::DiscreteGaussian {
+                epsilon,
+                delta,
+                sensitivity,
+            } => {
+                // sigma = sensitivity * sqrt(2 * ln(1.25 / delta)) / epsilon
+                let variance = (sensitivity.as_f64() / epsilon.as_f64()).powi(2)
+                    * 2.0
+                    * (1.25 / delta.as_f64()).ln();
+                Some(Self::DiscreteGaussian {
+                    numerator: (variance * VARIANCE_DENOMINATOR as f64).ceil() as u128,
+                    denominator: VARIANCE_DENOMINATOR,
+                })
+            }
+        }
+    }
+
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Result<i128, Error> {
+        match self {
+            Self::DiscreteLaplace {
+                numerator,
+                denominator,
+            } => sample_discrete_laplace(rng, *numerator, *denominator),
+            Self::DiscreteGaussian {
+                numerator,
+                denominator,
+            } => sample_discrete_gaussian(rng, *numerator, *denominator),
+        }
+    }
+}
+
+/// Samples from a Bernoulli distribution with success probability `numerator / denominator`.
+fn sample_bernoulli<R: Rng + ?Sized>(rng: &mut R, numerator: u128, denominator: u128) -> bool {
+    rng.gen_range(0..denominator) < numerator
+}
+
+/// Samples from a Bernoulli distribution with success probability `exp(-numerator / denominator)`,
+/// per algorithm 1 of CKS20.
+fn sample_bernoulli_exp<R: Rng + ?Sized>(
+    rng: &mut R,
+    mut numerator: u128,
+    denominator: u128,
+) -> Result<bool, Error> {
+    // exp(-gamma) = exp(-1)^floor(gamma) * exp(-(gamma - floor(gamma)))
+    while numerator > denominator {
+        if !sample_bernoulli_exp_at_most_one(rng, 1, 1)? {
+            return Ok(false);
+        }
+        numerator -= denominator;
+    }
+    sample_bernoulli_exp_at_most_one(rng, numerator, denominator)
+}
+
+/// Samples from a Bernoulli distribution with success probability `exp(-numerator / denominator)`,
+/// where `numerator / denominator` is at most 1.
+fn sample_bernoulli_exp_at_most_one<R: Rng + ?Sized>(
+    rng: &mut R,
+    numerator: u128,
+    denominator: u128,
+) -> Result<bool, Error> {
+    let mut k = 1;
+    loop {
+        let denominator = denominator.checked_mul(k).ok_or_else(|| {
+            Error::Internal("overflow sampling Bernoulli distribution".to_string())
+        })?;
+        if !sample_bernoulli(rng, numerator, denominator) {
+            return Ok(k % 2 == 1);
+        }
+        k += 1;
+    }
+}
+
+/// Samples from the discrete Laplace distribution with scale `numerator / denominator`, per
+/// algorithm 2 of CKS20.
+fn sample_discrete_laplace<R: Rng + ?Sized>(
+    rng: &mut R,
+    numerator: u128,
+    denominator: u128,
+) -> Result<i128, Error> {
+    loop {
+        let u = rng.gen_range(0..numerator);
+        if !sample_bernoulli_exp(rng, u, numerator)? {
+            continue;
+        }
+        let mut v = 0;
+        while sample_bernoulli_exp(rng, 1, 1)? {
+            v += 1;
+        }
+        let magnitude = ((u + numerator * v) / denominator) as i128;
+        let negative = rng.gen::<bool>();
+        if negative && magnitude == 0 {
+            continue;
+        }
+        return Ok(if negative { -magnitude } else { magnitude });
+    }
+}
+
+/// Samples from the discrete Gaussian distribution with variance parameter
+/// `numerator / denominator`, per algorithm 3 of CKS20.
+fn sample_discrete_gaussian<R: Rng + ?Sized>(
+    rng: &mut R,
+    numerator: u128,
+    denominator: u128,
+) -> Result<i128, Error> {
+    let overflow = || Error::Internal("overflow sampling discrete Gaussian noise".to_string());
+
+    // t = floor(sigma) + 1
+    let mut t = (numerator as f64 / denominator as f64).sqrt() as u128;
+    while t * t * denominator > numerator {
+        t -= 1;
+    }
+    while (t + 1) * (t + 1) * denominator <= numerator {
+        t += 1;
+    }
+    t += 1;
+
+    loop {
+        let y = sample_discrete_laplace(rng, t, 1)?;
+        // gamma = (|y| - sigma^2 / t)^2 / (2 * sigma^2)
+        //       = (|y| * denominator * t - numerator)^2 / (2 * numerator * denominator * t^2)
+        let gamma_numerator = y
+            .unsigned_abs()
+            .checked_mul(denominator)
+            .and_then(|v| v.checked_mul(t))
+            .map(|v| v.abs_diff(numerator))
+            .and_then(|v| v.checked_mul(v))
+            .ok_or_else(overflow)?;
+        let gamma_denominator = numerator
+            .checked_mul(2 * denominator)
+            .and_then(|v| v.checked_mul(t * t))
+            .ok_or_else(overflow)?;
+        if sample_bernoulli_exp(rng, gamma_numerator, gamma_denominator)? {
+            return Ok(y);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        add_noise_to_aggregate_share, sample_bernoulli_exp, sample_bernoulli_exp_at_most_one,
+        sample_discrete_gaussian, sample_discrete_laplace, NoiseDistribution,
+    };
+    use crate::aggregator::Error;
+    use assert_matches::assert_matches;
+    use janus_aggregator_core::task::{test_util::TaskBuilder, DpConfig, QueryType};
+    use janus_core::task::VdafInstance;
+    use janus_messages::{taskprov::Rational, Role};
+    use prio::{
+        field::{Field128, FieldElementWithInteger},
+        vdaf::{prio3::Prio3, AggregateShare, OutputShare},
+    };
+    use rand::{rngs::StdRng, SeedableRng};
+    use std::collections::HashMap;
+
+    const SAMPLES: usize = 100_000;
+
+    /// Returns a tolerance of five standard errors for an empirical mean over `SAMPLES` samples
+    /// with the given variance, making spurious failures vanishingly unlikely.
+    fn tolerance(variance: f64) -> f64 {
+        5.0 * (variance / SAMPLES as f64).sqrt()
+    }
+
+    /// Computes the empirical mean, variance, and probability mass function of `samples`.
+    fn statistics(samples: &[i128]) -> (f64, f64, HashMap<i128, f64>) {
+        let n = samples.len() as f64;
+        let mean = samples.iter().map(|x| *x as f64).sum::<f64>() / n;
+        let variance = samples
+            .iter()
+            .map(|x| (*x as f64 - mean).powi(2))
+            .sum::<f64>()
+            / n;
+        let mut pmf = HashMap::new();
+        for sample in samples {
+            *pmf.entry(*sample).or_default() += 1.0 / n;
+        }
+        (mean, variance, pmf)
+    }
+
+    #[test]
+    fn bernoulli_exp() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for (numerator, denominator) in [(0, 1), (1, 2), (1, 1), (5, 2)] {
+            let successes = (0..SAMPLES)
+                .filter(|_| sample_bernoulli_exp(&mut rng, numerator, denominator).unwrap())
+                .count();
+            let expected = (-(numerator as f64) / denominator as f64).exp();
+            let actual = successes as f64 / SAMPLES as f64;
+            assert!(
+                (actual - expected).abs() <= tolerance(expected * (1.0 - expected)),
+                "exp(-{numerator}/{denominator}): expected {expected}, got {actual}"
+            );
+        }
+    }
+
+    #[test]
+    fn bernoulli_exp_overflow() {
+        // Every trial succeeds while the numerator exceeds the growing denominator, until the
+        // denominator overflows.
+        let mut rng = StdRng::seed_from_u64(0);
+        assert_matches!(
+            sample_bernoulli_exp_at_most_one(&mut rng, u128::MAX, u128::MAX / 2),
+            Err(Error::Internal(_))
+        );
+    }
+
+    #[test]
+    fn discrete_laplace_distribution() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for (numerator, denominator) in [(1, 1), (5, 2), (10, 1)] {
+            let samples: Vec<_> = (0..SAMPLES)
+                .map(|_| sample_discrete_laplace(&mut rng, numerator, denominator).unwrap())
+                .collect();
+            let (mean, variance, pmf) = statistics(&samples);
+
+            // Pr[X = x] = (1 - q) / (1 + q) * q^|x|, where q = exp(-1 / scale).
+            let scale = numerator as f64 / denominator as f64;
+            let q = (-1.0 / scale).exp();
+            let expected_variance = 2.0 * q / (1.0 - q).powi(2);
+            assert!(
+                mean.abs() < tolerance(expected_variance),
+                "scale {scale}: mean {mean}"
+            );
+            assert!(
+                (variance / expected_variance - 1.0).abs() < 0.03,
+                "scale {scale}: expected variance {expected_variance}, got {variance}"
+            );
+            for x in -2i32..=2 {
+                let expected = (1.0 - q) / (1.0 + q) * q.powi(x.abs());
+                let actual = pmf.get(&i128::from(x)).copied().unwrap_or_default();
+                assert!(
+                    (actual - expected).abs() <= tolerance(expected * (1.0 - expected)),
+                    "scale {scale}: Pr[X = {x}] expected {expected}, got {actual}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn discrete_gaussian_distribution() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for (numerator, denominator) in [(1, 1), (9, 4), (25, 1)] {
+            let samples: Vec<_> = (0..SAMPLES)
+                .map(|_| sample_discrete_gaussian(&mut rng, numerator, denominator).unwrap())
+                .collect();
+            let (mean, variance, pmf) = statistics(&samples);
+
+            // Pr[X = x] is proportional to exp(-x^2 / (2 * sigma^2)).
+            let sigma_squared = numerator as f64 / denominator as f64;
+            let density = |x: i128| (-(x * x) as f64 / (2.0 * sigma_squared)).exp();
+            let normalizer: f64 = (-1000..=1000).map(density).sum();
+            let expected_variance = (-1000..=1000)
+                .map(|x: i128| (x * x) as f64 * density(x))
+                .sum::<f64>()
+                / normalizer;
+            assert!(
+                mean.abs() < tolerance(expected_variance),
+                "sigma^2 {sigma_squared}: mean {mean}"
+            );
+            assert!(
+                (variance / expected_variance - 1.0).abs() < 0.03,
+                "sigma^2 {sigma_squared}: expected variance {expected_variance}, got {variance}"
+            );
+            for x in -2..=2 {
+                let expected = density(x) / normalizer;
+                let actual = pmf.get(&x).copied().unwrap_or_default();
+                assert!(
+                    (actual - expected).abs() <= tolerance(expected * (1.0 - expected)),
+                    "sigma^2 {sigma_squared}: Pr[X = {x}] expected {expected}, got {actual}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn gaussian_calibration() {
+        // sigma^2 = (1 / 0.5)^2 * 2 * ln(1.25 / 0.01) ~= 38.6
+        let distribution = NoiseDistribution::new(&DpConfig::DiscreteGaussian {
+            epsilon: Rational::new(1, 2).unwrap(),
+            delta: Rational::new(1, 100).unwrap(),
+            sensitivity: Rational::new(1, 1).unwrap(),
+        })
+        .unwrap();
+        match distribution {
+            NoiseDistribution::DiscreteGaussian {
+                numerator,
+                denominator,
+            } => {
+                let variance = numerator as f64 / denominator as f64;
+                let expected = 8.0 * 125f64.ln();
+                assert!(variance >= expected && variance - expected < 1e-5);
+            }
+            _ => panic!("unexpected distribution {distribution:?}"),
+        }
+
+        assert_eq!(
+            NoiseDistribution::new(&DpConfig::DiscreteLaplace {
+                epsilon: Rational::new(1, 2).unwrap(),
+                sensitivity: Rational::new(3, 1).unwrap(),
+            }),
+            Some(NoiseDistribution::DiscreteLaplace {
+                numerator: 6,
+                denominator: 1
+            })
+        );
+        assert_eq!(NoiseDistribution::new(&DpConfig::None), None);
+    }
+
+    #[test]
+    fn noised_aggregate_share() {
+        let vdaf = Prio3::new_histogram(2, 4).unwrap();
+        let aggregate_share = AggregateShare::from(OutputShare::from(Vec::from([
+            Field128::from(10),
+            Field128::from(20),
+            Field128::from(30),
+            Field128::from(40),
+        ])));
+
+        let task = TaskBuilder::new(
+            QueryType::TimeInterval,
+            VdafInstance::Prio3Histogram { length: 4 },
+            Role::Leader,
+        )
+        .build();
+        assert_eq!(
+            add_noise_to_aggregate_share(&task, &vdaf, &(), aggregate_share.clone()).unwrap(),
+            aggregate_share
+        );
+
+        let task = TaskBuilder::from(task)
+            .with_dp_config(DpConfig::DiscreteLaplace {
+                epsilon: Rational::new(1, 1).unwrap(),
+                sensitivity: Rational::new(2, 1).unwrap(),
+            })
+            .build();
+        let noised_aggregate_share =
+            add_noise_to_aggregate_share(&task, &vdaf, &(), aggregate_share.clone()).unwrap();
+        assert_eq!(noised_aggregate_share.as_ref().len(), 4);
+        for (noised, original) in noised_aggregate_share
+            .as_ref()
+            .iter()
+            .zip(aggregate_share.as_ref())
+        {
+            // Noise may be negative, in which case the difference wraps around the modulus.
+            let difference = u128::from(*noised - *original);
+            let difference = if difference > Field128::modulus() / 2 {
+                Field128::modulus() - difference
+            } else {
+                difference
+            };
+            assert!(difference < 100, "noise {difference} implausibly large");
+        }
+    }
+}
@@ -1,5 +1,5 @@
 use http_api_problem::HttpApiProblem;
-use janus_aggregator_core::{datastore, task};
+use janus_aggregator_core::{datastore, task, taskprov::PolicyViolation};
 use janus_messages::{
     problem_type::DapProblemType, AggregationJobId, AggregationJobRound, CollectionJobId,
     HpkeConfigId, Interval, ReportId, ReportIdChecksum, Role, TaskId, Time,
@@ -8,6 +8,7 @@ use prio::vdaf::VdafError;
 use std::{
     fmt::{self, Display, Formatter},
     num::TryFromIntError,
+    time::Duration as StdDuration,
 };
 
 /// Errors returned by functions and methods in this module
@@ -128,6 +129,13 @@ pub enum Error {
     /// Corresponds to taskprov invalidType (§2)
     #[error("aggregator has opted out of the indicated task: {1}")]
     InvalidTask(TaskId, OptOutReason),
+    /// A request body exceeded the maximum size configured for its endpoint.
+    #[error("task {0}: request body exceeds maximum size of {1} bytes")]
+    RequestTooLarge(TaskId, usize),
+    /// A request was rejected because the client exceeded its configured rate limit. The client
+    /// may retry after the given duration.
+    #[error("task {0}: rate limit exceeded, retry after {1:?}")]
+    TooManyRequests(TaskId, StdDuration),
 }
 
 /// Errors that cause the aggregator to opt-out of a taskprov task.
@@ -141,6 +149,9 @@ pub enum OptOutReason {
     TaskParameters(#[from] task::Error),
     #[error("URL parse error: {0}")]
     Url(#[from] url::ParseError),
+    /// The task is not permitted by the peer aggregator's taskprov policy.
+    #[error("task violates policy: {0}")]
+    PolicyViolation(#[from] PolicyViolation),
     /// Catch-all error for generally invalid parameters.
     #[error("invalid parameter: {0}")]
     InvalidParameter(String),
@@ -182,6 +193,8 @@ impl Error {
             Error::ForbiddenMutation { .. } => "forbidden_mutation",
             Error::BadRequest(_) => "bad_request",
             Error::InvalidTask(_, _) => "invalid_task",
+            Error::RequestTooLarge(_, _) => "request_too_large",
+            Error::TooManyRequests(_, _) => "too_many_requests",
         }
     }
 }
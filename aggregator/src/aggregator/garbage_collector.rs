@@ -1,10 +1,24 @@
+use crate::config::GarbageCollectorConfig;
 use anyhow::{Context, Result};
 use futures::future::join_all;
-use janus_aggregator_core::{datastore::Datastore, task::Task};
+use janus_aggregator_core::{
+    datastore::{Datastore, DeletedRows},
+    task::Task,
+};
 use janus_core::time::Clock;
-use std::sync::Arc;
+use janus_messages::{Duration, TaskId};
+use opentelemetry::{
+    metrics::{Counter, Histogram, Meter, Unit},
+    KeyValue,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    num::NonZeroUsize,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
 use tokio::try_join;
-use tracing::error;
+use tracing::{error, info};
 
 pub struct GarbageCollector<C: Clock> {
     // Dependencies.
@@ -14,47 +28,264 @@ pub struct GarbageCollector<C: Clock> {
     report_limit: u64,
     aggregation_limit: u64,
     collection_limit: u64,
+    max_tasks_per_run: Option<NonZeroUsize>,
+    task_deletion: Option<TaskDeletionConfig>,
+
+    // State.
+    /// The ID of the last task handled by the previous run, if that run was cut short by
+    /// `max_tasks_per_run`. The next run resumes with the task following it.
+    task_cursor: Mutex<Option<TaskId>>,
+    /// The age of the oldest expired client report remaining after the last GC of each task, in
+    /// seconds. Tasks with no expired client reports remaining are absent.
+    expired_client_reports_backlog: Arc<Mutex<HashMap<TaskId, u64>>>,
+    /// The IDs of expired tasks already counted as deleted in dry-run mode, so that each such task
+    /// is counted only once, rather than once per run.
+    dry_run_deleted_task_ids: Mutex<HashSet<TaskId>>,
+
+    // Metrics.
+    deleted_tasks_counter: Counter<u64>,
+    deleted_rows_counter: Counter<u64>,
+    task_failures_counter: Counter<u64>,
+    dropped_report_partitions_counter: Counter<u64>,
+    run_duration_histogram: Histogram<f64>,
+    task_duration_histogram: Histogram<f64>,
+}
+
+/// Configuration for deletion of entire tasks once they have been expired for some time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TaskDeletionConfig {
+    /// How long after a task's expiration to keep it, and all of its data, before deleting it.
+    pub retention: Duration,
+
+    /// The limit to the number of rows deleted from each table for a single expired task by a
+    /// single run of the garbage collector. Tasks are deleted once all of their data has been
+    /// deleted.
+    pub limit: u64,
+
+    /// If set, expired tasks that would be deleted are only logged and counted.
+    pub dry_run: bool,
 }
 
 impl<C: Clock> GarbageCollector<C> {
     pub fn new(
         datastore: Arc<Datastore<C>>,
+        meter: &Meter,
         report_limit: u64,
         aggregation_limit: u64,
         collection_limit: u64,
+        task_deletion: Option<TaskDeletionConfig>,
     ) -> Self {
+        let deleted_tasks_counter = meter
+            .u64_counter("janus_gc_deleted_tasks")
+            .with_description(
+                "Count of expired tasks deleted by the garbage collector, or that would have been \
+                 deleted in dry-run mode.",
+            )
+            .with_unit(Unit::new("{task}"))
+            .init();
+        deleted_tasks_counter.add(0, &[KeyValue::new("dry_run", false)]);
+
+        let deleted_rows_counter = meter
+            .u64_counter("janus_gc_deleted_rows")
+            .with_description("Count of rows deleted by the garbage collector, by table and task.")
+            .with_unit(Unit::new("{row}"))
+            .init();
+
+        let task_failures_counter = meter
+            .u64_counter("janus_gc_task_failures")
+            .with_description("Count of failures to garbage collect a single task.")
+            .with_unit(Unit::new("{task}"))
+            .init();
+        task_failures_counter.add(0, &[]);
+
+        let dropped_report_partitions_counter = meter
+            .u64_counter("janus_gc_dropped_report_partitions")
+            .with_description(
+                "Count of expired partitions of the client_reports & report_aggregations tables \
+                 dropped by the garbage collector.",
+            )
+            .with_unit(Unit::new("{partition}"))
+            .init();
+        dropped_report_partitions_counter.add(0, &[]);
+
+        let run_duration_histogram = meter
+            .f64_histogram("janus_gc_run_duration")
+            .with_description("Time spent on a single run of the garbage collector.")
+            .with_unit(Unit::new("s"))
+            .init();
+        let task_duration_histogram = meter
+            .f64_histogram("janus_gc_task_duration")
+            .with_description("Time spent garbage collecting a single task.")
+            .with_unit(Unit::new("s"))
+            .init();
+
+        let expired_client_reports_backlog = Arc::new(Mutex::new(HashMap::<TaskId, u64>::new()));
+        meter
+            .u64_observable_gauge("janus_gc_expired_client_reports_backlog")
+            .with_description(
+                "Age of the oldest expired client report not yet deleted by the garbage \
+                 collector, beyond the task's report expiry age. A steadily growing value \
+                 indicates that garbage collection is falling behind.",
+            )
+            .with_unit(Unit::new("s"))
+            .with_callback({
+                let expired_client_reports_backlog = Arc::clone(&expired_client_reports_backlog);
+                move |gauge| {
+                    for (task_id, backlog) in expired_client_reports_backlog.lock().unwrap().iter()
+                    {
+                        gauge.observe(*backlog, &[KeyValue::new("task_id", task_id.to_string())]);
+                    }
+                }
+            })
+            .init();
+
         Self {
             datastore,
             report_limit,
             aggregation_limit,
             collection_limit,
+            max_tasks_per_run: None,
+            task_deletion,
+            task_cursor: Mutex::new(None),
+            expired_client_reports_backlog,
+            dry_run_deleted_task_ids: Mutex::new(HashSet::new()),
+            deleted_tasks_counter,
+            deleted_rows_counter,
+            task_failures_counter,
+            dropped_report_partitions_counter,
+            run_duration_histogram,
+            task_duration_histogram,
+        }
+    }
+
+    /// Constructs a garbage collector from its configuration in a binary's configuration file.
+    pub fn from_config(
+        datastore: Arc<Datastore<C>>,
+        meter: &Meter,
+        config: &GarbageCollectorConfig,
+    ) -> Self {
+        Self::new(
+            datastore,
+            meter,
+            config.report_limit,
+            config.aggregation_limit,
+            config.collection_limit,
+            config
+                .expired_task_deletion
+                .as_ref()
+                .map(|config| TaskDeletionConfig {
+                    retention: Duration::from_seconds(config.retention_s),
+                    limit: config.limit,
+                    dry_run: config.dry_run,
+                }),
+        )
+        .with_max_tasks_per_run(config.max_tasks_per_run)
+    }
+
+    /// Limits the number of tasks handled by a single run. Each run resumes where the previous run
+    /// left off, wrapping around to the start of the task list, so that every task is eventually
+    /// handled.
+    pub fn with_max_tasks_per_run(self, max_tasks_per_run: Option<NonZeroUsize>) -> Self {
+        Self {
+            max_tasks_per_run,
+            ..self
         }
     }
 
     #[tracing::instrument(skip(self))]
     pub async fn run(&self) -> Result<()> {
         // TODO(#224): add support for handling only a subset of tasks in a single job (i.e. sharding).
+        let start = Instant::now();
 
-        // Retrieve tasks.
-        let tasks = self
+        // Retrieve tasks, and determine which of them are due for deletion.
+        let retention = self.task_deletion.map(|config| config.retention);
+        let (tasks, expired_task_ids) = self
             .datastore
-            .run_tx(|tx| Box::pin(async move { tx.get_tasks().await }))
+            .run_tx(|tx| {
+                Box::pin(async move {
+                    let tasks = tx.get_tasks().await?;
+                    let expired_task_ids = match retention {
+                        Some(retention) => {
+                            tx.get_task_ids_expired_beyond_retention(&retention).await?
+                        }
+                        None => Vec::new(),
+                    };
+                    Ok((tasks, expired_task_ids))
+                })
+            })
             .await
             .context("couldn't retrieve tasks")?;
+        let expired_task_ids: HashSet<TaskId> = expired_task_ids.into_iter().collect();
+
+        // Forget the backlog of tasks that no longer exist.
+        {
+            let task_ids: HashSet<&TaskId> = tasks.iter().map(Task::id).collect();
+            self.expired_client_reports_backlog
+                .lock()
+                .unwrap()
+                .retain(|task_id, _| task_ids.contains(task_id));
+            self.dry_run_deleted_task_ids
+                .lock()
+                .unwrap()
+                .retain(|task_id| task_ids.contains(task_id));
+        }
 
         // Run GC for each task.
-        join_all(tasks.into_iter().map(|task| async move {
-            let task = Arc::new(task);
-            if let Err(err) = self.gc_task(Arc::clone(&task)).await {
-                error!(task_id = ?task.id(), ?err, "Couldn't GC task");
+        join_all(self.tasks_for_run(tasks).into_iter().map(|task| {
+            let is_expired = expired_task_ids.contains(task.id());
+            async move {
+                let task_start = Instant::now();
+                let task = Arc::new(task);
+                let result = match (is_expired, &self.task_deletion) {
+                    (true, Some(task_deletion)) => {
+                        self.gc_expired_task(*task.id(), task_deletion).await
+                    }
+                    _ => self.gc_task(Arc::clone(&task)).await,
+                };
+                self.task_duration_histogram
+                    .record(task_start.elapsed().as_secs_f64(), &[]);
+                if let Err(err) = result {
+                    error!(task_id = ?task.id(), ?err, "Couldn't GC task");
+                    self.task_failures_counter.add(1, &[]);
+                }
             }
         }))
         .await;
+
+        if let Err(err) = self.gc_report_partitions().await {
+            error!(?err, "Couldn't GC report partitions");
+        }
+
+        self.run_duration_histogram
+            .record(start.elapsed().as_secs_f64(), &[]);
         Ok(())
     }
 
+    /// Selects the tasks to be handled by this run, respecting `max_tasks_per_run`, and advances
+    /// the task cursor past them.
+    fn tasks_for_run(&self, mut tasks: Vec<Task>) -> Vec<Task> {
+        let max_tasks_per_run = match self.max_tasks_per_run {
+            Some(max_tasks_per_run) if max_tasks_per_run.get() < tasks.len() => {
+                max_tasks_per_run.get()
+            }
+            _ => return tasks,
+        };
+
+        tasks.sort_unstable_by(|a, b| a.id().cmp(b.id()));
+        let mut task_cursor = self.task_cursor.lock().unwrap();
+        let start = match *task_cursor {
+            Some(cursor) => tasks.partition_point(|task| task.id() <= &cursor) % tasks.len(),
+            None => 0,
+        };
+        tasks.rotate_left(start);
+        tasks.truncate(max_tasks_per_run);
+        *task_cursor = tasks.last().map(|task| *task.id());
+        tasks
+    }
+
     async fn gc_task(&self, task: Arc<Task>) -> Result<()> {
-        self.datastore
+        let (deleted_rows, backlog) = self
+            .datastore
             .run_tx(|tx| {
                 let task = Arc::clone(&task);
                 let report_limit = self.report_limit;
@@ -62,22 +293,144 @@ impl<C: Clock> GarbageCollector<C> {
                 let collection_limit = self.collection_limit;
 
                 Box::pin(async move {
-                    try_join!(
+                    let deleted_rows = try_join!(
                         tx.delete_expired_client_reports(task.id(), report_limit),
                         tx.delete_expired_aggregation_artifacts(task.id(), aggregation_limit),
                         tx.delete_expired_collection_artifacts(task.id(), collection_limit),
                     )?;
-                    Ok(())
+                    let backlog = tx.get_expired_client_reports_backlog(task.id()).await?;
+                    Ok((deleted_rows, backlog))
+                })
+            })
+            .await?;
+
+        for deleted_rows in [deleted_rows.0, deleted_rows.1, deleted_rows.2] {
+            self.record_deleted_rows(task.id(), &deleted_rows);
+        }
+        let mut expired_client_reports_backlog =
+            self.expired_client_reports_backlog.lock().unwrap();
+        match backlog {
+            Some(backlog) => {
+                expired_client_reports_backlog.insert(*task.id(), backlog.as_seconds());
+            }
+            None => {
+                expired_client_reports_backlog.remove(task.id());
+            }
+        }
+        Ok(())
+    }
+
+    /// Deletes a task that has been expired for longer than the configured retention period. Its
+    /// data is deleted a bounded number of rows at a time, over as many runs as necessary, and the
+    /// task itself is deleted on the first run that finds no data left.
+    async fn gc_expired_task(&self, task_id: TaskId, config: &TaskDeletionConfig) -> Result<()> {
+        if config.dry_run {
+            if self
+                .dry_run_deleted_task_ids
+                .lock()
+                .unwrap()
+                .insert(task_id)
+            {
+                info!(%task_id, "DRY RUN: Would delete expired task");
+                self.deleted_tasks_counter
+                    .add(1, &[KeyValue::new("dry_run", true)]);
+            }
+            return Ok(());
+        }
+
+        let limit = config.limit;
+        let (deleted_rows, deleted_task) = self
+            .datastore
+            .run_tx_with_name("gc_expired_task", |tx| {
+                Box::pin(async move {
+                    let deleted_rows = tx.delete_task_artifacts(&task_id, limit).await?;
+                    if deleted_rows.total() > 0 {
+                        return Ok((deleted_rows, false));
+                    }
+                    tx.delete_task(&task_id).await?;
+                    Ok((deleted_rows, true))
                 })
             })
             .await?;
+
+        self.record_deleted_rows(&task_id, &deleted_rows);
+        if deleted_task {
+            info!(%task_id, "Deleted expired task");
+            self.deleted_tasks_counter
+                .add(1, &[KeyValue::new("dry_run", false)]);
+            self.expired_client_reports_backlog
+                .lock()
+                .unwrap()
+                .remove(&task_id);
+        }
+        Ok(())
+    }
+
+    /// If the client_reports & report_aggregations tables are partitioned, creates partitions for
+    /// upcoming client timestamps, and drops partitions whose rows have all expired.
+    async fn gc_report_partitions(&self) -> Result<()> {
+        let partitioned = self
+            .datastore
+            .run_tx_with_name("gc_create_report_partitions", |tx| {
+                Box::pin(async move {
+                    let partition_size = match tx.get_report_partition_size().await? {
+                        Some(partition_size) => partition_size,
+                        None => return Ok(false),
+                    };
+                    // Keep one partition ahead of the current time, so that rows rarely land in the
+                    // default partitions.
+                    tx.create_report_partitions(&partition_size).await?;
+                    Ok(true)
+                })
+            })
+            .await
+            .context("couldn't create report partitions")?;
+        if !partitioned {
+            return Ok(());
+        }
+
+        let names = self
+            .datastore
+            .run_tx_with_name("gc_get_ended_report_partitions", |tx| {
+                Box::pin(async move { tx.get_ended_report_partitions().await })
+            })
+            .await
+            .context("couldn't retrieve report partitions")?;
+        for name in names {
+            // Each partition is dropped in its own transaction, to keep the time for which it is
+            // locked short.
+            let dropped = self
+                .datastore
+                .run_tx_with_name("gc_drop_report_partition", |tx| {
+                    let name = name.clone();
+                    Box::pin(async move { tx.drop_report_partition_if_expired(&name).await })
+                })
+                .await
+                .with_context(|| format!("couldn't drop report partition {name}"))?;
+            if dropped {
+                info!(%name, "Dropped expired report partition");
+                self.dropped_report_partitions_counter.add(1, &[]);
+            }
+        }
         Ok(())
     }
+
+    fn record_deleted_rows(&self, task_id: &TaskId, deleted_rows: &DeletedRows) {
+        for (table, count) in deleted_rows.iter().filter(|(_, count)| *count > 0) {
+            self.deleted_rows_counter.add(
+                count,
+                &[
+                    KeyValue::new("table", table.to_string()),
+                    KeyValue::new("task_id", task_id.to_string()),
+                ],
+            );
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::aggregator::garbage_collector::GarbageCollector;
+    use crate::aggregator::garbage_collector::{GarbageCollector, TaskDeletionConfig};
     use janus_aggregator_core::{
         datastore::{
             models::{
@@ -88,6 +441,7 @@ mod tests {
             test_util::ephemeral_datastore,
         },
         task::{self, test_util::TaskBuilder},
+        test_util::noop_meter,
     };
     use janus_core::{
         task::VdafInstance,
@@ -95,7 +449,7 @@ mod tests {
             dummy_vdaf::{self, AggregateShare, AggregationParam},
             install_test_trace_subscriber,
         },
-        time::{Clock, IntervalExt, MockClock, TimeExt},
+        time::{Clock, DurationExt, IntervalExt, MockClock, TimeExt},
     };
     use janus_messages::{
         query_type::{FixedSize, TimeInterval},
@@ -103,7 +457,7 @@ mod tests {
         Query, ReportIdChecksum, ReportMetadata, ReportShare, Role, Time,
     };
     use rand::random;
-    use std::sync::Arc;
+    use std::{collections::HashSet, num::NonZeroUsize, sync::Arc};
 
     const OLDEST_ALLOWED_REPORT_TIMESTAMP: Time = Time::from_seconds_since_epoch(1000);
     const REPORT_EXPIRY_AGE: Duration = Duration::from_seconds(500);
@@ -225,9 +579,11 @@ mod tests {
         let task = Arc::new(task);
         GarbageCollector::new(
             Arc::clone(&ds),
+            &noop_meter(),
             u64::try_from(i64::MAX).unwrap(),
             u64::try_from(i64::MAX).unwrap(),
             u64::try_from(i64::MAX).unwrap(),
+            None,
         )
         .gc_task(Arc::clone(&task))
         .await
@@ -417,9 +773,11 @@ mod tests {
         let task = Arc::new(task);
         GarbageCollector::new(
             Arc::clone(&ds),
+            &noop_meter(),
             u64::try_from(i64::MAX).unwrap(),
             u64::try_from(i64::MAX).unwrap(),
             u64::try_from(i64::MAX).unwrap(),
+            None,
         )
         .gc_task(Arc::clone(&task))
         .await
@@ -597,9 +955,11 @@ mod tests {
         let task = Arc::new(task);
         GarbageCollector::new(
             Arc::clone(&ds),
+            &noop_meter(),
             u64::try_from(i64::MAX).unwrap(),
             u64::try_from(i64::MAX).unwrap(),
             u64::try_from(i64::MAX).unwrap(),
+            None,
         )
         .gc_task(Arc::clone(&task))
         .await
@@ -792,9 +1152,11 @@ mod tests {
         let task = Arc::new(task);
         GarbageCollector::new(
             Arc::clone(&ds),
+            &noop_meter(),
             u64::try_from(i64::MAX).unwrap(),
             u64::try_from(i64::MAX).unwrap(),
             u64::try_from(i64::MAX).unwrap(),
+            None,
         )
         .gc_task(Arc::clone(&task))
         .await
@@ -856,4 +1218,293 @@ mod tests {
         .await
         .unwrap();
     }
+
+    #[tokio::test]
+    async fn gc_expired_tasks() {
+        install_test_trace_subscriber();
+
+        let clock = MockClock::new(OLDEST_ALLOWED_REPORT_TIMESTAMP);
+        let ephemeral_datastore = ephemeral_datastore().await;
+        let ds = Arc::new(ephemeral_datastore.datastore(clock.clone()).await);
+        let vdaf = dummy_vdaf::Vdaf::new();
+        const TASK_RETENTION: Duration = Duration::from_seconds(500);
+
+        // Setup: one task expired long ago, with some reports and an aggregation job; one task
+        // expired within the retention period; and one task with no expiration.
+        let (expired_task, recently_expired_task, unexpired_task) = ds
+            .run_tx(|tx| {
+                let (clock, vdaf) = (clock.clone(), vdaf.clone());
+                Box::pin(async move {
+                    let expired_task = TaskBuilder::new(
+                        task::QueryType::TimeInterval,
+                        VdafInstance::Fake,
+                        Role::Leader,
+                    )
+                    .with_task_expiration(Some(
+                        clock
+                            .now()
+                            .sub(&TASK_RETENTION)
+                            .unwrap()
+                            .sub(&Duration::from_seconds(1))
+                            .unwrap(),
+                    ))
+                    .build();
+                    tx.put_task(&expired_task).await?;
+
+                    let recently_expired_task = TaskBuilder::new(
+                        task::QueryType::TimeInterval,
+                        VdafInstance::Fake,
+                        Role::Leader,
+                    )
+                    .with_task_expiration(Some(
+                        clock.now().sub(&Duration::from_seconds(1)).unwrap(),
+                    ))
+                    .build();
+                    tx.put_task(&recently_expired_task).await?;
+
+                    let unexpired_task = TaskBuilder::new(
+                        task::QueryType::TimeInterval,
+                        VdafInstance::Fake,
+                        Role::Leader,
+                    )
+                    .with_task_expiration(None)
+                    .build();
+                    tx.put_task(&unexpired_task).await?;
+
+                    let client_timestamp = clock.now().sub(&Duration::from_seconds(2)).unwrap();
+                    for task in [&expired_task, &recently_expired_task] {
+                        for _ in 0..3 {
+                            tx.put_client_report(
+                                &vdaf,
+                                &LeaderStoredReport::new_dummy(*task.id(), client_timestamp),
+                            )
+                            .await?;
+                        }
+                        tx.put_aggregation_job(
+                            &AggregationJob::<0, TimeInterval, dummy_vdaf::Vdaf>::new(
+                                *task.id(),
+                                random(),
+                                AggregationParam(0),
+                                (),
+                                Interval::from_time(&client_timestamp).unwrap(),
+                                AggregationJobState::InProgress,
+                                AggregationJobRound::from(0),
+                            ),
+                        )
+                        .await?;
+                    }
+
+                    Ok((expired_task, recently_expired_task, unexpired_task))
+                })
+            })
+            .await
+            .unwrap();
+
+        let count_reports = |task_id| {
+            let ds = Arc::clone(&ds);
+            async move {
+                ds.run_tx(|tx| {
+                    Box::pin(async move {
+                        tx.get_client_reports_for_task::<0, dummy_vdaf::Vdaf>(
+                            &dummy_vdaf::Vdaf::new(),
+                            &task_id,
+                        )
+                        .await
+                    })
+                })
+                .await
+                .unwrap()
+                .len()
+            }
+        };
+        let get_task_ids = || {
+            let ds = Arc::clone(&ds);
+            async move {
+                let mut task_ids = ds
+                    .run_tx(|tx| Box::pin(async move { tx.get_task_ids(None).await }))
+                    .await
+                    .unwrap();
+                task_ids.sort();
+                task_ids
+            }
+        };
+        let mut all_task_ids = Vec::from([
+            *expired_task.id(),
+            *recently_expired_task.id(),
+            *unexpired_task.id(),
+        ]);
+        all_task_ids.sort();
+
+        // Dry run: nothing is deleted, and the expired task is counted once across runs.
+        let dry_run_gc = GarbageCollector::new(
+            Arc::clone(&ds),
+            &noop_meter(),
+            1,
+            1,
+            1,
+            Some(TaskDeletionConfig {
+                retention: TASK_RETENTION,
+                limit: 1,
+                dry_run: true,
+            }),
+        );
+        for _ in 0..2 {
+            dry_run_gc.run().await.unwrap();
+        }
+        assert_eq!(get_task_ids().await, all_task_ids);
+        assert_eq!(count_reports(*expired_task.id()).await, 3);
+        assert_eq!(
+            *dry_run_gc.dry_run_deleted_task_ids.lock().unwrap(),
+            HashSet::from([*expired_task.id()])
+        );
+
+        // Expired task data is deleted incrementally, then the task itself is deleted.
+        let gc = GarbageCollector::new(
+            Arc::clone(&ds),
+            &noop_meter(),
+            1,
+            1,
+            1,
+            Some(TaskDeletionConfig {
+                retention: TASK_RETENTION,
+                limit: 1,
+                dry_run: false,
+            }),
+        );
+        gc.run().await.unwrap();
+        assert_eq!(get_task_ids().await, all_task_ids);
+        assert_eq!(count_reports(*expired_task.id()).await, 2);
+
+        for _ in 0..3 {
+            gc.run().await.unwrap();
+        }
+        let mut want_task_ids = Vec::from([*recently_expired_task.id(), *unexpired_task.id()]);
+        want_task_ids.sort();
+        assert_eq!(get_task_ids().await, want_task_ids);
+
+        // The recently expired task's data is untouched, since its reports haven't expired.
+        assert_eq!(count_reports(*recently_expired_task.id()).await, 3);
+    }
+
+    #[tokio::test]
+    async fn max_tasks_per_run() {
+        install_test_trace_subscriber();
+
+        let clock = MockClock::new(OLDEST_ALLOWED_REPORT_TIMESTAMP);
+        let ephemeral_datastore = ephemeral_datastore().await;
+        let ds = Arc::new(ephemeral_datastore.datastore(clock.clone()).await);
+
+        let tasks = Vec::from_iter((0..3).map(|_| {
+            TaskBuilder::new(
+                task::QueryType::TimeInterval,
+                VdafInstance::Fake,
+                Role::Leader,
+            )
+            .build()
+        }));
+        let mut task_ids = Vec::from_iter(tasks.iter().map(|task| *task.id()));
+        task_ids.sort();
+
+        let gc = GarbageCollector::new(
+            Arc::clone(&ds),
+            &noop_meter(),
+            u64::try_from(i64::MAX).unwrap(),
+            u64::try_from(i64::MAX).unwrap(),
+            u64::try_from(i64::MAX).unwrap(),
+            None,
+        )
+        .with_max_tasks_per_run(NonZeroUsize::new(2));
+
+        // Successive runs resume where the previous run left off, wrapping around.
+        let selected_task_ids = |gc: &GarbageCollector<MockClock>| {
+            Vec::from_iter(
+                gc.tasks_for_run(tasks.clone())
+                    .iter()
+                    .map(|task| *task.id()),
+            )
+        };
+        assert_eq!(
+            selected_task_ids(&gc),
+            Vec::from([task_ids[0], task_ids[1]])
+        );
+        assert_eq!(
+            selected_task_ids(&gc),
+            Vec::from([task_ids[2], task_ids[0]])
+        );
+        assert_eq!(
+            selected_task_ids(&gc),
+            Vec::from([task_ids[1], task_ids[2]])
+        );
+
+        // A budget at least as large as the number of tasks selects every task.
+        let gc = gc.with_max_tasks_per_run(NonZeroUsize::new(3));
+        let mut all_task_ids = selected_task_ids(&gc);
+        all_task_ids.sort();
+        assert_eq!(all_task_ids, task_ids);
+    }
+
+    #[tokio::test]
+    async fn gc_report_partitions() {
+        install_test_trace_subscriber();
+
+        let clock = MockClock::new(OLDEST_ALLOWED_REPORT_TIMESTAMP);
+        let ephemeral_datastore = ephemeral_datastore().await;
+        let ds = Arc::new(ephemeral_datastore.datastore(clock.clone()).await);
+        let vdaf = dummy_vdaf::Vdaf::new();
+
+        // Setup: write a report, then partition the tables into hour-long partitions. The report
+        // lands in the legacy partition, which ends at 3600.
+        let task = TaskBuilder::new(
+            task::QueryType::TimeInterval,
+            VdafInstance::Fake,
+            Role::Leader,
+        )
+        .with_report_expiry_age(Some(REPORT_EXPIRY_AGE))
+        .build();
+        let report = LeaderStoredReport::new_dummy(*task.id(), clock.now());
+        ds.run_tx(|tx| {
+            let (task, report, vdaf) = (task.clone(), report.clone(), vdaf.clone());
+            Box::pin(async move {
+                tx.put_task(&task).await?;
+                tx.put_client_report(&vdaf, &report).await?;
+                tx.partition_report_tables(&Duration::from_hours(1).unwrap())
+                    .await
+            })
+        })
+        .await
+        .unwrap();
+
+        // Run GC with row-by-row deletion of client reports disabled, once the legacy partition has
+        // ended & its report has expired.
+        let gc = GarbageCollector::new(
+            Arc::clone(&ds),
+            &noop_meter(),
+            0,
+            u64::try_from(i64::MAX).unwrap(),
+            u64::try_from(i64::MAX).unwrap(),
+            None,
+        );
+        clock.set(Time::from_seconds_since_epoch(4000));
+        gc.run().await.unwrap();
+
+        // Verify: the legacy partition, and the report in it, are gone, and there is a partition
+        // covering the next hour.
+        let (ended_partitions, reports, created_partitions) = ds
+            .run_tx(|tx| {
+                let (task, vdaf) = (task.clone(), vdaf.clone());
+                Box::pin(async move {
+                    Ok((
+                        tx.get_ended_report_partitions().await?,
+                        tx.get_client_reports_for_task(&vdaf, task.id()).await?,
+                        tx.create_report_partitions(&Duration::from_hours(1).unwrap())
+                            .await?,
+                    ))
+                })
+            })
+            .await
+            .unwrap();
+        assert!(ended_partitions.is_empty());
+        assert!(reports.is_empty());
+        assert_eq!(created_partitions, 0);
+    }
 }
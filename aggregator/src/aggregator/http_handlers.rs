@@ -2,7 +2,8 @@ use super::{Aggregator, Config, Error};
 use crate::aggregator::problem_details::ProblemDetailsConnExt;
 use async_trait::async_trait;
 use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
-use janus_aggregator_core::{datastore::Datastore, instrumented};
+use futures::AsyncReadExt;
+use janus_aggregator_core::{datastore::Datastore, instrumented, task::TaskInvalidationHook};
 use janus_core::{
     http::extract_bearer_token,
     task::{AuthenticationToken, DAP_AUTH_HEADER},
@@ -10,10 +11,10 @@ use janus_core::{
     time::Clock,
 };
 use janus_messages::{
-    codec::Decode, problem_type::DapProblemType, query_type::TimeInterval, taskprov::TaskConfig,
-    AggregateShare, AggregateShareReq, AggregationJobContinueReq, AggregationJobId,
-    AggregationJobInitializeReq, AggregationJobResp, Collection, CollectionJobId, CollectionReq,
-    HpkeConfigList, Report, TaskId,
+    batch_discovery::CollectableBatchList, codec::Decode, problem_type::DapProblemType,
+    query_type::TimeInterval, taskprov::TaskConfig, AggregateShare, AggregateShareReq,
+    AggregationJobContinueReq, AggregationJobId, AggregationJobInitializeReq, AggregationJobResp,
+    BatchId, Collection, CollectionJobId, CollectionReq, HpkeConfigList, Report, TaskId,
 };
 use opentelemetry::{
     metrics::{Counter, Meter, Unit},
@@ -24,7 +25,7 @@ use ring::digest::{digest, SHA256};
 use routefinder::Captures;
 use serde::Deserialize;
 use std::time::Duration as StdDuration;
-use std::{io::Cursor, sync::Arc};
+use std::{io::Cursor, str::FromStr, sync::Arc};
 use tracing::warn;
 use trillium::{Conn, Handler, KnownHeaderName, Status};
 use trillium_api::{api, State};
@@ -106,6 +107,19 @@ impl Handler for Error {
             Error::InvalidTask(task_id, _) => {
                 conn.with_problem_details(DapProblemType::InvalidTask, Some(task_id))
             }
+            Error::RequestTooLarge(task_id, _) => {
+                conn.with_http_problem_details(Status::PayloadTooLarge, Some(task_id))
+            }
+            Error::TooManyRequests(task_id, retry_after) => conn
+                .with_header(
+                    KnownHeaderName::RetryAfter,
+                    // Round up, so that clients which retry promptly are not rejected again.
+                    retry_after
+                        .as_secs()
+                        .saturating_add(u64::from(retry_after.subsec_nanos() > 0))
+                        .to_string(),
+                )
+                .with_http_problem_details(Status::TooManyRequests, Some(task_id)),
         }
     }
 }
@@ -206,6 +220,7 @@ pub(crate) static AGGREGATION_JOB_ROUTE: &str =
     "tasks/:task_id/aggregation_jobs/:aggregation_job_id";
 pub(crate) static COLLECTION_JOB_ROUTE: &str = "tasks/:task_id/collection_jobs/:collection_job_id";
 pub(crate) static AGGREGATE_SHARES_ROUTE: &str = "tasks/:task_id/aggregate_shares";
+pub(crate) static BATCHES_ROUTE: &str = "tasks/:task_id/batches";
 
 /// Constructs a Trillium handler for the aggregator.
 pub async fn aggregator_handler<C: Clock>(
@@ -218,6 +233,23 @@ pub async fn aggregator_handler<C: Clock>(
     aggregator_handler_with_aggregator(aggregator, meter).await
 }
 
+/// Constructs a Trillium handler for the aggregator, along with a hook that invalidates the
+/// aggregator's cached copy of a task. The hook should be invoked whenever a task is changed by
+/// other means in the same process, such as the aggregator API.
+pub async fn aggregator_handler_and_task_invalidation_hook<C: Clock>(
+    datastore: Arc<Datastore<C>>,
+    clock: C,
+    meter: &Meter,
+    cfg: Config,
+) -> Result<(impl Handler, Arc<dyn TaskInvalidationHook>), Error> {
+    let aggregator = Arc::new(Aggregator::new(datastore, clock, meter, cfg).await?);
+    let task_invalidation_hook = aggregator.task_invalidation_hook();
+    Ok((
+        aggregator_handler_with_aggregator(aggregator, meter).await?,
+        task_invalidation_hook,
+    ))
+}
+
 async fn aggregator_handler_with_aggregator<C: Clock>(
     aggregator: Arc<Aggregator<C>>,
     meter: &Meter,
@@ -262,7 +294,8 @@ async fn aggregator_handler_with_aggregator<C: Clock>(
             .post(
                 AGGREGATE_SHARES_ROUTE,
                 instrumented(api(aggregate_shares::<C>)),
-            ),
+            )
+            .get(BATCHES_ROUTE, instrumented(api(batches_get::<C>))),
         StatusCounter::new(meter),
     ))
 }
@@ -320,16 +353,30 @@ async fn hpke_config_cors_preflight(mut conn: Conn) -> Conn {
 /// API handler for the "/tasks/.../reports" PUT endpoint.
 async fn upload<C: Clock>(
     conn: &mut Conn,
-    (State(aggregator), State(captures), body): (
+    (State(aggregator), State(captures)): (
         State<Arc<Aggregator<C>>>,
         State<Captures<'static, 'static>>,
-        Vec<u8>,
     ),
 ) -> Result<Status, Arc<Error>> {
     validate_content_type(conn, Report::MEDIA_TYPE).map_err(Arc::new)?;
 
     let task_id = parse_task_id(&captures).map_err(Arc::new)?;
-    aggregator.handle_upload(&task_id, &body).await?;
+    let taskprov_task_config =
+        parse_taskprov_header(&aggregator, &task_id, conn).map_err(Arc::new)?;
+    check_upload_rate_limit(&aggregator, &task_id, taskprov_task_config.is_some(), conn)
+        .await
+        .map_err(Arc::new)?;
+    let body = read_body(
+        &aggregator,
+        conn,
+        &task_id,
+        aggregator.cfg.request_body_size_limits.upload,
+    )
+    .await
+    .map_err(Arc::new)?;
+    aggregator
+        .handle_upload(&task_id, &body, taskprov_task_config.as_ref())
+        .await?;
 
     // Handle CORS, if the request header is present.
     if let Some(origin) = conn.request_headers().get(KnownHeaderName::Origin) {
@@ -363,10 +410,9 @@ async fn upload_cors_preflight(mut conn: Conn) -> Conn {
 /// API handler for the "/tasks/.../aggregation_jobs/..." PUT endpoint.
 async fn aggregation_jobs_put<C: Clock>(
     conn: &mut Conn,
-    (State(aggregator), State(captures), body): (
+    (State(aggregator), State(captures)): (
         State<Arc<Aggregator<C>>>,
         State<Captures<'static, 'static>>,
-        Vec<u8>,
     ),
 ) -> Result<EncodedBody<AggregationJobResp>, Error> {
     validate_content_type(
@@ -378,6 +424,13 @@ async fn aggregation_jobs_put<C: Clock>(
     let aggregation_job_id = parse_aggregation_job_id(&captures)?;
     let auth_token = parse_auth_token(&task_id, conn)?;
     let taskprov_task_config = parse_taskprov_header(&aggregator, &task_id, conn)?;
+    let body = read_body(
+        &aggregator,
+        conn,
+        &task_id,
+        aggregator.cfg.request_body_size_limits.aggregation_job,
+    )
+    .await?;
     let response = aggregator
         .handle_aggregate_init(
             &task_id,
@@ -394,10 +447,9 @@ async fn aggregation_jobs_put<C: Clock>(
 /// API handler for the "/tasks/.../aggregation_jobs/..." POST endpoint.
 async fn aggregation_jobs_post<C: Clock>(
     conn: &mut Conn,
-    (State(aggregator), State(captures), body): (
+    (State(aggregator), State(captures)): (
         State<Arc<Aggregator<C>>>,
         State<Captures<'static, 'static>>,
-        Vec<u8>,
     ),
 ) -> Result<EncodedBody<AggregationJobResp>, Error> {
     validate_content_type(conn, AggregationJobContinueReq::MEDIA_TYPE)?;
@@ -406,6 +458,13 @@ async fn aggregation_jobs_post<C: Clock>(
     let aggregation_job_id = parse_aggregation_job_id(&captures)?;
     let auth_token = parse_auth_token(&task_id, conn)?;
     let taskprov_task_config = parse_taskprov_header(&aggregator, &task_id, conn)?;
+    let body = read_body(
+        &aggregator,
+        conn,
+        &task_id,
+        aggregator.cfg.request_body_size_limits.aggregation_job,
+    )
+    .await?;
     let response = aggregator
         .handle_aggregate_continue(
             &task_id,
@@ -422,10 +481,9 @@ async fn aggregation_jobs_post<C: Clock>(
 /// API handler for the "/tasks/.../collection_jobs/..." PUT endpoint.
 async fn collection_jobs_put<C: Clock>(
     conn: &mut Conn,
-    (State(aggregator), State(captures), body): (
+    (State(aggregator), State(captures)): (
         State<Arc<Aggregator<C>>>,
         State<Captures<'static, 'static>>,
-        Vec<u8>,
     ),
 ) -> Result<Status, Error> {
     validate_content_type(conn, CollectionReq::<TimeInterval>::MEDIA_TYPE)?;
@@ -433,8 +491,22 @@ async fn collection_jobs_put<C: Clock>(
     let task_id = parse_task_id(&captures)?;
     let collection_job_id = parse_collection_job_id(&captures)?;
     let auth_token = parse_auth_token(&task_id, conn)?;
+    let taskprov_task_config = parse_taskprov_header(&aggregator, &task_id, conn)?;
+    let body = read_body(
+        &aggregator,
+        conn,
+        &task_id,
+        aggregator.cfg.request_body_size_limits.collection_job,
+    )
+    .await?;
     aggregator
-        .handle_create_collection_job(&task_id, &collection_job_id, &body, auth_token)
+        .handle_create_collection_job(
+            &task_id,
+            &collection_job_id,
+            &body,
+            auth_token,
+            taskprov_task_config.as_ref(),
+        )
         .await?;
 
     Ok(Status::Created)
@@ -488,10 +560,9 @@ async fn collection_jobs_delete<C: Clock>(
 /// API handler for the "/tasks/.../aggregate_shares" POST endpoint.
 async fn aggregate_shares<C: Clock>(
     conn: &mut Conn,
-    (State(aggregator), State(captures), body): (
+    (State(aggregator), State(captures)): (
         State<Arc<Aggregator<C>>>,
         State<Captures<'static, 'static>>,
-        Vec<u8>,
     ),
 ) -> Result<EncodedBody<AggregateShare>, Error> {
     validate_content_type(conn, AggregateShareReq::<TimeInterval>::MEDIA_TYPE)?;
@@ -499,6 +570,13 @@ async fn aggregate_shares<C: Clock>(
     let task_id = parse_task_id(&captures)?;
     let auth_token = parse_auth_token(&task_id, conn)?;
     let taskprov_task_config = parse_taskprov_header(&aggregator, &task_id, conn)?;
+    let body = read_body(
+        &aggregator,
+        conn,
+        &task_id,
+        aggregator.cfg.request_body_size_limits.aggregate_share,
+    )
+    .await?;
     let share = aggregator
         .handle_aggregate_share(&task_id, &body, auth_token, taskprov_task_config.as_ref())
         .await?;
@@ -506,6 +584,39 @@ async fn aggregate_shares<C: Clock>(
     Ok(EncodedBody::new(share, AggregateShare::MEDIA_TYPE))
 }
 
+/// Deserialization helper struct to extract a "pagination_token" parameter from a query string.
+#[derive(Deserialize)]
+struct BatchesQuery {
+    /// The optional "pagination_token" parameter, a batch ID in base64url-encoded form.
+    #[serde(default)]
+    pagination_token: Option<String>,
+}
+
+/// API handler for the "/tasks/.../batches" GET endpoint.
+async fn batches_get<C: Clock>(
+    conn: &mut Conn,
+    (State(aggregator), State(captures)): (
+        State<Arc<Aggregator<C>>>,
+        State<Captures<'static, 'static>>,
+    ),
+) -> Result<EncodedBody<CollectableBatchList>, Error> {
+    let task_id = parse_task_id(&captures)?;
+    let auth_token = parse_auth_token(&task_id, conn)?;
+    let query = serde_urlencoded::from_str::<BatchesQuery>(conn.querystring())
+        .map_err(|err| Error::BadRequest(format!("couldn't parse query string: {err}")))?;
+    let pagination_token = query
+        .pagination_token
+        .as_deref()
+        .map(BatchId::from_str)
+        .transpose()
+        .map_err(|err| Error::BadRequest(format!("couldn't parse pagination_token: {err:?}")))?;
+    let batches = aggregator
+        .handle_get_batches(&task_id, auth_token, pagination_token)
+        .await?;
+
+    Ok(EncodedBody::new(batches, CollectableBatchList::MEDIA_TYPE))
+}
+
 /// Check the request's Content-Type header, and return an error if it is missing or not equal to
 /// the expected value.
 fn validate_content_type(conn: &Conn, expected_media_type: &'static str) -> Result<(), Error> {
@@ -522,6 +633,79 @@ fn validate_content_type(conn: &Conn, expected_media_type: &'static str) -> Resu
     }
 }
 
+/// Read the request body, returning an error if it is larger than `max_len` bytes.
+async fn read_body<C: Clock>(
+    aggregator: &Aggregator<C>,
+    conn: &mut Conn,
+    task_id: &TaskId,
+    max_len: usize,
+) -> Result<Vec<u8>, Error> {
+    let too_large = || {
+        aggregator
+            .request_limit_rejection_counter
+            .add(1, &[KeyValue::new("limit", "body_size")]);
+        Error::RequestTooLarge(*task_id, max_len)
+    };
+    let max_len_u64 = u64::try_from(max_len)?;
+
+    let request_body = conn.request_body().await;
+    let body = match request_body.content_length() {
+        // Reject requests that declare an oversized body up front, without reading them.
+        Some(content_length) if content_length > max_len_u64 => return Err(too_large()),
+        Some(_) => request_body
+            .read_bytes()
+            .await
+            .map_err(|err| Error::BadRequest(format!("couldn't read request body: {err}")))?,
+        None => {
+            // Read at most one byte more than the limit, which is enough to tell whether the body
+            // is too large when its length isn't known in advance.
+            let mut body = Vec::new();
+            request_body
+                .take(max_len_u64.saturating_add(1))
+                .read_to_end(&mut body)
+                .await
+                .map_err(|err| Error::BadRequest(format!("couldn't read request body: {err}")))?;
+            if body.len() > max_len {
+                return Err(too_large());
+            }
+            body
+        }
+    };
+    Ok(body)
+}
+
+/// Check the rate limit for report uploads, if one is configured, returning an error if the
+/// client has exceeded it. `has_taskprov_task_config` indicates whether the request carries a
+/// taskprov task configuration.
+async fn check_upload_rate_limit<C: Clock>(
+    aggregator: &Aggregator<C>,
+    task_id: &TaskId,
+    has_taskprov_task_config: bool,
+    conn: &Conn,
+) -> Result<(), Error> {
+    if let Some(rate_limiter) = &aggregator.upload_rate_limiter {
+        // The rate limiter keeps a bucket per task, so only requests for tasks which exist, or
+        // which the upload may provision, are charged to it. Otherwise, requests for arbitrary
+        // task IDs could grow its state without bound.
+        let may_opt_in = aggregator.cfg.taskprov_config.enabled
+            && aggregator.cfg.taskprov_config.opt_in_on_upload
+            && has_taskprov_task_config;
+        if !may_opt_in && aggregator.task_aggregator_for(task_id).await?.is_none() {
+            return Err(Error::UnrecognizedTask(*task_id));
+        }
+
+        rate_limiter
+            .check(task_id, conn.peer_ip())
+            .map_err(|retry_after| {
+                aggregator
+                    .request_limit_rejection_counter
+                    .add(1, &[KeyValue::new("limit", "rate")]);
+                Error::TooManyRequests(*task_id, retry_after)
+            })?;
+    }
+    Ok(())
+}
+
 /// Parse a [`TaskId`] from the "task_id" parameter in a set of path parameter [`Captures`].
 fn parse_task_id(captures: &Captures) -> Result<TaskId, Error> {
     let encoded = captures
@@ -617,7 +801,7 @@ pub mod test_util {
     use std::borrow::Cow;
     use trillium_testing::{assert_headers, TestConn};
 
-    async fn take_response_body(test_conn: &mut TestConn) -> Cow<'_, [u8]> {
+    pub async fn take_response_body(test_conn: &mut TestConn) -> Cow<'_, [u8]> {
         test_conn
             .take_response_body()
             .unwrap()
@@ -657,7 +841,7 @@ mod tests {
             },
             Config,
         },
-        config::TaskprovConfig,
+        config::{RequestBodySizeLimits, TaskprovConfig, UploadRateLimitConfig},
     };
     use assert_matches::assert_matches;
     use futures::future::try_join_all;
@@ -673,7 +857,7 @@ mod tests {
         },
         query_type::{AccumulableQueryType, CollectableQueryType},
         task::{test_util::TaskBuilder, QueryType, VerifyKey},
-        taskprov,
+        taskprov::{self, test_util::PeerAggregatorBuilder},
         test_util::noop_meter,
     };
     use janus_core::{
@@ -691,13 +875,18 @@ mod tests {
         time::{Clock, DurationExt, IntervalExt, MockClock, TimeExt},
     };
     use janus_messages::{
-        query_type::TimeInterval, AggregateShare as AggregateShareMessage, AggregateShareAad,
-        AggregateShareReq, AggregationJobContinueReq, AggregationJobId,
-        AggregationJobInitializeReq, AggregationJobResp, AggregationJobRound, BatchSelector,
-        Collection, CollectionJobId, CollectionReq, Duration, Extension, ExtensionType,
-        HpkeCiphertext, HpkeConfigId, HpkeConfigList, InputShareAad, Interval,
-        PartialBatchSelector, PrepareStep, PrepareStepResult, Query, Report, ReportId,
-        ReportIdChecksum, ReportMetadata, ReportShare, ReportShareError, Role, TaskId, Time,
+        query_type::TimeInterval,
+        taskprov::{
+            DpConfig, DpMechanism, Query as TaskprovQuery, QueryConfig, TaskConfig, VdafConfig,
+            VdafType,
+        },
+        AggregateShare as AggregateShareMessage, AggregateShareAad, AggregateShareReq,
+        AggregationJobContinueReq, AggregationJobId, AggregationJobInitializeReq,
+        AggregationJobResp, AggregationJobRound, BatchSelector, Collection, CollectionJobId,
+        CollectionReq, Duration, Extension, ExtensionType, HpkeCiphertext, HpkeConfigId,
+        HpkeConfigList, InputShareAad, Interval, PartialBatchSelector, PrepareStep,
+        PrepareStepResult, Query, Report, ReportId, ReportIdChecksum, ReportMetadata, ReportShare,
+        ReportShareError, Role, TaskId, Time,
     };
     use prio::{
         codec::{Decode, Encode},
@@ -984,16 +1173,41 @@ mod tests {
             task.vdaf_verify_keys().to_vec(),
             task.max_batch_query_count(),
             task.task_expiration().cloned(),
-            task.report_expiry_age().cloned(),
             task.min_batch_size(),
             *task.time_precision(),
-            *task.tolerable_clock_skew(),
+            *task.dp_config(),
+            &TaskConfig::new(
+                Vec::from("foobar".as_bytes()),
+                Vec::from([
+                    task.leader_aggregator_endpoint()
+                        .as_str()
+                        .as_bytes()
+                        .try_into()
+                        .unwrap(),
+                    task.helper_aggregator_endpoint()
+                        .as_str()
+                        .as_bytes()
+                        .try_into()
+                        .unwrap(),
+                ]),
+                QueryConfig::new(*task.time_precision(), 1, 1, TaskprovQuery::TimeInterval),
+                Time::from_seconds_since_epoch(u64::MAX),
+                VdafConfig::new(DpConfig::new(DpMechanism::None), VdafType::Prio3Count).unwrap(),
+            )
+            .unwrap(),
+            &PeerAggregatorBuilder::new()
+                .with_endpoint(task.helper_aggregator_endpoint().clone())
+                .with_role(Role::Helper)
+                .build(),
         )
         .unwrap();
         datastore.put_task(&task.into()).await.unwrap();
 
         let cfg = Config {
-            taskprov_config: TaskprovConfig { enabled: true },
+            taskprov_config: TaskprovConfig {
+                enabled: true,
+                opt_in_on_upload: false,
+            },
             ..Default::default()
         };
 
@@ -1322,6 +1536,114 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn upload_handler_request_limits() {
+        install_test_trace_subscriber();
+        let clock = MockClock::default();
+        let ephemeral_datastore = ephemeral_datastore().await;
+        let datastore = Arc::new(ephemeral_datastore.datastore(clock.clone()).await);
+        let handler = aggregator_handler(
+            Arc::clone(&datastore),
+            clock.clone(),
+            &noop_meter(),
+            Config {
+                request_body_size_limits: RequestBodySizeLimits {
+                    upload: 16,
+                    ..Default::default()
+                },
+                upload_rate_limit: Some(UploadRateLimitConfig {
+                    requests_per_second: 1,
+                    burst: 1,
+                    per_client_ip: false,
+                }),
+                ..default_aggregator_config()
+            },
+        )
+        .await
+        .unwrap();
+
+        let task = TaskBuilder::new(
+            QueryType::TimeInterval,
+            VdafInstance::Prio3Count,
+            Role::Leader,
+        )
+        .build();
+        datastore.put_task(&task).await.unwrap();
+        let report = create_report(&task, clock.now());
+        assert!(report.get_encoded().len() > 16);
+
+        // A report larger than the configured limit is rejected.
+        let mut test_conn = put(task.report_upload_uri().unwrap().path())
+            .with_request_header(KnownHeaderName::ContentType, Report::MEDIA_TYPE)
+            .with_request_body(report.get_encoded())
+            .run_async(&handler)
+            .await;
+        assert_eq!(test_conn.status(), Some(Status::PayloadTooLarge));
+        assert_eq!(
+            take_problem_details(&mut test_conn).await,
+            json!({
+                "status": 413u16,
+                "type": "about:blank",
+                "title": "Payload Too Large",
+                "taskid": format!("{}", task.id()),
+            })
+        );
+
+        // The rate limiter's only token was taken by the previous request, so the next request
+        // is rejected, and the client is told when to retry.
+        let mut test_conn = put(task.report_upload_uri().unwrap().path())
+            .with_request_header(KnownHeaderName::ContentType, Report::MEDIA_TYPE)
+            .with_request_body(report.get_encoded())
+            .run_async(&handler)
+            .await;
+        assert_eq!(test_conn.status(), Some(Status::TooManyRequests));
+        assert_headers!(&test_conn, "retry-after" => "1");
+        assert_eq!(
+            take_problem_details(&mut test_conn).await,
+            json!({
+                "status": 429u16,
+                "type": "about:blank",
+                "title": "Too Many Requests",
+                "taskid": format!("{}", task.id()),
+            })
+        );
+
+        // Other tasks are rate limited separately, so a request for another task gets as far as the
+        // body size check.
+        let other_task = TaskBuilder::new(
+            QueryType::TimeInterval,
+            VdafInstance::Prio3Count,
+            Role::Leader,
+        )
+        .build();
+        datastore.put_task(&other_task).await.unwrap();
+        let test_conn = put(other_task.report_upload_uri().unwrap().path())
+            .with_request_header(KnownHeaderName::ContentType, Report::MEDIA_TYPE)
+            .with_request_body(create_report(&other_task, clock.now()).get_encoded())
+            .run_async(&handler)
+            .await;
+        assert_eq!(test_conn.status(), Some(Status::PayloadTooLarge));
+
+        // Requests for unknown tasks are rejected before they are charged to the rate limiter, or
+        // their bodies are read.
+        let unknown_task_id: TaskId = random();
+        let mut test_conn = put(&format!("/tasks/{unknown_task_id}/reports"))
+            .with_request_header(KnownHeaderName::ContentType, Report::MEDIA_TYPE)
+            .with_request_body(report.get_encoded())
+            .run_async(&handler)
+            .await;
+        assert_eq!(test_conn.status(), Some(Status::BadRequest));
+        assert_eq!(
+            take_problem_details(&mut test_conn).await,
+            json!({
+                "status": 400u16,
+                "type": "urn:ietf:params:ppm:dap:error:unrecognizedTask",
+                "title": "An endpoint received a message with an unknown task ID.",
+                "taskid": format!("{unknown_task_id}"),
+            })
+        );
+    }
+
     // Helper should not expose /upload endpoint
     #[tokio::test]
     async fn upload_handler_helper() {
@@ -4366,6 +4688,9 @@ mod tests {
                         .with_state(CollectionJobState::Finished {
                             report_count: 12,
                             encrypted_helper_aggregate_share,
+                            helper_aggregate_share_hpke_config: task
+                                .collector_hpke_config()
+                                .cloned(),
                             leader_aggregate_share,
                         });
 
@@ -40,6 +40,11 @@ pub trait ProblemDetailsConnExt {
     /// DAP-specific problem type, (optionally including the DAP task ID) and set the appropriate
     /// HTTP status code.
     fn with_problem_details(self, error_type: DapProblemType, task_id: Option<&TaskId>) -> Self;
+
+    /// Send a response containing a JSON-encoded problem details document for a problem that is
+    /// not specific to DAP, and which is fully described by the given HTTP status code, optionally
+    /// including the DAP task ID.
+    fn with_http_problem_details(self, status: Status, task_id: Option<&TaskId>) -> Self;
 }
 
 impl ProblemDetailsConnExt for Conn {
@@ -58,6 +63,22 @@ impl ProblemDetailsConnExt for Conn {
                 taskid: &task_id.as_ref().map(ToString::to_string),
             })
     }
+
+    fn with_http_problem_details(self, status: Status, task_id: Option<&TaskId>) -> Self {
+        self.with_status(status)
+            .with_header(
+                KnownHeaderName::ContentType,
+                PROBLEM_DETAILS_JSON_MEDIA_TYPE,
+            )
+            .with_json(&ProblemDocument {
+                // Per RFC 7807, "about:blank" indicates that the problem has no additional
+                // semantics beyond that of the HTTP status code.
+                type_: "about:blank",
+                title: status.canonical_reason(),
+                status: status as u16,
+                taskid: &task_id.as_ref().map(ToString::to_string),
+            })
+    }
 }
 
 #[cfg(test)]
@@ -242,6 +263,7 @@ mod tests {
                         "text/plain",
                         (),
                         &random(),
+                        None,
                         &request_histogram,
                     )
                     .await
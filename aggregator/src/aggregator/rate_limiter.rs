@@ -0,0 +1,205 @@
+//! Token bucket rate limiting of report uploads.
+
+use crate::config::UploadRateLimitConfig;
+use janus_messages::TaskId;
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// How often idle buckets are removed from the rate limiter.
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Identifies the token bucket that a request is charged against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct BucketKey {
+    task_id: TaskId,
+    client_ip: Option<IpAddr>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+#[derive(Debug)]
+struct State {
+    buckets: HashMap<BucketKey, Bucket>,
+    last_prune: Instant,
+}
+
+/// RateLimiter limits the rate of requests per task, and optionally per client IP address, using a
+/// token bucket for each.
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    rate: f64,
+    burst: f64,
+    per_client_ip: bool,
+    state: Mutex<State>,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(config: &UploadRateLimitConfig) -> Self {
+        Self {
+            rate: f64::from(config.requests_per_second),
+            burst: f64::from(config.burst.max(1)),
+            per_client_ip: config.per_client_ip,
+            state: Mutex::new(State {
+                buckets: HashMap::new(),
+                last_prune: Instant::now(),
+            }),
+        }
+    }
+
+    /// Attempts to take a token for a request in the given task, from the given client. Returns
+    /// `Ok(())` if the request is permitted, or otherwise the duration after which the client may
+    /// retry.
+    pub(crate) fn check(
+        &self,
+        task_id: &TaskId,
+        client_ip: Option<IpAddr>,
+    ) -> Result<(), Duration> {
+        self.check_at(task_id, client_ip, Instant::now())
+    }
+
+    fn check_at(
+        &self,
+        task_id: &TaskId,
+        client_ip: Option<IpAddr>,
+        now: Instant,
+    ) -> Result<(), Duration> {
+        let key = BucketKey {
+            task_id: *task_id,
+            client_ip: if self.per_client_ip { client_ip } else { None },
+        };
+
+        // Unwrap safety: the lock is only poisoned if a thread panicked while holding it, which
+        // none of the code below can do.
+        let mut state = self.state.lock().unwrap();
+        if now.saturating_duration_since(state.last_prune) >= PRUNE_INTERVAL {
+            // Buckets which would have refilled completely are indistinguishable from new ones, so
+            // they can be forgotten.
+            let (rate, burst) = (self.rate, self.burst);
+            state
+                .buckets
+                .retain(|_, bucket| Self::refilled_tokens(rate, burst, bucket, now) < burst);
+            state.last_prune = now;
+        }
+
+        let bucket = state.buckets.entry(key).or_insert(Bucket {
+            tokens: self.burst,
+            last_refill: now,
+        });
+        bucket.tokens = Self::refilled_tokens(self.rate, self.burst, bucket, now);
+        bucket.last_refill = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else if self.rate > 0.0 {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / self.rate))
+        } else {
+            Err(Duration::MAX)
+        }
+    }
+
+    fn refilled_tokens(rate: f64, burst: f64, bucket: &Bucket, now: Instant) -> f64 {
+        let elapsed = now.saturating_duration_since(bucket.last_refill);
+        (bucket.tokens + elapsed.as_secs_f64() * rate).min(burst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RateLimiter, PRUNE_INTERVAL};
+    use crate::config::UploadRateLimitConfig;
+    use janus_messages::TaskId;
+    use rand::random;
+    use std::{
+        net::{IpAddr, Ipv4Addr},
+        time::{Duration, Instant},
+    };
+
+    const CLIENT_1: Option<IpAddr> = Some(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)));
+    const CLIENT_2: Option<IpAddr> = Some(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 2)));
+
+    #[test]
+    fn burst_then_refill() {
+        let rate_limiter = RateLimiter::new(&UploadRateLimitConfig {
+            requests_per_second: 2,
+            burst: 3,
+            per_client_ip: false,
+        });
+        let task_id = random();
+        let start = Instant::now();
+
+        // The full burst is available immediately, from any client.
+        rate_limiter.check_at(&task_id, CLIENT_1, start).unwrap();
+        rate_limiter.check_at(&task_id, CLIENT_2, start).unwrap();
+        rate_limiter.check_at(&task_id, None, start).unwrap();
+        assert_eq!(
+            rate_limiter.check_at(&task_id, CLIENT_1, start),
+            Err(Duration::from_millis(500))
+        );
+
+        // Other tasks are limited separately.
+        rate_limiter.check_at(&random(), CLIENT_1, start).unwrap();
+
+        // Tokens are replenished at the configured rate.
+        let later = start + Duration::from_millis(500);
+        rate_limiter.check_at(&task_id, CLIENT_1, later).unwrap();
+        rate_limiter
+            .check_at(&task_id, CLIENT_1, later)
+            .unwrap_err();
+
+        // Tokens never accumulate beyond the burst size.
+        let much_later = later + Duration::from_secs(3600);
+        for _ in 0..3 {
+            rate_limiter
+                .check_at(&task_id, CLIENT_1, much_later)
+                .unwrap();
+        }
+        rate_limiter
+            .check_at(&task_id, CLIENT_1, much_later)
+            .unwrap_err();
+    }
+
+    #[test]
+    fn per_client_ip() {
+        let rate_limiter = RateLimiter::new(&UploadRateLimitConfig {
+            requests_per_second: 1,
+            burst: 1,
+            per_client_ip: true,
+        });
+        let task_id: TaskId = random();
+        let now = Instant::now();
+
+        rate_limiter.check_at(&task_id, CLIENT_1, now).unwrap();
+        rate_limiter.check_at(&task_id, CLIENT_1, now).unwrap_err();
+        rate_limiter.check_at(&task_id, CLIENT_2, now).unwrap();
+        rate_limiter.check_at(&task_id, CLIENT_2, now).unwrap_err();
+    }
+
+    #[test]
+    fn prune_idle_buckets() {
+        let rate_limiter = RateLimiter::new(&UploadRateLimitConfig {
+            requests_per_second: 1,
+            burst: 2,
+            per_client_ip: true,
+        });
+        let (task_id, start) = (random(), Instant::now());
+
+        rate_limiter.check_at(&task_id, CLIENT_1, start).unwrap();
+        rate_limiter.check_at(&task_id, CLIENT_2, start).unwrap();
+        assert_eq!(rate_limiter.state.lock().unwrap().buckets.len(), 2);
+
+        // After the prune interval, only the bucket in use remains.
+        rate_limiter
+            .check_at(&task_id, CLIENT_1, start + PRUNE_INTERVAL)
+            .unwrap();
+        assert_eq!(rate_limiter.state.lock().unwrap().buckets.len(), 1);
+    }
+}
@@ -1,7 +1,11 @@
 use crate::aggregator::{query_type::UploadableQueryType, Error};
 use async_trait::async_trait;
 use futures::future::join_all;
-use janus_aggregator_core::datastore::{self, models::LeaderStoredReport, Datastore, Transaction};
+use janus_aggregator_core::datastore::{
+    self,
+    models::{EncodedLeaderStoredReport, LeaderStoredReport},
+    Datastore, Transaction,
+};
 use janus_core::time::Clock;
 use prio::vdaf;
 use std::{fmt::Debug, marker::PhantomData, mem::replace, sync::Arc, time::Duration};
@@ -120,13 +124,42 @@ impl<C: Clock> ReportWriteBatcher<C> {
         // Check preconditions.
         assert_eq!(report_writers.len(), result_txs.len());
 
-        // Run all report writes concurrently.
+        // Validate all reports concurrently, then write the valid ones with a single statement.
         let report_writers = Arc::new(report_writers);
         let rslts = ds
             .run_tx_with_name("upload", |tx| {
                 let report_writers = Arc::clone(&report_writers);
                 Box::pin(async move {
-                    Ok(join_all(report_writers.iter().map(|rw| rw.write_report(tx))).await)
+                    let mut rslts =
+                        join_all(report_writers.iter().map(|rw| rw.validate_report(tx))).await;
+                    let (indices, reports): (Vec<_>, Vec<_>) = rslts
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, rslt)| rslt.is_ok())
+                        .map(|(index, _)| (index, report_writers[index].report()))
+                        .unzip();
+
+                    let write_rslts = tx.put_client_reports(&reports).await?;
+                    for ((index, report), write_rslt) in
+                        indices.into_iter().zip(reports).zip(write_rslts)
+                    {
+                        rslts[index] = match write_rslt {
+                            // Reject reports whose report IDs have been seen before.
+                            // https://datatracker.ietf.org/doc/html/draft-ietf-ppm-dap-03#section-4.3.2-16
+                            Err(datastore::Error::MutationTargetAlreadyExists) => {
+                                Err(datastore::Error::User(
+                                    Error::ReportRejected(
+                                        *report.task_id(),
+                                        *report.metadata().id(),
+                                        *report.metadata().time(),
+                                    )
+                                    .into(),
+                                ))
+                            }
+                            rslt => rslt,
+                        };
+                    }
+                    Ok(rslts)
                 })
             })
             .await;
@@ -163,7 +196,11 @@ impl<C: Clock> ReportWriteBatcher<C> {
 
 #[async_trait]
 pub trait ReportWriter<C: Clock>: Debug + Send + Sync {
-    async fn write_report(&self, tx: &Transaction<C>) -> Result<(), datastore::Error>;
+    /// Checks whether the report may be written, returning an error if it must be rejected.
+    async fn validate_report(&self, tx: &Transaction<C>) -> Result<(), datastore::Error>;
+
+    /// Returns the report to be written.
+    fn report(&self) -> &EncodedLeaderStoredReport;
 }
 
 #[derive(Debug)]
@@ -178,6 +215,7 @@ where
 {
     vdaf: Arc<A>,
     report: LeaderStoredReport<SEED_SIZE, A>,
+    encoded_report: EncodedLeaderStoredReport,
     _phantom_q: PhantomData<Q>,
 }
 
@@ -193,6 +231,7 @@ where
     pub fn new(vdaf: Arc<A>, report: LeaderStoredReport<SEED_SIZE, A>) -> Self {
         Self {
             vdaf,
+            encoded_report: EncodedLeaderStoredReport::from(&report),
             report,
             _phantom_q: PhantomData::<Q>,
         }
@@ -210,28 +249,11 @@ where
     C: Clock,
     Q: UploadableQueryType,
 {
-    async fn write_report(&self, tx: &Transaction<C>) -> Result<(), datastore::Error> {
-        Q::validate_uploaded_report(tx, self.vdaf.as_ref(), &self.report).await?;
+    async fn validate_report(&self, tx: &Transaction<C>) -> Result<(), datastore::Error> {
+        Q::validate_uploaded_report(tx, self.vdaf.as_ref(), &self.report).await
+    }
 
-        // Store the report.
-        match tx
-            .put_client_report::<SEED_SIZE, A>(&self.vdaf, &self.report)
-            .await
-        {
-            Ok(()) => Ok(()),
-
-            // Reject reports whose report IDs have been seen before.
-            // https://datatracker.ietf.org/doc/html/draft-ietf-ppm-dap-03#section-4.3.2-16
-            Err(datastore::Error::MutationTargetAlreadyExists) => Err(datastore::Error::User(
-                Error::ReportRejected(
-                    *self.report.task_id(),
-                    *self.report.metadata().id(),
-                    *self.report.metadata().time(),
-                )
-                .into(),
-            )),
-
-            err => err,
-        }
+    fn report(&self) -> &EncodedLeaderStoredReport {
+        &self.encoded_report
     }
 }
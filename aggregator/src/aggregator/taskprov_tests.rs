@@ -1,11 +1,12 @@
 use crate::{
     aggregator::{
+        error::OptOutReason,
         http_handlers::{
             aggregator_handler,
             test_util::{decode_response_body, take_problem_details},
         },
         tests::generate_helper_report_share,
-        Config,
+        Aggregator, Config, Error, TaskprovRequester,
     },
     config::TaskprovConfig,
 };
@@ -21,7 +22,7 @@ use janus_aggregator_core::{
         Datastore,
     },
     task::{QueryType, Task},
-    taskprov::{test_util::PeerAggregatorBuilder, PeerAggregator},
+    taskprov::{test_util::PeerAggregatorBuilder, PeerAggregator, PolicyViolation, TaskprovPolicy},
     test_util::noop_meter,
 };
 use janus_core::{
@@ -44,8 +45,9 @@ use janus_messages::{
     },
     AggregateShare as AggregateShareMessage, AggregateShareAad, AggregateShareReq,
     AggregationJobContinueReq, AggregationJobId, AggregationJobInitializeReq, AggregationJobResp,
-    AggregationJobRound, BatchSelector, Duration, Interval, PartialBatchSelector, PrepareStep,
-    PrepareStepResult, ReportIdChecksum, ReportMetadata, ReportShare, Role, TaskId, Time,
+    AggregationJobRound, BatchSelector, Duration, HpkeCiphertext, HpkeConfigId, Interval,
+    PartialBatchSelector, PrepareStep, PrepareStepResult, Report, ReportIdChecksum, ReportMetadata,
+    ReportShare, Role, TaskId, Time,
 };
 use prio::{
     field::Field64,
@@ -84,6 +86,10 @@ pub struct TaskprovTestCase {
 }
 
 async fn setup_taskprov_test() -> TaskprovTestCase {
+    setup_taskprov_test_with_policy(TaskprovPolicy::default()).await
+}
+
+async fn setup_taskprov_test_with_policy(policy: TaskprovPolicy) -> TaskprovTestCase {
     install_test_trace_subscriber();
 
     let clock = MockClock::default();
@@ -96,6 +102,7 @@ async fn setup_taskprov_test() -> TaskprovTestCase {
         .with_endpoint(url::Url::parse("https://leader.example.com/").unwrap())
         .with_role(Role::Leader)
         .with_collector_hpke_config(collector_hpke_keypair.config().clone())
+        .with_policy(policy)
         .build();
 
     datastore
@@ -118,7 +125,10 @@ async fn setup_taskprov_test() -> TaskprovTestCase {
         clock.clone(),
         &noop_meter(),
         Config {
-            taskprov_config: TaskprovConfig { enabled: true },
+            taskprov_config: TaskprovConfig {
+                enabled: true,
+                opt_in_on_upload: false,
+            },
             ..Default::default()
         },
     )
@@ -170,10 +180,11 @@ async fn setup_taskprov_test() -> TaskprovTestCase {
         Vec::from([vdaf_verify_key.clone()]),
         max_batch_query_count as u64,
         Some(task_expiration),
-        peer_aggregator.report_expiry_age().copied(),
         min_batch_size as u64,
         Duration::from_seconds(1),
-        Duration::from_seconds(1),
+        janus_aggregator_core::task::DpConfig::None,
+        &task_config,
+        &peer_aggregator,
     )
     .unwrap();
 
@@ -370,6 +381,102 @@ async fn taskprov_opt_out_task_expired() {
     );
 }
 
+#[tokio::test]
+async fn taskprov_opt_out_policy_violation() {
+    // The task config requests a min_batch_size of 1.
+    let test = setup_taskprov_test_with_policy(TaskprovPolicy {
+        minimum_min_batch_size: Some(100),
+        ..Default::default()
+    })
+    .await;
+
+    let batch_id = random();
+    let request = AggregationJobInitializeReq::new(
+        ().get_encoded(),
+        PartialBatchSelector::new_fixed_size(batch_id),
+        Vec::from([test.report_share.clone()]),
+    );
+
+    let aggregation_job_id: AggregationJobId = random();
+
+    let auth = test
+        .peer_aggregator
+        .primary_aggregator_auth_token()
+        .request_authentication();
+
+    let mut test_conn = put(test
+        .task
+        .aggregation_job_uri(&aggregation_job_id)
+        .unwrap()
+        .path())
+    .with_request_header(auth.0, auth.1)
+    .with_request_header(
+        KnownHeaderName::ContentType,
+        AggregationJobInitializeReq::<FixedSize>::MEDIA_TYPE,
+    )
+    .with_request_header(
+        TASKPROV_HEADER,
+        URL_SAFE_NO_PAD.encode(test.task_config.get_encoded()),
+    )
+    .with_request_body(request.get_encoded())
+    .run_async(&test.handler)
+    .await;
+    assert_eq!(test_conn.status(), Some(Status::BadRequest));
+    assert_eq!(
+        take_problem_details(&mut test_conn).await,
+        json!({
+            "status": Status::BadRequest as u16,
+            "type": "urn:ietf:params:ppm:dap:error:invalidTask",
+            "title": "Aggregator has opted out of the indicated task.",
+            "taskid": format!("{}", test.task_id),
+        })
+    );
+
+    // The task should not have been provisioned.
+    let task_id = test.task_id;
+    assert_eq!(
+        test.datastore
+            .run_tx(|tx| Box::pin(async move { tx.get_task(&task_id).await }))
+            .await
+            .unwrap(),
+        None
+    );
+
+    // The opt-out reason identifies the violated limit.
+    let aggregator = Aggregator::new(
+        Arc::clone(&test.datastore),
+        test.clock.clone(),
+        &noop_meter(),
+        Config {
+            taskprov_config: TaskprovConfig {
+                enabled: true,
+                opt_in_on_upload: false,
+            },
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    assert_matches!(
+        aggregator
+            .taskprov_opt_in(
+                TaskprovRequester::Leader(Some(
+                    test.peer_aggregator.primary_aggregator_auth_token()
+                )),
+                &test.task_id,
+                &test.task_config,
+            )
+            .await,
+        Err(Error::InvalidTask(
+            _,
+            OptOutReason::PolicyViolation(PolicyViolation::MinBatchSizeTooSmall {
+                actual: 1,
+                minimum: 100,
+            })
+        ))
+    );
+}
+
 #[tokio::test]
 async fn taskprov_opt_out_mismatched_task_id() {
     let test = setup_taskprov_test().await;
@@ -1058,3 +1165,105 @@ async fn end_to_end() {
     .unwrap();
     assert_eq!(plaintext, test.transcript.aggregate_shares[1].get_encoded());
 }
+
+#[tokio::test]
+async fn taskprov_leader_opt_in_on_upload() {
+    install_test_trace_subscriber();
+
+    for opt_in_on_upload in [false, true] {
+        let clock = MockClock::default();
+        let ephemeral_datastore = ephemeral_datastore().await;
+        let datastore = Arc::new(ephemeral_datastore.datastore(clock.clone()).await);
+
+        let global_hpke_key = generate_test_hpke_config_and_private_key();
+        let peer_aggregator = PeerAggregatorBuilder::new()
+            .with_endpoint(url::Url::parse("https://helper.example.com/").unwrap())
+            .with_role(Role::Helper)
+            .build();
+
+        datastore
+            .run_tx(|tx| {
+                let global_hpke_key = global_hpke_key.clone();
+                let peer_aggregator = peer_aggregator.clone();
+                Box::pin(async move {
+                    tx.put_global_hpke_keypair(&global_hpke_key).await.unwrap();
+                    tx.put_taskprov_peer_aggregator(&peer_aggregator)
+                        .await
+                        .unwrap();
+                    Ok(())
+                })
+            })
+            .await
+            .unwrap();
+
+        let handler = aggregator_handler(
+            Arc::clone(&datastore),
+            clock.clone(),
+            &noop_meter(),
+            Config {
+                taskprov_config: TaskprovConfig {
+                    enabled: true,
+                    opt_in_on_upload,
+                },
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+        let task_config = TaskConfig::new(
+            Vec::from("foobar".as_bytes()),
+            Vec::from([
+                "https://leader.example.com/".as_bytes().try_into().unwrap(),
+                "https://helper.example.com/".as_bytes().try_into().unwrap(),
+            ]),
+            QueryConfig::new(Duration::from_seconds(1), 1, 1, TaskprovQuery::TimeInterval),
+            clock.now().add(&Duration::from_hours(24).unwrap()).unwrap(),
+            VdafConfig::new(DpConfig::new(DpMechanism::None), VdafType::Prio3Count).unwrap(),
+        )
+        .unwrap();
+        let task_id =
+            TaskId::try_from(digest(&SHA256, &task_config.get_encoded()).as_ref()).unwrap();
+
+        // The report's contents don't matter, as the task must be provisioned before they are
+        // looked at.
+        let report = Report::new(
+            ReportMetadata::new(random(), clock.now()),
+            Vec::new(),
+            HpkeCiphertext::new(*global_hpke_key.config().id(), Vec::new(), Vec::new()),
+            HpkeCiphertext::new(HpkeConfigId::from(0), Vec::new(), Vec::new()),
+        );
+
+        let mut test_conn = put(&format!("/tasks/{task_id}/reports"))
+            .with_request_header(KnownHeaderName::ContentType, Report::MEDIA_TYPE)
+            .with_request_header(
+                TASKPROV_HEADER,
+                URL_SAFE_NO_PAD.encode(task_config.get_encoded()),
+            )
+            .with_request_body(report.get_encoded())
+            .run_async(&handler)
+            .await;
+
+        let task = datastore
+            .run_tx(|tx| Box::pin(async move { tx.get_task(&task_id).await }))
+            .await
+            .unwrap();
+        if opt_in_on_upload {
+            let task = task.unwrap();
+            assert_eq!(task.role(), &Role::Leader);
+        } else {
+            // A header-only upload must not provision a task.
+            assert_eq!(test_conn.status(), Some(Status::BadRequest));
+            assert_eq!(
+                take_problem_details(&mut test_conn).await,
+                json!({
+                    "status": Status::BadRequest as u16,
+                    "type": "urn:ietf:params:ppm:dap:error:unrecognizedTask",
+                    "title": "An endpoint received a message with an unknown task ID.",
+                    "taskid": format!("{task_id}"),
+                })
+            );
+            assert!(task.is_none());
+        }
+    }
+}
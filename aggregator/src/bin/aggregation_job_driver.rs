@@ -1,7 +1,7 @@
 use anyhow::Context;
 use clap::Parser;
 use janus_aggregator::{
-    aggregator::aggregation_job_driver::AggregationJobDriver,
+    aggregator::{aggregation_job_driver::AggregationJobDriver, compute_pool::ComputePool},
     binary_utils::{
         janus_main, job_driver::JobDriver, setup_signal_handler, BinaryOptions, CommonBinaryOptions,
     },
@@ -30,6 +30,7 @@ async fn main() -> anyhow::Result<()> {
                 .context("couldn't create HTTP client")?,
             &ctx.meter,
             ctx.config.batch_aggregation_shard_count,
+            ComputePool::new(&ctx.meter, ctx.config.vdaf_preparation_threads)?,
         ));
         let lease_duration =
             Duration::from_secs(ctx.config.job_driver_config.worker_lease_duration_secs);
@@ -101,6 +102,7 @@ impl BinaryOptions for Options {
 /// worker_lease_clock_skew_allowance_secs: 60
 /// maximum_attempts_before_failure: 5
 /// batch_aggregation_shard_count: 32
+/// vdaf_preparation_threads: 8
 /// taskprov_config:
 ///   enabled: false
 /// "#;
@@ -120,6 +122,11 @@ struct Config {
     /// will reduce the amount of database contention during leader aggregation, while increasing
     /// the cost of collection.
     batch_aggregation_shard_count: u64,
+
+    /// Defines the number of threads used for VDAF preparation. If unspecified, one thread per
+    /// available CPU is used.
+    #[serde(default)]
+    vdaf_preparation_threads: Option<usize>,
 }
 
 impl BinaryConfig for Config {
@@ -167,6 +174,7 @@ mod tests {
             },
             batch_aggregation_shard_count: 32,
             taskprov_config: TaskprovConfig::default(),
+            vdaf_preparation_threads: Some(8),
         })
     }
 
@@ -8,10 +8,14 @@ use janus_aggregator::{
     trace::{install_trace_subscriber, TraceGuards},
 };
 use janus_aggregator_core::{
-    datastore::{self, Datastore},
+    datastore::{self, models::ImportedHpkeKeypair, Datastore},
     task::{SerializedTask, Task},
 };
-use janus_core::time::{Clock, RealClock};
+use janus_core::{
+    hpke::HpkeKeypair,
+    time::{Clock, RealClock},
+};
+use janus_messages::Duration;
 use k8s_openapi::api::core::v1::Secret;
 use kube::api::{ObjectMeta, PostParams};
 use opentelemetry::global::meter;
@@ -75,6 +79,35 @@ enum Command {
         #[clap(flatten)]
         kubernetes_secret_options: KubernetesSecretOptions,
     },
+
+    /// Import an externally generated global HPKE keypair into the datastore. The key is created
+    /// in the pending state.
+    ImportGlobalHpkeKey {
+        #[clap(flatten)]
+        kubernetes_secret_options: KubernetesSecretOptions,
+
+        /// A YAML file containing the HPKE configuration (`config`) and either its private key
+        /// (`private_key`) or its private key wrapped to a transport key (`wrapped_private_key`).
+        key_file: PathBuf,
+
+        /// A YAML file containing the HPKE keypair of the transport key that the private key was
+        /// wrapped to. If omitted, a wrapped private key is unwrapped with the global HPKE key it
+        /// was wrapped to.
+        #[clap(long)]
+        transport_key_file: Option<PathBuf>,
+    },
+
+    /// Convert the client_reports & report_aggregations tables into tables partitioned by client
+    /// timestamp, so that the garbage collector can delete expired rows a partition at a time.
+    /// Both tables are locked for the duration of the conversion.
+    PartitionReportTables {
+        #[clap(flatten)]
+        kubernetes_secret_options: KubernetesSecretOptions,
+
+        /// The width of the range of client timestamps covered by each partition, in seconds.
+        #[clap(long)]
+        partition_size_s: u64,
+    },
 }
 
 impl Command {
@@ -135,6 +168,50 @@ impl Command {
                 )
                 .await
             }
+
+            Command::ImportGlobalHpkeKey {
+                kubernetes_secret_options,
+                key_file,
+                transport_key_file,
+            } => {
+                let datastore = datastore_from_opts(
+                    kubernetes_secret_options,
+                    command_line_options,
+                    config_file,
+                    &kube_client,
+                )
+                .await?;
+
+                import_global_hpke_key(
+                    &datastore,
+                    key_file,
+                    transport_key_file.as_deref(),
+                    command_line_options.dry_run,
+                )
+                .await?;
+
+                Ok(())
+            }
+
+            Command::PartitionReportTables {
+                kubernetes_secret_options,
+                partition_size_s,
+            } => {
+                let datastore = datastore_from_opts(
+                    kubernetes_secret_options,
+                    command_line_options,
+                    config_file,
+                    &kube_client,
+                )
+                .await?;
+
+                partition_report_tables(
+                    &datastore,
+                    &Duration::from_seconds(*partition_size_s),
+                    command_line_options.dry_run,
+                )
+                .await
+            }
         }
     }
 }
@@ -214,6 +291,95 @@ async fn provision_tasks<C: Clock>(
     Ok(written_tasks)
 }
 
+async fn import_global_hpke_key<C: Clock>(
+    datastore: &Datastore<C>,
+    key_file: &Path,
+    transport_key_file: Option<&Path>,
+    dry_run: bool,
+) -> Result<HpkeKeypair> {
+    let imported_keypair: ImportedHpkeKeypair = {
+        let key_file_contents = fs::read_to_string(key_file)
+            .await
+            .with_context(|| format!("couldn't read key file {key_file:?}"))?;
+        serde_yaml::from_str(&key_file_contents)
+            .with_context(|| format!("couldn't parse key file {key_file:?}"))?
+    };
+    let transport_keypair: Option<HpkeKeypair> = match transport_key_file {
+        Some(transport_key_file) => {
+            let transport_key_file_contents = fs::read_to_string(transport_key_file)
+                .await
+                .with_context(|| {
+                    format!("couldn't read transport key file {transport_key_file:?}")
+                })?;
+            Some(
+                serde_yaml::from_str(&transport_key_file_contents).with_context(|| {
+                    format!("couldn't parse transport key file {transport_key_file:?}")
+                })?,
+            )
+        }
+        None => None,
+    };
+    let config_id = *imported_keypair.config().id();
+
+    if dry_run {
+        info!(%config_id, "DRY RUN: Checking global HPKE key, but not writing it");
+    } else {
+        info!(%config_id, "Importing global HPKE key");
+    }
+    datastore
+        .run_tx_with_name("import_global_hpke_key", |tx| {
+            let imported_keypair = imported_keypair.clone();
+            let transport_keypair = transport_keypair.clone();
+            Box::pin(async move {
+                if dry_run {
+                    tx.check_global_hpke_keypair_import(
+                        &imported_keypair,
+                        transport_keypair.as_ref(),
+                    )
+                    .await
+                } else {
+                    tx.import_global_hpke_keypair(&imported_keypair, transport_keypair.as_ref())
+                        .await
+                }
+            })
+        })
+        .await
+        .map_err(|err| match err {
+            datastore::Error::MutationTargetAlreadyExists => {
+                anyhow!("a global HPKE key with ID {config_id} already exists")
+            }
+            datastore::Error::InvalidParameter(message) => {
+                anyhow!("couldn't import global HPKE key: {message}")
+            }
+            err => anyhow::Error::from(err).context("couldn't write global HPKE key"),
+        })
+}
+
+async fn partition_report_tables<C: Clock>(
+    datastore: &Datastore<C>,
+    partition_size: &Duration,
+    dry_run: bool,
+) -> Result<()> {
+    if dry_run {
+        info!(?partition_size, "DRY RUN: Not partitioning report tables");
+        return Ok(());
+    }
+
+    info!(?partition_size, "Partitioning report tables");
+    datastore
+        .run_tx_with_name("partition_report_tables", |tx| {
+            let partition_size = *partition_size;
+            Box::pin(async move { tx.partition_report_tables(&partition_size).await })
+        })
+        .await
+        .map_err(|err| match err {
+            datastore::Error::MutationTargetAlreadyExists => {
+                anyhow!("report tables are already partitioned")
+            }
+            err => anyhow::Error::from(err).context("couldn't partition report tables"),
+        })
+}
+
 async fn fetch_datastore_keys(
     kube_client: &LazyKubeClient,
     namespace: &str,
@@ -463,22 +629,31 @@ mod tests {
         config::CommonConfig,
     };
     use janus_aggregator_core::{
-        datastore::{test_util::ephemeral_datastore, Datastore},
+        datastore::{
+            models::{GlobalHpkeKeypair, HpkeKeyState},
+            test_util::ephemeral_datastore,
+            Datastore,
+        },
         task::{test_util::TaskBuilder, QueryType, Task},
     };
     use janus_core::{
+        hpke::{
+            test_util::generate_test_hpke_config_and_private_key_with_id, wrap_private_key,
+            HpkeKeypair,
+        },
         task::VdafInstance,
         test_util::{kubernetes, roundtrip_encoding},
         time::RealClock,
     };
-    use janus_messages::{Role, TaskId};
+    use janus_messages::{Duration, Role, TaskId};
     use ring::aead::{UnboundKey, AES_128_GCM};
+    use serde::Serialize;
     use std::{
         collections::HashMap,
         io::Write,
         net::{Ipv4Addr, SocketAddr},
     };
-    use tempfile::NamedTempFile;
+    use tempfile::{NamedTempFile, TempPath};
 
     #[test]
     fn verify_app() {
@@ -812,6 +987,140 @@ mod tests {
         );
     }
 
+    fn write_temp_yaml<T: Serialize>(value: &T) -> TempPath {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(serde_yaml::to_string(value).unwrap().as_bytes())
+            .unwrap();
+        file.into_temp_path()
+    }
+
+    async fn get_global_hpke_keypairs(ds: &Datastore<RealClock>) -> Vec<GlobalHpkeKeypair> {
+        ds.run_tx(|tx| Box::pin(async move { tx.get_global_hpke_keypairs().await }))
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn import_global_hpke_key() {
+        let ephemeral_datastore = ephemeral_datastore().await;
+        let ds = ephemeral_datastore.datastore(RealClock::default()).await;
+
+        let keypair = generate_test_hpke_config_and_private_key_with_id(10);
+        let key_file = write_temp_yaml(&keypair);
+
+        // Dry run: nothing is written.
+        super::import_global_hpke_key(&ds, &key_file, None, true)
+            .await
+            .unwrap();
+        assert!(get_global_hpke_keypairs(&ds).await.is_empty());
+
+        let imported_keypair = super::import_global_hpke_key(&ds, &key_file, None, false)
+            .await
+            .unwrap();
+        assert_eq!(imported_keypair, keypair);
+
+        let got_keypairs = get_global_hpke_keypairs(&ds).await;
+        assert_eq!(got_keypairs.len(), 1);
+        assert_eq!(got_keypairs[0].hpke_keypair(), &keypair);
+        assert_eq!(got_keypairs[0].state(), &HpkeKeyState::Pending);
+
+        // A key with a colliding ID is rejected.
+        let colliding_key_file =
+            write_temp_yaml(&generate_test_hpke_config_and_private_key_with_id(10));
+        super::import_global_hpke_key(&ds, &colliding_key_file, None, false)
+            .await
+            .unwrap_err();
+
+        // A private key that doesn't match its configuration is rejected.
+        let mismatched_key_file = write_temp_yaml(&HpkeKeypair::new(
+            generate_test_hpke_config_and_private_key_with_id(11)
+                .config()
+                .clone(),
+            keypair.private_key().clone(),
+        ));
+        super::import_global_hpke_key(&ds, &mismatched_key_file, None, false)
+            .await
+            .unwrap_err();
+
+        assert_eq!(get_global_hpke_keypairs(&ds).await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn import_wrapped_global_hpke_key() {
+        let ephemeral_datastore = ephemeral_datastore().await;
+        let ds = ephemeral_datastore.datastore(RealClock::default()).await;
+
+        let transport_keypair = generate_test_hpke_config_and_private_key_with_id(200);
+        let transport_key_file = write_temp_yaml(&transport_keypair);
+        let keypair = generate_test_hpke_config_and_private_key_with_id(20);
+        let key_file = write_temp_yaml(&serde_yaml::Mapping::from_iter([
+            (
+                "config".into(),
+                serde_yaml::to_value(keypair.config()).unwrap(),
+            ),
+            (
+                "wrapped_private_key".into(),
+                serde_yaml::to_value(
+                    wrap_private_key(transport_keypair.config(), &keypair).unwrap(),
+                )
+                .unwrap(),
+            ),
+        ]));
+
+        // The private key was not wrapped to a global HPKE key, so the transport key is required to
+        // unwrap it.
+        super::import_global_hpke_key(&ds, &key_file, None, false)
+            .await
+            .unwrap_err();
+
+        // The wrong transport key can't unwrap the private key.
+        let other_transport_key_file =
+            write_temp_yaml(&generate_test_hpke_config_and_private_key_with_id(200));
+        super::import_global_hpke_key(&ds, &key_file, Some(&other_transport_key_file), false)
+            .await
+            .unwrap_err();
+        assert!(get_global_hpke_keypairs(&ds).await.is_empty());
+
+        super::import_global_hpke_key(&ds, &key_file, Some(&transport_key_file), false)
+            .await
+            .unwrap();
+
+        let got_keypairs = get_global_hpke_keypairs(&ds).await;
+        assert_eq!(got_keypairs.len(), 1);
+        assert_eq!(got_keypairs[0].hpke_keypair(), &keypair);
+    }
+
+    #[tokio::test]
+    async fn partition_report_tables() {
+        let ephemeral_datastore = ephemeral_datastore().await;
+        let ds = ephemeral_datastore.datastore(RealClock::default()).await;
+        let get_report_partition_size =
+            || ds.run_tx(|tx| Box::pin(async move { tx.get_report_partition_size().await }));
+
+        // Dry run: the tables are not partitioned.
+        super::partition_report_tables(&ds, &Duration::from_seconds(3600), true)
+            .await
+            .unwrap();
+        assert_eq!(get_report_partition_size().await.unwrap(), None);
+
+        super::partition_report_tables(&ds, &Duration::from_seconds(3600), false)
+            .await
+            .unwrap();
+        assert_eq!(
+            get_report_partition_size().await.unwrap(),
+            Some(Duration::from_seconds(3600))
+        );
+
+        // The tables can only be partitioned once.
+        super::partition_report_tables(&ds, &Duration::from_seconds(60), false)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            get_report_partition_size().await.unwrap(),
+            Some(Duration::from_seconds(3600))
+        );
+    }
+
     #[tokio::test]
     async fn create_datastore_key() {
         let k8s_cluster = kubernetes::EphemeralCluster::create();
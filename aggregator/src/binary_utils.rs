@@ -13,7 +13,7 @@ use base64::{engine::general_purpose::STANDARD_NO_PAD, Engine};
 use clap::Parser;
 use deadpool::managed::TimeoutType;
 use deadpool_postgres::{Manager, Pool, PoolError, Runtime, Timeouts};
-use futures::StreamExt;
+use futures::{future::try_join_all, StreamExt};
 use janus_aggregator_core::datastore::{Crypter, Datastore};
 use janus_core::time::Clock;
 use opentelemetry::metrics::Meter;
@@ -35,6 +35,7 @@ use trillium::{Handler, Headers, Info, Init};
 use trillium_head::Head;
 use trillium_router::Router;
 use trillium_tokio::Stopper;
+use url::Url;
 
 /// Reads, parses, and returns the config referenced by the given options, or None if no config file
 /// path was set.
@@ -67,13 +68,31 @@ pub fn read_config<Config: BinaryConfig>(options: &CommonBinaryOptions) -> Resul
 /// Connects to a database, given a config. `db_password` is mutually exclusive with the database
 /// password specified in the connection URL in `db_config`.
 pub async fn database_pool(db_config: &DbConfig, db_password: Option<&str>) -> Result<Pool> {
-    let mut database_config = tokio_postgres::Config::from_str(db_config.url.as_str())
-        .with_context(|| {
-            format!(
-                "couldn't parse database connect string: {:?}",
-                db_config.url
-            )
-        })?;
+    database_pool_for_url(&db_config.url, db_config, db_password).await
+}
+
+/// Connects to each of the read replicas of a database, given a config. `db_password` is mutually
+/// exclusive with the database passwords specified in the replicas' connection URLs.
+pub async fn read_replica_database_pools(
+    db_config: &DbConfig,
+    db_password: Option<&str>,
+) -> Result<Vec<Pool>> {
+    try_join_all(
+        db_config
+            .read_replica_urls
+            .iter()
+            .map(|url| database_pool_for_url(url, db_config, db_password)),
+    )
+    .await
+}
+
+async fn database_pool_for_url(
+    url: &Url,
+    db_config: &DbConfig,
+    db_password: Option<&str>,
+) -> Result<Pool> {
+    let mut database_config = tokio_postgres::Config::from_str(url.as_str())
+        .with_context(|| format!("couldn't parse database connect string: {url:?}"))?;
     if database_config.get_password().is_some() && db_password.is_some() {
         return Err(anyhow!(
             "database config & password override are both specified"
@@ -294,6 +313,12 @@ where
     )
     .await
     .context("couldn't create database connection pool")?;
+    let read_replica_pools = read_replica_database_pools(
+        &config.common_config().database,
+        options.common_options().database_password.as_deref(),
+    )
+    .await
+    .context("couldn't create database read replica connection pools")?;
     let datastore = datastore(
         pool,
         clock.clone(),
@@ -302,7 +327,8 @@ where
         config.common_config().database.check_schema_version,
     )
     .await
-    .context("couldn't create datastore")?;
+    .context("couldn't create datastore")?
+    .with_read_replicas(read_replica_pools);
 
     let health_check_listen_address = config.common_config().health_check_listen_address;
     let healthz_task_handle =
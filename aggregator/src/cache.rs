@@ -3,17 +3,19 @@
 use crate::aggregator::Error;
 use janus_aggregator_core::{
     datastore::{models::HpkeKeyState, Datastore},
+    task::TaskInvalidationHook,
     taskprov::PeerAggregator,
 };
 use janus_core::{hpke::HpkeKeypair, time::Clock};
-use janus_messages::{HpkeConfig, HpkeConfigId, Role};
+use janus_messages::{HpkeConfig, HpkeConfigId, Role, TaskId};
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     fmt::Debug,
+    future::Future,
     sync::{Arc, Mutex as StdMutex},
     time::{Duration as StdDuration, Instant},
 };
-use tokio::{spawn, task::JoinHandle, time::sleep};
+use tokio::{spawn, sync::Mutex, task::JoinHandle, time::sleep};
 use tracing::{debug, error};
 use url::Url;
 
@@ -82,7 +84,7 @@ impl GlobalHpkeKeypairCache {
         keypairs: &StdMutex<HpkeKeypairs>,
     ) -> Result<(), Error> {
         let global_keypairs = datastore
-            .run_tx_with_name("refresh_global_hpke_keypairs_cache", |tx| {
+            .run_read_only_tx_with_name("refresh_global_hpke_keypairs_cache", |tx| {
                 Box::pin(async move { tx.get_global_hpke_keypairs().await })
             })
             .await?;
@@ -142,31 +144,511 @@ impl Drop for GlobalHpkeKeypairCache {
     }
 }
 
-/// Caches taskprov [`PeerAggregator`]'s. This cache is never invalidated, so the process needs to
-/// be restarted if there are any changes to peer aggregators.
+/// Caches taskprov [`PeerAggregator`]'s. The cache is periodically refreshed, so that changes to
+/// peer aggregators, such as authentication token rotations, are eventually observed without
+/// restarting the process.
 #[derive(Debug)]
 pub struct PeerAggregatorCache {
-    peers: Vec<PeerAggregator>,
+    // As with the HPKE keypair cache, we use a std::sync::Mutex because we won't hold locks across
+    // `.await` boundaries.
+    peers: Arc<StdMutex<Vec<Arc<PeerAggregator>>>>,
+
+    /// Handle for task responsible for periodically refreshing the cache.
+    refresh_handle: JoinHandle<()>,
 }
 
 impl PeerAggregatorCache {
-    pub async fn new<C: Clock>(datastore: &Datastore<C>) -> Result<Self, Error> {
+    pub const DEFAULT_REFRESH_INTERVAL: StdDuration = StdDuration::from_secs(60);
+
+    pub async fn new<C: Clock>(
+        datastore: Arc<Datastore<C>>,
+        refresh_interval: StdDuration,
+    ) -> Result<Self, Error> {
+        let peers = Arc::new(StdMutex::new(Vec::new()));
+
+        // Initial cache load.
+        Self::refresh_inner(&datastore, &peers).await?;
+
+        // Start refresh task.
+        let refresh_peers = Arc::clone(&peers);
+        let refresh_handle = spawn(async move {
+            loop {
+                sleep(refresh_interval).await;
+
+                let now = Instant::now();
+                let result = Self::refresh_inner(&datastore, &refresh_peers).await;
+                let elapsed = now.elapsed();
+
+                match result {
+                    Ok(_) => debug!(?elapsed, "successfully refreshed peer aggregator cache"),
+                    Err(err) => error!(?err, ?elapsed, "failed to refresh peer aggregator cache"),
+                }
+            }
+        });
+
         Ok(Self {
-            peers: datastore
-                .run_tx_with_name("refresh_peer_aggregators_cache", |tx| {
-                    Box::pin(async move { tx.get_taskprov_peer_aggregators().await })
-                })
-                .await?
-                .into_iter()
-                .collect(),
+            peers,
+            refresh_handle,
         })
     }
 
-    pub fn get(&self, endpoint: &Url, role: &Role) -> Option<&PeerAggregator> {
+    async fn refresh_inner<C: Clock>(
+        datastore: &Datastore<C>,
+        peers: &StdMutex<Vec<Arc<PeerAggregator>>>,
+    ) -> Result<(), Error> {
+        let new_peers = datastore
+            .run_read_only_tx_with_name("refresh_peer_aggregators_cache", |tx| {
+                Box::pin(async move { tx.get_taskprov_peer_aggregators().await })
+            })
+            .await?
+            .into_iter()
+            .map(Arc::new)
+            .collect();
+
+        let mut peers = peers.lock().unwrap();
+        *peers = new_peers;
+        Ok(())
+    }
+
+    #[cfg(feature = "test-util")]
+    pub async fn refresh<C: Clock>(&self, datastore: &Datastore<C>) -> Result<(), Error> {
+        Self::refresh_inner(datastore, &self.peers).await
+    }
+
+    pub fn get(&self, endpoint: &Url, role: &Role) -> Option<Arc<PeerAggregator>> {
         // The peer aggregator table is unlikely to be more than a few entries long (1-2 entries),
         // so a linear search should be fine.
         self.peers
+            .lock()
+            .unwrap()
             .iter()
             .find(|peer| peer.endpoint() == endpoint && peer.role() == role)
+            .cloned()
+    }
+}
+
+impl Drop for PeerAggregatorCache {
+    fn drop(&mut self) {
+        self.refresh_handle.abort()
+    }
+}
+
+/// Caches values derived from tasks, such as task aggregators, keyed by task ID.
+///
+/// Entries expire after a fixed TTL, after which they are reloaded, so that changes made to tasks
+/// by other processes are eventually observed. Once the cache reaches its capacity, the least
+/// recently used entry is evicted. The absence of a task is cached too, for a separate TTL, so
+/// that requests naming unknown tasks don't each query the datastore. Concurrent lookups of a task
+/// that is not cached share a single load.
+pub struct TaskCache<T> {
+    ttl: StdDuration,
+    negative_ttl: StdDuration,
+    capacity: usize,
+    // As in the other caches, we use a std::sync::Mutex because the lock is never held across an
+    // `.await` boundary.
+    state: StdMutex<TaskCacheState<T>>,
+}
+
+struct TaskCacheState<T> {
+    entries: HashMap<TaskId, TaskCacheEntry<T>>,
+    /// Index of cached task IDs by the tick at which they were last used, oldest first.
+    recency: BTreeMap<u64, TaskId>,
+    next_tick: u64,
+    /// Locks serializing loads of each task which is currently being loaded.
+    loads: HashMap<TaskId, Arc<Mutex<()>>>,
+    /// Number of invalidations so far. Loads which race with an invalidation don't cache their
+    /// result, since it may be stale.
+    invalidations: u64,
+}
+
+struct TaskCacheEntry<T> {
+    /// The cached value, or `None` if the task does not exist.
+    value: Option<T>,
+    expires_at: tokio::time::Instant,
+    last_used: u64,
+}
+
+impl<T: Clone> TaskCache<T> {
+    pub fn new(ttl: StdDuration, negative_ttl: StdDuration, capacity: usize) -> Self {
+        Self {
+            ttl,
+            negative_ttl,
+            capacity,
+            state: StdMutex::new(TaskCacheState {
+                entries: HashMap::new(),
+                recency: BTreeMap::new(),
+                next_tick: 0,
+                loads: HashMap::new(),
+                invalidations: 0,
+            }),
+        }
+    }
+
+    /// Returns the cached value for the given task, or calls `load` to obtain it if it is not
+    /// cached. At most one load runs for a given task at a time: concurrent callers wait for it to
+    /// complete and share its result. Errors are returned to the caller but not cached.
+    pub async fn get_or_load<F, Fut>(&self, task_id: &TaskId, load: F) -> Result<Option<T>, Error>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<Option<T>, Error>>,
+    {
+        let load_guard = {
+            let mut state = self.state.lock().unwrap();
+            if let Some(value) = state.get(task_id) {
+                return Ok(value);
+            }
+            LoadGuard {
+                state: &self.state,
+                task_id: *task_id,
+                load_lock: Arc::clone(state.loads.entry(*task_id).or_default()),
+            }
+        };
+        let _load_lock_guard = load_guard.load_lock.lock().await;
+
+        // Check the cache again, since another caller may have loaded the task while we waited.
+        let invalidations = {
+            let mut state = self.state.lock().unwrap();
+            if let Some(value) = state.get(task_id) {
+                return Ok(value);
+            }
+            state.invalidations
+        };
+
+        let result = load().await;
+        if let Ok(value) = &result {
+            let mut state = self.state.lock().unwrap();
+            if state.invalidations == invalidations {
+                let ttl = if value.is_some() {
+                    self.ttl
+                } else {
+                    self.negative_ttl
+                };
+                state.insert(*task_id, value.clone(), ttl, self.capacity);
+            }
+        }
+        result
+    }
+
+    /// Discards the cached value for the given task, if any, including a cached absence of the
+    /// task. The next lookup of the task will load it afresh.
+    pub fn invalidate(&self, task_id: &TaskId) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(entry) = state.entries.remove(task_id) {
+            state.recency.remove(&entry.last_used);
+        }
+        state.invalidations += 1;
+    }
+}
+
+impl<T: Clone> TaskCacheState<T> {
+    /// Returns the unexpired cached value for the given task, marking it as recently used.
+    fn get(&mut self, task_id: &TaskId) -> Option<Option<T>> {
+        let entry = self.entries.get_mut(task_id)?;
+        self.recency.remove(&entry.last_used);
+        if entry.expires_at <= tokio::time::Instant::now() {
+            self.entries.remove(task_id);
+            return None;
+        }
+
+        entry.last_used = self.next_tick;
+        self.recency.insert(self.next_tick, *task_id);
+        self.next_tick += 1;
+        Some(entry.value.clone())
+    }
+
+    fn insert(&mut self, task_id: TaskId, value: Option<T>, ttl: StdDuration, capacity: usize) {
+        if let Some(entry) = self.entries.remove(&task_id) {
+            self.recency.remove(&entry.last_used);
+        }
+        while !self.entries.is_empty() && self.entries.len() >= capacity {
+            // Unwrap safety: every cached entry has a corresponding entry in the recency index.
+            let (_, evicted_task_id) = self.recency.pop_first().unwrap();
+            self.entries.remove(&evicted_task_id);
+        }
+        if capacity == 0 {
+            return;
+        }
+
+        self.entries.insert(
+            task_id,
+            TaskCacheEntry {
+                value,
+                expires_at: tokio::time::Instant::now() + ttl,
+                last_used: self.next_tick,
+            },
+        );
+        self.recency.insert(self.next_tick, task_id);
+        self.next_tick += 1;
+    }
+}
+
+impl<T> TaskCacheState<T> {
+    /// Forgets the lock serializing loads of the given task, once no other caller is waiting on it.
+    fn finish_load(&mut self, task_id: &TaskId, load_lock: &Arc<Mutex<()>>) {
+        // Clones of the lock are only made while the state lock is held, so if the only references
+        // are ours and the map's, nobody else can be waiting on it.
+        if Arc::strong_count(load_lock) == 2
+            && self
+                .loads
+                .get(task_id)
+                .is_some_and(|lock| Arc::ptr_eq(lock, load_lock))
+        {
+            self.loads.remove(task_id);
+        }
+    }
+}
+
+/// A caller's claim on the lock serializing loads of a task. Dropping it forgets the lock once no
+/// other caller is waiting on it, whether the caller finished or its future was dropped mid-load.
+struct LoadGuard<'a, T> {
+    state: &'a StdMutex<TaskCacheState<T>>,
+    task_id: TaskId,
+    load_lock: Arc<Mutex<()>>,
+}
+
+impl<T> Drop for LoadGuard<'_, T> {
+    fn drop(&mut self) {
+        self.state
+            .lock()
+            .unwrap()
+            .finish_load(&self.task_id, &self.load_lock);
+    }
+}
+
+impl<T: Clone + Send> TaskInvalidationHook for TaskCache<T> {
+    fn invalidate_task(&self, task_id: &TaskId) {
+        self.invalidate(task_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PeerAggregatorCache, TaskCache};
+    use crate::aggregator::Error;
+    use futures::future::join_all;
+    use janus_aggregator_core::{
+        datastore::test_util::ephemeral_datastore, taskprov::test_util::PeerAggregatorBuilder,
+    };
+    use janus_core::{test_util::install_test_trace_subscriber, time::MockClock};
+    use janus_messages::TaskId;
+    use rand::random;
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+    use tokio::time::{advance, sleep, timeout};
+
+    const TTL: Duration = Duration::from_secs(600);
+    const NEGATIVE_TTL: Duration = Duration::from_secs(5);
+
+    /// Looks up a task in the cache, counting the number of loads and loading `value` if needed.
+    async fn get(
+        cache: &TaskCache<u64>,
+        task_id: &TaskId,
+        loads: &AtomicUsize,
+        value: Option<u64>,
+    ) -> Option<u64> {
+        cache
+            .get_or_load(task_id, || async {
+                loads.fetch_add(1, Ordering::SeqCst);
+                Ok(value)
+            })
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn caches_values_until_expiry() {
+        let cache = TaskCache::new(TTL, NEGATIVE_TTL, 10);
+        let (present, absent) = (random(), random());
+        let loads = AtomicUsize::new(0);
+
+        assert_eq!(get(&cache, &present, &loads, Some(1)).await, Some(1));
+        assert_eq!(get(&cache, &absent, &loads, None).await, None);
+        assert_eq!(loads.load(Ordering::SeqCst), 2);
+
+        // Both the value and the absence of a value are cached.
+        assert_eq!(get(&cache, &present, &loads, Some(2)).await, Some(1));
+        assert_eq!(get(&cache, &absent, &loads, Some(2)).await, None);
+        assert_eq!(loads.load(Ordering::SeqCst), 2);
+
+        // Absences expire after the negative TTL, values after the TTL.
+        advance(NEGATIVE_TTL).await;
+        assert_eq!(get(&cache, &present, &loads, Some(2)).await, Some(1));
+        assert_eq!(get(&cache, &absent, &loads, Some(2)).await, Some(2));
+        assert_eq!(loads.load(Ordering::SeqCst), 3);
+
+        advance(TTL).await;
+        assert_eq!(get(&cache, &present, &loads, Some(3)).await, Some(3));
+        assert_eq!(loads.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn evicts_least_recently_used() {
+        let cache = TaskCache::new(TTL, NEGATIVE_TTL, 2);
+        let (first, second, third) = (random(), random(), random());
+        let loads = AtomicUsize::new(0);
+
+        get(&cache, &first, &loads, Some(1)).await;
+        get(&cache, &second, &loads, Some(2)).await;
+        // Use the first task again, so that the second is now the least recently used.
+        get(&cache, &first, &loads, Some(1)).await;
+        get(&cache, &third, &loads, Some(3)).await;
+        assert_eq!(loads.load(Ordering::SeqCst), 3);
+
+        get(&cache, &first, &loads, Some(1)).await;
+        get(&cache, &third, &loads, Some(3)).await;
+        assert_eq!(loads.load(Ordering::SeqCst), 3);
+        get(&cache, &second, &loads, Some(2)).await;
+        assert_eq!(loads.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn single_flight() {
+        let cache = Arc::new(TaskCache::new(TTL, NEGATIVE_TTL, 10));
+        let task_id = random();
+        let loads = Arc::new(AtomicUsize::new(0));
+
+        let results = join_all((0..10).map(|_| {
+            let (cache, loads) = (Arc::clone(&cache), Arc::clone(&loads));
+            tokio::spawn(async move {
+                cache
+                    .get_or_load(&task_id, || async {
+                        loads.fetch_add(1, Ordering::SeqCst);
+                        sleep(Duration::from_secs(1)).await;
+                        Ok(Some(1))
+                    })
+                    .await
+                    .unwrap()
+            })
+        }))
+        .await;
+
+        for result in results {
+            assert_eq!(result.unwrap(), Some(1));
+        }
+        assert_eq!(loads.load(Ordering::SeqCst), 1);
+        assert!(cache.state.lock().unwrap().loads.is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn cancelled_load() {
+        let cache = TaskCache::new(TTL, NEGATIVE_TTL, 10);
+        let task_id = random();
+        let loads = AtomicUsize::new(0);
+
+        // Drop a load partway through, as happens when a request handler is cancelled.
+        timeout(
+            Duration::from_secs(1),
+            cache.get_or_load(&task_id, || async {
+                sleep(Duration::from_secs(10)).await;
+                Ok(Some(1))
+            }),
+        )
+        .await
+        .unwrap_err();
+        assert!(cache.state.lock().unwrap().loads.is_empty());
+
+        assert_eq!(get(&cache, &task_id, &loads, Some(2)).await, Some(2));
+        assert_eq!(loads.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn errors_not_cached() {
+        let cache = TaskCache::new(TTL, NEGATIVE_TTL, 10);
+        let task_id = random();
+        let loads = AtomicUsize::new(0);
+
+        cache
+            .get_or_load(&task_id, || async {
+                Err(Error::Internal("test error".to_string()))
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(get(&cache, &task_id, &loads, Some(1)).await, Some(1));
+        assert_eq!(loads.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn invalidate() {
+        let cache = TaskCache::new(TTL, NEGATIVE_TTL, 10);
+        let task_id = random();
+        let loads = AtomicUsize::new(0);
+
+        assert_eq!(get(&cache, &task_id, &loads, None).await, None);
+        cache.invalidate(&task_id);
+        assert_eq!(get(&cache, &task_id, &loads, Some(1)).await, Some(1));
+        assert_eq!(loads.load(Ordering::SeqCst), 2);
+
+        // A load which races with an invalidation returns its result, but does not cache it.
+        cache.invalidate(&task_id);
+        let value = cache
+            .get_or_load(&task_id, || async {
+                cache.invalidate(&task_id);
+                Ok(Some(2))
+            })
+            .await
+            .unwrap();
+        assert_eq!(value, Some(2));
+        assert_eq!(get(&cache, &task_id, &loads, Some(3)).await, Some(3));
+        assert_eq!(loads.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn peer_aggregator_cache_refresh() {
+        install_test_trace_subscriber();
+        let ephemeral_datastore = ephemeral_datastore().await;
+        let datastore = Arc::new(ephemeral_datastore.datastore(MockClock::default()).await);
+
+        let peer_aggregator = PeerAggregatorBuilder::new().build();
+        datastore
+            .run_tx(|tx| {
+                let peer_aggregator = peer_aggregator.clone();
+                Box::pin(async move { tx.put_taskprov_peer_aggregator(&peer_aggregator).await })
+            })
+            .await
+            .unwrap();
+
+        let cache = PeerAggregatorCache::new(Arc::clone(&datastore), TTL)
+            .await
+            .unwrap();
+        assert_eq!(
+            cache
+                .get(peer_aggregator.endpoint(), peer_aggregator.role())
+                .as_deref(),
+            Some(&peer_aggregator)
+        );
+
+        let updated_peer_aggregator = PeerAggregatorBuilder::from(peer_aggregator.clone())
+            .with_aggregator_auth_tokens(Vec::from([random()]))
+            .build();
+        datastore
+            .run_tx(|tx| {
+                let updated_peer_aggregator = updated_peer_aggregator.clone();
+                Box::pin(async move {
+                    tx.update_taskprov_peer_aggregator(&updated_peer_aggregator)
+                        .await
+                })
+            })
+            .await
+            .unwrap();
+
+        // The change is not observed until the cache is refreshed.
+        assert_eq!(
+            cache
+                .get(peer_aggregator.endpoint(), peer_aggregator.role())
+                .as_deref(),
+            Some(&peer_aggregator)
+        );
+        cache.refresh(&datastore).await.unwrap();
+        assert_eq!(
+            cache
+                .get(peer_aggregator.endpoint(), peer_aggregator.role())
+                .as_deref(),
+            Some(&updated_peer_aggregator)
+        );
     }
 }
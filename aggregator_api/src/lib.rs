@@ -6,7 +6,7 @@ mod tests;
 
 use async_trait::async_trait;
 use janus_aggregator_core::datastore;
-use janus_aggregator_core::{datastore::Datastore, instrumented};
+use janus_aggregator_core::{datastore::Datastore, instrumented, task::TaskInvalidationHook};
 use janus_core::{http::extract_bearer_token, task::AuthenticationToken, time::Clock};
 use janus_messages::{HpkeConfigId, RoleParseError, TaskId};
 use ring::constant_time;
@@ -29,6 +29,17 @@ use url::Url;
 pub struct Config {
     pub auth_tokens: Vec<AuthenticationToken>,
     pub public_dap_url: Url,
+    /// Hook notified whenever a task is created, deleted or modified through the API, so that an
+    /// aggregator sharing this process can drop its cached copy of the task.
+    pub task_invalidation_hook: Option<Arc<dyn TaskInvalidationHook>>,
+}
+
+impl Config {
+    fn invalidate_task(&self, task_id: &TaskId) {
+        if let Some(hook) = &self.task_invalidation_hook {
+            hook.invalidate_task(task_id);
+        }
+    }
 }
 
 /// Content type
@@ -100,6 +111,10 @@ pub fn aggregator_api_handler<C: Clock>(ds: Arc<Datastore<C>>, cfg: Config) -> i
                 "/hpke_configs",
                 instrumented(api(put_global_hpke_config::<C>)),
             )
+            .post(
+                "/hpke_configs",
+                instrumented(api(import_global_hpke_config::<C>)),
+            )
             .patch(
                 "/hpke_configs/:config_id",
                 instrumented(api(patch_global_hpke_config::<C>)),
@@ -116,6 +131,10 @@ pub fn aggregator_api_handler<C: Clock>(ds: Arc<Datastore<C>>, cfg: Config) -> i
                 "/taskprov/peer_aggregators",
                 instrumented(api(post_taskprov_peer_aggregator::<C>)),
             )
+            .patch(
+                "/taskprov/peer_aggregators",
+                instrumented(api(patch_taskprov_peer_aggregator::<C>)),
+            )
             .delete(
                 "/taskprov/peer_aggregators",
                 instrumented(api(delete_taskprov_peer_aggregator::<C>)),
@@ -1,15 +1,19 @@
 use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
 use janus_aggregator_core::{
-    datastore::models::{GlobalHpkeKeypair, HpkeKeyState},
-    task::{QueryType, Task},
-    taskprov::{PeerAggregator, VerifyKeyInit},
+    datastore::models::{
+        AggregationJobState, BatchState, CollectionJobStateCode, GlobalHpkeKeypair, HpkeKeyState,
+        ImportedHpkeKeypair, ReportAggregationStateCode, TaskMetrics,
+    },
+    task::{DpConfig, QueryType, Task, TaskPriority},
+    taskprov::{PeerAggregator, TaskprovPolicy, VerifyKeyInit},
 };
 use janus_core::task::{AuthenticationToken, VdafInstance};
 use janus_messages::{
-    query_type::Code as SupportedQueryType, Duration, HpkeAeadId, HpkeConfig, HpkeKdfId, HpkeKemId,
-    Role, TaskId, Time,
+    query_type::Code as SupportedQueryType, BatchId, Duration, HpkeAeadId, HpkeConfig, HpkeKdfId,
+    HpkeKemId, ReportShareError, Role, TaskId, Time,
 };
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::BTreeMap;
 use url::Url;
 
 #[allow(dead_code)]
@@ -72,6 +76,13 @@ pub(crate) struct PostTaskReq {
     pub(crate) time_precision: Duration,
     /// HPKE configuration for the collector.
     pub(crate) collector_hpke_config: HpkeConfig,
+    /// The scheduling priority class of this task's jobs. Defaults to `Normal` if not specified.
+    #[serde(default)]
+    pub(crate) priority: TaskPriority,
+    /// The differential privacy mechanism applied to aggregate shares. Defaults to `None` if not
+    /// specified.
+    #[serde(default)]
+    pub(crate) dp_config: DpConfig,
     /// If this aggregator is the leader, this is the token to use to authenticate requests to
     /// the helper. If this aggregator is the helper, the value is `None`.
     pub(crate) aggregator_auth_token: Option<AuthenticationToken>,
@@ -107,6 +118,10 @@ pub(crate) struct TaskResp {
     /// How much clock skew to allow between client and aggregator. Reports from
     /// farther than this duration into the future will be rejected.
     pub(crate) tolerable_clock_skew: Duration,
+    /// The scheduling priority class of this task's jobs.
+    pub(crate) priority: TaskPriority,
+    /// The differential privacy mechanism applied to aggregate shares.
+    pub(crate) dp_config: DpConfig,
     /// The authentication token for inter-aggregator communication in this task.
     /// If `role` is Leader, this token is used by the aggregator to authenticate requests to
     /// the Helper. If `role` is Helper, this token is used by the aggregator to authenticate
@@ -181,6 +196,8 @@ impl TryFrom<&Task> for TaskResp {
             min_batch_size: task.min_batch_size(),
             time_precision: *task.time_precision(),
             tolerable_clock_skew: *task.tolerable_clock_skew(),
+            priority: *task.priority(),
+            dp_config: *task.dp_config(),
             aggregator_auth_token: task.primary_aggregator_auth_token().clone(),
             collector_auth_token,
             collector_hpke_config: task
@@ -195,9 +212,90 @@ impl TryFrom<&Task> for TaskResp {
 #[derive(Serialize)]
 pub(crate) struct GetTaskMetricsResp {
     pub(crate) reports: u64,
+    pub(crate) unaggregated_reports: u64,
+    pub(crate) report_aggregations: u64,
+    pub(crate) report_aggregations_by_state: BTreeMap<ReportAggregationStateCode, u64>,
+    pub(crate) report_aggregation_failures: BTreeMap<&'static str, u64>,
+    pub(crate) aggregation_jobs_by_state: BTreeMap<AggregationJobState, u64>,
+    pub(crate) batches_by_state: BTreeMap<BatchState, u64>,
+    pub(crate) outstanding_batches: Vec<OutstandingBatchMetrics>,
+    pub(crate) collection_jobs_by_state: BTreeMap<CollectionJobStateCode, u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) time_buckets: Option<Vec<TaskMetricsTimeBucketResp>>,
+}
+
+impl From<TaskMetrics> for GetTaskMetricsResp {
+    fn from(metrics: TaskMetrics) -> Self {
+        Self {
+            reports: metrics.reports(),
+            unaggregated_reports: metrics.unaggregated_reports(),
+            report_aggregations: metrics.report_aggregations(),
+            report_aggregations_by_state: metrics.report_aggregations_by_state().clone(),
+            report_aggregation_failures: metrics
+                .report_aggregation_failures()
+                .iter()
+                .map(|(error, count)| (report_share_error_name(error), *count))
+                .collect(),
+            aggregation_jobs_by_state: metrics.aggregation_jobs_by_state().clone(),
+            batches_by_state: metrics.batches_by_state().clone(),
+            outstanding_batches: metrics
+                .outstanding_batches()
+                .iter()
+                .map(|batch| OutstandingBatchMetrics {
+                    batch_id: *batch.id(),
+                    min_size: *batch.size().start(),
+                    max_size: *batch.size().end(),
+                })
+                .collect(),
+            collection_jobs_by_state: metrics.collection_jobs_by_state().clone(),
+            time_buckets: metrics.time_buckets().map(|time_buckets| {
+                time_buckets
+                    .iter()
+                    .map(|time_bucket| TaskMetricsTimeBucketResp {
+                        start: *time_bucket.start(),
+                        reports: time_bucket.reports(),
+                        unaggregated_reports: time_bucket.unaggregated_reports(),
+                        report_aggregations: time_bucket.report_aggregations(),
+                    })
+                    .collect()
+            }),
+        }
+    }
+}
+
+/// An outstanding batch of a fixed-size task, along with how full it is. The minimum size counts
+/// reports which have finished aggregation, while the maximum size also counts reports which are
+/// still being aggregated.
+#[derive(Serialize)]
+pub(crate) struct OutstandingBatchMetrics {
+    pub(crate) batch_id: BatchId,
+    pub(crate) min_size: usize,
+    pub(crate) max_size: usize,
+}
+
+#[derive(Serialize)]
+pub(crate) struct TaskMetricsTimeBucketResp {
+    pub(crate) start: Time,
+    pub(crate) reports: u64,
+    pub(crate) unaggregated_reports: u64,
     pub(crate) report_aggregations: u64,
 }
 
+/// Names a report share error the way the DAP specification does.
+fn report_share_error_name(error: &ReportShareError) -> &'static str {
+    match error {
+        ReportShareError::BatchCollected => "batch_collected",
+        ReportShareError::ReportReplayed => "report_replayed",
+        ReportShareError::ReportDropped => "report_dropped",
+        ReportShareError::HpkeUnknownConfigId => "hpke_unknown_config_id",
+        ReportShareError::HpkeDecryptError => "hpke_decrypt_error",
+        ReportShareError::VdafPrepError => "vdaf_prep_error",
+        ReportShareError::BatchSaturated => "batch_saturated",
+        ReportShareError::TaskExpired => "task_expired",
+        ReportShareError::UnrecognizedMessage => "unrecognized_message",
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub(crate) struct GlobalHpkeConfigResp {
     pub(crate) config: HpkeConfig,
@@ -220,6 +318,10 @@ pub(crate) struct PutGlobalHpkeConfigReq {
     pub(crate) aead_id: Option<HpkeAeadId>,
 }
 
+/// Request body for importing an externally generated global HPKE keypair. The private key is
+/// either provided in the clear, or wrapped to one of the aggregator's global HPKE keys.
+pub(crate) type ImportGlobalHpkeConfigReq = ImportedHpkeKeypair;
+
 #[derive(Serialize, Deserialize)]
 pub(crate) struct PatchGlobalHpkeConfigReq {
     pub(crate) state: HpkeKeyState,
@@ -232,6 +334,7 @@ pub(crate) struct TaskprovPeerAggregatorResp {
     pub(crate) collector_hpke_config: HpkeConfig,
     pub(crate) report_expiry_age: Option<Duration>,
     pub(crate) tolerable_clock_skew: Duration,
+    pub(crate) policy: TaskprovPolicy,
 }
 
 impl From<PeerAggregator> for TaskprovPeerAggregatorResp {
@@ -243,6 +346,7 @@ impl From<PeerAggregator> for TaskprovPeerAggregatorResp {
             collector_hpke_config: value.collector_hpke_config().clone(),
             report_expiry_age: value.report_expiry_age().cloned(),
             tolerable_clock_skew: *value.tolerable_clock_skew(),
+            policy: value.policy().clone(),
         }
     }
 }
@@ -257,6 +361,76 @@ pub(crate) struct PostTaskprovPeerAggregatorReq {
     pub(crate) tolerable_clock_skew: Duration,
     pub(crate) aggregator_auth_tokens: Vec<AuthenticationToken>,
     pub(crate) collector_auth_tokens: Vec<AuthenticationToken>,
+    /// Limits on the tasks that may be provisioned via taskprov with this peer. Defaults to no
+    /// limits if not specified.
+    #[serde(default)]
+    pub(crate) policy: TaskprovPolicy,
+}
+
+/// Identifies a peer aggregator by its endpoint and role, and replaces each of its other parameters
+/// which is present. Tasks which were already provisioned keep the VDAF verify keys derived from
+/// the previous `verify_key_init`, so replacing it only affects tasks provisioned afterwards.
+///
+/// TODO: support several active `verify_key_init` values, so that the peers need not switch to a
+/// new value at the same moment. Only a single value is supported for now, which is replaced
+/// outright.
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct PatchTaskprovPeerAggregatorReq {
+    pub(crate) endpoint: Url,
+    pub(crate) role: Role,
+    #[serde(default)]
+    pub(crate) collector_hpke_config: Option<HpkeConfig>,
+    #[serde(default)]
+    pub(crate) verify_key_init: Option<VerifyKeyInit>,
+    /// Distinguishes an absent `report_expiry_age`, which leaves it unchanged, from an explicit
+    /// `null`, which disables report expiry.
+    #[serde(
+        default,
+        deserialize_with = "deserialize_present",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub(crate) report_expiry_age: Option<Option<Duration>>,
+    #[serde(default)]
+    pub(crate) tolerable_clock_skew: Option<Duration>,
+    #[serde(default)]
+    pub(crate) aggregator_auth_tokens: Option<Vec<AuthenticationToken>>,
+    #[serde(default)]
+    pub(crate) collector_auth_tokens: Option<Vec<AuthenticationToken>>,
+    #[serde(default)]
+    pub(crate) policy: Option<TaskprovPolicy>,
+}
+
+impl PatchTaskprovPeerAggregatorReq {
+    /// Applies the changes described by this request to the given peer aggregator.
+    pub(crate) fn apply(self, peer_aggregator: &PeerAggregator) -> PeerAggregator {
+        PeerAggregator::new(
+            self.endpoint,
+            self.role,
+            self.verify_key_init
+                .unwrap_or(*peer_aggregator.verify_key_init()),
+            self.collector_hpke_config
+                .unwrap_or_else(|| peer_aggregator.collector_hpke_config().clone()),
+            self.report_expiry_age
+                .unwrap_or_else(|| peer_aggregator.report_expiry_age().cloned()),
+            self.tolerable_clock_skew
+                .unwrap_or(*peer_aggregator.tolerable_clock_skew()),
+            self.aggregator_auth_tokens
+                .unwrap_or_else(|| peer_aggregator.aggregator_auth_tokens().to_vec()),
+            self.collector_auth_tokens
+                .unwrap_or_else(|| peer_aggregator.collector_auth_tokens().to_vec()),
+            self.policy
+                .unwrap_or_else(|| peer_aggregator.policy().clone()),
+        )
+    }
+}
+
+/// Deserializes a field which is present, even if its value is `null`, as `Some`.
+fn deserialize_present<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    T::deserialize(deserializer).map(Some)
 }
 
 #[derive(Clone, Serialize, Deserialize)]
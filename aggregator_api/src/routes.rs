@@ -1,7 +1,8 @@
 use crate::{
     models::{
         AggregatorApiConfig, AggregatorRole, DeleteTaskprovPeerAggregatorReq, GetTaskIdsResp,
-        GetTaskMetricsResp, GlobalHpkeConfigResp, PatchGlobalHpkeConfigReq, PostTaskReq,
+        GetTaskMetricsResp, GlobalHpkeConfigResp, ImportGlobalHpkeConfigReq,
+        PatchGlobalHpkeConfigReq, PatchTaskprovPeerAggregatorReq, PostTaskReq,
         PostTaskprovPeerAggregatorReq, PutGlobalHpkeConfigReq, SupportedVdaf, TaskResp,
         TaskprovPeerAggregatorResp,
     },
@@ -62,7 +63,7 @@ pub(super) async fn get_task_ids<C: Clock>(
         .map_err(|err| Error::BadRequest(format!("Couldn't parse pagination_token: {:?}", err)))?;
 
     let task_ids = ds
-        .run_tx_with_name("get_task_ids", |tx| {
+        .run_read_only_tx_with_name("get_task_ids", |tx| {
             Box::pin(async move { tx.get_task_ids(lower_bound).await })
         })
         .await?;
@@ -74,9 +75,14 @@ pub(super) async fn get_task_ids<C: Clock>(
     }))
 }
 
+#[allow(clippy::type_complexity)]
 pub(super) async fn post_task<C: Clock>(
     _: &mut Conn,
-    (State(ds), Json(req)): (State<Arc<Datastore<C>>>, Json<PostTaskReq>),
+    (State(ds), State(config), Json(req)): (
+        State<Arc<Datastore<C>>>,
+        State<Arc<Config>>,
+        Json<PostTaskReq>,
+    ),
 ) -> Result<Json<TaskResp>, Error> {
     // We have to resolve impedance mismatches between the aggregator API's view of a task and
     // `aggregator_core::task::Task`. For now, we deal with this in code, but someday the two
@@ -169,6 +175,8 @@ pub(super) async fn post_task<C: Clock>(
             /* time_precision */ req.time_precision,
             /* tolerable_clock_skew */
             Duration::from_seconds(60), // 1 minute,
+            /* priority */ req.priority,
+            /* dp_config */ req.dp_config,
             /* collector_hpke_config */ req.collector_hpke_config,
             aggregator_auth_tokens,
             collector_auth_tokens,
@@ -193,6 +201,8 @@ pub(super) async fn post_task<C: Clock>(
                 && existing_task.task_expiration() == task.task_expiration()
                 && existing_task.min_batch_size() == task.min_batch_size()
                 && existing_task.time_precision() == task.time_precision()
+                && existing_task.priority() == task.priority()
+                && existing_task.dp_config() == task.dp_config()
                 && existing_task.collector_hpke_config() == task.collector_hpke_config() {
                     return Ok(())
                 }
@@ -207,6 +217,7 @@ pub(super) async fn post_task<C: Clock>(
         })
     })
     .await?;
+    config.invalidate_task(task.id());
 
     Ok(Json(
         TaskResp::try_from(task.as_ref()).map_err(|err| Error::Internal(err.to_string()))?,
@@ -233,7 +244,7 @@ pub(super) async fn get_task<C: Clock>(
 
 pub(super) async fn delete_task<C: Clock>(
     conn: &mut Conn,
-    State(ds): State<Arc<Datastore<C>>>,
+    (State(ds), State(config)): (State<Arc<Datastore<C>>>, State<Arc<Config>>),
 ) -> Result<Status, Error> {
     let task_id = conn.task_id_param()?;
     match ds
@@ -242,7 +253,10 @@ pub(super) async fn delete_task<C: Clock>(
         })
         .await
     {
-        Ok(_) | Err(datastore::Error::MutationTargetNotFound) => Ok(Status::NoContent),
+        Ok(_) | Err(datastore::Error::MutationTargetNotFound) => {
+            config.invalidate_task(&task_id);
+            Ok(Status::NoContent)
+        }
         Err(err) => Err(err.into()),
     }
 }
@@ -251,19 +265,30 @@ pub(super) async fn get_task_metrics<C: Clock>(
     conn: &mut Conn,
     State(ds): State<Arc<Datastore<C>>>,
 ) -> Result<Json<GetTaskMetricsResp>, Error> {
+    const TIME_BUCKET_KEY: &str = "time_bucket_s";
     let task_id = conn.task_id_param()?;
+    let time_bucket_size = querify(conn.querystring())
+        .into_iter()
+        .find(|&(k, _)| k == TIME_BUCKET_KEY)
+        .map(|(_, v)| v.parse::<u64>())
+        .transpose()
+        .map_err(|err| Error::BadRequest(format!("Couldn't parse time_bucket_s: {:?}", err)))?
+        .map(|seconds| match seconds {
+            0 => Err(Error::BadRequest(
+                "time_bucket_s must be greater than zero".to_string(),
+            )),
+            _ => Ok(Duration::from_seconds(seconds)),
+        })
+        .transpose()?;
 
-    let (reports, report_aggregations) = ds
-        .run_tx_with_name("get_task_metrics", |tx| {
-            Box::pin(async move { tx.get_task_metrics(&task_id).await })
+    let metrics = ds
+        .run_read_only_tx_with_name("get_task_metrics", |tx| {
+            Box::pin(async move { tx.get_task_metrics(&task_id, time_bucket_size).await })
         })
         .await?
         .ok_or(Error::NotFound)?;
 
-    Ok(Json(GetTaskMetricsResp {
-        reports,
-        report_aggregations,
-    }))
+    Ok(Json(GetTaskMetricsResp::from(metrics)))
 }
 
 pub(super) async fn get_global_hpke_configs<C: Clock>(
@@ -271,7 +296,7 @@ pub(super) async fn get_global_hpke_configs<C: Clock>(
     State(ds): State<Arc<Datastore<C>>>,
 ) -> Result<Json<Vec<GlobalHpkeConfigResp>>, Error> {
     Ok(Json(
-        ds.run_tx_with_name("get_global_hpke_configs", |tx| {
+        ds.run_read_only_tx_with_name("get_global_hpke_configs", |tx| {
             Box::pin(async move { tx.get_global_hpke_keypairs().await })
         })
         .await?
@@ -339,6 +364,39 @@ pub(super) async fn put_global_hpke_config<C: Clock>(
     ))
 }
 
+pub(super) async fn import_global_hpke_config<C: Clock>(
+    _: &mut Conn,
+    (State(ds), Json(req)): (State<Arc<Datastore<C>>>, Json<ImportGlobalHpkeConfigReq>),
+) -> Result<(Status, Json<GlobalHpkeConfigResp>), Error> {
+    let config_id = *req.config().id();
+    let req = Arc::new(req);
+
+    let inserted_keypair = ds
+        .run_tx_with_name("import_global_hpke_config", |tx| {
+            let req = Arc::clone(&req);
+            Box::pin(async move {
+                tx.import_global_hpke_keypair(&req, None).await?;
+                tx.get_global_hpke_keypair(&config_id).await
+            })
+        })
+        .await
+        .map_err(|err| match err {
+            datastore::Error::MutationTargetAlreadyExists => Error::Conflict(format!(
+                "Global HPKE key with ID {config_id} already exists"
+            )),
+            datastore::Error::InvalidParameter(message) => {
+                Error::BadRequest(format!("Invalid HPKE keypair: {message}"))
+            }
+            err => err.into(),
+        })?
+        .ok_or_else(|| Error::Internal("Newly inserted key disappeared".to_string()))?;
+
+    Ok((
+        Status::Created,
+        Json(GlobalHpkeConfigResp::from(inserted_keypair)),
+    ))
+}
+
 pub(super) async fn patch_global_hpke_config<C: Clock>(
     conn: &mut Conn,
     (State(ds), Json(req)): (State<Arc<Datastore<C>>>, Json<PatchGlobalHpkeConfigReq>),
@@ -378,7 +436,7 @@ pub(super) async fn get_taskprov_peer_aggregators<C: Clock>(
     State(ds): State<Arc<Datastore<C>>>,
 ) -> Result<Json<Vec<TaskprovPeerAggregatorResp>>, Error> {
     Ok(Json(
-        ds.run_tx_with_name("get_taskprov_peer_aggregators", |tx| {
+        ds.run_read_only_tx_with_name("get_taskprov_peer_aggregators", |tx| {
             Box::pin(async move { tx.get_taskprov_peer_aggregators().await })
         })
         .await?
@@ -388,12 +446,8 @@ pub(super) async fn get_taskprov_peer_aggregators<C: Clock>(
     ))
 }
 
-/// Inserts a new peer aggregator. Insertion is only supported, attempting to modify an existing
-/// peer aggregator will fail.
-///
-/// TODO(1685): Requiring that we delete an existing peer aggregator before we can change it makes
-/// token rotation cumbersome and fragile. Since token rotation is the main use case for updating
-/// an existing peer aggregator, we will resolve peer aggregator updates in that issue.
+/// Inserts a new peer aggregator. Attempting to insert a peer aggregator which already exists will
+/// fail; use [`patch_taskprov_peer_aggregator`] to modify it instead.
 pub(super) async fn post_taskprov_peer_aggregator<C: Clock>(
     _: &mut Conn,
     (State(ds), Json(req)): (
@@ -410,6 +464,7 @@ pub(super) async fn post_taskprov_peer_aggregator<C: Clock>(
         req.tolerable_clock_skew,
         req.aggregator_auth_tokens,
         req.collector_auth_tokens,
+        req.policy,
     );
 
     let inserted = ds
@@ -428,6 +483,49 @@ pub(super) async fn post_taskprov_peer_aggregator<C: Clock>(
     Ok((Status::Created, Json(inserted)))
 }
 
+/// Modifies an existing peer aggregator in place. Only the fields present in the request are
+/// changed, and all changes are applied atomically, so that tasks with the peer aggregator keep
+/// working throughout, e.g., a rotation of authentication tokens.
+///
+/// Tasks provisioned with the peer aggregator are rewritten to match, and dropped from the task
+/// cache of an aggregator sharing this process. Aggregators in other processes keep using their
+/// cached copies of those tasks until the task cache TTL (`task_cache.ttl_s`) expires, and their
+/// cached copy of the peer aggregator until it is next refreshed, so both the old and new
+/// authentication tokens should be accepted by the peer for at least that long.
+#[allow(clippy::type_complexity)]
+pub(super) async fn patch_taskprov_peer_aggregator<C: Clock>(
+    _: &mut Conn,
+    (State(ds), State(config), Json(req)): (
+        State<Arc<Datastore<C>>>,
+        State<Arc<Config>>,
+        Json<PatchTaskprovPeerAggregatorReq>,
+    ),
+) -> Result<Json<TaskprovPeerAggregatorResp>, Error> {
+    let (updated, updated_task_ids) = ds
+        .run_tx_with_name("patch_taskprov_peer_aggregator", |tx| {
+            let req = req.clone();
+            Box::pin(async move {
+                let peer_aggregator = match tx
+                    .get_taskprov_peer_aggregator(&req.endpoint, &req.role)
+                    .await?
+                {
+                    Some(peer_aggregator) => peer_aggregator,
+                    None => return Ok(None),
+                };
+                let updated = req.apply(&peer_aggregator);
+                let updated_task_ids = tx.update_taskprov_peer_aggregator(&updated).await?;
+                Ok(Some((updated, updated_task_ids)))
+            })
+        })
+        .await?
+        .ok_or(Error::NotFound)?;
+    for task_id in &updated_task_ids {
+        config.invalidate_task(task_id);
+    }
+
+    Ok(Json(TaskprovPeerAggregatorResp::from(updated)))
+}
+
 pub(super) async fn delete_taskprov_peer_aggregator<C: Clock>(
     _: &mut Conn,
     (State(ds), Json(req)): (
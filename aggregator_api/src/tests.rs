@@ -2,8 +2,9 @@ use crate::{
     aggregator_api_handler,
     models::{
         DeleteTaskprovPeerAggregatorReq, GetTaskIdsResp, GetTaskMetricsResp, GlobalHpkeConfigResp,
-        PatchGlobalHpkeConfigReq, PostTaskReq, PostTaskprovPeerAggregatorReq,
-        PutGlobalHpkeConfigReq, TaskResp, TaskprovPeerAggregatorResp,
+        ImportGlobalHpkeConfigReq, OutstandingBatchMetrics, PatchGlobalHpkeConfigReq,
+        PatchTaskprovPeerAggregatorReq, PostTaskReq, PostTaskprovPeerAggregatorReq,
+        PutGlobalHpkeConfigReq, TaskMetricsTimeBucketResp, TaskResp, TaskprovPeerAggregatorResp,
     },
     Config, CONTENT_TYPE,
 };
@@ -12,14 +13,15 @@ use futures::future::try_join_all;
 use janus_aggregator_core::{
     datastore::{
         models::{
-            AggregationJob, AggregationJobState, HpkeKeyState, LeaderStoredReport,
-            ReportAggregation, ReportAggregationState,
+            AggregationJob, AggregationJobState, BatchState, CollectionJobStateCode, HpkeKeyState,
+            LeaderStoredReport, ReportAggregation, ReportAggregationState,
+            ReportAggregationStateCode,
         },
         test_util::{ephemeral_datastore, EphemeralDatastore},
         Datastore,
     },
-    task::{test_util::TaskBuilder, QueryType, Task},
-    taskprov::test_util::PeerAggregatorBuilder,
+    task::{test_util::TaskBuilder, DpConfig, QueryType, Task, TaskInvalidationHook, TaskPriority},
+    taskprov::{test_util::PeerAggregatorBuilder, TaskprovPolicy},
     SecretBytes,
 };
 use janus_core::{
@@ -29,7 +31,7 @@ use janus_core::{
             generate_test_hpke_config_and_private_key,
             generate_test_hpke_config_and_private_key_with_id,
         },
-        HpkeKeypair, HpkePrivateKey,
+        wrap_private_key, HpkeKeypair, HpkePrivateKey,
     },
     task::{AuthenticationToken, VdafInstance},
     test_util::{
@@ -39,12 +41,16 @@ use janus_core::{
     time::MockClock,
 };
 use janus_messages::{
-    query_type::TimeInterval, AggregationJobRound, Duration, HpkeAeadId, HpkeConfig, HpkeConfigId,
-    HpkeKdfId, HpkeKemId, HpkePublicKey, Interval, Role, TaskId, Time,
+    query_type::TimeInterval, AggregationJobRound, BatchId, Duration, HpkeAeadId, HpkeConfig,
+    HpkeConfigId, HpkeKdfId, HpkeKemId, HpkePublicKey, Interval, Role, TaskId, Time,
 };
 use rand::{distributions::Standard, random, thread_rng, Rng};
 use serde_test::{assert_ser_tokens, assert_tokens, Token};
-use std::{iter, sync::Arc};
+use std::{
+    collections::BTreeMap,
+    iter,
+    sync::{Arc, Mutex},
+};
 use trillium::{Handler, Status};
 use trillium_testing::{
     assert_response, assert_status,
@@ -65,6 +71,7 @@ async fn setup_api_test() -> (impl Handler, EphemeralDatastore, Arc<Datastore<Mo
                 AuthenticationToken::new_bearer_token_from_string(AUTH_TOKEN).unwrap(),
             ]),
             public_dap_url: "https://dap.url".parse().unwrap(),
+            task_invalidation_hook: None,
         },
     );
 
@@ -208,6 +215,8 @@ async fn post_task_bad_role() {
         )
         .config()
         .clone(),
+        priority: TaskPriority::Normal,
+        dp_config: DpConfig::None,
         aggregator_auth_token: Some(aggregator_auth_token),
     };
     assert_response!(
@@ -248,6 +257,8 @@ async fn post_task_unauthorized() {
         )
         .config()
         .clone(),
+        priority: TaskPriority::Normal,
+        dp_config: DpConfig::None,
         aggregator_auth_token: Some(aggregator_auth_token),
     };
     assert_response!(
@@ -262,6 +273,125 @@ async fn post_task_unauthorized() {
     );
 }
 
+#[derive(Default)]
+struct RecordingTaskInvalidationHook(Mutex<Vec<TaskId>>);
+
+impl TaskInvalidationHook for RecordingTaskInvalidationHook {
+    fn invalidate_task(&self, task_id: &TaskId) {
+        self.0.lock().unwrap().push(*task_id);
+    }
+}
+
+#[tokio::test]
+async fn task_mutations_invalidate_task() {
+    // Setup: create a handler which records task invalidations.
+    install_test_trace_subscriber();
+    let ephemeral_datastore = ephemeral_datastore().await;
+    let ds = Arc::new(ephemeral_datastore.datastore(MockClock::default()).await);
+    let hook = Arc::new(RecordingTaskInvalidationHook::default());
+    let handler = aggregator_api_handler(
+        Arc::clone(&ds),
+        Config {
+            auth_tokens: Vec::from([
+                AuthenticationToken::new_bearer_token_from_string(AUTH_TOKEN).unwrap(),
+            ]),
+            public_dap_url: "https://dap.url".parse().unwrap(),
+            task_invalidation_hook: Some(Arc::clone(&hook) as Arc<dyn TaskInvalidationHook>),
+        },
+    );
+
+    // Verify: creating a task invalidates it.
+    let req = PostTaskReq {
+        peer_aggregator_endpoint: "http://aggregator.endpoint".try_into().unwrap(),
+        query_type: QueryType::TimeInterval,
+        vdaf: VdafInstance::Prio3Count,
+        role: Role::Helper,
+        vdaf_verify_key: URL_SAFE_NO_PAD.encode(random::<[u8; 16]>()),
+        max_batch_query_count: 12,
+        task_expiration: Some(Time::from_seconds_since_epoch(12345)),
+        min_batch_size: 223,
+        time_precision: Duration::from_seconds(62),
+        collector_hpke_config: generate_test_hpke_config_and_private_key().config().clone(),
+        priority: TaskPriority::Normal,
+        dp_config: DpConfig::None,
+        aggregator_auth_token: None,
+    };
+    let mut conn = post("/tasks")
+        .with_request_body(serde_json::to_vec(&req).unwrap())
+        .with_request_header("Authorization", format!("Bearer {AUTH_TOKEN}"))
+        .with_request_header("Accept", CONTENT_TYPE)
+        .with_request_header("Content-Type", CONTENT_TYPE)
+        .run_async(&handler)
+        .await;
+    assert_status!(conn, Status::Ok);
+    let task_id = serde_json::from_slice::<TaskResp>(
+        &conn
+            .take_response_body()
+            .unwrap()
+            .into_bytes()
+            .await
+            .unwrap(),
+    )
+    .unwrap()
+    .task_id;
+    assert_eq!(*hook.0.lock().unwrap(), Vec::from([task_id]));
+
+    // Verify: deleting a task invalidates it.
+    assert_response!(
+        delete(&format!("/tasks/{}", &task_id))
+            .with_request_header("Authorization", format!("Bearer {AUTH_TOKEN}"))
+            .with_request_header("Accept", CONTENT_TYPE)
+            .run_async(&handler)
+            .await,
+        Status::NoContent,
+        "",
+    );
+    assert_eq!(*hook.0.lock().unwrap(), Vec::from([task_id, task_id]));
+
+    // Verify: modifying a taskprov peer aggregator invalidates the tasks provisioned with it.
+    let peer_aggregator = PeerAggregatorBuilder::new()
+        .with_endpoint(Url::parse("https://helper.example.com/").unwrap())
+        .with_role(Role::Helper)
+        .build();
+    let taskprov_task = TaskBuilder::new(
+        QueryType::TimeInterval,
+        VdafInstance::Prio3Count,
+        Role::Leader,
+    )
+    .with_helper_aggregator_endpoint(peer_aggregator.endpoint().clone())
+    .with_taskprov_task_config(Vec::from("task config".as_bytes()))
+    .build();
+    ds.run_tx(|tx| {
+        let (peer_aggregator, taskprov_task) = (peer_aggregator.clone(), taskprov_task.clone());
+        Box::pin(async move {
+            tx.put_taskprov_peer_aggregator(&peer_aggregator).await?;
+            tx.put_task(&taskprov_task).await
+        })
+    })
+    .await
+    .unwrap();
+
+    let conn = patch("/taskprov/peer_aggregators")
+        .with_request_body(
+            serde_json::to_vec(&serde_json::json!({
+                "endpoint": peer_aggregator.endpoint(),
+                "role": "Helper",
+                "aggregator_auth_tokens": [random::<AuthenticationToken>()],
+            }))
+            .unwrap(),
+        )
+        .with_request_header("Authorization", format!("Bearer {AUTH_TOKEN}"))
+        .with_request_header("Accept", CONTENT_TYPE)
+        .with_request_header("Content-Type", CONTENT_TYPE)
+        .run_async(&handler)
+        .await;
+    assert_response!(conn, Status::Ok);
+    assert_eq!(
+        *hook.0.lock().unwrap(),
+        Vec::from([task_id, task_id, *taskprov_task.id()])
+    );
+}
+
 /// Test the POST /tasks endpoint, with a helper task with no optional fields defined
 #[tokio::test]
 async fn post_task_helper_no_optional_fields() {
@@ -289,6 +419,8 @@ async fn post_task_helper_no_optional_fields() {
         )
         .config()
         .clone(),
+        priority: TaskPriority::Normal,
+        dp_config: DpConfig::None,
         aggregator_auth_token: None,
     };
     let mut conn = post("/tasks")
@@ -368,6 +500,8 @@ async fn post_task_helper_with_aggregator_auth_token() {
         )
         .config()
         .clone(),
+        priority: TaskPriority::Normal,
+        dp_config: DpConfig::None,
         aggregator_auth_token: Some(aggregator_auth_token),
     };
     assert_response!(
@@ -410,6 +544,8 @@ async fn post_task_idempotence() {
         )
         .config()
         .clone(),
+        priority: TaskPriority::Normal,
+        dp_config: DpConfig::None,
         aggregator_auth_token: Some(aggregator_auth_token.clone()),
     };
 
@@ -490,6 +626,8 @@ async fn post_task_leader_all_optional_fields() {
         )
         .config()
         .clone(),
+        priority: TaskPriority::Normal,
+        dp_config: DpConfig::None,
         aggregator_auth_token: Some(aggregator_auth_token.clone()),
     };
     let mut conn = post("/tasks")
@@ -579,6 +717,8 @@ async fn post_task_leader_no_aggregator_auth_token() {
         )
         .config()
         .clone(),
+        priority: TaskPriority::Normal,
+        dp_config: DpConfig::None,
         aggregator_auth_token: None,
     };
 
@@ -805,11 +945,58 @@ async fn get_task_metrics() {
         Status::Ok,
         serde_json::to_string(&GetTaskMetricsResp {
             reports: REPORT_COUNT.try_into().unwrap(),
+            unaggregated_reports: REPORT_COUNT.try_into().unwrap(),
             report_aggregations: REPORT_AGGREGATION_COUNT.try_into().unwrap(),
+            report_aggregations_by_state: BTreeMap::from([(
+                ReportAggregationStateCode::Start,
+                REPORT_AGGREGATION_COUNT.try_into().unwrap()
+            )]),
+            report_aggregation_failures: BTreeMap::new(),
+            aggregation_jobs_by_state: BTreeMap::from([(AggregationJobState::InProgress, 1)]),
+            batches_by_state: BTreeMap::new(),
+            outstanding_batches: Vec::new(),
+            collection_jobs_by_state: BTreeMap::new(),
+            time_buckets: None,
         })
         .unwrap(),
     );
 
+    // Verify: metrics can be broken down by time bucket.
+    let mut conn = get(&format!("/tasks/{}/metrics?time_bucket_s=3600", &task_id))
+        .with_request_header("Authorization", format!("Bearer {AUTH_TOKEN}"))
+        .with_request_header("Accept", CONTENT_TYPE)
+        .run_async(&handler)
+        .await;
+    assert_status!(conn, Status::Ok);
+    let resp: serde_json::Value = serde_json::from_slice(
+        &conn
+            .take_response_body()
+            .unwrap()
+            .into_bytes()
+            .await
+            .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        resp["time_buckets"],
+        serde_json::json!([{
+            "start": 0,
+            "reports": REPORT_COUNT,
+            "unaggregated_reports": REPORT_COUNT,
+            "report_aggregations": REPORT_AGGREGATION_COUNT,
+        }])
+    );
+
+    // Verify: an invalid time bucket size is rejected.
+    assert_status!(
+        get(&format!("/tasks/{}/metrics?time_bucket_s=0", &task_id))
+            .with_request_header("Authorization", format!("Bearer {AUTH_TOKEN}"))
+            .with_request_header("Accept", CONTENT_TYPE)
+            .run_async(&handler)
+            .await,
+        Status::BadRequest
+    );
+
     // Verify: requesting metrics on a nonexistent task returns NotFound.
     assert_response!(
         get(&format!("/tasks/{}/metrics", &random::<TaskId>()))
@@ -1097,6 +1284,151 @@ async fn put_global_hpke_config() {
     );
 }
 
+#[tokio::test]
+async fn import_global_hpke_config() {
+    let (handler, _ephemeral_datastore, ds) = setup_api_test().await;
+
+    let keypair = generate_test_hpke_config_and_private_key_with_id(17);
+    let req =
+        ImportGlobalHpkeConfigReq::new(keypair.config().clone(), keypair.private_key().clone());
+
+    let mut resp = post("/hpke_configs")
+        .with_request_body(serde_json::to_vec(&req).unwrap())
+        .with_request_header("Authorization", format!("Bearer {AUTH_TOKEN}"))
+        .with_request_header("Accept", CONTENT_TYPE)
+        .with_request_header("Content-Type", CONTENT_TYPE)
+        .run_async(&handler)
+        .await;
+    assert_response!(resp, Status::Created);
+    let imported: GlobalHpkeConfigResp = serde_json::from_slice(
+        &resp
+            .take_response_body()
+            .unwrap()
+            .into_bytes()
+            .await
+            .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        imported,
+        GlobalHpkeConfigResp {
+            config: keypair.config().clone(),
+            state: HpkeKeyState::Pending,
+        }
+    );
+
+    let got_keypair = ds
+        .run_tx(|tx| {
+            Box::pin(async move { tx.get_global_hpke_keypair(&HpkeConfigId::from(17)).await })
+        })
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(got_keypair.hpke_keypair(), &keypair);
+
+    // Verify: a private key wrapped to an existing global HPKE key is unwrapped with it.
+    let wrapped_keypair = generate_test_hpke_config_and_private_key_with_id(19);
+    assert_status!(
+        post("/hpke_configs")
+            .with_request_body(
+                serde_json::to_vec(&ImportGlobalHpkeConfigReq::new_wrapped(
+                    wrapped_keypair.config().clone(),
+                    wrap_private_key(keypair.config(), &wrapped_keypair).unwrap(),
+                ))
+                .unwrap()
+            )
+            .with_request_header("Authorization", format!("Bearer {AUTH_TOKEN}"))
+            .with_request_header("Accept", CONTENT_TYPE)
+            .with_request_header("Content-Type", CONTENT_TYPE)
+            .run_async(&handler)
+            .await,
+        Status::Created
+    );
+    let got_keypair = ds
+        .run_tx(|tx| {
+            Box::pin(async move { tx.get_global_hpke_keypair(&HpkeConfigId::from(19)).await })
+        })
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(got_keypair.hpke_keypair(), &wrapped_keypair);
+
+    // Verify: a private key wrapped to an unknown transport key is rejected.
+    let wrapped_keypair = generate_test_hpke_config_and_private_key_with_id(20);
+    assert_response!(
+        post("/hpke_configs")
+            .with_request_body(
+                serde_json::to_vec(&ImportGlobalHpkeConfigReq::new_wrapped(
+                    wrapped_keypair.config().clone(),
+                    wrap_private_key(
+                        generate_test_hpke_config_and_private_key_with_id(100).config(),
+                        &wrapped_keypair
+                    )
+                    .unwrap(),
+                ))
+                .unwrap()
+            )
+            .with_request_header("Authorization", format!("Bearer {AUTH_TOKEN}"))
+            .with_request_header("Accept", CONTENT_TYPE)
+            .with_request_header("Content-Type", CONTENT_TYPE)
+            .run_async(&handler)
+            .await,
+        Status::BadRequest,
+        "Invalid HPKE keypair: private key was wrapped to an unknown transport key",
+    );
+
+    // Verify: a key with the same ID is rejected.
+    let colliding_keypair = generate_test_hpke_config_and_private_key_with_id(17);
+    assert_response!(
+        post("/hpke_configs")
+            .with_request_body(
+                serde_json::to_vec(&ImportGlobalHpkeConfigReq::new(
+                    colliding_keypair.config().clone(),
+                    colliding_keypair.private_key().clone(),
+                ))
+                .unwrap()
+            )
+            .with_request_header("Authorization", format!("Bearer {AUTH_TOKEN}"))
+            .with_request_header("Accept", CONTENT_TYPE)
+            .with_request_header("Content-Type", CONTENT_TYPE)
+            .run_async(&handler)
+            .await,
+        Status::Conflict,
+        "Global HPKE key with ID 17 already exists",
+    );
+
+    // Verify: a private key that does not match the public key is rejected.
+    let other_keypair = generate_test_hpke_config_and_private_key_with_id(18);
+    let mismatched_keypair = generate_test_hpke_config_and_private_key_with_id(18);
+    assert_status!(
+        post("/hpke_configs")
+            .with_request_body(
+                serde_json::to_vec(&ImportGlobalHpkeConfigReq::new(
+                    other_keypair.config().clone(),
+                    mismatched_keypair.private_key().clone(),
+                ))
+                .unwrap()
+            )
+            .with_request_header("Authorization", format!("Bearer {AUTH_TOKEN}"))
+            .with_request_header("Accept", CONTENT_TYPE)
+            .with_request_header("Content-Type", CONTENT_TYPE)
+            .run_async(&handler)
+            .await,
+        Status::BadRequest
+    );
+
+    // Verify: unauthorized requests are denied appropriately.
+    assert_response!(
+        post("/hpke_configs")
+            .with_request_body(serde_json::to_vec(&req).unwrap())
+            .with_request_header("Accept", CONTENT_TYPE)
+            .run_async(&handler)
+            .await,
+        Status::Unauthorized,
+        "",
+    );
+}
+
 #[tokio::test]
 async fn patch_global_hpke_config() {
     let (handler, _ephemeral_datastore, ds) = setup_api_test().await;
@@ -1298,6 +1630,7 @@ async fn get_taskprov_peer_aggregator() {
             collector_hpke_config: leader.collector_hpke_config().clone(),
             report_expiry_age: leader.report_expiry_age().cloned(),
             tolerable_clock_skew: *leader.tolerable_clock_skew(),
+            policy: leader.policy().clone(),
         },
         TaskprovPeerAggregatorResp {
             endpoint: helper.endpoint().clone(),
@@ -1305,6 +1638,7 @@ async fn get_taskprov_peer_aggregator() {
             collector_hpke_config: helper.collector_hpke_config().clone(),
             report_expiry_age: helper.report_expiry_age().cloned(),
             tolerable_clock_skew: *helper.tolerable_clock_skew(),
+            policy: helper.policy().clone(),
         },
     ];
     expected.sort_by(|a, b| a.endpoint.cmp(&b.endpoint));
@@ -1330,6 +1664,10 @@ async fn post_taskprov_peer_aggregator() {
     let leader = PeerAggregatorBuilder::new()
         .with_endpoint(endpoint.clone())
         .with_role(Role::Leader)
+        .with_policy(TaskprovPolicy {
+            minimum_min_batch_size: Some(100),
+            ..Default::default()
+        })
         .build();
 
     let req = PostTaskprovPeerAggregatorReq {
@@ -1341,6 +1679,7 @@ async fn post_taskprov_peer_aggregator() {
         tolerable_clock_skew: *leader.tolerable_clock_skew(),
         aggregator_auth_tokens: Vec::from(leader.aggregator_auth_tokens()),
         collector_auth_tokens: Vec::from(leader.collector_auth_tokens()),
+        policy: leader.policy().clone(),
     };
 
     let mut conn = post("/taskprov/peer_aggregators")
@@ -1395,6 +1734,153 @@ async fn post_taskprov_peer_aggregator() {
     );
 }
 
+#[tokio::test]
+async fn patch_taskprov_peer_aggregator() {
+    let (handler, _ephemeral_datastore, ds) = setup_api_test().await;
+
+    let endpoint = Url::parse("https://leader.example.com/").unwrap();
+    let leader = PeerAggregatorBuilder::new()
+        .with_endpoint(endpoint.clone())
+        .with_role(Role::Leader)
+        .build();
+
+    ds.run_tx(|tx| {
+        let leader = leader.clone();
+        Box::pin(async move { tx.put_taskprov_peer_aggregator(&leader).await })
+    })
+    .await
+    .unwrap();
+
+    let policy = TaskprovPolicy {
+        maximum_max_batch_query_count: Some(1),
+        minimum_time_precision: Some(Duration::from_seconds(3600)),
+        ..Default::default()
+    };
+    let aggregator_auth_tokens = Vec::from([random(), leader.aggregator_auth_tokens()[0].clone()]);
+    let req = PatchTaskprovPeerAggregatorReq {
+        endpoint: endpoint.clone(),
+        role: Role::Leader,
+        collector_hpke_config: None,
+        verify_key_init: None,
+        report_expiry_age: Some(Some(Duration::from_seconds(3600))),
+        tolerable_clock_skew: Some(Duration::from_seconds(60)),
+        aggregator_auth_tokens: Some(aggregator_auth_tokens.clone()),
+        collector_auth_tokens: None,
+        policy: Some(policy.clone()),
+    };
+    let expected = PeerAggregatorBuilder::from(leader.clone())
+        .with_report_expiry_age(Some(Duration::from_seconds(3600)))
+        .with_tolerable_clock_skew(Duration::from_seconds(60))
+        .with_aggregator_auth_tokens(aggregator_auth_tokens)
+        .with_policy(policy)
+        .build();
+
+    let mut conn = patch("/taskprov/peer_aggregators")
+        .with_request_body(serde_json::to_vec(&req).unwrap())
+        .with_request_header("Authorization", format!("Bearer {AUTH_TOKEN}"))
+        .with_request_header("Accept", CONTENT_TYPE)
+        .with_request_header("Content-Type", CONTENT_TYPE)
+        .run_async(&handler)
+        .await;
+    assert_response!(conn, Status::Ok);
+    assert_eq!(
+        serde_json::from_slice::<TaskprovPeerAggregatorResp>(
+            &conn
+                .take_response_body()
+                .unwrap()
+                .into_bytes()
+                .await
+                .unwrap(),
+        )
+        .unwrap(),
+        expected.clone().into()
+    );
+
+    assert_eq!(
+        ds.run_tx(|tx| { Box::pin(async move { tx.get_taskprov_peer_aggregators().await }) })
+            .await
+            .unwrap(),
+        vec![expected.clone()]
+    );
+
+    // An explicit null clears the report expiry age, while absent fields are left unchanged.
+    let collector_hpke_config = generate_test_hpke_config_and_private_key().config().clone();
+    let mut conn = patch("/taskprov/peer_aggregators")
+        .with_request_body(
+            serde_json::to_vec(&serde_json::json!({
+                "endpoint": endpoint,
+                "role": "Leader",
+                "collector_hpke_config": collector_hpke_config,
+                "report_expiry_age": null,
+            }))
+            .unwrap(),
+        )
+        .with_request_header("Authorization", format!("Bearer {AUTH_TOKEN}"))
+        .with_request_header("Accept", CONTENT_TYPE)
+        .with_request_header("Content-Type", CONTENT_TYPE)
+        .run_async(&handler)
+        .await;
+    assert_response!(conn, Status::Ok);
+    let expected = PeerAggregatorBuilder::from(expected)
+        .with_collector_hpke_config(collector_hpke_config)
+        .with_report_expiry_age(None)
+        .build();
+    assert_eq!(
+        serde_json::from_slice::<TaskprovPeerAggregatorResp>(
+            &conn
+                .take_response_body()
+                .unwrap()
+                .into_bytes()
+                .await
+                .unwrap(),
+        )
+        .unwrap(),
+        expected.clone().into()
+    );
+    assert_eq!(
+        ds.run_tx(|tx| { Box::pin(async move { tx.get_taskprov_peer_aggregators().await }) })
+            .await
+            .unwrap(),
+        vec![expected]
+    );
+
+    // Non-existent target.
+    assert_response!(
+        patch("/taskprov/peer_aggregators")
+            .with_request_body(
+                serde_json::to_vec(&PatchTaskprovPeerAggregatorReq {
+                    endpoint: Url::parse("https://doesnt-exist.example.com/").unwrap(),
+                    role: Role::Leader,
+                    collector_hpke_config: None,
+                    verify_key_init: None,
+                    report_expiry_age: None,
+                    tolerable_clock_skew: None,
+                    aggregator_auth_tokens: None,
+                    collector_auth_tokens: None,
+                    policy: Some(TaskprovPolicy::default()),
+                })
+                .unwrap()
+            )
+            .with_request_header("Authorization", format!("Bearer {AUTH_TOKEN}"))
+            .with_request_header("Accept", CONTENT_TYPE)
+            .with_request_header("Content-Type", CONTENT_TYPE)
+            .run_async(&handler)
+            .await,
+        Status::NotFound
+    );
+
+    // Missing authorization.
+    assert_response!(
+        patch("/taskprov/peer_aggregators")
+            .with_request_body(serde_json::to_vec(&req).unwrap())
+            .with_request_header("Accept", CONTENT_TYPE)
+            .with_request_header("Content-Type", CONTENT_TYPE)
+            .run_async(&handler)
+            .await,
+        Status::Unauthorized
+    );
+}
+
 #[tokio::test]
 async fn delete_taskprov_peer_aggregator() {
     let (handler, _ephemeral_datastore, ds) = setup_api_test().await;
@@ -1530,12 +2016,14 @@ fn post_task_req_serialization() {
                 HpkeAeadId::Aes128Gcm,
                 HpkePublicKey::from([0u8; 32].to_vec()),
             ),
+            priority: TaskPriority::Normal,
+            dp_config: DpConfig::None,
             aggregator_auth_token: None,
         },
         &[
             Token::Struct {
                 name: "PostTaskReq",
-                len: 11,
+                len: 13,
             },
             Token::Str("peer_aggregator_endpoint"),
             Token::Str("https://example.com/"),
@@ -1603,6 +2091,16 @@ fn post_task_req_serialization() {
             Token::Str("public_key"),
             Token::Str("AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA"),
             Token::StructEnd,
+            Token::Str("priority"),
+            Token::UnitVariant {
+                name: "TaskPriority",
+                variant: "Normal",
+            },
+            Token::Str("dp_config"),
+            Token::UnitVariant {
+                name: "DpConfig",
+                variant: "None",
+            },
             Token::Str("aggregator_auth_token"),
             Token::None,
             Token::StructEnd,
@@ -1631,6 +2129,8 @@ fn post_task_req_serialization() {
                 HpkeAeadId::Aes128Gcm,
                 HpkePublicKey::from([0u8; 32].to_vec()),
             ),
+            priority: TaskPriority::High,
+            dp_config: DpConfig::None,
             aggregator_auth_token: Some(
                 AuthenticationToken::new_dap_auth_token_from_string("ZW5jb2RlZA").unwrap(),
             ),
@@ -1638,7 +2138,7 @@ fn post_task_req_serialization() {
         &[
             Token::Struct {
                 name: "PostTaskReq",
-                len: 11,
+                len: 13,
             },
             Token::Str("peer_aggregator_endpoint"),
             Token::Str("https://example.com/"),
@@ -1708,6 +2208,16 @@ fn post_task_req_serialization() {
             Token::Str("public_key"),
             Token::Str("AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA"),
             Token::StructEnd,
+            Token::Str("priority"),
+            Token::UnitVariant {
+                name: "TaskPriority",
+                variant: "High",
+            },
+            Token::Str("dp_config"),
+            Token::UnitVariant {
+                name: "DpConfig",
+                variant: "None",
+            },
             Token::Str("aggregator_auth_token"),
             Token::Some,
             Token::Struct {
@@ -1746,6 +2256,8 @@ fn task_resp_serialization() {
         100,
         Duration::from_seconds(3600),
         Duration::from_seconds(60),
+        TaskPriority::Normal,
+        DpConfig::None,
         HpkeConfig::new(
             HpkeConfigId::from(7),
             HpkeKemId::X25519HkdfSha256,
@@ -1778,7 +2290,7 @@ fn task_resp_serialization() {
         &[
             Token::Struct {
                 name: "TaskResp",
-                len: 16,
+                len: 18,
             },
             Token::Str("task_id"),
             Token::Str("AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA"),
@@ -1825,6 +2337,16 @@ fn task_resp_serialization() {
             Token::Str("tolerable_clock_skew"),
             Token::NewtypeStruct { name: "Duration" },
             Token::U64(60),
+            Token::Str("priority"),
+            Token::UnitVariant {
+                name: "TaskPriority",
+                variant: "Normal",
+            },
+            Token::Str("dp_config"),
+            Token::UnitVariant {
+                name: "DpConfig",
+                variant: "None",
+            },
             Token::Str("aggregator_auth_token"),
             Token::Struct {
                 name: "AuthenticationToken",
@@ -1920,18 +2442,114 @@ fn get_task_metrics_resp_serialization() {
     assert_ser_tokens(
         &GetTaskMetricsResp {
             reports: 87,
+            unaggregated_reports: 12,
             report_aggregations: 348,
+            report_aggregations_by_state: BTreeMap::from([
+                (ReportAggregationStateCode::Finished, 340),
+                (ReportAggregationStateCode::Failed, 8),
+            ]),
+            report_aggregation_failures: BTreeMap::from([("report_replayed", 8)]),
+            aggregation_jobs_by_state: BTreeMap::from([(AggregationJobState::Finished, 4)]),
+            batches_by_state: BTreeMap::from([(BatchState::Closed, 2)]),
+            outstanding_batches: Vec::from([OutstandingBatchMetrics {
+                batch_id: BatchId::from([0; 32]),
+                min_size: 10,
+                max_size: 12,
+            }]),
+            collection_jobs_by_state: BTreeMap::from([(CollectionJobStateCode::Finished, 1)]),
+            time_buckets: Some(Vec::from([TaskMetricsTimeBucketResp {
+                start: Time::from_seconds_since_epoch(3600),
+                reports: 87,
+                unaggregated_reports: 12,
+                report_aggregations: 348,
+            }])),
         },
         &[
             Token::Struct {
                 name: "GetTaskMetricsResp",
-                len: 2,
+                len: 10,
+            },
+            Token::Str("reports"),
+            Token::U64(87),
+            Token::Str("unaggregated_reports"),
+            Token::U64(12),
+            Token::Str("report_aggregations"),
+            Token::U64(348),
+            Token::Str("report_aggregations_by_state"),
+            Token::Map { len: Some(2) },
+            Token::UnitVariant {
+                name: "ReportAggregationStateCode",
+                variant: "finished",
+            },
+            Token::U64(340),
+            Token::UnitVariant {
+                name: "ReportAggregationStateCode",
+                variant: "failed",
             },
+            Token::U64(8),
+            Token::MapEnd,
+            Token::Str("report_aggregation_failures"),
+            Token::Map { len: Some(1) },
+            Token::Str("report_replayed"),
+            Token::U64(8),
+            Token::MapEnd,
+            Token::Str("aggregation_jobs_by_state"),
+            Token::Map { len: Some(1) },
+            Token::UnitVariant {
+                name: "AggregationJobState",
+                variant: "finished",
+            },
+            Token::U64(4),
+            Token::MapEnd,
+            Token::Str("batches_by_state"),
+            Token::Map { len: Some(1) },
+            Token::UnitVariant {
+                name: "BatchState",
+                variant: "closed",
+            },
+            Token::U64(2),
+            Token::MapEnd,
+            Token::Str("outstanding_batches"),
+            Token::Seq { len: Some(1) },
+            Token::Struct {
+                name: "OutstandingBatchMetrics",
+                len: 3,
+            },
+            Token::Str("batch_id"),
+            Token::Str("AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA"),
+            Token::Str("min_size"),
+            Token::U64(10),
+            Token::Str("max_size"),
+            Token::U64(12),
+            Token::StructEnd,
+            Token::SeqEnd,
+            Token::Str("collection_jobs_by_state"),
+            Token::Map { len: Some(1) },
+            Token::UnitVariant {
+                name: "CollectionJobStateCode",
+                variant: "finished",
+            },
+            Token::U64(1),
+            Token::MapEnd,
+            Token::Str("time_buckets"),
+            Token::Some,
+            Token::Seq { len: Some(1) },
+            Token::Struct {
+                name: "TaskMetricsTimeBucketResp",
+                len: 4,
+            },
+            Token::Str("start"),
+            Token::NewtypeStruct { name: "Time" },
+            Token::U64(3600),
             Token::Str("reports"),
             Token::U64(87),
+            Token::Str("unaggregated_reports"),
+            Token::U64(12),
             Token::Str("report_aggregations"),
             Token::U64(348),
             Token::StructEnd,
+            Token::SeqEnd,
+            Token::StructEnd,
         ],
     )
 }
@@ -0,0 +1,162 @@
+//! Benchmarks comparing datastore queries for reading the report aggregations & client reports
+//! needed to step an aggregation job in the leader.
+//!
+//! Only the datastore reads are measured: these benchmarks do not drive an aggregation job step,
+//! so VDAF preparation and helper requests are not included in the reported timings.
+//!
+//! These benchmarks require a Postgres instance, obtained in the same way as for the datastore
+//! tests.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use futures::future::try_join_all;
+use janus_aggregator_core::{
+    datastore::{
+        models::{
+            AggregationJob, AggregationJobState, LeaderStoredReport, ReportAggregation,
+            ReportAggregationState,
+        },
+        test_util::ephemeral_datastore,
+        Datastore,
+    },
+    task::{test_util::TaskBuilder, QueryType},
+};
+use janus_core::{
+    task::VdafInstance,
+    test_util::dummy_vdaf::{self, AggregationParam},
+    time::MockClock,
+};
+use janus_messages::{
+    query_type::TimeInterval, AggregationJobId, AggregationJobRound, Duration, Interval, Role,
+    TaskId, Time,
+};
+use rand::random;
+use std::sync::Arc;
+use tokio::runtime::Runtime;
+
+const REPORT_COUNTS: [usize; 3] = [10, 100, 1000];
+
+/// Writes a task with a single aggregation job containing `report_count` report aggregations in
+/// state `START`, along with their client reports.
+async fn setup(ds: &Datastore<MockClock>, report_count: usize) -> (TaskId, AggregationJobId) {
+    let task = TaskBuilder::new(QueryType::TimeInterval, VdafInstance::Fake, Role::Leader).build();
+    let aggregation_job_id = random();
+    let time = Time::from_seconds_since_epoch(1000);
+
+    ds.run_tx(|tx| {
+        let task = task.clone();
+        Box::pin(async move {
+            tx.put_task(&task).await?;
+            tx.put_aggregation_job(&AggregationJob::<0, TimeInterval, dummy_vdaf::Vdaf>::new(
+                *task.id(),
+                aggregation_job_id,
+                AggregationParam(0),
+                (),
+                Interval::new(time, Duration::from_seconds(1)).unwrap(),
+                AggregationJobState::InProgress,
+                AggregationJobRound::from(0),
+            ))
+            .await?;
+            for ord in 0..report_count {
+                let report = LeaderStoredReport::new_dummy(*task.id(), time);
+                tx.put_client_report(&dummy_vdaf::Vdaf::new(), &report)
+                    .await?;
+                tx.put_report_aggregation(&ReportAggregation::<0, dummy_vdaf::Vdaf>::new(
+                    *task.id(),
+                    aggregation_job_id,
+                    *report.metadata().id(),
+                    time,
+                    ord.try_into().unwrap(),
+                    None,
+                    ReportAggregationState::Start,
+                ))
+                .await?;
+            }
+            Ok(())
+        })
+    })
+    .await
+    .unwrap();
+
+    (*task.id(), aggregation_job_id)
+}
+
+/// Reads report aggregations, then reads each client report with a separate query.
+async fn read_per_report(
+    ds: &Datastore<MockClock>,
+    task_id: TaskId,
+    aggregation_job_id: AggregationJobId,
+) {
+    ds.run_tx(|tx| {
+        Box::pin(async move {
+            let vdaf = dummy_vdaf::Vdaf::new();
+            let report_aggregations = tx
+                .get_report_aggregations_for_aggregation_job(
+                    &vdaf,
+                    &Role::Leader,
+                    &task_id,
+                    &aggregation_job_id,
+                )
+                .await?;
+            try_join_all(
+                report_aggregations
+                    .iter()
+                    .map(|ra| tx.get_client_report(&vdaf, &task_id, ra.report_id())),
+            )
+            .await
+        })
+    })
+    .await
+    .unwrap();
+}
+
+/// Reads report aggregations & client reports with a single query.
+async fn read_batched(
+    ds: &Datastore<MockClock>,
+    task_id: TaskId,
+    aggregation_job_id: AggregationJobId,
+) {
+    ds.run_tx(|tx| {
+        Box::pin(async move {
+            tx.get_report_aggregations_and_client_reports_for_aggregation_job(
+                &dummy_vdaf::Vdaf::new(),
+                &task_id,
+                &aggregation_job_id,
+            )
+            .await
+        })
+    })
+    .await
+    .unwrap();
+}
+
+fn aggregation_job_report_queries(c: &mut Criterion) {
+    let runtime = Runtime::new().unwrap();
+    let ephemeral_datastore = runtime.block_on(ephemeral_datastore());
+    let ds = Arc::new(runtime.block_on(ephemeral_datastore.datastore(MockClock::default())));
+
+    let mut group = c.benchmark_group("aggregation_job_report_queries");
+    for report_count in REPORT_COUNTS {
+        let (task_id, aggregation_job_id) = runtime.block_on(setup(&ds, report_count));
+
+        group.bench_with_input(
+            BenchmarkId::new("per_report", report_count),
+            &report_count,
+            |b, _| {
+                b.to_async(&runtime)
+                    .iter(|| read_per_report(&ds, task_id, aggregation_job_id))
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("batched", report_count),
+            &report_count,
+            |b, _| {
+                b.to_async(&runtime)
+                    .iter(|| read_batched(&ds, task_id, aggregation_job_id))
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, aggregation_job_report_queries);
+criterion_main!(benches);
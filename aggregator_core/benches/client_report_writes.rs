@@ -0,0 +1,119 @@
+//! Benchmarks comparing strategies for writing a batch of uploaded client reports in the leader,
+//! at the batch sizes the report writer may be configured to use via `max_upload_batch_size`.
+//!
+//! These benchmarks require a Postgres instance, obtained in the same way as for the datastore
+//! tests.
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use futures::future::try_join_all;
+use janus_aggregator_core::{
+    datastore::{
+        models::{EncodedLeaderStoredReport, LeaderStoredReport},
+        test_util::ephemeral_datastore,
+        Datastore,
+    },
+    task::{test_util::TaskBuilder, QueryType},
+};
+use janus_core::{task::VdafInstance, test_util::dummy_vdaf, time::MockClock};
+use janus_messages::{Role, TaskId, Time};
+use std::sync::Arc;
+use tokio::runtime::Runtime;
+
+const MAX_UPLOAD_BATCH_SIZES: [usize; 4] = [1, 10, 100, 1000];
+
+/// Generates `batch_size` new client reports for the given task.
+fn reports(task_id: TaskId, batch_size: usize) -> Vec<LeaderStoredReport<0, dummy_vdaf::Vdaf>> {
+    let time = Time::from_seconds_since_epoch(1000);
+    (0..batch_size)
+        .map(|_| LeaderStoredReport::new_dummy(task_id, time))
+        .collect()
+}
+
+/// Writes each client report with a separate query.
+async fn write_per_report(
+    ds: &Datastore<MockClock>,
+    reports: Vec<LeaderStoredReport<0, dummy_vdaf::Vdaf>>,
+) {
+    let reports = Arc::new(reports);
+    ds.run_tx(|tx| {
+        let reports = Arc::clone(&reports);
+        Box::pin(async move {
+            let vdaf = dummy_vdaf::Vdaf::new();
+            try_join_all(
+                reports
+                    .iter()
+                    .map(|report| tx.put_client_report(&vdaf, report)),
+            )
+            .await
+        })
+    })
+    .await
+    .unwrap();
+}
+
+/// Writes all client reports with a single query.
+async fn write_batched(
+    ds: &Datastore<MockClock>,
+    reports: Vec<LeaderStoredReport<0, dummy_vdaf::Vdaf>>,
+) {
+    let reports: Arc<Vec<_>> = Arc::new(
+        reports
+            .iter()
+            .map(EncodedLeaderStoredReport::from)
+            .collect(),
+    );
+    ds.run_tx(|tx| {
+        let reports = Arc::clone(&reports);
+        Box::pin(async move {
+            tx.put_client_reports(&reports.iter().collect::<Vec<_>>())
+                .await
+        })
+    })
+    .await
+    .unwrap();
+}
+
+fn client_report_writes(c: &mut Criterion) {
+    let runtime = Runtime::new().unwrap();
+    let ephemeral_datastore = runtime.block_on(ephemeral_datastore());
+    let ds = Arc::new(runtime.block_on(ephemeral_datastore.datastore(MockClock::default())));
+
+    let task = TaskBuilder::new(QueryType::TimeInterval, VdafInstance::Fake, Role::Leader).build();
+    runtime
+        .block_on(ds.run_tx(|tx| {
+            let task = task.clone();
+            Box::pin(async move { tx.put_task(&task).await })
+        }))
+        .unwrap();
+
+    let mut group = c.benchmark_group("client_report_writes");
+    for batch_size in MAX_UPLOAD_BATCH_SIZES {
+        group.throughput(Throughput::Elements(batch_size.try_into().unwrap()));
+        group.bench_with_input(
+            BenchmarkId::new("per_report", batch_size),
+            &batch_size,
+            |b, &batch_size| {
+                b.to_async(&runtime).iter_batched(
+                    || reports(*task.id(), batch_size),
+                    |reports| write_per_report(&ds, reports),
+                    BatchSize::SmallInput,
+                )
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("batched", batch_size),
+            &batch_size,
+            |b, &batch_size| {
+                b.to_async(&runtime).iter_batched(
+                    || reports(*task.id(), batch_size),
+                    |reports| write_batched(&ds, reports),
+                    BatchSize::SmallInput,
+                )
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, client_report_writes);
+criterion_main!(benches);
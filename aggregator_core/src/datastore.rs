@@ -3,14 +3,15 @@
 use self::models::{
     AcquiredAggregationJob, AcquiredCollectionJob, AggregateShareJob, AggregationJob,
     AggregatorRole, AuthenticationTokenType, Batch, BatchAggregation, CollectionJob,
-    CollectionJobState, CollectionJobStateCode, GlobalHpkeKeypair, HpkeKeyState,
-    LeaderStoredReport, Lease, LeaseToken, OutstandingBatch, ReportAggregation,
-    ReportAggregationState, ReportAggregationStateCode, SqlInterval,
+    CollectionJobState, CollectionJobStateCode, EncodedLeaderStoredReport, GlobalHpkeKeypair,
+    HpkeKeyState, ImportedHpkeKeypair, LeaderStoredReport, Lease, LeaseToken, OutstandingBatch,
+    ReportAggregation, ReportAggregationState, ReportAggregationStateCode, SqlInterval,
+    TaskMetrics, TaskMetricsTimeBucket,
 };
 use crate::{
     query_type::{AccumulableQueryType, CollectableQueryType},
     task::{self, Task},
-    taskprov::{self, PeerAggregator},
+    taskprov::{self, PeerAggregator, TaskprovPolicy},
     SecretBytes,
 };
 use anyhow::anyhow;
@@ -23,9 +24,9 @@ use janus_core::{
 };
 use janus_messages::{
     query_type::{FixedSize, QueryType, TimeInterval},
-    AggregationJobId, BatchId, CollectionJobId, Duration, Extension, HpkeCiphertext, HpkeConfig,
-    HpkeConfigId, Interval, PrepareStep, Query, ReportId, ReportIdChecksum, ReportMetadata,
-    ReportShare, Role, TaskId, Time,
+    AggregationJobId, BatchId, Collection, CollectionJobId, Duration, Extension, HpkeCiphertext,
+    HpkeConfig, HpkeConfigId, Interval, PrepareStep, Query, ReportId, ReportIdChecksum,
+    ReportMetadata, ReportShare, ReportShareError, Role, TaskId, Time,
 };
 use opentelemetry::{
     metrics::{Counter, Histogram, Meter, Unit},
@@ -39,7 +40,7 @@ use prio::{
 use rand::random;
 use ring::aead::{self, LessSafeKey, AES_128_GCM};
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     convert::TryFrom,
     fmt::{Debug, Display},
     future::Future,
@@ -48,7 +49,7 @@ use std::{
     ops::RangeInclusive,
     pin::Pin,
     sync::{
-        atomic::{AtomicBool, Ordering},
+        atomic::{AtomicBool, AtomicUsize, Ordering},
         Arc, Mutex,
     },
     time::{Duration as StdDuration, Instant},
@@ -100,12 +101,14 @@ macro_rules! supported_schema_versions {
 // version is seen, [`Datastore::new`] fails.
 //
 // Note that the latest supported version must be first in the list.
-supported_schema_versions!(1);
+supported_schema_versions!(8);
 
 /// Datastore represents a datastore for Janus, with support for transactional reads and writes.
 /// In practice, Datastore instances are currently backed by a PostgreSQL database.
 pub struct Datastore<C: Clock> {
     pool: deadpool_postgres::Pool,
+    read_replica_pools: Vec<deadpool_postgres::Pool>,
+    next_read_replica: AtomicUsize,
     crypter: Crypter,
     clock: C,
     transaction_status_counter: Counter<u64>,
@@ -184,6 +187,8 @@ impl<C: Clock> Datastore<C> {
 
         Self {
             pool,
+            read_replica_pools: Vec::new(),
+            next_read_replica: AtomicUsize::new(0),
             crypter,
             clock,
             transaction_status_counter,
@@ -192,6 +197,15 @@ impl<C: Clock> Datastore<C> {
         }
     }
 
+    /// Configures the datastore to run read-only transactions started with
+    /// [`Datastore::run_read_only_tx`] against the given connection pools, which should connect to
+    /// read replicas of the primary database. Transactions are distributed across the replicas in
+    /// round-robin fashion.
+    pub fn with_read_replicas(mut self, read_replica_pools: Vec<deadpool_postgres::Pool>) -> Self {
+        self.read_replica_pools = read_replica_pools;
+        self
+    }
+
     /// run_tx runs a transaction, whose body is determined by the given function. The transaction
     /// is committed if the body returns a successful value, and rolled back if the body returns an
     /// error value.
@@ -214,13 +228,67 @@ impl<C: Clock> Datastore<C> {
     /// transaction, for use in database-related metrics.
     #[tracing::instrument(level = "trace", skip(self, f))]
     pub async fn run_tx_with_name<F, T>(&self, name: &'static str, f: F) -> Result<T, Error>
+    where
+        for<'a> F:
+            Fn(&'a Transaction<C>) -> Pin<Box<dyn Future<Output = Result<T, Error>> + Send + 'a>>,
+    {
+        self.run_tx_with_pool(name, &self.pool, false, f).await
+    }
+
+    /// run_read_only_tx runs a read-only transaction, whose body is determined by the given
+    /// function. If the datastore was configured with read replicas, the transaction runs against
+    /// one of them; otherwise, it runs against the primary database.
+    ///
+    /// Read replicas may lag behind the primary database, so the transaction may not observe
+    /// recently-committed writes. This method should only be used for reads that can tolerate
+    /// stale data, and which are not used to decide on later writes. Any attempt to write in the
+    /// transaction will fail.
+    pub fn run_read_only_tx<'s, F, T>(&'s self, f: F) -> impl Future<Output = Result<T, Error>> + 's
+    where
+        F: 's,
+        T: 's,
+        for<'a> F:
+            Fn(&'a Transaction<C>) -> Pin<Box<dyn Future<Output = Result<T, Error>> + Send + 'a>>,
+    {
+        self.run_read_only_tx_with_name("default_read_only", f)
+    }
+
+    /// See [`Datastore::run_read_only_tx`]. This method additionally allows specifying a name for
+    /// the transaction, for use in database-related metrics.
+    #[tracing::instrument(level = "trace", skip(self, f))]
+    pub async fn run_read_only_tx_with_name<F, T>(
+        &self,
+        name: &'static str,
+        f: F,
+    ) -> Result<T, Error>
+    where
+        for<'a> F:
+            Fn(&'a Transaction<C>) -> Pin<Box<dyn Future<Output = Result<T, Error>> + Send + 'a>>,
+    {
+        let pool = if self.read_replica_pools.is_empty() {
+            &self.pool
+        } else {
+            let index = self.next_read_replica.fetch_add(1, Ordering::Relaxed)
+                % self.read_replica_pools.len();
+            &self.read_replica_pools[index]
+        };
+        self.run_tx_with_pool(name, pool, true, f).await
+    }
+
+    async fn run_tx_with_pool<F, T>(
+        &self,
+        name: &'static str,
+        pool: &deadpool_postgres::Pool,
+        read_only: bool,
+        f: F,
+    ) -> Result<T, Error>
     where
         for<'a> F:
             Fn(&'a Transaction<C>) -> Pin<Box<dyn Future<Output = Result<T, Error>> + Send + 'a>>,
     {
         loop {
             let before = Instant::now();
-            let (rslt, retry) = self.run_tx_once(&f).await;
+            let (rslt, retry) = self.run_tx_once(pool, read_only, &f).await;
             let elapsed = before.elapsed();
             self.transaction_duration_histogram
                 .record(elapsed.as_secs_f64(), &[KeyValue::new("tx", name)]);
@@ -242,19 +310,25 @@ impl<C: Clock> Datastore<C> {
     }
 
     #[tracing::instrument(level = "trace", skip_all)]
-    async fn run_tx_once<F, T>(&self, f: &F) -> (Result<T, Error>, bool)
+    async fn run_tx_once<F, T>(
+        &self,
+        pool: &deadpool_postgres::Pool,
+        read_only: bool,
+        f: &F,
+    ) -> (Result<T, Error>, bool)
     where
         for<'a> F:
             Fn(&'a Transaction<C>) -> Pin<Box<dyn Future<Output = Result<T, Error>> + Send + 'a>>,
     {
         // Open transaction.
-        let mut client = match self.pool.get().await {
+        let mut client = match pool.get().await {
             Ok(client) => client,
             Err(err) => return (Err(err.into()), false),
         };
         let raw_tx = match client
             .build_transaction()
             .isolation_level(IsolationLevel::RepeatableRead)
+            .read_only(read_only)
             .start()
             .await
         {
@@ -496,6 +570,10 @@ impl<C: Clock> Transaction<'_, C> {
         self.run_op(self.raw_tx.query_opt(statement, params)).await
     }
 
+    async fn batch_execute(&self, query: &str) -> Result<(), tokio_postgres::Error> {
+        self.run_op(self.raw_tx.batch_execute(query)).await
+    }
+
     /// Calling this method will force the transaction to eventually be rolled back and retried; all
     /// datastore writes in this try will be lost. Calling this method does not interrupt or
     /// otherwise directly affect the transaction-processing callback; the caller may wish to e.g.
@@ -533,8 +611,9 @@ impl<C: Clock> Transaction<'_, C> {
                     task_id, aggregator_role, leader_aggregator_endpoint,
                     helper_aggregator_endpoint, query_type, vdaf, max_batch_query_count,
                     task_expiration, report_expiry_age, min_batch_size, time_precision,
-                    tolerable_clock_skew, collector_hpke_config)
-                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+                    tolerable_clock_skew, priority, dp_config, collector_hpke_config,
+                    taskprov_task_config)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16)
                 ON CONFLICT DO NOTHING",
             )
             .await?;
@@ -568,10 +647,13 @@ impl<C: Clock> Transaction<'_, C> {
                     &i64::try_from(task.time_precision().as_seconds())?,
                     /* tolerable_clock_skew */
                     &i64::try_from(task.tolerable_clock_skew().as_seconds())?,
+                    /* priority */ task.priority(),
+                    /* dp_config */ &Json(task.dp_config()),
                     /* collector_hpke_config */
                     &task
                         .collector_hpke_config()
                         .map(|config| config.get_encoded()),
+                    /* taskprov_task_config */ &task.taskprov_task_config(),
                 ],
             )
             .await?,
@@ -746,7 +828,8 @@ impl<C: Clock> Transaction<'_, C> {
             .prepare_cached(
                 "SELECT aggregator_role, leader_aggregator_endpoint, helper_aggregator_endpoint,
                     query_type, vdaf, max_batch_query_count, task_expiration, report_expiry_age,
-                    min_batch_size, time_precision, tolerable_clock_skew, collector_hpke_config
+                    min_batch_size, time_precision, tolerable_clock_skew, priority, dp_config,
+                    collector_hpke_config, taskprov_task_config
                 FROM tasks WHERE task_id = $1",
             )
             .await?;
@@ -819,7 +902,8 @@ impl<C: Clock> Transaction<'_, C> {
                 "SELECT task_id, aggregator_role, leader_aggregator_endpoint,
                     helper_aggregator_endpoint, query_type, vdaf, max_batch_query_count,
                     task_expiration, report_expiry_age, min_batch_size, time_precision,
-                    tolerable_clock_skew, collector_hpke_config
+                    tolerable_clock_skew, priority, dp_config, collector_hpke_config,
+                    taskprov_task_config
                 FROM tasks",
             )
             .await?;
@@ -972,10 +1056,13 @@ impl<C: Clock> Transaction<'_, C> {
         let time_precision = Duration::from_seconds(row.get_bigint_and_convert("time_precision")?);
         let tolerable_clock_skew =
             Duration::from_seconds(row.get_bigint_and_convert("tolerable_clock_skew")?);
+        let priority = row.try_get("priority")?;
+        let dp_config = row.try_get::<_, Json<task::DpConfig>>("dp_config")?.0;
         let collector_hpke_config = row
             .get::<_, Option<Vec<u8>>>("collector_hpke_config")
             .map(|config| HpkeConfig::get_decoded(&config))
             .transpose()?;
+        let taskprov_task_config = row.get::<_, Option<Vec<u8>>>("taskprov_task_config");
 
         // Aggregator authentication tokens.
         let mut aggregator_auth_tokens = Vec::new();
@@ -1066,10 +1153,13 @@ impl<C: Clock> Transaction<'_, C> {
             min_batch_size,
             time_precision,
             tolerable_clock_skew,
+            priority,
+            dp_config,
             collector_hpke_config,
             aggregator_auth_tokens,
             collector_auth_tokens,
             hpke_keypairs,
+            taskprov_task_config,
         );
         // Trial validation through all known schemes. This is a workaround to avoid extending the
         // schema to track the provenance of tasks. If we do end up implementing a task provenance
@@ -1095,44 +1185,236 @@ impl<C: Clock> Transaction<'_, C> {
         Ok(task)
     }
 
-    /// Retrieves report & report aggregation metrics for a given task: either a tuple
-    /// `Some((report_count, report_aggregation_count))`, or None if the task does not exist.
+    /// Retrieves metrics describing the reports, aggregations, batches and collections of a given
+    /// task, or None if the task does not exist. If `time_bucket_size` is provided, report and
+    /// report aggregation counts are additionally broken down into buckets of that size, by client
+    /// timestamp.
     #[tracing::instrument(skip(self), err)]
-    pub async fn get_task_metrics(&self, task_id: &TaskId) -> Result<Option<(u64, u64)>, Error> {
+    pub async fn get_task_metrics(
+        &self,
+        task_id: &TaskId,
+        time_bucket_size: Option<Duration>,
+    ) -> Result<Option<TaskMetrics>, Error> {
         let stmt = self
+            .prepare_cached("SELECT id FROM tasks WHERE task_id = $1")
+            .await?;
+        let task_info = match self
+            .query_opt(&stmt, &[/* task_id */ &task_id.as_ref()])
+            .await?
+        {
+            Some(row) => row.get::<_, i64>("id"),
+            None => return Ok(None),
+        };
+        let now = self.clock.now().as_naive_date_time()?;
+        let params: &[&(dyn ToSql + Sync)] = &[/* task_id */ &task_info, /* now */ &now];
+
+        let reports_stmt = self
             .prepare_cached(
                 "SELECT
-                    (SELECT COUNT(*) FROM tasks WHERE task_id = $1) AS task_count,
-                    (SELECT COUNT(*) FROM client_reports
-                     JOIN tasks ON tasks.id = client_reports.task_id
-                     WHERE tasks.task_id = $1
-                       AND client_reports.client_timestamp >= COALESCE($2::TIMESTAMP - tasks.report_expiry_age * '1 second'::INTERVAL, '-infinity'::TIMESTAMP)) AS report_count,
-                    (SELECT COUNT(*) FROM aggregation_jobs
-                     JOIN tasks ON tasks.id = aggregation_jobs.task_id
-                     RIGHT JOIN report_aggregations ON report_aggregations.aggregation_job_id = aggregation_jobs.id
-                     WHERE tasks.task_id = $1
-                       AND UPPER(aggregation_jobs.client_timestamp_interval) >= COALESCE($2::TIMESTAMP - tasks.report_expiry_age * '1 second'::INTERVAL, '-infinity'::TIMESTAMP)) AS report_aggregation_count",
+                    COUNT(*) AS reports,
+                    COUNT(*) FILTER (WHERE NOT aggregation_started) AS unaggregated_reports
+                FROM client_reports
+                JOIN tasks ON tasks.id = client_reports.task_id
+                WHERE tasks.id = $1
+                  AND client_reports.client_timestamp >= COALESCE($2::TIMESTAMP - tasks.report_expiry_age * '1 second'::INTERVAL, '-infinity'::TIMESTAMP)",
             )
             .await?;
-        let row = self
-            .query_one(
-                &stmt,
-                &[
-                    /* task_id */ &task_id.as_ref(),
-                    /* now */ &self.clock.now().as_naive_date_time()?,
-                ],
+        let report_aggregations_stmt = self
+            .prepare_cached(
+                "SELECT report_aggregations.state, report_aggregations.error_code, COUNT(*) AS count
+                FROM report_aggregations
+                JOIN aggregation_jobs ON aggregation_jobs.id = report_aggregations.aggregation_job_id
+                JOIN tasks ON tasks.id = aggregation_jobs.task_id
+                WHERE tasks.id = $1
+                  AND UPPER(aggregation_jobs.client_timestamp_interval) >= COALESCE($2::TIMESTAMP - tasks.report_expiry_age * '1 second'::INTERVAL, '-infinity'::TIMESTAMP)
+                GROUP BY report_aggregations.state, report_aggregations.error_code",
+            )
+            .await?;
+        let aggregation_jobs_stmt = self
+            .prepare_cached(
+                "SELECT aggregation_jobs.state, COUNT(*) AS count
+                FROM aggregation_jobs
+                JOIN tasks ON tasks.id = aggregation_jobs.task_id
+                WHERE tasks.id = $1
+                  AND UPPER(aggregation_jobs.client_timestamp_interval) >= COALESCE($2::TIMESTAMP - tasks.report_expiry_age * '1 second'::INTERVAL, '-infinity'::TIMESTAMP)
+                GROUP BY aggregation_jobs.state",
+            )
+            .await?;
+        let batches_stmt = self
+            .prepare_cached(
+                "SELECT batches.state, COUNT(*) AS count
+                FROM batches
+                JOIN tasks ON tasks.id = batches.task_id
+                WHERE tasks.id = $1
+                  AND UPPER(COALESCE(batches.batch_interval, batches.client_timestamp_interval)) >= COALESCE($2::TIMESTAMP - tasks.report_expiry_age * '1 second'::INTERVAL, '-infinity'::TIMESTAMP)
+                GROUP BY batches.state",
+            )
+            .await?;
+        let outstanding_batches_stmt = self
+            .prepare_cached(
+                "SELECT batch_id FROM outstanding_batches
+                JOIN tasks ON tasks.id = outstanding_batches.task_id
+                JOIN batches ON batches.task_id = outstanding_batches.task_id
+                            AND batches.batch_identifier = outstanding_batches.batch_id
+                WHERE tasks.id = $1
+                  AND UPPER(batches.client_timestamp_interval) >= COALESCE($2::TIMESTAMP - tasks.report_expiry_age * '1 second'::INTERVAL, '-infinity'::TIMESTAMP)
+                ORDER BY outstanding_batches.time_bucket_start, outstanding_batches.batch_id",
+            )
+            .await?;
+        // Time-interval collection jobs are expired along with their batch interval, while
+        // fixed-size collection jobs are expired along with the batch they collect.
+        let collection_jobs_stmt = self
+            .prepare_cached(
+                "SELECT collection_jobs.state, COUNT(*) AS count
+                FROM collection_jobs
+                JOIN tasks ON tasks.id = collection_jobs.task_id
+                LEFT JOIN batches ON batches.task_id = collection_jobs.task_id
+                                 AND batches.batch_identifier = collection_jobs.batch_identifier
+                                 AND batches.aggregation_param = collection_jobs.aggregation_param
+                WHERE tasks.id = $1
+                  AND COALESCE(UPPER(collection_jobs.batch_interval), UPPER(batches.client_timestamp_interval), 'infinity'::TIMESTAMP) >= COALESCE($2::TIMESTAMP - tasks.report_expiry_age * '1 second'::INTERVAL, '-infinity'::TIMESTAMP)
+                GROUP BY collection_jobs.state",
             )
             .await?;
 
-        let task_count: u64 = row.get_bigint_and_convert("task_count")?;
-        if task_count == 0 {
-            return Ok(None);
+        let (
+            reports_row,
+            report_aggregations_rows,
+            aggregation_jobs_rows,
+            batches_rows,
+            outstanding_batches_rows,
+            collection_jobs_rows,
+        ) = try_join!(
+            self.query_one(&reports_stmt, params),
+            self.query(&report_aggregations_stmt, params),
+            self.query(&aggregation_jobs_stmt, params),
+            self.query(&batches_stmt, params),
+            self.query(&outstanding_batches_stmt, params),
+            self.query(&collection_jobs_stmt, params),
+        )?;
+
+        let mut report_aggregations_by_state = BTreeMap::new();
+        let mut report_aggregation_failures = BTreeMap::new();
+        for row in report_aggregations_rows {
+            let state: ReportAggregationStateCode = row.get("state");
+            let error_code: Option<i16> = row.get("error_code");
+            let count: u64 = row.get_bigint_and_convert("count")?;
+            *report_aggregations_by_state.entry(state).or_default() += count;
+            if let Some(error_code) = error_code {
+                let error_code: u8 = error_code.try_into().map_err(|err| {
+                    Error::DbState(format!("couldn't convert error_code value: {err}"))
+                })?;
+                let error = ReportShareError::try_from(error_code).map_err(|err| {
+                    Error::DbState(format!("couldn't convert error_code value: {err}"))
+                })?;
+                *report_aggregation_failures.entry(error).or_default() += count;
+            }
+        }
+
+        let outstanding_batches =
+            try_join_all(outstanding_batches_rows.into_iter().map(|row| async move {
+                let batch_id = BatchId::get_decoded(row.get("batch_id"))?;
+                let size = self.read_batch_size(task_id, &batch_id).await?;
+                Ok::<_, Error>(OutstandingBatch::new(*task_id, batch_id, size))
+            }))
+            .await?;
+
+        let time_buckets = match time_bucket_size {
+            Some(time_bucket_size) => Some(
+                self.get_task_metrics_time_buckets(task_info, &now, &time_bucket_size)
+                    .await?,
+            ),
+            None => None,
+        };
+
+        Ok(Some(TaskMetrics {
+            reports: reports_row.get_bigint_and_convert("reports")?,
+            unaggregated_reports: reports_row.get_bigint_and_convert("unaggregated_reports")?,
+            report_aggregations_by_state,
+            report_aggregation_failures,
+            aggregation_jobs_by_state: state_counts(aggregation_jobs_rows)?,
+            batches_by_state: state_counts(batches_rows)?,
+            outstanding_batches,
+            collection_jobs_by_state: state_counts(collection_jobs_rows)?,
+            time_buckets,
+        }))
+    }
+
+    /// Retrieves report & report aggregation counts for a task, bucketed by client timestamp.
+    async fn get_task_metrics_time_buckets(
+        &self,
+        task_info: i64,
+        now: &NaiveDateTime,
+        time_bucket_size: &Duration,
+    ) -> Result<Vec<TaskMetricsTimeBucket>, Error> {
+        let time_bucket_size = i64::try_from(time_bucket_size.as_seconds())?;
+        if time_bucket_size == 0 {
+            return Err(Error::InvalidParameter("time_bucket_size"));
         }
+        let params: &[&(dyn ToSql + Sync)] = &[
+            /* task_id */ &task_info,
+            /* now */ now,
+            /* time_bucket_size */ &time_bucket_size,
+        ];
 
-        Ok(Some((
-            row.get_bigint_and_convert("report_count")?,
-            row.get_bigint_and_convert("report_aggregation_count")?,
-        )))
+        let reports_stmt = self
+            .prepare_cached(
+                "SELECT
+                    FLOOR(EXTRACT(EPOCH FROM client_reports.client_timestamp))::BIGINT / $3 * $3 AS bucket_start,
+                    COUNT(*) AS reports,
+                    COUNT(*) FILTER (WHERE NOT aggregation_started) AS unaggregated_reports
+                FROM client_reports
+                JOIN tasks ON tasks.id = client_reports.task_id
+                WHERE tasks.id = $1
+                  AND client_reports.client_timestamp >= COALESCE($2::TIMESTAMP - tasks.report_expiry_age * '1 second'::INTERVAL, '-infinity'::TIMESTAMP)
+                GROUP BY bucket_start",
+            )
+            .await?;
+        let report_aggregations_stmt = self
+            .prepare_cached(
+                "SELECT
+                    FLOOR(EXTRACT(EPOCH FROM report_aggregations.client_timestamp))::BIGINT / $3 * $3 AS bucket_start,
+                    COUNT(*) AS report_aggregations
+                FROM report_aggregations
+                JOIN aggregation_jobs ON aggregation_jobs.id = report_aggregations.aggregation_job_id
+                JOIN tasks ON tasks.id = aggregation_jobs.task_id
+                WHERE tasks.id = $1
+                  AND UPPER(aggregation_jobs.client_timestamp_interval) >= COALESCE($2::TIMESTAMP - tasks.report_expiry_age * '1 second'::INTERVAL, '-infinity'::TIMESTAMP)
+                GROUP BY bucket_start",
+            )
+            .await?;
+        let (reports_rows, report_aggregations_rows) = try_join!(
+            self.query(&reports_stmt, params),
+            self.query(&report_aggregations_stmt, params),
+        )?;
+
+        fn time_bucket<'a>(
+            time_buckets: &'a mut BTreeMap<u64, TaskMetricsTimeBucket>,
+            row: &Row,
+        ) -> Result<&'a mut TaskMetricsTimeBucket, Error> {
+            let start: u64 = row.get_bigint_and_convert("bucket_start")?;
+            Ok(time_buckets
+                .entry(start)
+                .or_insert_with(|| TaskMetricsTimeBucket {
+                    start: Time::from_seconds_since_epoch(start),
+                    reports: 0,
+                    unaggregated_reports: 0,
+                    report_aggregations: 0,
+                }))
+        }
+
+        let mut time_buckets = BTreeMap::new();
+        for row in reports_rows {
+            let time_bucket = time_bucket(&mut time_buckets, &row)?;
+            time_bucket.reports = row.get_bigint_and_convert("reports")?;
+            time_bucket.unaggregated_reports =
+                row.get_bigint_and_convert("unaggregated_reports")?;
+        }
+        for row in report_aggregations_rows {
+            time_bucket(&mut time_buckets, &row)?.report_aggregations =
+                row.get_bigint_and_convert("report_aggregations")?;
+        }
+        Ok(time_buckets.into_values().collect())
     }
 
     /// Retrieves task IDs, optionally after some specified lower bound. This method returns tasks
@@ -1541,6 +1823,51 @@ impl<C: Clock> Transaction<'_, C> {
             .try_into()?)
     }
 
+    /// Return the number of reports in each of the provided batches of a task, counted in the same
+    /// way as [`Self::count_client_reports_for_batch_id`]. Batches without any reports are omitted
+    /// from the result. Applies only to fixed-size queries.
+    #[tracing::instrument(skip(self), err)]
+    pub async fn count_client_reports_for_batch_ids(
+        &self,
+        task_id: &TaskId,
+        batch_ids: &[BatchId],
+    ) -> Result<HashMap<BatchId, u64>, Error> {
+        let batch_ids: Vec<&[u8]> = batch_ids
+            .iter()
+            .map(|batch_id| batch_id.as_ref().as_slice())
+            .collect();
+        let stmt = self
+            .prepare_cached(
+                "SELECT aggregation_jobs.batch_id,
+                    COUNT(DISTINCT report_aggregations.client_report_id) AS count
+                FROM report_aggregations
+                JOIN aggregation_jobs ON aggregation_jobs.id = report_aggregations.aggregation_job_id
+                JOIN tasks ON tasks.id = aggregation_jobs.task_id
+                WHERE tasks.task_id = $1
+                  AND aggregation_jobs.batch_id = ANY($2)
+                  AND UPPER(aggregation_jobs.client_timestamp_interval) >= COALESCE($3::TIMESTAMP - tasks.report_expiry_age * '1 second'::INTERVAL, '-infinity'::TIMESTAMP)
+                GROUP BY aggregation_jobs.batch_id",
+            )
+            .await?;
+        self.query(
+            &stmt,
+            &[
+                /* task_id */ task_id.as_ref(),
+                /* batch_ids */ &batch_ids,
+                /* now */ &self.clock.now().as_naive_date_time()?,
+            ],
+        )
+        .await?
+        .into_iter()
+        .map(|row| {
+            Ok((
+                BatchId::get_decoded(row.get("batch_id"))?,
+                row.get::<_, i64>("count").try_into()?,
+            ))
+        })
+        .collect()
+    }
+
     /// `put_client_report` stores a client report, the associated plaintext leader input share and
     /// the associated encrypted helper share. Returns `Ok(())` if the write succeeds, or if there
     /// was already a row in the table matching `new_report`. Returns an error if something goes
@@ -1562,6 +1889,9 @@ impl<C: Clock> Transaction<'_, C> {
         let mut encoded_extensions = Vec::new();
         encode_u16_items(&mut encoded_extensions, &(), new_report.leader_extensions());
 
+        // The NOT EXISTS check is needed in addition to the ON CONFLICT clause because, if the
+        // client_reports table is partitioned, its unique constraint also covers the client
+        // timestamp (see `partition_report_tables`).
         let stmt = self
             .prepare_cached(
                 "INSERT INTO client_reports (
@@ -1573,7 +1903,11 @@ impl<C: Clock> Transaction<'_, C> {
                     leader_input_share,
                     helper_encrypted_input_share
                 )
-                VALUES ((SELECT id FROM tasks WHERE task_id = $1), $2, $3, $4, $5, $6, $7)
+                SELECT (SELECT id FROM tasks WHERE task_id = $1), $2, $3, $4, $5, $6, $7
+                WHERE NOT EXISTS (
+                    SELECT 1 FROM client_reports
+                    WHERE task_id = (SELECT id FROM tasks WHERE task_id = $1) AND report_id = $2
+                )
                 ON CONFLICT DO NOTHING
                 RETURNING COALESCE(client_timestamp < COALESCE($3::TIMESTAMP - (SELECT report_expiry_age FROM tasks WHERE task_id = $1) * '1 second'::INTERVAL, '-infinity'::TIMESTAMP), FALSE) AS is_expired",
             )
@@ -1678,6 +2012,209 @@ impl<C: Clock> Transaction<'_, C> {
         Ok(())
     }
 
+    /// `put_client_reports` stores a number of client reports, which may belong to different
+    /// tasks, with a single statement. This is equivalent to, but much faster than, calling
+    /// [`Self::put_client_report`] for each report.
+    ///
+    /// The outer result indicates whether the statement succeeded. The inner results correspond
+    /// to `reports`, in order: each is `Ok(())` if the report was written or an identical report
+    /// already existed (including earlier in `reports`), `Err(Error::MutationTargetAlreadyExists)`
+    /// if its report ID is already in use with different values, or
+    /// `Err(Error::MutationTargetNotFound)` if its task does not exist.
+    #[tracing::instrument(skip_all, fields(report_count = reports.len()), err)]
+    pub async fn put_client_reports(
+        &self,
+        reports: &[&EncodedLeaderStoredReport],
+    ) -> Result<Vec<Result<(), Error>>, Error> {
+        // Reports repeated within the batch are written once, and compared to their first
+        // occurrence afterwards.
+        let mut first_occurrences = HashMap::new();
+        let mut unique_reports = Vec::new();
+        for (index, report) in reports.iter().enumerate() {
+            first_occurrences
+                .entry((report.task_id(), report.metadata().id()))
+                .or_insert_with(|| {
+                    unique_reports.push(*report);
+                    (index, unique_reports.len() - 1)
+                });
+        }
+
+        let task_ids: Vec<&[u8]> = unique_reports
+            .iter()
+            .map(|r| r.task_id().as_ref().as_slice())
+            .collect();
+        let report_ids: Vec<&[u8]> = unique_reports
+            .iter()
+            .map(|r| r.metadata().id().as_ref().as_slice())
+            .collect();
+        let client_timestamps = unique_reports
+            .iter()
+            .map(|r| r.metadata().time().as_naive_date_time())
+            .collect::<Result<Vec<_>, _>>()?;
+        let extensions: Vec<&[u8]> = unique_reports
+            .iter()
+            .map(|r| r.leader_extensions())
+            .collect();
+        let public_shares: Vec<&[u8]> = unique_reports.iter().map(|r| r.public_share()).collect();
+        let leader_input_shares: Vec<&[u8]> = unique_reports
+            .iter()
+            .map(|r| r.leader_input_share())
+            .collect();
+        let helper_encrypted_input_shares: Vec<&[u8]> = unique_reports
+            .iter()
+            .map(|r| r.helper_encrypted_input_share())
+            .collect();
+
+        // As in `put_client_report`, expired reports are not written, and reports whose ID is
+        // already in use are not written. The final SELECT reads from the snapshot taken before
+        // the INSERT, so it returns only pre-existing rows for comparison. (Which reports were
+        // inserted is inferred from the number of inserted rows, rather than by joining against
+        // them: the planner can't estimate the size of the input arrays, and picks a join that is
+        // quadratic in the batch size.)
+        let stmt = self
+            .prepare_cached(
+                "WITH input AS (
+                    SELECT * FROM UNNEST(
+                        $1::BYTEA[], $2::BYTEA[], $3::TIMESTAMP[], $4::BYTEA[], $5::BYTEA[],
+                        $6::BYTEA[], $7::BYTEA[]
+                    ) WITH ORDINALITY AS input(
+                        task_id, report_id, client_timestamp, extensions, public_share,
+                        leader_input_share, helper_encrypted_input_share, ord
+                    )
+                ),
+                new_reports AS (
+                    SELECT tasks.id AS task_id, input.report_id, input.client_timestamp,
+                        input.extensions, input.public_share, input.leader_input_share,
+                        input.helper_encrypted_input_share, input.ord,
+                        COALESCE(input.client_timestamp < COALESCE($8::TIMESTAMP - tasks.report_expiry_age * '1 second'::INTERVAL, '-infinity'::TIMESTAMP), FALSE) AS is_expired
+                    FROM input
+                    JOIN tasks ON tasks.task_id = input.task_id
+                ),
+                inserted AS (
+                    INSERT INTO client_reports (
+                        task_id,
+                        report_id,
+                        client_timestamp,
+                        extensions,
+                        public_share,
+                        leader_input_share,
+                        helper_encrypted_input_share
+                    )
+                    SELECT task_id, report_id, client_timestamp, extensions, public_share,
+                        leader_input_share, helper_encrypted_input_share
+                    FROM new_reports
+                    WHERE NOT is_expired AND NOT EXISTS (
+                        SELECT 1 FROM client_reports
+                        WHERE client_reports.task_id = new_reports.task_id
+                          AND client_reports.report_id = new_reports.report_id
+                    )
+                    ON CONFLICT DO NOTHING
+                    RETURNING 1
+                )
+                SELECT new_reports.ord, new_reports.is_expired,
+                    (SELECT COUNT(*) FROM inserted) AS inserted_count,
+                    client_reports.client_timestamp AS existing_client_timestamp,
+                    client_reports.extensions AS existing_extensions,
+                    client_reports.public_share AS existing_public_share,
+                    client_reports.leader_input_share AS existing_leader_input_share,
+                    client_reports.helper_encrypted_input_share
+                        AS existing_helper_encrypted_input_share
+                FROM new_reports
+                LEFT JOIN client_reports
+                    ON client_reports.task_id = new_reports.task_id
+                    AND client_reports.report_id = new_reports.report_id",
+            )
+            .await?;
+        let rows = self
+            .query(
+                &stmt,
+                &[
+                    /* task_id */ &task_ids,
+                    /* report_id */ &report_ids,
+                    /* client_timestamp */ &client_timestamps,
+                    /* extensions */ &extensions,
+                    /* public_share */ &public_shares,
+                    /* leader_input_share */ &leader_input_shares,
+                    /* helper_encrypted_input_share */ &helper_encrypted_input_shares,
+                    /* now */ &self.clock.now().as_naive_date_time()?,
+                ],
+            )
+            .await?;
+
+        // Reports with no rows belong to tasks that don't exist. A report may have several rows if
+        // the client_reports table is partitioned and its ID is in use with several timestamps;
+        // it is written successfully if any of them matches.
+        let mut unique_results: Vec<Result<(), Error>> = unique_reports
+            .iter()
+            .map(|_| Err(Error::MutationTargetNotFound))
+            .collect();
+        let mut inserted_count = 0;
+        let mut unwritten_indices = Vec::new();
+        for row in rows {
+            let index = usize::try_from(row.get::<_, i64>("ord") - 1)?;
+            let report = unique_reports[index];
+            inserted_count = row.get::<_, i64>("inserted_count");
+            let result = &mut unique_results[index];
+            if result.is_ok() {
+                continue;
+            }
+
+            *result = if row.get("is_expired") {
+                Ok(())
+            } else {
+                match row.get::<_, Option<NaiveDateTime>>("existing_client_timestamp") {
+                    Some(existing_client_timestamp) => {
+                        if existing_client_timestamp == client_timestamps[index]
+                            && row.get::<_, Option<&[u8]>>("existing_extensions")
+                                == Some(report.leader_extensions())
+                            && row.get::<_, Option<&[u8]>>("existing_public_share")
+                                == Some(report.public_share())
+                            && row.get::<_, Option<&[u8]>>("existing_leader_input_share")
+                                == Some(report.leader_input_share())
+                            && row.get::<_, Option<&[u8]>>("existing_helper_encrypted_input_share")
+                                == Some(report.helper_encrypted_input_share())
+                        {
+                            Ok(())
+                        } else {
+                            Err(Error::MutationTargetAlreadyExists)
+                        }
+                    }
+                    None => {
+                        unwritten_indices.push(index);
+                        Ok(())
+                    }
+                }
+            };
+        }
+
+        // Every report with no existing row should have been inserted. If some weren't, a
+        // concurrent writer wrote the same report, and its write is not visible to this
+        // transaction; see `put_client_report`.
+        if usize::try_from(inserted_count)? != unwritten_indices.len() {
+            self.retry();
+            for index in unwritten_indices {
+                unique_results[index] = Err(Error::MutationTargetAlreadyExists);
+            }
+        }
+
+        Ok(reports
+            .iter()
+            .enumerate()
+            .map(|(index, report)| {
+                let (first_index, unique_index) =
+                    first_occurrences[&(report.task_id(), report.metadata().id())];
+                if first_index != index && report != &reports[first_index] {
+                    return Err(Error::MutationTargetAlreadyExists);
+                }
+                match &unique_results[unique_index] {
+                    Ok(()) => Ok(()),
+                    Err(Error::MutationTargetNotFound) => Err(Error::MutationTargetNotFound),
+                    Err(_) => Err(Error::MutationTargetAlreadyExists),
+                }
+            })
+            .collect())
+    }
+
     /// put_report_share stores a report share, given its associated task ID.
     ///
     /// This method is intended for use by aggregators acting in the helper role; notably, it does
@@ -1693,20 +2230,31 @@ impl<C: Clock> Transaction<'_, C> {
         task_id: &TaskId,
         report_share: &ReportShare,
     ) -> Result<(), Error> {
-        // On conflict, we update the row, but only if the incoming client timestamp (excluded)
-        // matches the existing one. This lets us detect whether there's a row with a mismatching
-        // timestamp through the number of rows modified by the statement.
+        // We insert the row only if there is no existing row for the report ID, and report the
+        // client timestamps of any existing rows so that we can detect whether there's a row with a
+        // mismatching timestamp. (We can't rely on a unique constraint on the task & report ID for
+        // this, as there is no such constraint if the client_reports table is partitioned; see
+        // `partition_report_tables`.)
         let stmt = self
             .prepare_cached(
-                "INSERT INTO client_reports (task_id, report_id, client_timestamp)
-                VALUES ((SELECT id FROM tasks WHERE task_id = $1), $2, $3)
-                ON CONFLICT (task_id, report_id) DO UPDATE
-                  SET client_timestamp = client_reports.client_timestamp
-                    WHERE excluded.client_timestamp = client_reports.client_timestamp",
+                "WITH existing AS (
+                    SELECT client_timestamp FROM client_reports
+                    WHERE task_id = (SELECT id FROM tasks WHERE task_id = $1) AND report_id = $2
+                ),
+                inserted AS (
+                    INSERT INTO client_reports (task_id, report_id, client_timestamp)
+                    SELECT (SELECT id FROM tasks WHERE task_id = $1), $2, $3
+                    WHERE NOT EXISTS (SELECT 1 FROM existing)
+                    ON CONFLICT DO NOTHING
+                    RETURNING 1
+                )
+                SELECT (SELECT COUNT(*) FROM inserted) AS inserted,
+                    (SELECT COUNT(*) FROM existing) AS existing,
+                    (SELECT COUNT(*) FROM existing WHERE client_timestamp = $3) AS matching",
             )
             .await?;
-        check_insert(
-            self.execute(
+        let row = self
+            .query_one(
                 &stmt,
                 &[
                     /* task_id */ &task_id.get_encoded(),
@@ -1715,8 +2263,23 @@ impl<C: Clock> Transaction<'_, C> {
                     &report_share.metadata().time().as_naive_date_time()?,
                 ],
             )
-            .await?,
-        )
+            .await?;
+        let (inserted, existing, matching) = (
+            row.get::<_, i64>("inserted"),
+            row.get::<_, i64>("existing"),
+            row.get::<_, i64>("matching"),
+        );
+
+        if inserted > 0 || matching > 0 {
+            return Ok(());
+        }
+        if existing == 0 {
+            // Nothing was inserted, but there was no existing row either: a concurrent writer
+            // inserted the same report, and its write is not visible to this transaction. As in
+            // `put_client_report`, all we can do is retry the transaction.
+            self.retry();
+        }
+        Err(Error::MutationTargetAlreadyExists)
     }
 
     /// get_aggregation_job retrieves an aggregation job by ID.
@@ -1832,6 +2395,10 @@ impl<C: Clock> Transaction<'_, C> {
     /// aggregation jobs. At most `maximum_acquire_count` jobs are acquired. The job is acquired
     /// with a "lease" that will time out; the desired duration of the lease is a parameter, and the
     /// returned lease provides the absolute timestamp at which the lease is no longer live.
+    ///
+    /// Jobs are selected from the available tasks in a weighted round-robin fashion (weighted by
+    /// each task's [`task::TaskPriority`]), so that a task with a large backlog of jobs does not
+    /// prevent the jobs of other tasks from being acquired.
     #[tracing::instrument(skip(self), err)]
     pub async fn acquire_incomplete_aggregation_jobs(
         &self,
@@ -1850,13 +2417,27 @@ impl<C: Clock> Transaction<'_, C> {
         // per-row basis.
         let stmt = self
             .prepare_cached(
-                "WITH incomplete_jobs AS (
-                    SELECT aggregation_jobs.id FROM aggregation_jobs
+                "WITH candidate_jobs AS (
+                    SELECT aggregation_jobs.id,
+                        ROW_NUMBER() OVER (
+                            PARTITION BY aggregation_jobs.task_id
+                            ORDER BY aggregation_jobs.lease_expiry, aggregation_jobs.id
+                        ) AS task_rank,
+                        CASE tasks.priority WHEN 'HIGH' THEN 4 WHEN 'NORMAL' THEN 2 ELSE 1 END
+                            AS task_weight
+                    FROM aggregation_jobs
                     JOIN tasks ON tasks.id = aggregation_jobs.task_id
                     WHERE tasks.aggregator_role = 'LEADER'
                     AND aggregation_jobs.state = 'IN_PROGRESS'
                     AND aggregation_jobs.lease_expiry <= $2
                     AND UPPER(aggregation_jobs.client_timestamp_interval) >= COALESCE($2::TIMESTAMP - tasks.report_expiry_age * '1 second'::INTERVAL, '-infinity'::TIMESTAMP)
+                ),
+                incomplete_jobs AS (
+                    SELECT aggregation_jobs.id FROM aggregation_jobs
+                    JOIN candidate_jobs ON candidate_jobs.id = aggregation_jobs.id
+                    WHERE candidate_jobs.task_rank <= $3
+                    ORDER BY (candidate_jobs.task_rank - 1) / candidate_jobs.task_weight,
+                        candidate_jobs.task_weight DESC, candidate_jobs.task_rank
                     FOR UPDATE OF aggregation_jobs SKIP LOCKED LIMIT $3
                 )
                 UPDATE aggregation_jobs SET
@@ -2196,63 +2777,150 @@ impl<C: Clock> Transaction<'_, C> {
         .collect()
     }
 
-    /// get_report_aggregations_for_task retrieves all report aggregations associated with a given
-    /// task.
-    #[cfg(feature = "test-util")]
-    pub async fn get_report_aggregations_for_task<
+    /// get_report_aggregations_and_client_reports_for_aggregation_job retrieves all report
+    /// aggregations associated with a given aggregation job, ordered by their natural ordering.
+    /// Each report aggregation in state `START` is paired with its associated client report, if the
+    /// client report exists and has not expired; other report aggregations are paired with `None`.
+    ///
+    /// This is equivalent to calling [`Self::get_report_aggregations_for_aggregation_job`]
+    /// followed by [`Self::get_client_report`] for each report aggregation in state `START`, but
+    /// requires only a single query. It is only meaningful for the leader, as the helper does not
+    /// store client report contents.
+    #[tracing::instrument(skip(self), err)]
+    #[allow(clippy::type_complexity)]
+    pub async fn get_report_aggregations_and_client_reports_for_aggregation_job<
         const SEED_SIZE: usize,
         A: vdaf::Aggregator<SEED_SIZE, 16>,
     >(
         &self,
         vdaf: &A,
-        role: &Role,
         task_id: &TaskId,
-    ) -> Result<Vec<ReportAggregation<SEED_SIZE, A>>, Error>
+        aggregation_job_id: &AggregationJobId,
+    ) -> Result<
+        Vec<(
+            ReportAggregation<SEED_SIZE, A>,
+            Option<LeaderStoredReport<SEED_SIZE, A>>,
+        )>,
+        Error,
+    >
     where
         for<'a> A::PrepareState: ParameterizedDecode<(&'a A, usize)>,
+        A::InputShare: PartialEq,
+        A::PublicShare: PartialEq,
     {
         let stmt = self
             .prepare_cached(
                 "SELECT
-                    aggregation_jobs.aggregation_job_id, report_aggregations.client_report_id,
-                    report_aggregations.client_timestamp, report_aggregations.ord,
-                    report_aggregations.state, report_aggregations.prep_state,
-                    report_aggregations.prep_msg, report_aggregations.error_code,
-                    report_aggregations.last_prep_step
+                    report_aggregations.client_report_id, report_aggregations.client_timestamp,
+                    report_aggregations.ord, report_aggregations.state,
+                    report_aggregations.prep_state, report_aggregations.prep_msg,
+                    report_aggregations.error_code, report_aggregations.last_prep_step,
+                    client_reports.id IS NOT NULL AS client_report_found,
+                    client_reports.extensions, client_reports.public_share,
+                    client_reports.leader_input_share, client_reports.helper_encrypted_input_share
                 FROM report_aggregations
                 JOIN aggregation_jobs ON aggregation_jobs.id = report_aggregations.aggregation_job_id
                 JOIN tasks ON tasks.id = aggregation_jobs.task_id
+                LEFT JOIN client_reports
+                    ON client_reports.task_id = aggregation_jobs.task_id
+                    AND client_reports.report_id = report_aggregations.client_report_id
+                    AND report_aggregations.state = 'START'
+                    AND client_reports.client_timestamp >= COALESCE($3::TIMESTAMP - tasks.report_expiry_age * '1 second'::INTERVAL, '-infinity'::TIMESTAMP)
                 WHERE tasks.task_id = $1
-                  AND UPPER(aggregation_jobs.client_timestamp_interval) >= COALESCE($2::TIMESTAMP - tasks.report_expiry_age * '1 second'::INTERVAL, '-infinity'::TIMESTAMP)",
+                  AND aggregation_jobs.aggregation_job_id = $2
+                  AND UPPER(aggregation_jobs.client_timestamp_interval) >= COALESCE($3::TIMESTAMP - tasks.report_expiry_age * '1 second'::INTERVAL, '-infinity'::TIMESTAMP)
+                ORDER BY report_aggregations.ord ASC",
             )
             .await?;
         self.query(
             &stmt,
             &[
                 /* task_id */ &task_id.as_ref(),
+                /* aggregation_job_id */ &aggregation_job_id.as_ref(),
                 /* now */ &self.clock.now().as_naive_date_time()?,
             ],
         )
         .await?
         .into_iter()
         .map(|row| {
-            Self::report_aggregation_from_row(
+            let report_id = row.get_bytea_and_convert::<ReportId>("client_report_id")?;
+            let report_aggregation = Self::report_aggregation_from_row(
                 vdaf,
-                role,
+                &Role::Leader,
                 task_id,
-                &row.get_bytea_and_convert::<AggregationJobId>("aggregation_job_id")?,
-                &row.get_bytea_and_convert::<ReportId>("client_report_id")?,
+                aggregation_job_id,
+                &report_id,
                 &row,
-            )
+            )?;
+            let client_report = if row.get("client_report_found") {
+                Some(Self::client_report_from_row(
+                    vdaf, *task_id, report_id, row,
+                )?)
+            } else {
+                None
+            };
+            Ok((report_aggregation, client_report))
         })
         .collect()
     }
 
-    fn report_aggregation_from_row<const SEED_SIZE: usize, A: vdaf::Aggregator<SEED_SIZE, 16>>(
+    /// get_report_aggregations_for_task retrieves all report aggregations associated with a given
+    /// task.
+    #[cfg(feature = "test-util")]
+    pub async fn get_report_aggregations_for_task<
+        const SEED_SIZE: usize,
+        A: vdaf::Aggregator<SEED_SIZE, 16>,
+    >(
+        &self,
         vdaf: &A,
         role: &Role,
         task_id: &TaskId,
-        aggregation_job_id: &AggregationJobId,
+    ) -> Result<Vec<ReportAggregation<SEED_SIZE, A>>, Error>
+    where
+        for<'a> A::PrepareState: ParameterizedDecode<(&'a A, usize)>,
+    {
+        let stmt = self
+            .prepare_cached(
+                "SELECT
+                    aggregation_jobs.aggregation_job_id, report_aggregations.client_report_id,
+                    report_aggregations.client_timestamp, report_aggregations.ord,
+                    report_aggregations.state, report_aggregations.prep_state,
+                    report_aggregations.prep_msg, report_aggregations.error_code,
+                    report_aggregations.last_prep_step
+                FROM report_aggregations
+                JOIN aggregation_jobs ON aggregation_jobs.id = report_aggregations.aggregation_job_id
+                JOIN tasks ON tasks.id = aggregation_jobs.task_id
+                WHERE tasks.task_id = $1
+                  AND UPPER(aggregation_jobs.client_timestamp_interval) >= COALESCE($2::TIMESTAMP - tasks.report_expiry_age * '1 second'::INTERVAL, '-infinity'::TIMESTAMP)",
+            )
+            .await?;
+        self.query(
+            &stmt,
+            &[
+                /* task_id */ &task_id.as_ref(),
+                /* now */ &self.clock.now().as_naive_date_time()?,
+            ],
+        )
+        .await?
+        .into_iter()
+        .map(|row| {
+            Self::report_aggregation_from_row(
+                vdaf,
+                role,
+                task_id,
+                &row.get_bytea_and_convert::<AggregationJobId>("aggregation_job_id")?,
+                &row.get_bytea_and_convert::<ReportId>("client_report_id")?,
+                &row,
+            )
+        })
+        .collect()
+    }
+
+    fn report_aggregation_from_row<const SEED_SIZE: usize, A: vdaf::Aggregator<SEED_SIZE, 16>>(
+        vdaf: &A,
+        role: &Role,
+        task_id: &TaskId,
+        aggregation_job_id: &AggregationJobId,
         report_id: &ReportId,
         row: &Row,
     ) -> Result<ReportAggregation<SEED_SIZE, A>, Error>
@@ -2455,6 +3123,7 @@ impl<C: Clock> Transaction<'_, C> {
                     collection_jobs.state,
                     collection_jobs.report_count,
                     collection_jobs.helper_aggregate_share,
+                    collection_jobs.helper_aggregate_share_hpke_config,
                     collection_jobs.leader_aggregate_share
                 FROM collection_jobs
                 JOIN tasks ON tasks.id = collection_jobs.task_id
@@ -2508,6 +3177,7 @@ impl<C: Clock> Transaction<'_, C> {
                     collection_jobs.state,
                     collection_jobs.report_count,
                     collection_jobs.helper_aggregate_share,
+                    collection_jobs.helper_aggregate_share_hpke_config,
                     collection_jobs.leader_aggregate_share
                 FROM collection_jobs JOIN tasks ON tasks.id = collection_jobs.task_id
                 WHERE tasks.task_id = $1
@@ -2557,6 +3227,7 @@ impl<C: Clock> Transaction<'_, C> {
                     collection_jobs.state,
                     collection_jobs.report_count,
                     collection_jobs.helper_aggregate_share,
+                    collection_jobs.helper_aggregate_share_hpke_config,
                     collection_jobs.leader_aggregate_share
                 FROM collection_jobs JOIN tasks ON tasks.id = collection_jobs.task_id
                 WHERE tasks.task_id = $1
@@ -2611,6 +3282,7 @@ impl<C: Clock> Transaction<'_, C> {
                     collection_jobs.state,
                     collection_jobs.report_count,
                     collection_jobs.helper_aggregate_share,
+                    collection_jobs.helper_aggregate_share_hpke_config,
                     collection_jobs.leader_aggregate_share
                 FROM collection_jobs
                 JOIN tasks ON tasks.id = collection_jobs.task_id
@@ -2659,6 +3331,7 @@ impl<C: Clock> Transaction<'_, C> {
                     collection_jobs.state,
                     collection_jobs.report_count,
                     collection_jobs.helper_aggregate_share,
+                    collection_jobs.helper_aggregate_share_hpke_config,
                     collection_jobs.leader_aggregate_share
                 FROM collection_jobs
                 JOIN tasks ON tasks.id = collection_jobs.task_id
@@ -2700,6 +3373,8 @@ impl<C: Clock> Transaction<'_, C> {
         let state: CollectionJobStateCode = row.get("state");
         let report_count: Option<i64> = row.get("report_count");
         let helper_aggregate_share_bytes: Option<Vec<u8>> = row.get("helper_aggregate_share");
+        let helper_aggregate_share_hpke_config_bytes: Option<Vec<u8>> =
+            row.get("helper_aggregate_share_hpke_config");
         let leader_aggregate_share_bytes: Option<Vec<u8>> = row.get("leader_aggregate_share");
 
         let state = match state {
@@ -2721,6 +3396,9 @@ impl<C: Clock> Transaction<'_, C> {
                         )
                     })?,
                 )?;
+                let helper_aggregate_share_hpke_config = helper_aggregate_share_hpke_config_bytes
+                    .map(|bytes| HpkeConfig::get_decoded(&bytes))
+                    .transpose()?;
                 let leader_aggregate_share = A::AggregateShare::get_decoded_with_param(
                     &(vdaf, &aggregation_param),
                     &leader_aggregate_share_bytes.ok_or_else(|| {
@@ -2734,6 +3412,7 @@ impl<C: Clock> Transaction<'_, C> {
                 CollectionJobState::Finished {
                     report_count,
                     encrypted_helper_aggregate_share,
+                    helper_aggregate_share_hpke_config,
                     leader_aggregate_share,
                 }
             }
@@ -2831,6 +3510,9 @@ impl<C: Clock> Transaction<'_, C> {
     /// collection jobs. At most `maximum_acquire_count` jobs are acquired. The job is acquired with
     /// a "lease" that will time out; the desired duration of the lease is a parameter, and the
     /// lease expiration time is returned.
+    ///
+    /// As with [`Self::acquire_incomplete_aggregation_jobs`], jobs are selected from the available
+    /// tasks in a weighted round-robin fashion.
     #[tracing::instrument(skip(self), err)]
     pub async fn acquire_incomplete_collection_jobs(
         &self,
@@ -2843,14 +3525,29 @@ impl<C: Clock> Transaction<'_, C> {
 
         let stmt = self
             .prepare_cached(
-                "WITH incomplete_jobs AS (
-                    SELECT collection_jobs.id, tasks.task_id, tasks.query_type, tasks.vdaf
+                "WITH candidate_jobs AS (
+                    SELECT collection_jobs.id, tasks.task_id, tasks.query_type, tasks.vdaf,
+                        ROW_NUMBER() OVER (
+                            PARTITION BY collection_jobs.task_id
+                            ORDER BY collection_jobs.lease_expiry, collection_jobs.id
+                        ) AS task_rank,
+                        CASE tasks.priority WHEN 'HIGH' THEN 4 WHEN 'NORMAL' THEN 2 ELSE 1 END
+                            AS task_weight
                     FROM collection_jobs
                     JOIN tasks ON tasks.id = collection_jobs.task_id
                     WHERE tasks.aggregator_role = 'LEADER'
                       AND collection_jobs.state = 'COLLECTABLE'
                       AND collection_jobs.lease_expiry <= $2
                       AND COALESCE(LOWER(collection_jobs.batch_interval), UPPER((SELECT client_timestamp_interval FROM batches WHERE batches.task_id = collection_jobs.task_id AND batches.batch_identifier = collection_jobs.batch_identifier AND batches.aggregation_param = collection_jobs.aggregation_param))) >= COALESCE($2::TIMESTAMP - tasks.report_expiry_age * '1 second'::INTERVAL, '-infinity'::TIMESTAMP)
+                ),
+                incomplete_jobs AS (
+                    SELECT collection_jobs.id, candidate_jobs.task_id, candidate_jobs.query_type,
+                        candidate_jobs.vdaf
+                    FROM collection_jobs
+                    JOIN candidate_jobs ON candidate_jobs.id = collection_jobs.id
+                    WHERE candidate_jobs.task_rank <= $3
+                    ORDER BY (candidate_jobs.task_rank - 1) / candidate_jobs.task_weight,
+                        candidate_jobs.task_weight DESC, candidate_jobs.task_rank
                     FOR UPDATE OF collection_jobs SKIP LOCKED LIMIT $3
                 )
                 UPDATE collection_jobs SET
@@ -2930,7 +3627,8 @@ impl<C: Clock> Transaction<'_, C> {
         )
     }
 
-    /// Updates an existing collection job.
+    /// Updates an existing collection job. Any `Collection` message stored for the collection job
+    /// by [`Self::put_collection`] is discarded.
     #[tracing::instrument(skip(self), err)]
     pub async fn update_collection_job<
         const SEED_SIZE: usize,
@@ -2940,9 +3638,12 @@ impl<C: Clock> Transaction<'_, C> {
         &self,
         collection_job: &CollectionJob<SEED_SIZE, Q, A>,
     ) -> Result<(), Error> {
-        let (report_count, leader_aggregate_share, helper_aggregate_share) = match collection_job
-            .state()
-        {
+        let (
+            report_count,
+            leader_aggregate_share,
+            helper_aggregate_share,
+            helper_aggregate_share_hpke_config,
+        ) = match collection_job.state() {
             CollectionJobState::Start => {
                 return Err(Error::InvalidParameter(
                     "cannot update collection job into START state",
@@ -2951,18 +3652,27 @@ impl<C: Clock> Transaction<'_, C> {
             CollectionJobState::Finished {
                 report_count,
                 encrypted_helper_aggregate_share,
+                helper_aggregate_share_hpke_config,
                 leader_aggregate_share,
             } => {
                 let report_count: Option<i64> = Some(i64::try_from(*report_count)?);
                 let leader_aggregate_share: Option<Vec<u8>> =
                     Some(leader_aggregate_share.get_encoded());
                 let helper_aggregate_share = Some(encrypted_helper_aggregate_share.get_encoded());
+                let helper_aggregate_share_hpke_config = helper_aggregate_share_hpke_config
+                    .as_ref()
+                    .map(HpkeConfig::get_encoded);
 
-                (report_count, leader_aggregate_share, helper_aggregate_share)
+                (
+                    report_count,
+                    leader_aggregate_share,
+                    helper_aggregate_share,
+                    helper_aggregate_share_hpke_config,
+                )
             }
             CollectionJobState::Collectable
             | CollectionJobState::Abandoned
-            | CollectionJobState::Deleted => (None, None, None),
+            | CollectionJobState::Deleted => (None, None, None, None),
         };
 
         let stmt = self
@@ -2971,12 +3681,14 @@ impl<C: Clock> Transaction<'_, C> {
                     state = $1,
                     report_count = $2,
                     leader_aggregate_share = $3,
-                    helper_aggregate_share = $4
+                    helper_aggregate_share = $4,
+                    helper_aggregate_share_hpke_config = $5,
+                    collection = NULL
                 FROM tasks
                 WHERE tasks.id = collection_jobs.task_id
-                  AND tasks.task_id = $5
-                  AND collection_job_id = $6
-                  AND COALESCE(LOWER(collection_jobs.batch_interval), UPPER((SELECT client_timestamp_interval FROM batches WHERE batches.task_id = collection_jobs.task_id AND batches.batch_identifier = collection_jobs.batch_identifier AND batches.aggregation_param = collection_jobs.aggregation_param))) >= COALESCE($7::TIMESTAMP - tasks.report_expiry_age * '1 second'::INTERVAL, '-infinity'::TIMESTAMP)",
+                  AND tasks.task_id = $6
+                  AND collection_job_id = $7
+                  AND COALESCE(LOWER(collection_jobs.batch_interval), UPPER((SELECT client_timestamp_interval FROM batches WHERE batches.task_id = collection_jobs.task_id AND batches.batch_identifier = collection_jobs.batch_identifier AND batches.aggregation_param = collection_jobs.aggregation_param))) >= COALESCE($8::TIMESTAMP - tasks.report_expiry_age * '1 second'::INTERVAL, '-infinity'::TIMESTAMP)",
             )
             .await?;
 
@@ -2988,6 +3700,8 @@ impl<C: Clock> Transaction<'_, C> {
                     /* report_count */ &report_count,
                     /* leader_aggregate_share */ &leader_aggregate_share,
                     /* helper_aggregate_share */ &helper_aggregate_share,
+                    /* helper_aggregate_share_hpke_config */
+                    &helper_aggregate_share_hpke_config,
                     /* task_id */ &collection_job.task_id().as_ref(),
                     /* collection_job_id */ &collection_job.id().as_ref(),
                     /* now */ &self.clock.now().as_naive_date_time()?,
@@ -2997,6 +3711,71 @@ impl<C: Clock> Transaction<'_, C> {
         )
     }
 
+    /// Retrieves the `Collection` message stored for a finished collection job by
+    /// [`Self::put_collection`], if any.
+    #[tracing::instrument(skip(self), err)]
+    pub async fn get_collection<Q: QueryType>(
+        &self,
+        task_id: &TaskId,
+        collection_job_id: &CollectionJobId,
+    ) -> Result<Option<Collection<Q>>, Error> {
+        let stmt = self
+            .prepare_cached(
+                "SELECT collection_jobs.collection
+                FROM collection_jobs
+                JOIN tasks ON tasks.id = collection_jobs.task_id
+                WHERE tasks.task_id = $1
+                  AND collection_jobs.collection_job_id = $2
+                  AND COALESCE(LOWER(collection_jobs.batch_interval), UPPER((SELECT client_timestamp_interval FROM batches WHERE batches.task_id = collection_jobs.task_id AND batches.batch_identifier = collection_jobs.batch_identifier AND batches.aggregation_param = collection_jobs.aggregation_param))) >= COALESCE($3::TIMESTAMP - tasks.report_expiry_age * '1 second'::INTERVAL, '-infinity'::TIMESTAMP)",
+            )
+            .await?;
+        self.query_opt(
+            &stmt,
+            &[
+                /* task_id */ task_id.as_ref(),
+                /* collection_job_id */ &collection_job_id.as_ref(),
+                /* now */ &self.clock.now().as_naive_date_time()?,
+            ],
+        )
+        .await?
+        .and_then(|row| row.get::<_, Option<Vec<u8>>>("collection"))
+        .map(|collection| Collection::get_decoded(&collection).map_err(Error::from))
+        .transpose()
+    }
+
+    /// Stores the `Collection` message to be served for a finished collection job.
+    #[tracing::instrument(skip(self, collection), err)]
+    pub async fn put_collection<Q: QueryType>(
+        &self,
+        task_id: &TaskId,
+        collection_job_id: &CollectionJobId,
+        collection: &Collection<Q>,
+    ) -> Result<(), Error> {
+        let stmt = self
+            .prepare_cached(
+                "UPDATE collection_jobs SET collection = $1
+                FROM tasks
+                WHERE tasks.id = collection_jobs.task_id
+                  AND tasks.task_id = $2
+                  AND collection_job_id = $3
+                  AND state = 'FINISHED'
+                  AND COALESCE(LOWER(collection_jobs.batch_interval), UPPER((SELECT client_timestamp_interval FROM batches WHERE batches.task_id = collection_jobs.task_id AND batches.batch_identifier = collection_jobs.batch_identifier AND batches.aggregation_param = collection_jobs.aggregation_param))) >= COALESCE($4::TIMESTAMP - tasks.report_expiry_age * '1 second'::INTERVAL, '-infinity'::TIMESTAMP)",
+            )
+            .await?;
+        check_single_row_mutation(
+            self.execute(
+                &stmt,
+                &[
+                    /* collection */ &collection.get_encoded(),
+                    /* task_id */ task_id.as_ref(),
+                    /* collection_job_id */ &collection_job_id.as_ref(),
+                    /* now */ &self.clock.now().as_naive_date_time()?,
+                ],
+            )
+            .await?,
+        )
+    }
+
     /// Retrieves an existing batch aggregation.
     #[tracing::instrument(skip(self, aggregation_parameter), err)]
     pub async fn get_batch_aggregation<
@@ -4115,6 +4894,62 @@ impl<C: Clock> Transaction<'_, C> {
         .collect()
     }
 
+    /// Gets the unexpired batches of a fixed-size task with the first `limit` batch IDs, in order,
+    /// following `lower_bound` if it is provided. Every batch written for each of these batch IDs
+    /// is returned, one per aggregation parameter.
+    #[tracing::instrument(skip(self), err)]
+    pub async fn get_fixed_size_batches_for_task<
+        const SEED_SIZE: usize,
+        A: vdaf::Aggregator<SEED_SIZE, 16>,
+    >(
+        &self,
+        task_id: &TaskId,
+        lower_bound: Option<&BatchId>,
+        limit: u64,
+    ) -> Result<Vec<Batch<SEED_SIZE, FixedSize, A>>, Error> {
+        let lower_bound = lower_bound.map(|batch_id| batch_id.get_encoded());
+        let stmt = self
+            .prepare_cached(
+                "WITH batch_ids AS (
+                    SELECT DISTINCT batches.task_id, batches.batch_identifier
+                    FROM batches
+                    JOIN tasks ON tasks.id = batches.task_id
+                    WHERE tasks.task_id = $1
+                      AND (batches.batch_identifier > $2 OR $2 IS NULL)
+                      AND UPPER(batches.client_timestamp_interval) >= COALESCE($4::TIMESTAMP - tasks.report_expiry_age * '1 second'::INTERVAL, '-infinity'::TIMESTAMP)
+                    ORDER BY batches.batch_identifier
+                    LIMIT $3
+                )
+                SELECT
+                    batches.batch_identifier, aggregation_param, state,
+                    outstanding_aggregation_jobs, client_timestamp_interval
+                FROM batches
+                JOIN batch_ids ON batch_ids.task_id = batches.task_id
+                  AND batch_ids.batch_identifier = batches.batch_identifier
+                JOIN tasks ON tasks.id = batches.task_id
+                WHERE UPPER(batches.client_timestamp_interval) >= COALESCE($4::TIMESTAMP - tasks.report_expiry_age * '1 second'::INTERVAL, '-infinity'::TIMESTAMP)",
+            )
+            .await?;
+        self.query(
+            &stmt,
+            &[
+                /* task_id */ task_id.as_ref(),
+                /* lower_bound */ &lower_bound,
+                /* limit */ &i64::try_from(limit)?,
+                /* now */ &self.clock.now().as_naive_date_time()?,
+            ],
+        )
+        .await?
+        .into_iter()
+        .map(|row| {
+            let batch_identifier = BatchId::get_decoded(row.get("batch_identifier"))?;
+            let aggregation_parameter =
+                A::AggregationParam::get_decoded(row.get("aggregation_param"))?;
+            Self::batch_from_row(*task_id, batch_identifier, aggregation_parameter, row)
+        })
+        .collect()
+    }
+
     fn batch_from_row<const SEED_SIZE: usize, Q: QueryType, A: vdaf::Aggregator<SEED_SIZE, 16>>(
         task_id: TaskId,
         batch_identifier: Q::BatchIdentifier,
@@ -4145,7 +4980,7 @@ impl<C: Clock> Transaction<'_, C> {
         &self,
         task_id: &TaskId,
         limit: u64,
-    ) -> Result<(), Error> {
+    ) -> Result<DeletedRows, Error> {
         let stmt = self
             .prepare_cached(
                 "WITH client_reports_to_delete AS (
@@ -4154,22 +4989,28 @@ impl<C: Clock> Transaction<'_, C> {
                     WHERE tasks.task_id = $1
                       AND client_reports.client_timestamp < COALESCE($2::TIMESTAMP - tasks.report_expiry_age * '1 second'::INTERVAL, '-infinity'::TIMESTAMP)
                     LIMIT $3
+                ),
+                deleted_client_reports AS (
+                    DELETE FROM client_reports
+                    USING client_reports_to_delete
+                    WHERE client_reports.id = client_reports_to_delete.id
+                    RETURNING 1
                 )
-                DELETE FROM client_reports
-                USING client_reports_to_delete
-                WHERE client_reports.id = client_reports_to_delete.id",
+                SELECT (SELECT COUNT(*) FROM deleted_client_reports) AS client_reports",
             )
             .await?;
-        self.execute(
-            &stmt,
-            &[
-                /* task_id */ &task_id.get_encoded(),
-                /* now */ &self.clock.now().as_naive_date_time()?,
-                /* limit */ &i64::try_from(limit)?,
-            ],
+        DeletedRows::from_row(
+            &self
+                .query_one(
+                    &stmt,
+                    &[
+                        /* task_id */ &task_id.get_encoded(),
+                        /* now */ &self.clock.now().as_naive_date_time()?,
+                        /* limit */ &i64::try_from(limit)?,
+                    ],
+                )
+                .await?,
         )
-        .await?;
-        Ok(())
     }
 
     /// Deletes old aggregation artifacts (aggregation jobs/report aggregations) for a given task,
@@ -4181,7 +5022,7 @@ impl<C: Clock> Transaction<'_, C> {
         &self,
         task_id: &TaskId,
         limit: u64,
-    ) -> Result<(), Error> {
+    ) -> Result<DeletedRows, Error> {
         let stmt = self
             .prepare_cached(
                 "WITH aggregation_jobs_to_delete AS (
@@ -4194,21 +5035,29 @@ impl<C: Clock> Transaction<'_, C> {
                 deleted_report_aggregations AS (
                     DELETE FROM report_aggregations
                     WHERE aggregation_job_id IN (SELECT id FROM aggregation_jobs_to_delete)
+                    RETURNING 1
+                ),
+                deleted_aggregation_jobs AS (
+                    DELETE FROM aggregation_jobs
+                    WHERE id IN (SELECT id FROM aggregation_jobs_to_delete)
+                    RETURNING 1
                 )
-                DELETE FROM aggregation_jobs
-                WHERE id IN (SELECT id FROM aggregation_jobs_to_delete)",
+                SELECT (SELECT COUNT(*) FROM deleted_aggregation_jobs) AS aggregation_jobs,
+                    (SELECT COUNT(*) FROM deleted_report_aggregations) AS report_aggregations",
             )
             .await?;
-        self.execute(
-            &stmt,
-            &[
-                /* task_id */ &task_id.get_encoded(),
-                /* now */ &self.clock.now().as_naive_date_time()?,
-                /* limit */ &i64::try_from(limit)?,
-            ],
+        DeletedRows::from_row(
+            &self
+                .query_one(
+                    &stmt,
+                    &[
+                        /* task_id */ &task_id.get_encoded(),
+                        /* now */ &self.clock.now().as_naive_date_time()?,
+                        /* limit */ &i64::try_from(limit)?,
+                    ],
+                )
+                .await?,
         )
-        .await?;
-        Ok(())
     }
 
     /// Deletes old collection artifacts (batches/outstanding batches/batch aggregations/collection
@@ -4234,7 +5083,7 @@ impl<C: Clock> Transaction<'_, C> {
         &self,
         task_id: &TaskId,
         limit: u64,
-    ) -> Result<(), Error> {
+    ) -> Result<DeletedRows, Error> {
         let stmt = self
             .prepare_cached(
                 "WITH batches_to_delete AS (
@@ -4251,12 +5100,14 @@ impl<C: Clock> Transaction<'_, C> {
                     WHERE batch_aggregations.task_id = batches_to_delete.task_id
                       AND batch_aggregations.batch_identifier = batches_to_delete.batch_identifier
                       AND batch_aggregations.aggregation_param = batches_to_delete.aggregation_param
+                    RETURNING 1
                 ),
                 deleted_outstanding_batches AS (
                     DELETE FROM outstanding_batches
                     USING batches_to_delete
                     WHERE outstanding_batches.task_id = batches_to_delete.task_id
                       AND outstanding_batches.batch_id = batches_to_delete.batch_identifier
+                    RETURNING 1
                 ),
                 deleted_collection_jobs AS (
                     DELETE FROM collection_jobs
@@ -4265,6 +5116,7 @@ impl<C: Clock> Transaction<'_, C> {
                       AND tasks.task_id = $1
                       AND (LOWER(batch_interval) < COALESCE($2::TIMESTAMP - tasks.report_expiry_age * '1 second'::INTERVAL, '-infinity'::TIMESTAMP)
                         OR (collection_jobs.task_id = batches_to_delete.task_id AND collection_jobs.batch_identifier = batches_to_delete.batch_identifier AND collection_jobs.aggregation_param = batches_to_delete.aggregation_param))
+                    RETURNING 1
                 ),
                 deleted_aggregate_share_jobs AS (
                     DELETE FROM aggregate_share_jobs
@@ -4273,52 +5125,542 @@ impl<C: Clock> Transaction<'_, C> {
                       AND tasks.task_id = $1
                       AND (LOWER(batch_interval) < COALESCE($2::TIMESTAMP - tasks.report_expiry_age * '1 second'::INTERVAL, '-infinity'::TIMESTAMP)
                         OR (aggregate_share_jobs.task_id = batches_to_delete.task_id AND aggregate_share_jobs.batch_identifier = batches_to_delete.batch_identifier AND aggregate_share_jobs.aggregation_param = batches_to_delete.aggregation_param))
+                    RETURNING 1
+                ),
+                deleted_batches AS (
+                    DELETE FROM batches
+                    USING batches_to_delete
+                    WHERE batches.task_id = batches_to_delete.task_id
+                      AND batches.batch_identifier = batches_to_delete.batch_identifier
+                      AND batches.aggregation_param = batches_to_delete.aggregation_param
+                    RETURNING 1
                 )
-                DELETE FROM batches
-                USING batches_to_delete
-                WHERE batches.task_id = batches_to_delete.task_id
-                  AND batches.batch_identifier = batches_to_delete.batch_identifier
-                  AND batches.aggregation_param = batches_to_delete.aggregation_param",
+                SELECT (SELECT COUNT(*) FROM deleted_batches) AS batches,
+                    (SELECT COUNT(*) FROM deleted_batch_aggregations) AS batch_aggregations,
+                    (SELECT COUNT(*) FROM deleted_outstanding_batches) AS outstanding_batches,
+                    (SELECT COUNT(*) FROM deleted_collection_jobs) AS collection_jobs,
+                    (SELECT COUNT(*) FROM deleted_aggregate_share_jobs) AS aggregate_share_jobs",
             )
             .await?;
-        self.execute(
+        DeletedRows::from_row(
+            &self
+                .query_one(
+                    &stmt,
+                    &[
+                        /* task_id */ &task_id.get_encoded(),
+                        /* now */ &self.clock.now().as_naive_date_time()?,
+                        /* limit */ &i64::try_from(limit)?,
+                    ],
+                )
+                .await?,
+        )
+    }
+
+    /// Retrieves the IDs of all tasks whose expiration time is further in the past than
+    /// `retention`. Tasks without an expiration time are never returned.
+    #[tracing::instrument(skip(self), err)]
+    pub async fn get_task_ids_expired_beyond_retention(
+        &self,
+        retention: &Duration,
+    ) -> Result<Vec<TaskId>, Error> {
+        let stmt = self
+            .prepare_cached(
+                "SELECT task_id FROM tasks
+                WHERE task_expiration < $1::TIMESTAMP - $2::BIGINT * '1 second'::INTERVAL",
+            )
+            .await?;
+        self.query(
             &stmt,
             &[
-                /* task_id */ &task_id.get_encoded(),
                 /* now */ &self.clock.now().as_naive_date_time()?,
-                /* limit */ &i64::try_from(limit)?,
+                /* retention */ &i64::try_from(retention.as_seconds())?,
             ],
         )
-        .await?;
-        Ok(())
+        .await?
+        .into_iter()
+        .map(|row| Ok(TaskId::get_decoded(row.get("task_id"))?))
+        .collect()
     }
 
-    /// Retrieve all global HPKE keypairs.
+    /// Returns how long ago the oldest client report of the given task that is eligible for
+    /// garbage collection became eligible, i.e. how far garbage collection of the task's client
+    /// reports is behind. Returns `None` if no client reports are eligible.
     #[tracing::instrument(skip(self), err)]
-    pub async fn get_global_hpke_keypairs(&self) -> Result<Vec<GlobalHpkeKeypair>, Error> {
+    pub async fn get_expired_client_reports_backlog(
+        &self,
+        task_id: &TaskId,
+    ) -> Result<Option<Duration>, Error> {
         let stmt = self
             .prepare_cached(
-                "SELECT config_id, config, private_key, state, updated_at FROM global_hpke_keys;",
+                "SELECT EXTRACT(EPOCH FROM
+                        $2::TIMESTAMP - tasks.report_expiry_age * '1 second'::INTERVAL
+                            - MIN(client_reports.client_timestamp))::BIGINT AS backlog
+                FROM client_reports
+                JOIN tasks ON tasks.id = client_reports.task_id
+                WHERE tasks.task_id = $1
+                  AND client_reports.client_timestamp < COALESCE($2::TIMESTAMP - tasks.report_expiry_age * '1 second'::INTERVAL, '-infinity'::TIMESTAMP)
+                GROUP BY tasks.report_expiry_age",
             )
             .await?;
-        let hpke_key_rows = self.query(&stmt, &[]).await?;
-
-        hpke_key_rows
-            .iter()
-            .map(|row| self.global_hpke_keypair_from_row(row))
-            .collect()
+        self.query_opt(
+            &stmt,
+            &[
+                /* task_id */ &task_id.get_encoded(),
+                /* now */ &self.clock.now().as_naive_date_time()?,
+            ],
+        )
+        .await?
+        .map(|row| {
+            Ok(Duration::from_seconds(u64::try_from(
+                row.get::<_, i64>("backlog"),
+            )?))
+        })
+        .transpose()
     }
 
-    /// Retrieve a global HPKE keypair by config ID.
+    /// Deletes client reports, aggregation artifacts and collection artifacts belonging to a task,
+    /// regardless of their age, so that the task itself can later be deleted without a single
+    /// long-running cascading delete. Up to `limit` rows are deleted from each table in a single
+    /// call; once no rows are deleted, only the task's definition, keys and tokens remain.
+    /// Aggregation jobs are only deleted once a previous call has deleted all of their report
+    /// aggregations, so that deleting them never cascades to an unbounded number of rows.
     #[tracing::instrument(skip(self), err)]
-    pub async fn get_global_hpke_keypair(
+    pub async fn delete_task_artifacts(
         &self,
-        config_id: &HpkeConfigId,
-    ) -> Result<Option<GlobalHpkeKeypair>, Error> {
+        task_id: &TaskId,
+        limit: u64,
+    ) -> Result<DeletedRows, Error> {
         let stmt = self
             .prepare_cached(
-                "SELECT config_id, config, private_key, state, updated_at FROM global_hpke_keys
-                    WHERE config_id = $1;",
+                "WITH task AS (
+                    SELECT id FROM tasks WHERE task_id = $1
+                ),
+                deleted_client_reports AS (
+                    DELETE FROM client_reports WHERE id IN (
+                        SELECT id FROM client_reports WHERE task_id = (SELECT id FROM task) LIMIT $2
+                    )
+                    RETURNING 1
+                ),
+                deleted_report_aggregations AS (
+                    DELETE FROM report_aggregations WHERE id IN (
+                        SELECT id FROM report_aggregations WHERE aggregation_job_id IN (
+                            SELECT id FROM aggregation_jobs WHERE task_id = (SELECT id FROM task)
+                        ) LIMIT $2
+                    )
+                    RETURNING 1
+                ),
+                deleted_aggregation_jobs AS (
+                    DELETE FROM aggregation_jobs WHERE id IN (
+                        SELECT id FROM aggregation_jobs
+                        WHERE task_id = (SELECT id FROM task)
+                          AND NOT EXISTS (
+                              SELECT 1 FROM report_aggregations
+                              WHERE report_aggregations.aggregation_job_id = aggregation_jobs.id
+                          )
+                        LIMIT $2
+                    )
+                    RETURNING 1
+                ),
+                deleted_batch_aggregations AS (
+                    DELETE FROM batch_aggregations WHERE id IN (
+                        SELECT id FROM batch_aggregations WHERE task_id = (SELECT id FROM task) LIMIT $2
+                    )
+                    RETURNING 1
+                ),
+                deleted_outstanding_batches AS (
+                    DELETE FROM outstanding_batches WHERE id IN (
+                        SELECT id FROM outstanding_batches WHERE task_id = (SELECT id FROM task) LIMIT $2
+                    )
+                    RETURNING 1
+                ),
+                deleted_collection_jobs AS (
+                    DELETE FROM collection_jobs WHERE id IN (
+                        SELECT id FROM collection_jobs WHERE task_id = (SELECT id FROM task) LIMIT $2
+                    )
+                    RETURNING 1
+                ),
+                deleted_aggregate_share_jobs AS (
+                    DELETE FROM aggregate_share_jobs WHERE id IN (
+                        SELECT id FROM aggregate_share_jobs WHERE task_id = (SELECT id FROM task) LIMIT $2
+                    )
+                    RETURNING 1
+                ),
+                deleted_batches AS (
+                    DELETE FROM batches WHERE id IN (
+                        SELECT id FROM batches WHERE task_id = (SELECT id FROM task) LIMIT $2
+                    )
+                    RETURNING 1
+                )
+                SELECT (SELECT COUNT(*) FROM deleted_client_reports) AS client_reports,
+                    (SELECT COUNT(*) FROM deleted_report_aggregations) AS report_aggregations,
+                    (SELECT COUNT(*) FROM deleted_aggregation_jobs) AS aggregation_jobs,
+                    (SELECT COUNT(*) FROM deleted_batch_aggregations) AS batch_aggregations,
+                    (SELECT COUNT(*) FROM deleted_outstanding_batches) AS outstanding_batches,
+                    (SELECT COUNT(*) FROM deleted_collection_jobs) AS collection_jobs,
+                    (SELECT COUNT(*) FROM deleted_aggregate_share_jobs) AS aggregate_share_jobs,
+                    (SELECT COUNT(*) FROM deleted_batches) AS batches",
+            )
+            .await?;
+        DeletedRows::from_row(
+            &self
+                .query_one(
+                    &stmt,
+                    &[
+                        /* task_id */ &task_id.as_ref(),
+                        /* limit */ &i64::try_from(limit)?,
+                    ],
+                )
+                .await?,
+        )
+    }
+
+    /// Returns the width of the range of client timestamps covered by each partition of the
+    /// client_reports & report_aggregations tables, or `None` if these tables are not partitioned.
+    #[tracing::instrument(skip(self), err)]
+    pub async fn get_report_partition_size(&self) -> Result<Option<Duration>, Error> {
+        let stmt = self
+            .prepare_cached("SELECT partition_size FROM report_partitioning")
+            .await?;
+        self.query_opt(&stmt, &[])
+            .await?
+            .map(|row| {
+                Ok(Duration::from_seconds(
+                    row.get_bigint_and_convert("partition_size")?,
+                ))
+            })
+            .transpose()
+    }
+
+    /// Converts the client_reports & report_aggregations tables into tables partitioned by client
+    /// timestamp, each partition covering a range of `partition_size` worth of client timestamps.
+    /// Once partitioned, expired client reports & report aggregations can be deleted a whole
+    /// partition at a time; see [`Self::create_report_partitions`] and
+    /// [`Self::drop_report_partition_if_expired`].
+    ///
+    /// Existing rows are kept in a single partition covering all client timestamps up to the first
+    /// partition boundary after both the current time & the latest existing client timestamp.
+    ///
+    /// Partitioning has some costs:
+    ///
+    /// * The tables' unique constraints must include the client timestamp, so the datastore can no
+    ///   longer guarantee that concurrent writes of the same report ID with different client
+    ///   timestamps are detected; non-concurrent writes are still detected.
+    /// * Both tables are locked for the duration of the conversion, which rebuilds their indexes.
+    ///   This should be done during a maintenance window, in a transaction of its own.
+    ///
+    /// Returns `Err(Error::MutationTargetAlreadyExists)` if the tables are already partitioned.
+    #[tracing::instrument(skip(self), err)]
+    pub async fn partition_report_tables(&self, partition_size: &Duration) -> Result<(), Error> {
+        if partition_size.as_seconds() == 0 {
+            return Err(Error::InvalidParameter("partition_size"));
+        }
+
+        // Take the locks before running any query, so that no rows can be written that are not
+        // visible to this transaction's snapshot.
+        self.batch_execute(
+            "LOCK TABLE client_reports, report_aggregations IN ACCESS EXCLUSIVE MODE",
+        )
+        .await?;
+
+        let stmt = self
+            .prepare_cached(
+                "INSERT INTO report_partitioning (partition_size) VALUES ($1)
+                ON CONFLICT DO NOTHING",
+            )
+            .await?;
+        check_insert(
+            self.execute(
+                &stmt,
+                &[
+                    /* partition_size */ &i64::try_from(partition_size.as_seconds())?,
+                ],
+            )
+            .await?,
+        )?;
+
+        // Report aggregations' client timestamps are bounded by those of their aggregation jobs,
+        // which are cheaper to scan.
+        let stmt = self
+            .prepare_cached(
+                "SELECT GREATEST(
+                    $1::TIMESTAMP,
+                    (SELECT MAX(latest) FROM tasks, LATERAL (
+                        SELECT MAX(client_timestamp) AS latest FROM client_reports
+                        WHERE client_reports.task_id = tasks.id
+                    ) AS task_latest),
+                    (SELECT MAX(UPPER(client_timestamp_interval)) FROM aggregation_jobs)
+                ) AS latest",
+            )
+            .await?;
+        let latest = Time::from_naive_date_time(
+            &self
+                .query_one(&stmt, &[/* now */ &self.clock.now().as_naive_date_time()?])
+                .await?
+                .get("latest"),
+        );
+        let cutover = latest
+            .to_batch_interval_start(partition_size)?
+            .add(partition_size)?;
+        let cutover_literal = cutover.as_naive_date_time()?.format("%Y-%m-%d %H:%M:%S");
+
+        // The existing tables become the legacy partitions. Their identity columns are moved to the
+        // new partitioned tables, and their primary keys & unique constraints are replaced by ones
+        // that include the partition key, as Postgres requires.
+        self.batch_execute(&format!(
+            "ALTER TABLE client_reports RENAME TO client_reports_legacy;
+            ALTER TABLE client_reports_legacy DROP CONSTRAINT client_reports_pkey;
+            ALTER TABLE client_reports_legacy RENAME CONSTRAINT client_reports_unique_task_id_and_report_id TO client_reports_legacy_unique_task_id_and_report_id;
+            ALTER INDEX client_reports_task_and_timestamp_unaggregated_index RENAME TO client_reports_legacy_task_and_timestamp_unaggregated_index;
+            ALTER INDEX client_reports_task_and_timestamp_index RENAME TO client_reports_legacy_task_and_timestamp_index;
+            ALTER TABLE client_reports_legacy ALTER COLUMN id DROP IDENTITY;
+
+            CREATE TABLE client_reports (LIKE client_reports_legacy INCLUDING DEFAULTS) PARTITION BY RANGE (client_timestamp);
+            ALTER TABLE client_reports ALTER COLUMN id ADD GENERATED ALWAYS AS IDENTITY;
+            ALTER TABLE client_reports ADD CONSTRAINT client_reports_pkey PRIMARY KEY (id, client_timestamp);
+            ALTER TABLE client_reports ADD CONSTRAINT client_reports_unique_task_id_and_report_id UNIQUE (task_id, report_id, client_timestamp);
+            ALTER TABLE client_reports ADD CONSTRAINT fk_task_id FOREIGN KEY (task_id) REFERENCES tasks(id) ON DELETE CASCADE;
+            CREATE INDEX client_reports_task_and_timestamp_unaggregated_index ON client_reports (task_id, client_timestamp) WHERE aggregation_started = FALSE;
+            CREATE INDEX client_reports_task_and_timestamp_index ON client_reports (task_id, client_timestamp);
+            SELECT setval(pg_get_serial_sequence('client_reports', 'id'), (SELECT COALESCE(MAX(id), 0) + 1 FROM client_reports_legacy), false);
+            ALTER TABLE client_reports ATTACH PARTITION client_reports_legacy FOR VALUES FROM (MINVALUE) TO ('{cutover_literal}');
+            CREATE TABLE client_reports_default PARTITION OF client_reports DEFAULT;
+
+            ALTER TABLE report_aggregations RENAME TO report_aggregations_legacy;
+            ALTER TABLE report_aggregations_legacy DROP CONSTRAINT report_aggregations_pkey;
+            ALTER TABLE report_aggregations_legacy RENAME CONSTRAINT report_aggregations_unique_ord TO report_aggregations_legacy_unique_ord;
+            ALTER INDEX report_aggregations_aggregation_job_id_index RENAME TO report_aggregations_legacy_aggregation_job_id_index;
+            ALTER INDEX report_aggregations_client_report_id_index RENAME TO report_aggregations_legacy_client_report_id_index;
+            ALTER TABLE report_aggregations_legacy ALTER COLUMN id DROP IDENTITY;
+
+            CREATE TABLE report_aggregations (LIKE report_aggregations_legacy INCLUDING DEFAULTS) PARTITION BY RANGE (client_timestamp);
+            ALTER TABLE report_aggregations ALTER COLUMN id ADD GENERATED ALWAYS AS IDENTITY;
+            ALTER TABLE report_aggregations ADD CONSTRAINT report_aggregations_pkey PRIMARY KEY (id, client_timestamp);
+            ALTER TABLE report_aggregations ADD CONSTRAINT report_aggregations_unique_ord UNIQUE (aggregation_job_id, ord, client_timestamp);
+            ALTER TABLE report_aggregations ADD CONSTRAINT fk_aggregation_job_id FOREIGN KEY (aggregation_job_id) REFERENCES aggregation_jobs(id) ON DELETE CASCADE;
+            CREATE INDEX report_aggregations_aggregation_job_id_index ON report_aggregations (aggregation_job_id);
+            CREATE INDEX report_aggregations_client_report_id_index ON report_aggregations (client_report_id);
+            SELECT setval(pg_get_serial_sequence('report_aggregations', 'id'), (SELECT COALESCE(MAX(id), 0) + 1 FROM report_aggregations_legacy), false);
+            ALTER TABLE report_aggregations ATTACH PARTITION report_aggregations_legacy FOR VALUES FROM (MINVALUE) TO ('{cutover_literal}');
+            CREATE TABLE report_aggregations_default PARTITION OF report_aggregations DEFAULT;"
+        ))
+        .await?;
+
+        let stmt = self
+            .prepare_cached(
+                "INSERT INTO report_partitions (name, partition_start, partition_end)
+                VALUES ('legacy', '-infinity'::TIMESTAMP, $1)",
+            )
+            .await?;
+        self.execute(&stmt, &[/* partition_end */ &cutover.as_naive_date_time()?])
+            .await?;
+
+        // Create the partition that the current time will fall into once it passes the cutover.
+        self.add_report_partitions(cutover, partition_size, &cutover.add(partition_size)?)
+            .await?;
+        Ok(())
+    }
+
+    /// Creates partitions of the client_reports & report_aggregations tables so that they cover
+    /// client timestamps up to at least `lookahead` past the current time. Any rows in the default
+    /// partitions (which hold rows whose client timestamp is not covered by any other partition)
+    /// that fall into the new partitions are moved into them. Returns the number of partitions
+    /// created; does nothing if the tables are not partitioned.
+    #[tracing::instrument(skip(self), err)]
+    pub async fn create_report_partitions(&self, lookahead: &Duration) -> Result<u64, Error> {
+        let stmt = self
+            .prepare_cached(
+                "SELECT partition_size, (SELECT MAX(partition_end) FROM report_partitions)
+                    AS partitioned_until
+                FROM report_partitioning",
+            )
+            .await?;
+        let row = match self.query_opt(&stmt, &[]).await? {
+            Some(row) => row,
+            None => return Ok(0),
+        };
+        let partition_size = Duration::from_seconds(row.get_bigint_and_convert("partition_size")?);
+        let partitioned_until = Time::from_naive_date_time(&row.get("partitioned_until"));
+
+        self.add_report_partitions(
+            partitioned_until,
+            &partition_size,
+            &self.clock.now().add(lookahead)?,
+        )
+        .await
+    }
+
+    /// Adds consecutive partitions of `partition_size` to the client_reports & report_aggregations
+    /// tables, starting at `start`, until they cover client timestamps up to `until`.
+    async fn add_report_partitions(
+        &self,
+        mut start: Time,
+        partition_size: &Duration,
+        until: &Time,
+    ) -> Result<u64, Error> {
+        let stmt = self
+            .prepare_cached(
+                "INSERT INTO report_partitions (name, partition_start, partition_end)
+                VALUES ($1, $2, $3)",
+            )
+            .await?;
+
+        let mut created = 0;
+        while until.is_after(&start) {
+            let end = start.add(partition_size)?;
+            let name = format!("p{}", start.as_seconds_since_epoch());
+            let (start_literal, end_literal) = (
+                start.as_naive_date_time()?.format("%Y-%m-%d %H:%M:%S"),
+                end.as_naive_date_time()?.format("%Y-%m-%d %H:%M:%S"),
+            );
+
+            for table in ["client_reports", "report_aggregations"] {
+                self.batch_execute(&format!(
+                    "CREATE TABLE {table}_{name} (LIKE {table} INCLUDING DEFAULTS);
+                    WITH moved AS (
+                        DELETE FROM {table}_default
+                        WHERE client_timestamp >= '{start_literal}' AND client_timestamp < '{end_literal}'
+                        RETURNING *
+                    )
+                    INSERT INTO {table}_{name} SELECT * FROM moved;
+                    ALTER TABLE {table} ATTACH PARTITION {table}_{name}
+                        FOR VALUES FROM ('{start_literal}') TO ('{end_literal}');"
+                ))
+                .await?;
+            }
+            self.execute(
+                &stmt,
+                &[
+                    /* name */ &name,
+                    /* partition_start */ &start.as_naive_date_time()?,
+                    /* partition_end */ &end.as_naive_date_time()?,
+                ],
+            )
+            .await?;
+
+            created += 1;
+            start = end;
+        }
+        Ok(created)
+    }
+
+    /// Returns the names of the partitions of the client_reports & report_aggregations tables
+    /// covering only client timestamps before the current time, oldest first. These are the
+    /// candidates for [`Self::drop_report_partition_if_expired`].
+    #[tracing::instrument(skip(self), err)]
+    pub async fn get_ended_report_partitions(&self) -> Result<Vec<String>, Error> {
+        let stmt = self
+            .prepare_cached(
+                "SELECT name FROM report_partitions
+                WHERE partition_end <= $1
+                ORDER BY partition_end",
+            )
+            .await?;
+        Ok(self
+            .query(&stmt, &[/* now */ &self.clock.now().as_naive_date_time()?])
+            .await?
+            .into_iter()
+            .map(|row| row.get("name"))
+            .collect())
+    }
+
+    /// Drops the named partition of the client_reports & report_aggregations tables if none of
+    /// the rows in it would be visible to the datastore anymore, i.e. if the client reports in it
+    /// are all older than their task's report expiry age, and so are all aggregation jobs which
+    /// might have report aggregations in it. Returns whether the partition was dropped.
+    ///
+    /// This locks the partition, and should be called in a transaction of its own.
+    #[tracing::instrument(skip(self), err)]
+    pub async fn drop_report_partition_if_expired(&self, name: &str) -> Result<bool, Error> {
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+        {
+            return Err(Error::InvalidParameter("name"));
+        }
+
+        // Take the locks before running any query, so that no rows can be written to the partition
+        // that are not visible to this transaction's snapshot.
+        self.batch_execute(&format!(
+            "LOCK TABLE client_reports_{name}, report_aggregations_{name} IN ACCESS EXCLUSIVE MODE"
+        ))
+        .await?;
+
+        let stmt = self
+            .prepare_cached(
+                "SELECT EXISTS(
+                    SELECT 1 FROM tasks
+                    WHERE COALESCE($2::TIMESTAMP - tasks.report_expiry_age * '1 second'::INTERVAL, '-infinity'::TIMESTAMP) < report_partitions.partition_end
+                      AND (
+                        EXISTS(
+                            SELECT 1 FROM client_reports
+                            WHERE client_reports.task_id = tasks.id
+                              AND client_reports.client_timestamp >= GREATEST(report_partitions.partition_start, COALESCE($2::TIMESTAMP - tasks.report_expiry_age * '1 second'::INTERVAL, '-infinity'::TIMESTAMP))
+                              AND client_reports.client_timestamp < report_partitions.partition_end
+                        )
+                        OR EXISTS(
+                            SELECT 1 FROM aggregation_jobs
+                            WHERE aggregation_jobs.task_id = tasks.id
+                              AND aggregation_jobs.client_timestamp_interval && TSRANGE(report_partitions.partition_start, report_partitions.partition_end)
+                              AND UPPER(aggregation_jobs.client_timestamp_interval) >= COALESCE($2::TIMESTAMP - tasks.report_expiry_age * '1 second'::INTERVAL, '-infinity'::TIMESTAMP)
+                        )
+                      )
+                ) AS unexpired
+                FROM report_partitions WHERE name = $1",
+            )
+            .await?;
+        let unexpired: bool = self
+            .query_opt(
+                &stmt,
+                &[
+                    /* name */ &name,
+                    /* now */ &self.clock.now().as_naive_date_time()?,
+                ],
+            )
+            .await?
+            .ok_or(Error::MutationTargetNotFound)?
+            .get("unexpired");
+        if unexpired {
+            return Ok(false);
+        }
+
+        self.batch_execute(&format!(
+            "DROP TABLE client_reports_{name}, report_aggregations_{name}"
+        ))
+        .await?;
+        let stmt = self
+            .prepare_cached("DELETE FROM report_partitions WHERE name = $1")
+            .await?;
+        check_single_row_mutation(self.execute(&stmt, &[/* name */ &name]).await?)?;
+        Ok(true)
+    }
+
+    /// Retrieve all global HPKE keypairs.
+    #[tracing::instrument(skip(self), err)]
+    pub async fn get_global_hpke_keypairs(&self) -> Result<Vec<GlobalHpkeKeypair>, Error> {
+        let stmt = self
+            .prepare_cached(
+                "SELECT config_id, config, private_key, state, updated_at FROM global_hpke_keys;",
+            )
+            .await?;
+        let hpke_key_rows = self.query(&stmt, &[]).await?;
+
+        hpke_key_rows
+            .iter()
+            .map(|row| self.global_hpke_keypair_from_row(row))
+            .collect()
+    }
+
+    /// Retrieve a global HPKE keypair by config ID.
+    #[tracing::instrument(skip(self), err)]
+    pub async fn get_global_hpke_keypair(
+        &self,
+        config_id: &HpkeConfigId,
+    ) -> Result<Option<GlobalHpkeKeypair>, Error> {
+        let stmt = self
+            .prepare_cached(
+                "SELECT config_id, config, private_key, state, updated_at FROM global_hpke_keys
+                    WHERE config_id = $1;",
             )
             .await?;
         self.query_opt(&stmt, &[&(u8::from(*config_id) as i16)])
@@ -4414,12 +5756,64 @@ impl<C: Clock> Transaction<'_, C> {
         )
     }
 
+    /// Validates an externally generated HPKE keypair for import as a global HPKE keypair, and
+    /// returns it with its private key unwrapped. A wrapped private key is unwrapped with
+    /// `transport_keypair` if one is provided, and otherwise with the global HPKE keypair it was
+    /// wrapped to. Returns [`Error::InvalidParameter`] if the keypair is invalid, and
+    /// [`Error::MutationTargetAlreadyExists`] if a global HPKE keypair with the same ID exists.
+    #[tracing::instrument(skip(self), err)]
+    pub async fn check_global_hpke_keypair_import(
+        &self,
+        imported_keypair: &ImportedHpkeKeypair,
+        transport_keypair: Option<&HpkeKeypair>,
+    ) -> Result<HpkeKeypair, Error> {
+        let global_transport_keypair;
+        let transport_keypair = match (transport_keypair, imported_keypair.wrapped_private_key()) {
+            (None, Some(wrapped_private_key)) => {
+                global_transport_keypair = self
+                    .get_global_hpke_keypair(wrapped_private_key.config_id())
+                    .await?
+                    .ok_or(Error::InvalidParameter(
+                        "private key was wrapped to an unknown transport key",
+                    ))?;
+                Some(global_transport_keypair.hpke_keypair())
+            }
+            (transport_keypair, _) => transport_keypair,
+        };
+        let keypair = imported_keypair.keypair(transport_keypair)?;
+
+        if self
+            .get_global_hpke_keypair(keypair.config().id())
+            .await?
+            .is_some()
+        {
+            return Err(Error::MutationTargetAlreadyExists);
+        }
+        Ok(keypair)
+    }
+
+    /// Imports an externally generated HPKE keypair as a global HPKE keypair, in the
+    /// [`HpkeKeyState::Pending`] state, after checking it with
+    /// [`Self::check_global_hpke_keypair_import`]. Returns the imported keypair.
+    #[tracing::instrument(skip(self), err)]
+    pub async fn import_global_hpke_keypair(
+        &self,
+        imported_keypair: &ImportedHpkeKeypair,
+        transport_keypair: Option<&HpkeKeypair>,
+    ) -> Result<HpkeKeypair, Error> {
+        let keypair = self
+            .check_global_hpke_keypair_import(imported_keypair, transport_keypair)
+            .await?;
+        self.put_global_hpke_keypair(&keypair).await?;
+        Ok(keypair)
+    }
+
     #[tracing::instrument(skip(self), err)]
     pub async fn get_taskprov_peer_aggregators(&self) -> Result<Vec<PeerAggregator>, Error> {
         let stmt = self
             .prepare_cached(
                 "SELECT id, endpoint, role, verify_key_init, collector_hpke_config,
-                        report_expiry_age, tolerable_clock_skew
+                        report_expiry_age, tolerable_clock_skew, policy
                     FROM taskprov_peer_aggregators",
             )
             .await?;
@@ -4497,7 +5891,7 @@ impl<C: Clock> Transaction<'_, C> {
         let stmt = self
             .prepare_cached(
                 "SELECT id, endpoint, role, verify_key_init, collector_hpke_config,
-                        report_expiry_age, tolerable_clock_skew
+                        report_expiry_age, tolerable_clock_skew, policy
                     FROM taskprov_peer_aggregators WHERE endpoint = $1 AND role = $2",
             )
             .await?;
@@ -4556,6 +5950,9 @@ impl<C: Clock> Transaction<'_, C> {
         );
         let collector_hpke_config =
             HpkeConfig::get_decoded(peer_aggregator_row.get("collector_hpke_config"))?;
+        let policy = peer_aggregator_row
+            .try_get::<_, Json<TaskprovPolicy>>("policy")?
+            .0;
 
         let encrypted_verify_key_init: Vec<u8> = peer_aggregator_row.get("verify_key_init");
         let verify_key_init = self
@@ -4607,6 +6004,7 @@ impl<C: Clock> Transaction<'_, C> {
             tolerable_clock_skew,
             aggregator_auth_tokens,
             collector_auth_tokens,
+            policy,
         ))
     }
 
@@ -4628,8 +6026,8 @@ impl<C: Clock> Transaction<'_, C> {
             .prepare_cached(
                 "INSERT INTO taskprov_peer_aggregators (
                     endpoint, role, verify_key_init, tolerable_clock_skew, report_expiry_age,
-                    collector_hpke_config
-                ) VALUES ($1, $2, $3, $4, $5, $6)
+                    collector_hpke_config, policy
+                ) VALUES ($1, $2, $3, $4, $5, $6, $7)
                 ON CONFLICT DO NOTHING",
             )
             .await?;
@@ -4650,11 +6048,25 @@ impl<C: Clock> Transaction<'_, C> {
                         .transpose()?,
                     /* collector_hpke_config */
                     &peer_aggregator.collector_hpke_config().get_encoded(),
+                    /* policy */ &Json(peer_aggregator.policy()),
                 ],
             )
             .await?,
         )?;
 
+        self.put_taskprov_peer_aggregator_auth_tokens(peer_aggregator)
+            .await
+    }
+
+    /// Writes the aggregator and collector authentication tokens of the given peer aggregator,
+    /// which must already exist.
+    async fn put_taskprov_peer_aggregator_auth_tokens(
+        &self,
+        peer_aggregator: &PeerAggregator,
+    ) -> Result<(), Error> {
+        let endpoint = peer_aggregator.endpoint().as_str();
+        let role = &AggregatorRole::from_role(*peer_aggregator.role())?;
+
         let encrypt_tokens = |tokens: &[AuthenticationToken], table| -> Result<_, Error> {
             let mut ords = Vec::new();
             let mut types = Vec::new();
@@ -4726,6 +6138,243 @@ impl<C: Clock> Transaction<'_, C> {
         Ok(())
     }
 
+    /// Updates the peer aggregator with the same endpoint and role as the given peer aggregator,
+    /// replacing all of its other parameters, including its authentication tokens. Tasks that were
+    /// provisioned with the peer aggregator via taskprov are updated to match, and their IDs are
+    /// returned.
+    #[tracing::instrument(skip(self, peer_aggregator), err)]
+    pub async fn update_taskprov_peer_aggregator(
+        &self,
+        peer_aggregator: &PeerAggregator,
+    ) -> Result<Vec<TaskId>, Error> {
+        let endpoint = peer_aggregator.endpoint().as_str();
+        let role = &AggregatorRole::from_role(*peer_aggregator.role())?;
+        let encrypted_verify_key_init = self.crypter.encrypt(
+            "taskprov_peer_aggregator",
+            endpoint.as_ref(),
+            "verify_key_init",
+            peer_aggregator.verify_key_init().as_ref(),
+        )?;
+
+        let stmt = self
+            .prepare_cached(
+                "UPDATE taskprov_peer_aggregators SET
+                    verify_key_init = $3, tolerable_clock_skew = $4, report_expiry_age = $5,
+                    collector_hpke_config = $6, policy = $7
+                WHERE endpoint = $1 AND role = $2",
+            )
+            .await?;
+        check_single_row_mutation(
+            self.execute(
+                &stmt,
+                &[
+                    /* endpoint */ &endpoint,
+                    /* role */ role,
+                    /* verify_key_init */ &encrypted_verify_key_init,
+                    /* tolerable_clock_skew */
+                    &i64::try_from(peer_aggregator.tolerable_clock_skew().as_seconds())?,
+                    /* report_expiry_age */
+                    &peer_aggregator
+                        .report_expiry_age()
+                        .map(Duration::as_seconds)
+                        .map(i64::try_from)
+                        .transpose()?,
+                    /* collector_hpke_config */
+                    &peer_aggregator.collector_hpke_config().get_encoded(),
+                    /* policy */ &Json(peer_aggregator.policy()),
+                ],
+            )
+            .await?,
+        )?;
+
+        // Replace the authentication tokens wholesale, since their order is significant.
+        let params: &[&(dyn ToSql + Sync)] = &[/* endpoint */ &endpoint, /* role */ role];
+        let stmt = self
+            .prepare_cached(
+                "DELETE FROM taskprov_aggregator_auth_tokens
+                    WHERE peer_aggregator_id = (SELECT id FROM taskprov_peer_aggregators
+                        WHERE endpoint = $1 AND role = $2)",
+            )
+            .await?;
+        let aggregator_auth_tokens_future = self.execute(&stmt, params);
+        let stmt = self
+            .prepare_cached(
+                "DELETE FROM taskprov_collector_auth_tokens
+                    WHERE peer_aggregator_id = (SELECT id FROM taskprov_peer_aggregators
+                        WHERE endpoint = $1 AND role = $2)",
+            )
+            .await?;
+        let collector_auth_tokens_future = self.execute(&stmt, params);
+        try_join!(aggregator_auth_tokens_future, collector_auth_tokens_future)?;
+
+        self.put_taskprov_peer_aggregator_auth_tokens(peer_aggregator)
+            .await?;
+
+        self.update_taskprov_peer_aggregator_tasks(peer_aggregator)
+            .await
+    }
+
+    /// Applies the parameters of the given peer aggregator to the tasks which were provisioned
+    /// with it via taskprov, since these parameters are copied into each task when we opt in to it.
+    /// Returns the IDs of the updated tasks.
+    async fn update_taskprov_peer_aggregator_tasks(
+        &self,
+        peer_aggregator: &PeerAggregator,
+    ) -> Result<Vec<TaskId>, Error> {
+        // We are the helper in tasks provisioned with a leader peer, and vice versa.
+        let (stmt, our_role) = match peer_aggregator.role() {
+            Role::Leader => (
+                "UPDATE tasks SET tolerable_clock_skew = $3, report_expiry_age = $4
+                    WHERE aggregator_role = $2 AND leader_aggregator_endpoint = $1
+                        AND taskprov_task_config IS NOT NULL
+                    RETURNING task_id",
+                Role::Helper,
+            ),
+            Role::Helper => (
+                "UPDATE tasks SET tolerable_clock_skew = $3, report_expiry_age = $4,
+                        collector_hpke_config = $5
+                    WHERE aggregator_role = $2 AND helper_aggregator_endpoint = $1
+                        AND taskprov_task_config IS NOT NULL
+                    RETURNING task_id",
+                Role::Leader,
+            ),
+            _ => return Err(Error::InvalidParameter("peer aggregator has invalid role")),
+        };
+        let endpoint = peer_aggregator.endpoint().as_str();
+        let aggregator_role = AggregatorRole::from_role(our_role)?;
+        let tolerable_clock_skew =
+            i64::try_from(peer_aggregator.tolerable_clock_skew().as_seconds())?;
+        let report_expiry_age = peer_aggregator
+            .report_expiry_age()
+            .map(Duration::as_seconds)
+            .map(i64::try_from)
+            .transpose()?;
+        let collector_hpke_config = peer_aggregator.collector_hpke_config().get_encoded();
+        let mut params: Vec<&(dyn ToSql + Sync)> = Vec::from([
+            /* endpoint */ &endpoint as &(dyn ToSql + Sync),
+            /* aggregator_role */ &aggregator_role,
+            /* tolerable_clock_skew */ &tolerable_clock_skew,
+            /* report_expiry_age */ &report_expiry_age,
+        ]);
+        if our_role == Role::Leader {
+            params.push(/* collector_hpke_config */ &collector_hpke_config);
+        }
+
+        let stmt = self.prepare_cached(stmt).await?;
+        let rows = self.query(&stmt, &params).await?;
+        let task_ids = rows
+            .iter()
+            .map(|row| row.get::<_, Vec<u8>>("task_id"))
+            .collect::<Vec<_>>();
+        let updated_task_ids = rows
+            .iter()
+            .map(|row| row.get_bytea_and_convert::<TaskId>("task_id"))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Only the leader uses the peer aggregator's authentication tokens in its tasks. The helper
+        // authenticates taskprov requests against the peer aggregator itself.
+        if our_role != Role::Leader || task_ids.is_empty() {
+            return Ok(updated_task_ids);
+        }
+
+        let params: &[&(dyn ToSql + Sync)] = &[/* task_ids */ &task_ids];
+        let stmt = self
+            .prepare_cached(
+                "DELETE FROM task_aggregator_auth_tokens
+                    WHERE task_id IN (SELECT id FROM tasks WHERE task_id = ANY($1))",
+            )
+            .await?;
+        let aggregator_auth_tokens_future = self.execute(&stmt, params);
+        let stmt = self
+            .prepare_cached(
+                "DELETE FROM task_collector_auth_tokens
+                    WHERE task_id IN (SELECT id FROM tasks WHERE task_id = ANY($1))",
+            )
+            .await?;
+        let collector_auth_tokens_future = self.execute(&stmt, params);
+        try_join!(aggregator_auth_tokens_future, collector_auth_tokens_future)?;
+
+        let encrypt_tokens = |tokens: &[AuthenticationToken], table| -> Result<_, Error> {
+            let mut token_task_ids = Vec::new();
+            let mut ords = Vec::new();
+            let mut types = Vec::new();
+            let mut encrypted_tokens = Vec::new();
+            for task_id in &task_ids {
+                for (ord, token) in tokens.iter().enumerate() {
+                    let ord = i64::try_from(ord)?;
+
+                    let mut row_id = Vec::with_capacity(task_id.len() + size_of::<i64>());
+                    row_id.extend_from_slice(task_id);
+                    row_id.extend_from_slice(&ord.to_be_bytes());
+
+                    token_task_ids.push(task_id.clone());
+                    ords.push(ord);
+                    types.push(AuthenticationTokenType::from(token));
+                    encrypted_tokens.push(self.crypter.encrypt(
+                        table,
+                        &row_id,
+                        "token",
+                        token.as_ref(),
+                    )?);
+                }
+            }
+            Ok((token_task_ids, ords, types, encrypted_tokens))
+        };
+
+        let (
+            aggregator_auth_token_task_ids,
+            aggregator_auth_token_ords,
+            aggregator_auth_token_types,
+            aggregator_auth_tokens,
+        ) = encrypt_tokens(
+            peer_aggregator.aggregator_auth_tokens(),
+            "task_aggregator_auth_tokens",
+        )?;
+        let stmt = self
+            .prepare_cached(
+                "INSERT INTO task_aggregator_auth_tokens (task_id, ord, type, token)
+                SELECT (SELECT id FROM tasks WHERE tasks.task_id = t.task_id), t.ord, t.type, t.token
+                    FROM UNNEST($1::BYTEA[], $2::BIGINT[], $3::AUTH_TOKEN_TYPE[], $4::BYTEA[])
+                        AS t(task_id, ord, type, token)",
+            )
+            .await?;
+        let aggregator_auth_tokens_params: &[&(dyn ToSql + Sync)] = &[
+            /* task_ids */ &aggregator_auth_token_task_ids,
+            /* ords */ &aggregator_auth_token_ords,
+            /* token_types */ &aggregator_auth_token_types,
+            /* tokens */ &aggregator_auth_tokens,
+        ];
+        let aggregator_auth_tokens_future = self.execute(&stmt, aggregator_auth_tokens_params);
+
+        let (
+            collector_auth_token_task_ids,
+            collector_auth_token_ords,
+            collector_auth_token_types,
+            collector_auth_tokens,
+        ) = encrypt_tokens(
+            peer_aggregator.collector_auth_tokens(),
+            "task_collector_auth_tokens",
+        )?;
+        let stmt = self
+            .prepare_cached(
+                "INSERT INTO task_collector_auth_tokens (task_id, ord, type, token)
+                SELECT (SELECT id FROM tasks WHERE tasks.task_id = t.task_id), t.ord, t.type, t.token
+                    FROM UNNEST($1::BYTEA[], $2::BIGINT[], $3::AUTH_TOKEN_TYPE[], $4::BYTEA[])
+                        AS t(task_id, ord, type, token)",
+            )
+            .await?;
+        let collector_auth_tokens_params: &[&(dyn ToSql + Sync)] = &[
+            /* task_ids */ &collector_auth_token_task_ids,
+            /* ords */ &collector_auth_token_ords,
+            /* token_types */ &collector_auth_token_types,
+            /* tokens */ &collector_auth_tokens,
+        ];
+        let collector_auth_tokens_future = self.execute(&stmt, collector_auth_tokens_params);
+
+        try_join!(aggregator_auth_tokens_future, collector_auth_tokens_future)?;
+        Ok(updated_task_ids)
+    }
+
     #[tracing::instrument(skip(self), err)]
     pub async fn delete_taskprov_peer_aggregator(
         &self,
@@ -4745,6 +6394,53 @@ impl<C: Clock> Transaction<'_, C> {
     }
 }
 
+/// Number of rows deleted from each table by a garbage collection operation, keyed by table name.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DeletedRows(BTreeMap<String, u64>);
+
+impl DeletedRows {
+    /// Builds a `DeletedRows` from a row in which each column is named for a table, and holds the
+    /// number of rows deleted from that table.
+    fn from_row(row: &Row) -> Result<Self, Error> {
+        row.columns()
+            .iter()
+            .enumerate()
+            .map(|(index, column)| {
+                Ok((
+                    column.name().to_string(),
+                    u64::try_from(row.get::<_, i64>(index))?,
+                ))
+            })
+            .collect::<Result<_, Error>>()
+            .map(Self)
+    }
+
+    /// Returns the number of rows deleted from the given table.
+    pub fn get(&self, table: &str) -> u64 {
+        self.0.get(table).copied().unwrap_or(0)
+    }
+
+    /// Returns the total number of rows deleted across all tables.
+    pub fn total(&self) -> u64 {
+        self.0.values().sum()
+    }
+
+    /// Iterates over table names and the number of rows deleted from each.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, u64)> {
+        self.0.iter().map(|(table, count)| (table.as_str(), *count))
+    }
+}
+
+/// Builds a map of counts by state from rows consisting of a `state` column and a `count` column.
+fn state_counts<S>(rows: Vec<Row>) -> Result<BTreeMap<S, u64>, Error>
+where
+    S: for<'a> FromSql<'a> + Ord,
+{
+    rows.into_iter()
+        .map(|row| Ok((row.get("state"), row.get_bigint_and_convert("count")?)))
+        .collect()
+}
+
 fn check_insert(row_count: u64) -> Result<(), Error> {
     match row_count {
         0 => Err(Error::MutationTargetAlreadyExists),
@@ -5,7 +5,7 @@ use base64::{display::Base64Display, engine::general_purpose::URL_SAFE_NO_PAD};
 use chrono::NaiveDateTime;
 use derivative::Derivative;
 use janus_core::{
-    hpke::HpkeKeypair,
+    hpke::{unwrap_private_key, HpkeKeypair, HpkePrivateKey},
     report_id::ReportIdChecksumExt,
     task::{AuthenticationToken, VdafInstance},
     time::{DurationExt, IntervalExt, TimeExt},
@@ -13,20 +13,21 @@ use janus_core::{
 use janus_messages::{
     query_type::{FixedSize, QueryType, TimeInterval},
     AggregationJobId, AggregationJobRound, BatchId, CollectionJobId, Duration, Extension,
-    HpkeCiphertext, Interval, PrepareStep, Query, ReportId, ReportIdChecksum, ReportMetadata,
-    ReportShareError, Role, TaskId, Time,
+    HpkeCiphertext, HpkeConfig, Interval, PrepareStep, Query, ReportId, ReportIdChecksum,
+    ReportMetadata, ReportShareError, Role, TaskId, Time,
 };
 use postgres_protocol::types::{
     range_from_sql, range_to_sql, timestamp_from_sql, timestamp_to_sql, Range, RangeBound,
 };
 use postgres_types::{accepts, to_sql_checked, FromSql, ToSql};
 use prio::{
-    codec::Encode,
+    codec::{encode_u16_items, Encode},
     vdaf::{self, Aggregatable},
 };
 use rand::{distributions::Standard, prelude::Distribution};
 use serde::{Deserialize, Serialize};
 use std::{
+    collections::BTreeMap,
     fmt::{Debug, Display, Formatter},
     hash::{Hash, Hasher},
     ops::RangeInclusive,
@@ -160,6 +161,70 @@ where
 {
 }
 
+/// A [`LeaderStoredReport`] with its VDAF-specific fields encoded, as they are stored in the
+/// `client_reports` table. This allows reports for tasks with different VDAFs to be written
+/// together, by [`Transaction::put_client_reports`](crate::datastore::Transaction::put_client_reports).
+#[derive(Clone, Derivative, PartialEq, Eq)]
+#[derivative(Debug)]
+pub struct EncodedLeaderStoredReport {
+    task_id: TaskId,
+    metadata: ReportMetadata,
+    #[derivative(Debug = "ignore")]
+    public_share: Vec<u8>,
+    #[derivative(Debug = "ignore")]
+    leader_extensions: Vec<u8>,
+    #[derivative(Debug = "ignore")]
+    leader_input_share: Vec<u8>,
+    #[derivative(Debug = "ignore")]
+    helper_encrypted_input_share: Vec<u8>,
+}
+
+impl EncodedLeaderStoredReport {
+    pub fn task_id(&self) -> &TaskId {
+        &self.task_id
+    }
+
+    pub fn metadata(&self) -> &ReportMetadata {
+        &self.metadata
+    }
+
+    pub(super) fn public_share(&self) -> &[u8] {
+        &self.public_share
+    }
+
+    pub(super) fn leader_extensions(&self) -> &[u8] {
+        &self.leader_extensions
+    }
+
+    pub(super) fn leader_input_share(&self) -> &[u8] {
+        &self.leader_input_share
+    }
+
+    pub(super) fn helper_encrypted_input_share(&self) -> &[u8] {
+        &self.helper_encrypted_input_share
+    }
+}
+
+impl<const SEED_SIZE: usize, A> From<&LeaderStoredReport<SEED_SIZE, A>>
+    for EncodedLeaderStoredReport
+where
+    A: vdaf::Aggregator<SEED_SIZE, 16>,
+{
+    fn from(report: &LeaderStoredReport<SEED_SIZE, A>) -> Self {
+        let mut leader_extensions = Vec::new();
+        encode_u16_items(&mut leader_extensions, &(), report.leader_extensions());
+
+        Self {
+            task_id: *report.task_id(),
+            metadata: report.metadata().clone(),
+            public_share: report.public_share().get_encoded(),
+            leader_extensions,
+            leader_input_share: report.leader_input_share().get_encoded(),
+            helper_encrypted_input_share: report.helper_encrypted_input_share().get_encoded(),
+        }
+    }
+}
+
 #[cfg(feature = "test-util")]
 impl LeaderStoredReport<0, janus_core::test_util::dummy_vdaf::Vdaf> {
     pub fn new_dummy(task_id: TaskId, when: Time) -> Self {
@@ -368,8 +433,11 @@ where
 
 /// AggregationJobState represents the state of an aggregation job. It corresponds to the
 /// AGGREGATION_JOB_STATE enum in the schema.
-#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, ToSql, FromSql)]
+#[derive(
+    Copy, Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord, ToSql, FromSql, Serialize, Deserialize,
+)]
 #[postgres(name = "aggregation_job_state")]
+#[serde(rename_all = "snake_case")]
 pub enum AggregationJobState {
     #[postgres(name = "IN_PROGRESS")]
     InProgress,
@@ -771,8 +839,11 @@ pub(super) struct EncodedReportAggregationStateValues {
 // because there is no apparent way to denote a Postgres enum literal without deriving
 // FromSql/ToSql on a Rust enum type, but it is not possible to derive FromSql/ToSql on a
 // non-C-style enum.
-#[derive(Debug, FromSql, ToSql)]
+#[derive(
+    Copy, Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord, FromSql, ToSql, Serialize, Deserialize,
+)]
 #[postgres(name = "report_aggregation_state")]
+#[serde(rename_all = "snake_case")]
 pub enum ReportAggregationStateCode {
     #[postgres(name = "START")]
     Start,
@@ -1178,6 +1249,9 @@ pub enum CollectionJobState<const SEED_SIZE: usize, A: vdaf::Aggregator<SEED_SIZ
         report_count: u64,
         /// The helper's encrypted aggregate share over the input shares in the interval.
         encrypted_helper_aggregate_share: HpkeCiphertext,
+        /// The collector HPKE config that the helper's aggregate share was encrypted to, if known.
+        /// This is `None` for collection jobs that finished before it was recorded.
+        helper_aggregate_share_hpke_config: Option<HpkeConfig>,
         /// The leader's aggregate share over the input shares in the interval.
         #[derivative(Debug = "ignore")]
         leader_aggregate_share: A::AggregateShare,
@@ -1231,16 +1305,19 @@ where
                 Self::Finished {
                     report_count: self_report_count,
                     encrypted_helper_aggregate_share: self_helper_agg_share,
+                    helper_aggregate_share_hpke_config: self_helper_agg_share_hpke_config,
                     leader_aggregate_share: self_leader_agg_share,
                 },
                 Self::Finished {
                     report_count: other_report_count,
                     encrypted_helper_aggregate_share: other_helper_agg_share,
+                    helper_aggregate_share_hpke_config: other_helper_agg_share_hpke_config,
                     leader_aggregate_share: other_leader_agg_share,
                 },
             ) => {
                 self_report_count == other_report_count
                     && self_helper_agg_share == other_helper_agg_share
+                    && self_helper_agg_share_hpke_config == other_helper_agg_share_hpke_config
                     && self_leader_agg_share == other_leader_agg_share
             }
             _ => core::mem::discriminant(self) == core::mem::discriminant(other),
@@ -1255,8 +1332,11 @@ where
 {
 }
 
-#[derive(Debug, FromSql, ToSql)]
+#[derive(
+    Copy, Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord, FromSql, ToSql, Serialize, Deserialize,
+)]
 #[postgres(name = "collection_job_state")]
+#[serde(rename_all = "snake_case")]
 pub enum CollectionJobStateCode {
     #[postgres(name = "START")]
     Start,
@@ -1442,8 +1522,11 @@ impl OutstandingBatch {
 }
 
 /// Represents the state of a `Batch`.
-#[derive(Copy, Clone, Debug, FromSql, ToSql, PartialEq, Eq, Hash)]
+#[derive(
+    Copy, Clone, Debug, FromSql, ToSql, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
 #[postgres(name = "batch_state")]
+#[serde(rename_all = "snake_case")]
 pub enum BatchState {
     /// This batch can accept the creation of additional aggregation jobs.
     #[postgres(name = "OPEN")]
@@ -1766,3 +1849,185 @@ impl GlobalHpkeKeypair {
         &self.updated_at
     }
 }
+
+/// An externally generated HPKE keypair, to be imported as a global HPKE keypair. The private key
+/// is either provided in the clear, or wrapped to a transport key by
+/// [`janus_core::hpke::wrap_private_key`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ImportedHpkeKeypair {
+    config: HpkeConfig,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    private_key: Option<HpkePrivateKey>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    wrapped_private_key: Option<HpkeCiphertext>,
+}
+
+impl ImportedHpkeKeypair {
+    /// Creates an imported keypair whose private key is provided in the clear.
+    pub fn new(config: HpkeConfig, private_key: HpkePrivateKey) -> Self {
+        Self {
+            config,
+            private_key: Some(private_key),
+            wrapped_private_key: None,
+        }
+    }
+
+    /// Creates an imported keypair whose private key is wrapped to a transport key.
+    pub fn new_wrapped(config: HpkeConfig, wrapped_private_key: HpkeCiphertext) -> Self {
+        Self {
+            config,
+            private_key: None,
+            wrapped_private_key: Some(wrapped_private_key),
+        }
+    }
+
+    pub fn config(&self) -> &HpkeConfig {
+        &self.config
+    }
+
+    /// Returns the wrapped private key, if the private key is not provided in the clear.
+    pub fn wrapped_private_key(&self) -> Option<&HpkeCiphertext> {
+        self.wrapped_private_key.as_ref()
+    }
+
+    /// Returns the keypair, unwrapping the private key with `transport_keypair` if it is wrapped,
+    /// and checking that the private key matches the configuration.
+    pub fn keypair(&self, transport_keypair: Option<&HpkeKeypair>) -> Result<HpkeKeypair, Error> {
+        match (
+            &self.private_key,
+            &self.wrapped_private_key,
+            transport_keypair,
+        ) {
+            (Some(private_key), None, None) => {
+                let keypair = HpkeKeypair::new(self.config.clone(), private_key.clone());
+                keypair.validate().map_err(|_| {
+                    Error::InvalidParameter("private key does not match HPKE configuration")
+                })?;
+                Ok(keypair)
+            }
+            (None, Some(wrapped_private_key), Some(transport_keypair)) => unwrap_private_key(
+                transport_keypair,
+                &self.config,
+                wrapped_private_key,
+            )
+            .map_err(|_| {
+                Error::InvalidParameter("couldn't unwrap private key with the transport key")
+            }),
+            (Some(_), None, Some(_)) => Err(Error::InvalidParameter(
+                "a transport key was provided, but the private key is not wrapped",
+            )),
+            (None, Some(_), None) => Err(Error::InvalidParameter(
+                "a transport key is required to unwrap the private key",
+            )),
+            _ => Err(Error::InvalidParameter(
+                "exactly one of private_key or wrapped_private_key must be provided",
+            )),
+        }
+    }
+}
+
+/// Metrics describing the reports, aggregations, batches and collections of a single task. Rows
+/// belonging to expired reports are not counted.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TaskMetrics {
+    pub(super) reports: u64,
+    pub(super) unaggregated_reports: u64,
+    pub(super) report_aggregations_by_state: BTreeMap<ReportAggregationStateCode, u64>,
+    pub(super) report_aggregation_failures: BTreeMap<ReportShareError, u64>,
+    pub(super) aggregation_jobs_by_state: BTreeMap<AggregationJobState, u64>,
+    pub(super) batches_by_state: BTreeMap<BatchState, u64>,
+    pub(super) outstanding_batches: Vec<OutstandingBatch>,
+    pub(super) collection_jobs_by_state: BTreeMap<CollectionJobStateCode, u64>,
+    pub(super) time_buckets: Option<Vec<TaskMetricsTimeBucket>>,
+}
+
+impl TaskMetrics {
+    /// Gets the number of client reports stored for the task.
+    pub fn reports(&self) -> u64 {
+        self.reports
+    }
+
+    /// Gets the number of client reports which have not yet been included in an aggregation job.
+    pub fn unaggregated_reports(&self) -> u64 {
+        self.unaggregated_reports
+    }
+
+    /// Gets the total number of report aggregations, across all states.
+    pub fn report_aggregations(&self) -> u64 {
+        self.report_aggregations_by_state.values().sum()
+    }
+
+    /// Gets the number of report aggregations in each state. States with no report aggregations
+    /// are omitted.
+    pub fn report_aggregations_by_state(&self) -> &BTreeMap<ReportAggregationStateCode, u64> {
+        &self.report_aggregations_by_state
+    }
+
+    /// Gets the number of failed report aggregations, by the error that caused them to fail.
+    pub fn report_aggregation_failures(&self) -> &BTreeMap<ReportShareError, u64> {
+        &self.report_aggregation_failures
+    }
+
+    /// Gets the number of aggregation jobs in each state. States with no aggregation jobs are
+    /// omitted.
+    pub fn aggregation_jobs_by_state(&self) -> &BTreeMap<AggregationJobState, u64> {
+        &self.aggregation_jobs_by_state
+    }
+
+    /// Gets the number of batches in each state. States with no batches are omitted.
+    pub fn batches_by_state(&self) -> &BTreeMap<BatchState, u64> {
+        &self.batches_by_state
+    }
+
+    /// Gets the task's outstanding batches, along with their current sizes. Only fixed-size tasks
+    /// have outstanding batches.
+    pub fn outstanding_batches(&self) -> &[OutstandingBatch] {
+        &self.outstanding_batches
+    }
+
+    /// Gets the number of collection jobs in each state. States with no collection jobs are
+    /// omitted.
+    pub fn collection_jobs_by_state(&self) -> &BTreeMap<CollectionJobStateCode, u64> {
+        &self.collection_jobs_by_state
+    }
+
+    /// Gets report and report aggregation counts, bucketed by client timestamp, if requested.
+    /// Buckets with no reports or report aggregations are omitted.
+    pub fn time_buckets(&self) -> Option<&[TaskMetricsTimeBucket]> {
+        self.time_buckets.as_deref()
+    }
+}
+
+/// Report and report aggregation counts for the reports of a task whose client timestamps fall
+/// within a single time bucket.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TaskMetricsTimeBucket {
+    pub(super) start: Time,
+    pub(super) reports: u64,
+    pub(super) unaggregated_reports: u64,
+    pub(super) report_aggregations: u64,
+}
+
+impl TaskMetricsTimeBucket {
+    /// Gets the start of this time bucket.
+    pub fn start(&self) -> &Time {
+        &self.start
+    }
+
+    /// Gets the number of client reports in this time bucket.
+    pub fn reports(&self) -> u64 {
+        self.reports
+    }
+
+    /// Gets the number of client reports in this time bucket which have not yet been included in
+    /// an aggregation job.
+    pub fn unaggregated_reports(&self) -> u64 {
+        self.unaggregated_reports
+    }
+
+    /// Gets the number of report aggregations in this time bucket, across all states.
+    pub fn report_aggregations(&self) -> u64 {
+        self.report_aggregations
+    }
+}
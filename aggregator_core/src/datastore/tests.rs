@@ -3,16 +3,20 @@ use crate::{
         models::{
             AcquiredAggregationJob, AcquiredCollectionJob, AggregateShareJob, AggregationJob,
             AggregationJobState, Batch, BatchAggregation, BatchAggregationState, BatchState,
-            CollectionJob, CollectionJobState, GlobalHpkeKeypair, HpkeKeyState, LeaderStoredReport,
-            Lease, OutstandingBatch, ReportAggregation, ReportAggregationState, SqlInterval,
+            CollectionJob, CollectionJobState, CollectionJobStateCode, EncodedLeaderStoredReport,
+            GlobalHpkeKeypair, HpkeKeyState, ImportedHpkeKeypair, LeaderStoredReport, Lease,
+            OutstandingBatch, ReportAggregation, ReportAggregationState,
+            ReportAggregationStateCode, SqlInterval,
         },
         schema_versions_template,
-        test_util::{ephemeral_datastore_schema_version, generate_aead_key, EphemeralDatastore},
+        test_util::{
+            self, ephemeral_datastore_schema_version, generate_aead_key, EphemeralDatastore,
+        },
         Crypter, Datastore, Error, Transaction, SUPPORTED_SCHEMA_VERSIONS,
     },
     query_type::CollectableQueryType,
-    task::{self, test_util::TaskBuilder, Task},
-    taskprov::test_util::PeerAggregatorBuilder,
+    task::{self, test_util::TaskBuilder, Task, TaskPriority},
+    taskprov::{test_util::PeerAggregatorBuilder, TaskprovPolicy, VdafPolicy},
     test_util::noop_meter,
 };
 use assert_matches::assert_matches;
@@ -21,7 +25,12 @@ use chrono::NaiveDate;
 use futures::future::try_join_all;
 use janus_core::{
     hpke::{
-        self, test_util::generate_test_hpke_config_and_private_key, HpkeApplicationInfo, Label,
+        self,
+        test_util::{
+            generate_test_hpke_config_and_private_key,
+            generate_test_hpke_config_and_private_key_with_id,
+        },
+        wrap_private_key, HpkeApplicationInfo, Label,
     },
     task::{VdafInstance, VERIFY_KEY_LENGTH},
     test_util::{
@@ -32,10 +41,11 @@ use janus_core::{
 };
 use janus_messages::{
     query_type::{FixedSize, QueryType, TimeInterval},
-    AggregateShareAad, AggregationJobId, AggregationJobRound, BatchId, BatchSelector,
+    taskprov::Rational,
+    AggregateShareAad, AggregationJobId, AggregationJobRound, BatchId, BatchSelector, Collection,
     CollectionJobId, Duration, Extension, ExtensionType, FixedSizeQuery, HpkeCiphertext,
-    HpkeConfigId, Interval, PrepareStep, PrepareStepResult, Query, ReportId, ReportIdChecksum,
-    ReportMetadata, ReportShare, ReportShareError, Role, TaskId, Time,
+    HpkeConfigId, Interval, PartialBatchSelector, PrepareStep, PrepareStepResult, Query, ReportId,
+    ReportIdChecksum, ReportMetadata, ReportShare, ReportShareError, Role, TaskId, Time,
 };
 use prio::{
     codec::{Decode, Encode},
@@ -43,7 +53,7 @@ use prio::{
 };
 use rand::{distributions::Standard, random, thread_rng, Rng};
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     iter,
     ops::RangeInclusive,
     sync::Arc,
@@ -94,6 +104,51 @@ async fn down_migrations(
     ephemeral_datastore.downgrade(0).await;
 }
 
+#[rstest_reuse::apply(schema_versions_template)]
+#[tokio::test]
+async fn run_read_only_tx(ephemeral_datastore: EphemeralDatastore) {
+    install_test_trace_subscriber();
+    // Stand in for a read replica with a separate database, so that we can tell which database
+    // each transaction ran against.
+    let replica_ephemeral_datastore = test_util::ephemeral_datastore().await;
+    let replica_ds = replica_ephemeral_datastore
+        .datastore(MockClock::default())
+        .await;
+    let ds = ephemeral_datastore
+        .datastore(MockClock::default())
+        .await
+        .with_read_replicas(Vec::from([replica_ephemeral_datastore.pool()]));
+
+    let task = TaskBuilder::new(
+        task::QueryType::TimeInterval,
+        VdafInstance::Fake,
+        Role::Leader,
+    )
+    .build();
+    replica_ds.put_task(&task).await.unwrap();
+
+    let read_only_task_ids = ds
+        .run_read_only_tx(|tx| Box::pin(async move { tx.get_task_ids(None).await }))
+        .await
+        .unwrap();
+    assert_eq!(read_only_task_ids, Vec::from([*task.id()]));
+
+    let task_ids = ds
+        .run_tx(|tx| Box::pin(async move { tx.get_task_ids(None).await }))
+        .await
+        .unwrap();
+    assert!(task_ids.is_empty());
+
+    // Writes are rejected in read-only transactions.
+    let rslt = ds
+        .run_read_only_tx(|tx| {
+            let task = task.clone();
+            Box::pin(async move { tx.put_task(&task).await })
+        })
+        .await;
+    assert_matches!(rslt, Err(Error::Db(_)));
+}
+
 #[rstest_reuse::apply(schema_versions_template)]
 #[tokio::test]
 async fn roundtrip_task(ephemeral_datastore: EphemeralDatastore) {
@@ -102,18 +157,54 @@ async fn roundtrip_task(ephemeral_datastore: EphemeralDatastore) {
 
     // Insert tasks, check that they can be retrieved by ID.
     let mut want_tasks = HashMap::new();
-    for (vdaf, role) in [
-        (VdafInstance::Prio3Count, Role::Leader),
-        (VdafInstance::Prio3CountVec { length: 8 }, Role::Leader),
-        (VdafInstance::Prio3CountVec { length: 64 }, Role::Helper),
-        (VdafInstance::Prio3Sum { bits: 64 }, Role::Helper),
-        (VdafInstance::Prio3Sum { bits: 32 }, Role::Helper),
-        (VdafInstance::Prio3Histogram { length: 4 }, Role::Leader),
-        (VdafInstance::Prio3Histogram { length: 5 }, Role::Leader),
-        (VdafInstance::Poplar1 { bits: 8 }, Role::Helper),
-        (VdafInstance::Poplar1 { bits: 64 }, Role::Helper),
+    for (vdaf, role, dp_config) in [
+        (VdafInstance::Prio3Count, Role::Leader, task::DpConfig::None),
+        (
+            VdafInstance::Prio3CountVec { length: 8 },
+            Role::Leader,
+            task::DpConfig::None,
+        ),
+        (
+            VdafInstance::Prio3CountVec { length: 64 },
+            Role::Helper,
+            task::DpConfig::None,
+        ),
+        (
+            VdafInstance::Prio3Sum { bits: 64 },
+            Role::Helper,
+            task::DpConfig::None,
+        ),
+        (
+            VdafInstance::Prio3Sum { bits: 32 },
+            Role::Helper,
+            task::DpConfig::DiscreteLaplace {
+                epsilon: Rational::new(1, 2).unwrap(),
+                sensitivity: Rational::new(1, 1).unwrap(),
+            },
+        ),
+        (
+            VdafInstance::Prio3Histogram { length: 4 },
+            Role::Leader,
+            task::DpConfig::None,
+        ),
+        (
+            VdafInstance::Prio3Histogram { length: 5 },
+            Role::Leader,
+            task::DpConfig::None,
+        ),
+        (
+            VdafInstance::Poplar1 { bits: 8 },
+            Role::Helper,
+            task::DpConfig::None,
+        ),
+        (
+            VdafInstance::Poplar1 { bits: 64 },
+            Role::Helper,
+            task::DpConfig::None,
+        ),
     ] {
         let task = TaskBuilder::new(task::QueryType::TimeInterval, vdaf, role)
+            .with_dp_config(dp_config)
             .with_report_expiry_age(Some(Duration::from_seconds(3600)))
             .build();
         want_tasks.insert(*task.id(), task.clone());
@@ -309,10 +400,49 @@ async fn get_task_metrics(ephemeral_datastore: EphemeralDatastore) {
                             *report.metadata().time(),
                             ord.try_into().unwrap(),
                             None,
-                            ReportAggregationState::Start,
+                            if ord == 0 {
+                                ReportAggregationState::Start
+                            } else {
+                                ReportAggregationState::Failed(ReportShareError::VdafPrepError)
+                            },
                         )
                     })
                     .collect();
+
+                let batch_interval =
+                    Interval::new(OLDEST_ALLOWED_REPORT_TIMESTAMP, Duration::from_seconds(1))
+                        .unwrap();
+                let batch = Batch::<0, TimeInterval, dummy_vdaf::Vdaf>::new(
+                    *task.id(),
+                    batch_interval,
+                    AggregationParam(0),
+                    BatchState::Open,
+                    1,
+                    batch_interval,
+                );
+                let expired_batch_interval = Interval::new(
+                    OLDEST_ALLOWED_REPORT_TIMESTAMP
+                        .sub(&Duration::from_seconds(2))
+                        .unwrap(),
+                    Duration::from_seconds(1),
+                )
+                .unwrap();
+                let expired_batch = Batch::<0, TimeInterval, dummy_vdaf::Vdaf>::new(
+                    *task.id(),
+                    expired_batch_interval,
+                    AggregationParam(0),
+                    BatchState::Closed,
+                    0,
+                    expired_batch_interval,
+                );
+                let collection_job = CollectionJob::<0, TimeInterval, dummy_vdaf::Vdaf>::new(
+                    *task.id(),
+                    random(),
+                    Query::new_time_interval(batch_interval),
+                    AggregationParam(0),
+                    batch_interval,
+                    CollectionJobState::Start,
+                );
                 let expired_report_aggregations: Vec<_> = expired_reports
                     .iter()
                     .take(REPORT_AGGREGATION_COUNT)
@@ -371,6 +501,9 @@ async fn get_task_metrics(ephemeral_datastore: EphemeralDatastore) {
                         }),
                 )
                 .await?;
+                tx.put_batch(&batch).await?;
+                tx.put_batch(&expired_batch).await?;
+                tx.put_collection_job(&collection_job).await?;
 
                 Ok(*task.id())
             })
@@ -384,16 +517,69 @@ async fn get_task_metrics(ephemeral_datastore: EphemeralDatastore) {
     ds.run_tx(|tx| {
         Box::pin(async move {
             // Verify we get the correct results when we check metrics on our target task.
+            let metrics = tx.get_task_metrics(&task_id, None).await.unwrap().unwrap();
+            assert_eq!(metrics.reports(), u64::try_from(REPORT_COUNT).unwrap());
             assert_eq!(
-                tx.get_task_metrics(&task_id).await.unwrap(),
-                Some((
-                    REPORT_COUNT.try_into().unwrap(),
-                    REPORT_AGGREGATION_COUNT.try_into().unwrap()
-                ))
+                metrics.unaggregated_reports(),
+                u64::try_from(REPORT_COUNT).unwrap()
+            );
+            assert_eq!(
+                metrics.report_aggregations(),
+                u64::try_from(REPORT_AGGREGATION_COUNT).unwrap()
+            );
+            assert_eq!(
+                metrics.report_aggregations_by_state(),
+                &BTreeMap::from([
+                    (ReportAggregationStateCode::Start, 1),
+                    (ReportAggregationStateCode::Failed, 1),
+                ])
+            );
+            assert_eq!(
+                metrics.report_aggregation_failures(),
+                &BTreeMap::from([(ReportShareError::VdafPrepError, 1)])
+            );
+            assert_eq!(
+                metrics.aggregation_jobs_by_state(),
+                &BTreeMap::from([(AggregationJobState::InProgress, 1)])
+            );
+            assert_eq!(
+                metrics.batches_by_state(),
+                &BTreeMap::from([(BatchState::Open, 1)])
+            );
+            assert_eq!(metrics.outstanding_batches(), &[]);
+            assert_eq!(
+                metrics.collection_jobs_by_state(),
+                &BTreeMap::from([(CollectionJobStateCode::Start, 1)])
+            );
+            assert_eq!(metrics.time_buckets(), None);
+
+            // Verify that report counts can be broken down by time bucket.
+            let metrics = tx
+                .get_task_metrics(&task_id, Some(Duration::from_seconds(100)))
+                .await
+                .unwrap()
+                .unwrap();
+            let time_buckets = metrics.time_buckets().unwrap();
+            assert_eq!(time_buckets.len(), 1);
+            assert_eq!(
+                time_buckets[0].start(),
+                &Time::from_seconds_since_epoch(1000)
+            );
+            assert_eq!(
+                time_buckets[0].reports(),
+                u64::try_from(REPORT_COUNT).unwrap()
+            );
+            assert_eq!(
+                time_buckets[0].unaggregated_reports(),
+                u64::try_from(REPORT_COUNT).unwrap()
+            );
+            assert_eq!(
+                time_buckets[0].report_aggregations(),
+                u64::try_from(REPORT_AGGREGATION_COUNT).unwrap()
             );
 
             // Verify that we get None if we ask about a task that doesn't exist.
-            assert_eq!(tx.get_task_metrics(&random()).await.unwrap(), None);
+            assert_eq!(tx.get_task_metrics(&random(), None).await.unwrap(), None);
 
             Ok(())
         })
@@ -585,6 +771,122 @@ async fn report_not_found(ephemeral_datastore: EphemeralDatastore) {
     assert_eq!(rslt, None);
 }
 
+#[rstest_reuse::apply(schema_versions_template)]
+#[tokio::test]
+async fn put_client_reports(ephemeral_datastore: EphemeralDatastore) {
+    install_test_trace_subscriber();
+    let clock = MockClock::default();
+    let ds = ephemeral_datastore.datastore(clock.clone()).await;
+    let report_expiry_age = clock
+        .now()
+        .difference(&OLDEST_ALLOWED_REPORT_TIMESTAMP)
+        .unwrap();
+
+    let task = TaskBuilder::new(
+        task::QueryType::TimeInterval,
+        VdafInstance::Fake,
+        Role::Leader,
+    )
+    .with_report_expiry_age(Some(report_expiry_age))
+    .build();
+    let with_helper_share = |report: &LeaderStoredReport<0, dummy_vdaf::Vdaf>| {
+        LeaderStoredReport::<0, dummy_vdaf::Vdaf>::new(
+            *report.task_id(),
+            report.metadata().clone(),
+            (),
+            Vec::new(),
+            dummy_vdaf::InputShare::default(),
+            HpkeCiphertext::new(
+                HpkeConfigId::from(14),
+                Vec::from("encapsulated_context_1"),
+                Vec::from("payload_1"),
+            ),
+        )
+    };
+
+    let new_report = LeaderStoredReport::new_dummy(*task.id(), OLDEST_ALLOWED_REPORT_TIMESTAMP);
+    let existing_report =
+        LeaderStoredReport::new_dummy(*task.id(), OLDEST_ALLOWED_REPORT_TIMESTAMP);
+    let mismatched_existing_report = with_helper_share(&existing_report);
+    let expired_report = LeaderStoredReport::new_dummy(
+        *task.id(),
+        OLDEST_ALLOWED_REPORT_TIMESTAMP
+            .sub(&Duration::from_seconds(1))
+            .unwrap(),
+    );
+    let unknown_task_report =
+        LeaderStoredReport::new_dummy(random(), OLDEST_ALLOWED_REPORT_TIMESTAMP);
+    let mismatched_new_report = with_helper_share(&new_report);
+
+    ds.run_tx(|tx| {
+        let (task, existing_report) = (task.clone(), existing_report.clone());
+        Box::pin(async move {
+            tx.put_task(&task).await?;
+            tx.put_client_report(&dummy_vdaf::Vdaf::new(), &existing_report)
+                .await
+        })
+    })
+    .await
+    .unwrap();
+
+    let reports = Vec::from(
+        [
+            &new_report,
+            &existing_report,
+            &mismatched_existing_report,
+            &expired_report,
+            &unknown_task_report,
+            &new_report,
+            &mismatched_new_report,
+        ]
+        .map(EncodedLeaderStoredReport::from),
+    );
+    let rslts = ds
+        .run_tx(|tx| {
+            let reports = reports.clone();
+            Box::pin(async move {
+                tx.put_client_reports(&reports.iter().collect::<Vec<_>>())
+                    .await
+            })
+        })
+        .await
+        .unwrap();
+    assert_eq!(rslts.len(), 7);
+    assert_matches!(rslts[0], Ok(()));
+    assert_matches!(rslts[1], Ok(()));
+    assert_matches!(rslts[2], Err(Error::MutationTargetAlreadyExists));
+    assert_matches!(rslts[3], Ok(()));
+    assert_matches!(rslts[4], Err(Error::MutationTargetNotFound));
+    assert_matches!(rslts[5], Ok(()));
+    assert_matches!(rslts[6], Err(Error::MutationTargetAlreadyExists));
+
+    let (got_new_report, got_existing_report, got_expired_report) = ds
+        .run_tx(|tx| {
+            let (task_id, new_report_id, existing_report_id, expired_report_id) = (
+                *task.id(),
+                *new_report.metadata().id(),
+                *existing_report.metadata().id(),
+                *expired_report.metadata().id(),
+            );
+            Box::pin(async move {
+                let vdaf = dummy_vdaf::Vdaf::new();
+                Ok((
+                    tx.get_client_report(&vdaf, &task_id, &new_report_id)
+                        .await?,
+                    tx.get_client_report(&vdaf, &task_id, &existing_report_id)
+                        .await?,
+                    tx.get_client_report(&vdaf, &task_id, &expired_report_id)
+                        .await?,
+                ))
+            })
+        })
+        .await
+        .unwrap();
+    assert_eq!(got_new_report, Some(new_report));
+    assert_eq!(got_existing_report, Some(existing_report));
+    assert_eq!(got_expired_report, None);
+}
+
 #[rstest_reuse::apply(schema_versions_template)]
 #[tokio::test]
 async fn get_unaggregated_client_report_ids_for_task(ephemeral_datastore: EphemeralDatastore) {
@@ -1070,6 +1372,19 @@ async fn count_client_reports_for_batch_id(ephemeral_datastore: EphemeralDatasto
         .await
         .unwrap();
     assert_eq!(report_count, 2);
+
+    // Counting several batches at once agrees, omitting batches without reports.
+    let report_counts = ds
+        .run_tx(|tx| {
+            let task_id = *task.id();
+            Box::pin(async move {
+                tx.count_client_reports_for_batch_ids(&task_id, &[batch_id, random()])
+                    .await
+            })
+        })
+        .await
+        .unwrap();
+    assert_eq!(report_counts, HashMap::from([(batch_id, 2)]));
 }
 
 #[rstest_reuse::apply(schema_versions_template)]
@@ -1723,6 +2038,122 @@ async fn aggregation_job_acquire_release(ephemeral_datastore: EphemeralDatastore
     .unwrap();
 }
 
+#[rstest_reuse::apply(schema_versions_template)]
+#[tokio::test]
+async fn aggregation_job_acquire_fairness(ephemeral_datastore: EphemeralDatastore) {
+    // Setup: insert a large backlog of aggregation jobs for one task, and a handful of jobs for
+    // each of two other tasks, one of which has a high priority.
+    install_test_trace_subscriber();
+
+    const LEASE_DURATION: StdDuration = StdDuration::from_secs(300);
+    let clock = MockClock::new(OLDEST_ALLOWED_REPORT_TIMESTAMP);
+    let ds = ephemeral_datastore.datastore(clock.clone()).await;
+
+    let big_task = TaskBuilder::new(
+        task::QueryType::TimeInterval,
+        VdafInstance::Prio3Count,
+        Role::Leader,
+    )
+    .with_report_expiry_age(Some(REPORT_EXPIRY_AGE))
+    .build();
+    let small_task = TaskBuilder::new(
+        task::QueryType::TimeInterval,
+        VdafInstance::Prio3Count,
+        Role::Leader,
+    )
+    .with_report_expiry_age(Some(REPORT_EXPIRY_AGE))
+    .build();
+    let high_priority_task = TaskBuilder::new(
+        task::QueryType::TimeInterval,
+        VdafInstance::Prio3Count,
+        Role::Leader,
+    )
+    .with_report_expiry_age(Some(REPORT_EXPIRY_AGE))
+    .with_priority(TaskPriority::High)
+    .build();
+
+    ds.run_tx(|tx| {
+        let tasks = Vec::from([
+            (big_task.clone(), 50),
+            (small_task.clone(), 4),
+            (high_priority_task.clone(), 8),
+        ]);
+        Box::pin(async move {
+            for (task, job_count) in tasks {
+                tx.put_task(&task).await?;
+                for _ in 0..job_count {
+                    tx.put_aggregation_job(&AggregationJob::<
+                        VERIFY_KEY_LENGTH,
+                        TimeInterval,
+                        Prio3Count,
+                    >::new(
+                        *task.id(),
+                        random(),
+                        (),
+                        (),
+                        Interval::new(
+                            OLDEST_ALLOWED_REPORT_TIMESTAMP
+                                .add(&REPORT_EXPIRY_AGE)
+                                .unwrap(),
+                            Duration::from_seconds(1),
+                        )
+                        .unwrap(),
+                        AggregationJobState::InProgress,
+                        AggregationJobRound::from(0),
+                    ))
+                    .await?;
+                }
+            }
+            Ok(())
+        })
+    })
+    .await
+    .unwrap();
+
+    // Advance the clock to "enable" report expiry.
+    clock.advance(&REPORT_EXPIRY_AGE);
+
+    // Run: acquire a small number of jobs at a time.
+    const MAXIMUM_ACQUIRE_COUNT: usize = 8;
+    let acquire = || async {
+        ds.run_tx(|tx| {
+            Box::pin(async move {
+                tx.acquire_incomplete_aggregation_jobs(&LEASE_DURATION, MAXIMUM_ACQUIRE_COUNT)
+                    .await
+            })
+        })
+        .await
+        .unwrap()
+        .into_iter()
+        .fold(HashMap::<TaskId, usize>::new(), |mut counts, lease| {
+            *counts.entry(*lease.leased().task_id()).or_default() += 1;
+            counts
+        })
+    };
+
+    // Verify: each of the first two acquisitions interleaves jobs from all tasks, weighted by
+    // priority: the high-priority task receives twice as many jobs as each normal-priority task,
+    // and the small task makes progress despite the much larger backlog of the big task.
+    for _ in 0..2 {
+        let counts = acquire().await;
+        assert_eq!(counts.get(big_task.id()), Some(&2));
+        assert_eq!(counts.get(small_task.id()), Some(&2));
+        assert_eq!(counts.get(high_priority_task.id()), Some(&4));
+    }
+
+    // Subsequent acquisitions drain the big task's backlog.
+    let mut remaining = 50 - 4;
+    loop {
+        let counts = acquire().await;
+        if counts.is_empty() {
+            break;
+        }
+        assert_eq!(counts.len(), 1);
+        remaining -= counts.get(big_task.id()).unwrap();
+    }
+    assert_eq!(remaining, 0);
+}
+
 #[rstest_reuse::apply(schema_versions_template)]
 #[tokio::test]
 async fn aggregation_job_not_found(ephemeral_datastore: EphemeralDatastore) {
@@ -2373,15 +2804,144 @@ async fn get_report_aggregations_for_aggregation_job(ephemeral_datastore: Epheme
     assert!(got_report_aggregations.is_empty());
 }
 
+#[rstest_reuse::apply(schema_versions_template)]
 #[tokio::test]
-async fn crypter() {
-    let crypter = Crypter::new(Vec::from([generate_aead_key(), generate_aead_key()]));
-    let bad_key = generate_aead_key();
+async fn get_report_aggregations_and_client_reports_for_aggregation_job(
+    ephemeral_datastore: EphemeralDatastore,
+) {
+    install_test_trace_subscriber();
 
-    const TABLE: &str = "some_table";
-    const ROW: &[u8] = b"12345";
-    const COLUMN: &str = "some_column";
-    const PLAINTEXT: &[u8] = b"This is my plaintext value.";
+    let clock = MockClock::new(OLDEST_ALLOWED_REPORT_TIMESTAMP);
+    let ds = ephemeral_datastore.datastore(clock.clone()).await;
+    let vdaf = dummy_vdaf::Vdaf::new();
+
+    let task = TaskBuilder::new(
+        task::QueryType::TimeInterval,
+        VdafInstance::Fake,
+        Role::Leader,
+    )
+    .with_report_expiry_age(Some(REPORT_EXPIRY_AGE))
+    .build();
+    let aggregation_job_id = random();
+
+    let want = ds
+        .run_tx(|tx| {
+            let task = task.clone();
+            Box::pin(async move {
+                tx.put_task(&task).await?;
+                tx.put_aggregation_job(&AggregationJob::<0, TimeInterval, dummy_vdaf::Vdaf>::new(
+                    *task.id(),
+                    aggregation_job_id,
+                    AggregationParam(0),
+                    (),
+                    Interval::new(OLDEST_ALLOWED_REPORT_TIMESTAMP, Duration::from_seconds(1))
+                        .unwrap(),
+                    AggregationJobState::InProgress,
+                    AggregationJobRound::from(0),
+                ))
+                .await?;
+
+                // Write report aggregations in a variety of states. Only report aggregations in
+                // state START should be returned with their client report; the second report
+                // aggregation's client report is never written, simulating a report that has been
+                // garbage collected.
+                let mut want = Vec::new();
+                for (ord, (state, write_client_report)) in [
+                    (ReportAggregationState::<0, dummy_vdaf::Vdaf>::Start, true),
+                    (ReportAggregationState::Start, false),
+                    (
+                        ReportAggregationState::Waiting(
+                            dummy_vdaf::PrepareState::default(),
+                            Some(()),
+                        ),
+                        true,
+                    ),
+                    (ReportAggregationState::Finished, true),
+                    (
+                        ReportAggregationState::Failed(ReportShareError::VdafPrepError),
+                        true,
+                    ),
+                ]
+                .into_iter()
+                .enumerate()
+                {
+                    let report =
+                        LeaderStoredReport::new_dummy(*task.id(), OLDEST_ALLOWED_REPORT_TIMESTAMP);
+                    if write_client_report {
+                        tx.put_client_report(&dummy_vdaf::Vdaf::new(), &report)
+                            .await?;
+                    }
+
+                    let report_aggregation = ReportAggregation::new(
+                        *task.id(),
+                        aggregation_job_id,
+                        *report.metadata().id(),
+                        OLDEST_ALLOWED_REPORT_TIMESTAMP,
+                        ord.try_into().unwrap(),
+                        None,
+                        state.clone(),
+                    );
+                    tx.put_report_aggregation(&report_aggregation).await?;
+
+                    let want_report = (matches!(state, ReportAggregationState::Start)
+                        && write_client_report)
+                        .then_some(report);
+                    want.push((report_aggregation, want_report));
+                }
+                Ok(want)
+            })
+        })
+        .await
+        .unwrap();
+
+    // Advance the clock to "enable" report expiry.
+    clock.advance(&REPORT_EXPIRY_AGE);
+
+    let got = ds
+        .run_tx(|tx| {
+            let (vdaf, task) = (vdaf.clone(), task.clone());
+            Box::pin(async move {
+                tx.get_report_aggregations_and_client_reports_for_aggregation_job(
+                    &vdaf,
+                    task.id(),
+                    &aggregation_job_id,
+                )
+                .await
+            })
+        })
+        .await
+        .unwrap();
+    assert_eq!(want, got);
+
+    // Advance the clock again to expire relevant datastore entities.
+    clock.advance(&REPORT_EXPIRY_AGE);
+
+    let got = ds
+        .run_tx(|tx| {
+            let (vdaf, task) = (vdaf.clone(), task.clone());
+            Box::pin(async move {
+                tx.get_report_aggregations_and_client_reports_for_aggregation_job(
+                    &vdaf,
+                    task.id(),
+                    &aggregation_job_id,
+                )
+                .await
+            })
+        })
+        .await
+        .unwrap();
+    assert!(got.is_empty());
+}
+
+#[tokio::test]
+async fn crypter() {
+    let crypter = Crypter::new(Vec::from([generate_aead_key(), generate_aead_key()]));
+    let bad_key = generate_aead_key();
+
+    const TABLE: &str = "some_table";
+    const ROW: &[u8] = b"12345";
+    const COLUMN: &str = "some_column";
+    const PLAINTEXT: &[u8] = b"This is my plaintext value.";
 
     // Test that roundtripping encryption works.
     let ciphertext = crypter.encrypt(TABLE, ROW, COLUMN, PLAINTEXT).unwrap();
@@ -2518,6 +3078,7 @@ async fn get_collection_job(ephemeral_datastore: EphemeralDatastore) {
                 first_collection_job.with_state(CollectionJobState::Finished {
                     report_count: 12,
                     encrypted_helper_aggregate_share,
+                    helper_aggregate_share_hpke_config: task.collector_hpke_config().cloned(),
                     leader_aggregate_share: AggregateShare(41),
                 });
 
@@ -2696,6 +3257,116 @@ async fn update_collection_jobs(ephemeral_datastore: EphemeralDatastore) {
     .unwrap();
 }
 
+#[rstest_reuse::apply(schema_versions_template)]
+#[tokio::test]
+async fn get_put_collection(ephemeral_datastore: EphemeralDatastore) {
+    install_test_trace_subscriber();
+
+    let ds = ephemeral_datastore.datastore(MockClock::default()).await;
+
+    let task = TaskBuilder::new(
+        task::QueryType::TimeInterval,
+        VdafInstance::Fake,
+        Role::Leader,
+    )
+    .build();
+    let batch_interval = Interval::new(
+        Time::from_seconds_since_epoch(100),
+        Duration::from_seconds(100),
+    )
+    .unwrap();
+    let finished_state = CollectionJobState::Finished {
+        report_count: 10,
+        encrypted_helper_aggregate_share: HpkeCiphertext::new(
+            HpkeConfigId::from(2),
+            Vec::from("encapsulated_context"),
+            Vec::from("payload"),
+        ),
+        helper_aggregate_share_hpke_config: None,
+        leader_aggregate_share: AggregateShare(23),
+    };
+    let collection = Collection::<TimeInterval>::new(
+        PartialBatchSelector::new_time_interval(),
+        10,
+        batch_interval,
+        HpkeCiphertext::new(
+            HpkeConfigId::from(2),
+            Vec::from("leader encapsulated_context"),
+            Vec::from("leader payload"),
+        ),
+        HpkeCiphertext::new(
+            HpkeConfigId::from(2),
+            Vec::from("encapsulated_context"),
+            Vec::from("payload"),
+        ),
+    );
+
+    ds.run_tx(|tx| {
+        let (task, finished_state, collection) =
+            (task.clone(), finished_state.clone(), collection.clone());
+        Box::pin(async move {
+            tx.put_task(&task).await?;
+
+            let collection_job = CollectionJob::<0, TimeInterval, dummy_vdaf::Vdaf>::new(
+                *task.id(),
+                random(),
+                Query::new_time_interval(batch_interval),
+                AggregationParam(10),
+                batch_interval,
+                CollectionJobState::Collectable,
+            );
+            tx.put_collection_job(&collection_job).await?;
+
+            // A collection can't be stored for a collection job which isn't finished.
+            assert_matches!(
+                tx.put_collection(task.id(), collection_job.id(), &collection)
+                    .await,
+                Err(Error::MutationTargetNotFound)
+            );
+            assert_eq!(
+                tx.get_collection::<TimeInterval>(task.id(), collection_job.id())
+                    .await?,
+                None
+            );
+
+            // Once the collection job is finished, a collection can be stored & retrieved.
+            let collection_job = collection_job.with_state(finished_state);
+            tx.update_collection_job(&collection_job).await?;
+            assert_eq!(
+                tx.get_collection::<TimeInterval>(task.id(), collection_job.id())
+                    .await?,
+                None
+            );
+            tx.put_collection(task.id(), collection_job.id(), &collection)
+                .await?;
+            assert_eq!(
+                tx.get_collection::<TimeInterval>(task.id(), collection_job.id())
+                    .await?,
+                Some(collection)
+            );
+
+            // Updating the collection job clears the stored collection.
+            tx.update_collection_job(&collection_job).await?;
+            assert_eq!(
+                tx.get_collection::<TimeInterval>(task.id(), collection_job.id())
+                    .await?,
+                None
+            );
+
+            // Nonexistent collection jobs have no collection.
+            assert_eq!(
+                tx.get_collection::<TimeInterval>(task.id(), &random())
+                    .await?,
+                None
+            );
+
+            Ok(())
+        })
+    })
+    .await
+    .unwrap();
+}
+
 #[derive(Copy, Clone)]
 enum CollectionJobTestCaseState {
     Start,
@@ -2873,6 +3544,7 @@ async fn setup_collection_job_acquire_test_case<Q: TestQueryTypeExt>(
                                 Vec::new(),
                                 Vec::new(),
                             ),
+                            helper_aggregate_share_hpke_config: None,
                             leader_aggregate_share: AggregateShare(0),
                         },
                         CollectionJobTestCaseState::Abandoned => CollectionJobState::Abandoned,
@@ -5135,108 +5807,612 @@ async fn roundtrip_batch(ephemeral_datastore: EphemeralDatastore) {
 
 #[rstest_reuse::apply(schema_versions_template)]
 #[tokio::test]
-async fn delete_expired_client_reports(ephemeral_datastore: EphemeralDatastore) {
+async fn get_fixed_size_batches_for_task(ephemeral_datastore: EphemeralDatastore) {
     install_test_trace_subscriber();
 
-    let clock = MockClock::default();
+    let clock = MockClock::new(OLDEST_ALLOWED_REPORT_TIMESTAMP);
     let ds = ephemeral_datastore.datastore(clock.clone()).await;
-    let vdaf = dummy_vdaf::Vdaf::new();
-
-    // Setup.
-    let report_expiry_age = clock
-        .now()
-        .difference(&OLDEST_ALLOWED_REPORT_TIMESTAMP)
-        .unwrap();
-    let (task_id, new_report_id, other_task_id, other_task_report_id) = ds
-        .run_tx(|tx| {
-            Box::pin(async move {
-                let task = TaskBuilder::new(
-                    task::QueryType::TimeInterval,
-                    VdafInstance::Fake,
-                    Role::Leader,
-                )
-                .with_report_expiry_age(Some(report_expiry_age))
-                .build();
-                let other_task = TaskBuilder::new(
-                    task::QueryType::TimeInterval,
-                    VdafInstance::Fake,
-                    Role::Leader,
-                )
-                .build();
-                tx.put_task(&task).await?;
-                tx.put_task(&other_task).await?;
 
-                let old_report = LeaderStoredReport::new_dummy(
-                    *task.id(),
-                    OLDEST_ALLOWED_REPORT_TIMESTAMP
-                        .sub(&Duration::from_seconds(1))
-                        .unwrap(),
-                );
-                let new_report =
-                    LeaderStoredReport::new_dummy(*task.id(), OLDEST_ALLOWED_REPORT_TIMESTAMP);
-                let other_task_report = LeaderStoredReport::new_dummy(
-                    *other_task.id(),
-                    OLDEST_ALLOWED_REPORT_TIMESTAMP
-                        .sub(&Duration::from_seconds(1))
-                        .unwrap(),
-                );
-                tx.put_client_report(&dummy_vdaf::Vdaf::new(), &old_report)
-                    .await?;
-                tx.put_client_report(&dummy_vdaf::Vdaf::new(), &new_report)
-                    .await?;
-                tx.put_client_report(&dummy_vdaf::Vdaf::new(), &other_task_report)
-                    .await?;
+    let task = TaskBuilder::new(
+        task::QueryType::FixedSize {
+            max_batch_size: 10,
+            batch_time_window_size: None,
+        },
+        VdafInstance::Fake,
+        Role::Leader,
+    )
+    .with_report_expiry_age(Some(REPORT_EXPIRY_AGE))
+    .build();
+    let mut batch_ids: [BatchId; 4] = random();
+    batch_ids.sort();
+    let new_batch = |batch_id: BatchId, aggregation_param: u8, start: Time| {
+        Batch::<0, FixedSize, dummy_vdaf::Vdaf>::new(
+            *task.id(),
+            batch_id,
+            AggregationParam(aggregation_param),
+            BatchState::Closed,
+            0,
+            Interval::new(start, Duration::from_seconds(1)).unwrap(),
+        )
+    };
 
-                Ok((
-                    *task.id(),
-                    *new_report.metadata().id(),
-                    *other_task.id(),
-                    *other_task_report.metadata().id(),
-                ))
-            })
-        })
-        .await
+    // The first batch has been aggregated with two aggregation parameters, and the third batch
+    // has expired.
+    let unexpired_time = OLDEST_ALLOWED_REPORT_TIMESTAMP
+        .add(&REPORT_EXPIRY_AGE)
         .unwrap();
+    let batches = Vec::from([
+        new_batch(batch_ids[0], 0, unexpired_time),
+        new_batch(batch_ids[0], 1, unexpired_time),
+        new_batch(batch_ids[1], 0, unexpired_time),
+        new_batch(batch_ids[2], 0, OLDEST_ALLOWED_REPORT_TIMESTAMP),
+        new_batch(batch_ids[3], 0, unexpired_time),
+    ]);
 
-    // Run.
     ds.run_tx(|tx| {
+        let (task, batches) = (task.clone(), batches.clone());
         Box::pin(async move {
-            tx.delete_expired_client_reports(&task_id, u64::try_from(i64::MAX)?)
-                .await
+            tx.put_task(&task).await?;
+            for batch in &batches {
+                tx.put_batch(batch).await?;
+            }
+            Ok(())
         })
     })
     .await
     .unwrap();
 
-    // Verify.
-    let want_report_ids = HashSet::from([new_report_id, other_task_report_id]);
-    let got_report_ids = ds
+    // Advance the clock to expire the third batch.
+    clock.advance(&Duration::from_seconds(REPORT_EXPIRY_AGE.as_seconds() + 2));
+
+    let pages = ds
         .run_tx(|tx| {
-            let vdaf = vdaf.clone();
+            let task_id = *task.id();
             Box::pin(async move {
-                let task_client_reports = tx.get_client_reports_for_task(&vdaf, &task_id).await?;
-                let other_task_client_reports = tx
-                    .get_client_reports_for_task(&vdaf, &other_task_id)
+                let first_page = tx
+                    .get_fixed_size_batches_for_task::<0, dummy_vdaf::Vdaf>(&task_id, None, 2)
                     .await?;
-                Ok(HashSet::from_iter(
-                    task_client_reports
-                        .into_iter()
-                        .chain(other_task_client_reports)
-                        .map(|report| *report.metadata().id()),
-                ))
+                let second_page = tx
+                    .get_fixed_size_batches_for_task::<0, dummy_vdaf::Vdaf>(
+                        &task_id,
+                        Some(&batch_ids[1]),
+                        2,
+                    )
+                    .await?;
+                Ok((first_page, second_page))
             })
         })
         .await
         .unwrap();
-    assert_eq!(want_report_ids, got_report_ids);
+
+    let (mut first_page, second_page) = pages;
+    first_page.sort_by_key(|batch| (*batch.batch_identifier(), *batch.aggregation_parameter()));
+    assert_eq!(first_page, batches[..3]);
+    assert_eq!(second_page, batches[4..]);
 }
 
 #[rstest_reuse::apply(schema_versions_template)]
 #[tokio::test]
-async fn delete_expired_aggregation_artifacts(ephemeral_datastore: EphemeralDatastore) {
+async fn get_task_ids_expired_beyond_retention(ephemeral_datastore: EphemeralDatastore) {
     install_test_trace_subscriber();
 
-    let clock = MockClock::new(OLDEST_ALLOWED_REPORT_TIMESTAMP);
+    let clock = MockClock::default();
+    let ds = ephemeral_datastore.datastore(clock.clone()).await;
+    let retention = Duration::from_seconds(3600);
+
+    ds.run_tx(|tx| {
+        let clock = clock.clone();
+        Box::pin(async move {
+            let expired_task = TaskBuilder::new(
+                task::QueryType::TimeInterval,
+                VdafInstance::Fake,
+                Role::Leader,
+            )
+            .with_task_expiration(Some(
+                clock.now().sub(&Duration::from_seconds(3601)).unwrap(),
+            ))
+            .build();
+            let recently_expired_task = TaskBuilder::new(
+                task::QueryType::TimeInterval,
+                VdafInstance::Fake,
+                Role::Leader,
+            )
+            .with_task_expiration(Some(clock.now().sub(&Duration::from_seconds(60)).unwrap()))
+            .build();
+            let unexpired_task = TaskBuilder::new(
+                task::QueryType::TimeInterval,
+                VdafInstance::Fake,
+                Role::Leader,
+            )
+            .with_task_expiration(None)
+            .build();
+            for task in [&expired_task, &recently_expired_task, &unexpired_task] {
+                tx.put_task(task).await?;
+            }
+
+            assert_eq!(
+                tx.get_task_ids_expired_beyond_retention(&retention).await?,
+                Vec::from([*expired_task.id()])
+            );
+            Ok(())
+        })
+    })
+    .await
+    .unwrap();
+}
+
+#[rstest_reuse::apply(schema_versions_template)]
+#[tokio::test]
+async fn delete_task_artifacts(ephemeral_datastore: EphemeralDatastore) {
+    install_test_trace_subscriber();
+
+    let clock = MockClock::default();
+    let ds = ephemeral_datastore.datastore(clock.clone()).await;
+    let vdaf = dummy_vdaf::Vdaf::new();
+
+    let (task, other_task) = ds
+        .run_tx(|tx| {
+            let (clock, vdaf) = (clock.clone(), vdaf.clone());
+            Box::pin(async move {
+                let task = TaskBuilder::new(
+                    task::QueryType::TimeInterval,
+                    VdafInstance::Fake,
+                    Role::Leader,
+                )
+                .build();
+                let other_task = TaskBuilder::new(
+                    task::QueryType::TimeInterval,
+                    VdafInstance::Fake,
+                    Role::Leader,
+                )
+                .build();
+                tx.put_task(&task).await?;
+                tx.put_task(&other_task).await?;
+
+                for task in [&task, &other_task] {
+                    for _ in 0..2 {
+                        tx.put_client_report(
+                            &vdaf,
+                            &LeaderStoredReport::new_dummy(*task.id(), clock.now()),
+                        )
+                        .await?;
+                    }
+                    tx.put_batch(&Batch::<0, TimeInterval, dummy_vdaf::Vdaf>::new(
+                        *task.id(),
+                        Interval::from_time(&clock.now()).unwrap(),
+                        AggregationParam(0),
+                        BatchState::Closed,
+                        0,
+                        Interval::from_time(&clock.now()).unwrap(),
+                    ))
+                    .await?;
+                }
+
+                let aggregation_job_id = random();
+                tx.put_aggregation_job(&AggregationJob::<0, TimeInterval, dummy_vdaf::Vdaf>::new(
+                    *task.id(),
+                    aggregation_job_id,
+                    AggregationParam(0),
+                    (),
+                    Interval::from_time(&clock.now()).unwrap(),
+                    AggregationJobState::InProgress,
+                    AggregationJobRound::from(0),
+                ))
+                .await?;
+                for ord in 0..2 {
+                    tx.put_report_aggregation(&ReportAggregation::<0, dummy_vdaf::Vdaf>::new(
+                        *task.id(),
+                        aggregation_job_id,
+                        random(),
+                        clock.now(),
+                        ord,
+                        None,
+                        ReportAggregationState::Start,
+                    ))
+                    .await?;
+                }
+
+                Ok((task, other_task))
+            })
+        })
+        .await
+        .unwrap();
+
+    // Each call deletes at most one row from each table. The aggregation job is only deleted once
+    // all of its report aggregations have been deleted.
+    let deleted_counts = ds
+        .run_tx(|tx| {
+            let task_id = *task.id();
+            Box::pin(async move {
+                let mut deleted_counts = Vec::new();
+                for _ in 0..4 {
+                    let deleted_rows = tx.delete_task_artifacts(&task_id, 1).await?;
+                    deleted_counts.push((
+                        deleted_rows.get("client_reports"),
+                        deleted_rows.get("report_aggregations"),
+                        deleted_rows.get("aggregation_jobs"),
+                        deleted_rows.get("batches"),
+                        deleted_rows.total(),
+                    ));
+                }
+                Ok(deleted_counts)
+            })
+        })
+        .await
+        .unwrap();
+    assert_eq!(
+        deleted_counts,
+        Vec::from([
+            (1, 1, 0, 1, 3),
+            (1, 1, 0, 0, 2),
+            (0, 0, 1, 0, 1),
+            (0, 0, 0, 0, 0)
+        ])
+    );
+
+    let (task_reports, other_task_reports) = ds
+        .run_tx(|tx| {
+            let (task_id, other_task_id, vdaf) = (*task.id(), *other_task.id(), vdaf.clone());
+            Box::pin(async move {
+                Ok((
+                    tx.get_client_reports_for_task::<0, dummy_vdaf::Vdaf>(&vdaf, &task_id)
+                        .await?,
+                    tx.get_client_reports_for_task::<0, dummy_vdaf::Vdaf>(&vdaf, &other_task_id)
+                        .await?,
+                ))
+            })
+        })
+        .await
+        .unwrap();
+    assert!(task_reports.is_empty());
+    assert_eq!(other_task_reports.len(), 2);
+}
+
+#[rstest_reuse::apply(schema_versions_template)]
+#[tokio::test]
+async fn delete_expired_client_reports(ephemeral_datastore: EphemeralDatastore) {
+    install_test_trace_subscriber();
+
+    let clock = MockClock::default();
+    let ds = ephemeral_datastore.datastore(clock.clone()).await;
+    let vdaf = dummy_vdaf::Vdaf::new();
+
+    // Setup.
+    let report_expiry_age = clock
+        .now()
+        .difference(&OLDEST_ALLOWED_REPORT_TIMESTAMP)
+        .unwrap();
+    let (task_id, new_report_id, other_task_id, other_task_report_id) = ds
+        .run_tx(|tx| {
+            Box::pin(async move {
+                let task = TaskBuilder::new(
+                    task::QueryType::TimeInterval,
+                    VdafInstance::Fake,
+                    Role::Leader,
+                )
+                .with_report_expiry_age(Some(report_expiry_age))
+                .build();
+                let other_task = TaskBuilder::new(
+                    task::QueryType::TimeInterval,
+                    VdafInstance::Fake,
+                    Role::Leader,
+                )
+                .build();
+                tx.put_task(&task).await?;
+                tx.put_task(&other_task).await?;
+
+                let old_report = LeaderStoredReport::new_dummy(
+                    *task.id(),
+                    OLDEST_ALLOWED_REPORT_TIMESTAMP
+                        .sub(&Duration::from_seconds(1))
+                        .unwrap(),
+                );
+                let new_report =
+                    LeaderStoredReport::new_dummy(*task.id(), OLDEST_ALLOWED_REPORT_TIMESTAMP);
+                let other_task_report = LeaderStoredReport::new_dummy(
+                    *other_task.id(),
+                    OLDEST_ALLOWED_REPORT_TIMESTAMP
+                        .sub(&Duration::from_seconds(1))
+                        .unwrap(),
+                );
+                tx.put_client_report(&dummy_vdaf::Vdaf::new(), &old_report)
+                    .await?;
+                tx.put_client_report(&dummy_vdaf::Vdaf::new(), &new_report)
+                    .await?;
+                tx.put_client_report(&dummy_vdaf::Vdaf::new(), &other_task_report)
+                    .await?;
+
+                Ok((
+                    *task.id(),
+                    *new_report.metadata().id(),
+                    *other_task.id(),
+                    *other_task_report.metadata().id(),
+                ))
+            })
+        })
+        .await
+        .unwrap();
+
+    // Run.
+    let (backlog_before, deleted_rows, backlog_after) = ds
+        .run_tx(|tx| {
+            Box::pin(async move {
+                let backlog_before = tx.get_expired_client_reports_backlog(&task_id).await?;
+                let deleted_rows = tx
+                    .delete_expired_client_reports(&task_id, u64::try_from(i64::MAX)?)
+                    .await?;
+                let backlog_after = tx.get_expired_client_reports_backlog(&task_id).await?;
+                Ok((backlog_before, deleted_rows, backlog_after))
+            })
+        })
+        .await
+        .unwrap();
+    assert_eq!(backlog_before, Some(Duration::from_seconds(1)));
+    assert_eq!(deleted_rows.get("client_reports"), 1);
+    assert_eq!(deleted_rows.total(), 1);
+    assert_eq!(backlog_after, None);
+
+    // Verify.
+    let want_report_ids = HashSet::from([new_report_id, other_task_report_id]);
+    let got_report_ids = ds
+        .run_tx(|tx| {
+            let vdaf = vdaf.clone();
+            Box::pin(async move {
+                let task_client_reports = tx.get_client_reports_for_task(&vdaf, &task_id).await?;
+                let other_task_client_reports = tx
+                    .get_client_reports_for_task(&vdaf, &other_task_id)
+                    .await?;
+                Ok(HashSet::from_iter(
+                    task_client_reports
+                        .into_iter()
+                        .chain(other_task_client_reports)
+                        .map(|report| *report.metadata().id()),
+                ))
+            })
+        })
+        .await
+        .unwrap();
+    assert_eq!(want_report_ids, got_report_ids);
+}
+
+#[rstest_reuse::apply(schema_versions_template)]
+#[tokio::test]
+async fn partition_report_tables(ephemeral_datastore: EphemeralDatastore) {
+    install_test_trace_subscriber();
+
+    let clock = MockClock::default();
+    let ds = ephemeral_datastore.datastore(clock.clone()).await;
+    let partition_size = Duration::from_hours(1).unwrap();
+
+    // Returns the name of the client_reports partition holding each client report.
+    async fn client_report_partitions(ds: &Datastore<MockClock>) -> HashMap<ReportId, String> {
+        ds.run_tx(|tx| {
+            Box::pin(async move {
+                Ok(tx
+                    .query(
+                        "SELECT tableoid::regclass::TEXT AS partition, report_id FROM client_reports",
+                        &[],
+                    )
+                    .await?
+                    .into_iter()
+                    .map(|row| {
+                        (
+                            ReportId::get_decoded(row.get("report_id")).unwrap(),
+                            row.get("partition"),
+                        )
+                    })
+                    .collect())
+            })
+        })
+        .await
+        .unwrap()
+    }
+
+    // Setup: write a report & an aggregation job before partitioning the tables. The current time
+    // is 1000000000, so the existing rows end up in a partition ending at 1000000800. The
+    // aggregation job's client timestamp interval extends to the current time.
+    let task = TaskBuilder::new(
+        task::QueryType::TimeInterval,
+        VdafInstance::Fake,
+        Role::Leader,
+    )
+    .with_report_expiry_age(Some(Duration::from_hours(2).unwrap()))
+    .build();
+    let old_report =
+        LeaderStoredReport::new_dummy(*task.id(), clock.now().sub(&partition_size).unwrap());
+    let new_report =
+        LeaderStoredReport::new_dummy(*task.id(), Time::from_seconds_since_epoch(1000001400));
+    let future_report =
+        LeaderStoredReport::new_dummy(*task.id(), Time::from_seconds_since_epoch(1000008000));
+    ds.run_tx(|tx| {
+        let (task, old_report) = (task.clone(), old_report.clone());
+        Box::pin(async move {
+            tx.put_task(&task).await?;
+            tx.put_client_report(&dummy_vdaf::Vdaf::new(), &old_report)
+                .await?;
+
+            let aggregation_job = AggregationJob::<0, TimeInterval, dummy_vdaf::Vdaf>::new(
+                *task.id(),
+                random(),
+                AggregationParam(0),
+                (),
+                Interval::new(
+                    *old_report.metadata().time(),
+                    partition_size.add(&Duration::from_seconds(1)).unwrap(),
+                )
+                .unwrap(),
+                AggregationJobState::InProgress,
+                AggregationJobRound::from(0),
+            );
+            tx.put_aggregation_job(&aggregation_job).await?;
+            tx.put_report_aggregation(&ReportAggregation::<0, dummy_vdaf::Vdaf>::new(
+                *task.id(),
+                *aggregation_job.id(),
+                *old_report.metadata().id(),
+                *old_report.metadata().time(),
+                0,
+                None,
+                ReportAggregationState::Start,
+            ))
+            .await
+        })
+    })
+    .await
+    .unwrap();
+
+    // Partition the tables.
+    let (partition_size_before, partition_size_after) = ds
+        .run_tx(|tx| {
+            Box::pin(async move {
+                let partition_size_before = tx.get_report_partition_size().await?;
+                tx.partition_report_tables(&partition_size).await?;
+                let partition_size_after = tx.get_report_partition_size().await?;
+                Ok((partition_size_before, partition_size_after))
+            })
+        })
+        .await
+        .unwrap();
+    assert_eq!(partition_size_before, None);
+    assert_eq!(partition_size_after, Some(partition_size));
+    assert_matches!(
+        ds.run_tx(|tx| {
+            Box::pin(async move { tx.partition_report_tables(&partition_size).await })
+        })
+        .await,
+        Err(Error::MutationTargetAlreadyExists)
+    );
+
+    // Write reports after partitioning the tables, and check that report IDs are still unique.
+    ds.run_tx(|tx| {
+        let (new_report, future_report) = (new_report.clone(), future_report.clone());
+        Box::pin(async move {
+            tx.put_client_report(&dummy_vdaf::Vdaf::new(), &new_report)
+                .await?;
+            tx.put_client_report(&dummy_vdaf::Vdaf::new(), &new_report)
+                .await?;
+            tx.put_client_report(&dummy_vdaf::Vdaf::new(), &future_report)
+                .await?;
+
+            let mismatched_report_share = ReportShare::new(
+                ReportMetadata::new(
+                    *new_report.metadata().id(),
+                    *future_report.metadata().time(),
+                ),
+                Vec::from("public_share"),
+                HpkeCiphertext::new(
+                    HpkeConfigId::from(12),
+                    Vec::from("encapsulated_context_0"),
+                    Vec::from("payload_0"),
+                ),
+            );
+            assert_matches!(
+                tx.put_report_share(new_report.task_id(), &mismatched_report_share)
+                    .await,
+                Err(Error::MutationTargetAlreadyExists)
+            );
+            Ok(())
+        })
+    })
+    .await
+    .unwrap();
+
+    assert_eq!(
+        client_report_partitions(&ds).await,
+        HashMap::from([
+            (
+                *old_report.metadata().id(),
+                "client_reports_legacy".to_string()
+            ),
+            (
+                *new_report.metadata().id(),
+                "client_reports_p1000000800".to_string()
+            ),
+            (
+                *future_report.metadata().id(),
+                "client_reports_default".to_string()
+            ),
+        ])
+    );
+
+    // Create partitions an hour ahead of the current time, two hours later. The future report is
+    // moved out of the default partition.
+    clock.advance(&Duration::from_hours(2).unwrap());
+    let created = ds
+        .run_tx(|tx| {
+            Box::pin(async move { tx.create_report_partitions(&Duration::from_hours(1)?).await })
+        })
+        .await
+        .unwrap();
+    assert_eq!(created, 2);
+    assert_eq!(
+        client_report_partitions(&ds)
+            .await
+            .get(future_report.metadata().id())
+            .unwrap(),
+        "client_reports_p1000008000"
+    );
+
+    // Attempt to drop the partitions that have ended. The legacy partition holds an expired report,
+    // but also a report aggregation of an unexpired aggregation job; the next partition holds an
+    // unexpired report. Neither is dropped.
+    async fn drop_expired_report_partitions(ds: &Datastore<MockClock>) -> Vec<String> {
+        let names = ds
+            .run_tx(|tx| Box::pin(async move { tx.get_ended_report_partitions().await }))
+            .await
+            .unwrap();
+        let mut dropped = Vec::new();
+        for name in names {
+            if ds
+                .run_tx(|tx| {
+                    let name = name.clone();
+                    Box::pin(async move { tx.drop_report_partition_if_expired(&name).await })
+                })
+                .await
+                .unwrap()
+            {
+                dropped.push(name);
+            }
+        }
+        dropped
+    }
+
+    assert!(drop_expired_report_partitions(&ds).await.is_empty());
+    assert_eq!(client_report_partitions(&ds).await.len(), 3);
+
+    // Two hours later, everything written before partitioning has expired, and so have the
+    // partitions up to the one holding the future report.
+    clock.advance(&Duration::from_hours(2).unwrap());
+    assert_eq!(
+        drop_expired_report_partitions(&ds).await,
+        Vec::from([
+            "legacy".to_string(),
+            "p1000000800".to_string(),
+            "p1000004400".to_string(),
+        ])
+    );
+    assert_eq!(
+        client_report_partitions(&ds).await,
+        HashMap::from([(
+            *future_report.metadata().id(),
+            "client_reports_p1000008000".to_string()
+        )])
+    );
+
+    assert_matches!(
+        ds.run_tx(|tx| {
+            Box::pin(async move { tx.drop_report_partition_if_expired("legacy; --").await })
+        })
+        .await,
+        Err(Error::InvalidParameter(_))
+    );
+}
+
+#[rstest_reuse::apply(schema_versions_template)]
+#[tokio::test]
+async fn delete_expired_aggregation_artifacts(ephemeral_datastore: EphemeralDatastore) {
+    install_test_trace_subscriber();
+
+    let clock = MockClock::new(OLDEST_ALLOWED_REPORT_TIMESTAMP);
     let ds = ephemeral_datastore.datastore(clock.clone()).await;
     let vdaf = dummy_vdaf::Vdaf::new();
 
@@ -6856,6 +8032,103 @@ async fn roundtrip_global_hpke_keypair(ephemeral_datastore: EphemeralDatastore)
         .unwrap();
 }
 
+#[rstest_reuse::apply(schema_versions_template)]
+#[tokio::test]
+async fn import_global_hpke_keypair(ephemeral_datastore: EphemeralDatastore) {
+    install_test_trace_subscriber();
+    let datastore = ephemeral_datastore.datastore(MockClock::default()).await;
+
+    datastore
+        .run_tx(|tx| {
+            Box::pin(async move {
+                let keypair = generate_test_hpke_config_and_private_key_with_id(1);
+                let imported_keypair = ImportedHpkeKeypair::new(
+                    keypair.config().clone(),
+                    keypair.private_key().clone(),
+                );
+                assert_eq!(
+                    tx.import_global_hpke_keypair(&imported_keypair, None)
+                        .await?,
+                    keypair
+                );
+                let got_keypair = tx
+                    .get_global_hpke_keypair(keypair.config().id())
+                    .await?
+                    .unwrap();
+                assert_eq!(got_keypair.hpke_keypair(), &keypair);
+                assert_eq!(got_keypair.state(), &HpkeKeyState::Pending);
+
+                // A keypair with the same ID is rejected.
+                let colliding_keypair = generate_test_hpke_config_and_private_key_with_id(1);
+                assert_matches!(
+                    tx.import_global_hpke_keypair(
+                        &ImportedHpkeKeypair::new(
+                            colliding_keypair.config().clone(),
+                            colliding_keypair.private_key().clone(),
+                        ),
+                        None,
+                    )
+                    .await,
+                    Err(Error::MutationTargetAlreadyExists)
+                );
+
+                // A private key that doesn't match its configuration is rejected.
+                assert_matches!(
+                    tx.import_global_hpke_keypair(
+                        &ImportedHpkeKeypair::new(
+                            generate_test_hpke_config_and_private_key_with_id(2)
+                                .config()
+                                .clone(),
+                            keypair.private_key().clone(),
+                        ),
+                        None,
+                    )
+                    .await,
+                    Err(Error::InvalidParameter(_))
+                );
+
+                // A wrapped private key is unwrapped with the provided transport key, or else with
+                // the global HPKE keypair it was wrapped to.
+                let transport_keypair = generate_test_hpke_config_and_private_key_with_id(100);
+                let wrapped_keypair = generate_test_hpke_config_and_private_key_with_id(3);
+                let imported_keypair = ImportedHpkeKeypair::new_wrapped(
+                    wrapped_keypair.config().clone(),
+                    wrap_private_key(transport_keypair.config(), &wrapped_keypair).unwrap(),
+                );
+                assert_matches!(
+                    tx.import_global_hpke_keypair(&imported_keypair, None).await,
+                    Err(Error::InvalidParameter(_))
+                );
+                assert_eq!(
+                    tx.import_global_hpke_keypair(&imported_keypair, Some(&transport_keypair))
+                        .await?,
+                    wrapped_keypair
+                );
+
+                let wrapped_keypair = generate_test_hpke_config_and_private_key_with_id(4);
+                let imported_keypair = ImportedHpkeKeypair::new_wrapped(
+                    wrapped_keypair.config().clone(),
+                    wrap_private_key(keypair.config(), &wrapped_keypair).unwrap(),
+                );
+                assert_matches!(
+                    tx.import_global_hpke_keypair(&imported_keypair, Some(&transport_keypair))
+                        .await,
+                    Err(Error::InvalidParameter(_))
+                );
+                assert_eq!(
+                    tx.import_global_hpke_keypair(&imported_keypair, None)
+                        .await?,
+                    wrapped_keypair
+                );
+
+                assert_eq!(tx.get_global_hpke_keypairs().await?.len(), 3);
+                Ok(())
+            })
+        })
+        .await
+        .unwrap();
+}
+
 #[rstest_reuse::apply(schema_versions_template)]
 #[tokio::test]
 async fn roundtrip_taskprov_peer_aggregator(ephemeral_datastore: EphemeralDatastore) {
@@ -6869,6 +8142,11 @@ async fn roundtrip_taskprov_peer_aggregator(ephemeral_datastore: EphemeralDatast
         .with_role(Role::Helper)
         .with_aggregator_auth_tokens(vec![random(), random()])
         .with_collector_auth_tokens(vec![])
+        .with_policy(TaskprovPolicy {
+            allowed_vdafs: Some(Vec::from([VdafPolicy::Prio3Count])),
+            minimum_min_batch_size: Some(100),
+            ..Default::default()
+        })
         .build();
     let another_example_leader_peer_aggregator = PeerAggregatorBuilder::new()
         .with_endpoint(Url::parse("https://another.example.com/").unwrap())
@@ -6955,3 +8233,143 @@ async fn roundtrip_taskprov_peer_aggregator(ephemeral_datastore: EphemeralDatast
         .await
         .unwrap();
 }
+
+#[rstest_reuse::apply(schema_versions_template)]
+#[tokio::test]
+async fn update_taskprov_peer_aggregator(ephemeral_datastore: EphemeralDatastore) {
+    install_test_trace_subscriber();
+    let datastore = ephemeral_datastore.datastore(MockClock::default()).await;
+
+    let peer_aggregator = PeerAggregatorBuilder::new().build();
+    let updated_peer_aggregator = PeerAggregatorBuilder::from(peer_aggregator.clone())
+        .with_verify_key_init(random())
+        .with_collector_hpke_config(generate_test_hpke_config_and_private_key().config().clone())
+        .with_report_expiry_age(Some(Duration::from_seconds(3600)))
+        .with_tolerable_clock_skew(Duration::from_seconds(60))
+        .with_aggregator_auth_tokens(Vec::from([random(), random()]))
+        .with_collector_auth_tokens(Vec::new())
+        .with_policy(TaskprovPolicy {
+            maximum_max_batch_query_count: Some(1),
+            minimum_time_precision: Some(Duration::from_seconds(3600)),
+            ..Default::default()
+        })
+        .build();
+
+    datastore
+        .run_tx(|tx| {
+            let peer_aggregator = peer_aggregator.clone();
+            let updated_peer_aggregator = updated_peer_aggregator.clone();
+            Box::pin(async move {
+                tx.put_taskprov_peer_aggregator(&peer_aggregator).await?;
+                tx.update_taskprov_peer_aggregator(&updated_peer_aggregator)
+                    .await?;
+
+                assert_eq!(
+                    tx.get_taskprov_peer_aggregator(
+                        peer_aggregator.endpoint(),
+                        peer_aggregator.role()
+                    )
+                    .await
+                    .unwrap(),
+                    Some(updated_peer_aggregator.clone()),
+                );
+
+                // Updating a nonexistent peer aggregator fails.
+                assert_matches!(
+                    tx.update_taskprov_peer_aggregator(
+                        &PeerAggregatorBuilder::from(updated_peer_aggregator)
+                            .with_role(Role::Helper)
+                            .build()
+                    )
+                    .await,
+                    Err(Error::MutationTargetNotFound)
+                );
+
+                Ok(())
+            })
+        })
+        .await
+        .unwrap();
+}
+
+#[rstest_reuse::apply(schema_versions_template)]
+#[tokio::test]
+async fn update_taskprov_peer_aggregator_tasks(ephemeral_datastore: EphemeralDatastore) {
+    install_test_trace_subscriber();
+    let datastore = ephemeral_datastore.datastore(MockClock::default()).await;
+
+    // We are the leader in tasks provisioned with a helper peer aggregator.
+    let peer_aggregator = PeerAggregatorBuilder::new()
+        .with_endpoint(Url::parse("https://helper.example.com/").unwrap())
+        .with_role(Role::Helper)
+        .build();
+    let taskprov_task = TaskBuilder::new(
+        task::QueryType::TimeInterval,
+        VdafInstance::Prio3Count,
+        Role::Leader,
+    )
+    .with_helper_aggregator_endpoint(peer_aggregator.endpoint().clone())
+    .with_taskprov_task_config(Vec::from("task config".as_bytes()))
+    .build();
+    let other_task = TaskBuilder::new(
+        task::QueryType::TimeInterval,
+        VdafInstance::Prio3Count,
+        Role::Leader,
+    )
+    .with_helper_aggregator_endpoint(peer_aggregator.endpoint().clone())
+    .build();
+
+    let updated_peer_aggregator = PeerAggregatorBuilder::from(peer_aggregator.clone())
+        .with_collector_hpke_config(generate_test_hpke_config_and_private_key().config().clone())
+        .with_report_expiry_age(Some(Duration::from_seconds(7200)))
+        .with_tolerable_clock_skew(Duration::from_seconds(120))
+        .with_aggregator_auth_tokens(Vec::from([random(), random()]))
+        .with_collector_auth_tokens(Vec::from([random()]))
+        .build();
+
+    datastore
+        .run_tx(|tx| {
+            let peer_aggregator = peer_aggregator.clone();
+            let updated_peer_aggregator = updated_peer_aggregator.clone();
+            let (taskprov_task, other_task) = (taskprov_task.clone(), other_task.clone());
+            Box::pin(async move {
+                tx.put_taskprov_peer_aggregator(&peer_aggregator).await?;
+                tx.put_task(&taskprov_task).await?;
+                tx.put_task(&other_task).await?;
+                assert_eq!(
+                    tx.update_taskprov_peer_aggregator(&updated_peer_aggregator)
+                        .await?,
+                    Vec::from([*taskprov_task.id()])
+                );
+
+                let task = tx.get_task(taskprov_task.id()).await?.unwrap();
+                assert_eq!(
+                    task.collector_hpke_config(),
+                    Some(updated_peer_aggregator.collector_hpke_config())
+                );
+                assert_eq!(
+                    task.report_expiry_age(),
+                    updated_peer_aggregator.report_expiry_age()
+                );
+                assert_eq!(
+                    task.tolerable_clock_skew(),
+                    updated_peer_aggregator.tolerable_clock_skew()
+                );
+                assert_eq!(
+                    task.aggregator_auth_tokens(),
+                    updated_peer_aggregator.aggregator_auth_tokens()
+                );
+                assert_eq!(
+                    task.collector_auth_tokens(),
+                    updated_peer_aggregator.collector_auth_tokens()
+                );
+
+                // Tasks which were not provisioned via taskprov are left alone.
+                assert_eq!(tx.get_task(other_task.id()).await?.unwrap(), other_task);
+
+                Ok(())
+            })
+        })
+        .await
+        .unwrap();
+}
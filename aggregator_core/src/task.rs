@@ -12,6 +12,7 @@ use janus_messages::{
     taskprov, AggregationJobId, CollectionJobId, Duration, HpkeAeadId, HpkeConfig, HpkeConfigId,
     HpkeKdfId, HpkeKemId, Role, TaskId, Time,
 };
+use postgres_types::{FromSql, ToSql};
 use rand::{distributions::Standard, random, thread_rng, Rng};
 use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
 use std::{array::TryFromSliceError, collections::HashMap};
@@ -68,6 +69,150 @@ impl TryFrom<&taskprov::Query> for QueryType {
     }
 }
 
+/// The scheduling priority class of a task. When acquiring jobs, the job drivers interleave jobs
+/// from different tasks in a weighted round-robin fashion, so that a task with a large backlog
+/// cannot starve other tasks. A task's weight is determined by its priority: `Low` tasks have a
+/// weight of 1, `Normal` tasks a weight of 2, and `High` tasks a weight of 4. This corresponds to
+/// enum `TASK_PRIORITY` in the schema.
+///
+/// This is an implementation-specific configuration parameter, and not part of DAP.
+#[derive(
+    Debug,
+    Default,
+    Copy,
+    Clone,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Serialize,
+    Deserialize,
+    ToSql,
+    FromSql,
+)]
+#[postgres(name = "task_priority")]
+pub enum TaskPriority {
+    #[postgres(name = "LOW")]
+    Low,
+    #[default]
+    #[postgres(name = "NORMAL")]
+    Normal,
+    #[postgres(name = "HIGH")]
+    High,
+}
+
+/// The differential privacy mechanism an aggregator applies to each aggregate share it computes
+/// for a task. Noise is sampled once, when the aggregate share is computed, and the noised share is
+/// stored, so that repeated requests for the same aggregate share cannot be averaged to remove the
+/// noise.
+///
+/// Noise is added to each field element of the aggregate share. Since noise may be negative,
+/// aggregate results may wrap around to values close to the field modulus; collectors should
+/// interpret such values as negative.
+///
+/// This is an implementation-specific configuration parameter, and not part of DAP.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DpConfig {
+    /// No noise is added to aggregate shares.
+    #[default]
+    None,
+    /// Discrete Laplace noise with scale `sensitivity / epsilon` is added, providing
+    /// `epsilon`-differential privacy for aggregates with the given L1 sensitivity.
+    DiscreteLaplace {
+        epsilon: taskprov::Rational,
+        sensitivity: taskprov::Rational,
+    },
+    /// Discrete Gaussian noise with standard deviation
+    /// `sensitivity * sqrt(2 * ln(1.25 / delta)) / epsilon` is added, providing
+    /// `(epsilon, delta)`-differential privacy for aggregates with the given L2 sensitivity.
+    /// `epsilon` must be less than 1.
+    DiscreteGaussian {
+        epsilon: taskprov::Rational,
+        delta: taskprov::Rational,
+        sensitivity: taskprov::Rational,
+    },
+}
+
+impl DpConfig {
+    fn validate(&self, vdaf: &VdafInstance) -> Result<(), Error> {
+        fn is_positive(value: &taskprov::Rational) -> bool {
+            value.numerator() > 0 && value.denominator() > 0
+        }
+
+        match self {
+            Self::None => return Ok(()),
+            Self::DiscreteLaplace {
+                epsilon,
+                sensitivity,
+            } => {
+                if !is_positive(epsilon) {
+                    return Err(Error::InvalidParameter("dp_config epsilon"));
+                }
+                if !is_positive(sensitivity) {
+                    return Err(Error::InvalidParameter("dp_config sensitivity"));
+                }
+            }
+            Self::DiscreteGaussian {
+                epsilon,
+                delta,
+                sensitivity,
+            } => {
+                if !is_positive(epsilon) || epsilon.numerator() >= epsilon.denominator() {
+                    return Err(Error::InvalidParameter("dp_config epsilon"));
+                }
+                if !is_positive(delta) || delta.numerator() >= delta.denominator() {
+                    return Err(Error::InvalidParameter("dp_config delta"));
+                }
+                if !is_positive(sensitivity) {
+                    return Err(Error::InvalidParameter("dp_config sensitivity"));
+                }
+            }
+        }
+
+        // Noise is added to field elements which directly represent integer counts or sums, so
+        // only VDAFs with such aggregate shares are supported.
+        match vdaf {
+            VdafInstance::Prio3Count
+            | VdafInstance::Prio3CountVec { .. }
+            | VdafInstance::Prio3Sum { .. }
+            | VdafInstance::Prio3SumVec { .. }
+            | VdafInstance::Prio3Histogram { .. } => Ok(()),
+            _ => Err(Error::InvalidParameter(
+                "dp_config is not supported for this VDAF",
+            )),
+        }
+    }
+}
+
+impl TryFrom<&taskprov::DpConfig> for DpConfig {
+    type Error = Error;
+
+    fn try_from(value: &taskprov::DpConfig) -> Result<Self, Self::Error> {
+        match value.dp_mechanism() {
+            // Taskprov peers which predate support for differential privacy may send the reserved
+            // mechanism; such tasks have always been accepted without noise.
+            taskprov::DpMechanism::Reserved | taskprov::DpMechanism::None => Ok(Self::None),
+            taskprov::DpMechanism::DiscreteLaplace {
+                epsilon,
+                sensitivity,
+            } => Ok(Self::DiscreteLaplace {
+                epsilon: *epsilon,
+                sensitivity: *sensitivity,
+            }),
+            taskprov::DpMechanism::DiscreteGaussian {
+                epsilon,
+                delta,
+                sensitivity,
+            } => Ok(Self::DiscreteGaussian {
+                epsilon: *epsilon,
+                delta: *delta,
+                sensitivity: *sensitivity,
+            }),
+            _ => Err(Error::InvalidParameter("unknown dp mechanism")),
+        }
+    }
+}
+
 /// A verification key for a VDAF, with a fixed length. It must be kept secret from clients to
 /// maintain robustness, and it must be shared between aggregators.
 pub struct VerifyKey<const SEED_SIZE: usize>([u8; SEED_SIZE]);
@@ -128,6 +273,10 @@ pub struct Task {
     /// How much clock skew to allow between client and aggregator. Reports from
     /// farther than this duration into the future will be rejected.
     tolerable_clock_skew: Duration,
+    /// The scheduling priority class of this task's jobs.
+    priority: TaskPriority,
+    /// The differential privacy mechanism applied to aggregate shares computed for this task.
+    dp_config: DpConfig,
     /// HPKE configuration for the collector.
     collector_hpke_config: Option<HpkeConfig>,
     /// Tokens used to authenticate messages sent to or received from the other aggregator.
@@ -136,6 +285,8 @@ pub struct Task {
     collector_auth_tokens: Vec<AuthenticationToken>,
     /// HPKE configurations & private keys used by this aggregator to decrypt client reports.
     hpke_keys: HashMap<HpkeConfigId, HpkeKeypair>,
+    /// The encoded taskprov `TaskConfig` from which this task was provisioned, if any.
+    taskprov_task_config: Option<Vec<u8>>,
 }
 
 impl Task {
@@ -155,6 +306,8 @@ impl Task {
         min_batch_size: u64,
         time_precision: Duration,
         tolerable_clock_skew: Duration,
+        priority: TaskPriority,
+        dp_config: DpConfig,
         collector_hpke_config: HpkeConfig,
         aggregator_auth_tokens: Vec<AuthenticationToken>,
         collector_auth_tokens: Vec<AuthenticationToken>,
@@ -174,10 +327,13 @@ impl Task {
             min_batch_size,
             time_precision,
             tolerable_clock_skew,
+            priority,
+            dp_config,
             Some(collector_hpke_config),
             aggregator_auth_tokens,
             collector_auth_tokens,
             hpke_keys,
+            None,
         );
         task.validate()?;
         Ok(task)
@@ -200,10 +356,13 @@ impl Task {
         min_batch_size: u64,
         time_precision: Duration,
         tolerable_clock_skew: Duration,
+        priority: TaskPriority,
+        dp_config: DpConfig,
         collector_hpke_config: Option<HpkeConfig>,
         aggregator_auth_tokens: Vec<AuthenticationToken>,
         collector_auth_tokens: Vec<AuthenticationToken>,
         hpke_keys: I,
+        taskprov_task_config: Option<Vec<u8>>,
     ) -> Self {
         // Compute hpke_configs mapping cfg.id -> (cfg, key).
         let hpke_keys: HashMap<HpkeConfigId, HpkeKeypair> = hpke_keys
@@ -228,10 +387,13 @@ impl Task {
             min_batch_size,
             time_precision,
             tolerable_clock_skew,
+            priority,
+            dp_config,
             collector_hpke_config,
             aggregator_auth_tokens,
             collector_auth_tokens,
             hpke_keys,
+            taskprov_task_config,
         }
     }
 
@@ -262,6 +424,7 @@ impl Task {
                 .as_naive_date_time()
                 .map_err(|_| Error::InvalidParameter("task_expiration out of range"))?;
         }
+        self.dp_config.validate(&self.vdaf)?;
 
         Ok(())
     }
@@ -356,6 +519,16 @@ impl Task {
         &self.tolerable_clock_skew
     }
 
+    /// Retrieves the scheduling priority class associated with this task.
+    pub fn priority(&self) -> &TaskPriority {
+        &self.priority
+    }
+
+    /// Retrieves the differential privacy mechanism associated with this task.
+    pub fn dp_config(&self) -> &DpConfig {
+        &self.dp_config
+    }
+
     /// Retrieves the collector HPKE config associated with this task.
     pub fn collector_hpke_config(&self) -> Option<&HpkeConfig> {
         self.collector_hpke_config.as_ref()
@@ -376,6 +549,12 @@ impl Task {
         &self.hpke_keys
     }
 
+    /// Retrieves the encoded taskprov `TaskConfig` from which this task was provisioned, or `None`
+    /// if the task was not provisioned via taskprov.
+    pub fn taskprov_task_config(&self) -> Option<&[u8]> {
+        self.taskprov_task_config.as_deref()
+    }
+
     /// Retrieve the "current" HPKE in use for this task.
     #[cfg(feature = "test-util")]
     pub fn current_hpke_key(&self) -> &HpkeKeypair {
@@ -503,6 +682,10 @@ pub struct SerializedTask {
     min_batch_size: u64,
     time_precision: Duration,
     tolerable_clock_skew: Duration,
+    #[serde(default)]
+    priority: TaskPriority,
+    #[serde(default)]
+    dp_config: DpConfig,
     collector_hpke_config: HpkeConfig,
     aggregator_auth_tokens: Vec<AuthenticationToken>,
     collector_auth_tokens: Vec<AuthenticationToken>,
@@ -585,6 +768,8 @@ impl Serialize for Task {
             min_batch_size: self.min_batch_size,
             time_precision: self.time_precision,
             tolerable_clock_skew: self.tolerable_clock_skew,
+            priority: self.priority,
+            dp_config: self.dp_config,
             collector_hpke_config: self
                 .collector_hpke_config()
                 .expect("serializable tasks must have collector_hpke_config")
@@ -627,6 +812,8 @@ impl TryFrom<SerializedTask> for Task {
             serialized_task.min_batch_size,
             serialized_task.time_precision,
             serialized_task.tolerable_clock_skew,
+            serialized_task.priority,
+            serialized_task.dp_config,
             serialized_task.collector_hpke_config,
             serialized_task.aggregator_auth_tokens,
             serialized_task.collector_auth_tokens,
@@ -643,11 +830,19 @@ impl<'de> Deserialize<'de> for Task {
     }
 }
 
+/// A hook notified when a task is created, modified or deleted outside of the DAP request path,
+/// e.g. via the aggregator API. Aggregators implement this to discard any copy of the task they
+/// have cached, so that the change is observed without waiting for the cached copy to expire.
+pub trait TaskInvalidationHook: Send + Sync {
+    /// Invalidates any cached state derived from the task with the given ID.
+    fn invalidate_task(&self, task_id: &TaskId);
+}
+
 #[cfg(feature = "test-util")]
 #[cfg_attr(docsrs, doc(cfg(feature = "test-util")))]
 pub mod test_util {
     use crate::{
-        task::{QueryType, Task},
+        task::{DpConfig, QueryType, Task, TaskPriority},
         SecretBytes,
     };
     use janus_core::{
@@ -724,6 +919,8 @@ pub mod test_util {
                     0,
                     Duration::from_hours(8).unwrap(),
                     Duration::from_minutes(10).unwrap(),
+                    TaskPriority::Normal,
+                    DpConfig::None,
                     generate_test_hpke_config_and_private_key().config().clone(),
                     Vec::from([random(), AuthenticationToken::DapAuth(random())]),
                     collector_auth_tokens,
@@ -847,6 +1044,27 @@ pub mod test_util {
             })
         }
 
+        /// Sets the task scheduling priority class.
+        pub fn with_priority(self, priority: TaskPriority) -> Self {
+            Self(Task { priority, ..self.0 })
+        }
+
+        /// Sets the differential privacy mechanism.
+        pub fn with_dp_config(self, dp_config: DpConfig) -> Self {
+            Self(Task {
+                dp_config,
+                ..self.0
+            })
+        }
+
+        /// Sets the encoded taskprov `TaskConfig` from which the task was provisioned.
+        pub fn with_taskprov_task_config(self, taskprov_task_config: Vec<u8>) -> Self {
+            Self(Task {
+                taskprov_task_config: Some(taskprov_task_config),
+                ..self.0
+            })
+        }
+
         /// Sets the task HPKE keys
         pub fn with_hpke_keys(self, hpke_keys: Vec<HpkeKeypair>) -> Self {
             let hpke_keys = hpke_keys
@@ -876,7 +1094,7 @@ pub mod test_util {
 #[cfg(test)]
 mod tests {
     use crate::{
-        task::{test_util::TaskBuilder, QueryType, Task, VdafInstance},
+        task::{test_util::TaskBuilder, DpConfig, QueryType, Task, TaskPriority, VdafInstance},
         SecretBytes,
     };
     use assert_matches::assert_matches;
@@ -887,6 +1105,7 @@ mod tests {
         time::DurationExt,
     };
     use janus_messages::{
+        taskprov::{self, Rational},
         Duration, HpkeAeadId, HpkeConfig, HpkeConfigId, HpkeKdfId, HpkeKemId, HpkePublicKey, Role,
         TaskId,
     };
@@ -928,6 +1147,8 @@ mod tests {
             0,
             Duration::from_hours(8).unwrap(),
             Duration::from_minutes(10).unwrap(),
+            TaskPriority::Normal,
+            DpConfig::None,
             generate_test_hpke_config_and_private_key().config().clone(),
             Vec::from([random()]),
             Vec::new(),
@@ -950,6 +1171,8 @@ mod tests {
             0,
             Duration::from_hours(8).unwrap(),
             Duration::from_minutes(10).unwrap(),
+            TaskPriority::Normal,
+            DpConfig::None,
             generate_test_hpke_config_and_private_key().config().clone(),
             Vec::from([random()]),
             Vec::from([random()]),
@@ -972,6 +1195,8 @@ mod tests {
             0,
             Duration::from_hours(8).unwrap(),
             Duration::from_minutes(10).unwrap(),
+            TaskPriority::Normal,
+            DpConfig::None,
             generate_test_hpke_config_and_private_key().config().clone(),
             Vec::from([random()]),
             Vec::new(),
@@ -994,6 +1219,8 @@ mod tests {
             0,
             Duration::from_hours(8).unwrap(),
             Duration::from_minutes(10).unwrap(),
+            TaskPriority::Normal,
+            DpConfig::None,
             generate_test_hpke_config_and_private_key().config().clone(),
             Vec::from([random()]),
             Vec::from([random()]),
@@ -1018,6 +1245,8 @@ mod tests {
             0,
             Duration::from_hours(8).unwrap(),
             Duration::from_minutes(10).unwrap(),
+            TaskPriority::Normal,
+            DpConfig::None,
             generate_test_hpke_config_and_private_key().config().clone(),
             Vec::from([random()]),
             Vec::from([random()]),
@@ -1095,6 +1324,8 @@ mod tests {
                 10,
                 Duration::from_seconds(3600),
                 Duration::from_seconds(60),
+                TaskPriority::Normal,
+                DpConfig::None,
                 HpkeConfig::new(
                     HpkeConfigId::from(8),
                     HpkeKemId::X25519HkdfSha256,
@@ -1125,7 +1356,7 @@ mod tests {
             &[
                 Token::Struct {
                     name: "SerializedTask",
-                    len: 17,
+                    len: 19,
                 },
                 Token::Str("task_id"),
                 Token::Some,
@@ -1167,6 +1398,16 @@ mod tests {
                 Token::Str("tolerable_clock_skew"),
                 Token::NewtypeStruct { name: "Duration" },
                 Token::U64(60),
+                Token::Str("priority"),
+                Token::UnitVariant {
+                    name: "TaskPriority",
+                    variant: "Normal",
+                },
+                Token::Str("dp_config"),
+                Token::UnitVariant {
+                    name: "DpConfig",
+                    variant: "None",
+                },
                 Token::Str("collector_hpke_config"),
                 Token::Struct {
                     name: "HpkeConfig",
@@ -1285,6 +1526,8 @@ mod tests {
                 10,
                 Duration::from_seconds(3600),
                 Duration::from_seconds(60),
+                TaskPriority::High,
+                DpConfig::None,
                 HpkeConfig::new(
                     HpkeConfigId::from(8),
                     HpkeKemId::X25519HkdfSha256,
@@ -1312,7 +1555,7 @@ mod tests {
             &[
                 Token::Struct {
                     name: "SerializedTask",
-                    len: 17,
+                    len: 19,
                 },
                 Token::Str("task_id"),
                 Token::Some,
@@ -1366,6 +1609,16 @@ mod tests {
                 Token::Str("tolerable_clock_skew"),
                 Token::NewtypeStruct { name: "Duration" },
                 Token::U64(60),
+                Token::Str("priority"),
+                Token::UnitVariant {
+                    name: "TaskPriority",
+                    variant: "High",
+                },
+                Token::Str("dp_config"),
+                Token::UnitVariant {
+                    name: "DpConfig",
+                    variant: "None",
+                },
                 Token::Str("collector_hpke_config"),
                 Token::Struct {
                     name: "HpkeConfig",
@@ -1535,4 +1788,101 @@ mod tests {
             })
         );
     }
+
+    #[test]
+    fn dp_config_serde() {
+        assert_matches!(
+            serde_yaml::from_str(
+                "!DiscreteLaplace { epsilon: { numerator: 1, denominator: 2 }, \
+                sensitivity: { numerator: 1, denominator: 1 } }"
+            ),
+            Ok(DpConfig::DiscreteLaplace { epsilon, sensitivity }) => {
+                assert_eq!(epsilon, Rational::new(1, 2).unwrap());
+                assert_eq!(sensitivity, Rational::new(1, 1).unwrap());
+            }
+        );
+        assert_matches!(serde_json::from_value(json!("None")), Ok(DpConfig::None));
+    }
+
+    #[test]
+    fn dp_config_from_taskprov() {
+        for (dp_mechanism, expected) in [
+            (taskprov::DpMechanism::Reserved, DpConfig::None),
+            (taskprov::DpMechanism::None, DpConfig::None),
+            (
+                taskprov::DpMechanism::DiscreteLaplace {
+                    epsilon: Rational::new(1, 2).unwrap(),
+                    sensitivity: Rational::new(1, 1).unwrap(),
+                },
+                DpConfig::DiscreteLaplace {
+                    epsilon: Rational::new(1, 2).unwrap(),
+                    sensitivity: Rational::new(1, 1).unwrap(),
+                },
+            ),
+        ] {
+            assert_eq!(
+                DpConfig::try_from(&taskprov::DpConfig::new(dp_mechanism)).unwrap(),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn dp_config_validation() {
+        let task = TaskBuilder::new(
+            QueryType::TimeInterval,
+            VdafInstance::Prio3Count,
+            Role::Leader,
+        )
+        .build();
+        let laplace = DpConfig::DiscreteLaplace {
+            epsilon: Rational::new(1, 2).unwrap(),
+            sensitivity: Rational::new(1, 1).unwrap(),
+        };
+        let gaussian = |epsilon, delta| DpConfig::DiscreteGaussian {
+            epsilon,
+            delta,
+            sensitivity: Rational::new(1, 1).unwrap(),
+        };
+
+        for (dp_config, vdaf, valid) in [
+            (laplace, VdafInstance::Prio3Count, true),
+            (laplace, VdafInstance::Prio3Histogram { length: 4 }, true),
+            (laplace, VdafInstance::Poplar1 { bits: 8 }, false),
+            (
+                DpConfig::DiscreteLaplace {
+                    epsilon: Rational::new(0, 1).unwrap(),
+                    sensitivity: Rational::new(1, 1).unwrap(),
+                },
+                VdafInstance::Prio3Count,
+                false,
+            ),
+            (
+                gaussian(Rational::new(1, 2).unwrap(), Rational::new(1, 100).unwrap()),
+                VdafInstance::Prio3Sum { bits: 8 },
+                true,
+            ),
+            (
+                gaussian(Rational::new(1, 1).unwrap(), Rational::new(1, 100).unwrap()),
+                VdafInstance::Prio3Sum { bits: 8 },
+                false,
+            ),
+            (
+                gaussian(Rational::new(1, 2).unwrap(), Rational::new(1, 1).unwrap()),
+                VdafInstance::Prio3Sum { bits: 8 },
+                false,
+            ),
+        ] {
+            let task = Task {
+                vdaf: vdaf.clone(),
+                dp_config,
+                ..task.clone()
+            };
+            assert_eq!(
+                task.validate().is_ok(),
+                valid,
+                "{dp_config:?} with {vdaf:?}"
+            );
+        }
+    }
 }
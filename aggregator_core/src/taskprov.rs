@@ -1,11 +1,16 @@
 use crate::{
-    task::{self, Error, QueryType},
+    task::{self, DpConfig, Error, QueryType, TaskPriority},
     SecretBytes,
 };
 use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
 use derivative::Derivative;
-use janus_core::task::{AuthenticationToken, VdafInstance};
-use janus_messages::{Duration, HpkeConfig, Role, TaskId, Time};
+use janus_core::{
+    task::{AuthenticationToken, VdafInstance},
+    time::TimeExt,
+};
+use janus_messages::{
+    codec::Encode, taskprov::TaskConfig, Duration, HpkeConfig, Role, TaskId, Time,
+};
 use rand::{distributions::Standard, prelude::Distribution};
 use ring::hkdf::{KeyType, Salt, HKDF_SHA256};
 use serde::{
@@ -119,6 +124,9 @@ pub struct PeerAggregator {
     /// Auth tokens used for authenticating Collector to Leader requests. It should be empty if the
     /// peer aggregator is the Leader.
     collector_auth_tokens: Vec<AuthenticationToken>,
+
+    /// Limits on the tasks that this peer may provision.
+    policy: TaskprovPolicy,
 }
 
 /// Salt generated by the SHA256 of the string 'dap-taskprov". See [taskprov section 3.2][1].
@@ -149,6 +157,7 @@ impl PeerAggregator {
         tolerable_clock_skew: Duration,
         aggregator_auth_tokens: Vec<AuthenticationToken>,
         collector_auth_tokens: Vec<AuthenticationToken>,
+        policy: TaskprovPolicy,
     ) -> Self {
         Self {
             endpoint,
@@ -159,6 +168,7 @@ impl PeerAggregator {
             tolerable_clock_skew,
             aggregator_auth_tokens,
             collector_auth_tokens,
+            policy,
         }
     }
 
@@ -193,6 +203,11 @@ impl PeerAggregator {
         &self.tolerable_clock_skew
     }
 
+    /// Retrieve the policy limiting the tasks that this peer may provision.
+    pub fn policy(&self) -> &TaskprovPolicy {
+        &self.policy
+    }
+
     /// Retrieve the [`AuthenticationToken`]s used for authenticating leader to helper requests.
     pub fn aggregator_auth_tokens(&self) -> &[AuthenticationToken] {
         &self.aggregator_auth_tokens
@@ -257,6 +272,193 @@ impl PeerAggregator {
     }
 }
 
+/// Limits on the tasks that a peer aggregator may provision via taskprov, so that peers cannot
+/// create tasks which undermine the privacy guarantees or capacity of this aggregator. Limits that
+/// are not set are not enforced, so the default policy accepts any task.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TaskprovPolicy {
+    /// The VDAFs that tasks may use, along with limits on their dimensions. If not set, any VDAF
+    /// is allowed.
+    pub allowed_vdafs: Option<Vec<VdafPolicy>>,
+    /// The query types that tasks may use. If not set, any query type is allowed.
+    pub allowed_query_types: Option<Vec<QueryTypePolicy>>,
+    /// The smallest `min_batch_size` that tasks may use.
+    pub minimum_min_batch_size: Option<u64>,
+    /// The largest `max_batch_query_count` that tasks may use.
+    pub maximum_max_batch_query_count: Option<u64>,
+    /// The longest time, measured from when the task is provisioned until its expiration, that
+    /// tasks may run for.
+    pub maximum_task_lifetime: Option<Duration>,
+    /// The smallest `time_precision` that tasks may use.
+    pub minimum_time_precision: Option<Duration>,
+}
+
+/// A VDAF that a [`TaskprovPolicy`] allows, along with the largest dimensions allowed for it.
+/// Dimensions that are not set are not limited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum VdafPolicy {
+    Prio3Count,
+    Prio3CountVec {
+        max_length: Option<usize>,
+    },
+    Prio3Sum {
+        max_bits: Option<usize>,
+    },
+    Prio3SumVec {
+        max_bits: Option<usize>,
+        max_length: Option<usize>,
+    },
+    Prio3Histogram {
+        max_length: Option<usize>,
+    },
+    Poplar1 {
+        max_bits: Option<usize>,
+    },
+}
+
+impl VdafPolicy {
+    /// Returns true if this policy allows the given VDAF.
+    fn allows(&self, vdaf: &VdafInstance) -> bool {
+        fn within(value: usize, limit: Option<usize>) -> bool {
+            limit.map_or(true, |limit| value <= limit)
+        }
+
+        match (self, vdaf) {
+            (Self::Prio3Count, VdafInstance::Prio3Count) => true,
+            (Self::Prio3CountVec { max_length }, VdafInstance::Prio3CountVec { length }) => {
+                within(*length, *max_length)
+            }
+            (Self::Prio3Sum { max_bits }, VdafInstance::Prio3Sum { bits }) => {
+                within(*bits, *max_bits)
+            }
+            (
+                Self::Prio3SumVec {
+                    max_bits,
+                    max_length,
+                },
+                VdafInstance::Prio3SumVec { bits, length },
+            ) => within(*bits, *max_bits) && within(*length, *max_length),
+            (Self::Prio3Histogram { max_length }, VdafInstance::Prio3Histogram { length }) => {
+                within(*length, *max_length)
+            }
+            (Self::Poplar1 { max_bits }, VdafInstance::Poplar1 { bits }) => {
+                within(*bits, *max_bits)
+            }
+            _ => false,
+        }
+    }
+}
+
+/// A query type that a [`TaskprovPolicy`] allows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum QueryTypePolicy {
+    TimeInterval,
+    FixedSize {
+        /// The largest `max_batch_size` that tasks may use. If not set, it is not limited.
+        #[serde(default)]
+        max_batch_size: Option<u64>,
+    },
+}
+
+impl QueryTypePolicy {
+    /// Returns true if this policy allows the given query type.
+    fn allows(&self, query_type: &QueryType) -> bool {
+        match (self, query_type) {
+            (Self::TimeInterval, QueryType::TimeInterval) => true,
+            (
+                Self::FixedSize {
+                    max_batch_size: limit,
+                },
+                QueryType::FixedSize { max_batch_size, .. },
+            ) => limit.map_or(true, |limit| *max_batch_size <= limit),
+            _ => false,
+        }
+    }
+}
+
+/// The ways in which a task may violate a [`TaskprovPolicy`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum PolicyViolation {
+    #[error("VDAF {0:?} is not allowed")]
+    VdafNotAllowed(VdafInstance),
+    #[error("query type {0:?} is not allowed")]
+    QueryTypeNotAllowed(QueryType),
+    #[error("min_batch_size {actual} is less than the minimum of {minimum}")]
+    MinBatchSizeTooSmall { actual: u64, minimum: u64 },
+    #[error("max_batch_query_count {actual} is greater than the maximum of {maximum}")]
+    MaxBatchQueryCountTooLarge { actual: u64, maximum: u64 },
+    #[error(
+        "task lifetime of {} seconds is greater than the maximum of {} seconds",
+        actual.as_seconds(),
+        maximum.as_seconds()
+    )]
+    TaskLifetimeTooLong { actual: Duration, maximum: Duration },
+    #[error(
+        "time_precision of {} seconds is less than the minimum of {} seconds",
+        actual.as_seconds(),
+        minimum.as_seconds()
+    )]
+    TimePrecisionTooSmall { actual: Duration, minimum: Duration },
+}
+
+impl TaskprovPolicy {
+    /// Checks whether this policy allows the given task, which is being provisioned at time `now`.
+    pub fn check(&self, task: &task::Task, now: Time) -> Result<(), PolicyViolation> {
+        if let Some(allowed_vdafs) = &self.allowed_vdafs {
+            if !allowed_vdafs
+                .iter()
+                .any(|policy| policy.allows(task.vdaf()))
+            {
+                return Err(PolicyViolation::VdafNotAllowed(task.vdaf().clone()));
+            }
+        }
+        if let Some(allowed_query_types) = &self.allowed_query_types {
+            if !allowed_query_types
+                .iter()
+                .any(|policy| policy.allows(task.query_type()))
+            {
+                return Err(PolicyViolation::QueryTypeNotAllowed(*task.query_type()));
+            }
+        }
+        if let Some(minimum) = self.minimum_min_batch_size {
+            if task.min_batch_size() < minimum {
+                return Err(PolicyViolation::MinBatchSizeTooSmall {
+                    actual: task.min_batch_size(),
+                    minimum,
+                });
+            }
+        }
+        if let Some(maximum) = self.maximum_max_batch_query_count {
+            if task.max_batch_query_count() > maximum {
+                return Err(PolicyViolation::MaxBatchQueryCountTooLarge {
+                    actual: task.max_batch_query_count(),
+                    maximum,
+                });
+            }
+        }
+        if let Some(maximum) = self.maximum_task_lifetime {
+            let actual = match task.task_expiration() {
+                Some(task_expiration) => task_expiration.difference(&now).unwrap_or(Duration::ZERO),
+                // Tasks without an expiration have an unbounded lifetime.
+                None => Duration::from_seconds(u64::MAX),
+            };
+            if actual > maximum {
+                return Err(PolicyViolation::TaskLifetimeTooLong { actual, maximum });
+            }
+        }
+        if let Some(minimum) = self.minimum_time_precision {
+            if task.time_precision() < &minimum {
+                return Err(PolicyViolation::TimePrecisionTooSmall {
+                    actual: *task.time_precision(),
+                    minimum,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
 /// Helper type for using `ring::Prk::expand()`.
 struct VdafVerifyKeyLength(usize);
 
@@ -271,6 +473,9 @@ impl KeyType for VdafVerifyKeyLength {
 pub struct Task(pub(super) task::Task);
 
 impl Task {
+    /// Creates a task provisioned via taskprov from the given `TaskConfig`. Parameters which are
+    /// not carried in the `TaskConfig` are taken from the peer aggregator that the task is shared
+    /// with.
     #[allow(clippy::too_many_arguments)]
     pub fn new(
         task_id: TaskId,
@@ -282,11 +487,24 @@ impl Task {
         vdaf_verify_keys: Vec<SecretBytes>,
         max_batch_query_count: u64,
         task_expiration: Option<Time>,
-        report_expiry_age: Option<Duration>,
         min_batch_size: u64,
         time_precision: Duration,
-        tolerable_clock_skew: Duration,
+        dp_config: DpConfig,
+        task_config: &TaskConfig,
+        peer_aggregator: &PeerAggregator,
     ) -> Result<Self, Error> {
+        // The leader talks to the helper and the collector using the credentials shared with the
+        // peer, and encrypts its aggregate shares to the peer's collector. The helper instead
+        // authenticates each request against the peer aggregator directly.
+        let (collector_hpke_config, aggregator_auth_tokens, collector_auth_tokens) = match role {
+            Role::Leader => (
+                Some(peer_aggregator.collector_hpke_config().clone()),
+                peer_aggregator.aggregator_auth_tokens().to_vec(),
+                peer_aggregator.collector_auth_tokens().to_vec(),
+            ),
+            _ => (None, Vec::new(), Vec::new()),
+        };
+
         let task = Self(task::Task::new_without_validation(
             task_id,
             leader_aggregator_endpoint,
@@ -297,14 +515,17 @@ impl Task {
             vdaf_verify_keys,
             max_batch_query_count,
             task_expiration,
-            report_expiry_age,
+            peer_aggregator.report_expiry_age().cloned(),
             min_batch_size,
             time_precision,
-            tolerable_clock_skew,
-            None,
-            Vec::new(),
-            Vec::new(),
+            *peer_aggregator.tolerable_clock_skew(),
+            TaskPriority::default(),
+            dp_config,
+            collector_hpke_config,
+            aggregator_auth_tokens,
+            collector_auth_tokens,
             Vec::new(),
+            Some(task_config.get_encoded()),
         ));
         task.validate()?;
         Ok(task)
@@ -323,6 +544,14 @@ impl Task {
                 ));
             }
         }
+        if self.0.role() == &Role::Leader {
+            if self.0.aggregator_auth_tokens().is_empty() {
+                return Err(Error::InvalidParameter("aggregator_auth_tokens"));
+            }
+            if self.0.collector_auth_tokens().is_empty() {
+                return Err(Error::InvalidParameter("collector_auth_tokens"));
+            }
+        }
         Ok(())
     }
 
@@ -347,7 +576,7 @@ pub mod test_util {
     use rand::random;
     use url::Url;
 
-    use super::{PeerAggregator, VerifyKeyInit};
+    use super::{PeerAggregator, TaskprovPolicy, VerifyKeyInit};
 
     #[derive(Debug, Clone)]
     pub struct PeerAggregatorBuilder(PeerAggregator);
@@ -363,6 +592,7 @@ pub mod test_util {
                 Duration::from_seconds(1),
                 Vec::from([random()]),
                 Vec::from([random()]),
+                TaskprovPolicy::default(),
             ))
         }
 
@@ -422,6 +652,10 @@ pub mod test_util {
             })
         }
 
+        pub fn with_policy(self, policy: TaskprovPolicy) -> Self {
+            Self(PeerAggregator { policy, ..self.0 })
+        }
+
         pub fn build(self) -> PeerAggregator {
             self.0
         }
@@ -439,3 +673,162 @@ pub mod test_util {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        task::{test_util::TaskBuilder, QueryType},
+        taskprov::{PolicyViolation, QueryTypePolicy, TaskprovPolicy, VdafPolicy},
+    };
+    use janus_core::task::VdafInstance;
+    use janus_messages::{Duration, Role, Time};
+
+    #[test]
+    fn policy_check() {
+        let now = Time::from_seconds_since_epoch(1_000_000);
+        let task = TaskBuilder::new(
+            QueryType::FixedSize {
+                max_batch_size: 1000,
+                batch_time_window_size: None,
+            },
+            VdafInstance::Prio3SumVec {
+                bits: 8,
+                length: 10,
+            },
+            Role::Helper,
+        )
+        .with_min_batch_size(100)
+        .with_task_expiration(Some(Time::from_seconds_since_epoch(1_086_400)))
+        .with_time_precision(Duration::from_seconds(3600))
+        .build();
+
+        assert_eq!(TaskprovPolicy::default().check(&task, now), Ok(()));
+
+        let policy = TaskprovPolicy {
+            allowed_vdafs: Some(Vec::from([
+                VdafPolicy::Prio3Count,
+                VdafPolicy::Prio3SumVec {
+                    max_bits: Some(8),
+                    max_length: None,
+                },
+            ])),
+            allowed_query_types: Some(Vec::from([
+                QueryTypePolicy::TimeInterval,
+                QueryTypePolicy::FixedSize {
+                    max_batch_size: Some(1000),
+                },
+            ])),
+            minimum_min_batch_size: Some(100),
+            maximum_max_batch_query_count: Some(1),
+            maximum_task_lifetime: Some(Duration::from_seconds(86_400)),
+            minimum_time_precision: Some(Duration::from_seconds(3600)),
+        };
+        assert_eq!(policy.check(&task, now), Ok(()));
+
+        for (policy, violation) in [
+            (
+                TaskprovPolicy {
+                    allowed_vdafs: Some(Vec::from([VdafPolicy::Prio3SumVec {
+                        max_bits: Some(8),
+                        max_length: Some(9),
+                    }])),
+                    ..policy.clone()
+                },
+                PolicyViolation::VdafNotAllowed(task.vdaf().clone()),
+            ),
+            (
+                TaskprovPolicy {
+                    allowed_vdafs: Some(Vec::from([VdafPolicy::Prio3Count])),
+                    ..policy.clone()
+                },
+                PolicyViolation::VdafNotAllowed(task.vdaf().clone()),
+            ),
+            (
+                TaskprovPolicy {
+                    allowed_query_types: Some(Vec::from([QueryTypePolicy::FixedSize {
+                        max_batch_size: Some(999),
+                    }])),
+                    ..policy.clone()
+                },
+                PolicyViolation::QueryTypeNotAllowed(*task.query_type()),
+            ),
+            (
+                TaskprovPolicy {
+                    minimum_min_batch_size: Some(101),
+                    ..policy.clone()
+                },
+                PolicyViolation::MinBatchSizeTooSmall {
+                    actual: 100,
+                    minimum: 101,
+                },
+            ),
+            (
+                TaskprovPolicy {
+                    maximum_max_batch_query_count: Some(0),
+                    ..policy.clone()
+                },
+                PolicyViolation::MaxBatchQueryCountTooLarge {
+                    actual: 1,
+                    maximum: 0,
+                },
+            ),
+            (
+                TaskprovPolicy {
+                    maximum_task_lifetime: Some(Duration::from_seconds(86_399)),
+                    ..policy.clone()
+                },
+                PolicyViolation::TaskLifetimeTooLong {
+                    actual: Duration::from_seconds(86_400),
+                    maximum: Duration::from_seconds(86_399),
+                },
+            ),
+            (
+                TaskprovPolicy {
+                    minimum_time_precision: Some(Duration::from_seconds(7200)),
+                    ..policy.clone()
+                },
+                PolicyViolation::TimePrecisionTooSmall {
+                    actual: Duration::from_seconds(3600),
+                    minimum: Duration::from_seconds(7200),
+                },
+            ),
+        ] {
+            assert_eq!(policy.check(&task, now), Err(violation));
+        }
+    }
+
+    #[test]
+    fn policy_serde() {
+        let policy: TaskprovPolicy = serde_yaml::from_str(
+            "---
+allowed_vdafs:
+  - Prio3Count
+  - !Prio3Histogram
+    max_length: 100
+allowed_query_types:
+  - TimeInterval
+minimum_min_batch_size: 100
+",
+        )
+        .unwrap();
+        assert_eq!(
+            policy,
+            TaskprovPolicy {
+                allowed_vdafs: Some(Vec::from([
+                    VdafPolicy::Prio3Count,
+                    VdafPolicy::Prio3Histogram {
+                        max_length: Some(100)
+                    },
+                ])),
+                allowed_query_types: Some(Vec::from([QueryTypePolicy::TimeInterval])),
+                minimum_min_batch_size: Some(100),
+                ..Default::default()
+            }
+        );
+
+        assert_eq!(
+            serde_json::from_str::<TaskprovPolicy>("{}").unwrap(),
+            TaskprovPolicy::default()
+        );
+    }
+}
@@ -10,11 +10,12 @@ use janus_core::{
     http::response_to_problem_details,
     retries::{http_request_exponential_backoff, retry_http_request},
     task::url_ensure_trailing_slash,
+    taskprov::{self, TASKPROV_HEADER},
     time::{Clock, TimeExt},
 };
 use janus_messages::{
-    Duration, HpkeConfig, HpkeConfigList, InputShareAad, PlaintextInputShare, Report, ReportId,
-    ReportMetadata, Role, TaskId,
+    taskprov::TaskConfig, Duration, HpkeConfig, HpkeConfigList, InputShareAad, PlaintextInputShare,
+    Report, ReportId, ReportMetadata, Role, TaskId,
 };
 use prio::{
     codec::{Decode, Encode},
@@ -69,6 +70,9 @@ pub struct ClientParameters {
     time_precision: Duration,
     /// Parameters to use when retrying HTTP requests.
     http_request_retry_parameters: ExponentialBackoff,
+    /// The encoded taskprov `TaskConfig` describing the task, if the task is provisioned via
+    /// taskprov. It is sent to the leader along with each report.
+    taskprov_task_config: Option<Vec<u8>>,
 }
 
 impl ClientParameters {
@@ -102,9 +106,47 @@ impl ClientParameters {
             helper_aggregator_endpoint: url_ensure_trailing_slash(helper_aggregator_endpoint),
             time_precision,
             http_request_retry_parameters,
+            taskprov_task_config: None,
         }
     }
 
+    /// Creates a new set of client task parameters for a task provisioned via taskprov. The task
+    /// ID, aggregator endpoints and time precision are all taken from the `TaskConfig`.
+    pub fn new_taskprov(task_config: &TaskConfig) -> Result<Self, Error> {
+        Self::new_taskprov_with_backoff(task_config, http_request_exponential_backoff())
+    }
+
+    /// Creates a new set of client task parameters for a task provisioned via taskprov, with
+    /// non-default HTTP request retry parameters.
+    pub fn new_taskprov_with_backoff(
+        task_config: &TaskConfig,
+        http_request_retry_parameters: ExponentialBackoff,
+    ) -> Result<Self, Error> {
+        let encoded_task_config = task_config.get_encoded();
+        let [leader_aggregator_endpoint, helper_aggregator_endpoint] =
+            task_config.aggregator_endpoints()
+        else {
+            return Err(Error::InvalidParameter(
+                "taskprov task config must have exactly two aggregator endpoints",
+            ));
+        };
+        Ok(Self {
+            taskprov_task_config: Some(encoded_task_config.clone()),
+            ..Self::new_with_backoff(
+                taskprov::compute_task_id(&encoded_task_config),
+                leader_aggregator_endpoint.try_into()?,
+                helper_aggregator_endpoint.try_into()?,
+                *task_config.query_config().time_precision(),
+                http_request_retry_parameters,
+            )
+        })
+    }
+
+    /// The ID of the task.
+    pub fn task_id(&self) -> &TaskId {
+        &self.task_id
+    }
+
     /// The URL relative to which the API endpoints for the aggregator may be found, if the role is
     /// an aggregator, or an error otherwise.
     fn aggregator_endpoint(&self, role: &Role) -> Result<&Url, Error> {
@@ -269,12 +311,17 @@ impl<V: vdaf::Client<16>, C: Clock> Client<V, C> {
         let upload_response = retry_http_request(
             self.parameters.http_request_retry_parameters.clone(),
             || async {
-                self.http_client
+                let mut request = self
+                    .http_client
                     .put(upload_endpoint.clone())
-                    .header(CONTENT_TYPE, Report::MEDIA_TYPE)
-                    .body(report.get_encoded())
-                    .send()
-                    .await
+                    .header(CONTENT_TYPE, Report::MEDIA_TYPE);
+                if let Some(taskprov_task_config) = &self.parameters.taskprov_task_config {
+                    request = request.header(
+                        TASKPROV_HEADER,
+                        taskprov::header_value(taskprov_task_config),
+                    );
+                }
+                request.body(report.get_encoded()).send().await
             },
         )
         .await
@@ -297,11 +344,22 @@ mod tests {
     use http::{header::CONTENT_TYPE, StatusCode};
     use janus_core::{
         hpke::test_util::generate_test_hpke_config_and_private_key,
-        retries::test_http_request_exponential_backoff, test_util::install_test_trace_subscriber,
+        retries::test_http_request_exponential_backoff,
+        taskprov::{compute_task_id, header_value, TASKPROV_HEADER},
+        test_util::install_test_trace_subscriber,
         time::MockClock,
     };
-    use janus_messages::{Duration, Report, Time};
-    use prio::vdaf::{self, prio3::Prio3};
+    use janus_messages::{
+        taskprov::{
+            DpConfig, DpMechanism, Query as TaskprovQuery, QueryConfig, TaskConfig, VdafConfig,
+            VdafType,
+        },
+        Duration, Report, Time,
+    };
+    use prio::{
+        codec::Encode,
+        vdaf::{self, prio3::Prio3},
+    };
     use rand::random;
     use url::Url;
 
@@ -367,6 +425,55 @@ mod tests {
         mocked_upload.assert_async().await;
     }
 
+    #[tokio::test]
+    async fn upload_taskprov() {
+        install_test_trace_subscriber();
+        let mut server = mockito::Server::new_async().await;
+        let server_url = server.url();
+        let task_config = TaskConfig::new(
+            Vec::from("foobar".as_bytes()),
+            Vec::from([
+                server_url.as_bytes().try_into().unwrap(),
+                server_url.as_bytes().try_into().unwrap(),
+            ]),
+            QueryConfig::new(Duration::from_seconds(1), 1, 1, TaskprovQuery::TimeInterval),
+            Time::from_seconds_since_epoch(u64::MAX),
+            VdafConfig::new(DpConfig::new(DpMechanism::None), VdafType::Prio3Count).unwrap(),
+        )
+        .unwrap();
+        let parameters = ClientParameters::new_taskprov_with_backoff(
+            &task_config,
+            test_http_request_exponential_backoff(),
+        )
+        .unwrap();
+        let task_id = compute_task_id(&task_config.get_encoded());
+        assert_eq!(parameters.task_id(), &task_id);
+        let client = Client::new(
+            parameters,
+            Prio3::new_count(2).unwrap(),
+            MockClock::default(),
+            &default_http_client().unwrap(),
+            generate_test_hpke_config_and_private_key().config().clone(),
+            generate_test_hpke_config_and_private_key().config().clone(),
+        );
+
+        let mocked_upload = server
+            .mock("PUT", format!("/tasks/{task_id}/reports").as_str())
+            .match_header(CONTENT_TYPE.as_str(), Report::MEDIA_TYPE)
+            .match_header(
+                TASKPROV_HEADER,
+                header_value(&task_config.get_encoded()).as_str(),
+            )
+            .with_status(200)
+            .expect(1)
+            .create_async()
+            .await;
+
+        client.upload(&1).await.unwrap();
+
+        mocked_upload.assert_async().await;
+    }
+
     #[tokio::test]
     async fn upload_prio3_invalid_measurement() {
         install_test_trace_subscriber();
@@ -63,12 +63,15 @@ use janus_core::{
     http::response_to_problem_details,
     retries::{http_request_exponential_backoff, retry_http_request},
     task::url_ensure_trailing_slash,
+    taskprov::{self, TASKPROV_HEADER},
     time::{DurationExt, TimeExt},
 };
 use janus_messages::{
+    batch_discovery::CollectableBatchList,
     problem_type::DapProblemType,
     query_type::{QueryType, TimeInterval},
-    AggregateShareAad, BatchSelector, Collection as CollectionMessage, CollectionJobId,
+    taskprov::TaskConfig,
+    AggregateShareAad, BatchId, BatchSelector, Collection as CollectionMessage, CollectionJobId,
     CollectionReq, HpkeConfig, PartialBatchSelector, Query, Role, TaskId,
 };
 use prio::{
@@ -172,6 +175,10 @@ pub struct CollectorParameters {
     /// Parameters to use when waiting for a collection job to be processed.
     #[derivative(Debug = "ignore")]
     collect_poll_wait_parameters: ExponentialBackoff,
+    /// The encoded taskprov `TaskConfig` describing the task, if the task is provisioned via
+    /// taskprov.
+    #[derivative(Debug = "ignore")]
+    taskprov_task_config: Option<Vec<u8>>,
 }
 
 impl CollectorParameters {
@@ -197,6 +204,7 @@ impl CollectorParameters {
                 max_elapsed_time: None,
                 ..Default::default()
             },
+            taskprov_task_config: None,
         }
     }
 
@@ -212,6 +220,14 @@ impl CollectorParameters {
         self
     }
 
+    /// Send the given taskprov `TaskConfig` to the leader when creating collection jobs, allowing
+    /// the leader to opt in to the task if it has not done so already. The task ID must be the one
+    /// derived from this `TaskConfig`.
+    pub fn with_taskprov_task_config(mut self, task_config: &TaskConfig) -> CollectorParameters {
+        self.taskprov_task_config = Some(task_config.get_encoded());
+        self
+    }
+
     /// Construct a URI for a collection.
     fn collection_job_uri(&self, collection_job_id: CollectionJobId) -> Result<Url, Error> {
         Ok(self.leader_endpoint.join(&format!(
@@ -219,6 +235,18 @@ impl CollectorParameters {
             self.task_id
         ))?)
     }
+
+    /// Construct a URI for a page of the list of collectable batches.
+    fn batches_uri(&self, pagination_token: Option<&BatchId>) -> Result<Url, Error> {
+        let mut uri = self
+            .leader_endpoint
+            .join(&format!("tasks/{}/batches", self.task_id))?;
+        if let Some(pagination_token) = pagination_token {
+            uri.query_pairs_mut()
+                .append_pair("pagination_token", &pagination_token.to_string());
+        }
+        Ok(uri)
+    }
 }
 
 /// Construct a [`reqwest::Client`] suitable for use in a DAP [`Collector`].
@@ -229,10 +257,12 @@ pub fn default_http_client() -> Result<reqwest::Client, Error> {
         .build()?)
 }
 
-/// Collector state related to a collection job that is in progress.
+/// Collector state related to a collection job that is in progress. Applications that need to
+/// resume polling a collection job after restarting may persist its collection job ID and query,
+/// and later reconstruct it with [`CollectionJob::new`].
 #[derive(Derivative)]
 #[derivative(Debug)]
-struct CollectionJob<P, Q>
+pub struct CollectionJob<P, Q>
 where
     Q: QueryType,
 {
@@ -246,7 +276,9 @@ where
 }
 
 impl<P, Q: QueryType> CollectionJob<P, Q> {
-    fn new(
+    /// Creates a new [`CollectionJob`], representing a collection job that has already been
+    /// created at the leader.
+    pub fn new(
         collection_job_id: CollectionJobId,
         query: Query<Q>,
         aggregation_parameter: P,
@@ -257,6 +289,21 @@ impl<P, Q: QueryType> CollectionJob<P, Q> {
             aggregation_parameter,
         }
     }
+
+    /// Retrieves the ID of this collection job.
+    pub fn collection_job_id(&self) -> &CollectionJobId {
+        &self.collection_job_id
+    }
+
+    /// Retrieves the query of this collection job.
+    pub fn query(&self) -> &Query<Q> {
+        &self.query
+    }
+
+    /// Retrieves the aggregation parameter of this collection job.
+    pub fn aggregation_parameter(&self) -> &P {
+        &self.aggregation_parameter
+    }
 }
 
 #[derive(Derivative)]
@@ -353,6 +400,58 @@ where
 {
 }
 
+/// A batch of a fixed-size task which may be collected, as listed by the leader.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CollectableBatch {
+    batch_id: BatchId,
+    report_count: u64,
+    interval: (DateTime<Utc>, Duration),
+    collected: bool,
+}
+
+impl CollectableBatch {
+    /// Retrieves the ID of this batch.
+    pub fn batch_id(&self) -> &BatchId {
+        &self.batch_id
+    }
+
+    /// Retrieves the number of client reports in this batch.
+    pub fn report_count(&self) -> u64 {
+        self.report_count
+    }
+
+    /// Retrieves the interval of time spanned by the reports in this batch.
+    pub fn interval(&self) -> &(DateTime<Utc>, Duration) {
+        &self.interval
+    }
+
+    /// Returns true if this batch has already been collected. Collected batches are no longer
+    /// selected by [`FixedSizeQuery::CurrentBatch`](janus_messages::FixedSizeQuery::CurrentBatch)
+    /// queries, and may only be collected again by their batch ID.
+    pub fn collected(&self) -> bool {
+        self.collected
+    }
+}
+
+#[cfg(feature = "test-util")]
+#[cfg_attr(docsrs, doc(cfg(feature = "test-util")))]
+impl CollectableBatch {
+    /// Creates a new [`CollectableBatch`].
+    pub fn new(
+        batch_id: BatchId,
+        report_count: u64,
+        interval: (DateTime<Utc>, Duration),
+        collected: bool,
+    ) -> Self {
+        Self {
+            batch_id,
+            report_count,
+            interval,
+            collected,
+        }
+    }
+}
+
 /// A DAP collector.
 #[derive(Derivative)]
 #[derivative(Debug)]
@@ -379,16 +478,29 @@ impl<V: vdaf::Collector> Collector<V> {
         }
     }
 
-    /// Send a collect request to the leader aggregator.
+    /// Send a collect request to the leader aggregator, creating a new collection job with a random
+    /// ID. The result may be fetched later with [`Collector::poll_until_complete`].
+    pub async fn start_collection<Q: QueryType>(
+        &self,
+        query: Query<Q>,
+        aggregation_parameter: &V::AggregationParam,
+    ) -> Result<CollectionJob<V::AggregationParam, Q>, Error> {
+        self.start_collection_with_id(random(), query, aggregation_parameter)
+            .await
+    }
+
+    /// Send a collect request to the leader aggregator, creating a new collection job with the
+    /// given ID. Creating a collection job is idempotent, so applications may record the ID before
+    /// calling this, and safely retry with the same ID and query if the outcome is unknown.
     #[tracing::instrument(skip(aggregation_parameter), err)]
-    async fn start_collection<Q: QueryType>(
+    pub async fn start_collection_with_id<Q: QueryType>(
         &self,
+        collection_job_id: CollectionJobId,
         query: Query<Q>,
         aggregation_parameter: &V::AggregationParam,
     ) -> Result<CollectionJob<V::AggregationParam, Q>, Error> {
         let collect_request =
             CollectionReq::new(query.clone(), aggregation_parameter.get_encoded());
-        let collection_job_id = random();
         let collection_job_url = self.parameters.collection_job_uri(collection_job_id)?;
 
         let response_res = retry_http_request(
@@ -396,13 +508,19 @@ impl<V: vdaf::Collector> Collector<V> {
             || async {
                 let (auth_header, auth_value) =
                     self.parameters.authentication.request_authentication();
-                self.http_client
+                let mut request = self
+                    .http_client
                     .put(collection_job_url.clone())
                     .header(CONTENT_TYPE, CollectionReq::<TimeInterval>::MEDIA_TYPE)
                     .body(collect_request.get_encoded())
-                    .header(auth_header, auth_value)
-                    .send()
-                    .await
+                    .header(auth_header, auth_value);
+                if let Some(taskprov_task_config) = &self.parameters.taskprov_task_config {
+                    request = request.header(
+                        TASKPROV_HEADER,
+                        taskprov::header_value(taskprov_task_config),
+                    );
+                }
+                request.send().await
             },
         )
         .await;
@@ -560,7 +678,7 @@ impl<V: vdaf::Collector> Collector<V> {
 
     /// A convenience method to repeatedly request the result of an in-progress collection until it
     /// completes.
-    async fn poll_until_complete<Q: QueryType>(
+    pub async fn poll_until_complete<Q: QueryType>(
         &self,
         job: &CollectionJob<V::AggregationParam, Q>,
     ) -> Result<Collection<V::AggregateResult, Q>, Error> {
@@ -616,6 +734,90 @@ impl<V: vdaf::Collector> Collector<V> {
         }
     }
 
+    /// Request the list of batches of a fixed-size task which have enough reports to be collected
+    /// from the leader aggregator. This allows collectors to discover the IDs of batches they did
+    /// not collect with a current-batch query. The leader lists batches a page at a time; every page
+    /// is requested, and batches are returned in order of batch ID. Listing batches is an extension
+    /// to DAP supported by Janus, and may not be supported by other leader implementations.
+    #[tracing::instrument(err)]
+    pub async fn list_batches(&self) -> Result<Vec<CollectableBatch>, Error> {
+        let mut batches = Vec::new();
+        let mut pagination_token = None;
+        loop {
+            let page = self.list_batches_page(pagination_token.as_ref()).await?;
+            for batch in page.batches() {
+                batches.push(CollectableBatch {
+                    batch_id: *batch.batch_id(),
+                    report_count: batch.report_count(),
+                    interval: (
+                        DateTime::<Utc>::from_utc(
+                            batch.interval().start().as_naive_date_time()?,
+                            Utc,
+                        ),
+                        batch.interval().duration().as_chrono_duration()?,
+                    ),
+                    collected: batch.collected(),
+                });
+            }
+            match page.pagination_token() {
+                Some(next_pagination_token) => pagination_token = Some(*next_pagination_token),
+                None => return Ok(batches),
+            }
+        }
+    }
+
+    /// Request a single page of the list of collectable batches from the leader aggregator.
+    async fn list_batches_page(
+        &self,
+        pagination_token: Option<&BatchId>,
+    ) -> Result<CollectableBatchList, Error> {
+        let batches_url = self.parameters.batches_uri(pagination_token)?;
+        let response_res = retry_http_request(
+            self.parameters.http_request_retry_parameters.clone(),
+            || async {
+                let (auth_header, auth_value) =
+                    self.parameters.authentication.request_authentication();
+                self.http_client
+                    .get(batches_url.clone())
+                    .header(auth_header, auth_value)
+                    .send()
+                    .await
+            },
+        )
+        .await;
+
+        let response = match response_res {
+            // Successful response or unretryable error status code:
+            Ok(response) => {
+                let status = response.status();
+                if status.is_client_error() || status.is_server_error() {
+                    return Err(Error::from_http_response(response).await);
+                } else if status != StatusCode::OK {
+                    // Incorrect success/redirect status code:
+                    return Err(Error::Http {
+                        problem_details: Box::new(HttpApiProblem::new(status)),
+                        dap_problem_type: None,
+                    });
+                }
+                response
+            }
+            // Retryable error status code, but ran out of retries:
+            Err(Ok(response)) => return Err(Error::from_http_response(response).await),
+            // Lower level errors, either unretryable or ran out of retries:
+            Err(Err(error)) => return Err(Error::HttpClient(error)),
+        };
+
+        let content_type = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .ok_or(Error::BadContentType(None))?;
+        if content_type != CollectableBatchList::MEDIA_TYPE {
+            return Err(Error::BadContentType(Some(content_type.clone())));
+        }
+
+        Ok(CollectableBatchList::get_decoded(&response.bytes().await?)?)
+    }
+
     /// Send a collect request to the leader aggregator, wait for it to complete, and return the
     /// result of the aggregation.
     pub async fn collect<Q: QueryType>(
@@ -631,8 +833,8 @@ impl<V: vdaf::Collector> Collector<V> {
 #[cfg(test)]
 mod tests {
     use crate::{
-        default_http_client, Collection, CollectionJob, Collector, CollectorParameters, Error,
-        PollResult,
+        default_http_client, CollectableBatch, Collection, CollectionJob, Collector,
+        CollectorParameters, Error, PollResult,
     };
     use assert_matches::assert_matches;
     use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
@@ -644,11 +846,17 @@ mod tests {
         },
         retries::test_http_request_exponential_backoff,
         task::AuthenticationToken,
+        taskprov::{compute_task_id, header_value, TASKPROV_HEADER},
         test_util::{install_test_trace_subscriber, run_vdaf, VdafTranscript},
     };
     use janus_messages::{
+        batch_discovery::{CollectableBatch as CollectableBatchMessage, CollectableBatchList},
         problem_type::DapProblemType,
         query_type::{FixedSize, TimeInterval},
+        taskprov::{
+            DpConfig, DpMechanism, Query as TaskprovQuery, QueryConfig, TaskConfig, VdafConfig,
+            VdafType,
+        },
         AggregateShareAad, BatchId, BatchSelector, Collection as CollectionMessage,
         CollectionJobId, CollectionReq, Duration, FixedSizeQuery, HpkeCiphertext, Interval,
         PartialBatchSelector, Query, Role, TaskId, Time,
@@ -780,6 +988,63 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn start_collection_taskprov() {
+        install_test_trace_subscriber();
+        let mut server = mockito::Server::new_async().await;
+        let server_url = server.url();
+        let task_config = TaskConfig::new(
+            Vec::from("foobar".as_bytes()),
+            Vec::from([
+                server_url.as_bytes().try_into().unwrap(),
+                server_url.as_bytes().try_into().unwrap(),
+            ]),
+            QueryConfig::new(Duration::from_seconds(1), 1, 1, TaskprovQuery::TimeInterval),
+            Time::from_seconds_since_epoch(u64::MAX),
+            VdafConfig::new(DpConfig::new(DpMechanism::None), VdafType::Prio3Count).unwrap(),
+        )
+        .unwrap();
+        let task_id = compute_task_id(&task_config.get_encoded());
+        let hpke_keypair = generate_test_hpke_config_and_private_key();
+        let parameters = CollectorParameters::new(
+            task_id,
+            Url::parse(&server_url).unwrap(),
+            AuthenticationToken::new_bearer_token_from_string("Y29sbGVjdG9yIHRva2Vu").unwrap(),
+            hpke_keypair.config().clone(),
+            hpke_keypair.private_key().clone(),
+        )
+        .with_http_request_backoff(test_http_request_exponential_backoff())
+        .with_taskprov_task_config(&task_config);
+        let collector = Collector::new(
+            parameters,
+            Prio3::new_count(2).unwrap(),
+            default_http_client().unwrap(),
+        );
+
+        let mocked_collect_start = server
+            .mock("PUT", collection_uri_regex_matcher(&task_id))
+            .match_header(
+                TASKPROV_HEADER,
+                header_value(&task_config.get_encoded()).as_str(),
+            )
+            .with_status(201)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let batch_interval = Interval::new(
+            Time::from_seconds_since_epoch(1_000_000),
+            Duration::from_seconds(3600),
+        )
+        .unwrap();
+        collector
+            .start_collection(Query::new_time_interval(batch_interval), &())
+            .await
+            .unwrap();
+
+        mocked_collect_start.assert_async().await;
+    }
+
     #[tokio::test]
     async fn successful_collect_prio3_count() {
         install_test_trace_subscriber();
@@ -1595,6 +1860,151 @@ mod tests {
         mock_collection_job_always_fail.assert_async().await;
     }
 
+    #[tokio::test]
+    async fn successful_list_batches() {
+        install_test_trace_subscriber();
+        let mut server = mockito::Server::new_async().await;
+        let vdaf = Prio3::new_count(2).unwrap();
+        let collector = setup_collector(&mut server, vdaf);
+
+        let batch_id_1: BatchId = random();
+        let batch_id_2 = random();
+        let batches_path = format!("/tasks/{}/batches", collector.parameters.task_id);
+        // The batches are listed over two pages, the second following on from the first.
+        let mocked_list_batches_first_page = server
+            .mock("GET", batches_path.as_str())
+            .match_query(Matcher::Missing)
+            .match_header(AUTHORIZATION.as_str(), "Bearer Y29sbGVjdG9yIHRva2Vu")
+            .with_status(200)
+            .with_header(CONTENT_TYPE.as_str(), CollectableBatchList::MEDIA_TYPE)
+            .with_body(
+                CollectableBatchList::new(
+                    Vec::from([CollectableBatchMessage::new(
+                        batch_id_1,
+                        10,
+                        Interval::new(
+                            Time::from_seconds_since_epoch(1_000_000),
+                            Duration::from_seconds(3600),
+                        )
+                        .unwrap(),
+                        true,
+                    )]),
+                    Some(batch_id_1),
+                )
+                .get_encoded(),
+            )
+            .expect(1)
+            .create_async()
+            .await;
+        let mocked_list_batches_second_page = server
+            .mock("GET", batches_path.as_str())
+            .match_query(Matcher::UrlEncoded(
+                "pagination_token".to_string(),
+                batch_id_1.to_string(),
+            ))
+            .match_header(AUTHORIZATION.as_str(), "Bearer Y29sbGVjdG9yIHRva2Vu")
+            .with_status(200)
+            .with_header(CONTENT_TYPE.as_str(), CollectableBatchList::MEDIA_TYPE)
+            .with_body(
+                CollectableBatchList::new(
+                    Vec::from([CollectableBatchMessage::new(
+                        batch_id_2,
+                        12,
+                        Interval::new(
+                            Time::from_seconds_since_epoch(1_003_600),
+                            Duration::from_seconds(60),
+                        )
+                        .unwrap(),
+                        false,
+                    )]),
+                    None,
+                )
+                .get_encoded(),
+            )
+            .expect(1)
+            .create_async()
+            .await;
+
+        let batches = collector.list_batches().await.unwrap();
+        assert_eq!(
+            batches,
+            Vec::from([
+                CollectableBatch::new(
+                    batch_id_1,
+                    10,
+                    (
+                        DateTime::<Utc>::from_utc(
+                            NaiveDateTime::from_timestamp_opt(1_000_000, 0).unwrap(),
+                            Utc
+                        ),
+                        chrono::Duration::seconds(3600),
+                    ),
+                    true,
+                ),
+                CollectableBatch::new(
+                    batch_id_2,
+                    12,
+                    (
+                        DateTime::<Utc>::from_utc(
+                            NaiveDateTime::from_timestamp_opt(1_003_600, 0).unwrap(),
+                            Utc
+                        ),
+                        chrono::Duration::seconds(60),
+                    ),
+                    false,
+                ),
+            ])
+        );
+
+        mocked_list_batches_first_page.assert_async().await;
+        mocked_list_batches_second_page.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn failed_list_batches() {
+        install_test_trace_subscriber();
+        let mut server = mockito::Server::new_async().await;
+        let vdaf = Prio3::new_count(2).unwrap();
+        let collector = setup_collector(&mut server, vdaf);
+        let batches_path = format!("/tasks/{}/batches", collector.parameters.task_id);
+
+        let mock_bad_request = server
+            .mock("GET", batches_path.as_str())
+            .with_status(400)
+            .with_header("Content-Type", "application/problem+json")
+            .with_body(concat!(
+                "{\"type\": \"urn:ietf:params:ppm:dap:error:unauthorizedRequest\", ",
+                "\"detail\": \"The request's authorization is not valid.\"}"
+            ))
+            .expect(1)
+            .create_async()
+            .await;
+
+        let error = collector.list_batches().await.unwrap_err();
+        assert_matches!(error, Error::Http { problem_details, dap_problem_type } => {
+            assert_eq!(problem_details.status.unwrap(), StatusCode::BAD_REQUEST);
+            assert_eq!(dap_problem_type, Some(DapProblemType::UnauthorizedRequest));
+        });
+
+        mock_bad_request.assert_async().await;
+
+        let mock_bad_content_type = server
+            .mock("GET", batches_path.as_str())
+            .with_status(200)
+            .with_header(CONTENT_TYPE.as_str(), "application/octet-stream")
+            .with_body(CollectableBatchList::new(Vec::new(), None).get_encoded())
+            .expect(1)
+            .create_async()
+            .await;
+
+        let error = collector.list_batches().await.unwrap_err();
+        assert_matches!(error, Error::BadContentType(Some(content_type)) => {
+            assert_eq!(content_type, "application/octet-stream");
+        });
+
+        mock_bad_content_type.assert_async().await;
+    }
+
     #[tokio::test]
     async fn collect_poll_retry_after() {
         install_test_trace_subscriber();
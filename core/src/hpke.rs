@@ -6,6 +6,7 @@ use hpke_dispatch::{Aead, HpkeError, Kdf, Kem, Keypair};
 use janus_messages::{
     HpkeAeadId, HpkeCiphertext, HpkeConfig, HpkeConfigId, HpkeKdfId, HpkeKemId, HpkePublicKey, Role,
 };
+use prio::codec::Encode;
 use serde::{
     de::{self, Visitor},
     Deserialize, Serialize, Serializer,
@@ -73,6 +74,12 @@ impl HpkeApplicationInfo {
             .concat(),
         )
     }
+
+    /// Application info used when wrapping an HPKE private key to a transport key. This is distinct
+    /// from any application info used in DAP, so wrapped keys can't be confused with DAP messages.
+    fn key_transport() -> Self {
+        Self(b"janus hpke private key transport".to_vec())
+    }
 }
 
 /// An HPKE private key, serialized using the `SerializePrivateKey` function as
@@ -199,6 +206,45 @@ pub fn open(
         .map_err(Into::into)
 }
 
+/// Encrypt the private key of `keypair` to the provided transport key, so that it may be moved
+/// between systems without exposing it. The keypair's HPKE configuration is bound to the resulting
+/// ciphertext, and must be provided to [`unwrap_private_key`] along with it.
+pub fn wrap_private_key(
+    transport_config: &HpkeConfig,
+    keypair: &HpkeKeypair,
+) -> Result<HpkeCiphertext, Error> {
+    seal(
+        transport_config,
+        &HpkeApplicationInfo::key_transport(),
+        keypair.private_key().as_ref(),
+        &keypair.config().get_encoded(),
+    )
+}
+
+/// Decrypt a private key that was wrapped to `transport_keypair` by [`wrap_private_key`], and check
+/// that it corresponds to `config`.
+pub fn unwrap_private_key(
+    transport_keypair: &HpkeKeypair,
+    config: &HpkeConfig,
+    wrapped_private_key: &HpkeCiphertext,
+) -> Result<HpkeKeypair, Error> {
+    if wrapped_private_key.config_id() != transport_keypair.config().id() {
+        return Err(Error::InvalidConfiguration(
+            "private key was not wrapped to the provided transport key",
+        ));
+    }
+    let private_key = open(
+        transport_keypair.config(),
+        transport_keypair.private_key(),
+        &HpkeApplicationInfo::key_transport(),
+        wrapped_private_key,
+        &config.get_encoded(),
+    )?;
+    let keypair = HpkeKeypair::new(config.clone(), HpkePrivateKey::new(private_key));
+    keypair.validate()?;
+    Ok(keypair)
+}
+
 /// Generate a new HPKE keypair and return it as an HpkeConfig (public portion) and
 /// HpkePrivateKey (private portion).
 pub fn generate_hpke_config_and_private_key(
@@ -251,10 +297,33 @@ impl HpkeKeypair {
     pub fn private_key(&self) -> &HpkePrivateKey {
         &self.private_key
     }
+
+    /// Check that the private key corresponds to the public key in the HPKE configuration, and
+    /// that the configuration's algorithms are supported, by encrypting and then decrypting a
+    /// message.
+    pub fn validate(&self) -> Result<(), Error> {
+        const PLAINTEXT: &[u8] = b"janus HPKE keypair validation";
+        let application_info =
+            HpkeApplicationInfo::new(&Label::InputShare, &Role::Client, &Role::Leader);
+        let ciphertext = seal(&self.config, &application_info, PLAINTEXT, &[])?;
+        if open(
+            &self.config,
+            &self.private_key,
+            &application_info,
+            &ciphertext,
+            &[],
+        )? != PLAINTEXT
+        {
+            return Err(Error::InvalidConfiguration(
+                "private key does not match public key",
+            ));
+        }
+        Ok(())
+    }
 }
 
 /// HPKE configuration compatible with the output of `divviup hpke-config generate`.
-#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct DivviUpHpkeConfig {
     id: HpkeConfigId,
     kem: Kem,
@@ -289,6 +358,22 @@ impl TryFrom<DivviUpHpkeConfig> for HpkeKeypair {
     }
 }
 
+impl TryFrom<HpkeKeypair> for DivviUpHpkeConfig {
+    type Error = Error;
+
+    fn try_from(value: HpkeKeypair) -> Result<Self, Self::Error> {
+        let config = hpke_dispatch_config_from_hpke_config(value.config())?;
+        Ok(Self {
+            id: *value.config().id(),
+            kem: config.kem,
+            kdf: config.kdf,
+            aead: config.aead,
+            public_key: value.config().public_key().clone(),
+            private_key: value.private_key,
+        })
+    }
+}
+
 #[cfg(feature = "test-util")]
 #[cfg_attr(docsrs, doc(cfg(feature = "test-util")))]
 pub mod test_util {
@@ -328,8 +413,11 @@ pub mod test_util {
 #[cfg(test)]
 mod tests {
     use super::{
-        test_util::{generate_test_hpke_config_and_private_key, SAMPLE_DIVVIUP_HPKE_CONFIG},
-        DivviUpHpkeConfig, HpkeApplicationInfo, Label,
+        test_util::{
+            generate_test_hpke_config_and_private_key,
+            generate_test_hpke_config_and_private_key_with_id, SAMPLE_DIVVIUP_HPKE_CONFIG,
+        },
+        unwrap_private_key, wrap_private_key, DivviUpHpkeConfig, HpkeApplicationInfo, Label,
     };
     use crate::hpke::{open, seal, HpkeKeypair, HpkePrivateKey};
     use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
@@ -640,4 +728,51 @@ mod tests {
             ),
         );
     }
+
+    #[test]
+    fn serialize_divviup_api_hpke_config() {
+        let deserialized: DivviUpHpkeConfig =
+            serde_json::from_str(SAMPLE_DIVVIUP_HPKE_CONFIG).unwrap();
+        let hpke_keypair = HpkeKeypair::try_from(deserialized.clone()).unwrap();
+        let round_tripped = DivviUpHpkeConfig::try_from(hpke_keypair).unwrap();
+        assert_eq!(round_tripped, deserialized);
+        assert_eq!(
+            serde_json::to_value(&round_tripped).unwrap(),
+            serde_json::from_str::<serde_json::Value>(SAMPLE_DIVVIUP_HPKE_CONFIG).unwrap(),
+        );
+    }
+
+    #[test]
+    fn validate_keypair() {
+        let keypair = generate_test_hpke_config_and_private_key();
+        keypair.validate().unwrap();
+
+        let other_keypair = generate_test_hpke_config_and_private_key();
+        HpkeKeypair::new(
+            keypair.config().clone(),
+            other_keypair.private_key().clone(),
+        )
+        .validate()
+        .unwrap_err();
+    }
+
+    #[test]
+    fn wrap_and_unwrap_private_key() {
+        let transport_keypair = generate_test_hpke_config_and_private_key_with_id(1);
+        let keypair = generate_test_hpke_config_and_private_key_with_id(2);
+
+        let wrapped = wrap_private_key(transport_keypair.config(), &keypair).unwrap();
+        assert_eq!(
+            unwrap_private_key(&transport_keypair, keypair.config(), &wrapped).unwrap(),
+            keypair
+        );
+
+        // The wrapped key is bound to the HPKE configuration it belongs to.
+        let other_keypair = generate_test_hpke_config_and_private_key_with_id(2);
+        unwrap_private_key(&transport_keypair, other_keypair.config(), &wrapped).unwrap_err();
+
+        // Only the transport key can unwrap the private key.
+        let other_transport_keypair = generate_test_hpke_config_and_private_key_with_id(1);
+        unwrap_private_key(&other_transport_keypair, keypair.config(), &wrapped).unwrap_err();
+    }
 }
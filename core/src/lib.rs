@@ -37,5 +37,25 @@ impl Runtime for TokioRuntime {
 }
 
 pub mod taskprov {
+    use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+    use janus_messages::TaskId;
+    use ring::digest::{digest, SHA256};
+
     pub const TASKPROV_HEADER: &str = "dap-taskprov";
+
+    /// Computes the ID of the task described by an encoded `TaskConfig`, per
+    /// [draft-wang-ppm-dap-taskprov][1].
+    ///
+    /// [1]: https://www.ietf.org/archive/id/draft-wang-ppm-dap-taskprov-04.html#name-deriving-the-task-id
+    pub fn compute_task_id(encoded_task_config: &[u8]) -> TaskId {
+        // Unwrap safety: SHA-256 digests are always 32 bytes long.
+        TaskId::from(
+            <[u8; TaskId::LEN]>::try_from(digest(&SHA256, encoded_task_config).as_ref()).unwrap(),
+        )
+    }
+
+    /// Encodes a `TaskConfig` as the value of the [`TASKPROV_HEADER`] header.
+    pub fn header_value(encoded_task_config: &[u8]) -> String {
+        URL_SAFE_NO_PAD.encode(encoded_task_config)
+    }
 }
@@ -0,0 +1,9 @@
+-- Identifies the scheduling priority class of a task. Jobs are acquired from tasks in a weighted
+-- round-robin fashion, where the weight of each task is determined by its priority class.
+CREATE TYPE TASK_PRIORITY AS ENUM(
+    'LOW',     -- weight 1
+    'NORMAL',  -- weight 2
+    'HIGH'     -- weight 4
+);
+
+ALTER TABLE tasks ADD COLUMN priority TASK_PRIORITY NOT NULL DEFAULT 'NORMAL';  -- the scheduling priority class of this task's jobs
@@ -0,0 +1,3 @@
+-- The differential privacy mechanism applied to aggregate shares computed for a task, serialized
+-- as JSON in the same fashion as the vdaf column.
+ALTER TABLE tasks ADD COLUMN dp_config JSON NOT NULL DEFAULT '"None"';
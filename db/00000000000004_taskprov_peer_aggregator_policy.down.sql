@@ -0,0 +1 @@
+ALTER TABLE taskprov_peer_aggregators DROP COLUMN policy;
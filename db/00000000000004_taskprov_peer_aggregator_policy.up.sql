@@ -0,0 +1,3 @@
+-- Limits on the tasks that a peer aggregator may provision, serialized as JSON. The empty object
+-- imposes no limits.
+ALTER TABLE taskprov_peer_aggregators ADD COLUMN policy JSON NOT NULL DEFAULT '{}';
@@ -0,0 +1,3 @@
+-- The encoded taskprov TaskConfig from which a task was provisioned, or NULL if the task was not
+-- provisioned via taskprov. The leader forwards this to the helper in the dap-taskprov header.
+ALTER TABLE tasks ADD COLUMN taskprov_task_config BYTEA;
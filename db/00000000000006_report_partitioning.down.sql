@@ -0,0 +1,9 @@
+DO $$
+BEGIN
+    IF EXISTS (SELECT 1 FROM report_partitioning) THEN
+        RAISE EXCEPTION 'client_reports & report_aggregations are partitioned, and must be converted back to regular tables before downgrading';
+    END IF;
+END
+$$;
+DROP TABLE report_partitions;
+DROP TABLE report_partitioning;
@@ -0,0 +1,18 @@
+-- Settings for the optional partitioning of the client_reports & report_aggregations tables by
+-- client timestamp. These tables are partitioned if and only if this table contains a row; see
+-- `Transaction::partition_report_tables` for how partitioning is enabled.
+CREATE TABLE report_partitioning(
+    id              BOOLEAN PRIMARY KEY DEFAULT TRUE CHECK (id),  -- ensures there is at most one row
+    partition_size  BIGINT NOT NULL                               -- the width of the range of client timestamps covered by each partition, in seconds
+);
+
+-- The time-range partitions of the client_reports & report_aggregations tables, if partitioned.
+-- Each row corresponds to one partition of each table, named `client_reports_<name>` &
+-- `report_aggregations_<name>`. Rows whose client timestamp falls outside of every partition
+-- listed here are stored in the `client_reports_default` & `report_aggregations_default`
+-- partitions.
+CREATE TABLE report_partitions(
+    name             TEXT PRIMARY KEY,
+    partition_start  TIMESTAMP NOT NULL,  -- inclusive lower bound on client timestamps; '-infinity' for the partition holding rows written before partitioning was enabled
+    partition_end    TIMESTAMP NOT NULL   -- exclusive upper bound on client timestamps
+);
@@ -0,0 +1 @@
+ALTER TABLE collection_jobs DROP COLUMN collection;
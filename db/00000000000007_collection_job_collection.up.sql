@@ -0,0 +1,4 @@
+-- The encoded Collection message served to the collector for a finished collection job, or NULL if
+-- the collection job has not yet been polled since it finished. Once written, this is served as-is
+-- for every subsequent poll of the collection job.
+ALTER TABLE collection_jobs ADD COLUMN collection BYTEA;
@@ -0,0 +1 @@
+ALTER TABLE collection_jobs DROP COLUMN helper_aggregate_share_hpke_config;
@@ -0,0 +1,3 @@
+-- The encoded collector HPKE config that helper_aggregate_share was encrypted to, for a finished
+-- collection job. This is NULL for collection jobs that finished before it was recorded.
+ALTER TABLE collection_jobs ADD COLUMN helper_aggregate_share_hpke_config BYTEA;
@@ -20,6 +20,7 @@ group "janus" {
     "janus_aggregation_job_creator",
     "janus_aggregation_job_driver",
     "janus_collection_job_driver",
+    "janus_garbage_collector",
     "janus_cli",
   ]
 }
@@ -42,6 +43,7 @@ group "janus_release" {
     "janus_aggregation_job_creator_release",
     "janus_aggregation_job_driver_release",
     "janus_collection_job_driver_release",
+    "janus_garbage_collector_release",
     "janus_cli_release",
   ]
 }
@@ -146,6 +148,27 @@ target "janus_collection_job_driver_release" {
   ]
 }
 
+target "janus_garbage_collector" {
+  args = {
+    BINARY       = "garbage_collector"
+    GIT_REVISION = "${GIT_REVISION}"
+  }
+  cache-from = [
+    "type=gha,scope=main-janus",
+    "type=gha,scope=${GITHUB_BASE_REF}-janus",
+    "type=gha,scope=${GITHUB_REF_NAME}-janus",
+  ]
+  tags = ["janus_garbage_collector:${VERSION}"]
+}
+
+target "janus_garbage_collector_release" {
+  inherits = ["janus_garbage_collector"]
+  tags = [
+    "us-west2-docker.pkg.dev/janus-artifacts/janus/janus_garbage_collector:${VERSION}",
+    "us-west2-docker.pkg.dev/divviup-artifacts-public/janus/janus_garbage_collector:${VERSION}",
+  ]
+}
+
 target "janus_cli" {
   args = {
     BINARY       = "janus_cli"
@@ -1,16 +1,34 @@
 //! Functionality for tests interacting with Janus (<https://github.com/divviup/janus>).
 
 use crate::interop_api;
-use janus_aggregator_core::task::Task;
+use janus_aggregator::{
+    aggregator::{
+        self, aggregation_job_creator::AggregationJobCreator,
+        aggregation_job_driver::AggregationJobDriver, collection_job_driver::CollectionJobDriver,
+        compute_pool::ComputePool, http_handlers::aggregator_handler,
+    },
+    binary_utils::{job_driver::JobDriver, setup_server},
+};
+use janus_aggregator_core::{
+    datastore::{test_util::EphemeralDatastore, Datastore},
+    task::Task,
+    test_util::noop_meter,
+};
+use janus_core::{time::RealClock, TokioRuntime};
 use janus_interop_binaries::{
     log_export_path, test_util::await_http_server, testcontainer::Aggregator,
 };
 use janus_messages::Role;
 use std::{
+    net::{Ipv4Addr, SocketAddr},
     process::{Command, Stdio},
+    sync::Arc,
     thread::panicking,
+    time::Duration,
 };
 use testcontainers::{clients::Cli, Container, RunnableImage};
+use trillium::Headers;
+use trillium_tokio::Stopper;
 
 /// Represents a running Janus test instance in a container.
 pub struct Janus<'a> {
@@ -84,3 +102,131 @@ impl<'a> Drop for Janus<'a> {
         }
     }
 }
+
+/// Represents a Janus aggregator running in the test process, backed by an ephemeral datastore.
+/// This includes the DAP server along with the aggregation job creator and the aggregation and
+/// collection job drivers.
+pub struct JanusInProcess {
+    ephemeral_datastore: EphemeralDatastore,
+    port: u16,
+    stopper: Stopper,
+}
+
+impl JanusInProcess {
+    /// Start a new Janus aggregator listening on the given port of the loopback interface. Any
+    /// state that must be present when the aggregator starts up, such as global HPKE keys or
+    /// taskprov peer aggregators, should already have been written to the datastore.
+    pub async fn new(
+        ephemeral_datastore: EphemeralDatastore,
+        cfg: aggregator::Config,
+        port: u16,
+    ) -> JanusInProcess {
+        let clock = RealClock::default();
+        let meter = noop_meter();
+        let stopper = Stopper::new();
+        let datastore = Arc::new(ephemeral_datastore.datastore(clock).await);
+        let batch_aggregation_shard_count = cfg.batch_aggregation_shard_count;
+
+        // Start the DAP server.
+        let handler = aggregator_handler(Arc::clone(&datastore), clock, &meter, cfg)
+            .await
+            .unwrap();
+        let (_, server) = setup_server(
+            SocketAddr::from((Ipv4Addr::LOCALHOST, port)),
+            Headers::new(),
+            stopper.clone(),
+            handler,
+        )
+        .await
+        .unwrap();
+        tokio::spawn(server);
+
+        // Start the aggregation job creator.
+        let aggregation_job_creator = Arc::new(AggregationJobCreator::new(
+            ephemeral_datastore.datastore(clock).await,
+            meter.clone(),
+            Duration::from_secs(1),
+            Duration::from_secs(1),
+            1,
+            100,
+        ));
+        tokio::spawn(aggregation_job_creator.run(stopper.clone()));
+
+        // Start the aggregation and collection job drivers.
+        let http_client = reqwest::Client::new();
+        let aggregation_job_driver = Arc::new(AggregationJobDriver::new(
+            http_client.clone(),
+            &meter,
+            batch_aggregation_shard_count,
+            ComputePool::new(&meter, Some(1)).unwrap(),
+        ));
+        let aggregation_job_driver = Arc::new(
+            JobDriver::new(
+                clock,
+                TokioRuntime,
+                meter.clone(),
+                stopper.clone(),
+                Duration::from_secs(1),
+                Duration::from_secs(1),
+                10,
+                Duration::from_secs(60),
+                aggregation_job_driver.make_incomplete_job_acquirer_callback(
+                    Arc::clone(&datastore),
+                    Duration::from_secs(600),
+                ),
+                aggregation_job_driver.make_job_stepper_callback(Arc::clone(&datastore), 5),
+            )
+            .unwrap(),
+        );
+        tokio::spawn(aggregation_job_driver.run());
+
+        let collection_job_driver = Arc::new(CollectionJobDriver::new(
+            http_client,
+            &meter,
+            batch_aggregation_shard_count,
+        ));
+        let collection_job_driver = Arc::new(
+            JobDriver::new(
+                clock,
+                TokioRuntime,
+                meter,
+                stopper.clone(),
+                Duration::from_secs(1),
+                Duration::from_secs(1),
+                10,
+                Duration::from_secs(60),
+                collection_job_driver.make_incomplete_job_acquirer_callback(
+                    Arc::clone(&datastore),
+                    Duration::from_secs(600),
+                ),
+                collection_job_driver.make_job_stepper_callback(Arc::clone(&datastore), 5),
+            )
+            .unwrap(),
+        );
+        tokio::spawn(collection_job_driver.run());
+
+        Self {
+            ephemeral_datastore,
+            port,
+            stopper,
+        }
+    }
+
+    /// Returns the port the aggregator is listening on.
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// Returns a handle to the aggregator's datastore.
+    pub async fn datastore(&self) -> Datastore<RealClock> {
+        self.ephemeral_datastore
+            .datastore(RealClock::default())
+            .await
+    }
+}
+
+impl Drop for JanusInProcess {
+    fn drop(&mut self) {
+        self.stopper.stop();
+    }
+}
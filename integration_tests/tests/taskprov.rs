@@ -0,0 +1,203 @@
+use backoff::ExponentialBackoffBuilder;
+use janus_aggregator::{aggregator, config::TaskprovConfig};
+use janus_aggregator_core::{
+    datastore::{
+        models::HpkeKeyState,
+        test_util::{ephemeral_datastore, EphemeralDatastore},
+    },
+    taskprov::test_util::PeerAggregatorBuilder,
+};
+use janus_client::{aggregator_hpke_config, default_http_client, Client, ClientParameters};
+use janus_collector::{Collector, CollectorParameters};
+use janus_core::{
+    hpke::{
+        test_util::{
+            generate_test_hpke_config_and_private_key,
+            generate_test_hpke_config_and_private_key_with_id,
+        },
+        HpkeKeypair,
+    },
+    retries::test_http_request_exponential_backoff,
+    task::AuthenticationToken,
+    test_util::install_test_trace_subscriber,
+    time::{Clock, RealClock, TimeExt},
+};
+use janus_integration_tests::janus::JanusInProcess;
+use janus_messages::{
+    taskprov::{
+        DpConfig, DpMechanism, Query as TaskprovQuery, QueryConfig, TaskConfig, VdafConfig,
+        VdafType,
+    },
+    Duration, Interval, Query, Role,
+};
+use prio::vdaf::prio3::Prio3;
+use rand::random;
+use std::{
+    net::{Ipv4Addr, SocketAddr},
+    time::Duration as StdDuration,
+};
+use tokio::net::TcpListener;
+use url::Url;
+
+async fn select_open_port() -> u16 {
+    let listener = TcpListener::bind(SocketAddr::from((Ipv4Addr::LOCALHOST, 0)))
+        .await
+        .unwrap();
+    listener.local_addr().unwrap().port()
+}
+
+/// Write a global HPKE keypair, which taskprov tasks use, and a taskprov peer aggregator to an
+/// aggregator's datastore.
+async fn provision_aggregator(
+    ephemeral_datastore: &EphemeralDatastore,
+    hpke_keypair: HpkeKeypair,
+    peer_aggregator_builder: PeerAggregatorBuilder,
+) {
+    let datastore = ephemeral_datastore.datastore(RealClock::default()).await;
+    let peer_aggregator = peer_aggregator_builder.build();
+    datastore
+        .run_tx(|tx| {
+            let (hpke_keypair, peer_aggregator) = (hpke_keypair.clone(), peer_aggregator.clone());
+            Box::pin(async move {
+                tx.put_global_hpke_keypair(&hpke_keypair).await?;
+                tx.set_global_hpke_keypair_state(hpke_keypair.config().id(), &HpkeKeyState::Active)
+                    .await?;
+                tx.put_taskprov_peer_aggregator(&peer_aggregator).await
+            })
+        })
+        .await
+        .unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn janus_janus_taskprov() {
+    install_test_trace_subscriber();
+
+    let (leader_port, helper_port) = (select_open_port().await, select_open_port().await);
+    let leader_endpoint = Url::parse(&format!("http://127.0.0.1:{leader_port}/")).unwrap();
+    let helper_endpoint = Url::parse(&format!("http://127.0.0.1:{helper_port}/")).unwrap();
+
+    // Both aggregators share taskprov parameters with each other, and the leader additionally
+    // shares the collector's credentials.
+    let collector_keypair = generate_test_hpke_config_and_private_key();
+    let collector_auth_token: AuthenticationToken = random();
+    let peer_aggregator_builder = PeerAggregatorBuilder::new()
+        .with_verify_key_init(random())
+        .with_collector_hpke_config(collector_keypair.config().clone())
+        .with_tolerable_clock_skew(Duration::from_seconds(60))
+        .with_aggregator_auth_tokens(Vec::from([random()]));
+
+    let leader_datastore = ephemeral_datastore().await;
+    provision_aggregator(
+        &leader_datastore,
+        generate_test_hpke_config_and_private_key_with_id(1),
+        peer_aggregator_builder
+            .clone()
+            .with_endpoint(helper_endpoint.clone())
+            .with_role(Role::Helper)
+            .with_collector_auth_tokens(Vec::from([collector_auth_token.clone()])),
+    )
+    .await;
+    let helper_datastore = ephemeral_datastore().await;
+    provision_aggregator(
+        &helper_datastore,
+        generate_test_hpke_config_and_private_key_with_id(2),
+        peer_aggregator_builder
+            .with_endpoint(leader_endpoint.clone())
+            .with_role(Role::Leader)
+            .with_collector_auth_tokens(Vec::new()),
+    )
+    .await;
+
+    let cfg = || aggregator::Config {
+        taskprov_config: TaskprovConfig {
+            enabled: true,
+            opt_in_on_upload: true,
+        },
+        ..Default::default()
+    };
+    let _leader = JanusInProcess::new(leader_datastore, cfg(), leader_port).await;
+    let _helper = JanusInProcess::new(helper_datastore, cfg(), helper_port).await;
+
+    // Neither aggregator knows about the task until the client and collector present it.
+    let clock = RealClock::default();
+    let time_precision = Duration::from_seconds(3600);
+    let task_config = TaskConfig::new(
+        Vec::from("janus_janus_taskprov".as_bytes()),
+        Vec::from([
+            leader_endpoint.as_str().as_bytes().try_into().unwrap(),
+            helper_endpoint.as_str().as_bytes().try_into().unwrap(),
+        ]),
+        QueryConfig::new(time_precision, 1, 4, TaskprovQuery::TimeInterval),
+        clock.now().add(&Duration::from_seconds(86400)).unwrap(),
+        VdafConfig::new(DpConfig::new(DpMechanism::None), VdafType::Prio3Count).unwrap(),
+    )
+    .unwrap();
+
+    let http_client = default_http_client().unwrap();
+    let client_parameters = ClientParameters::new_taskprov_with_backoff(
+        &task_config,
+        test_http_request_exponential_backoff(),
+    )
+    .unwrap();
+    let task_id = *client_parameters.task_id();
+    let leader_hpke_config =
+        aggregator_hpke_config(&client_parameters, &Role::Leader, &task_id, &http_client)
+            .await
+            .unwrap();
+    let helper_hpke_config =
+        aggregator_hpke_config(&client_parameters, &Role::Helper, &task_id, &http_client)
+            .await
+            .unwrap();
+    let vdaf = Prio3::new_count(2).unwrap();
+    let client = Client::new(
+        client_parameters,
+        vdaf.clone(),
+        clock,
+        &http_client,
+        leader_hpke_config,
+        helper_hpke_config,
+    );
+
+    let before_timestamp = clock.now();
+    for measurement in [1, 0, 1, 1] {
+        client.upload(&measurement).await.unwrap();
+    }
+
+    let collector = Collector::new(
+        CollectorParameters::new(
+            task_id,
+            leader_endpoint,
+            collector_auth_token,
+            collector_keypair.config().clone(),
+            collector_keypair.private_key().clone(),
+        )
+        .with_taskprov_task_config(&task_config)
+        .with_http_request_backoff(test_http_request_exponential_backoff())
+        .with_collect_poll_backoff(
+            ExponentialBackoffBuilder::new()
+                .with_initial_interval(StdDuration::from_millis(500))
+                .with_max_interval(StdDuration::from_millis(500))
+                .with_max_elapsed_time(Some(StdDuration::from_secs(60)))
+                .build(),
+        ),
+        vdaf,
+        janus_collector::default_http_client().unwrap(),
+    );
+    let batch_interval = Interval::new(
+        before_timestamp
+            .to_batch_interval_start(&time_precision)
+            .unwrap(),
+        // Use two time precisions as the interval duration in order to avoid a race condition if
+        // this test happens to run very close to the end of a batch window.
+        Duration::from_seconds(2 * time_precision.as_seconds()),
+    )
+    .unwrap();
+    let collection = collector
+        .collect(Query::new_time_interval(batch_interval), &())
+        .await
+        .unwrap();
+
+    assert_eq!(collection.report_count(), 4);
+    assert_eq!(collection.aggregate_result(), &3);
+}
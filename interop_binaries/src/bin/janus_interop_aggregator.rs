@@ -8,7 +8,7 @@ use janus_aggregator::{
 };
 use janus_aggregator_core::{
     datastore::Datastore,
-    task::{self, Task},
+    task::{self, DpConfig, Task, TaskPriority},
     SecretBytes,
 };
 use janus_core::{task::AuthenticationToken, time::RealClock};
@@ -102,6 +102,8 @@ async fn handle_add_task(
         // We can be strict about clock skew since this executable is only intended for use with
         // other aggregators running on the same host.
         Duration::from_seconds(1),
+        TaskPriority::Normal,
+        DpConfig::None,
         collector_hpke_config,
         Vec::from([leader_authentication_token]),
         collector_authentication_tokens,
@@ -0,0 +1,266 @@
+//! Messages used by Janus' batch discovery endpoint, which lets the collector of a fixed-size task
+//! list the batches which may be collected. This endpoint is a Janus extension, and is not part of
+//! DAP.
+
+use crate::{BatchId, Interval};
+use prio::codec::{decode_u32_items, encode_u32_items, CodecError, Decode, Encode};
+use serde::{Deserialize, Serialize};
+use std::io::Cursor;
+
+/// A batch of a fixed-size task which has enough reports to be collected.
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CollectableBatch {
+    batch_id: BatchId,
+    report_count: u64,
+    interval: Interval,
+    collected: bool,
+}
+
+impl CollectableBatch {
+    /// Constructs a new collectable batch.
+    pub fn new(batch_id: BatchId, report_count: u64, interval: Interval, collected: bool) -> Self {
+        Self {
+            batch_id,
+            report_count,
+            interval,
+            collected,
+        }
+    }
+
+    /// Gets the ID of this batch.
+    pub fn batch_id(&self) -> &BatchId {
+        &self.batch_id
+    }
+
+    /// Gets the number of reports in this batch.
+    pub fn report_count(&self) -> u64 {
+        self.report_count
+    }
+
+    /// Gets the interval spanned by the reports in this batch, aligned to the task's time
+    /// precision.
+    pub fn interval(&self) -> &Interval {
+        &self.interval
+    }
+
+    /// Returns true if this batch has already been collected, i.e. a collection job over it has been
+    /// polled or deleted. Collected batches are no longer returned by current-batch queries, and
+    /// may only be collected again by their batch ID.
+    pub fn collected(&self) -> bool {
+        self.collected
+    }
+}
+
+impl Encode for CollectableBatch {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        self.batch_id.encode(bytes);
+        self.report_count.encode(bytes);
+        self.interval.encode(bytes);
+        u8::from(self.collected).encode(bytes);
+    }
+
+    fn encoded_len(&self) -> Option<usize> {
+        Some(
+            self.batch_id.encoded_len()?
+                + self.report_count.encoded_len()?
+                + self.interval.encoded_len()?
+                + 1,
+        )
+    }
+}
+
+impl Decode for CollectableBatch {
+    fn decode(bytes: &mut Cursor<&[u8]>) -> Result<Self, CodecError> {
+        let batch_id = BatchId::decode(bytes)?;
+        let report_count = u64::decode(bytes)?;
+        let interval = Interval::decode(bytes)?;
+        let collected = match u8::decode(bytes)? {
+            0 => false,
+            1 => true,
+            _ => return Err(CodecError::UnexpectedValue),
+        };
+
+        Ok(Self {
+            batch_id,
+            report_count,
+            interval,
+            collected,
+        })
+    }
+}
+
+/// A page of the collectable batches of a fixed-size task, ordered by batch ID, served by the leader
+/// in response to a GET request to `tasks/{task-id}/batches`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CollectableBatchList {
+    batches: Vec<CollectableBatch>,
+    pagination_token: Option<BatchId>,
+}
+
+impl CollectableBatchList {
+    /// The media type associated with this message.
+    pub const MEDIA_TYPE: &'static str = "application/x-janus-collectable-batch-list";
+
+    /// Constructs a collectable batch list.
+    pub fn new(batches: Vec<CollectableBatch>, pagination_token: Option<BatchId>) -> Self {
+        Self {
+            batches,
+            pagination_token,
+        }
+    }
+
+    /// Gets the batches in this list.
+    pub fn batches(&self) -> &[CollectableBatch] {
+        &self.batches
+    }
+
+    /// Gets the token from which to list the next page of batches, passed as the
+    /// `pagination_token` query parameter, or `None` if this is the last page. Later pages list
+    /// only batches whose IDs sort after the token.
+    pub fn pagination_token(&self) -> Option<&BatchId> {
+        self.pagination_token.as_ref()
+    }
+}
+
+impl Encode for CollectableBatchList {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        encode_u32_items(bytes, &(), &self.batches);
+        match &self.pagination_token {
+            Some(pagination_token) => {
+                1u8.encode(bytes);
+                pagination_token.encode(bytes);
+            }
+            None => 0u8.encode(bytes),
+        }
+    }
+
+    fn encoded_len(&self) -> Option<usize> {
+        let mut length = 4;
+        for batch in self.batches.iter() {
+            length += batch.encoded_len()?;
+        }
+        length += 1;
+        if let Some(pagination_token) = &self.pagination_token {
+            length += pagination_token.encoded_len()?;
+        }
+        Some(length)
+    }
+}
+
+impl Decode for CollectableBatchList {
+    fn decode(bytes: &mut Cursor<&[u8]>) -> Result<Self, CodecError> {
+        let batches = decode_u32_items(&(), bytes)?;
+        let pagination_token = match u8::decode(bytes)? {
+            0 => None,
+            1 => Some(BatchId::decode(bytes)?),
+            _ => return Err(CodecError::UnexpectedValue),
+        };
+
+        Ok(Self {
+            batches,
+            pagination_token,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{roundtrip_encoding, Duration, Time};
+    use assert_matches::assert_matches;
+
+    #[test]
+    fn roundtrip_collectable_batch_list() {
+        roundtrip_encoding(&[
+            (
+                CollectableBatchList::new(Vec::new(), None),
+                concat!(
+                    "00000000", // length
+                    "00",       // pagination_token
+                ),
+            ),
+            (
+                CollectableBatchList::new(
+                    Vec::from([
+                        CollectableBatch::new(
+                            BatchId::from([1u8; BatchId::LEN]),
+                            100,
+                            Interval::new(
+                                Time::from_seconds_since_epoch(54321),
+                                Duration::from_seconds(3600),
+                            )
+                            .unwrap(),
+                            false,
+                        ),
+                        CollectableBatch::new(
+                            BatchId::from([2u8; BatchId::LEN]),
+                            23,
+                            Interval::new(
+                                Time::from_seconds_since_epoch(12345),
+                                Duration::from_seconds(60),
+                            )
+                            .unwrap(),
+                            true,
+                        ),
+                    ]),
+                    Some(BatchId::from([2u8; BatchId::LEN])),
+                ),
+                concat!(
+                    "00000072", // length
+                    concat!(
+                        // batch
+                        "0101010101010101010101010101010101010101010101010101010101010101", // batch_id
+                        "0000000000000064", // report_count
+                        concat!(
+                            // interval
+                            "000000000000D431", // start
+                            "0000000000000E10", // duration
+                        ),
+                        "00", // collected
+                    ),
+                    concat!(
+                        // batch
+                        "0202020202020202020202020202020202020202020202020202020202020202", // batch_id
+                        "0000000000000017", // report_count
+                        concat!(
+                            // interval
+                            "0000000000003039", // start
+                            "000000000000003C", // duration
+                        ),
+                        "01", // collected
+                    ),
+                    concat!(
+                        // pagination_token
+                        "01", // present
+                        "0202020202020202020202020202020202020202020202020202020202020202", // batch_id
+                    ),
+                ),
+            ),
+        ])
+    }
+
+    #[test]
+    fn decode_collectable_batch_invalid_collected() {
+        assert_matches!(
+            CollectableBatch::get_decoded(
+                &hex::decode(concat!(
+                    "0101010101010101010101010101010101010101010101010101010101010101",
+                    "0000000000000064",
+                    "000000000000D431",
+                    "0000000000000E10",
+                    "02",
+                ))
+                .unwrap()
+            ),
+            Err(CodecError::UnexpectedValue)
+        );
+    }
+
+    #[test]
+    fn decode_collectable_batch_list_invalid_pagination_token() {
+        assert_matches!(
+            CollectableBatchList::get_decoded(&hex::decode(concat!("00000000", "02")).unwrap()),
+            Err(CodecError::UnexpectedValue)
+        );
+    }
+}
@@ -25,6 +25,7 @@ use std::{
     str::FromStr,
 };
 
+pub mod batch_discovery;
 pub mod problem_type;
 pub mod taskprov;
 pub use prio::codec;
@@ -43,6 +44,34 @@ pub enum Error {
     UnsupportedAlgorithmIdentifier(&'static str, u16),
 }
 
+/// Serde helpers which serialize opaque byte strings in protocol messages as base64url-encoded
+/// strings, rather than as arrays of numbers.
+mod base64url_bytes {
+    use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+    use serde::{de, Deserialize, Deserializer, Serializer};
+    use std::fmt::Display;
+
+    pub(crate) fn serialize<S, T>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        T: AsRef<[u8]>,
+    {
+        serializer.serialize_str(&URL_SAFE_NO_PAD.encode(value))
+    }
+
+    pub(crate) fn deserialize<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: for<'a> TryFrom<&'a [u8]>,
+        for<'a> <T as TryFrom<&'a [u8]>>::Error: Display,
+    {
+        let decoded = URL_SAFE_NO_PAD
+            .decode(String::deserialize(deserializer)?)
+            .map_err(|_| de::Error::custom("invalid base64url value"))?;
+        T::try_from(&decoded).map_err(de::Error::custom)
+    }
+}
+
 /// Wire-representation of an ASCII-encoded URL with minimum length 1 and maximum
 /// length 2^16 - 1.
 #[derive(Clone, PartialEq, Eq)]
@@ -110,6 +139,26 @@ impl TryFrom<&[u8]> for Url {
     }
 }
 
+impl Serialize for Url {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        // Unwrap safety: this type can't be constructed without being validated as consisting
+        // only of ASCII.
+        serializer.serialize_str(str::from_utf8(&self.0).unwrap())
+    }
+}
+
+impl<'de> Deserialize<'de> for Url {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        Url::try_from(String::deserialize(deserializer)?.as_bytes()).map_err(de::Error::custom)
+    }
+}
+
 impl TryFrom<&Url> for url::Url {
     type Error = url::ParseError;
 
@@ -203,7 +252,8 @@ impl Decode for Time {
 
 /// DAP protocol message representing a half-open interval of time with a resolution of seconds;
 /// the start of the interval is included while the end of the interval is excluded.
-#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "SerializedInterval")]
 pub struct Interval {
     /// The start of the interval.
     start: Time,
@@ -211,6 +261,23 @@ pub struct Interval {
     duration: Duration,
 }
 
+/// Unvalidated serialized form of [`Interval`], so that deserialization goes through
+/// [`Interval::new`].
+#[derive(Deserialize)]
+#[serde(rename = "Interval")]
+struct SerializedInterval {
+    start: Time,
+    duration: Duration,
+}
+
+impl TryFrom<SerializedInterval> for Interval {
+    type Error = Error;
+
+    fn try_from(value: SerializedInterval) -> Result<Self, Self::Error> {
+        Self::new(value.start, value.duration)
+    }
+}
+
 impl Interval {
     pub const EMPTY: Self = Self {
         start: Time::from_seconds_since_epoch(0),
@@ -312,6 +379,37 @@ impl Display for BatchId {
     }
 }
 
+impl FromStr for BatchId {
+    type Err = Box<dyn Debug>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = URL_SAFE_NO_PAD
+            .decode(s)
+            .map_err(|err| Box::new(err) as Box<dyn Debug>)?;
+        Self::try_from(bytes.as_ref()).map_err(|err| Box::new(err) as Box<dyn Debug>)
+    }
+}
+
+/// This customized implementation serializes a [`BatchId`] as a base64url-encoded string, instead of
+/// as a byte array.
+impl Serialize for BatchId {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        base64url_bytes::serialize(&self.0, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for BatchId {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        base64url_bytes::deserialize(deserializer)
+    }
+}
+
 impl Encode for BatchId {
     fn encode(&self, bytes: &mut Vec<u8>) {
         bytes.extend_from_slice(&self.0)
@@ -383,6 +481,26 @@ impl Display for ReportId {
     }
 }
 
+/// This customized implementation serializes a [`ReportId`] as a base64url-encoded string, instead of
+/// as a byte array.
+impl Serialize for ReportId {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        base64url_bytes::serialize(&self.0, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ReportId {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        base64url_bytes::deserialize(deserializer)
+    }
+}
+
 impl Encode for ReportId {
     fn encode(&self, bytes: &mut Vec<u8>) {
         bytes.extend_from_slice(&self.0)
@@ -460,6 +578,26 @@ impl Display for ReportIdChecksum {
     }
 }
 
+/// This customized implementation serializes a [`ReportIdChecksum`] as a base64url-encoded string, instead of
+/// as a byte array.
+impl Serialize for ReportIdChecksum {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        base64url_bytes::serialize(&self.0, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ReportIdChecksum {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        base64url_bytes::deserialize(deserializer)
+    }
+}
+
 impl Encode for ReportIdChecksum {
     fn encode(&self, bytes: &mut Vec<u8>) {
         bytes.extend_from_slice(&self.0)
@@ -830,9 +968,10 @@ impl Decode for HpkeAeadId {
 }
 
 /// DAP protocol message representing an arbitrary extension included in a client report.
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Extension {
     extension_type: ExtensionType,
+    #[serde(with = "base64url_bytes")]
     extension_data: Vec<u8>,
 }
 
@@ -881,7 +1020,7 @@ impl Decode for Extension {
 }
 
 /// DAP protocol message representing the type of an extension included in a client report.
-#[derive(Clone, Copy, Debug, Hash, Eq, PartialEq, TryFromPrimitive)]
+#[derive(Clone, Copy, Debug, Hash, Eq, PartialEq, TryFromPrimitive, Serialize, Deserialize)]
 #[repr(u16)]
 pub enum ExtensionType {
     Tbd = 0,
@@ -907,16 +1046,18 @@ impl Decode for ExtensionType {
 }
 
 /// DAP protocol message representing an HPKE ciphertext.
-#[derive(Clone, Derivative, Eq, PartialEq)]
+#[derive(Clone, Derivative, Eq, PartialEq, Serialize, Deserialize)]
 #[derivative(Debug)]
 pub struct HpkeCiphertext {
     /// An identifier of the HPKE configuration used to seal the message.
     config_id: HpkeConfigId,
     /// An encapsulated HPKE key.
     #[derivative(Debug = "ignore")]
+    #[serde(with = "base64url_bytes")]
     encapsulated_key: Vec<u8>,
     /// An HPKE ciphertext.
     #[derivative(Debug = "ignore")]
+    #[serde(with = "base64url_bytes")]
     payload: Vec<u8>,
 }
 
@@ -1164,7 +1305,7 @@ impl Decode for HpkeConfig {
 }
 
 /// DAP protocol message representing a list of HPKE configurations.
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct HpkeConfigList(Vec<HpkeConfig>);
 
 impl HpkeConfigList {
@@ -1202,7 +1343,7 @@ impl Decode for HpkeConfigList {
 }
 
 /// DAP protocol message representing client report metadata.
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ReportMetadata {
     report_id: ReportId,
     time: Time,
@@ -1246,9 +1387,10 @@ impl Decode for ReportMetadata {
 }
 
 /// DAP protocol message representing the plaintext of an input share.
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct PlaintextInputShare {
     extensions: Vec<Extension>,
+    #[serde(with = "base64url_bytes")]
     payload: Vec<u8>,
 }
 
@@ -1302,9 +1444,10 @@ impl Decode for PlaintextInputShare {
 }
 
 /// DAP protocol message representing a client report.
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Report {
     metadata: ReportMetadata,
+    #[serde(with = "base64url_bytes")]
     public_share: Vec<u8>,
     leader_encrypted_input_share: HpkeCiphertext,
     helper_encrypted_input_share: HpkeCiphertext,
@@ -1385,7 +1528,7 @@ impl Decode for Report {
 }
 
 /// DAP protocol message representing a fixed-size query.
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum FixedSizeQuery {
     ByBatchId { batch_id: BatchId },
     CurrentBatch,
@@ -1428,7 +1571,8 @@ impl Decode for FixedSizeQuery {
 
 /// Represents a query for a specific batch identifier, received from a Collector as part of the
 /// collection flow.
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct Query<Q: QueryType> {
     query_body: Q::QueryBody,
 }
@@ -1499,11 +1643,13 @@ impl<Q: QueryType> Decode for Query<Q> {
 
 /// DAP protocol message representing a request from the collector to the leader to provide
 /// aggregate shares for a given batch.
-#[derive(Clone, Derivative, PartialEq, Eq)]
+#[derive(Clone, Derivative, PartialEq, Eq, Serialize, Deserialize)]
 #[derivative(Debug)]
+#[serde(bound = "")]
 pub struct CollectionReq<Q: QueryType> {
     query: Query<Q>,
     #[derivative(Debug = "ignore")]
+    #[serde(with = "base64url_bytes")]
     aggregation_parameter: Vec<u8>,
 }
 
@@ -1555,7 +1701,8 @@ impl<Q: QueryType> Decode for CollectionReq<Q> {
 
 /// DAP protocol message representing a partial batch selector, identifying a batch of interest in
 /// cases where some query types can infer the selector.
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct PartialBatchSelector<Q: QueryType> {
     batch_identifier: Q::PartialBatchIdentifier,
 }
@@ -1670,6 +1817,26 @@ impl Display for CollectionJobId {
     }
 }
 
+/// This customized implementation serializes a [`CollectionJobId`] as a base64url-encoded string, instead of
+/// as a byte array.
+impl Serialize for CollectionJobId {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        base64url_bytes::serialize(&self.0, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for CollectionJobId {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        base64url_bytes::deserialize(deserializer)
+    }
+}
+
 impl Distribution<CollectionJobId> for Standard {
     fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> CollectionJobId {
         CollectionJobId(rng.gen())
@@ -1678,7 +1845,8 @@ impl Distribution<CollectionJobId> for Standard {
 
 /// DAP protocol message representing a leader's response to the collector's request to provide
 /// aggregate shares for a given query.
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct Collection<Q: QueryType> {
     partial_batch_selector: PartialBatchSelector<Q>,
     report_count: u64,
@@ -1773,10 +1941,11 @@ impl<Q: QueryType> Decode for Collection<Q> {
 }
 
 /// DAP message representing the additional associated data for an input share encryption operation.
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct InputShareAad {
     task_id: TaskId,
     metadata: ReportMetadata,
+    #[serde(with = "base64url_bytes")]
     public_share: Vec<u8>,
 }
 
@@ -1839,7 +2008,8 @@ impl Decode for InputShareAad {
 
 /// DAP message representing the additional associated data for an aggregate share encryption
 /// operation.
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct AggregateShareAad<Q: QueryType> {
     task_id: TaskId,
     batch_selector: BatchSelector<Q>,
@@ -1895,7 +2065,7 @@ pub mod query_type {
     use anyhow::anyhow;
     use num_enum::TryFromPrimitive;
     use prio::codec::{CodecError, Decode, Encode};
-    use serde::{Deserialize, Serialize};
+    use serde::{de::DeserializeOwned, Deserialize, Serialize};
     use std::{
         fmt::{Debug, Display},
         hash::Hash,
@@ -1917,6 +2087,8 @@ pub mod query_type {
             + Eq
             + Encode
             + Decode
+            + Serialize
+            + DeserializeOwned
             + Send
             + Sync;
 
@@ -1929,11 +2101,22 @@ pub mod query_type {
             + Eq
             + Encode
             + Decode
+            + Serialize
+            + DeserializeOwned
             + Send
             + Sync;
 
         /// The type of the body of a [`Query`] for this query type.
-        type QueryBody: Debug + Clone + PartialEq + Eq + Encode + Decode + Send + Sync;
+        type QueryBody: Debug
+            + Clone
+            + PartialEq
+            + Eq
+            + Encode
+            + Decode
+            + Serialize
+            + DeserializeOwned
+            + Send
+            + Sync;
 
         /// Computes the `PartialBatchIdentifier` corresponding to the given
         /// `BatchIdentifier`.
@@ -2041,11 +2224,12 @@ pub mod query_type {
 }
 
 /// DAP protocol message representing one aggregator's share of a single client report.
-#[derive(Derivative, Clone, PartialEq, Eq)]
+#[derive(Derivative, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[derivative(Debug)]
 pub struct ReportShare {
     metadata: ReportMetadata,
     #[derivative(Debug = "ignore")]
+    #[serde(with = "base64url_bytes")]
     public_share: Vec<u8>,
     encrypted_input_share: HpkeCiphertext,
 }
@@ -2112,7 +2296,7 @@ impl Decode for ReportShare {
 }
 
 /// DAP protocol message representing the result of a preparation step in a VDAF evaluation.
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct PrepareStep {
     report_id: ReportId,
     result: PrepareStepResult,
@@ -2157,10 +2341,15 @@ impl Decode for PrepareStep {
 
 /// DAP protocol message representing result-type-specific data associated with a preparation step
 /// in a VDAF evaluation. Included in a PrepareStep message.
-#[derive(Clone, Derivative, PartialEq, Eq)]
+#[derive(Clone, Derivative, PartialEq, Eq, Serialize, Deserialize)]
 #[derivative(Debug)]
 pub enum PrepareStepResult {
-    Continued(#[derivative(Debug = "ignore")] Vec<u8>), // content is a serialized preparation message
+    // content is a serialized preparation message
+    Continued(
+        #[derivative(Debug = "ignore")]
+        #[serde(with = "base64url_bytes")]
+        Vec<u8>,
+    ),
     Finished,
     Failed(ReportShareError),
 }
@@ -2204,7 +2393,19 @@ impl Decode for PrepareStepResult {
 }
 
 /// DAP protocol message representing an error while preparing a report share for aggregation.
-#[derive(Clone, Copy, Debug, PartialEq, Eq, TryFromPrimitive)]
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    TryFromPrimitive,
+    Serialize,
+    Deserialize,
+)]
 #[repr(u8)]
 pub enum ReportShareError {
     BatchCollected = 0,
@@ -2295,6 +2496,26 @@ impl Display for AggregationJobId {
     }
 }
 
+/// This customized implementation serializes a [`AggregationJobId`] as a base64url-encoded string, instead of
+/// as a byte array.
+impl Serialize for AggregationJobId {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        base64url_bytes::serialize(&self.0, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for AggregationJobId {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        base64url_bytes::deserialize(deserializer)
+    }
+}
+
 impl Distribution<AggregationJobId> for Standard {
     fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> AggregationJobId {
         AggregationJobId(rng.gen())
@@ -2303,10 +2524,12 @@ impl Distribution<AggregationJobId> for Standard {
 
 /// DAP protocol message representing an aggregation job initialization request from leader to
 /// helper.
-#[derive(Clone, Derivative, PartialEq, Eq)]
+#[derive(Clone, Derivative, PartialEq, Eq, Serialize, Deserialize)]
 #[derivative(Debug)]
+#[serde(bound = "")]
 pub struct AggregationJobInitializeReq<Q: QueryType> {
     #[derivative(Debug = "ignore")]
+    #[serde(with = "base64url_bytes")]
     aggregation_parameter: Vec<u8>,
     partial_batch_selector: PartialBatchSelector<Q>,
     report_shares: Vec<ReportShare>,
@@ -2435,7 +2658,7 @@ impl TryFrom<i32> for AggregationJobRound {
 }
 
 /// DAP protocol message representing a request to continue an aggregation job.
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct AggregationJobContinueReq {
     round: AggregationJobRound,
     prepare_steps: Vec<PrepareStep>,
@@ -2490,7 +2713,7 @@ impl Decode for AggregationJobContinueReq {
 
 /// DAP protocol message representing the response to an aggregation job initialization or
 /// continuation request.
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct AggregationJobResp {
     prepare_steps: Vec<PrepareStep>,
 }
@@ -2532,7 +2755,8 @@ impl Decode for AggregationJobResp {
 }
 
 /// DAP protocol message identifying a batch of interest.
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct BatchSelector<Q: QueryType> {
     batch_identifier: Q::BatchIdentifier,
 }
@@ -2603,11 +2827,13 @@ impl<Q: QueryType> Decode for BatchSelector<Q> {
 
 /// DAP protocol message representing a request from the leader to a helper to provide an
 /// encrypted aggregate of its share of data for a given batch interval.
-#[derive(Clone, Derivative, PartialEq, Eq)]
+#[derive(Clone, Derivative, PartialEq, Eq, Serialize, Deserialize)]
 #[derivative(Debug)]
+#[serde(bound = "")]
 pub struct AggregateShareReq<Q: QueryType> {
     batch_selector: BatchSelector<Q>,
     #[derivative(Debug = "ignore")]
+    #[serde(with = "base64url_bytes")]
     aggregation_parameter: Vec<u8>,
     report_count: u64,
     checksum: ReportIdChecksum,
@@ -2690,7 +2916,7 @@ impl<Q: QueryType> Decode for AggregateShareReq<Q> {
 
 /// DAP protocol message representing a helper's response to the leader's request to provide an
 /// encrypted aggregate of its share of data for a given batch interval.
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct AggregateShare {
     encrypted_aggregate_share: HpkeCiphertext,
 }
@@ -4563,4 +4789,113 @@ mod tests {
         );
         assert_de_tokens_error::<HpkePublicKey>(&[Token::Str("/AAAA")], "invalid base64url value");
     }
+
+    #[test]
+    fn report_id_serde() {
+        assert_tokens(
+            &ReportId::from([1; 16]),
+            &[Token::Str("AQEBAQEBAQEBAQEBAQEBAQ")],
+        );
+        assert_de_tokens_error::<ReportId>(&[Token::Str("/AAAA")], "invalid base64url value");
+        assert_de_tokens_error::<ReportId>(
+            &[Token::Str("AQEB")],
+            "byte slice has incorrect length for ReportId",
+        );
+    }
+
+    #[test]
+    fn url_serde() {
+        assert_tokens(
+            &Url::try_from("https://example.com/".as_bytes()).unwrap(),
+            &[Token::Str("https://example.com/")],
+        );
+        assert_de_tokens_error::<Url>(
+            &[Token::Str("")],
+            "other error: Url must be at least 1 byte long",
+        );
+    }
+
+    #[test]
+    fn interval_serde() {
+        assert_tokens(
+            &Interval::new(
+                Time::from_seconds_since_epoch(1000),
+                Duration::from_seconds(100),
+            )
+            .unwrap(),
+            &[
+                Token::Struct {
+                    name: "Interval",
+                    len: 2,
+                },
+                Token::Str("start"),
+                Token::NewtypeStruct { name: "Time" },
+                Token::U64(1000),
+                Token::Str("duration"),
+                Token::NewtypeStruct { name: "Duration" },
+                Token::U64(100),
+                Token::StructEnd,
+            ],
+        );
+        assert_matches!(
+            serde_json::from_str::<Interval>(&format!(
+                r#"{{"start":{},"duration":1}}"#,
+                u64::MAX
+            )),
+            Err(e) => assert!(e.to_string().contains("duration overflows time"))
+        );
+    }
+
+    #[test]
+    fn prepare_step_serde() {
+        assert_tokens(
+            &PrepareStep {
+                report_id: ReportId::from([0; 16]),
+                result: PrepareStepResult::Continued(Vec::from([1, 2, 3, 4])),
+            },
+            &[
+                Token::Struct {
+                    name: "PrepareStep",
+                    len: 2,
+                },
+                Token::Str("report_id"),
+                Token::Str("AAAAAAAAAAAAAAAAAAAAAA"),
+                Token::Str("result"),
+                Token::NewtypeVariant {
+                    name: "PrepareStepResult",
+                    variant: "Continued",
+                },
+                Token::Str("AQIDBA"),
+                Token::StructEnd,
+            ],
+        );
+    }
+
+    #[test]
+    fn partial_batch_selector_serde() {
+        assert_tokens(
+            &PartialBatchSelector::new_time_interval(),
+            &[
+                Token::Struct {
+                    name: "PartialBatchSelector",
+                    len: 1,
+                },
+                Token::Str("batch_identifier"),
+                Token::Unit,
+                Token::StructEnd,
+            ],
+        );
+        assert_tokens(
+            &PartialBatchSelector::new_fixed_size(BatchId::from([0; 32])),
+            &[
+                Token::Struct {
+                    name: "PartialBatchSelector",
+                    len: 1,
+                },
+                Token::Str("batch_identifier"),
+                Token::Str("AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA"),
+                Token::StructEnd,
+            ],
+        );
+    }
 }
@@ -9,13 +9,16 @@ use prio::codec::{
     decode_u16_items, decode_u24_items, decode_u8_items, encode_u16_items, encode_u24_items,
     encode_u8_items, CodecError, Decode, Encode,
 };
+use serde::{Deserialize, Serialize};
 use std::{fmt::Debug, io::Cursor};
 
 /// Defines all parameters necessary to configure an aggregator with a new task.
 /// Provided by taskprov participants in all requests incident to task execution.
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "SerializedTaskConfig")]
 pub struct TaskConfig {
     /// Opaque info specific for a task.
+    #[serde(with = "crate::base64url_bytes")]
     task_info: Vec<u8>,
     /// List of URLs where the aggregator's API endpoints can be found.
     aggregator_endpoints: Vec<Url>,
@@ -27,6 +30,33 @@ pub struct TaskConfig {
     vdaf_config: VdafConfig,
 }
 
+/// Unvalidated serialized form of [`TaskConfig`], so that deserialization goes through
+/// [`TaskConfig::new`].
+#[derive(Deserialize)]
+#[serde(rename = "TaskConfig")]
+struct SerializedTaskConfig {
+    #[serde(with = "crate::base64url_bytes")]
+    task_info: Vec<u8>,
+    aggregator_endpoints: Vec<Url>,
+    query_config: QueryConfig,
+    task_expiration: Time,
+    vdaf_config: VdafConfig,
+}
+
+impl TryFrom<SerializedTaskConfig> for TaskConfig {
+    type Error = Error;
+
+    fn try_from(value: SerializedTaskConfig) -> Result<Self, Self::Error> {
+        Self::new(
+            value.task_info,
+            value.aggregator_endpoints,
+            value.query_config,
+            value.task_expiration,
+            value.vdaf_config,
+        )
+    }
+}
+
 impl TaskConfig {
     pub fn new(
         task_info: Vec<u8>,
@@ -133,7 +163,7 @@ impl Decode for TaskConfig {
 /// in DAP[1].
 ///
 /// [1]: https://www.ietf.org/archive/id/draft-ietf-ppm-dap-05.html#name-queries
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct QueryConfig {
     /// Used by clients to truncate report timestamps.
     time_precision: Duration,
@@ -238,7 +268,7 @@ impl Decode for QueryConfig {
 ///   - The parent mod decoding logic assumes that the query type is encoded
 ///     directly adjacent to its associated parameters. This is not the case
 ///     in taskprov.
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[non_exhaustive]
 pub enum Query {
     Reserved,
@@ -253,12 +283,30 @@ impl Query {
 }
 
 /// Describes all VDAF parameters.
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "SerializedVdafConfig")]
 pub struct VdafConfig {
     dp_config: DpConfig,
     vdaf_type: VdafType,
 }
 
+/// Unvalidated serialized form of [`VdafConfig`], so that deserialization goes through
+/// [`VdafConfig::new`].
+#[derive(Deserialize)]
+#[serde(rename = "VdafConfig")]
+struct SerializedVdafConfig {
+    dp_config: DpConfig,
+    vdaf_type: VdafType,
+}
+
+impl TryFrom<SerializedVdafConfig> for VdafConfig {
+    type Error = Error;
+
+    fn try_from(value: SerializedVdafConfig) -> Result<Self, Self::Error> {
+        Self::new(value.dp_config, value.vdaf_type)
+    }
+}
+
 impl VdafConfig {
     pub fn new(dp_config: DpConfig, vdaf_type: VdafType) -> Result<Self, Error> {
         if let VdafType::Prio3Histogram { buckets } = &vdaf_type {
@@ -311,7 +359,7 @@ impl Decode for VdafConfig {
     }
 }
 
-#[derive(Clone, PartialEq, Eq, Derivative)]
+#[derive(Clone, PartialEq, Eq, Derivative, Serialize, Deserialize)]
 #[derivative(Debug)]
 #[repr(u32)]
 #[non_exhaustive]
@@ -395,7 +443,7 @@ impl Decode for VdafType {
 /// See [draft-irtf-cfrg-vdaf/#94][1] for discussion.
 ///
 /// [1]: https://github.com/cfrg/draft-irtf-cfrg-vdaf/issues/94
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct DpConfig {
     dp_mechanism: DpMechanism,
 }
@@ -428,17 +476,35 @@ impl Decode for DpConfig {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
-#[repr(u8)]
+/// A differential privacy mechanism, along with its parameters.
+///
+/// The taskprov draft only assigns codepoints for `Reserved` and `None`. The remaining mechanisms
+/// use codepoints from the private-use range (`0xF0`-`0xFF`), and are only understood by Janus.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[non_exhaustive]
 pub enum DpMechanism {
     Reserved,
     None,
+    /// Discrete Laplace noise, providing `epsilon`-differential privacy for queries with the given
+    /// L1 `sensitivity`.
+    DiscreteLaplace {
+        epsilon: Rational,
+        sensitivity: Rational,
+    },
+    /// Discrete Gaussian noise, providing `(epsilon, delta)`-differential privacy for queries with
+    /// the given L2 `sensitivity`.
+    DiscreteGaussian {
+        epsilon: Rational,
+        delta: Rational,
+        sensitivity: Rational,
+    },
 }
 
 impl DpMechanism {
     const RESERVED: u8 = 0;
     const NONE: u8 = 1;
+    const DISCRETE_LAPLACE: u8 = 0xF0;
+    const DISCRETE_GAUSSIAN: u8 = 0xF1;
 }
 
 impl Encode for DpMechanism {
@@ -446,12 +512,32 @@ impl Encode for DpMechanism {
         match self {
             Self::Reserved => Self::RESERVED.encode(bytes),
             Self::None => Self::NONE.encode(bytes),
+            Self::DiscreteLaplace {
+                epsilon,
+                sensitivity,
+            } => {
+                Self::DISCRETE_LAPLACE.encode(bytes);
+                epsilon.encode(bytes);
+                sensitivity.encode(bytes);
+            }
+            Self::DiscreteGaussian {
+                epsilon,
+                delta,
+                sensitivity,
+            } => {
+                Self::DISCRETE_GAUSSIAN.encode(bytes);
+                epsilon.encode(bytes);
+                delta.encode(bytes);
+                sensitivity.encode(bytes);
+            }
         }
     }
 
     fn encoded_len(&self) -> Option<usize> {
         match self {
             Self::Reserved | Self::None => Some(1),
+            Self::DiscreteLaplace { .. } => Some(1 + 2 * Rational::ENCODED_LEN),
+            Self::DiscreteGaussian { .. } => Some(1 + 3 * Rational::ENCODED_LEN),
         }
     }
 }
@@ -461,6 +547,15 @@ impl Decode for DpMechanism {
         match u8::decode(bytes)? {
             Self::RESERVED => Ok(Self::Reserved),
             Self::NONE => Ok(Self::None),
+            Self::DISCRETE_LAPLACE => Ok(Self::DiscreteLaplace {
+                epsilon: Rational::decode(bytes)?,
+                sensitivity: Rational::decode(bytes)?,
+            }),
+            Self::DISCRETE_GAUSSIAN => Ok(Self::DiscreteGaussian {
+                epsilon: Rational::decode(bytes)?,
+                delta: Rational::decode(bytes)?,
+                sensitivity: Rational::decode(bytes)?,
+            }),
             val => Err(CodecError::Other(
                 anyhow!("unexpected DpMechanism value {}", val).into(),
             )),
@@ -468,6 +563,79 @@ impl Decode for DpMechanism {
     }
 }
 
+/// A non-negative rational number, used for differential privacy parameters so that they can be
+/// represented exactly.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "SerializedRational")]
+pub struct Rational {
+    numerator: u32,
+    denominator: u32,
+}
+
+/// Unvalidated serialized form of [`Rational`], so that deserialization goes through
+/// [`Rational::new`].
+#[derive(Deserialize)]
+#[serde(rename = "Rational")]
+struct SerializedRational {
+    numerator: u32,
+    denominator: u32,
+}
+
+impl TryFrom<SerializedRational> for Rational {
+    type Error = Error;
+
+    fn try_from(value: SerializedRational) -> Result<Self, Self::Error> {
+        Self::new(value.numerator, value.denominator)
+    }
+}
+
+impl Rational {
+    const ENCODED_LEN: usize = 8;
+
+    /// Constructs a new rational number. Returns an error if the denominator is zero.
+    pub fn new(numerator: u32, denominator: u32) -> Result<Self, Error> {
+        if denominator == 0 {
+            return Err(Error::InvalidParameter("denominator must not be zero"));
+        }
+        Ok(Self {
+            numerator,
+            denominator,
+        })
+    }
+
+    pub fn numerator(&self) -> u32 {
+        self.numerator
+    }
+
+    pub fn denominator(&self) -> u32 {
+        self.denominator
+    }
+
+    /// Returns an approximation of this number as a floating-point value.
+    pub fn as_f64(&self) -> f64 {
+        f64::from(self.numerator) / f64::from(self.denominator)
+    }
+}
+
+impl Encode for Rational {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        self.numerator.encode(bytes);
+        self.denominator.encode(bytes);
+    }
+
+    fn encoded_len(&self) -> Option<usize> {
+        Some(Self::ENCODED_LEN)
+    }
+}
+
+impl Decode for Rational {
+    fn decode(bytes: &mut Cursor<&[u8]>) -> Result<Self, CodecError> {
+        let numerator = u32::decode(bytes)?;
+        let denominator = u32::decode(bytes)?;
+        Self::new(numerator, denominator).map_err(|e| CodecError::Other(e.into()))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -479,9 +647,58 @@ mod tests {
         roundtrip_encoding(&[
             (DpConfig::new(DpMechanism::Reserved), "00"),
             (DpConfig::new(DpMechanism::None), "01"),
+            (
+                DpConfig::new(DpMechanism::DiscreteLaplace {
+                    epsilon: Rational::new(1, 2).unwrap(),
+                    sensitivity: Rational::new(1, 1).unwrap(),
+                }),
+                concat!(
+                    "F0",
+                    concat!("00000001", "00000002"), // epsilon
+                    concat!("00000001", "00000001"), // sensitivity
+                ),
+            ),
+            (
+                DpConfig::new(DpMechanism::DiscreteGaussian {
+                    epsilon: Rational::new(1, 2).unwrap(),
+                    delta: Rational::new(1, 1000000).unwrap(),
+                    sensitivity: Rational::new(3, 1).unwrap(),
+                }),
+                concat!(
+                    "F1",
+                    concat!("00000001", "00000002"), // epsilon
+                    concat!("00000001", "000F4240"), // delta
+                    concat!("00000003", "00000001"), // sensitivity
+                ),
+            ),
         ])
     }
 
+    #[test]
+    fn decode_rational_zero_denominator() {
+        assert_matches!(
+            Rational::get_decoded(&hex::decode("0000000100000000").unwrap()),
+            Err(CodecError::Other(_))
+        );
+    }
+
+    #[test]
+    fn rational_serde() {
+        let rational = Rational::new(1, 2).unwrap();
+        let json = serde_json::to_value(rational).unwrap();
+        assert_eq!(
+            serde_json::from_value::<Rational>(json.clone()).unwrap(),
+            rational
+        );
+
+        let mut bad_json = json;
+        bad_json["denominator"] = serde_json::json!(0);
+        assert_matches!(
+            serde_json::from_value::<Rational>(bad_json),
+            Err(e) => assert!(e.to_string().contains("denominator must not be zero"))
+        );
+    }
+
     #[test]
     fn roundtrip_vdaf_type() {
         roundtrip_encoding(&[
@@ -830,4 +1047,54 @@ mod tests {
             Err(CodecError::Other(_))
         );
     }
+
+    #[test]
+    fn task_config_serde() {
+        let task_config = TaskConfig::new(
+            Vec::from("foobar".as_bytes()),
+            Vec::from([
+                Url::try_from("https://leader.example.com/".as_bytes()).unwrap(),
+                Url::try_from("https://helper.example.com/".as_bytes()).unwrap(),
+            ]),
+            QueryConfig::new(Duration::from_seconds(60), 1, 100, Query::TimeInterval),
+            Time::from_seconds_since_epoch(1000),
+            VdafConfig::new(
+                DpConfig::new(DpMechanism::None),
+                VdafType::Prio3Histogram {
+                    buckets: Vec::from([1, 2, 3]),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let json = serde_json::to_value(&task_config).unwrap();
+        assert_eq!(
+            serde_json::from_value::<TaskConfig>(json.clone()).unwrap(),
+            task_config
+        );
+
+        // Empty task_info
+        let mut bad_json = json.clone();
+        bad_json["task_info"] = serde_json::json!("");
+        assert_matches!(
+            serde_json::from_value::<TaskConfig>(bad_json),
+            Err(e) => assert!(e.to_string().contains("task_info must not be empty"))
+        );
+
+        // Empty aggregator_endpoints
+        let mut bad_json = json.clone();
+        bad_json["aggregator_endpoints"] = serde_json::json!([]);
+        assert_matches!(
+            serde_json::from_value::<TaskConfig>(bad_json),
+            Err(e) => assert!(e.to_string().contains("aggregator_endpoints must not be empty"))
+        );
+
+        // Empty histogram buckets
+        let mut bad_json = json;
+        bad_json["vdaf_config"]["vdaf_type"]["Prio3Histogram"]["buckets"] = serde_json::json!([]);
+        assert_matches!(
+            serde_json::from_value::<TaskConfig>(bad_json),
+            Err(e) => assert!(e.to_string().contains("buckets must not be empty"))
+        );
+    }
 }
@@ -13,9 +13,12 @@ use fixed::{FixedI16, FixedI32, FixedI64};
 use janus_collector::{default_http_client, AuthenticationToken, Collector, CollectorParameters};
 use janus_core::hpke::{DivviUpHpkeConfig, HpkeKeypair, HpkePrivateKey};
 use janus_messages::{
-    query_type::{FixedSize, QueryType, TimeInterval},
-    BatchId, Duration, FixedSizeQuery, HpkeConfig, Interval, PartialBatchSelector, Query, TaskId,
-    Time,
+    query_type::{FixedSize, QueryType},
+    BatchId, Duration, FixedSizeQuery, HpkeConfig, Interval, Query, TaskId, Time,
+};
+use janus_tools::output::{
+    write_batches, write_collection, write_file_atomically, AggregateResult, OutputFormat,
+    QueryTypeExt,
 };
 #[cfg(feature = "fpvec_bounded_l2")]
 use prio::vdaf::prio3::Prio3FixedPointBoundedL2VecSumMultithreaded;
@@ -23,7 +26,12 @@ use prio::{
     codec::Decode,
     vdaf::{self, prio3::Prio3},
 };
-use std::{fmt::Debug, fs::File, path::PathBuf};
+use std::{
+    fmt::Debug,
+    fs::File,
+    io::{self, Write},
+    path::PathBuf,
+};
 use tracing_log::LogTracer;
 use tracing_subscriber::{prelude::*, EnvFilter, Registry};
 use url::Url;
@@ -257,7 +265,12 @@ struct QueryOptions {
     #[clap(
         long,
         value_parser = BatchIdValueParser::new(),
-        conflicts_with_all = ["batch_interval_start", "batch_interval_duration", "current_batch"],
+        conflicts_with_all = [
+            "batch_interval_start",
+            "batch_interval_duration",
+            "current_batch",
+            "list_batches",
+        ],
         help_heading = "Collect Request Parameters (Fixed Size)",
     )]
     batch_id: Option<BatchId>,
@@ -265,10 +278,29 @@ struct QueryOptions {
     #[clap(
         long,
         action = ArgAction::SetTrue,
-        conflicts_with_all = ["batch_interval_start", "batch_interval_duration", "batch_id"],
+        conflicts_with_all = [
+            "batch_interval_start",
+            "batch_interval_duration",
+            "batch_id",
+            "list_batches",
+        ],
         help_heading = "Collect Request Parameters (Fixed Size)",
     )]
     current_batch: bool,
+    /// List the batches that have enough reports to be collected, along with whether they have
+    /// already been collected, instead of collecting a batch
+    #[clap(
+        long,
+        action = ArgAction::SetTrue,
+        conflicts_with_all = [
+            "batch_interval_start",
+            "batch_interval_duration",
+            "batch_id",
+            "current_batch",
+        ],
+        help_heading = "Collect Request Parameters (Fixed Size)",
+    )]
+    list_batches: bool,
 }
 
 #[derive(Derivative, Parser, PartialEq, Eq)]
@@ -344,6 +376,20 @@ struct Options {
 
     #[clap(flatten)]
     query: QueryOptions,
+
+    /// Format of the collection result
+    #[clap(
+        long,
+        value_enum,
+        default_value_t = OutputFormat::Text,
+        help_heading = "Output",
+        display_order = 0
+    )]
+    output: OutputFormat,
+    /// Path of a file to write the collection result to, instead of standard output. The file is
+    /// replaced atomically once the result is available.
+    #[clap(long, help_heading = "Output", display_order = 1)]
+    output_file: Option<PathBuf>,
 }
 
 impl Options {
@@ -396,31 +442,53 @@ async fn run(options: Options) -> Result<(), Error> {
         &options.query.batch_interval_duration,
         &options.query.batch_id,
         options.query.current_batch,
+        options.query.list_batches,
     ) {
-        (Some(batch_interval_start), Some(batch_interval_duration), None, false) => {
+        (Some(batch_interval_start), Some(batch_interval_duration), None, false, false) => {
             let batch_interval = Interval::new(
                 Time::from_seconds_since_epoch(*batch_interval_start),
                 Duration::from_seconds(*batch_interval_duration),
             )
             .map_err(|err| Error::Anyhow(err.into()))?;
-            run_with_query(options, Query::new_time_interval(batch_interval)).await
+            run_with_request(
+                options,
+                Request::Collect(Query::new_time_interval(batch_interval)),
+            )
+            .await
         }
-        (None, None, Some(batch_id), false) => {
+        (None, None, Some(batch_id), false, false) => {
             let batch_id = *batch_id;
-            run_with_query(
+            run_with_request(
                 options,
-                Query::new_fixed_size(FixedSizeQuery::ByBatchId { batch_id }),
+                Request::Collect(Query::new_fixed_size(FixedSizeQuery::ByBatchId {
+                    batch_id,
+                })),
             )
             .await
         }
-        (None, None, None, true) => {
-            run_with_query(options, Query::new_fixed_size(FixedSizeQuery::CurrentBatch)).await
+        (None, None, None, true, false) => {
+            run_with_request(
+                options,
+                Request::Collect(Query::new_fixed_size(FixedSizeQuery::CurrentBatch)),
+            )
+            .await
+        }
+        (None, None, None, false, true) => {
+            run_with_request(options, Request::<FixedSize>::ListBatches).await
         }
         _ => unreachable!(),
     }
 }
 
-async fn run_with_query<Q: QueryType>(options: Options, query: Query<Q>) -> Result<(), Error>
+/// The request made of the leader.
+enum Request<Q: QueryType> {
+    /// Collect the aggregate result of a batch.
+    Collect(Query<Q>),
+    /// List the collectable batches of a fixed-size task.
+    ListBatches,
+}
+
+async fn run_with_request<Q: QueryType>(options: Options, request: Request<Q>) -> Result<(), Error>
 where
     Q: QueryTypeExt,
 {
@@ -443,64 +511,68 @@ where
         hpke_keypair.private_key().clone(),
     );
     let http_client = default_http_client().map_err(|err| Error::Anyhow(err.into()))?;
+    let output = OutputOptions {
+        format: options.output,
+        file: options.output_file.clone(),
+    };
     match (options.vdaf, options.length, options.bits) {
         (VdafType::Count, None, None) => {
             let vdaf = Prio3::new_count(2).map_err(|err| Error::Anyhow(err.into()))?;
-            run_collection_generic(parameters, vdaf, http_client, query, &())
+            run_collection_generic(parameters, vdaf, http_client, request, &(), &output)
                 .await
-                .map_err(|err| Error::Anyhow(err.into()))
+                .map_err(Error::Anyhow)
         }
         (VdafType::CountVec, Some(length), None) => {
             let vdaf = Prio3::new_sum_vec(2, 1, length).map_err(|err| Error::Anyhow(err.into()))?;
-            run_collection_generic(parameters, vdaf, http_client, query, &())
+            run_collection_generic(parameters, vdaf, http_client, request, &(), &output)
                 .await
-                .map_err(|err| Error::Anyhow(err.into()))
+                .map_err(Error::Anyhow)
         }
         (VdafType::Sum, None, Some(bits)) => {
             let vdaf = Prio3::new_sum(2, bits).map_err(|err| Error::Anyhow(err.into()))?;
-            run_collection_generic(parameters, vdaf, http_client, query, &())
+            run_collection_generic(parameters, vdaf, http_client, request, &(), &output)
                 .await
-                .map_err(|err| Error::Anyhow(err.into()))
+                .map_err(Error::Anyhow)
         }
         (VdafType::SumVec, Some(length), Some(bits)) => {
             let vdaf =
                 Prio3::new_sum_vec(2, bits, length).map_err(|err| Error::Anyhow(err.into()))?;
-            run_collection_generic(parameters, vdaf, http_client, query, &())
+            run_collection_generic(parameters, vdaf, http_client, request, &(), &output)
                 .await
-                .map_err(|err| Error::Anyhow(err.into()))
+                .map_err(Error::Anyhow)
         }
         (VdafType::Histogram, Some(length), None) => {
             let vdaf = Prio3::new_histogram(2, length).map_err(|err| Error::Anyhow(err.into()))?;
-            run_collection_generic(parameters, vdaf, http_client, query, &())
+            run_collection_generic(parameters, vdaf, http_client, request, &(), &output)
                 .await
-                .map_err(|err| Error::Anyhow(err.into()))
+                .map_err(Error::Anyhow)
         }
         #[cfg(feature = "fpvec_bounded_l2")]
         (VdafType::FixedPoint16BitBoundedL2VecSum, Some(length), None) => {
             let vdaf: Prio3FixedPointBoundedL2VecSumMultithreaded<FixedI16<U15>> =
                 Prio3::new_fixedpoint_boundedl2_vec_sum_multithreaded(2, length)
                     .map_err(|err| Error::Anyhow(err.into()))?;
-            run_collection_generic(parameters, vdaf, http_client, query, &())
+            run_collection_generic(parameters, vdaf, http_client, request, &(), &output)
                 .await
-                .map_err(|err| Error::Anyhow(err.into()))
+                .map_err(Error::Anyhow)
         }
         #[cfg(feature = "fpvec_bounded_l2")]
         (VdafType::FixedPoint32BitBoundedL2VecSum, Some(length), None) => {
             let vdaf: Prio3FixedPointBoundedL2VecSumMultithreaded<FixedI32<U31>> =
                 Prio3::new_fixedpoint_boundedl2_vec_sum_multithreaded(2, length)
                     .map_err(|err| Error::Anyhow(err.into()))?;
-            run_collection_generic(parameters, vdaf, http_client, query, &())
+            run_collection_generic(parameters, vdaf, http_client, request, &(), &output)
                 .await
-                .map_err(|err| Error::Anyhow(err.into()))
+                .map_err(Error::Anyhow)
         }
         #[cfg(feature = "fpvec_bounded_l2")]
         (VdafType::FixedPoint64BitBoundedL2VecSum, Some(length), None) => {
             let vdaf: Prio3FixedPointBoundedL2VecSumMultithreaded<FixedI64<U63>> =
                 Prio3::new_fixedpoint_boundedl2_vec_sum_multithreaded(2, length)
                     .map_err(|err| Error::Anyhow(err.into()))?;
-            run_collection_generic(parameters, vdaf, http_client, query, &())
+            run_collection_generic(parameters, vdaf, http_client, request, &(), &output)
                 .await
-                .map_err(|err| Error::Anyhow(err.into()))
+                .map_err(Error::Anyhow)
         }
         _ => Err(clap::Error::raw(
             ErrorKind::ArgumentConflict,
@@ -522,28 +594,37 @@ async fn run_collection_generic<V: vdaf::Collector, Q: QueryTypeExt>(
     parameters: CollectorParameters,
     vdaf: V,
     http_client: reqwest::Client,
-    query: Query<Q>,
+    request: Request<Q>,
     agg_param: &V::AggregationParam,
-) -> Result<(), janus_collector::Error>
+    output: &OutputOptions,
+) -> anyhow::Result<()>
 where
-    V::AggregateResult: Debug,
+    V::AggregateResult: Debug + Clone + Into<AggregateResult>,
 {
     let collector = Collector::new(parameters, vdaf, http_client);
-    let collection = collector.collect(query, agg_param).await?;
-    if !Q::IS_PARTIAL_BATCH_SELECTOR_TRIVIAL {
-        println!(
-            "Batch: {}",
-            Q::format_partial_batch_selector(collection.partial_batch_selector())
-        );
+
+    let mut buffer = Vec::new();
+    match request {
+        Request::Collect(query) => {
+            let collection = collector.collect(query, agg_param).await?;
+            write_collection(&collection, output.format, &mut buffer)?;
+        }
+        Request::ListBatches => {
+            let batches = collector.list_batches().await?;
+            write_batches(&batches, output.format, &mut buffer)?;
+        }
+    }
+    match &output.file {
+        Some(path) => write_file_atomically(path, &buffer)
+            .with_context(|| format!("could not write output file {}", path.display())),
+        None => Ok(io::stdout().write_all(&buffer)?),
     }
-    println!("Number of reports: {}", collection.report_count());
-    println!(
-        "Spanned interval: start: {} length: {}",
-        collection.interval().0,
-        collection.interval().1
-    );
-    println!("Aggregation result: {:?}", collection.aggregate_result());
-    Ok(())
+}
+
+/// Where and how the result of a collection, or a list of batches, should be written.
+struct OutputOptions {
+    format: OutputFormat,
+    file: Option<PathBuf>,
 }
 
 fn install_tracing_subscriber() -> anyhow::Result<()> {
@@ -560,35 +641,11 @@ fn install_tracing_subscriber() -> anyhow::Result<()> {
     Ok(())
 }
 
-trait QueryTypeExt: QueryType {
-    const IS_PARTIAL_BATCH_SELECTOR_TRIVIAL: bool;
-
-    fn format_partial_batch_selector(partial_batch_selector: &PartialBatchSelector<Self>)
-        -> String;
-}
-
-impl QueryTypeExt for TimeInterval {
-    const IS_PARTIAL_BATCH_SELECTOR_TRIVIAL: bool = true;
-
-    fn format_partial_batch_selector(_: &PartialBatchSelector<Self>) -> String {
-        "()".to_string()
-    }
-}
-
-impl QueryTypeExt for FixedSize {
-    const IS_PARTIAL_BATCH_SELECTOR_TRIVIAL: bool = false;
-
-    fn format_partial_batch_selector(
-        partial_batch_selector: &PartialBatchSelector<Self>,
-    ) -> String {
-        URL_SAFE_NO_PAD.encode(partial_batch_selector.batch_id().as_ref())
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use crate::{
-        run, AuthenticationOptions, AuthenticationToken, Error, Options, QueryOptions, VdafType,
+        run, AuthenticationOptions, AuthenticationToken, Error, Options, OutputFormat,
+        QueryOptions, VdafType,
     };
     use assert_matches::assert_matches;
     use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
@@ -640,7 +697,10 @@ mod tests {
                 batch_interval_duration: Some(1_000),
                 batch_id: None,
                 current_batch: false,
+                list_batches: false,
             },
+            output: OutputFormat::Text,
+            output_file: None,
         };
         let task_id_encoded = URL_SAFE_NO_PAD.encode(task_id.get_encoded());
         let correct_arguments = [
@@ -862,7 +922,10 @@ mod tests {
                 batch_interval_duration: None,
                 batch_id: None,
                 current_batch: true,
+                list_batches: false,
             },
+            output: OutputFormat::Text,
+            output_file: None,
         };
         let correct_arguments = [
             "collect",
@@ -902,7 +965,10 @@ mod tests {
                 batch_interval_duration: None,
                 batch_id: Some(batch_id),
                 current_batch: false,
+                list_batches: false,
             },
+            output: OutputFormat::Text,
+            output_file: None,
         };
         let correct_arguments = [
             "collect",
@@ -936,6 +1002,15 @@ mod tests {
         good_arguments.push("--current-batch".to_string());
         Options::try_parse_from(good_arguments).unwrap();
 
+        let mut good_arguments = base_arguments.clone();
+        good_arguments.push("--list-batches".to_string());
+        assert!(
+            Options::try_parse_from(good_arguments)
+                .unwrap()
+                .query
+                .list_batches
+        );
+
         // Check that clap enforces all the constraints we need on combinations of query arguments.
         // This allows us to treat a default match branch as `unreachable!()` when unpacking the
         // argument matches.
@@ -1012,7 +1087,7 @@ mod tests {
             ErrorKind::ArgumentConflict
         );
 
-        let mut bad_arguments = base_arguments;
+        let mut bad_arguments = base_arguments.clone();
         bad_arguments.extend([
             "--batch-id=AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA".to_string(),
             "--current-batch".to_string(),
@@ -1021,6 +1096,34 @@ mod tests {
             Options::try_parse_from(bad_arguments).unwrap_err().kind(),
             ErrorKind::ArgumentConflict
         );
+
+        let mut bad_arguments = base_arguments.clone();
+        bad_arguments.extend([
+            "--batch-interval-start=1".to_string(),
+            "--batch-interval-duration=1".to_string(),
+            "--list-batches".to_string(),
+        ]);
+        assert_eq!(
+            Options::try_parse_from(bad_arguments).unwrap_err().kind(),
+            ErrorKind::ArgumentConflict
+        );
+
+        let mut bad_arguments = base_arguments.clone();
+        bad_arguments.extend([
+            "--batch-id=AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA".to_string(),
+            "--list-batches".to_string(),
+        ]);
+        assert_eq!(
+            Options::try_parse_from(bad_arguments).unwrap_err().kind(),
+            ErrorKind::ArgumentConflict
+        );
+
+        let mut bad_arguments = base_arguments;
+        bad_arguments.extend(["--current-batch".to_string(), "--list-batches".to_string()]);
+        assert_eq!(
+            Options::try_parse_from(bad_arguments).unwrap_err().kind(),
+            ErrorKind::ArgumentConflict
+        );
     }
 
     #[test]
@@ -1136,7 +1239,10 @@ mod tests {
                 batch_interval_duration: Some(1_000),
                 batch_id: None,
                 current_batch: false,
+                list_batches: false,
             },
+            output: OutputFormat::Text,
+            output_file: None,
         };
 
         assert_eq!(options.hpke_keypair().unwrap(), hpke_keypair);
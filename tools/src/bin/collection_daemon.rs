@@ -0,0 +1,1353 @@
+use anyhow::{anyhow, Context};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use clap::Parser;
+#[cfg(feature = "fpvec_bounded_l2")]
+use fixed::types::extra::{U15, U31, U63};
+#[cfg(feature = "fpvec_bounded_l2")]
+use fixed::{FixedI16, FixedI32, FixedI64};
+use futures::future::join_all;
+use janus_collector::{
+    default_http_client, AuthenticationToken, Collection, Collector, CollectorParameters,
+};
+use janus_core::{
+    hpke::{DivviUpHpkeConfig, HpkeKeypair},
+    task::VdafInstance,
+    time::{Clock, IntervalExt, RealClock, TimeExt},
+};
+use janus_messages::{
+    query_type::{FixedSize, TimeInterval},
+    CollectionJobId, Duration, FixedSizeQuery, Interval, Query, TaskId, Time,
+};
+use janus_tools::output::{
+    write_collection, write_file_atomically, AggregateResult, OutputFormat, QueryTypeExt,
+};
+use opentelemetry::{
+    global,
+    metrics::{Counter, Unit},
+    sdk::metrics::MeterProvider,
+    KeyValue,
+};
+#[cfg(feature = "fpvec_bounded_l2")]
+use prio::vdaf::prio3::Prio3FixedPointBoundedL2VecSumMultithreaded;
+use prio::vdaf::{self, prio3::Prio3};
+use prometheus::Registry;
+use rand::random;
+use reqwest::header::CONTENT_TYPE;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fmt::Debug,
+    fs::{self, File},
+    net::SocketAddr,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::Duration as StdDuration,
+};
+use tokio::{task::JoinHandle, time::sleep};
+use tracing::{error, info};
+use tracing_log::LogTracer;
+use tracing_subscriber::{prelude::*, EnvFilter, Registry as SubscriberRegistry};
+use url::Url;
+
+#[derive(Debug, Parser)]
+#[clap(
+    name = "collection_daemon",
+    version,
+    about = "Long-running DAP-PPM collector that periodically collects batches for a set of tasks",
+    long_about = None,
+)]
+struct Options {
+    /// Path to configuration YAML
+    #[clap(long, env = "CONFIG_FILE", num_args = 1)]
+    config_file: PathBuf,
+    /// Collect every batch that is currently due, and then exit, rather than running indefinitely
+    #[clap(long)]
+    once: bool,
+}
+
+/// Configuration for the collection daemon.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Config {
+    /// Path to the file in which the daemon records its progress for each task.
+    state_path: PathBuf,
+    /// How often to check whether any task has a batch due for collection, in seconds.
+    #[serde(default = "default_poll_interval_secs")]
+    poll_interval_secs: u64,
+    /// How long to wait for a collection job to complete, in seconds, counted from when it was
+    /// first recorded. A collection job that takes longer is abandoned, and its batch is skipped.
+    #[serde(default = "default_collection_timeout_secs")]
+    collection_timeout_secs: u64,
+    /// Address on which to serve Prometheus metrics scrape requests, if any.
+    #[serde(default)]
+    metrics_listen_address: Option<SocketAddr>,
+    /// Tasks to collect.
+    tasks: Vec<TaskConfig>,
+}
+
+fn default_poll_interval_secs() -> u64 {
+    60
+}
+
+fn default_collection_timeout_secs() -> u64 {
+    86400
+}
+
+/// Configuration for a single task to be collected by the daemon.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TaskConfig {
+    /// DAP task identifier.
+    task_id: TaskId,
+    /// The leader aggregator's endpoint URL.
+    leader: Url,
+    /// Token used to authenticate to the leader.
+    authentication_token: AuthenticationToken,
+    /// Path to a JSON document containing the collector's HPKE configuration and private key, in
+    /// the format output by `divviup hpke-config generate`.
+    hpke_config_json: PathBuf,
+    /// The task's VDAF.
+    vdaf: VdafInstance,
+    /// The task's query type, and the cadence at which batches are collected.
+    query: QueryConfig,
+    /// Where collection results are delivered.
+    delivery: DeliveryConfig,
+}
+
+/// The query type of a task, along with the parameters that determine when its batches are
+/// collected.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+enum QueryConfig {
+    /// Collect consecutive batch intervals, each aligned to and a multiple of the task's time
+    /// precision.
+    TimeInterval {
+        /// The task's time precision, in seconds.
+        time_precision_secs: u64,
+        /// The duration of each batch interval, in seconds. Defaults to the time precision.
+        #[serde(default)]
+        batch_duration_secs: Option<u64>,
+        /// Start of the first batch interval to collect, in seconds since the Unix epoch. Defaults
+        /// to the start of the batch interval containing the time the daemon first runs.
+        #[serde(default)]
+        start: Option<u64>,
+        /// How long to wait after a batch interval ends before collecting it, in seconds, to allow
+        /// for late reports and aggregation.
+        #[serde(default)]
+        collection_delay_secs: u64,
+    },
+    /// Collect the current batch, at most once per collection interval.
+    FixedSize {
+        /// Minimum time between collections, in seconds.
+        collection_interval_secs: u64,
+    },
+}
+
+/// Destination for collection results.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+enum DeliveryConfig {
+    /// Write each result to its own file, in a subdirectory per task.
+    Directory {
+        path: PathBuf,
+        #[serde(default = "default_directory_format")]
+        format: OutputFormat,
+    },
+    /// Send each result, formatted as JSON, in the body of a POST request to a URL.
+    Webhook { url: Url },
+}
+
+fn default_directory_format() -> OutputFormat {
+    OutputFormat::Json
+}
+
+impl TaskConfig {
+    fn validate(&self) -> anyhow::Result<()> {
+        match &self.query {
+            QueryConfig::TimeInterval {
+                time_precision_secs,
+                batch_duration_secs,
+                start,
+                ..
+            } => {
+                if *time_precision_secs == 0 {
+                    return Err(anyhow!("time_precision_secs must be positive"));
+                }
+                if let Some(batch_duration_secs) = batch_duration_secs {
+                    if *batch_duration_secs == 0 || batch_duration_secs % time_precision_secs != 0 {
+                        return Err(anyhow!(
+                            "batch_duration_secs must be a positive multiple of \
+                             time_precision_secs"
+                        ));
+                    }
+                }
+                if let Some(start) = start {
+                    if start % time_precision_secs != 0 {
+                        return Err(anyhow!("start must be a multiple of time_precision_secs"));
+                    }
+                }
+            }
+            QueryConfig::FixedSize {
+                collection_interval_secs,
+            } => {
+                if *collection_interval_secs == 0 {
+                    return Err(anyhow!("collection_interval_secs must be positive"));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn collector_parameters(&self) -> anyhow::Result<CollectorParameters> {
+        let reader =
+            File::open(&self.hpke_config_json).context("could not open HPKE config file")?;
+        let divviup_hpke_config: DivviUpHpkeConfig =
+            serde_json::from_reader(reader).context("could not parse HPKE config file")?;
+        let hpke_keypair =
+            HpkeKeypair::try_from(divviup_hpke_config).context("could not convert HPKE config")?;
+        Ok(CollectorParameters::new(
+            self.task_id,
+            self.leader.clone(),
+            self.authentication_token.clone(),
+            hpke_keypair.config().clone(),
+            hpke_keypair.private_key().clone(),
+        ))
+    }
+}
+
+/// Progress of the daemon, persisted across restarts.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+struct State {
+    tasks: BTreeMap<TaskId, TaskState>,
+}
+
+/// Progress of the daemon for a single task.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct TaskState {
+    /// Start of the next time interval batch to collect.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    next_batch_start: Option<Time>,
+    /// When the most recent collection was delivered.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    last_collection_time: Option<Time>,
+    /// A collection job that has been, or is about to be, created at the leader, but whose result
+    /// has not yet been delivered. It is recorded before the collection job is created, and the
+    /// same collection job is resumed after a restart, so that no batch is collected twice.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    in_flight: Option<InFlightCollection>,
+    /// When the in-flight collection was first recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    in_flight_since: Option<Time>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum InFlightCollection {
+    TimeInterval {
+        collection_job_id: CollectionJobId,
+        query: Query<TimeInterval>,
+    },
+    FixedSize {
+        collection_job_id: CollectionJobId,
+        query: Query<FixedSize>,
+    },
+}
+
+/// Local store of the daemon's progress, backed by a JSON file that is replaced atomically on every
+/// update.
+struct StateStore {
+    path: PathBuf,
+    state: Mutex<State>,
+}
+
+impl StateStore {
+    fn open(path: PathBuf) -> anyhow::Result<StateStore> {
+        let state = if path.exists() {
+            let reader = File::open(&path).context("could not open state file")?;
+            serde_json::from_reader(reader).context("could not parse state file")?
+        } else {
+            State::default()
+        };
+        Ok(StateStore {
+            path,
+            state: Mutex::new(state),
+        })
+    }
+
+    fn get(&self, task_id: &TaskId) -> TaskState {
+        let state = self.state.lock().unwrap();
+        state.tasks.get(task_id).cloned().unwrap_or_default()
+    }
+
+    fn put(&self, task_id: TaskId, task_state: TaskState) -> anyhow::Result<()> {
+        let mut state = self.state.lock().unwrap();
+        state.tasks.insert(task_id, task_state);
+        let contents = serde_json::to_vec_pretty(&*state)?;
+        write_file_atomically(&self.path, &contents).context("could not write state file")
+    }
+}
+
+struct Metrics {
+    collections: Counter<u64>,
+    reports: Counter<u64>,
+    abandoned_collections: Counter<u64>,
+}
+
+impl Metrics {
+    fn new<C: Clock>(state: Arc<StateStore>, clock: C) -> Metrics {
+        let meter = global::meter("janus_collection_daemon");
+        meter
+            .u64_observable_gauge("janus_collection_daemon_in_flight_collection_age")
+            .with_description(
+                "Time since each task's in-flight collection job was first recorded. A steadily \
+                 growing value indicates that collection is stuck.",
+            )
+            .with_unit(Unit::new("s"))
+            .with_callback(move |gauge| {
+                let now = clock.now();
+                for (task_id, task_state) in state.state.lock().unwrap().tasks.iter() {
+                    let age = task_state
+                        .in_flight_since
+                        .and_then(|since| now.difference(&since).ok())
+                        .map_or(0, |age| age.as_seconds());
+                    gauge.observe(age, &[KeyValue::new("task_id", task_id.to_string())]);
+                }
+            })
+            .init();
+        Metrics {
+            collections: meter
+                .u64_counter("janus_collection_daemon_collections")
+                .with_description("Number of collection attempts made by the collection daemon.")
+                .with_unit(Unit::new("{collection}"))
+                .init(),
+            reports: meter
+                .u64_counter("janus_collection_daemon_reports")
+                .with_description("Number of reports included in delivered collections.")
+                .with_unit(Unit::new("{report}"))
+                .init(),
+            abandoned_collections: meter
+                .u64_counter("janus_collection_daemon_abandoned_collections")
+                .with_description(
+                    "Number of collection jobs abandoned because they did not complete within the \
+                     collection timeout.",
+                )
+                .with_unit(Unit::new("{collection}"))
+                .init(),
+        }
+    }
+
+    fn record_success(&self, task_id: &TaskId, report_count: u64) {
+        let task_id = KeyValue::new("task_id", task_id.to_string());
+        self.collections
+            .add(1, &[task_id.clone(), KeyValue::new("status", "success")]);
+        self.reports.add(report_count, &[task_id]);
+    }
+
+    fn record_failure(&self, task_id: &TaskId) {
+        self.collections.add(
+            1,
+            &[
+                KeyValue::new("task_id", task_id.to_string()),
+                KeyValue::new("status", "error"),
+            ],
+        );
+    }
+
+    fn record_abandoned(&self, task_id: &TaskId) {
+        self.abandoned_collections
+            .add(1, &[KeyValue::new("task_id", task_id.to_string())]);
+    }
+}
+
+struct Daemon<C> {
+    config: Config,
+    clock: C,
+    http_client: reqwest::Client,
+    state: Arc<StateStore>,
+    metrics: Metrics,
+}
+
+impl<C: Clock> Daemon<C> {
+    fn new(config: Config, clock: C) -> anyhow::Result<Daemon<C>> {
+        for task in &config.tasks {
+            task.validate()
+                .with_context(|| format!("invalid configuration for task {}", task.task_id))?;
+        }
+        let state = Arc::new(StateStore::open(config.state_path.clone())?);
+        let metrics = Metrics::new(Arc::clone(&state), clock.clone());
+        Ok(Daemon {
+            config,
+            clock,
+            http_client: default_http_client()?,
+            state,
+            metrics,
+        })
+    }
+
+    /// Collects every batch that is currently due, for all tasks. Returns an error if any task
+    /// failed.
+    async fn run_once(&self) -> anyhow::Result<()> {
+        let failures = join_all(self.config.tasks.iter().map(|task| self.step_task(task)))
+            .await
+            .into_iter()
+            .filter(Result::is_err)
+            .count();
+        if failures > 0 {
+            return Err(anyhow!("collection failed for {failures} task(s)"));
+        }
+        Ok(())
+    }
+
+    /// Collects batches for all tasks as they become due, until the process is interrupted.
+    async fn run(&self) {
+        let poll_interval = StdDuration::from_secs(self.config.poll_interval_secs);
+        join_all(self.config.tasks.iter().map(|task| async move {
+            loop {
+                // Errors are logged and counted by step_task(), and the task is retried at the next
+                // poll.
+                let _ = self.step_task(task).await;
+                sleep(poll_interval).await;
+            }
+        }))
+        .await;
+    }
+
+    async fn step_task(&self, task: &TaskConfig) -> anyhow::Result<()> {
+        let result = self.step_task_dispatch(task).await;
+        if let Err(error) = &result {
+            error!(task_id = %task.task_id, ?error, "Collection failed");
+            self.metrics.record_failure(&task.task_id);
+        }
+        result
+    }
+
+    async fn step_task_dispatch(&self, task: &TaskConfig) -> anyhow::Result<()> {
+        let parameters = task.collector_parameters()?;
+        match &task.vdaf {
+            VdafInstance::Prio3Count => {
+                let vdaf = Prio3::new_count(2)?;
+                self.step_task_with_vdaf(task, parameters, vdaf).await
+            }
+            VdafInstance::Prio3CountVec { length } => {
+                let vdaf = Prio3::new_sum_vec(2, 1, *length)?;
+                self.step_task_with_vdaf(task, parameters, vdaf).await
+            }
+            VdafInstance::Prio3Sum { bits } => {
+                let vdaf = Prio3::new_sum(2, *bits)?;
+                self.step_task_with_vdaf(task, parameters, vdaf).await
+            }
+            VdafInstance::Prio3SumVec { bits, length } => {
+                let vdaf = Prio3::new_sum_vec(2, *bits, *length)?;
+                self.step_task_with_vdaf(task, parameters, vdaf).await
+            }
+            VdafInstance::Prio3Histogram { length } => {
+                let vdaf = Prio3::new_histogram(2, *length)?;
+                self.step_task_with_vdaf(task, parameters, vdaf).await
+            }
+            #[cfg(feature = "fpvec_bounded_l2")]
+            VdafInstance::Prio3FixedPoint16BitBoundedL2VecSum { length } => {
+                let vdaf: Prio3FixedPointBoundedL2VecSumMultithreaded<FixedI16<U15>> =
+                    Prio3::new_fixedpoint_boundedl2_vec_sum_multithreaded(2, *length)?;
+                self.step_task_with_vdaf(task, parameters, vdaf).await
+            }
+            #[cfg(feature = "fpvec_bounded_l2")]
+            VdafInstance::Prio3FixedPoint32BitBoundedL2VecSum { length } => {
+                let vdaf: Prio3FixedPointBoundedL2VecSumMultithreaded<FixedI32<U31>> =
+                    Prio3::new_fixedpoint_boundedl2_vec_sum_multithreaded(2, *length)?;
+                self.step_task_with_vdaf(task, parameters, vdaf).await
+            }
+            #[cfg(feature = "fpvec_bounded_l2")]
+            VdafInstance::Prio3FixedPoint64BitBoundedL2VecSum { length } => {
+                let vdaf: Prio3FixedPointBoundedL2VecSumMultithreaded<FixedI64<U63>> =
+                    Prio3::new_fixedpoint_boundedl2_vec_sum_multithreaded(2, *length)?;
+                self.step_task_with_vdaf(task, parameters, vdaf).await
+            }
+            vdaf => Err(anyhow!("unsupported VDAF: {vdaf:?}")),
+        }
+    }
+
+    async fn step_task_with_vdaf<V>(
+        &self,
+        task: &TaskConfig,
+        parameters: CollectorParameters,
+        vdaf: V,
+    ) -> anyhow::Result<()>
+    where
+        V: vdaf::Collector<AggregationParam = ()>,
+        V::AggregateResult: Debug + Clone + Into<AggregateResult>,
+    {
+        let collector = Collector::new(parameters, vdaf, self.http_client.clone());
+        match &task.query {
+            QueryConfig::TimeInterval {
+                time_precision_secs,
+                batch_duration_secs,
+                start,
+                collection_delay_secs,
+            } => {
+                self.step_time_interval_task(
+                    task,
+                    &collector,
+                    Duration::from_seconds(batch_duration_secs.unwrap_or(*time_precision_secs)),
+                    start.map(Time::from_seconds_since_epoch),
+                    Duration::from_seconds(*collection_delay_secs),
+                )
+                .await
+            }
+            QueryConfig::FixedSize {
+                collection_interval_secs,
+            } => {
+                self.step_fixed_size_task(
+                    task,
+                    &collector,
+                    Duration::from_seconds(*collection_interval_secs),
+                )
+                .await
+            }
+        }
+    }
+
+    /// Collects each consecutive batch interval of a time interval task that is due.
+    async fn step_time_interval_task<V>(
+        &self,
+        task: &TaskConfig,
+        collector: &Collector<V>,
+        batch_duration: Duration,
+        start: Option<Time>,
+        collection_delay: Duration,
+    ) -> anyhow::Result<()>
+    where
+        V: vdaf::Collector<AggregationParam = ()>,
+        V::AggregateResult: Debug + Clone + Into<AggregateResult>,
+    {
+        loop {
+            let mut task_state = self.state.get(&task.task_id);
+            let (collection_job_id, query) = match task_state.in_flight.clone() {
+                Some(InFlightCollection::TimeInterval {
+                    collection_job_id,
+                    query,
+                }) => (collection_job_id, query),
+                Some(InFlightCollection::FixedSize { .. }) => {
+                    return Err(anyhow!(
+                        "in-flight collection does not match the task's query type"
+                    ))
+                }
+                None => {
+                    let batch_start = match (task_state.next_batch_start, start) {
+                        (Some(next_batch_start), _) => next_batch_start,
+                        (None, start) => {
+                            // Record where collection starts, so that the first batch interval is
+                            // not skipped if it becomes due after a restart.
+                            let batch_start = match start {
+                                Some(start) => start,
+                                None => {
+                                    self.clock.now().to_batch_interval_start(&batch_duration)?
+                                }
+                            };
+                            task_state.next_batch_start = Some(batch_start);
+                            self.state.put(task.task_id, task_state.clone())?;
+                            batch_start
+                        }
+                    };
+                    let batch_interval = Interval::new(batch_start, batch_duration)?;
+                    if self.clock.now() < batch_interval.end().add(&collection_delay)? {
+                        return Ok(());
+                    }
+
+                    let collection_job_id = random();
+                    let query = Query::new_time_interval(batch_interval);
+                    task_state.in_flight = Some(InFlightCollection::TimeInterval {
+                        collection_job_id,
+                        query: query.clone(),
+                    });
+                    task_state.in_flight_since = Some(self.clock.now());
+                    self.state.put(task.task_id, task_state.clone())?;
+                    (collection_job_id, query)
+                }
+            };
+
+            let batch_interval = *query.batch_interval();
+            let timeout = self.remaining_collection_time(task.task_id, &mut task_state)?;
+            let collection = match self
+                .collect(collector, collection_job_id, query, timeout)
+                .await?
+            {
+                Some(collection) => collection,
+                None => {
+                    task_state.next_batch_start = Some(batch_interval.end());
+                    return Err(self.abandon(task.task_id, task_state, collection_job_id)?);
+                }
+            };
+            let name = format!(
+                "{}-{}",
+                batch_interval.start().as_seconds_since_epoch(),
+                batch_interval.duration().as_seconds()
+            );
+            self.deliver(task, &name, &collection).await?;
+
+            task_state.in_flight = None;
+            task_state.in_flight_since = None;
+            task_state.next_batch_start = Some(batch_interval.end());
+            task_state.last_collection_time = Some(self.clock.now());
+            self.state.put(task.task_id, task_state)?;
+            self.metrics
+                .record_success(&task.task_id, collection.report_count());
+            info!(task_id = %task.task_id, ?batch_interval, "Delivered collection");
+        }
+    }
+
+    /// Collects the current batch of a fixed size task, if the collection interval has elapsed
+    /// since the last collection.
+    async fn step_fixed_size_task<V>(
+        &self,
+        task: &TaskConfig,
+        collector: &Collector<V>,
+        collection_interval: Duration,
+    ) -> anyhow::Result<()>
+    where
+        V: vdaf::Collector<AggregationParam = ()>,
+        V::AggregateResult: Debug + Clone + Into<AggregateResult>,
+    {
+        let mut task_state = self.state.get(&task.task_id);
+        let (collection_job_id, query) = match task_state.in_flight.clone() {
+            Some(InFlightCollection::FixedSize {
+                collection_job_id,
+                query,
+            }) => (collection_job_id, query),
+            Some(InFlightCollection::TimeInterval { .. }) => {
+                return Err(anyhow!(
+                    "in-flight collection does not match the task's query type"
+                ))
+            }
+            None => {
+                if let Some(last_collection_time) = task_state.last_collection_time {
+                    if self.clock.now() < last_collection_time.add(&collection_interval)? {
+                        return Ok(());
+                    }
+                }
+
+                let collection_job_id = random();
+                let query = Query::new_fixed_size(FixedSizeQuery::CurrentBatch);
+                task_state.in_flight = Some(InFlightCollection::FixedSize {
+                    collection_job_id,
+                    query: query.clone(),
+                });
+                task_state.in_flight_since = Some(self.clock.now());
+                self.state.put(task.task_id, task_state.clone())?;
+                (collection_job_id, query)
+            }
+        };
+
+        let timeout = self.remaining_collection_time(task.task_id, &mut task_state)?;
+        let collection = match self
+            .collect(collector, collection_job_id, query, timeout)
+            .await?
+        {
+            Some(collection) => collection,
+            None => {
+                task_state.last_collection_time = Some(self.clock.now());
+                return Err(self.abandon(task.task_id, task_state, collection_job_id)?);
+            }
+        };
+        let batch_id = collection.partial_batch_selector().batch_id();
+        let name = URL_SAFE_NO_PAD.encode(batch_id.as_ref());
+        self.deliver(task, &name, &collection).await?;
+
+        task_state.in_flight = None;
+        task_state.in_flight_since = None;
+        task_state.last_collection_time = Some(self.clock.now());
+        self.state.put(task.task_id, task_state)?;
+        self.metrics
+            .record_success(&task.task_id, collection.report_count());
+        info!(task_id = %task.task_id, %batch_id, "Delivered collection");
+        Ok(())
+    }
+
+    /// Returns how much longer to wait for the task's in-flight collection job before abandoning
+    /// it. In-flight collections recorded before the collection timeout was introduced are timed
+    /// from now.
+    fn remaining_collection_time(
+        &self,
+        task_id: TaskId,
+        task_state: &mut TaskState,
+    ) -> anyhow::Result<StdDuration> {
+        let now = self.clock.now();
+        let in_flight_since = match task_state.in_flight_since {
+            Some(in_flight_since) => in_flight_since,
+            None => {
+                task_state.in_flight_since = Some(now);
+                self.state.put(task_id, task_state.clone())?;
+                now
+            }
+        };
+        let elapsed = now
+            .difference(&in_flight_since)
+            .map_or(0, |elapsed| elapsed.as_seconds());
+        Ok(StdDuration::from_secs(
+            self.config.collection_timeout_secs.saturating_sub(elapsed),
+        ))
+    }
+
+    /// Gives up on the task's in-flight collection job, which did not complete within the
+    /// collection timeout, so that the daemon moves on to the next batch. The caller updates
+    /// `task_state` to skip the batch. Returns the error to be reported for this step.
+    fn abandon(
+        &self,
+        task_id: TaskId,
+        mut task_state: TaskState,
+        collection_job_id: CollectionJobId,
+    ) -> anyhow::Result<anyhow::Error> {
+        task_state.in_flight = None;
+        task_state.in_flight_since = None;
+        self.state.put(task_id, task_state)?;
+        self.metrics.record_abandoned(&task_id);
+        Ok(anyhow!(
+            "collection job {collection_job_id} did not complete within {} seconds, abandoned it",
+            self.config.collection_timeout_secs
+        ))
+    }
+
+    /// Creates the collection job, or confirms that it already exists, and waits for its result.
+    /// Returns `None` if the result is not available within `timeout`.
+    async fn collect<V, Q>(
+        &self,
+        collector: &Collector<V>,
+        collection_job_id: CollectionJobId,
+        query: Query<Q>,
+        timeout: StdDuration,
+    ) -> anyhow::Result<Option<Collection<V::AggregateResult, Q>>>
+    where
+        V: vdaf::Collector<AggregationParam = ()>,
+        Q: QueryTypeExt,
+    {
+        tokio::time::timeout(timeout, async {
+            let job = collector
+                .start_collection_with_id(collection_job_id, query, &())
+                .await?;
+            Ok::<_, anyhow::Error>(collector.poll_until_complete(&job).await?)
+        })
+        .await
+        .ok()
+        .transpose()
+    }
+
+    async fn deliver<T, Q>(
+        &self,
+        task: &TaskConfig,
+        name: &str,
+        collection: &Collection<T, Q>,
+    ) -> anyhow::Result<()>
+    where
+        T: Debug + Clone + Into<AggregateResult>,
+        Q: QueryTypeExt,
+    {
+        match &task.delivery {
+            DeliveryConfig::Directory { path, format } => {
+                let mut contents = Vec::new();
+                write_collection(collection, *format, &mut contents)?;
+                let directory = path.join(task.task_id.to_string());
+                fs::create_dir_all(&directory).context("could not create delivery directory")?;
+                let path = directory.join(format!("{name}.{}", format.file_extension()));
+                write_file_atomically(&path, &contents)
+                    .with_context(|| format!("could not write {}", path.display()))
+            }
+            DeliveryConfig::Webhook { url } => {
+                let mut body = Vec::new();
+                write_collection(collection, OutputFormat::Json, &mut body)?;
+                self.http_client
+                    .post(url.clone())
+                    .header(CONTENT_TYPE, "application/json")
+                    .body(body)
+                    .send()
+                    .await
+                    .and_then(|response| response.error_for_status())
+                    .context("could not deliver collection to webhook")?;
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Installs an OpenTelemetry meter provider with a Prometheus exporter, and serves the metrics on
+/// the given address.
+fn install_metrics_exporter(listen_address: SocketAddr) -> anyhow::Result<JoinHandle<()>> {
+    let registry = Registry::new();
+    let exporter = opentelemetry_prometheus::exporter()
+        .with_registry(registry.clone())
+        .build()?;
+    global::set_meter_provider(MeterProvider::builder().with_reader(exporter).build());
+
+    Ok(tokio::task::spawn(
+        trillium_tokio::config()
+            .with_host(&listen_address.ip().to_string())
+            .with_port(listen_address.port())
+            .without_signals()
+            .run_async(trillium_prometheus::text_format_handler(registry)),
+    ))
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    install_tracing_subscriber()?;
+    let options = Options::parse();
+
+    let config_content = fs::read_to_string(&options.config_file)
+        .with_context(|| format!("couldn't read config file {:?}", options.config_file))?;
+    let config: Config = serde_yaml::from_str(&config_content)
+        .with_context(|| format!("couldn't parse config file {:?}", options.config_file))?;
+
+    let _metrics_server = config
+        .metrics_listen_address
+        .map(install_metrics_exporter)
+        .transpose()
+        .context("failed to install metrics exporter")?;
+
+    let daemon = Daemon::new(config, RealClock::default())?;
+    if options.once {
+        return daemon.run_once().await;
+    }
+    tokio::select! {
+        _ = daemon.run() => {},
+        result = tokio::signal::ctrl_c() => result?,
+    }
+    Ok(())
+}
+
+fn install_tracing_subscriber() -> anyhow::Result<()> {
+    let stdout_filter = EnvFilter::builder().from_env()?;
+    let layer = tracing_subscriber::fmt::layer()
+        .with_level(true)
+        .with_target(true)
+        .pretty();
+    let subscriber = SubscriberRegistry::default().with(stdout_filter.and_then(layer));
+    tracing::subscriber::set_global_default(subscriber)?;
+
+    LogTracer::init()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Config, Daemon, DeliveryConfig, InFlightCollection, Options, QueryConfig, State,
+        TaskConfig, TaskState,
+    };
+    use clap::CommandFactory;
+    use janus_collector::AuthenticationToken;
+    use janus_core::{
+        hpke::{
+            self, test_util::SAMPLE_DIVVIUP_HPKE_CONFIG, DivviUpHpkeConfig, HpkeApplicationInfo,
+            HpkeKeypair, Label,
+        },
+        task::VdafInstance,
+        test_util::{install_test_trace_subscriber, run_vdaf},
+        time::{Clock, IntervalExt, MockClock},
+    };
+    use janus_messages::{
+        query_type::{FixedSize, QueryType, TimeInterval},
+        AggregateShareAad, BatchId, BatchSelector, Collection as CollectionMessage,
+        CollectionJobId, Duration, HpkeCiphertext, Interval, PartialBatchSelector, Query, Role,
+        TaskId, Time,
+    };
+    use janus_tools::output::OutputFormat;
+    use mockito::Matcher;
+    use prio::{
+        codec::Encode,
+        vdaf::{self, prio3::Prio3},
+    };
+    use rand::random;
+    use std::{fs, path::Path};
+    use tempfile::TempDir;
+
+    #[test]
+    fn verify_app() {
+        Options::command().debug_assert();
+    }
+
+    #[test]
+    fn sample_config() {
+        let config: Config =
+            serde_yaml::from_str(include_str!("../../../docs/samples/collection_daemon.yaml"))
+                .unwrap();
+        assert_eq!(config.tasks.len(), 2);
+        for task in &config.tasks {
+            task.validate().unwrap();
+        }
+    }
+
+    #[test]
+    fn config_validation() {
+        let task = |query| TaskConfig {
+            task_id: random(),
+            leader: "https://leader.example.com/".parse().unwrap(),
+            authentication_token: AuthenticationToken::DapAuth(random()),
+            hpke_config_json: "hpke_config.json".into(),
+            vdaf: VdafInstance::Prio3Count,
+            query,
+            delivery: DeliveryConfig::Directory {
+                path: "collections".into(),
+                format: OutputFormat::Json,
+            },
+        };
+
+        task(QueryConfig::TimeInterval {
+            time_precision_secs: 3600,
+            batch_duration_secs: Some(7200),
+            start: Some(3600),
+            collection_delay_secs: 0,
+        })
+        .validate()
+        .unwrap();
+        task(QueryConfig::TimeInterval {
+            time_precision_secs: 3600,
+            batch_duration_secs: Some(5400),
+            start: None,
+            collection_delay_secs: 0,
+        })
+        .validate()
+        .unwrap_err();
+        task(QueryConfig::TimeInterval {
+            time_precision_secs: 3600,
+            batch_duration_secs: None,
+            start: Some(1800),
+            collection_delay_secs: 0,
+        })
+        .validate()
+        .unwrap_err();
+        task(QueryConfig::FixedSize {
+            collection_interval_secs: 0,
+        })
+        .validate()
+        .unwrap_err();
+    }
+
+    fn write_hpke_config(directory: &Path) -> HpkeKeypair {
+        fs::write(
+            directory.join("hpke_config.json"),
+            SAMPLE_DIVVIUP_HPKE_CONFIG,
+        )
+        .unwrap();
+        HpkeKeypair::try_from(
+            serde_json::from_str::<DivviUpHpkeConfig>(SAMPLE_DIVVIUP_HPKE_CONFIG).unwrap(),
+        )
+        .unwrap()
+    }
+
+    fn build_config(
+        directory: &TempDir,
+        leader: &str,
+        task_id: TaskId,
+        query: QueryConfig,
+        delivery: DeliveryConfig,
+    ) -> Config {
+        Config {
+            state_path: directory.path().join("state.json"),
+            poll_interval_secs: 60,
+            collection_timeout_secs: 86400,
+            metrics_listen_address: None,
+            tasks: Vec::from([TaskConfig {
+                task_id,
+                leader: leader.parse().unwrap(),
+                authentication_token: AuthenticationToken::DapAuth(random()),
+                hpke_config_json: directory.path().join("hpke_config.json"),
+                vdaf: VdafInstance::Prio3Count,
+                query,
+                delivery,
+            }]),
+        }
+    }
+
+    fn seal_aggregate_shares<V, Q>(
+        vdaf: &V,
+        measurement: &V::Measurement,
+        hpke_keypair: &HpkeKeypair,
+        aad: &AggregateShareAad<Q>,
+    ) -> [HpkeCiphertext; 2]
+    where
+        V: vdaf::Aggregator<16, 16, AggregationParam = ()> + vdaf::Client<16>,
+        Q: QueryType,
+    {
+        let transcript = run_vdaf(vdaf, &random(), &(), &random(), measurement);
+        [Role::Leader, Role::Helper].map(|role| {
+            hpke::seal(
+                hpke_keypair.config(),
+                &HpkeApplicationInfo::new(&Label::AggregateShare, &role, &Role::Collector),
+                &transcript.aggregate_shares[role.index().unwrap()].get_encoded(),
+                &aad.get_encoded(),
+            )
+            .unwrap()
+        })
+    }
+
+    fn read_state(directory: &TempDir) -> State {
+        serde_json::from_slice(&fs::read(directory.path().join("state.json")).unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn time_interval_collection() {
+        install_test_trace_subscriber();
+        let mut server = mockito::Server::new_async().await;
+        let directory = tempfile::tempdir().unwrap();
+        let hpke_keypair = write_hpke_config(directory.path());
+        let task_id: TaskId = random();
+        let results_path = directory.path().join("collections");
+        let config = build_config(
+            &directory,
+            &server.url(),
+            task_id,
+            QueryConfig::TimeInterval {
+                time_precision_secs: 3600,
+                batch_duration_secs: None,
+                start: None,
+                collection_delay_secs: 600,
+            },
+            DeliveryConfig::Directory {
+                path: results_path.clone(),
+                format: OutputFormat::Json,
+            },
+        );
+
+        // The clock starts within the batch interval [999997200, 1000000800).
+        let clock = MockClock::default();
+        let batch_interval = Interval::new(
+            Time::from_seconds_since_epoch(999_997_200),
+            Duration::from_seconds(3600),
+        )
+        .unwrap();
+        let [leader_share, helper_share] = seal_aggregate_shares(
+            &Prio3::new_count(2).unwrap(),
+            &1,
+            &hpke_keypair,
+            &AggregateShareAad::new(task_id, BatchSelector::new_time_interval(batch_interval)),
+        );
+        let collection_job_path = format!("^/tasks/{task_id}/collection_jobs/[A-Za-z0-9-_]{{22}}$");
+        let mocked_put = server
+            .mock("PUT", Matcher::Regex(collection_job_path.clone()))
+            .with_status(201)
+            .expect(1)
+            .create_async()
+            .await;
+        let mocked_post = server
+            .mock("POST", Matcher::Regex(collection_job_path))
+            .with_status(200)
+            .with_header(
+                "Content-Type",
+                CollectionMessage::<TimeInterval>::MEDIA_TYPE,
+            )
+            .with_body(
+                CollectionMessage::new(
+                    PartialBatchSelector::new_time_interval(),
+                    1,
+                    batch_interval,
+                    leader_share,
+                    helper_share,
+                )
+                .get_encoded(),
+            )
+            .expect(1)
+            .create_async()
+            .await;
+
+        let daemon = Daemon::new(config, clock.clone()).unwrap();
+
+        // Nothing is due until the batch interval and the collection delay have passed.
+        daemon.run_once().await.unwrap();
+        clock.advance(&Duration::from_seconds(1200));
+        daemon.run_once().await.unwrap();
+        clock.advance(&Duration::from_seconds(600));
+        daemon.run_once().await.unwrap();
+        // The next batch interval is not yet due.
+        daemon.run_once().await.unwrap();
+
+        mocked_put.assert_async().await;
+        mocked_post.assert_async().await;
+
+        let result: serde_json::Value = serde_json::from_slice(
+            &fs::read(
+                results_path
+                    .join(task_id.to_string())
+                    .join("999997200-3600.json"),
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(result["report_count"], 1);
+        assert_eq!(result["aggregate_result"], 1);
+
+        assert_eq!(
+            read_state(&directory).tasks[&task_id],
+            TaskState {
+                next_batch_start: Some(Time::from_seconds_since_epoch(1_000_000_800)),
+                last_collection_time: Some(clock.now()),
+                in_flight: None,
+                in_flight_since: None,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn resume_in_flight_collection() {
+        install_test_trace_subscriber();
+        let mut server = mockito::Server::new_async().await;
+        let directory = tempfile::tempdir().unwrap();
+        let hpke_keypair = write_hpke_config(directory.path());
+        let task_id: TaskId = random();
+        let config = build_config(
+            &directory,
+            &server.url(),
+            task_id,
+            QueryConfig::TimeInterval {
+                time_precision_secs: 3600,
+                batch_duration_secs: None,
+                start: None,
+                collection_delay_secs: 0,
+            },
+            DeliveryConfig::Directory {
+                path: directory.path().join("collections"),
+                format: OutputFormat::Csv,
+            },
+        );
+
+        // Simulate a restart after the collection job was recorded, but before its result was
+        // delivered.
+        let batch_interval = Interval::new(
+            Time::from_seconds_since_epoch(999_990_000),
+            Duration::from_seconds(3600),
+        )
+        .unwrap();
+        let collection_job_id: CollectionJobId = random();
+        let state = State {
+            tasks: [(
+                task_id,
+                TaskState {
+                    next_batch_start: Some(*batch_interval.start()),
+                    last_collection_time: None,
+                    in_flight: Some(InFlightCollection::TimeInterval {
+                        collection_job_id,
+                        query: Query::new_time_interval(batch_interval),
+                    }),
+                    in_flight_since: None,
+                },
+            )]
+            .into(),
+        };
+        fs::write(&config.state_path, serde_json::to_vec(&state).unwrap()).unwrap();
+
+        let [leader_share, helper_share] = seal_aggregate_shares(
+            &Prio3::new_count(2).unwrap(),
+            &0,
+            &hpke_keypair,
+            &AggregateShareAad::new(task_id, BatchSelector::new_time_interval(batch_interval)),
+        );
+        let collection_job_path = format!("/tasks/{task_id}/collection_jobs/{collection_job_id}");
+        let mocked_put = server
+            .mock("PUT", collection_job_path.as_str())
+            .with_status(201)
+            .expect(1)
+            .create_async()
+            .await;
+        let mocked_post = server
+            .mock("POST", collection_job_path.as_str())
+            .with_status(200)
+            .with_header(
+                "Content-Type",
+                CollectionMessage::<TimeInterval>::MEDIA_TYPE,
+            )
+            .with_body(
+                CollectionMessage::new(
+                    PartialBatchSelector::new_time_interval(),
+                    1,
+                    batch_interval,
+                    leader_share,
+                    helper_share,
+                )
+                .get_encoded(),
+            )
+            .expect(1)
+            .create_async()
+            .await;
+
+        // The in-flight collection job is resumed, rather than a new one being created. The
+        // following batch interval is not yet due.
+        let clock = MockClock::new(Time::from_seconds_since_epoch(999_994_000));
+        let daemon = Daemon::new(config, clock.clone()).unwrap();
+        daemon.run_once().await.unwrap();
+
+        mocked_put.assert_async().await;
+        mocked_post.assert_async().await;
+
+        assert_eq!(
+            fs::read_to_string(
+                directory
+                    .path()
+                    .join("collections")
+                    .join(task_id.to_string())
+                    .join("999990000-3600.csv")
+            )
+            .unwrap(),
+            "batch_id,report_count,interval_start,interval_duration,index,value\n\
+             ,1,999990000,3600,,0\n"
+        );
+        assert_eq!(
+            read_state(&directory).tasks[&task_id],
+            TaskState {
+                next_batch_start: Some(Time::from_seconds_since_epoch(999_993_600)),
+                last_collection_time: Some(clock.now()),
+                in_flight: None,
+                in_flight_since: None,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn abandon_stuck_collection() {
+        install_test_trace_subscriber();
+        let server = mockito::Server::new_async().await;
+        let directory = tempfile::tempdir().unwrap();
+        write_hpke_config(directory.path());
+        let task_id: TaskId = random();
+        let mut config = build_config(
+            &directory,
+            &server.url(),
+            task_id,
+            QueryConfig::TimeInterval {
+                time_precision_secs: 3600,
+                batch_duration_secs: None,
+                start: None,
+                collection_delay_secs: 0,
+            },
+            DeliveryConfig::Directory {
+                path: directory.path().join("collections"),
+                format: OutputFormat::Json,
+            },
+        );
+        config.collection_timeout_secs = 3600;
+
+        // The in-flight collection job was recorded longer ago than the collection timeout.
+        let clock = MockClock::new(Time::from_seconds_since_epoch(999_994_000));
+        let batch_interval = Interval::new(
+            Time::from_seconds_since_epoch(999_986_400),
+            Duration::from_seconds(3600),
+        )
+        .unwrap();
+        let state = State {
+            tasks: [(
+                task_id,
+                TaskState {
+                    next_batch_start: Some(*batch_interval.start()),
+                    last_collection_time: None,
+                    in_flight: Some(InFlightCollection::TimeInterval {
+                        collection_job_id: random(),
+                        query: Query::new_time_interval(batch_interval),
+                    }),
+                    in_flight_since: Some(Time::from_seconds_since_epoch(999_990_000)),
+                },
+            )]
+            .into(),
+        };
+        fs::write(&config.state_path, serde_json::to_vec(&state).unwrap()).unwrap();
+
+        // The collection job is abandoned, and its batch interval is skipped.
+        let daemon = Daemon::new(config, clock.clone()).unwrap();
+        daemon.run_once().await.unwrap_err();
+        assert_eq!(
+            read_state(&directory).tasks[&task_id],
+            TaskState {
+                next_batch_start: Some(batch_interval.end()),
+                last_collection_time: None,
+                in_flight: None,
+                in_flight_since: None,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn fixed_size_collection_to_webhook() {
+        install_test_trace_subscriber();
+        let mut server = mockito::Server::new_async().await;
+        let directory = tempfile::tempdir().unwrap();
+        let hpke_keypair = write_hpke_config(directory.path());
+        let task_id: TaskId = random();
+        let config = build_config(
+            &directory,
+            &server.url(),
+            task_id,
+            QueryConfig::FixedSize {
+                collection_interval_secs: 3600,
+            },
+            DeliveryConfig::Webhook {
+                url: format!("{}/webhook", server.url()).parse().unwrap(),
+            },
+        );
+
+        let batch_id: BatchId = random();
+        let batch_interval = Interval::new(
+            Time::from_seconds_since_epoch(999_997_200),
+            Duration::from_seconds(3600),
+        )
+        .unwrap();
+        let [leader_share, helper_share] = seal_aggregate_shares(
+            &Prio3::new_count(2).unwrap(),
+            &1,
+            &hpke_keypair,
+            &AggregateShareAad::new(task_id, BatchSelector::new_fixed_size(batch_id)),
+        );
+        let collection_job_path = format!("^/tasks/{task_id}/collection_jobs/[A-Za-z0-9-_]{{22}}$");
+        let mocked_put = server
+            .mock("PUT", Matcher::Regex(collection_job_path.clone()))
+            .with_status(201)
+            .expect(1)
+            .create_async()
+            .await;
+        let mocked_post = server
+            .mock("POST", Matcher::Regex(collection_job_path))
+            .with_status(200)
+            .with_header("Content-Type", CollectionMessage::<FixedSize>::MEDIA_TYPE)
+            .with_body(
+                CollectionMessage::new(
+                    PartialBatchSelector::new_fixed_size(batch_id),
+                    1,
+                    batch_interval,
+                    leader_share,
+                    helper_share,
+                )
+                .get_encoded(),
+            )
+            .expect(1)
+            .create_async()
+            .await;
+        let mocked_webhook = server
+            .mock("POST", "/webhook")
+            .match_header("Content-Type", "application/json")
+            .match_body(Matcher::PartialJson(serde_json::json!({
+                "partial_batch_selector": {"batch_identifier": batch_id},
+                "report_count": 1,
+                "aggregate_result": 1,
+            })))
+            .with_status(200)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let clock = MockClock::default();
+        let daemon = Daemon::new(config, clock.clone()).unwrap();
+        daemon.run_once().await.unwrap();
+        // The collection interval has not elapsed since the last collection.
+        clock.advance(&Duration::from_seconds(1800));
+        daemon.run_once().await.unwrap();
+
+        mocked_put.assert_async().await;
+        mocked_post.assert_async().await;
+        mocked_webhook.assert_async().await;
+
+        assert_eq!(
+            read_state(&directory).tasks[&task_id],
+            TaskState {
+                next_batch_start: None,
+                last_collection_time: Some(Time::from_seconds_since_epoch(1_000_000_000)),
+                in_flight: None,
+                in_flight_since: None,
+            }
+        );
+    }
+}
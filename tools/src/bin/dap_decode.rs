@@ -1,109 +1,247 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
 use clap::{Parser, ValueEnum};
 use janus_messages::{
+    problem_type::DapProblemType,
     query_type::{FixedSize, TimeInterval},
-    AggregateShare, AggregateShareReq, AggregationJobContinueReq, AggregationJobInitializeReq,
-    AggregationJobResp, Collection, CollectionReq, HpkeConfig, HpkeConfigList, Report,
+    taskprov::TaskConfig,
+    AggregateShare, AggregateShareAad, AggregateShareReq, AggregationJobContinueReq,
+    AggregationJobInitializeReq, AggregationJobResp, Collection, CollectionReq, HpkeConfig,
+    HpkeConfigList, InputShareAad, PlaintextInputShare, Report, TaskId,
 };
-use prio::codec::Decode;
+use prio::codec::{Decode, Encode};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::{
     fmt::Debug,
     fs::File,
-    io::{stdin, Read},
+    io::{stdin, stdout, Read, Write},
 };
 
 fn main() -> Result<()> {
     let options = Options::parse();
 
-    let decoded = decode_dap_message(&options.message_file, &options.media_type)?;
-    println!("{decoded:#?}");
+    let input = read_input(&options.message_file)?;
+    let mut writer = stdout().lock();
+
+    if options.encode {
+        let encoded = encode_dap_message(&input, &options.media_type, options.query_type.as_ref())?;
+        match options.encoding {
+            Encoding::Binary => writer.write_all(&encoded)?,
+            Encoding::Base64Url => writeln!(writer, "{}", URL_SAFE_NO_PAD.encode(encoded))?,
+        }
+    } else {
+        let message = match options.encoding {
+            Encoding::Binary => input,
+            Encoding::Base64Url => decode_base64url(&input)?,
+        };
+        let decoded =
+            decode_dap_message(&message, &options.media_type, options.query_type.as_ref())?;
+        match options.output {
+            OutputFormat::Debug => writeln!(writer, "{decoded:#?}")?,
+            OutputFormat::Json => writeln!(writer, "{}", decoded.to_json()?)?,
+        }
+    }
 
     Ok(())
 }
 
-/// Decode the contents of `message_file` as a DAP message with `media_type`, returning the decoded
-/// object.
-fn decode_dap_message(message_file: &str, media_type: &MediaType) -> Result<Box<dyn Debug>> {
+/// Read the entire contents of `message_file`, or of stdin if `message_file` is "-".
+fn read_input(message_file: &str) -> Result<Vec<u8>> {
     let mut reader = if message_file.eq("-") {
         Box::new(stdin()) as Box<dyn Read>
     } else {
         Box::new(File::open(message_file)?) as Box<dyn Read>
     };
 
-    let mut message_buf = Vec::new();
-    reader.read_to_end(&mut message_buf)?;
+    let mut buf = Vec::new();
+    reader.read_to_end(&mut buf)?;
+    Ok(buf)
+}
 
-    let decoded: Box<dyn Debug> = match media_type {
-        MediaType::HpkeConfig => {
-            let message: HpkeConfig = HpkeConfig::get_decoded(&message_buf)?;
-            Box::new(message)
-        }
-        MediaType::HpkeConfigList => {
-            let message: HpkeConfigList = HpkeConfigList::get_decoded(&message_buf)?;
-            Box::new(message)
-        }
-        MediaType::Report => {
-            let message: Report = Report::get_decoded(&message_buf)?;
-            Box::new(message)
-        }
-        MediaType::AggregationJobInitializeReq => {
-            if let Ok(decoded) =
-                AggregationJobInitializeReq::<TimeInterval>::get_decoded(&message_buf)
-            {
-                let message: AggregationJobInitializeReq<TimeInterval> = decoded;
-                Box::new(message)
-            } else {
-                let message: AggregationJobInitializeReq<FixedSize> =
-                    AggregationJobInitializeReq::<FixedSize>::get_decoded(&message_buf)?;
-                Box::new(message)
-            }
-        }
-        MediaType::AggregationJobContinueReq => {
-            let message: AggregationJobContinueReq =
-                AggregationJobContinueReq::get_decoded(&message_buf)?;
-            Box::new(message)
-        }
-        MediaType::AggregationJobResp => {
-            let message: AggregationJobResp = AggregationJobResp::get_decoded(&message_buf)?;
-            Box::new(message)
-        }
-        MediaType::AggregateShareReq => {
-            if let Ok(decoded) = AggregateShareReq::<TimeInterval>::get_decoded(&message_buf) {
-                let message: AggregateShareReq<TimeInterval> = decoded;
-                Box::new(message)
-            } else {
-                let message: AggregateShareReq<FixedSize> =
-                    AggregateShareReq::<FixedSize>::get_decoded(&message_buf)?;
-                Box::new(message)
-            }
-        }
-        MediaType::AggregateShare => {
-            let message: AggregateShare = AggregateShare::get_decoded(&message_buf)?;
-            Box::new(message)
-        }
-        MediaType::CollectionReq => {
-            if let Ok(decoded) = CollectionReq::<TimeInterval>::get_decoded(&message_buf) {
-                let message: CollectionReq<TimeInterval> = decoded;
-                Box::new(message)
-            } else {
-                let message: CollectionReq<FixedSize> =
-                    CollectionReq::<FixedSize>::get_decoded(&message_buf)?;
-                Box::new(message)
-            }
+/// Decode base64url-encoded input. Surrounding whitespace and padding are tolerated, so that
+/// values copied from HTTP headers or logs can be used as-is.
+fn decode_base64url(input: &[u8]) -> Result<Vec<u8>> {
+    let input = std::str::from_utf8(input).context("base64url input is not valid UTF-8")?;
+    URL_SAFE_NO_PAD
+        .decode(input.trim().trim_end_matches('='))
+        .context("invalid base64url input")
+}
+
+/// A decoded message, which can be displayed either in Rust's debug format or as JSON.
+trait DecodedMessage: Debug {
+    fn to_json(&self) -> serde_json::Result<String>;
+}
+
+impl<T: Debug + Serialize> DecodedMessage for T {
+    fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+}
+
+/// Decode `message` as a DAP message with `media_type`, returning the decoded object. Messages
+/// which depend on the task's query type are decoded as `query_type` if it is provided, and
+/// otherwise as whichever query type succeeds.
+fn decode_dap_message(
+    message: &[u8],
+    media_type: &MediaType,
+    query_type: Option<&QueryType>,
+) -> Result<Box<dyn DecodedMessage>> {
+    match media_type {
+        MediaType::HpkeConfig => decode::<HpkeConfig>(message),
+        MediaType::HpkeConfigList => decode::<HpkeConfigList>(message),
+        MediaType::Report => decode::<Report>(message),
+        MediaType::AggregationJobInitializeReq => decode_query_typed::<
+            AggregationJobInitializeReq<TimeInterval>,
+            AggregationJobInitializeReq<FixedSize>,
+        >(message, query_type),
+        MediaType::AggregationJobContinueReq => decode::<AggregationJobContinueReq>(message),
+        MediaType::AggregationJobResp => decode::<AggregationJobResp>(message),
+        MediaType::AggregateShareReq => decode_query_typed::<
+            AggregateShareReq<TimeInterval>,
+            AggregateShareReq<FixedSize>,
+        >(message, query_type),
+        MediaType::AggregateShare => decode::<AggregateShare>(message),
+        MediaType::CollectionReq => decode_query_typed::<
+            CollectionReq<TimeInterval>,
+            CollectionReq<FixedSize>,
+        >(message, query_type),
+        MediaType::Collection => decode_query_typed::<
+            Collection<TimeInterval>,
+            Collection<FixedSize>,
+        >(message, query_type),
+        MediaType::PlaintextInputShare => decode::<PlaintextInputShare>(message),
+        MediaType::InputShareAad => decode::<InputShareAad>(message),
+        MediaType::AggregateShareAad => decode_query_typed::<
+            AggregateShareAad<TimeInterval>,
+            AggregateShareAad<FixedSize>,
+        >(message, query_type),
+        MediaType::TaskConfig => decode::<TaskConfig>(message),
+        MediaType::ProblemDocument => {
+            let document: ProblemDocument = serde_json::from_slice(message)?;
+            Ok(Box::new(document))
         }
+    }
+}
+
+fn decode<M>(message: &[u8]) -> Result<Box<dyn DecodedMessage>>
+where
+    M: Decode + Debug + Serialize + 'static,
+{
+    Ok(Box::new(M::get_decoded(message)?))
+}
+
+fn decode_query_typed<T, F>(
+    message: &[u8],
+    query_type: Option<&QueryType>,
+) -> Result<Box<dyn DecodedMessage>>
+where
+    T: Decode + Debug + Serialize + 'static,
+    F: Decode + Debug + Serialize + 'static,
+{
+    match query_type {
+        Some(QueryType::TimeInterval) => decode::<T>(message),
+        Some(QueryType::FixedSize) => decode::<F>(message),
+        None => decode::<T>(message).or_else(|_| decode::<F>(message)),
+    }
+}
+
+/// Encode the JSON representation of a DAP message with `media_type`, as produced by `--output
+/// json`, into its binary form. Messages which depend on the task's query type are parsed as
+/// `query_type` if it is provided, and otherwise as whichever query type succeeds.
+fn encode_dap_message(
+    json: &[u8],
+    media_type: &MediaType,
+    query_type: Option<&QueryType>,
+) -> Result<Vec<u8>> {
+    match media_type {
+        MediaType::HpkeConfig => encode::<HpkeConfig>(json),
+        MediaType::HpkeConfigList => encode::<HpkeConfigList>(json),
+        MediaType::Report => encode::<Report>(json),
+        MediaType::AggregationJobInitializeReq => encode_query_typed::<
+            AggregationJobInitializeReq<TimeInterval>,
+            AggregationJobInitializeReq<FixedSize>,
+        >(json, query_type),
+        MediaType::AggregationJobContinueReq => encode::<AggregationJobContinueReq>(json),
+        MediaType::AggregationJobResp => encode::<AggregationJobResp>(json),
+        MediaType::AggregateShareReq => encode_query_typed::<
+            AggregateShareReq<TimeInterval>,
+            AggregateShareReq<FixedSize>,
+        >(json, query_type),
+        MediaType::AggregateShare => encode::<AggregateShare>(json),
+        MediaType::CollectionReq => encode_query_typed::<
+            CollectionReq<TimeInterval>,
+            CollectionReq<FixedSize>,
+        >(json, query_type),
         MediaType::Collection => {
-            if let Ok(decoded) = Collection::<TimeInterval>::get_decoded(&message_buf) {
-                let message: Collection<TimeInterval> = decoded;
-                Box::new(message)
-            } else {
-                let message: Collection<FixedSize> =
-                    Collection::<FixedSize>::get_decoded(&message_buf)?;
-                Box::new(message)
-            }
+            encode_query_typed::<Collection<TimeInterval>, Collection<FixedSize>>(json, query_type)
         }
-    };
+        MediaType::PlaintextInputShare => encode::<PlaintextInputShare>(json),
+        MediaType::InputShareAad => encode::<InputShareAad>(json),
+        MediaType::AggregateShareAad => encode_query_typed::<
+            AggregateShareAad<TimeInterval>,
+            AggregateShareAad<FixedSize>,
+        >(json, query_type),
+        MediaType::TaskConfig => encode::<TaskConfig>(json),
+        MediaType::ProblemDocument => {
+            let document: ProblemDocument = serde_json::from_slice(json)?;
+            Ok(serde_json::to_vec(&document)?)
+        }
+    }
+}
 
-    Ok(decoded)
+fn encode<M>(json: &[u8]) -> Result<Vec<u8>>
+where
+    M: DeserializeOwned + Encode,
+{
+    Ok(serde_json::from_slice::<M>(json)?.get_encoded())
+}
+
+fn encode_query_typed<T, F>(json: &[u8], query_type: Option<&QueryType>) -> Result<Vec<u8>>
+where
+    T: DeserializeOwned + Encode,
+    F: DeserializeOwned + Encode,
+{
+    match query_type {
+        Some(QueryType::TimeInterval) => encode::<T>(json),
+        Some(QueryType::FixedSize) => encode::<F>(json),
+        None => encode::<T>(json).or_else(|_| encode::<F>(json)),
+    }
+}
+
+/// A problem details document, as defined in RFC 7807, including the extension members used by
+/// DAP.
+#[derive(Serialize, Deserialize)]
+struct ProblemDocument {
+    #[serde(rename = "type")]
+    type_: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    status: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    detail: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    instance: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    taskid: Option<TaskId>,
+}
+
+impl Debug for ProblemDocument {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Identify the DAP problem type, if the type URI is one defined by DAP.
+        f.debug_struct("ProblemDocument")
+            .field("type", &self.type_)
+            .field(
+                "dap_problem_type",
+                &self.type_.parse::<DapProblemType>().ok(),
+            )
+            .field("title", &self.title)
+            .field("status", &self.status)
+            .field("detail", &self.detail)
+            .field("instance", &self.instance)
+            .field("taskid", &self.taskid)
+            .finish()
+    }
 }
 
 #[derive(Debug, Clone, ValueEnum)]
@@ -129,6 +267,45 @@ enum MediaType {
     CollectionReq,
     #[value(name = "collection")]
     Collection,
+    #[value(name = "plaintext-input-share")]
+    PlaintextInputShare,
+    #[value(name = "input-share-aad")]
+    InputShareAad,
+    #[value(name = "aggregate-share-aad")]
+    AggregateShareAad,
+    /// The value of the taskprov `dap-taskprov` header.
+    #[value(name = "task-config")]
+    TaskConfig,
+    /// A problem details document, as returned in error responses.
+    #[value(name = "problem-document")]
+    ProblemDocument,
+}
+
+#[derive(Debug, Clone, ValueEnum)]
+#[value()]
+enum QueryType {
+    #[value(name = "time-interval")]
+    TimeInterval,
+    #[value(name = "fixed-size")]
+    FixedSize,
+}
+
+#[derive(Debug, Clone, ValueEnum)]
+#[value()]
+enum Encoding {
+    #[value(name = "binary")]
+    Binary,
+    #[value(name = "base64url")]
+    Base64Url,
+}
+
+#[derive(Debug, Clone, ValueEnum)]
+#[value()]
+enum OutputFormat {
+    #[value(name = "debug")]
+    Debug,
+    #[value(name = "json")]
+    Json,
 }
 
 #[derive(Debug, Parser)]
@@ -145,15 +322,88 @@ struct Options {
     /// Media type of the message to decode.
     #[arg(long, short = 't', required = true)]
     media_type: MediaType,
+
+    /// Query type of the message, for messages that depend on the task's query type. If not
+    /// provided, each query type is tried in turn.
+    #[arg(long, short = 'q')]
+    query_type: Option<QueryType>,
+
+    /// Encoding of the binary form of the message: the input when decoding, or the output when
+    /// encoding.
+    #[arg(long, short = 'e', default_value = "binary")]
+    encoding: Encoding,
+
+    /// Format in which to print the decoded message.
+    #[arg(long, short = 'o', default_value = "debug", conflicts_with = "encode")]
+    output: OutputFormat,
+
+    /// Encode a message from its JSON representation, as printed by `--output json`, instead of
+    /// decoding it.
+    #[arg(long)]
+    encode: bool,
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::Options;
+    use crate::{decode_dap_message, encode_dap_message, MediaType, Options, QueryType};
     use clap::CommandFactory;
+    use janus_messages::{
+        query_type::FixedSize, AggregateShareAad, BatchSelector, CollectionReq, FixedSizeQuery,
+        HpkeCiphertext, HpkeConfigId, Query, Report, ReportMetadata, Time,
+    };
+    use prio::codec::Encode;
+    use rand::random;
 
     #[test]
     fn verify_clap_app() {
         Options::command().debug_assert();
     }
+
+    #[test]
+    fn json_roundtrip() {
+        let report = Report::new(
+            ReportMetadata::new(random(), Time::from_seconds_since_epoch(1234)),
+            Vec::from("public share"),
+            HpkeCiphertext::new(HpkeConfigId::from(1), Vec::from("key"), Vec::from("leader")),
+            HpkeCiphertext::new(HpkeConfigId::from(2), Vec::from("key"), Vec::from("helper")),
+        );
+        let collection_req = CollectionReq::<FixedSize>::new(
+            Query::new_fixed_size(FixedSizeQuery::ByBatchId { batch_id: random() }),
+            Vec::new(),
+        );
+
+        for (message, media_type) in [
+            (report.get_encoded(), MediaType::Report),
+            (collection_req.get_encoded(), MediaType::CollectionReq),
+        ] {
+            let json = decode_dap_message(&message, &media_type, None)
+                .unwrap()
+                .to_json()
+                .unwrap();
+            let encoded = encode_dap_message(json.as_bytes(), &media_type, None).unwrap();
+            assert_eq!(encoded, message);
+        }
+    }
+
+    #[test]
+    fn explicit_query_type() {
+        let message = AggregateShareAad::new(
+            random(),
+            BatchSelector::<FixedSize>::new_fixed_size(random()),
+        )
+        .get_encoded();
+
+        decode_dap_message(
+            &message,
+            &MediaType::AggregateShareAad,
+            Some(&QueryType::FixedSize),
+        )
+        .unwrap();
+        decode_dap_message(
+            &message,
+            &MediaType::AggregateShareAad,
+            Some(&QueryType::TimeInterval),
+        )
+        .unwrap_err();
+    }
 }
@@ -0,0 +1,643 @@
+use anyhow::{anyhow, bail, Context, Result};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use clap::{
+    builder::{NonEmptyStringValueParser, PossibleValuesParser, TypedValueParser},
+    error::ErrorKind,
+    Parser, ValueEnum,
+};
+use derivative::Derivative;
+use janus_core::{
+    hpke::{self, DivviUpHpkeConfig, HpkeApplicationInfo, HpkeKeypair, HpkePrivateKey, Label},
+    task::VdafInstance,
+    vdaf_dispatch,
+};
+use janus_messages::{
+    query_type::{FixedSize, QueryType, TimeInterval},
+    AggregateShare, AggregateShareAad, BatchId, BatchSelector, Collection, Duration, Extension,
+    HpkeCiphertext, HpkeConfig, InputShareAad, Interval, PartialBatchSelector, PlaintextInputShare,
+    Report, ReportMetadata, Role, TaskId, Time,
+};
+use prio::{
+    codec::{Decode, Encode, ParameterizedDecode},
+    vdaf,
+};
+use std::{
+    fmt::Debug,
+    fs::File,
+    io::{stdin, Read},
+    path::PathBuf,
+};
+
+fn main() -> Result<()> {
+    let options = Options::parse();
+
+    let message = read_message(&options.message_file, &options.encoding)?;
+    let keypair = options.hpke_keypair()?;
+
+    vdaf_dispatch!(&options.vdaf, (vdaf, VdafType, VERIFY_KEY_LENGTH) => {
+        print_decrypted_message::<VERIFY_KEY_LENGTH, VdafType>(&options, &keypair, &vdaf, &message)
+    })
+}
+
+/// Read a message from `message_file`, or from stdin if `message_file` is "-".
+fn read_message(message_file: &str, encoding: &Encoding) -> Result<Vec<u8>> {
+    let mut reader = if message_file.eq("-") {
+        Box::new(stdin()) as Box<dyn Read>
+    } else {
+        Box::new(File::open(message_file)?) as Box<dyn Read>
+    };
+
+    let mut buf = Vec::new();
+    reader.read_to_end(&mut buf)?;
+
+    match encoding {
+        Encoding::Binary => Ok(buf),
+        Encoding::Base64Url => {
+            let input = std::str::from_utf8(&buf).context("base64url input is not valid UTF-8")?;
+            URL_SAFE_NO_PAD
+                .decode(input.trim().trim_end_matches('='))
+                .context("invalid base64url input")
+        }
+    }
+}
+
+fn print_decrypted_message<const SEED_SIZE: usize, A: vdaf::Vdaf>(
+    options: &Options,
+    keypair: &HpkeKeypair,
+    vdaf: &A,
+    message: &[u8],
+) -> Result<()> {
+    match options.media_type {
+        MediaType::Report => {
+            let role = options
+                .role
+                .ok_or_else(|| anyhow!("--role is required to decrypt a report"))?;
+            let report = decrypt_report(vdaf, &options.task_id, &role, keypair, message)?;
+            println!("{report:#?}");
+        }
+        MediaType::AggregateShare => match options.batch_selector()? {
+            EitherBatchSelector::TimeInterval(batch_selector) => {
+                let aggregate_share = decrypt_aggregate_share(
+                    vdaf,
+                    &options.task_id,
+                    batch_selector,
+                    &options.aggregation_param()?,
+                    keypair,
+                    message,
+                )?;
+                println!("{aggregate_share:#?}");
+            }
+            EitherBatchSelector::FixedSize(batch_selector) => {
+                let aggregate_share = decrypt_aggregate_share(
+                    vdaf,
+                    &options.task_id,
+                    batch_selector,
+                    &options.aggregation_param()?,
+                    keypair,
+                    message,
+                )?;
+                println!("{aggregate_share:#?}");
+            }
+        },
+        MediaType::Collection => match options.batch_selector()? {
+            EitherBatchSelector::TimeInterval(batch_selector) => {
+                let collection = decrypt_collection(
+                    vdaf,
+                    &options.task_id,
+                    batch_selector,
+                    &options.aggregation_param()?,
+                    keypair,
+                    message,
+                )?;
+                println!("{collection:#?}");
+            }
+            EitherBatchSelector::FixedSize(batch_selector) => {
+                let collection = decrypt_collection(
+                    vdaf,
+                    &options.task_id,
+                    batch_selector,
+                    &options.aggregation_param()?,
+                    keypair,
+                    message,
+                )?;
+                println!("{collection:#?}");
+            }
+        },
+    }
+
+    Ok(())
+}
+
+/// A client report, with the input share for one aggregator decrypted and decoded.
+#[allow(dead_code)]
+// ^^ fields are only read via the Debug implementation, to print them
+#[derive(Debug)]
+struct DecryptedReport<P, I> {
+    metadata: ReportMetadata,
+    public_share: P,
+    extensions: Vec<Extension>,
+    input_share: I,
+}
+
+/// Decrypt and decode the input share for the aggregator with `role` in an encoded [`Report`].
+fn decrypt_report<A: vdaf::Vdaf>(
+    vdaf: &A,
+    task_id: &TaskId,
+    role: &Role,
+    keypair: &HpkeKeypair,
+    message: &[u8],
+) -> Result<DecryptedReport<A::PublicShare, A::InputShare>> {
+    let report = Report::get_decoded(message).context("could not decode report")?;
+    let (ciphertext, aggregator_index) = match role {
+        Role::Leader => (report.leader_encrypted_input_share(), 0),
+        Role::Helper => (report.helper_encrypted_input_share(), 1),
+        _ => bail!("reports can only be decrypted by the leader or the helper"),
+    };
+
+    let plaintext = open(
+        keypair,
+        &HpkeApplicationInfo::new(&Label::InputShare, &Role::Client, role),
+        ciphertext,
+        &InputShareAad::new(
+            *task_id,
+            report.metadata().clone(),
+            report.public_share().to_vec(),
+        )
+        .get_encoded(),
+    )?;
+    let plaintext_input_share = PlaintextInputShare::get_decoded(&plaintext)
+        .context("could not decode plaintext input share")?;
+
+    Ok(DecryptedReport {
+        metadata: report.metadata().clone(),
+        public_share: A::PublicShare::get_decoded_with_param(vdaf, report.public_share())
+            .context("could not decode public share")?,
+        extensions: plaintext_input_share.extensions().to_vec(),
+        input_share: A::InputShare::get_decoded_with_param(
+            &(vdaf, aggregator_index),
+            plaintext_input_share.payload(),
+        )
+        .context("could not decode input share")?,
+    })
+}
+
+/// Decrypt and decode the helper's aggregate share in an encoded [`AggregateShare`].
+fn decrypt_aggregate_share<Q: QueryType, A: vdaf::Vdaf>(
+    vdaf: &A,
+    task_id: &TaskId,
+    batch_selector: BatchSelector<Q>,
+    aggregation_param: &A::AggregationParam,
+    keypair: &HpkeKeypair,
+    message: &[u8],
+) -> Result<A::AggregateShare> {
+    let aggregate_share =
+        AggregateShare::get_decoded(message).context("could not decode aggregate share")?;
+    open_aggregate_share(
+        vdaf,
+        &Role::Helper,
+        &AggregateShareAad::new(*task_id, batch_selector),
+        aggregation_param,
+        keypair,
+        aggregate_share.encrypted_aggregate_share(),
+    )
+}
+
+/// A collection, with both aggregate shares decrypted and decoded.
+#[allow(dead_code)]
+// ^^ fields are only read via the Debug implementation, to print them
+#[derive(Debug)]
+struct DecryptedCollection<Q: QueryType, S> {
+    partial_batch_selector: PartialBatchSelector<Q>,
+    report_count: u64,
+    interval: Interval,
+    leader_aggregate_share: S,
+    helper_aggregate_share: S,
+}
+
+/// Decrypt and decode both aggregate shares in an encoded [`Collection`].
+fn decrypt_collection<Q: QueryType, A: vdaf::Vdaf>(
+    vdaf: &A,
+    task_id: &TaskId,
+    batch_selector: BatchSelector<Q>,
+    aggregation_param: &A::AggregationParam,
+    keypair: &HpkeKeypair,
+    message: &[u8],
+) -> Result<DecryptedCollection<Q, A::AggregateShare>> {
+    let collection =
+        Collection::<Q>::get_decoded(message).context("could not decode collection")?;
+    let aad = AggregateShareAad::new(*task_id, batch_selector);
+
+    Ok(DecryptedCollection {
+        partial_batch_selector: collection.partial_batch_selector().clone(),
+        report_count: collection.report_count(),
+        interval: *collection.interval(),
+        leader_aggregate_share: open_aggregate_share(
+            vdaf,
+            &Role::Leader,
+            &aad,
+            aggregation_param,
+            keypair,
+            collection.leader_encrypted_aggregate_share(),
+        )?,
+        helper_aggregate_share: open_aggregate_share(
+            vdaf,
+            &Role::Helper,
+            &aad,
+            aggregation_param,
+            keypair,
+            collection.helper_encrypted_aggregate_share(),
+        )?,
+    })
+}
+
+/// Decrypt and decode an aggregate share sent from the aggregator with `sender_role` to the
+/// collector.
+fn open_aggregate_share<Q: QueryType, A: vdaf::Vdaf>(
+    vdaf: &A,
+    sender_role: &Role,
+    aad: &AggregateShareAad<Q>,
+    aggregation_param: &A::AggregationParam,
+    keypair: &HpkeKeypair,
+    ciphertext: &HpkeCiphertext,
+) -> Result<A::AggregateShare> {
+    let plaintext = open(
+        keypair,
+        &HpkeApplicationInfo::new(&Label::AggregateShare, sender_role, &Role::Collector),
+        ciphertext,
+        &aad.get_encoded(),
+    )?;
+    A::AggregateShare::get_decoded_with_param(&(vdaf, aggregation_param), &plaintext)
+        .with_context(|| format!("could not decode {sender_role} aggregate share"))
+}
+
+fn open(
+    keypair: &HpkeKeypair,
+    application_info: &HpkeApplicationInfo,
+    ciphertext: &HpkeCiphertext,
+    associated_data: &[u8],
+) -> Result<Vec<u8>> {
+    if ciphertext.config_id() != keypair.config().id() {
+        bail!(
+            "ciphertext was encrypted to HPKE config {}, but the provided key is for HPKE config {}",
+            ciphertext.config_id(),
+            keypair.config().id()
+        );
+    }
+    hpke::open(
+        keypair.config(),
+        keypair.private_key(),
+        application_info,
+        ciphertext,
+        associated_data,
+    )
+    .context("could not decrypt ciphertext")
+}
+
+/// Batch selector for either query type, as determined by the command-line arguments.
+enum EitherBatchSelector {
+    TimeInterval(BatchSelector<TimeInterval>),
+    FixedSize(BatchSelector<FixedSize>),
+}
+
+#[derive(Debug, Clone, ValueEnum)]
+#[value()]
+enum MediaType {
+    #[value(name = "report")]
+    Report,
+    #[value(name = "aggregate-share")]
+    AggregateShare,
+    #[value(name = "collection")]
+    Collection,
+}
+
+#[derive(Debug, Clone, ValueEnum)]
+#[value()]
+enum Encoding {
+    #[value(name = "binary")]
+    Binary,
+    #[value(name = "base64url")]
+    Base64Url,
+}
+
+#[derive(Clone)]
+struct Base64UrlValueParser<T> {
+    inner: NonEmptyStringValueParser,
+    convert: fn(Vec<u8>) -> Result<T, String>,
+}
+
+impl<T> Base64UrlValueParser<T> {
+    fn new(convert: fn(Vec<u8>) -> Result<T, String>) -> Self {
+        Self {
+            inner: NonEmptyStringValueParser::new(),
+            convert,
+        }
+    }
+}
+
+impl<T: Clone + Send + Sync + 'static> TypedValueParser for Base64UrlValueParser<T> {
+    type Value = T;
+
+    fn parse_ref(
+        &self,
+        cmd: &clap::Command,
+        arg: Option<&clap::Arg>,
+        value: &std::ffi::OsStr,
+    ) -> Result<Self::Value, clap::Error> {
+        let input = self.inner.parse_ref(cmd, arg, value)?;
+        let bytes = URL_SAFE_NO_PAD
+            .decode(input)
+            .map_err(|err| clap::Error::raw(ErrorKind::ValueValidation, err))?;
+        (self.convert)(bytes).map_err(|err| clap::Error::raw(ErrorKind::ValueValidation, err))
+    }
+}
+
+fn parse_vdaf_instance(value: &str) -> Result<VdafInstance, serde_yaml::Error> {
+    serde_yaml::from_str(value)
+}
+
+#[derive(Derivative, Parser)]
+#[derivative(Debug)]
+#[command(
+    name = "dap_decrypt",
+    about = "Decrypts and decodes Distributed Aggregation Protocol reports and aggregate shares",
+    version,
+    rename_all = "kebab-case"
+)]
+struct Options {
+    /// Path to file containing message to decrypt. Pass "-" to read from stdin.
+    message_file: String,
+
+    /// Media type of the message to decrypt.
+    #[arg(long, short = 't', required = true)]
+    media_type: MediaType,
+
+    /// Encoding of the message.
+    #[arg(long, short = 'e', default_value = "binary")]
+    encoding: Encoding,
+
+    /// DAP task identifier, encoded with base64url
+    #[arg(
+        long,
+        value_parser = Base64UrlValueParser::new(|bytes| {
+            TaskId::try_from(bytes.as_slice()).map_err(ToString::to_string)
+        }),
+        help_heading = "DAP Task Parameters"
+    )]
+    task_id: TaskId,
+
+    /// The task's VDAF, in the same YAML format as in task definitions, e.g. "Prio3Count" or
+    /// "!Prio3Sum {bits: 8}"
+    #[arg(long, value_parser = parse_vdaf_instance, help_heading = "DAP Task Parameters")]
+    vdaf: VdafInstance,
+
+    /// Role of the aggregator whose input share should be decrypted, when decrypting a report
+    #[arg(
+        long,
+        value_parser = PossibleValuesParser::new(["leader", "helper"])
+            .map(|role| role.parse::<Role>().unwrap()),
+        help_heading = "DAP Task Parameters"
+    )]
+    role: Option<Role>,
+
+    /// DAP message for the recipient's HPKE configuration, encoded with base64url
+    #[arg(
+        long,
+        value_parser = Base64UrlValueParser::new(|bytes| {
+            HpkeConfig::get_decoded(&bytes).map_err(|err| err.to_string())
+        }),
+        help_heading = "HPKE Key",
+        requires = "hpke_private_key",
+        conflicts_with = "hpke_config_json"
+    )]
+    hpke_config: Option<HpkeConfig>,
+    /// The recipient's HPKE private key, encoded with base64url
+    #[arg(
+        long,
+        value_parser = Base64UrlValueParser::new(|bytes| Ok(HpkePrivateKey::new(bytes))),
+        env,
+        help_heading = "HPKE Key",
+        requires = "hpke_config",
+        conflicts_with = "hpke_config_json"
+    )]
+    #[derivative(Debug = "ignore")]
+    hpke_private_key: Option<HpkePrivateKey>,
+    /// Path to a JSON document containing the recipient's HPKE configuration and private key, in
+    /// the format output by `divviup hpke-config generate`.
+    #[arg(
+        long,
+        help_heading = "HPKE Key",
+        required_unless_present = "hpke_config",
+        conflicts_with_all = ["hpke_config", "hpke_private_key"]
+    )]
+    hpke_config_json: Option<PathBuf>,
+
+    /// Start of the batch interval, as the number of seconds since the Unix epoch
+    #[arg(
+        long,
+        requires = "batch_interval_duration",
+        help_heading = "Aggregate Share Parameters"
+    )]
+    batch_interval_start: Option<u64>,
+    /// Duration of the batch interval, in seconds
+    #[arg(
+        long,
+        requires = "batch_interval_start",
+        help_heading = "Aggregate Share Parameters"
+    )]
+    batch_interval_duration: Option<u64>,
+    /// Batch identifier, encoded with base64url
+    #[arg(
+        long,
+        value_parser = Base64UrlValueParser::new(|bytes| {
+            BatchId::try_from(bytes.as_slice()).map_err(ToString::to_string)
+        }),
+        conflicts_with_all = ["batch_interval_start", "batch_interval_duration"],
+        help_heading = "Aggregate Share Parameters"
+    )]
+    batch_id: Option<BatchId>,
+    /// Aggregation parameter, encoded with base64url
+    #[arg(long, default_value = "", help_heading = "Aggregate Share Parameters")]
+    aggregation_param: String,
+}
+
+impl Options {
+    fn hpke_keypair(&self) -> Result<HpkeKeypair> {
+        match (
+            &self.hpke_config,
+            &self.hpke_private_key,
+            &self.hpke_config_json,
+        ) {
+            (Some(config), Some(private), _) => {
+                Ok(HpkeKeypair::new(config.clone(), private.clone()))
+            }
+            (None, None, Some(hpke_config_json_path)) => {
+                let reader =
+                    File::open(hpke_config_json_path).context("could not open HPKE config file")?;
+                let divviup_hpke_config: DivviUpHpkeConfig =
+                    serde_json::from_reader(reader).context("could not parse HPKE config file")?;
+                HpkeKeypair::try_from(divviup_hpke_config).context("could not convert HPKE config")
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn batch_selector(&self) -> Result<EitherBatchSelector> {
+        match (
+            self.batch_interval_start,
+            self.batch_interval_duration,
+            self.batch_id,
+        ) {
+            (Some(start), Some(duration), None) => Ok(EitherBatchSelector::TimeInterval(
+                BatchSelector::new_time_interval(Interval::new(
+                    Time::from_seconds_since_epoch(start),
+                    Duration::from_seconds(duration),
+                )?),
+            )),
+            (None, None, Some(batch_id)) => Ok(EitherBatchSelector::FixedSize(
+                BatchSelector::new_fixed_size(batch_id),
+            )),
+            _ => bail!(
+                "either --batch-interval-start and --batch-interval-duration, or --batch-id, are \
+                 required to decrypt aggregate shares"
+            ),
+        }
+    }
+
+    fn aggregation_param<P: Decode>(&self) -> Result<P> {
+        let bytes = URL_SAFE_NO_PAD
+            .decode(&self.aggregation_param)
+            .context("invalid base64url aggregation parameter")?;
+        P::get_decoded(&bytes).context("could not decode aggregation parameter")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{decrypt_collection, decrypt_report, Options};
+    use clap::CommandFactory;
+    use janus_core::hpke::{
+        self, test_util::generate_test_hpke_config_and_private_key, HpkeApplicationInfo,
+        HpkeKeypair, Label,
+    };
+    use janus_messages::{
+        AggregateShareAad, BatchSelector, Collection, Duration, InputShareAad, Interval,
+        PartialBatchSelector, PlaintextInputShare, Report, ReportId, ReportMetadata, Role, TaskId,
+        Time,
+    };
+    use prio::{
+        codec::Encode,
+        field::Field64,
+        vdaf::{
+            self,
+            prio3::{Prio3, Prio3InputShare},
+            Client, OutputShare,
+        },
+    };
+    use rand::random;
+
+    #[test]
+    fn verify_clap_app() {
+        Options::command().debug_assert();
+    }
+
+    #[test]
+    fn decrypt_report_input_shares() {
+        let vdaf = Prio3::new_count(2).unwrap();
+        let task_id: TaskId = random();
+        let report_id: ReportId = random();
+        let (leader_keypair, helper_keypair) = (
+            generate_test_hpke_config_and_private_key(),
+            generate_test_hpke_config_and_private_key(),
+        );
+
+        let (public_share, input_shares) = vdaf.shard(&1, report_id.as_ref()).unwrap();
+        let metadata = ReportMetadata::new(report_id, Time::from_seconds_since_epoch(1000));
+        let encoded_public_share = public_share.get_encoded();
+        let aad = InputShareAad::new(task_id, metadata.clone(), encoded_public_share.clone())
+            .get_encoded();
+        let seal = |keypair: &HpkeKeypair, role, input_share: &Prio3InputShare<Field64, 16>| {
+            hpke::seal(
+                keypair.config(),
+                &HpkeApplicationInfo::new(&Label::InputShare, &Role::Client, role),
+                &PlaintextInputShare::new(Vec::new(), input_share.get_encoded()).get_encoded(),
+                &aad,
+            )
+            .unwrap()
+        };
+        let report = Report::new(
+            metadata.clone(),
+            encoded_public_share,
+            seal(&leader_keypair, &Role::Leader, &input_shares[0]),
+            seal(&helper_keypair, &Role::Helper, &input_shares[1]),
+        )
+        .get_encoded();
+
+        for (role, keypair, input_share) in [
+            (Role::Leader, &leader_keypair, &input_shares[0]),
+            (Role::Helper, &helper_keypair, &input_shares[1]),
+        ] {
+            let decrypted = decrypt_report(&vdaf, &task_id, &role, keypair, &report).unwrap();
+            assert_eq!(decrypted.metadata, metadata);
+            assert_eq!(decrypted.public_share, public_share);
+            assert!(decrypted.extensions.is_empty());
+            assert_eq!(&decrypted.input_share, input_share);
+        }
+
+        // Decrypting with the wrong key, or in a different task, fails.
+        decrypt_report(&vdaf, &task_id, &Role::Leader, &helper_keypair, &report).unwrap_err();
+        decrypt_report(&vdaf, &random(), &Role::Leader, &leader_keypair, &report).unwrap_err();
+    }
+
+    #[test]
+    fn decrypt_collection_aggregate_shares() {
+        let vdaf = Prio3::new_count(2).unwrap();
+        let task_id: TaskId = random();
+        let collector_keypair = generate_test_hpke_config_and_private_key();
+        let batch_interval = Interval::new(
+            Time::from_seconds_since_epoch(3600),
+            Duration::from_seconds(3600),
+        )
+        .unwrap();
+
+        let aggregate_shares = [5, 7].map(|value| {
+            vdaf::AggregateShare::from(OutputShare::from(Vec::from([Field64::from(value)])))
+        });
+        let aad = AggregateShareAad::new(task_id, BatchSelector::new_time_interval(batch_interval))
+            .get_encoded();
+        let seal = |role, aggregate_share: &vdaf::AggregateShare<Field64>| {
+            hpke::seal(
+                collector_keypair.config(),
+                &HpkeApplicationInfo::new(&Label::AggregateShare, role, &Role::Collector),
+                &aggregate_share.get_encoded(),
+                &aad,
+            )
+            .unwrap()
+        };
+        let collection = Collection::new(
+            PartialBatchSelector::new_time_interval(),
+            1,
+            batch_interval,
+            seal(&Role::Leader, &aggregate_shares[0]),
+            seal(&Role::Helper, &aggregate_shares[1]),
+        )
+        .get_encoded();
+
+        let decrypted = decrypt_collection(
+            &vdaf,
+            &task_id,
+            BatchSelector::new_time_interval(batch_interval),
+            &(),
+            &collector_keypair,
+            &collection,
+        )
+        .unwrap();
+        assert_eq!(
+            decrypted.partial_batch_selector,
+            PartialBatchSelector::new_time_interval()
+        );
+        assert_eq!(decrypted.report_count, 1);
+        assert_eq!(decrypted.interval, batch_interval);
+        assert_eq!(decrypted.leader_aggregate_share, aggregate_shares[0]);
+        assert_eq!(decrypted.helper_aggregate_share, aggregate_shares[1]);
+    }
+}
@@ -1,19 +1,70 @@
-use anyhow::Result;
+use anyhow::{anyhow, Context, Result};
 use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
 use clap::{Parser, ValueEnum};
-use janus_core::hpke::generate_hpke_config_and_private_key;
-use janus_messages::{HpkeAeadId, HpkeConfigId, HpkeKdfId, HpkeKemId};
+use derivative::Derivative;
+use janus_core::hpke::{
+    generate_hpke_config_and_private_key, wrap_private_key, DivviUpHpkeConfig, HpkeKeypair,
+};
+use janus_messages::{HpkeAeadId, HpkeCiphertext, HpkeConfig, HpkeConfigId, HpkeKdfId, HpkeKemId};
 use prio::codec::Encode;
+use rand::random;
+use reqwest::header::{ACCEPT, CONTENT_TYPE};
+use serde::{Deserialize, Serialize};
 use serde_yaml::to_writer;
 use std::{
     fmt::Display,
+    fs,
     io::{stdout, Write},
+    path::PathBuf,
 };
+use url::Url;
+
+/// Media type used by the Janus aggregator API.
+const AGGREGATOR_API_CONTENT_TYPE: &str = "application/vnd.janus.aggregator+json;version=0.1";
 
-fn main() -> Result<()> {
+#[tokio::main]
+async fn main() -> Result<()> {
     let options = Options::parse();
+    run(options, &mut stdout().lock()).await
+}
+
+async fn run<W: Write>(options: Options, writer: &mut W) -> Result<()> {
+    let transport_config = match &options.transport_config_file {
+        Some(path) => Some(
+            serde_yaml::from_str(
+                &fs::read_to_string(path)
+                    .with_context(|| format!("couldn't read transport config file {path:?}"))?,
+            )
+            .with_context(|| format!("couldn't parse transport config file {path:?}"))?,
+        ),
+        None => None,
+    };
+
+    let upload_target = match (
+        &options.aggregator_api_url,
+        &options.aggregator_api_auth_token,
+    ) {
+        (Some(url), Some(auth_token)) => Some(AggregatorApiClient::new(url, auth_token)),
+        _ => None,
+    };
+
+    let id = match (options.id, &upload_target) {
+        (Some(id), _) => HpkeConfigId::from(id),
+        // Pick the lowest ID that is not yet in use by the aggregator, mirroring what the
+        // aggregator API does when it generates keys itself.
+        (None, Some(client)) => {
+            let existing_ids = client.existing_config_ids().await?;
+            HpkeConfigId::from(
+                (0..=u8::MAX)
+                    .find(|id| !existing_ids.contains(&HpkeConfigId::from(*id)))
+                    .ok_or_else(|| {
+                        anyhow!("all possible IDs for global HPKE keys have been taken")
+                    })?,
+            )
+        }
+        (None, None) => HpkeConfigId::from(random::<u8>()),
+    };
 
-    let id = HpkeConfigId::from(options.id);
     let keypair = generate_hpke_config_and_private_key(
         id,
         options.kem.into(),
@@ -21,37 +72,176 @@ fn main() -> Result<()> {
         options.aead.into(),
     );
 
-    let mut writer = stdout().lock();
+    if let Some(client) = &upload_target {
+        client.import_keypair(&keypair).await?;
+    }
+
+    write_keypair(&keypair, options.output, transport_config.as_ref(), writer)
+}
 
-    writeln!(writer, "# HPKE configuration, Janus format")?;
-    to_writer(&mut writer, keypair.config())?;
+/// Key file format accepted by `janus_cli import-global-hpke-key`.
+#[derive(Serialize)]
+struct WrappedKeyFile<'a> {
+    config: &'a HpkeConfig,
+    wrapped_private_key: HpkeCiphertext,
+}
 
-    writeln!(writer, "---")?;
+fn write_keypair<W: Write>(
+    keypair: &HpkeKeypair,
+    format: OutputFormat,
+    transport_config: Option<&HpkeConfig>,
+    writer: &mut W,
+) -> Result<()> {
+    match format {
+        OutputFormat::Summary => {
+            writeln!(writer, "# HPKE configuration, Janus format")?;
+            to_writer(&mut *writer, keypair.config())?;
 
-    writeln!(writer, "# HPKE private key, in base64url")?;
-    writeln!(
-        writer,
-        "{}",
-        URL_SAFE_NO_PAD.encode(keypair.private_key().as_ref())
-    )?;
+            writeln!(writer, "---")?;
 
-    writeln!(writer, "---")?;
+            writeln!(writer, "# HPKE private key, in base64url")?;
+            writeln!(
+                writer,
+                "{}",
+                URL_SAFE_NO_PAD.encode(keypair.private_key().as_ref())
+            )?;
 
-    writeln!(writer, "# HPKE keypair, Janus format")?;
-    to_writer(&mut writer, &keypair)?;
+            writeln!(writer, "---")?;
 
-    writeln!(writer, "---")?;
+            writeln!(writer, "# HPKE keypair, Janus format")?;
+            to_writer(&mut *writer, keypair)?;
 
-    writeln!(writer, "# HPKE configuration, DAP encoded, in base64url")?;
-    writeln!(
-        writer,
-        "{}",
-        URL_SAFE_NO_PAD.encode(keypair.config().get_encoded())
-    )?;
+            writeln!(writer, "---")?;
+
+            writeln!(writer, "# HPKE configuration, DAP encoded, in base64url")?;
+            writeln!(
+                writer,
+                "{}",
+                URL_SAFE_NO_PAD.encode(keypair.config().get_encoded())
+            )?;
+        }
+        OutputFormat::AggregatorApi => {
+            serde_json::to_writer_pretty(&mut *writer, keypair)?;
+            writeln!(writer)?;
+        }
+        OutputFormat::TaskYaml => to_writer(&mut *writer, &[keypair])?,
+        OutputFormat::Divviup => {
+            serde_json::to_writer_pretty(
+                &mut *writer,
+                &DivviUpHpkeConfig::try_from(keypair.clone())?,
+            )?;
+            writeln!(writer)?;
+        }
+        OutputFormat::Wrapped => {
+            let transport_config = transport_config
+                .ok_or_else(|| anyhow!("a transport config is required to wrap the private key"))?;
+            to_writer(
+                &mut *writer,
+                &WrappedKeyFile {
+                    config: keypair.config(),
+                    wrapped_private_key: wrap_private_key(transport_config, keypair)?,
+                },
+            )?;
+        }
+    }
 
     Ok(())
 }
 
+/// Minimal client for the global HPKE key endpoints of the Janus aggregator API.
+struct AggregatorApiClient {
+    http_client: reqwest::Client,
+    hpke_configs_url: Url,
+    auth_token: String,
+}
+
+/// Subset of the aggregator API's representation of a global HPKE key.
+#[derive(Deserialize)]
+struct GlobalHpkeConfigResp {
+    config: HpkeConfig,
+}
+
+impl AggregatorApiClient {
+    fn new(base_url: &Url, auth_token: &str) -> Self {
+        let mut hpke_configs_url = base_url.clone();
+        if !hpke_configs_url.path().ends_with('/') {
+            hpke_configs_url.set_path(&format!("{}/", hpke_configs_url.path()));
+        }
+        Self {
+            http_client: reqwest::Client::new(),
+            // Unwrap safety: joining a relative path onto a URL with a trailing slash can't fail.
+            hpke_configs_url: hpke_configs_url.join("hpke_configs").unwrap(),
+            auth_token: auth_token.to_string(),
+        }
+    }
+
+    async fn existing_config_ids(&self) -> Result<Vec<HpkeConfigId>> {
+        let configs: Vec<GlobalHpkeConfigResp> = self
+            .http_client
+            .get(self.hpke_configs_url.clone())
+            .bearer_auth(&self.auth_token)
+            .header(ACCEPT, AGGREGATOR_API_CONTENT_TYPE)
+            .send()
+            .await
+            .context("couldn't list global HPKE keys")?
+            .error_for_status()
+            .context("couldn't list global HPKE keys")?
+            .json()
+            .await
+            .context("couldn't parse global HPKE keys")?;
+        Ok(configs.into_iter().map(|resp| *resp.config.id()).collect())
+    }
+
+    async fn import_keypair(&self, keypair: &HpkeKeypair) -> Result<()> {
+        let response = self
+            .http_client
+            .post(self.hpke_configs_url.clone())
+            .bearer_auth(&self.auth_token)
+            .header(ACCEPT, AGGREGATOR_API_CONTENT_TYPE)
+            .header(CONTENT_TYPE, AGGREGATOR_API_CONTENT_TYPE)
+            .body(serde_json::to_vec(keypair)?)
+            .send()
+            .await
+            .context("couldn't upload global HPKE key")?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(anyhow!(
+                "aggregator API rejected global HPKE key: {status}: {body}"
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+#[value()]
+enum OutputFormat {
+    /// Human-readable summary of the keypair in several formats
+    Summary,
+
+    /// Request body for importing the keypair as a global HPKE key via the aggregator API
+    AggregatorApi,
+
+    /// Entry for the `hpke_keys` list of a task definition file
+    TaskYaml,
+
+    /// Collector HPKE configuration, in the format of `divviup hpke-config generate`
+    Divviup,
+
+    /// Key file for `janus_cli import-global-hpke-key`, with the private key wrapped to the
+    /// transport key given by --transport-config-file
+    Wrapped,
+}
+
+impl Display for OutputFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // This is safe to unwrap because we don't skip any enum variants.
+        let possible_value = self.to_possible_value().unwrap();
+        f.write_str(possible_value.get_name())
+    }
+}
+
 #[derive(Debug, Clone, ValueEnum)]
 #[value()]
 enum KemAlgorithm {
@@ -149,11 +339,15 @@ impl Display for AeadAlgorithm {
     }
 }
 
-#[derive(Debug, Parser)]
+#[derive(Derivative, Parser)]
+#[derivative(Debug)]
 #[command(name = "hpke_keygen", about = "DAP-compatible HPKE keypair generator")]
 struct Options {
     /// Numeric identifier of the HPKE configuration.
-    id: u8,
+    ///
+    /// If omitted, the lowest ID not used by any global HPKE key is chosen when uploading to an
+    /// aggregator API, and a random ID is chosen otherwise.
+    id: Option<u8>,
 
     /// HPKE Key Encapsulation Mechanism algorithm.
     #[arg(long, default_value_t = KemAlgorithm::X25519HkdfSha256)]
@@ -166,15 +360,184 @@ struct Options {
     /// HPKE Authenticated Encryption with Associated Data algorithm.
     #[arg(long, default_value_t = AeadAlgorithm::Aes128Gcm)]
     aead: AeadAlgorithm,
+
+    /// Format in which to write the generated keypair to stdout.
+    #[arg(long, default_value_t = OutputFormat::Summary)]
+    output: OutputFormat,
+
+    /// YAML or JSON file containing the HPKE configuration of the transport key to wrap the
+    /// private key to. Required for the `wrapped` output format.
+    #[arg(long, required_if_eq("output", "wrapped"))]
+    transport_config_file: Option<PathBuf>,
+
+    /// Base URL of an aggregator API instance to upload the keypair to, as a global HPKE key.
+    #[arg(long, requires = "aggregator_api_auth_token", help_heading = "Upload")]
+    aggregator_api_url: Option<Url>,
+
+    /// Authentication token for the aggregator API's "Authorization: Bearer ..." HTTP header.
+    #[arg(long, env, help_heading = "Upload")]
+    #[derivative(Debug = "ignore")]
+    aggregator_api_auth_token: Option<String>,
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::Options;
-    use clap::CommandFactory;
+    use crate::{run, write_keypair, Options, OutputFormat, AGGREGATOR_API_CONTENT_TYPE};
+    use clap::{CommandFactory, Parser};
+    use janus_core::hpke::{
+        test_util::generate_test_hpke_config_and_private_key_with_id, unwrap_private_key,
+        DivviUpHpkeConfig, HpkeKeypair,
+    };
+    use janus_messages::{HpkeCiphertext, HpkeConfig, HpkeConfigId, HpkeKemId};
+    use mockito::Matcher;
+    use serde_json::json;
 
     #[test]
     fn verify_clap_app() {
         Options::command().debug_assert();
     }
+
+    #[test]
+    fn output_formats() {
+        let keypair = generate_test_hpke_config_and_private_key_with_id(12);
+
+        let mut output = Vec::new();
+        write_keypair(&keypair, OutputFormat::AggregatorApi, None, &mut output).unwrap();
+        assert_eq!(
+            serde_json::from_slice::<HpkeKeypair>(&output).unwrap(),
+            keypair
+        );
+
+        let mut output = Vec::new();
+        write_keypair(&keypair, OutputFormat::TaskYaml, None, &mut output).unwrap();
+        assert_eq!(
+            serde_yaml::from_slice::<Vec<HpkeKeypair>>(&output).unwrap(),
+            Vec::from([keypair.clone()])
+        );
+
+        let mut output = Vec::new();
+        write_keypair(&keypair, OutputFormat::Divviup, None, &mut output).unwrap();
+        assert_eq!(
+            HpkeKeypair::try_from(serde_json::from_slice::<DivviUpHpkeConfig>(&output).unwrap())
+                .unwrap(),
+            keypair
+        );
+
+        let transport_keypair = generate_test_hpke_config_and_private_key_with_id(100);
+        let mut output = Vec::new();
+        write_keypair(
+            &keypair,
+            OutputFormat::Wrapped,
+            Some(transport_keypair.config()),
+            &mut output,
+        )
+        .unwrap();
+        let wrapped_key_file: serde_yaml::Mapping = serde_yaml::from_slice(&output).unwrap();
+        let config: HpkeConfig =
+            serde_yaml::from_value(wrapped_key_file["config"].clone()).unwrap();
+        let wrapped_private_key: HpkeCiphertext =
+            serde_yaml::from_value(wrapped_key_file["wrapped_private_key"].clone()).unwrap();
+        assert_eq!(
+            unwrap_private_key(&transport_keypair, &config, &wrapped_private_key).unwrap(),
+            keypair
+        );
+    }
+
+    #[tokio::test]
+    async fn upload() {
+        let mut server = mockito::Server::new_async().await;
+        let existing_configs = [0, 1, 3]
+            .into_iter()
+            .map(|id| {
+                json!({
+                    "config": generate_test_hpke_config_and_private_key_with_id(id).config(),
+                    "state": "Active",
+                })
+            })
+            .collect::<Vec<_>>();
+        let mock_list = server
+            .mock("GET", "/api/hpke_configs")
+            .match_header("Authorization", "Bearer secret-token")
+            .match_header("Accept", AGGREGATOR_API_CONTENT_TYPE)
+            .with_status(200)
+            .with_header("Content-Type", AGGREGATOR_API_CONTENT_TYPE)
+            .with_body(serde_json::to_vec(&existing_configs).unwrap())
+            .expect(1)
+            .create_async()
+            .await;
+        let mock_import = server
+            .mock("POST", "/api/hpke_configs")
+            .match_header("Authorization", "Bearer secret-token")
+            .match_header("Content-Type", AGGREGATOR_API_CONTENT_TYPE)
+            .match_body(Matcher::PartialJson(json!({"config": {"id": 2}})))
+            .with_status(201)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let options = Options::try_parse_from([
+            "hpke_keygen",
+            "--output",
+            "aggregator-api",
+            "--kem",
+            "p-256",
+            "--aggregator-api-url",
+            &format!("{}/api", server.url()),
+            "--aggregator-api-auth-token",
+            "secret-token",
+        ])
+        .unwrap();
+        let mut output = Vec::new();
+        run(options, &mut output).await.unwrap();
+
+        mock_list.assert_async().await;
+        mock_import.assert_async().await;
+
+        let keypair = serde_json::from_slice::<HpkeKeypair>(&output).unwrap();
+        assert_eq!(keypair.config().id(), &HpkeConfigId::from(2));
+        assert_eq!(keypair.config().kem_id(), &HpkeKemId::P256HkdfSha256);
+    }
+
+    #[tokio::test]
+    async fn upload_rejected() {
+        let mut server = mockito::Server::new_async().await;
+        let mock_import = server
+            .mock("POST", "/hpke_configs")
+            .with_status(409)
+            .with_body("Global HPKE key with ID 7 already exists")
+            .expect(1)
+            .create_async()
+            .await;
+
+        let options = Options::try_parse_from([
+            "hpke_keygen",
+            "7",
+            "--aggregator-api-url",
+            &server.url(),
+            "--aggregator-api-auth-token",
+            "secret-token",
+        ])
+        .unwrap();
+        let mut output = Vec::new();
+        let error = run(options, &mut output).await.unwrap_err();
+
+        mock_import.assert_async().await;
+        assert!(error.to_string().contains("already exists"));
+        assert!(output.is_empty());
+    }
+
+    #[test]
+    fn wrapped_output_requires_transport_config() {
+        Options::try_parse_from(["hpke_keygen", "--output", "wrapped"]).unwrap_err();
+    }
+
+    #[test]
+    fn upload_requires_auth_token() {
+        Options::try_parse_from([
+            "hpke_keygen",
+            "--aggregator-api-url",
+            "https://example.com/",
+        ])
+        .unwrap_err();
+    }
 }
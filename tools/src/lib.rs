@@ -0,0 +1,3 @@
+//! Functionality shared between the command line tools associated with Janus.
+
+pub mod output;
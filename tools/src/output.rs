@@ -0,0 +1,490 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use clap::ValueEnum;
+use janus_collector::{CollectableBatch, Collection};
+use janus_messages::{
+    query_type::{FixedSize, QueryType, TimeInterval},
+    PartialBatchSelector,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::Debug,
+    io::{self, Write},
+    path::Path,
+};
+use tempfile::NamedTempFile;
+
+/// Format in which the result of a collection is written.
+#[derive(Debug, Clone, Copy, ValueEnum, PartialEq, Eq, Deserialize)]
+#[clap(rename_all = "lower")]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// Human-readable summary
+    Text,
+    /// JSON object
+    Json,
+    /// CSV table, with one row per element of the aggregate result
+    Csv,
+}
+
+impl OutputFormat {
+    /// Returns the conventional file name extension for this format.
+    pub fn file_extension(&self) -> &'static str {
+        match self {
+            OutputFormat::Text => "txt",
+            OutputFormat::Json => "json",
+            OutputFormat::Csv => "csv",
+        }
+    }
+}
+
+/// Representation of the aggregate results of the VDAFs supported by the command line tools, used
+/// in the JSON and CSV output formats.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum AggregateResult {
+    Integer(u128),
+    IntegerVector(Vec<u128>),
+    FloatVector(Vec<f64>),
+}
+
+impl From<u64> for AggregateResult {
+    fn from(value: u64) -> Self {
+        Self::Integer(value.into())
+    }
+}
+
+impl From<u128> for AggregateResult {
+    fn from(value: u128) -> Self {
+        Self::Integer(value)
+    }
+}
+
+impl From<Vec<u128>> for AggregateResult {
+    fn from(value: Vec<u128>) -> Self {
+        Self::IntegerVector(value)
+    }
+}
+
+impl From<Vec<f64>> for AggregateResult {
+    fn from(value: Vec<f64>) -> Self {
+        Self::FloatVector(value)
+    }
+}
+
+impl AggregateResult {
+    /// Returns each element of the aggregate result as a CSV index and value. Scalar results have
+    /// an empty index.
+    fn csv_rows(&self) -> Vec<(String, String)> {
+        match self {
+            Self::Integer(value) => Vec::from([(String::new(), value.to_string())]),
+            Self::IntegerVector(values) => values
+                .iter()
+                .enumerate()
+                .map(|(index, value)| (index.to_string(), value.to_string()))
+                .collect(),
+            Self::FloatVector(values) => values
+                .iter()
+                .enumerate()
+                .map(|(index, value)| (index.to_string(), value.to_string()))
+                .collect(),
+        }
+    }
+}
+
+/// JSON representation of a collection. Changes to this structure are breaking changes for
+/// consumers of `--output=json`.
+#[derive(Serialize)]
+#[serde(bound = "")]
+struct CollectionOutput<'a, Q: QueryType> {
+    partial_batch_selector: &'a PartialBatchSelector<Q>,
+    report_count: u64,
+    interval: IntervalOutput,
+    aggregate_result: AggregateResult,
+}
+
+/// The interval spanned by the reports in a collection, in seconds.
+#[derive(Serialize)]
+struct IntervalOutput {
+    start: i64,
+    duration: i64,
+}
+
+/// Header row of the CSV output format.
+const CSV_HEADER: &str = "batch_id,report_count,interval_start,interval_duration,index,value";
+
+/// Writes a collection to `writer`, in the requested format.
+pub fn write_collection<T, Q>(
+    collection: &Collection<T, Q>,
+    format: OutputFormat,
+    writer: &mut impl Write,
+) -> io::Result<()>
+where
+    T: Debug + Clone + Into<AggregateResult>,
+    Q: QueryTypeExt,
+{
+    let (interval_start, interval_duration) = collection.interval();
+    match format {
+        OutputFormat::Text => {
+            if !Q::IS_PARTIAL_BATCH_SELECTOR_TRIVIAL {
+                writeln!(
+                    writer,
+                    "Batch: {}",
+                    Q::format_partial_batch_selector(collection.partial_batch_selector())
+                )?;
+            }
+            writeln!(writer, "Number of reports: {}", collection.report_count())?;
+            writeln!(
+                writer,
+                "Spanned interval: start: {} length: {}",
+                interval_start, interval_duration
+            )?;
+            writeln!(
+                writer,
+                "Aggregation result: {:?}",
+                collection.aggregate_result()
+            )
+        }
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(
+                &mut *writer,
+                &CollectionOutput {
+                    partial_batch_selector: collection.partial_batch_selector(),
+                    report_count: collection.report_count(),
+                    interval: IntervalOutput {
+                        start: interval_start.timestamp(),
+                        duration: interval_duration.num_seconds(),
+                    },
+                    aggregate_result: collection.aggregate_result().clone().into(),
+                },
+            )?;
+            writeln!(writer)
+        }
+        OutputFormat::Csv => {
+            let batch_id = if Q::IS_PARTIAL_BATCH_SELECTOR_TRIVIAL {
+                String::new()
+            } else {
+                Q::format_partial_batch_selector(collection.partial_batch_selector())
+            };
+            let aggregate_result: AggregateResult = collection.aggregate_result().clone().into();
+            writeln!(writer, "{CSV_HEADER}")?;
+            for (index, value) in aggregate_result.csv_rows() {
+                writeln!(
+                    writer,
+                    "{batch_id},{},{},{},{index},{value}",
+                    collection.report_count(),
+                    interval_start.timestamp(),
+                    interval_duration.num_seconds(),
+                )?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// JSON representation of a collectable batch. Changes to this structure are breaking changes for
+/// consumers of `--list-batches --output=json`.
+#[derive(Serialize)]
+struct CollectableBatchOutput {
+    batch_id: String,
+    report_count: u64,
+    interval: IntervalOutput,
+    collected: bool,
+}
+
+/// Header row of the CSV output format, when listing batches.
+const BATCHES_CSV_HEADER: &str = "batch_id,report_count,interval_start,interval_duration,collected";
+
+/// Writes a list of collectable batches to `writer`, in the requested format.
+pub fn write_batches(
+    batches: &[CollectableBatch],
+    format: OutputFormat,
+    writer: &mut impl Write,
+) -> io::Result<()> {
+    match format {
+        OutputFormat::Text => {
+            for (i, batch) in batches.iter().enumerate() {
+                if i > 0 {
+                    writeln!(writer)?;
+                }
+                let (interval_start, interval_duration) = batch.interval();
+                writeln!(
+                    writer,
+                    "Batch: {}",
+                    URL_SAFE_NO_PAD.encode(batch.batch_id().as_ref())
+                )?;
+                writeln!(writer, "Number of reports: {}", batch.report_count())?;
+                writeln!(
+                    writer,
+                    "Spanned interval: start: {} length: {}",
+                    interval_start, interval_duration
+                )?;
+                writeln!(writer, "Collected: {}", batch.collected())?;
+            }
+            Ok(())
+        }
+        OutputFormat::Json => {
+            let batches: Vec<_> = batches
+                .iter()
+                .map(|batch| {
+                    let (interval_start, interval_duration) = batch.interval();
+                    CollectableBatchOutput {
+                        batch_id: URL_SAFE_NO_PAD.encode(batch.batch_id().as_ref()),
+                        report_count: batch.report_count(),
+                        interval: IntervalOutput {
+                            start: interval_start.timestamp(),
+                            duration: interval_duration.num_seconds(),
+                        },
+                        collected: batch.collected(),
+                    }
+                })
+                .collect();
+            serde_json::to_writer_pretty(&mut *writer, &batches)?;
+            writeln!(writer)
+        }
+        OutputFormat::Csv => {
+            writeln!(writer, "{BATCHES_CSV_HEADER}")?;
+            for batch in batches {
+                let (interval_start, interval_duration) = batch.interval();
+                writeln!(
+                    writer,
+                    "{},{},{},{},{}",
+                    URL_SAFE_NO_PAD.encode(batch.batch_id().as_ref()),
+                    batch.report_count(),
+                    interval_start.timestamp(),
+                    interval_duration.num_seconds(),
+                    batch.collected(),
+                )?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Writes `contents` to a temporary file in the same directory as `path`, and then renames it over
+/// `path`, so that readers never observe a partially written file.
+pub fn write_file_atomically(path: &Path, contents: &[u8]) -> anyhow::Result<()> {
+    let directory = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let mut file = NamedTempFile::new_in(directory)?;
+    file.write_all(contents)?;
+    file.as_file().sync_all()?;
+    file.persist(path)?;
+    Ok(())
+}
+
+/// Extension trait for query types, describing how their partial batch selectors are displayed.
+pub trait QueryTypeExt: QueryType {
+    const IS_PARTIAL_BATCH_SELECTOR_TRIVIAL: bool;
+
+    fn format_partial_batch_selector(partial_batch_selector: &PartialBatchSelector<Self>)
+        -> String;
+}
+
+impl QueryTypeExt for TimeInterval {
+    const IS_PARTIAL_BATCH_SELECTOR_TRIVIAL: bool = true;
+
+    fn format_partial_batch_selector(_: &PartialBatchSelector<Self>) -> String {
+        "()".to_string()
+    }
+}
+
+impl QueryTypeExt for FixedSize {
+    const IS_PARTIAL_BATCH_SELECTOR_TRIVIAL: bool = false;
+
+    fn format_partial_batch_selector(
+        partial_batch_selector: &PartialBatchSelector<Self>,
+    ) -> String {
+        URL_SAFE_NO_PAD.encode(partial_batch_selector.batch_id().as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::output::{write_batches, write_collection, write_file_atomically, OutputFormat};
+    use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+    use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+    use janus_collector::{CollectableBatch, Collection};
+    use janus_messages::{
+        query_type::{FixedSize, TimeInterval},
+        BatchId, PartialBatchSelector,
+    };
+    use rand::random;
+
+    fn collection_interval() -> (DateTime<Utc>, Duration) {
+        (
+            DateTime::from_utc(
+                NaiveDateTime::from_timestamp_opt(1_000_000, 0).unwrap(),
+                Utc,
+            ),
+            Duration::seconds(1000),
+        )
+    }
+
+    #[test]
+    fn output_formats() {
+        let collection = Collection::new(
+            PartialBatchSelector::new_time_interval(),
+            10,
+            collection_interval(),
+            Vec::from([3u128, 0, 7]),
+        );
+
+        let mut text = Vec::new();
+        write_collection(&collection, OutputFormat::Text, &mut text).unwrap();
+        assert_eq!(
+            String::from_utf8(text).unwrap(),
+            "Number of reports: 10\n\
+             Spanned interval: start: 1970-01-12 13:46:40 UTC length: PT1000S\n\
+             Aggregation result: [3, 0, 7]\n"
+        );
+
+        let mut json = Vec::new();
+        write_collection(&collection, OutputFormat::Json, &mut json).unwrap();
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(&json).unwrap(),
+            serde_json::json!({
+                "partial_batch_selector": {"batch_identifier": null},
+                "report_count": 10,
+                "interval": {"start": 1_000_000, "duration": 1000},
+                "aggregate_result": [3, 0, 7],
+            })
+        );
+
+        let mut csv = Vec::new();
+        write_collection(&collection, OutputFormat::Csv, &mut csv).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "batch_id,report_count,interval_start,interval_duration,index,value\n\
+             ,10,1000000,1000,0,3\n\
+             ,10,1000000,1000,1,0\n\
+             ,10,1000000,1000,2,7\n"
+        );
+
+        let batch_id: BatchId = random();
+        let batch_id_encoded = URL_SAFE_NO_PAD.encode(batch_id.as_ref());
+        let collection = Collection::new(
+            PartialBatchSelector::<FixedSize>::new_fixed_size(batch_id),
+            5,
+            collection_interval(),
+            5u64,
+        );
+
+        let mut json = Vec::new();
+        write_collection(&collection, OutputFormat::Json, &mut json).unwrap();
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(&json).unwrap(),
+            serde_json::json!({
+                "partial_batch_selector": {"batch_identifier": batch_id_encoded},
+                "report_count": 5,
+                "interval": {"start": 1_000_000, "duration": 1000},
+                "aggregate_result": 5,
+            })
+        );
+
+        let mut csv = Vec::new();
+        write_collection(&collection, OutputFormat::Csv, &mut csv).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            format!(
+                "batch_id,report_count,interval_start,interval_duration,index,value\n\
+                 {batch_id_encoded},5,1000000,1000,,5\n"
+            )
+        );
+
+        let collection = Collection::new(
+            PartialBatchSelector::<TimeInterval>::new_time_interval(),
+            2,
+            collection_interval(),
+            Vec::from([0.5f64, -0.25]),
+        );
+        let mut json = Vec::new();
+        write_collection(&collection, OutputFormat::Json, &mut json).unwrap();
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(&json).unwrap()["aggregate_result"],
+            serde_json::json!([0.5, -0.25])
+        );
+    }
+
+    #[test]
+    fn batch_output_formats() {
+        let batch_id_1 = BatchId::from([1u8; BatchId::LEN]);
+        let batch_id_2 = BatchId::from([2u8; BatchId::LEN]);
+        let batch_id_1_encoded = URL_SAFE_NO_PAD.encode(batch_id_1.as_ref());
+        let batch_id_2_encoded = URL_SAFE_NO_PAD.encode(batch_id_2.as_ref());
+        let batches = Vec::from([
+            CollectableBatch::new(batch_id_1, 10, collection_interval(), true),
+            CollectableBatch::new(batch_id_2, 12, collection_interval(), false),
+        ]);
+
+        let mut text = Vec::new();
+        write_batches(&batches, OutputFormat::Text, &mut text).unwrap();
+        assert_eq!(
+            String::from_utf8(text).unwrap(),
+            format!(
+                "Batch: {batch_id_1_encoded}\n\
+                 Number of reports: 10\n\
+                 Spanned interval: start: 1970-01-12 13:46:40 UTC length: PT1000S\n\
+                 Collected: true\n\
+                 \n\
+                 Batch: {batch_id_2_encoded}\n\
+                 Number of reports: 12\n\
+                 Spanned interval: start: 1970-01-12 13:46:40 UTC length: PT1000S\n\
+                 Collected: false\n"
+            )
+        );
+
+        let mut json = Vec::new();
+        write_batches(&batches, OutputFormat::Json, &mut json).unwrap();
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(&json).unwrap(),
+            serde_json::json!([
+                {
+                    "batch_id": batch_id_1_encoded,
+                    "report_count": 10,
+                    "interval": {"start": 1_000_000, "duration": 1000},
+                    "collected": true,
+                },
+                {
+                    "batch_id": batch_id_2_encoded,
+                    "report_count": 12,
+                    "interval": {"start": 1_000_000, "duration": 1000},
+                    "collected": false,
+                },
+            ])
+        );
+
+        let mut csv = Vec::new();
+        write_batches(&batches, OutputFormat::Csv, &mut csv).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            format!(
+                "batch_id,report_count,interval_start,interval_duration,collected\n\
+                 {batch_id_1_encoded},10,1000000,1000,true\n\
+                 {batch_id_2_encoded},12,1000000,1000,false\n"
+            )
+        );
+
+        let mut json = Vec::new();
+        write_batches(&[], OutputFormat::Json, &mut json).unwrap();
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(&json).unwrap(),
+            serde_json::json!([])
+        );
+    }
+
+    #[test]
+    fn output_file() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("result.json");
+
+        write_file_atomically(&path, b"first").unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"first");
+        write_file_atomically(&path, b"second").unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"second");
+
+        // No temporary files are left behind.
+        assert_eq!(std::fs::read_dir(directory.path()).unwrap().count(), 1);
+    }
+}
@@ -5,7 +5,9 @@ use trycmd::TestCases;
 fn cli_tests() {
     let test_cases = TestCases::new();
 
+    test_cases.case("tests/cmd/collection_daemon.trycmd");
     test_cases.case("tests/cmd/dap_decode.trycmd");
+    test_cases.case("tests/cmd/dap_decrypt.trycmd");
     test_cases.case("tests/cmd/hpke_keygen.trycmd");
 
     cfg_if! {